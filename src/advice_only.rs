@@ -0,0 +1,97 @@
+//! Advice-only mode: when the git tools can't be attached at init, because their
+//! manifest is missing or the actor won't spawn, the assistant starts anyway. Sessions
+//! get no git tools and the model is told to advise rather than act. `RetryToolAttach`
+//! tries to spawn the tools again and, once they attach, gives them back to every
+//! session.
+
+use crate::preflight::{CheckStatus, PreflightReport};
+use serde::{Deserialize, Serialize};
+
+/// Preflight check of the assistant's own git tools actor, spawned from the same
+/// manifest as the model's.
+const GIT_TOOLS_CHECK: &str = "actor:git_tools";
+
+pub const PROMPT_FRAGMENT: &str = "\n\nADVICE ONLY: Your git tools could not be attached \
+in this session, so you can't look at the repository or change it. Wherever your \
+instructions say to run a git command or use a git tool, explain what to run and why \
+instead, and ask the user for any output you need. Don't claim to have run anything.";
+
+/// Why the git tools are unavailable, kept in state until `RetryToolAttach` attaches
+/// them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ToolOutage {
+    /// The error attaching them failed with most recently
+    pub error: String,
+    /// Seconds since the epoch
+    pub since: u64,
+    /// `RetryToolAttach` requests that failed
+    #[serde(default)]
+    pub retries: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_retry_at: Option<u64>,
+}
+
+impl ToolOutage {
+    pub fn new(error: String, now: u64) -> Self {
+        Self {
+            error,
+            since: now,
+            retries: 0,
+            last_retry_at: None,
+        }
+    }
+
+    pub fn retry_failed(&mut self, error: String, now: u64) {
+        self.error = error;
+        self.retries += 1;
+        self.last_retry_at = Some(now);
+    }
+}
+
+/// The outage the preflight `report` found, if the git tools actor didn't spawn.
+pub fn outage(report: &PreflightReport) -> Option<ToolOutage> {
+    report
+        .checks
+        .iter()
+        .find(|check| check.name == GIT_TOOLS_CHECK && check.status == CheckStatus::Failed)
+        .map(|check| {
+            let error = check
+                .detail
+                .clone()
+                .unwrap_or_else(|| "the git tools actor could not be spawned".to_string());
+            ToolOutage::new(error, report.checked_at)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preflight::{self, PreflightCheck};
+
+    #[test]
+    fn a_failed_git_tools_check_is_an_outage() {
+        let spawned = Err("Spawn failed: manifest not found".to_string());
+        let mut report = PreflightReport {
+            checked_at: 100,
+            checks: vec![
+                preflight::actor_check("exec_tools", &Ok("exec-1".to_string())),
+                preflight::actor_check("git_tools", &spawned),
+            ],
+        };
+        let mut outage = outage(&report).unwrap();
+        assert_eq!(
+            outage,
+            ToolOutage::new("Spawn failed: manifest not found".to_string(), 100)
+        );
+        outage.retry_failed("Spawn failed: still missing".to_string(), 160);
+        assert_eq!((outage.retries, outage.last_retry_at), (1, Some(160)));
+        assert_eq!(outage.since, 100);
+
+        report.checks[1] = PreflightCheck {
+            name: GIT_TOOLS_CHECK.to_string(),
+            status: CheckStatus::Passed,
+            detail: Some("git-1".to_string()),
+        };
+        assert!(super::outage(&report).is_none());
+    }
+}
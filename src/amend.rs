@@ -0,0 +1,187 @@
+use crate::git_tools::run_git;
+use crate::sanitize::{self, TextKind};
+
+/// Action under which amending a commit that is already on a remote waits for
+/// `Confirm`, unless `allow_published_rewrite` is set.
+pub const AMEND_PUBLISHED: &str = "amend-published-commit";
+
+/// What the `amend` workflow can change: the last commit and the pending changes that
+/// could be folded into it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmendContext {
+    pub sha: String,
+    /// The full commit message
+    pub message: String,
+    /// Files the commit changed, as `<status> <path>`
+    pub files: Vec<String>,
+    pub staged: Vec<String>,
+    /// Unstaged and untracked files
+    pub unstaged: Vec<String>,
+    /// Remote-tracking branches that already contain the commit
+    pub remote_branches: Vec<String>,
+}
+
+impl AmendContext {
+    /// Whether amending needs a force push over work others may have pulled.
+    pub fn is_published(&self) -> bool {
+        !self.remote_branches.is_empty()
+    }
+
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(7)]
+    }
+
+    /// Block for the opening message of the `amend` workflow.
+    pub fn render(&self) -> String {
+        let mut block = format!(
+            "\n\nLAST COMMIT: {}\n{}",
+            self.short_sha(),
+            sanitize::escape(self.message.trim_end(), TextKind::Message)
+        );
+        push_files(&mut block, "Files in the commit", &self.files);
+        push_files(&mut block, "Staged changes", &self.staged);
+        push_files(&mut block, "Unstaged changes", &self.unstaged);
+        if self.staged.is_empty() && self.unstaged.is_empty() {
+            block.push_str(
+                "\nThere are no pending changes; only the commit message can be amended.",
+            );
+        }
+        if self.is_published() {
+            block.push_str(&format!(
+                "\nNOTE: The commit is already on {}. Amending it was approved, but \
+                publishing the result needs a force push; mention this in your summary.",
+                self.remote_branches.join(", ")
+            ));
+        }
+        block
+    }
+}
+
+fn push_files(block: &mut String, title: &str, files: &[String]) {
+    if files.is_empty() {
+        return;
+    }
+    block.push_str(&format!("\n{} ({}):", title, files.len()));
+    for file in files {
+        block.push_str(&format!("\n- {}", sanitize::escape(file, TextKind::Name)));
+    }
+}
+
+/// Read the last commit, the pending changes, and the remote branches that contain
+/// the commit.
+pub fn fetch_amend_context(
+    git_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<AmendContext, String> {
+    let log = run_git(git_actor_id, directory, &["log", "-1", "--format=%H%x1f%B"])?;
+    let (sha, message) = log
+        .split_once('\x1f')
+        .ok_or_else(|| "The repository has no commits to amend".to_string())?;
+    let files = run_git(
+        git_actor_id,
+        directory,
+        &[
+            "diff-tree",
+            "--no-commit-id",
+            "--name-status",
+            "-r",
+            "--root",
+            "HEAD",
+        ],
+    )?
+    .lines()
+    .map(|line| line.replacen('\t', " ", 1))
+    .filter(|line| !line.trim().is_empty())
+    .collect();
+    let status = run_git(git_actor_id, directory, &["status", "--porcelain=v1"])?;
+    let (staged, unstaged) = split_status(&status);
+    Ok(AmendContext {
+        sha: sha.trim().to_string(),
+        message: message.to_string(),
+        files,
+        staged,
+        unstaged,
+        remote_branches: fetch_remote_branches(git_actor_id, directory)?,
+    })
+}
+
+/// Remote-tracking branches that contain HEAD, without the remotes' `HEAD` aliases.
+pub fn fetch_remote_branches(
+    git_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &[
+            "branch",
+            "-r",
+            "--contains",
+            "HEAD",
+            "--format=%(refname:short)",
+        ],
+    )?;
+    Ok(output
+        .lines()
+        .map(str::trim)
+        .filter(|branch| !branch.is_empty() && !branch.ends_with("/HEAD"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Split `git status --porcelain=v1` output into staged and unstaged entries, each as
+/// `<status> <path>`. A file with both is listed in both.
+pub fn split_status(status: &str) -> (Vec<String>, Vec<String>) {
+    let mut staged = Vec::new();
+    let mut unstaged = Vec::new();
+    for line in status.lines() {
+        let (Some(index), Some(worktree), Some(path)) =
+            (line.chars().next(), line.chars().nth(1), line.get(3..))
+        else {
+            continue;
+        };
+        if index == '?' {
+            unstaged.push(format!("? {}", path));
+            continue;
+        }
+        if index != ' ' {
+            staged.push(format!("{} {}", index, path));
+        }
+        if worktree != ' ' {
+            unstaged.push(format!("{} {}", worktree, path));
+        }
+    }
+    (staged, unstaged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_splits_into_staged_and_unstaged() {
+        let status = "M  src/lib.rs\n M README.md\nMM src/parser.rs\n?? notes.txt\n";
+        let (staged, unstaged) = split_status(status);
+        assert_eq!(staged, ["M src/lib.rs", "M src/parser.rs"]);
+        assert_eq!(unstaged, ["M README.md", "M src/parser.rs", "? notes.txt"]);
+    }
+
+    #[test]
+    fn published_commits_get_a_force_push_note() {
+        let mut context = AmendContext {
+            sha: "0123abcdef".to_string(),
+            message: "feat: add parser\n".to_string(),
+            files: vec!["A src/parser.rs".to_string()],
+            staged: Vec::new(),
+            unstaged: Vec::new(),
+            remote_branches: Vec::new(),
+        };
+        let block = context.render();
+        assert!(block.contains("LAST COMMIT: 0123abc\nfeat: add parser"));
+        assert!(block.contains("only the commit message can be amended"));
+        assert!(!block.contains("force push"));
+
+        context.remote_branches = vec!["origin/main".to_string()];
+        assert!(context.render().contains("already on origin/main"));
+    }
+}
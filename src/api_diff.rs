@@ -0,0 +1,113 @@
+use crate::exec_tools::run_command;
+use serde::{Deserialize, Serialize};
+
+/// The `review.api_diff` section: a public-API diff run before Rust library reviews.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiDiffConfig {
+    /// Command printing a `cargo public-api diff` style report
+    #[serde(default = "default_api_diff_command")]
+    pub command: String,
+}
+
+fn default_api_diff_command() -> String {
+    "cargo public-api diff latest".to_string()
+}
+
+/// Version bump a set of public API changes calls for.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SemverBump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// Public API changes, as reported by `cargo public-api diff`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ApiDiff {
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+    pub added: Vec<String>,
+}
+
+impl ApiDiff {
+    /// Parse the "Removed/Changed/Added items" sections of a `cargo public-api diff` report.
+    pub fn parse(output: &str) -> Self {
+        let mut diff = ApiDiff::default();
+        // Section being read and the marker of the lines to keep; changed items are printed
+        // as a "-old" / "+new" pair, of which the new signature is kept
+        let mut section: Option<(&mut Vec<String>, char)> = None;
+        for line in output.lines() {
+            let trimmed = line.trim();
+            let lower = trimmed.to_ascii_lowercase();
+            if lower.starts_with("removed items") {
+                section = Some((&mut diff.removed, '-'));
+            } else if lower.starts_with("changed items") {
+                section = Some((&mut diff.changed, '+'));
+            } else if lower.starts_with("added items") {
+                section = Some((&mut diff.added, '+'));
+            } else if let Some((items, marker)) = section.as_mut() {
+                if let Some(item) = trimmed.strip_prefix(*marker) {
+                    items.push(item.trim().to_string());
+                }
+            }
+        }
+        diff
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty() || !self.changed.is_empty()
+    }
+
+    pub fn required_bump(&self) -> SemverBump {
+        if self.is_breaking() {
+            SemverBump::Major
+        } else if !self.added.is_empty() {
+            SemverBump::Minor
+        } else {
+            SemverBump::Patch
+        }
+    }
+
+    /// Render the context block injected into the review.
+    pub fn render(&self) -> String {
+        let mut block = format!(
+            "PUBLIC API DIFF: {} removed, {} changed, {} added",
+            self.removed.len(),
+            self.changed.len(),
+            self.added.len()
+        );
+        for (label, items) in [("Removed", &self.removed), ("Changed", &self.changed)] {
+            for item in items {
+                block.push_str(&format!("\n- {} (breaking): {}", label, item));
+            }
+        }
+        for item in &self.added {
+            block.push_str(&format!("\n- Added: {}", item));
+        }
+        block.push_str(match self.required_bump() {
+            SemverBump::Major => {
+                "\nSEMVER: These changes break the public API and require a major version bump \
+                (a minor bump for 0.x crates). Call out every breaking change in the review and \
+                whether it is intentional."
+            }
+            SemverBump::Minor => {
+                "\nSEMVER: The public API only gains items; a minor version bump is required \
+                (a patch bump for 0.x crates)."
+            }
+            SemverBump::Patch => {
+                "\nSEMVER: The public API is unchanged; a patch release is enough."
+            }
+        });
+        block
+    }
+}
+
+pub fn fetch_api_diff(
+    exec_actor_id: &str,
+    directory: Option<&str>,
+    config: &ApiDiffConfig,
+) -> Result<ApiDiff, String> {
+    let output = run_command(exec_actor_id, directory, &config.command)?;
+    Ok(ApiDiff::parse(&output))
+}
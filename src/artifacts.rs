@@ -0,0 +1,215 @@
+//! Session artifacts kept in `<gitdir>/assistant/`: session logs, rebase plans, and
+//! backups of the refs history rewrites start from. They live inside the git directory,
+//! so they never show up in the worktree, and are rotated to stay within the limits of
+//! the `artifacts` config.
+
+use crate::exec_tools::{run_command, shell_quote};
+use serde::{Deserialize, Serialize};
+
+/// Directory under the git directory that holds the artifacts.
+const ARTIFACTS_DIR: &str = "assistant";
+
+/// Kinds of artifacts, the second part of each file name.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArtifactKind {
+    /// The workflow result and the default session's transcript, when a workflow ends
+    SessionLog,
+    /// A rebase plan the model proposed
+    Plan,
+    /// The branch and HEAD a rebase started from
+    Backup,
+}
+
+impl ArtifactKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ArtifactKind::SessionLog => "session-log",
+            ArtifactKind::Plan => "plan",
+            ArtifactKind::Backup => "backup",
+        }
+    }
+}
+
+/// The `artifacts` section of the assistant config. Without it, nothing is written.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArtifactsConfig {
+    /// Files kept; the oldest are removed beyond this
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    /// Bytes all files may take together; the oldest are removed beyond this
+    #[serde(default = "default_max_total_bytes")]
+    pub max_total_bytes: u64,
+    /// Bytes of a single file; larger artifacts are cut short. Files are written through
+    /// a shell command line, which limits how far this can be raised
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: usize,
+}
+
+fn default_max_files() -> usize {
+    50
+}
+
+fn default_max_total_bytes() -> u64 {
+    20 * 1024 * 1024
+}
+
+fn default_max_file_bytes() -> usize {
+    64 * 1024
+}
+
+impl Default for ArtifactsConfig {
+    fn default() -> Self {
+        Self {
+            max_files: default_max_files(),
+            max_total_bytes: default_max_total_bytes(),
+            max_file_bytes: default_max_file_bytes(),
+        }
+    }
+}
+
+/// An artifact file, as listed for rotation and cleanup.
+#[derive(Debug, Clone)]
+struct ArtifactFile {
+    name: String,
+    bytes: u64,
+}
+
+/// Absolute path of the artifacts directory of the repository in `directory`.
+pub fn artifacts_dir(exec_actor_id: &str, directory: Option<&str>) -> Result<String, String> {
+    let git_dir = run_command(exec_actor_id, directory, "git rev-parse --absolute-git-dir")?;
+    let git_dir = git_dir.trim();
+    if git_dir.is_empty() {
+        return Err("git didn't name a git directory".to_string());
+    }
+    Ok(format!("{}/{}", git_dir, ARTIFACTS_DIR))
+}
+
+/// Write `contents` as the newest artifact, removing the oldest ones beyond the limits
+/// of `config`. Returns the file name.
+pub fn write(
+    exec_actor_id: &str,
+    dir: &str,
+    config: &ArtifactsConfig,
+    kind: ArtifactKind,
+    label: &str,
+    contents: &str,
+) -> Result<String, String> {
+    let mut end = contents.len().min(config.max_file_bytes);
+    while !contents.is_char_boundary(end) {
+        end -= 1;
+    }
+    let contents = &contents[..end];
+
+    let mut files = list(exec_actor_id, dir)?;
+    // Numbering carries on from the newest file, so it survives restarts
+    let sequence = files
+        .last()
+        .and_then(|file| file.name.split('-').next())
+        .and_then(|sequence| sequence.parse::<u64>().ok())
+        .unwrap_or(0)
+        + 1;
+    let label: String = label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let name = format!("{:06}-{}-{}.json", sequence, kind.name(), label);
+    files.push(ArtifactFile {
+        name: name.clone(),
+        bytes: contents.len() as u64,
+    });
+    let removed = over_limits(files, config);
+
+    let mut command = format!(
+        "mkdir -p {} && printf '%s' {} > {}",
+        shell_quote(dir),
+        shell_quote(contents),
+        shell_quote(&format!("{}/{}", dir, name)),
+    );
+    if !removed.is_empty() {
+        command.push_str(&format!(" && {}", remove_command(dir, &removed)));
+    }
+    run_command(exec_actor_id, None, &command)?;
+    Ok(name)
+}
+
+/// Artifact files in `dir`, oldest first.
+fn list(exec_actor_id: &str, dir: &str) -> Result<Vec<ArtifactFile>, String> {
+    let command = format!(
+        "for f in {}/*.json; do [ -f \"$f\" ] && echo \"$(wc -c < \"$f\") ${{f##*/}}\"; done; true",
+        shell_quote(dir)
+    );
+    let output = run_command(exec_actor_id, None, &command)?;
+    let mut files: Vec<ArtifactFile> = output
+        .lines()
+        .filter_map(|line| {
+            let (bytes, name) = line.trim().split_once(' ')?;
+            Some(ArtifactFile {
+                name: name.to_string(),
+                bytes: bytes.trim().parse().ok()?,
+            })
+        })
+        .collect();
+    // Names start with a zero-padded sequence number
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+fn remove_command(dir: &str, names: &[String]) -> String {
+    let paths = names
+        .iter()
+        .map(|name| shell_quote(&format!("{}/{}", dir, name)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("rm -f {}", paths)
+}
+
+/// The oldest of `files` that have to go for the rest to fit `config`.
+fn over_limits(mut files: Vec<ArtifactFile>, config: &ArtifactsConfig) -> Vec<String> {
+    let mut total: u64 = files.iter().map(|file| file.bytes).sum();
+    let mut removed = Vec::new();
+    while files.len() > 1 && (files.len() > config.max_files || total > config.max_total_bytes) {
+        let oldest = files.remove(0);
+        total -= oldest.bytes;
+        removed.push(oldest.name);
+    }
+    removed
+}
+
+/// Remove the artifacts of `kinds` (every kind when empty), keeping the newest `keep`
+/// of them. Returns the removed names and the number of artifacts left.
+pub fn clean(
+    exec_actor_id: &str,
+    dir: &str,
+    kinds: &[ArtifactKind],
+    keep: usize,
+) -> Result<(Vec<String>, usize), String> {
+    let files = list(exec_actor_id, dir)?;
+    let matching: Vec<&ArtifactFile> = files
+        .iter()
+        .filter(|file| {
+            kinds.is_empty()
+                || kinds.iter().any(|kind| {
+                    file.name
+                        .get(7..)
+                        .is_some_and(|rest| rest.starts_with(kind.name()))
+                })
+        })
+        .collect();
+    let removed: Vec<String> = matching
+        .iter()
+        .take(matching.len().saturating_sub(keep))
+        .map(|file| file.name.clone())
+        .collect();
+    if !removed.is_empty() {
+        run_command(exec_actor_id, None, &remove_command(dir, &removed))?;
+    }
+    let kept = files.len() - removed.len();
+    Ok((removed, kept))
+}
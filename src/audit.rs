@@ -0,0 +1,226 @@
+//! Audit log of the operations the model and clients asked for: the model's tool calls,
+//! as chat-state actors relay them or as the assistant proxies them, and the requests
+//! held for confirmation. Entries are kept in the actor state, oldest first, and the
+//! oldest are dropped beyond `MAX_ENTRIES`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// Entries kept; the oldest are dropped beyond this.
+pub const MAX_ENTRIES: usize = 500;
+
+/// Characters of a tool's output kept with its outcome.
+const MAX_DETAIL_CHARS: usize = 500;
+
+/// What asked for the operation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditSource {
+    /// One of the model's tools
+    Tool,
+    /// A client request held for confirmation
+    Request,
+}
+
+/// Whether the operation was let through.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Approval {
+    /// Ran without asking
+    Automatic,
+    /// Waiting for `Confirm` or `RejectConfirmation`
+    Pending,
+    Confirmed,
+    Rejected,
+}
+
+/// How an operation ended.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditOutcome {
+    pub ok: bool,
+    /// The error, or the start of the tool's output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl AuditOutcome {
+    /// An outcome with `detail` cut to `MAX_DETAIL_CHARS`.
+    pub fn new(ok: bool, detail: Option<&str>) -> Self {
+        Self {
+            ok,
+            detail: detail
+                .filter(|detail| !detail.trim().is_empty())
+                .map(|detail| detail.chars().take(MAX_DETAIL_CHARS).collect()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// Numbers entries in the order they were recorded
+    pub id: u64,
+    /// When the operation was asked for, in seconds since the epoch
+    pub at: u64,
+    pub source: AuditSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// The tool's name, or the action of a held request, e.g. `delete-branches`
+    pub command: String,
+    /// The tool's arguments or the request, with secrets redacted
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub arguments: Value,
+    pub approval: Approval,
+    /// Unset until the operation finishes, and for tool calls whose result isn't relayed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<AuditOutcome>,
+    /// The confirmation of a held request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AuditLog {
+    entries: VecDeque<AuditEntry>,
+    next_id: u64,
+}
+
+impl AuditLog {
+    /// Id the next entry will get; entries from here on are newer.
+    pub fn next_id(&self) -> u64 {
+        self.next_id
+    }
+
+    /// Record an entry and return its id.
+    pub fn record(&mut self, mut entry: AuditEntry) -> u64 {
+        entry.id = self.next_id;
+        self.next_id += 1;
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+        self.next_id - 1
+    }
+
+    /// Attach `outcome` to the newest call of the entry's tool that has none, in the
+    /// entry's session when it names one, or record the call when it wasn't announced.
+    pub fn finish_tool(&mut self, entry: AuditEntry, outcome: AuditOutcome) {
+        let open = self.entries.iter_mut().rev().find(|open| {
+            open.source == AuditSource::Tool
+                && open.outcome.is_none()
+                && open.command == entry.command
+                && (entry.session_id.is_none() || open.session_id == entry.session_id)
+        });
+        match open {
+            Some(open) => {
+                if open.arguments.is_null() {
+                    open.arguments = entry.arguments;
+                }
+                open.outcome = Some(outcome);
+            }
+            None => {
+                self.record(AuditEntry {
+                    outcome: Some(outcome),
+                    ..entry
+                });
+            }
+        }
+    }
+
+    /// Settle the entry of confirmation `confirmation_id`.
+    pub fn settle(
+        &mut self,
+        confirmation_id: &str,
+        approval: Approval,
+        outcome: Option<AuditOutcome>,
+    ) {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.confirmation_id.as_deref() == Some(confirmation_id))
+        {
+            entry.approval = approval;
+            entry.outcome = outcome;
+        }
+    }
+
+    /// Entries recorded from id `from` on, oldest first.
+    pub fn since(&self, from: u64) -> Vec<AuditEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.id >= from)
+            .cloned()
+            .collect()
+    }
+
+    /// The newest `limit` entries, or all of them, oldest first.
+    pub fn newest(&self, limit: Option<usize>) -> Vec<AuditEntry> {
+        let skip = limit.map_or(0, |limit| self.entries.len().saturating_sub(limit));
+        self.entries.iter().skip(skip).cloned().collect()
+    }
+
+    /// Drop all but the newest `keep` entries; returns how many were dropped.
+    pub fn prune(&mut self, keep: usize) -> usize {
+        let dropped = self.entries.len().saturating_sub(keep);
+        self.entries.drain(..dropped);
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_call(tool: &str) -> AuditEntry {
+        AuditEntry {
+            id: 0,
+            at: 100,
+            source: AuditSource::Tool,
+            session_id: Some("default".to_string()),
+            command: tool.to_string(),
+            arguments: Value::Null,
+            approval: Approval::Automatic,
+            outcome: None,
+            confirmation_id: None,
+        }
+    }
+
+    #[test]
+    fn results_attach_to_the_newest_open_call() {
+        let mut log = AuditLog::default();
+        log.record(tool_call("git_status"));
+        log.record(tool_call("git_status"));
+        log.finish_tool(
+            AuditEntry {
+                arguments: serde_json::json!({"path": "."}),
+                ..tool_call("git_status")
+            },
+            AuditOutcome::new(true, Some("clean")),
+        );
+        log.finish_tool(tool_call("git_commit"), AuditOutcome::new(false, None));
+
+        let entries = log.newest(None);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].outcome, None);
+        assert_eq!(entries[1].arguments, serde_json::json!({"path": "."}));
+        assert_eq!(
+            entries[1].outcome,
+            Some(AuditOutcome::new(true, Some("clean")))
+        );
+        assert_eq!(entries[2].command, "git_commit");
+        assert_eq!(log.since(2), entries[2..]);
+    }
+
+    #[test]
+    fn the_oldest_entries_are_dropped() {
+        let mut log = AuditLog::default();
+        for _ in 0..MAX_ENTRIES + 2 {
+            log.record(tool_call("git_log"));
+        }
+        let entries = log.newest(None);
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].id, 2);
+        assert_eq!(log.newest(Some(1))[0].id, MAX_ENTRIES as u64 + 1);
+    }
+}
@@ -0,0 +1,157 @@
+//! Retry delays: exponential backoff with jitter. The jitter comes from the runtime's
+//! randomness, or from a seeded generator when runs have to be repeatable, as in tests.
+
+use crate::bindings::theater::simple::random;
+use serde::{Deserialize, Serialize};
+
+/// The `retry_backoff` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackoffConfig {
+    /// Delay before the first retry, in seconds
+    #[serde(default = "default_initial_seconds")]
+    pub initial_seconds: u64,
+    /// Upper bound on any delay, in seconds
+    #[serde(default = "default_max_seconds")]
+    pub max_seconds: u64,
+    /// Factor each delay grows by from one retry to the next
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// Share of each delay that is random, from 0 (none) to 1 (anywhere down to zero)
+    #[serde(default = "default_jitter")]
+    pub jitter: f64,
+    /// Seed for repeatable jitter; the runtime's randomness when unset
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+fn default_initial_seconds() -> u64 {
+    1
+}
+
+fn default_max_seconds() -> u64 {
+    60
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+fn default_jitter() -> f64 {
+    0.5
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        Self {
+            initial_seconds: default_initial_seconds(),
+            max_seconds: default_max_seconds(),
+            multiplier: default_multiplier(),
+            jitter: default_jitter(),
+            seed: None,
+        }
+    }
+}
+
+impl BackoffConfig {
+    /// Delay before retry `retry`, counted from 1, before jitter.
+    pub fn base_delay(&self, retry: u32) -> u64 {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(retry.saturating_sub(1) as i32);
+        let delay = (self.initial_seconds as f64 * factor).min(self.max_seconds as f64);
+        delay as u64
+    }
+
+    /// Delay before retry `retry`: the base delay less a random share of up to
+    /// `jitter` of it, so clients failing together don't retry together.
+    pub fn delay(&self, retry: u32, randomness: &mut Randomness) -> u64 {
+        let delay = self.base_delay(retry);
+        let spread = (delay as f64 * self.jitter.clamp(0.0, 1.0)) as u64;
+        if spread == 0 {
+            return delay;
+        }
+        delay - randomness.up_to(spread)
+    }
+}
+
+/// Where jitter comes from. Kept in the actor state, so a seeded sequence carries on
+/// across restarts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Randomness {
+    /// The runtime's `random` interface
+    #[default]
+    Runtime,
+    /// A splitmix64 generator, repeatable from its seed
+    Seeded { state: u64 },
+}
+
+impl Randomness {
+    /// Seeded randomness when there is a `seed`, the runtime's otherwise.
+    pub fn new(seed: Option<u64>) -> Self {
+        match seed {
+            Some(state) => Randomness::Seeded { state },
+            None => Randomness::Runtime,
+        }
+    }
+
+    /// A number from 0 to `max`, both included. Without the runtime's randomness,
+    /// `max`: the longest delay is the safe one.
+    pub fn up_to(&mut self, max: u64) -> u64 {
+        match self {
+            Randomness::Runtime => match random::random_range(0, max.saturating_add(1)) {
+                Ok(value) => value.min(max),
+                Err(_) => max,
+            },
+            Randomness::Seeded { state } => {
+                *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                let mut value = *state;
+                value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                value ^= value >> 31;
+                match max.checked_add(1) {
+                    Some(range) => value % range,
+                    None => value,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_grow_up_to_the_cap() {
+        let config = BackoffConfig {
+            jitter: 0.0,
+            ..BackoffConfig::default()
+        };
+        let delays: Vec<u64> = (1..=8).map(|retry| config.base_delay(retry)).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+    }
+
+    #[test]
+    fn seeded_jitter_repeats_and_stays_in_range() {
+        let config = BackoffConfig {
+            initial_seconds: 10,
+            max_seconds: 600,
+            seed: Some(42),
+            ..BackoffConfig::default()
+        };
+        let delays = |randomness: &mut Randomness| -> Vec<u64> {
+            (1..=6)
+                .map(|retry| config.delay(retry, randomness))
+                .collect()
+        };
+        let first = delays(&mut Randomness::new(config.seed));
+        assert_eq!(first, delays(&mut Randomness::new(config.seed)));
+        for (retry, delay) in (1..=6).zip(&first) {
+            let base = config.base_delay(retry);
+            assert!((base / 2..=base).contains(delay), "{} of {}", delay, base);
+        }
+        assert_ne!(first, delays(&mut Randomness::new(Some(7))));
+    }
+}
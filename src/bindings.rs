@@ -2151,6 +2151,1829 @@ pub mod theater {
                 }
             }
         }
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod http_types {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            /// Raw binary data type
+            pub type Bytes = _rt::Vec<u8>;
+            #[derive(Clone, serde::Deserialize, serde::Serialize)]
+            pub struct HttpRequest {
+                /// HTTP method (GET, POST, PUT, DELETE, etc.)
+                pub method: _rt::String,
+                /// Full request URI including query parameters
+                pub uri: _rt::String,
+                /// List of request headers as key-value pairs
+                pub headers: _rt::Vec<(_rt::String, _rt::String)>,
+                /// Optional request body as binary data
+                pub body: Option<Bytes>,
+            }
+            impl ::core::fmt::Debug for HttpRequest {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("HttpRequest")
+                        .field("method", &self.method)
+                        .field("uri", &self.uri)
+                        .field("headers", &self.headers)
+                        .field("body", &self.body)
+                        .finish()
+                }
+            }
+            #[derive(Clone, serde::Deserialize, serde::Serialize)]
+            pub struct HttpResponse {
+                /// HTTP status code (e.g., 200, 404, 500)
+                pub status: u16,
+                /// List of response headers as key-value pairs
+                pub headers: _rt::Vec<(_rt::String, _rt::String)>,
+                /// Optional response body as binary data
+                pub body: Option<Bytes>,
+            }
+            impl ::core::fmt::Debug for HttpResponse {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("HttpResponse")
+                        .field("status", &self.status)
+                        .field("headers", &self.headers)
+                        .field("body", &self.body)
+                        .finish()
+                }
+            }
+            #[derive(Clone, serde::Deserialize, serde::Serialize)]
+            pub struct TlsConfig {
+                /// Path to the certificate file
+                pub cert_path: _rt::String,
+                /// Path to the key file
+                pub key_path: _rt::String,
+            }
+            impl ::core::fmt::Debug for TlsConfig {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("TlsConfig")
+                        .field("cert-path", &self.cert_path)
+                        .field("key-path", &self.key_path)
+                        .finish()
+                }
+            }
+            #[derive(Clone, serde::Deserialize, serde::Serialize)]
+            pub struct ServerConfig {
+                /// Port to listen on, 0 means system-assigned
+                pub port: Option<u16>,
+                /// Host address to bind to
+                pub host: Option<_rt::String>,
+                /// TLS configuration for HTTPS
+                pub tls_config: Option<TlsConfig>,
+            }
+            impl ::core::fmt::Debug for ServerConfig {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("ServerConfig")
+                        .field("port", &self.port)
+                        .field("host", &self.host)
+                        .field("tls-config", &self.tls_config)
+                        .finish()
+                }
+            }
+            #[derive(Clone, serde::Deserialize, serde::Serialize)]
+            pub struct ServerInfo {
+                /// Server ID
+                pub id: u64,
+                /// Current listening port
+                pub port: u16,
+                /// Host address
+                pub host: _rt::String,
+                /// Whether the server is running
+                pub running: bool,
+                /// Number of active routes
+                pub routes_count: u32,
+                /// Number of active middleware
+                pub middleware_count: u32,
+                /// Whether WebSocket is enabled
+                pub websocket_enabled: bool,
+            }
+            impl ::core::fmt::Debug for ServerInfo {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("ServerInfo")
+                        .field("id", &self.id)
+                        .field("port", &self.port)
+                        .field("host", &self.host)
+                        .field("running", &self.running)
+                        .field("routes-count", &self.routes_count)
+                        .field("middleware-count", &self.middleware_count)
+                        .field("websocket-enabled", &self.websocket_enabled)
+                        .finish()
+                }
+            }
+            #[derive(Clone, serde::Deserialize, serde::Serialize)]
+            pub struct MiddlewareResult {
+                /// Whether to continue processing the request
+                pub proceed: bool,
+                /// The potentially modified request
+                pub request: HttpRequest,
+            }
+            impl ::core::fmt::Debug for MiddlewareResult {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("MiddlewareResult")
+                        .field("proceed", &self.proceed)
+                        .field("request", &self.request)
+                        .finish()
+                }
+            }
+        }
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod websocket_types {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            #[derive(Clone, serde::Deserialize, serde::Serialize)]
+            pub enum MessageType {
+                /// A text message (UTF-8 encoded)
+                Text,
+                /// A binary message
+                Binary,
+                /// A new connection was established
+                Connect,
+                /// The connection was closed
+                Close,
+                /// A ping message (for keep-alive)
+                Ping,
+                /// A pong message (response to ping)
+                Pong,
+                /// Any other message type with string identifier
+                Other(_rt::String),
+            }
+            impl ::core::fmt::Debug for MessageType {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    match self {
+                        MessageType::Text => f.debug_tuple("MessageType::Text").finish(),
+                        MessageType::Binary => {
+                            f.debug_tuple("MessageType::Binary").finish()
+                        }
+                        MessageType::Connect => {
+                            f.debug_tuple("MessageType::Connect").finish()
+                        }
+                        MessageType::Close => {
+                            f.debug_tuple("MessageType::Close").finish()
+                        }
+                        MessageType::Ping => f.debug_tuple("MessageType::Ping").finish(),
+                        MessageType::Pong => f.debug_tuple("MessageType::Pong").finish(),
+                        MessageType::Other(e) => {
+                            f.debug_tuple("MessageType::Other").field(e).finish()
+                        }
+                    }
+                }
+            }
+            /// # WebSocket Message
+            ///
+            /// Represents a message sent or received over a WebSocket connection.
+            #[derive(Clone, serde::Deserialize, serde::Serialize)]
+            pub struct WebsocketMessage {
+                /// The type of the message
+                pub ty: MessageType,
+                /// Binary data payload (used for binary messages)
+                pub data: Option<_rt::Vec<u8>>,
+                /// Text payload (used for text messages)
+                pub text: Option<_rt::String>,
+            }
+            impl ::core::fmt::Debug for WebsocketMessage {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("WebsocketMessage")
+                        .field("ty", &self.ty)
+                        .field("data", &self.data)
+                        .field("text", &self.text)
+                        .finish()
+                }
+            }
+        }
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod http_framework {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            pub type HttpRequest = super::super::super::theater::simple::http_types::HttpRequest;
+            pub type HttpResponse = super::super::super::theater::simple::http_types::HttpResponse;
+            pub type ServerConfig = super::super::super::theater::simple::http_types::ServerConfig;
+            pub type ServerInfo = super::super::super::theater::simple::http_types::ServerInfo;
+            pub type TlsConfig = super::super::super::theater::simple::http_types::TlsConfig;
+            pub type WebsocketMessage = super::super::super::theater::simple::websocket_types::WebsocketMessage;
+            /// Core types
+            /// Unique identifier for an HTTP server instance
+            pub type ServerId = u64;
+            /// Unique identifier for a registered handler function
+            pub type HandlerId = u64;
+            /// Unique identifier for a registered route
+            pub type RouteId = u64;
+            /// Unique identifier for registered middleware
+            pub type MiddlewareId = u64;
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn create_server(config: &ServerConfig) -> Result<ServerId, _rt::String> {
+                unsafe {
+                    #[repr(align(8))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 8 + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 8
+                            + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let super::super::super::theater::simple::http_types::ServerConfig {
+                        port: port0,
+                        host: host0,
+                        tls_config: tls_config0,
+                    } = config;
+                    let (result1_0, result1_1) = match port0 {
+                        Some(e) => (1i32, _rt::as_i32(e)),
+                        None => (0i32, 0i32),
+                    };
+                    let (result3_0, result3_1, result3_2) = match host0 {
+                        Some(e) => {
+                            let vec2 = e;
+                            let ptr2 = vec2.as_ptr().cast::<u8>();
+                            let len2 = vec2.len();
+                            (1i32, ptr2.cast_mut(), len2)
+                        }
+                        None => (0i32, ::core::ptr::null_mut(), 0usize),
+                    };
+                    let (result7_0, result7_1, result7_2, result7_3, result7_4) = match tls_config0 {
+                        Some(e) => {
+                            let super::super::super::theater::simple::http_types::TlsConfig {
+                                cert_path: cert_path4,
+                                key_path: key_path4,
+                            } = e;
+                            let vec5 = cert_path4;
+                            let ptr5 = vec5.as_ptr().cast::<u8>();
+                            let len5 = vec5.len();
+                            let vec6 = key_path4;
+                            let ptr6 = vec6.as_ptr().cast::<u8>();
+                            let len6 = vec6.len();
+                            (1i32, ptr5.cast_mut(), len5, ptr6.cast_mut(), len6)
+                        }
+                        None => {
+                            (
+                                0i32,
+                                ::core::ptr::null_mut(),
+                                0usize,
+                                ::core::ptr::null_mut(),
+                                0usize,
+                            )
+                        }
+                    };
+                    let ptr8 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "create-server"]
+                        fn wit_import9(
+                            _: i32,
+                            _: i32,
+                            _: i32,
+                            _: *mut u8,
+                            _: usize,
+                            _: i32,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import9(
+                        _: i32,
+                        _: i32,
+                        _: i32,
+                        _: *mut u8,
+                        _: usize,
+                        _: i32,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import9(
+                            result1_0,
+                            result1_1,
+                            result3_0,
+                            result3_1,
+                            result3_2,
+                            result7_0,
+                            result7_1,
+                            result7_2,
+                            result7_3,
+                            result7_4,
+                            ptr8,
+                        )
+                    };
+                    let l10 = i32::from(*ptr8.add(0).cast::<u8>());
+                    let result15 = match l10 {
+                        0 => {
+                            let e = {
+                                let l11 = *ptr8.add(8).cast::<i64>();
+                                l11 as u64
+                            };
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l12 = *ptr8.add(8).cast::<*mut u8>();
+                                let l13 = *ptr8
+                                    .add(8 + 1 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len14 = l13;
+                                let bytes14 = _rt::Vec::from_raw_parts(
+                                    l12.cast(),
+                                    len14,
+                                    len14,
+                                );
+                                _rt::string_lift(bytes14)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result15
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn get_server_info(server_id: ServerId) -> Result<ServerInfo, _rt::String> {
+                unsafe {
+                    #[repr(align(8))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 40 + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 40
+                            + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "get-server-info"]
+                        fn wit_import1(_: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(_rt::as_i64(server_id), ptr0) };
+                    let l2 = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result15 = match l2 {
+                        0 => {
+                            let e = {
+                                let l3 = *ptr0.add(8).cast::<i64>();
+                                let l4 = i32::from(*ptr0.add(16).cast::<u16>());
+                                let l5 = *ptr0
+                                    .add(16 + 1 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l6 = *ptr0
+                                    .add(16 + 2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len7 = l6;
+                                let bytes7 = _rt::Vec::from_raw_parts(
+                                    l5.cast(),
+                                    len7,
+                                    len7,
+                                );
+                                let l8 = i32::from(
+                                    *ptr0
+                                        .add(16 + 3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>(),
+                                );
+                                let l9 = *ptr0
+                                    .add(20 + 3 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<i32>();
+                                let l10 = *ptr0
+                                    .add(24 + 3 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<i32>();
+                                let l11 = i32::from(
+                                    *ptr0
+                                        .add(28 + 3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>(),
+                                );
+                                super::super::super::theater::simple::http_types::ServerInfo {
+                                    id: l3 as u64,
+                                    port: l4 as u16,
+                                    host: _rt::string_lift(bytes7),
+                                    running: _rt::bool_lift(l8 as u8),
+                                    routes_count: l9 as u32,
+                                    middleware_count: l10 as u32,
+                                    websocket_enabled: _rt::bool_lift(l11 as u8),
+                                }
+                            };
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l12 = *ptr0.add(8).cast::<*mut u8>();
+                                let l13 = *ptr0
+                                    .add(8 + 1 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len14 = l13;
+                                let bytes14 = _rt::Vec::from_raw_parts(
+                                    l12.cast(),
+                                    len14,
+                                    len14,
+                                );
+                                _rt::string_lift(bytes14)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result15
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn start_server(server_id: ServerId) -> Result<u16, _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "start-server"]
+                        fn wit_import1(_: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(_rt::as_i64(server_id), ptr0) };
+                    let l2 = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result7 = match l2 {
+                        0 => {
+                            let e = {
+                                let l3 = i32::from(
+                                    *ptr0
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u16>(),
+                                );
+                                l3 as u16
+                            };
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l4 = *ptr0
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l5 = *ptr0
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len6 = l5;
+                                let bytes6 = _rt::Vec::from_raw_parts(
+                                    l4.cast(),
+                                    len6,
+                                    len6,
+                                );
+                                _rt::string_lift(bytes6)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result7
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn stop_server(server_id: ServerId) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "stop-server"]
+                        fn wit_import1(_: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(_rt::as_i64(server_id), ptr0) };
+                    let l_tag = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr0
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l_len = *ptr0
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn destroy_server(server_id: ServerId) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "destroy-server"]
+                        fn wit_import1(_: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(_rt::as_i64(server_id), ptr0) };
+                    let l_tag = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr0
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l_len = *ptr0
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn register_handler(handler_name: &str) -> Result<HandlerId, _rt::String> {
+                unsafe {
+                    #[repr(align(8))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 8 + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 8
+                            + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = handler_name;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let ptr1 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "register-handler"]
+                        fn wit_import2(_: *mut u8, _: usize, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import2(_: *mut u8, _: usize, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import2(ptr0.cast_mut(), len0, ptr1) };
+                    let l_tag = i32::from(*ptr1.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = {
+                                let l_id = *ptr1.add(8).cast::<i64>();
+                                l_id as u64
+                            };
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr1.add(8).cast::<*mut u8>();
+                                let l_len = *ptr1
+                                    .add(8 + 1 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn add_route(
+                server_id: ServerId,
+                path: &str,
+                method: &str,
+                handler_id: HandlerId,
+            ) -> Result<RouteId, _rt::String> {
+                unsafe {
+                    #[repr(align(8))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 8 + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 8
+                            + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = path;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let vec1 = method;
+                    let ptr1 = vec1.as_ptr().cast::<u8>();
+                    let len1 = vec1.len();
+                    let ptr2 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "add-route"]
+                        fn wit_import3(
+                            _: i64,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: i64,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import3(
+                        _: i64,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: i64,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import3(
+                            _rt::as_i64(server_id),
+                            ptr0.cast_mut(),
+                            len0,
+                            ptr1.cast_mut(),
+                            len1,
+                            _rt::as_i64(handler_id),
+                            ptr2,
+                        )
+                    };
+                    let l_tag = i32::from(*ptr2.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = {
+                                let l_id = *ptr2.add(8).cast::<i64>();
+                                l_id as u64
+                            };
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr2.add(8).cast::<*mut u8>();
+                                let l_len = *ptr2
+                                    .add(8 + 1 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn remove_route(route_id: RouteId) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "remove-route"]
+                        fn wit_import1(_: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(_rt::as_i64(route_id), ptr0) };
+                    let l_tag = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr0
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l_len = *ptr0
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn add_middleware(
+                server_id: ServerId,
+                path: &str,
+                handler_id: HandlerId,
+            ) -> Result<MiddlewareId, _rt::String> {
+                unsafe {
+                    #[repr(align(8))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 8 + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 8
+                            + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = path;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let ptr1 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "add-middleware"]
+                        fn wit_import2(_: i64, _: *mut u8, _: usize, _: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import2(
+                        _: i64,
+                        _: *mut u8,
+                        _: usize,
+                        _: i64,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import2(
+                            _rt::as_i64(server_id),
+                            ptr0.cast_mut(),
+                            len0,
+                            _rt::as_i64(handler_id),
+                            ptr1,
+                        )
+                    };
+                    let l_tag = i32::from(*ptr1.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = {
+                                let l_id = *ptr1.add(8).cast::<i64>();
+                                l_id as u64
+                            };
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr1.add(8).cast::<*mut u8>();
+                                let l_len = *ptr1
+                                    .add(8 + 1 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn remove_middleware(middleware_id: MiddlewareId) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "remove-middleware"]
+                        fn wit_import1(_: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(_rt::as_i64(middleware_id), ptr0) };
+                    let l_tag = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr0
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l_len = *ptr0
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn enable_websocket(
+                server_id: ServerId,
+                path: &str,
+                connect_handler_id: Option<HandlerId>,
+                message_handler_id: HandlerId,
+                disconnect_handler_id: Option<HandlerId>,
+            ) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = path;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let (result1_0, result1_1) = match connect_handler_id {
+                        Some(e) => (1i32, _rt::as_i64(e)),
+                        None => (0i32, 0i64),
+                    };
+                    let (result2_0, result2_1) = match disconnect_handler_id {
+                        Some(e) => (1i32, _rt::as_i64(e)),
+                        None => (0i32, 0i64),
+                    };
+                    let ptr3 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "enable-websocket"]
+                        fn wit_import4(
+                            _: i64,
+                            _: *mut u8,
+                            _: usize,
+                            _: i32,
+                            _: i64,
+                            _: i64,
+                            _: i32,
+                            _: i64,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import4(
+                        _: i64,
+                        _: *mut u8,
+                        _: usize,
+                        _: i32,
+                        _: i64,
+                        _: i64,
+                        _: i32,
+                        _: i64,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import4(
+                            _rt::as_i64(server_id),
+                            ptr0.cast_mut(),
+                            len0,
+                            result1_0,
+                            result1_1,
+                            _rt::as_i64(message_handler_id),
+                            result2_0,
+                            result2_1,
+                            ptr3,
+                        )
+                    };
+                    let l_tag = i32::from(*ptr3.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr3
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l_len = *ptr3
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn send_websocket_message(
+                server_id: ServerId,
+                connection_id: u64,
+                message: &WebsocketMessage,
+            ) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let super::super::super::theater::simple::websocket_types::WebsocketMessage {
+                        ty: ty0,
+                        data: data0,
+                        text: text0,
+                    } = message;
+                    use super::super::super::theater::simple::websocket_types::MessageType as V2;
+                    let (result3_0, result3_1, result3_2) = match ty0 {
+                        V2::Text => (0i32, ::core::ptr::null_mut(), 0usize),
+                        V2::Binary => (1i32, ::core::ptr::null_mut(), 0usize),
+                        V2::Connect => (2i32, ::core::ptr::null_mut(), 0usize),
+                        V2::Close => (3i32, ::core::ptr::null_mut(), 0usize),
+                        V2::Ping => (4i32, ::core::ptr::null_mut(), 0usize),
+                        V2::Pong => (5i32, ::core::ptr::null_mut(), 0usize),
+                        V2::Other(e) => {
+                            let vec1 = e;
+                            let ptr1 = vec1.as_ptr().cast::<u8>();
+                            let len1 = vec1.len();
+                            (6i32, ptr1.cast_mut(), len1)
+                        }
+                    };
+                    let (result5_0, result5_1, result5_2) = match data0 {
+                        Some(e) => {
+                            let vec4 = e;
+                            let ptr4 = vec4.as_ptr().cast::<u8>();
+                            let len4 = vec4.len();
+                            (1i32, ptr4.cast_mut(), len4)
+                        }
+                        None => (0i32, ::core::ptr::null_mut(), 0usize),
+                    };
+                    let (result7_0, result7_1, result7_2) = match text0 {
+                        Some(e) => {
+                            let vec6 = e;
+                            let ptr6 = vec6.as_ptr().cast::<u8>();
+                            let len6 = vec6.len();
+                            (1i32, ptr6.cast_mut(), len6)
+                        }
+                        None => (0i32, ::core::ptr::null_mut(), 0usize),
+                    };
+                    let ptr8 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "send-websocket-message"]
+                        fn wit_import9(
+                            _: i64,
+                            _: i64,
+                            _: i32,
+                            _: *mut u8,
+                            _: usize,
+                            _: i32,
+                            _: *mut u8,
+                            _: usize,
+                            _: i32,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import9(
+                        _: i64,
+                        _: i64,
+                        _: i32,
+                        _: *mut u8,
+                        _: usize,
+                        _: i32,
+                        _: *mut u8,
+                        _: usize,
+                        _: i32,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import9(
+                            _rt::as_i64(server_id),
+                            _rt::as_i64(&connection_id),
+                            result3_0,
+                            result3_1,
+                            result3_2,
+                            result5_0,
+                            result5_1,
+                            result5_2,
+                            result7_0,
+                            result7_1,
+                            result7_2,
+                            ptr8,
+                        )
+                    };
+                    let l_tag = i32::from(*ptr8.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr8
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l_len = *ptr8
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn close_websocket(
+                server_id: ServerId,
+                connection_id: u64,
+            ) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-framework")]
+                    unsafe extern "C" {
+                        #[link_name = "close-websocket"]
+                        fn wit_import1(_: i64, _: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import1(
+                            _rt::as_i64(server_id),
+                            _rt::as_i64(&connection_id),
+                            ptr0,
+                        )
+                    };
+                    let l_tag = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result = match l_tag {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l_ptr = *ptr0
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l_len = *ptr0
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let bytes = _rt::Vec::from_raw_parts(l_ptr.cast(), l_len, l_len);
+                                _rt::string_lift(bytes)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result
+                }
+            }
+        }
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod timing {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Get current time
+            ///
+            /// Returns the current time in milliseconds since the UNIX epoch (January 1, 1970 UTC).
+            ///
+            /// ## Returns
+            ///
+            /// The current timestamp in milliseconds
+            ///
+            /// ## Implementation Notes
+            ///
+            /// The time value is consistent across the entire Theater runtime, ensuring that
+            /// all actors have a synchronized view of time.
+            pub fn now() -> u64 {
+                unsafe {
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/timing")]
+                    unsafe extern "C" {
+                        #[link_name = "now"]
+                        fn wit_import0() -> i64;
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import0() -> i64 {
+                        unreachable!()
+                    }
+                    let ret = unsafe { wit_import0() };
+                    ret as u64
+                }
+            }
+        }
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod random {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            #[allow(unused_unsafe, clippy::all)]
+            pub fn random_range(min: u64, max: u64) -> Result<u64, _rt::String> {
+                unsafe {
+                    #[repr(align(8))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 8 + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 8
+                            + 2 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/random")]
+                    unsafe extern "C" {
+                        #[link_name = "random-range"]
+                        fn wit_import1(_: i64, _: i64, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: i64, _: i64, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(_rt::as_i64(&min), _rt::as_i64(&max), ptr0) };
+                    let l2 = i32::from(*ptr0.add(0).cast::<u8>());
+                    let result7 = match l2 {
+                        0 => {
+                            let e = {
+                                let l3 = *ptr0.add(8).cast::<i64>();
+                                l3 as u64
+                            };
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l4 = *ptr0.add(8).cast::<*mut u8>();
+                                let l5 = *ptr0
+                                    .add(8 + 1 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len6 = l5;
+                                let bytes6 = _rt::Vec::from_raw_parts(
+                                    l4.cast(),
+                                    len6,
+                                    len6,
+                                );
+                                _rt::string_lift(bytes6)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result7
+                }
+            }
+        }
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod store {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            /// # Content Reference
+            ///
+            /// A reference to content stored in the content-addressable store.
+            #[derive(Clone, serde::Deserialize, serde::Serialize)]
+            pub struct ContentRef {
+                /// Cryptographic hash of the content (SHA-256 in hexadecimal format)
+                pub hash: _rt::String,
+            }
+            impl ::core::fmt::Debug for ContentRef {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("ContentRef").field("hash", &self.hash).finish()
+                }
+            }
+            /// A `string` at `ptr`, as lowered into a return area.
+            unsafe fn lift_string(ptr: *mut u8) -> _rt::String {
+                unsafe {
+                    let l0 = *ptr.cast::<*mut u8>();
+                    let l1 = *ptr.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+                    let bytes2 = _rt::Vec::from_raw_parts(l0.cast(), l1, l1);
+                    _rt::string_lift(bytes2)
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Create a new store
+            ///
+            /// Creates a new content-addressable store instance.
+            ///
+            /// ## Returns
+            ///
+            /// * `Ok(string)` - The ID of the newly created store
+            /// * `Err(string)` - Error message if store creation fails
+            pub fn new() -> Result<_rt::String, _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/store")]
+                    unsafe extern "C" {
+                        #[link_name = "new"]
+                        fn wit_import1(_: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(_: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import1(ptr0) };
+                    let l2 = i32::from(*ptr0.add(0).cast::<u8>());
+                    let payload = ptr0.add(::core::mem::size_of::<*const u8>());
+                    match l2 {
+                        0 => Ok(lift_string(payload)),
+                        1 => Err(lift_string(payload)),
+                        _ => _rt::invalid_enum_discriminant(),
+                    }
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Store content
+            ///
+            /// Stores content in the content-addressable store and returns a reference to it.
+            /// If identical content already exists in the store, the existing content
+            /// reference is returned without storing a duplicate copy.
+            pub fn store(store_id: &str, content: &[u8]) -> Result<ContentRef, _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = store_id;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let vec1 = content;
+                    let ptr1 = vec1.as_ptr().cast::<u8>();
+                    let len1 = vec1.len();
+                    let ptr2 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/store")]
+                    unsafe extern "C" {
+                        #[link_name = "store"]
+                        fn wit_import3(
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import3(
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import3(ptr0.cast_mut(), len0, ptr1.cast_mut(), len1, ptr2)
+                    };
+                    let l4 = i32::from(*ptr2.add(0).cast::<u8>());
+                    let payload = ptr2.add(::core::mem::size_of::<*const u8>());
+                    match l4 {
+                        0 => {
+                            Ok(ContentRef {
+                                hash: lift_string(payload),
+                            })
+                        }
+                        1 => Err(lift_string(payload)),
+                        _ => _rt::invalid_enum_discriminant(),
+                    }
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Retrieve content
+            ///
+            /// Retrieves content from the store using its content reference.
+            pub fn get(
+                store_id: &str,
+                content_ref: &ContentRef,
+            ) -> Result<_rt::Vec<u8>, _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = store_id;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let ContentRef { hash: hash1 } = content_ref;
+                    let vec2 = hash1;
+                    let ptr2 = vec2.as_ptr().cast::<u8>();
+                    let len2 = vec2.len();
+                    let ptr3 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/store")]
+                    unsafe extern "C" {
+                        #[link_name = "get"]
+                        fn wit_import4(
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import4(
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import4(ptr0.cast_mut(), len0, ptr2.cast_mut(), len2, ptr3)
+                    };
+                    let l5 = i32::from(*ptr3.add(0).cast::<u8>());
+                    let payload = ptr3.add(::core::mem::size_of::<*const u8>());
+                    match l5 {
+                        0 => {
+                            let l6 = *payload.cast::<*mut u8>();
+                            let l7 = *payload
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            Ok(_rt::Vec::from_raw_parts(l6.cast(), l7, l7))
+                        }
+                        1 => Err(lift_string(payload)),
+                        _ => _rt::invalid_enum_discriminant(),
+                    }
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Label content
+            ///
+            /// Attaches a human-readable label to a content reference.
+            pub fn label(
+                store_id: &str,
+                label: &str,
+                content_ref: &ContentRef,
+            ) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = store_id;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let vec1 = label;
+                    let ptr1 = vec1.as_ptr().cast::<u8>();
+                    let len1 = vec1.len();
+                    let ContentRef { hash: hash2 } = content_ref;
+                    let vec3 = hash2;
+                    let ptr3 = vec3.as_ptr().cast::<u8>();
+                    let len3 = vec3.len();
+                    let ptr4 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/store")]
+                    unsafe extern "C" {
+                        #[link_name = "label"]
+                        fn wit_import5(
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import5(
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import5(
+                            ptr0.cast_mut(),
+                            len0,
+                            ptr1.cast_mut(),
+                            len1,
+                            ptr3.cast_mut(),
+                            len3,
+                            ptr4,
+                        )
+                    };
+                    let l6 = i32::from(*ptr4.add(0).cast::<u8>());
+                    match l6 {
+                        0 => Ok(()),
+                        1 => Err(lift_string(ptr4.add(::core::mem::size_of::<*const u8>()))),
+                        _ => _rt::invalid_enum_discriminant(),
+                    }
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Get content reference by label
+            ///
+            /// Retrieves the content reference a label points to, if any.
+            pub fn get_by_label(
+                store_id: &str,
+                label: &str,
+            ) -> Result<Option<ContentRef>, _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 4 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 4
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = store_id;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let vec1 = label;
+                    let ptr1 = vec1.as_ptr().cast::<u8>();
+                    let len1 = vec1.len();
+                    let ptr2 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/store")]
+                    unsafe extern "C" {
+                        #[link_name = "get-by-label"]
+                        fn wit_import3(
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import3(
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import3(ptr0.cast_mut(), len0, ptr1.cast_mut(), len1, ptr2)
+                    };
+                    let l4 = i32::from(*ptr2.add(0).cast::<u8>());
+                    let payload = ptr2.add(::core::mem::size_of::<*const u8>());
+                    match l4 {
+                        0 => {
+                            let l5 = i32::from(*payload.cast::<u8>());
+                            match l5 {
+                                0 => Ok(None),
+                                1 => {
+                                    Ok(
+                                        Some(ContentRef {
+                                            hash: lift_string(
+                                                payload.add(::core::mem::size_of::<*const u8>()),
+                                            ),
+                                        }),
+                                    )
+                                }
+                                _ => _rt::invalid_enum_discriminant(),
+                            }
+                        }
+                        1 => Err(lift_string(payload)),
+                        _ => _rt::invalid_enum_discriminant(),
+                    }
+                }
+            }
+        }
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod http_client {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            pub type HttpRequest = super::super::super::theater::simple::http_types::HttpRequest;
+            pub type HttpResponse = super::super::super::theater::simple::http_types::HttpResponse;
+            /// A `tuple<string, string>` as lowered into a list.
+            #[repr(C)]
+            struct LoweredHeader {
+                name: *const u8,
+                name_len: usize,
+                value: *const u8,
+                value_len: usize,
+            }
+            /// A `string` at `ptr`, as lowered into a return area or a list.
+            unsafe fn lift_string(ptr: *mut u8) -> _rt::String {
+                unsafe {
+                    let l0 = *ptr.cast::<*mut u8>();
+                    let l1 = *ptr.add(::core::mem::size_of::<*const u8>()).cast::<usize>();
+                    let bytes2 = _rt::Vec::from_raw_parts(l0.cast(), l1, l1);
+                    _rt::string_lift(bytes2)
+                }
+            }
+            #[allow(unused_unsafe, clippy::all)]
+            /// # Send an HTTP request
+            ///
+            /// Sends `req` from the host and waits for the response.
+            ///
+            /// ## Returns
+            ///
+            /// * `Ok(http-response)` - The response, whatever its status
+            /// * `Err(string)` - Error message if the request could not be sent
+            pub fn send_http(req: &HttpRequest) -> Result<HttpResponse, _rt::String> {
+                unsafe {
+                    const P: usize = ::core::mem::size_of::<*const u8>();
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea([::core::mem::MaybeUninit<u8>; 7 * P]);
+                    let mut ret_area = RetArea([::core::mem::MaybeUninit::uninit(); 7 * P]);
+                    let headers: _rt::Vec<LoweredHeader> = req
+                        .headers
+                        .iter()
+                        .map(|(name, value)| LoweredHeader {
+                            name: name.as_ptr(),
+                            name_len: name.len(),
+                            value: value.as_ptr(),
+                            value_len: value.len(),
+                        })
+                        .collect();
+                    let (body_tag, body_ptr, body_len) = match &req.body {
+                        Some(body) => (1i32, body.as_ptr(), body.len()),
+                        None => (0i32, ::core::ptr::null(), 0),
+                    };
+                    let ptr0 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "theater:simple/http-client")]
+                    unsafe extern "C" {
+                        #[link_name = "send-http"]
+                        fn wit_import1(
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                            _: usize,
+                            _: i32,
+                            _: *mut u8,
+                            _: usize,
+                            _: *mut u8,
+                        );
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import1(
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                        _: usize,
+                        _: i32,
+                        _: *mut u8,
+                        _: usize,
+                        _: *mut u8,
+                    ) {
+                        unreachable!()
+                    }
+                    unsafe {
+                        wit_import1(
+                            req.method.as_ptr().cast_mut(),
+                            req.method.len(),
+                            req.uri.as_ptr().cast_mut(),
+                            req.uri.len(),
+                            headers.as_ptr().cast::<u8>().cast_mut(),
+                            headers.len(),
+                            body_tag,
+                            body_ptr.cast_mut(),
+                            body_len,
+                            ptr0,
+                        )
+                    };
+                    drop(headers);
+                    let l2 = i32::from(*ptr0.add(0).cast::<u8>());
+                    let payload = ptr0.add(P);
+                    match l2 {
+                        0 => {
+                            let status = *payload.cast::<u16>();
+                            let base = *payload.add(P).cast::<*mut u8>();
+                            let len = *payload.add(2 * P).cast::<usize>();
+                            let mut headers = _rt::Vec::with_capacity(len);
+                            for i in 0..len {
+                                let entry = base.add(i * 4 * P);
+                                headers.push((lift_string(entry), lift_string(entry.add(2 * P))));
+                            }
+                            _rt::cabi_dealloc(base, len * 4 * P, P);
+                            let body = match i32::from(*payload.add(3 * P).cast::<u8>()) {
+                                0 => None,
+                                1 => {
+                                    let l3 = *payload.add(4 * P).cast::<*mut u8>();
+                                    let l4 = *payload.add(5 * P).cast::<usize>();
+                                    Some(_rt::Vec::from_raw_parts(l3.cast(), l4, l4))
+                                }
+                                _ => _rt::invalid_enum_discriminant(),
+                            };
+                            Ok(HttpResponse {
+                                status,
+                                headers,
+                                body,
+                            })
+                        }
+                        1 => Err(lift_string(payload)),
+                        _ => _rt::invalid_enum_discriminant(),
+                    }
+                }
+            }
+        }
     }
 }
 #[rustfmt::skip]
@@ -2233,21 +4056,353 @@ pub mod exports {
             pub mod actor {
                 #[used]
                 #[doc(hidden)]
-                static __FORCE_SECTION_REF: fn() = super::super::super::super::__link_custom_section_describing_imports;
-                use super::super::super::super::_rt;
+                static __FORCE_SECTION_REF: fn() = super::super::super::super::__link_custom_section_describing_imports;
+                use super::super::super::super::_rt;
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_init_cabi<T: Guest>(
+                    arg0: i32,
+                    arg1: *mut u8,
+                    arg2: usize,
+                    arg3: *mut u8,
+                    arg4: usize,
+                ) -> *mut u8 {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let len1 = arg4;
+                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
+                    let result2 = T::init(
+                        match arg0 {
+                            0 => None,
+                            1 => {
+                                let e = {
+                                    let len0 = arg2;
+                                    _rt::Vec::from_raw_parts(arg1.cast(), len0, len0)
+                                };
+                                Some(e)
+                            }
+                            _ => _rt::invalid_enum_discriminant(),
+                        },
+                        (_rt::string_lift(bytes1),),
+                    );
+                    let ptr3 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result2 {
+                        Ok(e) => {
+                            *ptr3.add(0).cast::<u8>() = (0i32) as u8;
+                            let (t4_0,) = e;
+                            match t4_0 {
+                                Some(e) => {
+                                    *ptr3
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec5 = (e).into_boxed_slice();
+                                    let ptr5 = vec5.as_ptr().cast::<u8>();
+                                    let len5 = vec5.len();
+                                    ::core::mem::forget(vec5);
+                                    *ptr3
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len5;
+                                    *ptr3
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr5.cast_mut();
+                                }
+                                None => {
+                                    *ptr3
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                        }
+                        Err(e) => {
+                            *ptr3.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec6 = (e.into_bytes()).into_boxed_slice();
+                            let ptr6 = vec6.as_ptr().cast::<u8>();
+                            let len6 = vec6.len();
+                            ::core::mem::forget(vec6);
+                            *ptr3
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len6;
+                            *ptr3
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr6.cast_mut();
+                        }
+                    };
+                    ptr3
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn __post_return_init<T: Guest>(arg0: *mut u8) {
+                    let l0 = i32::from(*arg0.add(0).cast::<u8>());
+                    match l0 {
+                        0 => {
+                            let l1 = i32::from(
+                                *arg0.add(::core::mem::size_of::<*const u8>()).cast::<u8>(),
+                            );
+                            match l1 {
+                                0 => {}
+                                _ => {
+                                    let l2 = *arg0
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l3 = *arg0
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base4 = l2;
+                                    let len4 = l3;
+                                    _rt::cabi_dealloc(base4, len4 * 1, 1);
+                                }
+                            }
+                        }
+                        _ => {
+                            let l5 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l6 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l5, l6, 1);
+                        }
+                    }
+                }
+                pub trait Guest {
+                    /// # Initialize the actor
+                    ///
+                    /// Called when the actor is first started or restarted. This function is responsible
+                    /// for setting up the actor's initial state and responding to initialization parameters.
+                    ///
+                    /// ## Parameters
+                    ///
+                    /// * `state` - Current state of the actor, or None if first initialization
+                    /// * `params` - Tuple of initialization parameters, typically including actor ID
+                    ///
+                    /// ## Returns
+                    ///
+                    /// * `Ok((state,))` - The updated state to store
+                    /// * `Err(string)` - An error message if initialization fails
+                    ///
+                    /// ## Implementation Notes
+                    ///
+                    /// - If state is None, the actor should create a new initial state
+                    /// - If state contains data, the actor should validate and use that state
+                    /// - The first parameter in the tuple is typically the actor's ID
+                    /// - Any error returned will cause the actor to fail to start
+                    fn init(
+                        state: Option<_rt::Vec<u8>>,
+                        params: (_rt::String,),
+                    ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
+                }
+                #[doc(hidden)]
+                macro_rules! __export_theater_simple_actor_cabi {
+                    ($ty:ident with_types_in $($path_to_types:tt)*) => {
+                        const _ : () = { #[unsafe (export_name =
+                        "theater:simple/actor#init")] unsafe extern "C" fn
+                        export_init(arg0 : i32, arg1 : * mut u8, arg2 : usize, arg3 : *
+                        mut u8, arg4 : usize,) -> * mut u8 { unsafe {
+                        $($path_to_types)*:: _export_init_cabi::<$ty > (arg0, arg1, arg2,
+                        arg3, arg4) } } #[unsafe (export_name =
+                        "cabi_post_theater:simple/actor#init")] unsafe extern "C" fn
+                        _post_return_init(arg0 : * mut u8,) { unsafe {
+                        $($path_to_types)*:: __post_return_init::<$ty > (arg0) } } };
+                    };
+                }
+                #[doc(hidden)]
+                pub(crate) use __export_theater_simple_actor_cabi;
+                #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                struct _RetArea(
+                    [::core::mem::MaybeUninit<
+                        u8,
+                    >; 4 * ::core::mem::size_of::<*const u8>()],
+                );
+                static mut _RET_AREA: _RetArea = _RetArea(
+                    [::core::mem::MaybeUninit::uninit(); 4
+                        * ::core::mem::size_of::<*const u8>()],
+                );
+            }
+            /// # Message Server Client Interface
+            ///
+            /// Defines the callback handlers that actors must implement to receive messages through
+            /// the message server system.
+            ///
+            /// ## Purpose
+            ///
+            /// This interface enables actors to receive and process various types of messages:
+            /// - One-way messages (send)
+            /// - Request-response interactions (request)
+            /// - Bidirectional channel-based communication (channel operations)
+            ///
+            /// By implementing these handler functions, an actor can participate in different
+            /// communication patterns with other actors and external systems.
+            ///
+            /// ## Example
+            ///
+            /// ```rust
+            /// use ntwk::theater::message_server_client::Guest;
+            /// use ntwk::theater::types::{channel_accept, channel_id};
+            /// use serde_json::{json, Value};
+            ///
+            /// struct MyMessageHandler;
+            ///
+            /// impl Guest for MyMessageHandler {
+            /// fn handle_send(state: Option<Value>, params: (Value,))
+            /// -> Result<(Option<Value>,), String> {
+            /// let (message,) = params;
+            /// println!("Received message: {}", message);
+            ///
+            /// // Update state if needed
+            /// let new_state = if let Some(mut state) = state {
+            /// state["message_count"] = json!(state["message_count"].as_u64().unwrap_or(0) + 1);
+            /// Some(state)
+            /// } else {
+            /// Some(json!({"message_count": 1}))
+            /// };
+            ///
+            /// Ok((new_state,))
+            /// }
+            ///
+            /// // Implement other handlers...
+            /// }
+            /// ```
+            ///
+            /// ## Security
+            ///
+            /// The message handlers receive input from potentially untrusted sources, so they should:
+            /// - Validate all incoming message data
+            /// - Handle malformed messages gracefully
+            /// - Protect against common attack vectors like JSON injection
+            ///
+            /// ## Implementation Notes
+            ///
+            /// - All handlers receive and can update the actor's state
+            /// - Errors returned from handlers are logged and may trigger supervision
+            /// - Handler execution is tracked in the actor's event chain
+            #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+            pub mod message_server_client {
+                #[used]
+                #[doc(hidden)]
+                static __FORCE_SECTION_REF: fn() = super::super::super::super::__link_custom_section_describing_imports;
+                use super::super::super::super::_rt;
+                pub type ChannelId = super::super::super::super::theater::simple::types::ChannelId;
+                pub type ChannelAccept = super::super::super::super::theater::simple::types::ChannelAccept;
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_handle_send_cabi<T: Guest>(
+                    arg0: i32,
+                    arg1: *mut u8,
+                    arg2: usize,
+                    arg3: *mut u8,
+                    arg4: usize,
+                ) -> *mut u8 {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let len1 = arg4;
+                    let result2 = T::handle_send(
+                        match arg0 {
+                            0 => None,
+                            1 => {
+                                let e = {
+                                    let len0 = arg2;
+                                    _rt::Vec::from_raw_parts(arg1.cast(), len0, len0)
+                                };
+                                Some(e)
+                            }
+                            _ => _rt::invalid_enum_discriminant(),
+                        },
+                        (_rt::Vec::from_raw_parts(arg3.cast(), len1, len1),),
+                    );
+                    let ptr3 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result2 {
+                        Ok(e) => {
+                            *ptr3.add(0).cast::<u8>() = (0i32) as u8;
+                            let (t4_0,) = e;
+                            match t4_0 {
+                                Some(e) => {
+                                    *ptr3
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec5 = (e).into_boxed_slice();
+                                    let ptr5 = vec5.as_ptr().cast::<u8>();
+                                    let len5 = vec5.len();
+                                    ::core::mem::forget(vec5);
+                                    *ptr3
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len5;
+                                    *ptr3
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr5.cast_mut();
+                                }
+                                None => {
+                                    *ptr3
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                        }
+                        Err(e) => {
+                            *ptr3.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec6 = (e.into_bytes()).into_boxed_slice();
+                            let ptr6 = vec6.as_ptr().cast::<u8>();
+                            let len6 = vec6.len();
+                            ::core::mem::forget(vec6);
+                            *ptr3
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len6;
+                            *ptr3
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr6.cast_mut();
+                        }
+                    };
+                    ptr3
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn __post_return_handle_send<T: Guest>(arg0: *mut u8) {
+                    let l0 = i32::from(*arg0.add(0).cast::<u8>());
+                    match l0 {
+                        0 => {
+                            let l1 = i32::from(
+                                *arg0.add(::core::mem::size_of::<*const u8>()).cast::<u8>(),
+                            );
+                            match l1 {
+                                0 => {}
+                                _ => {
+                                    let l2 = *arg0
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l3 = *arg0
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base4 = l2;
+                                    let len4 = l3;
+                                    _rt::cabi_dealloc(base4, len4 * 1, 1);
+                                }
+                            }
+                        }
+                        _ => {
+                            let l5 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l6 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l5, l6, 1);
+                        }
+                    }
+                }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn _export_init_cabi<T: Guest>(
+                pub unsafe fn _export_handle_request_cabi<T: Guest>(
                     arg0: i32,
                     arg1: *mut u8,
                     arg2: usize,
                     arg3: *mut u8,
                     arg4: usize,
+                    arg5: *mut u8,
+                    arg6: usize,
                 ) -> *mut u8 {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
                     let len1 = arg4;
                     let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
-                    let result2 = T::init(
+                    let len2 = arg6;
+                    let result3 = T::handle_request(
                         match arg0 {
                             0 => None,
                             1 => {
@@ -2259,55 +4414,246 @@ pub mod exports {
                             }
                             _ => _rt::invalid_enum_discriminant(),
                         },
-                        (_rt::string_lift(bytes1),),
+                        (
+                            _rt::string_lift(bytes1),
+                            _rt::Vec::from_raw_parts(arg5.cast(), len2, len2),
+                        ),
                     );
-                    let ptr3 = (&raw mut _RET_AREA.0).cast::<u8>();
-                    match result2 {
+                    let ptr4 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result3 {
                         Ok(e) => {
-                            *ptr3.add(0).cast::<u8>() = (0i32) as u8;
-                            let (t4_0,) = e;
-                            match t4_0 {
+                            *ptr4.add(0).cast::<u8>() = (0i32) as u8;
+                            let (t5_0, t5_1) = e;
+                            match t5_0 {
+                                Some(e) => {
+                                    *ptr4
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec6 = (e).into_boxed_slice();
+                                    let ptr6 = vec6.as_ptr().cast::<u8>();
+                                    let len6 = vec6.len();
+                                    ::core::mem::forget(vec6);
+                                    *ptr4
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len6;
+                                    *ptr4
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr6.cast_mut();
+                                }
+                                None => {
+                                    *ptr4
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                            let (t7_0,) = t5_1;
+                            match t7_0 {
+                                Some(e) => {
+                                    *ptr4
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec8 = (e).into_boxed_slice();
+                                    let ptr8 = vec8.as_ptr().cast::<u8>();
+                                    let len8 = vec8.len();
+                                    ::core::mem::forget(vec8);
+                                    *ptr4
+                                        .add(6 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len8;
+                                    *ptr4
+                                        .add(5 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr8.cast_mut();
+                                }
+                                None => {
+                                    *ptr4
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                        }
+                        Err(e) => {
+                            *ptr4.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec9 = (e.into_bytes()).into_boxed_slice();
+                            let ptr9 = vec9.as_ptr().cast::<u8>();
+                            let len9 = vec9.len();
+                            ::core::mem::forget(vec9);
+                            *ptr4
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len9;
+                            *ptr4
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr9.cast_mut();
+                        }
+                    };
+                    ptr4
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn __post_return_handle_request<T: Guest>(arg0: *mut u8) {
+                    let l0 = i32::from(*arg0.add(0).cast::<u8>());
+                    match l0 {
+                        0 => {
+                            let l1 = i32::from(
+                                *arg0.add(::core::mem::size_of::<*const u8>()).cast::<u8>(),
+                            );
+                            match l1 {
+                                0 => {}
+                                _ => {
+                                    let l2 = *arg0
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l3 = *arg0
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base4 = l2;
+                                    let len4 = l3;
+                                    _rt::cabi_dealloc(base4, len4 * 1, 1);
+                                }
+                            }
+                            let l5 = i32::from(
+                                *arg0
+                                    .add(4 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<u8>(),
+                            );
+                            match l5 {
+                                0 => {}
+                                _ => {
+                                    let l6 = *arg0
+                                        .add(5 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l7 = *arg0
+                                        .add(6 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base8 = l6;
+                                    let len8 = l7;
+                                    _rt::cabi_dealloc(base8, len8 * 1, 1);
+                                }
+                            }
+                        }
+                        _ => {
+                            let l9 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l10 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l9, l10, 1);
+                        }
+                    }
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_handle_channel_open_cabi<T: Guest>(
+                    arg0: i32,
+                    arg1: *mut u8,
+                    arg2: usize,
+                    arg3: *mut u8,
+                    arg4: usize,
+                    arg5: *mut u8,
+                    arg6: usize,
+                ) -> *mut u8 {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let len1 = arg4;
+                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
+                    let len2 = arg6;
+                    let result3 = T::handle_channel_open(
+                        match arg0 {
+                            0 => None,
+                            1 => {
+                                let e = {
+                                    let len0 = arg2;
+                                    _rt::Vec::from_raw_parts(arg1.cast(), len0, len0)
+                                };
+                                Some(e)
+                            }
+                            _ => _rt::invalid_enum_discriminant(),
+                        },
+                        (
+                            _rt::string_lift(bytes1),
+                            _rt::Vec::from_raw_parts(arg5.cast(), len2, len2),
+                        ),
+                    );
+                    let ptr4 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result3 {
+                        Ok(e) => {
+                            *ptr4.add(0).cast::<u8>() = (0i32) as u8;
+                            let (t5_0, t5_1) = e;
+                            match t5_0 {
                                 Some(e) => {
-                                    *ptr3
+                                    *ptr4
                                         .add(::core::mem::size_of::<*const u8>())
                                         .cast::<u8>() = (1i32) as u8;
-                                    let vec5 = (e).into_boxed_slice();
-                                    let ptr5 = vec5.as_ptr().cast::<u8>();
-                                    let len5 = vec5.len();
-                                    ::core::mem::forget(vec5);
-                                    *ptr3
+                                    let vec6 = (e).into_boxed_slice();
+                                    let ptr6 = vec6.as_ptr().cast::<u8>();
+                                    let len6 = vec6.len();
+                                    ::core::mem::forget(vec6);
+                                    *ptr4
                                         .add(3 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<usize>() = len5;
-                                    *ptr3
+                                        .cast::<usize>() = len6;
+                                    *ptr4
                                         .add(2 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<*mut u8>() = ptr5.cast_mut();
+                                        .cast::<*mut u8>() = ptr6.cast_mut();
                                 }
                                 None => {
-                                    *ptr3
+                                    *ptr4
                                         .add(::core::mem::size_of::<*const u8>())
                                         .cast::<u8>() = (0i32) as u8;
                                 }
                             };
+                            let (t7_0,) = t5_1;
+                            let super::super::super::super::theater::simple::types::ChannelAccept {
+                                accepted: accepted8,
+                                message: message8,
+                            } = t7_0;
+                            *ptr4
+                                .add(4 * ::core::mem::size_of::<*const u8>())
+                                .cast::<u8>() = (match accepted8 {
+                                true => 1,
+                                false => 0,
+                            }) as u8;
+                            match message8 {
+                                Some(e) => {
+                                    *ptr4
+                                        .add(5 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec9 = (e).into_boxed_slice();
+                                    let ptr9 = vec9.as_ptr().cast::<u8>();
+                                    let len9 = vec9.len();
+                                    ::core::mem::forget(vec9);
+                                    *ptr4
+                                        .add(7 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len9;
+                                    *ptr4
+                                        .add(6 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr9.cast_mut();
+                                }
+                                None => {
+                                    *ptr4
+                                        .add(5 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
                         }
                         Err(e) => {
-                            *ptr3.add(0).cast::<u8>() = (1i32) as u8;
-                            let vec6 = (e.into_bytes()).into_boxed_slice();
-                            let ptr6 = vec6.as_ptr().cast::<u8>();
-                            let len6 = vec6.len();
-                            ::core::mem::forget(vec6);
-                            *ptr3
+                            *ptr4.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec10 = (e.into_bytes()).into_boxed_slice();
+                            let ptr10 = vec10.as_ptr().cast::<u8>();
+                            let len10 = vec10.len();
+                            ::core::mem::forget(vec10);
+                            *ptr4
                                 .add(2 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>() = len6;
-                            *ptr3
+                                .cast::<usize>() = len10;
+                            *ptr4
                                 .add(::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>() = ptr6.cast_mut();
+                                .cast::<*mut u8>() = ptr10.cast_mut();
                         }
                     };
-                    ptr3
+                    ptr4
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn __post_return_init<T: Guest>(arg0: *mut u8) {
+                pub unsafe fn __post_return_handle_channel_open<T: Guest>(
+                    arg0: *mut u8,
+                ) {
                     let l0 = i32::from(*arg0.add(0).cast::<u8>());
                     match l0 {
                         0 => {
@@ -2328,141 +4674,153 @@ pub mod exports {
                                     _rt::cabi_dealloc(base4, len4 * 1, 1);
                                 }
                             }
+                            let l5 = i32::from(
+                                *arg0
+                                    .add(5 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<u8>(),
+                            );
+                            match l5 {
+                                0 => {}
+                                _ => {
+                                    let l6 = *arg0
+                                        .add(6 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l7 = *arg0
+                                        .add(7 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base8 = l6;
+                                    let len8 = l7;
+                                    _rt::cabi_dealloc(base8, len8 * 1, 1);
+                                }
+                            }
                         }
                         _ => {
-                            let l5 = *arg0
+                            let l9 = *arg0
                                 .add(::core::mem::size_of::<*const u8>())
                                 .cast::<*mut u8>();
-                            let l6 = *arg0
+                            let l10 = *arg0
                                 .add(2 * ::core::mem::size_of::<*const u8>())
                                 .cast::<usize>();
-                            _rt::cabi_dealloc(l5, l6, 1);
+                            _rt::cabi_dealloc(l9, l10, 1);
                         }
                     }
                 }
-                pub trait Guest {
-                    /// # Initialize the actor
-                    ///
-                    /// Called when the actor is first started or restarted. This function is responsible
-                    /// for setting up the actor's initial state and responding to initialization parameters.
-                    ///
-                    /// ## Parameters
-                    ///
-                    /// * `state` - Current state of the actor, or None if first initialization
-                    /// * `params` - Tuple of initialization parameters, typically including actor ID
-                    ///
-                    /// ## Returns
-                    ///
-                    /// * `Ok((state,))` - The updated state to store
-                    /// * `Err(string)` - An error message if initialization fails
-                    ///
-                    /// ## Implementation Notes
-                    ///
-                    /// - If state is None, the actor should create a new initial state
-                    /// - If state contains data, the actor should validate and use that state
-                    /// - The first parameter in the tuple is typically the actor's ID
-                    /// - Any error returned will cause the actor to fail to start
-                    fn init(
-                        state: Option<_rt::Vec<u8>>,
-                        params: (_rt::String,),
-                    ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
-                }
-                #[doc(hidden)]
-                macro_rules! __export_theater_simple_actor_cabi {
-                    ($ty:ident with_types_in $($path_to_types:tt)*) => {
-                        const _ : () = { #[unsafe (export_name =
-                        "theater:simple/actor#init")] unsafe extern "C" fn
-                        export_init(arg0 : i32, arg1 : * mut u8, arg2 : usize, arg3 : *
-                        mut u8, arg4 : usize,) -> * mut u8 { unsafe {
-                        $($path_to_types)*:: _export_init_cabi::<$ty > (arg0, arg1, arg2,
-                        arg3, arg4) } } #[unsafe (export_name =
-                        "cabi_post_theater:simple/actor#init")] unsafe extern "C" fn
-                        _post_return_init(arg0 : * mut u8,) { unsafe {
-                        $($path_to_types)*:: __post_return_init::<$ty > (arg0) } } };
-                    };
-                }
                 #[doc(hidden)]
-                pub(crate) use __export_theater_simple_actor_cabi;
-                #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
-                #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
-                struct _RetArea(
-                    [::core::mem::MaybeUninit<
-                        u8,
-                    >; 4 * ::core::mem::size_of::<*const u8>()],
-                );
-                static mut _RET_AREA: _RetArea = _RetArea(
-                    [::core::mem::MaybeUninit::uninit(); 4
-                        * ::core::mem::size_of::<*const u8>()],
-                );
-            }
-            /// # Message Server Client Interface
-            ///
-            /// Defines the callback handlers that actors must implement to receive messages through
-            /// the message server system.
-            ///
-            /// ## Purpose
-            ///
-            /// This interface enables actors to receive and process various types of messages:
-            /// - One-way messages (send)
-            /// - Request-response interactions (request)
-            /// - Bidirectional channel-based communication (channel operations)
-            ///
-            /// By implementing these handler functions, an actor can participate in different
-            /// communication patterns with other actors and external systems.
-            ///
-            /// ## Example
-            ///
-            /// ```rust
-            /// use ntwk::theater::message_server_client::Guest;
-            /// use ntwk::theater::types::{channel_accept, channel_id};
-            /// use serde_json::{json, Value};
-            ///
-            /// struct MyMessageHandler;
-            ///
-            /// impl Guest for MyMessageHandler {
-            /// fn handle_send(state: Option<Value>, params: (Value,))
-            /// -> Result<(Option<Value>,), String> {
-            /// let (message,) = params;
-            /// println!("Received message: {}", message);
-            ///
-            /// // Update state if needed
-            /// let new_state = if let Some(mut state) = state {
-            /// state["message_count"] = json!(state["message_count"].as_u64().unwrap_or(0) + 1);
-            /// Some(state)
-            /// } else {
-            /// Some(json!({"message_count": 1}))
-            /// };
-            ///
-            /// Ok((new_state,))
-            /// }
-            ///
-            /// // Implement other handlers...
-            /// }
-            /// ```
-            ///
-            /// ## Security
-            ///
-            /// The message handlers receive input from potentially untrusted sources, so they should:
-            /// - Validate all incoming message data
-            /// - Handle malformed messages gracefully
-            /// - Protect against common attack vectors like JSON injection
-            ///
-            /// ## Implementation Notes
-            ///
-            /// - All handlers receive and can update the actor's state
-            /// - Errors returned from handlers are logged and may trigger supervision
-            /// - Handler execution is tracked in the actor's event chain
-            #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
-            pub mod message_server_client {
-                #[used]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_handle_channel_message_cabi<T: Guest>(
+                    arg0: i32,
+                    arg1: *mut u8,
+                    arg2: usize,
+                    arg3: *mut u8,
+                    arg4: usize,
+                    arg5: *mut u8,
+                    arg6: usize,
+                ) -> *mut u8 {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let len1 = arg4;
+                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
+                    let len2 = arg6;
+                    let result3 = T::handle_channel_message(
+                        match arg0 {
+                            0 => None,
+                            1 => {
+                                let e = {
+                                    let len0 = arg2;
+                                    _rt::Vec::from_raw_parts(arg1.cast(), len0, len0)
+                                };
+                                Some(e)
+                            }
+                            _ => _rt::invalid_enum_discriminant(),
+                        },
+                        (
+                            _rt::string_lift(bytes1),
+                            _rt::Vec::from_raw_parts(arg5.cast(), len2, len2),
+                        ),
+                    );
+                    let ptr4 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result3 {
+                        Ok(e) => {
+                            *ptr4.add(0).cast::<u8>() = (0i32) as u8;
+                            let (t5_0,) = e;
+                            match t5_0 {
+                                Some(e) => {
+                                    *ptr4
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec6 = (e).into_boxed_slice();
+                                    let ptr6 = vec6.as_ptr().cast::<u8>();
+                                    let len6 = vec6.len();
+                                    ::core::mem::forget(vec6);
+                                    *ptr4
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len6;
+                                    *ptr4
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr6.cast_mut();
+                                }
+                                None => {
+                                    *ptr4
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                        }
+                        Err(e) => {
+                            *ptr4.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec7 = (e.into_bytes()).into_boxed_slice();
+                            let ptr7 = vec7.as_ptr().cast::<u8>();
+                            let len7 = vec7.len();
+                            ::core::mem::forget(vec7);
+                            *ptr4
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len7;
+                            *ptr4
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr7.cast_mut();
+                        }
+                    };
+                    ptr4
+                }
                 #[doc(hidden)]
-                static __FORCE_SECTION_REF: fn() = super::super::super::super::__link_custom_section_describing_imports;
-                use super::super::super::super::_rt;
-                pub type ChannelId = super::super::super::super::theater::simple::types::ChannelId;
-                pub type ChannelAccept = super::super::super::super::theater::simple::types::ChannelAccept;
+                #[allow(non_snake_case)]
+                pub unsafe fn __post_return_handle_channel_message<T: Guest>(
+                    arg0: *mut u8,
+                ) {
+                    let l0 = i32::from(*arg0.add(0).cast::<u8>());
+                    match l0 {
+                        0 => {
+                            let l1 = i32::from(
+                                *arg0.add(::core::mem::size_of::<*const u8>()).cast::<u8>(),
+                            );
+                            match l1 {
+                                0 => {}
+                                _ => {
+                                    let l2 = *arg0
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l3 = *arg0
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base4 = l2;
+                                    let len4 = l3;
+                                    _rt::cabi_dealloc(base4, len4 * 1, 1);
+                                }
+                            }
+                        }
+                        _ => {
+                            let l5 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l6 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l5, l6, 1);
+                        }
+                    }
+                }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn _export_handle_send_cabi<T: Guest>(
+                pub unsafe fn _export_handle_channel_close_cabi<T: Guest>(
                     arg0: i32,
                     arg1: *mut u8,
                     arg2: usize,
@@ -2471,7 +4829,8 @@ pub mod exports {
                 ) -> *mut u8 {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
                     let len1 = arg4;
-                    let result2 = T::handle_send(
+                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
+                    let result2 = T::handle_channel_close(
                         match arg0 {
                             0 => None,
                             1 => {
@@ -2483,7 +4842,7 @@ pub mod exports {
                             }
                             _ => _rt::invalid_enum_discriminant(),
                         },
-                        (_rt::Vec::from_raw_parts(arg3.cast(), len1, len1),),
+                        (_rt::string_lift(bytes1),),
                     );
                     let ptr3 = (&raw mut _RET_AREA.0).cast::<u8>();
                     match result2 {
@@ -2531,7 +4890,9 @@ pub mod exports {
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn __post_return_handle_send<T: Guest>(arg0: *mut u8) {
+                pub unsafe fn __post_return_handle_channel_close<T: Guest>(
+                    arg0: *mut u8,
+                ) {
                     let l0 = i32::from(*arg0.add(0).cast::<u8>());
                     match l0 {
                         0 => {
@@ -2564,175 +4925,291 @@ pub mod exports {
                         }
                     }
                 }
+                pub trait Guest {
+                    /// # Handle one-way message
+                    ///
+                    /// Processes a one-way message that doesn't require a response.
+                    ///
+                    /// ## Parameters
+                    ///
+                    /// * `state` - The current actor state or None if not initialized
+                    /// * `params` - Tuple containing:
+                    /// * `json` - The message payload
+                    ///
+                    /// ## Returns
+                    ///
+                    /// * `Ok((option<list<u8>>,))` - Updated actor state (or None to retain current state)
+                    /// * `Err(string)` - Error message if message handling fails
+                    ///
+                    /// ## Example
+                    ///
+                    /// ```rust
+                    /// fn handle_send(state: Option<Value>, params: (Value,)) -> Result<(Option<Value>,), String> {
+                    /// let (message,) = params;
+                    ///
+                    /// // Process the message...
+                    ///
+                    /// // Return updated state (or None to keep current state)
+                    /// Ok((Some(updated_state),))
+                    /// }
+                    /// ```
+                    fn handle_send(
+                        state: Option<_rt::Vec<u8>>,
+                        params: (_rt::Vec<u8>,),
+                    ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
+                    /// # Handle request-response message
+                    ///
+                    /// Processes a request that requires a response.
+                    ///
+                    /// ## Parameters
+                    ///
+                    /// * `state` - The current actor state or None if not initialized
+                    /// * `params` - Tuple containing:
+                    /// * `string` - The request ID
+                    /// * `json` - The request payload
+                    ///
+                    /// ## Returns
+                    ///
+                    /// * `Ok((option<list<u8>>, (option<list<u8>>,)))` - Tuple containing:
+                    /// * Updated actor state (or None)
+                    /// * Response message to send back (or None to send a response yet)
+                    /// * `Err(string)` - Error message if request handling fails
+                    ///
+                    /// ## Example
+                    ///
+                    /// ```rust
+                    /// fn handle_request(state: Option<Vec<u8>>, params: (String, Vec<u8>))
+                    /// -> Result<(Option<Vec<u8>>, (Option<Vec<u8>>,)), String> {
+                    /// let (request_id, request) = params;
+                    ///
+                    /// // Process the request...
+                    /// let response = json!({"status": "success", "data": "result"});
+                    ///
+                    /// // Return updated state and response
+                    /// Ok((Some(updated_state), (Some(response),)))
+                    /// }
+                    /// ```
+                    fn handle_request(
+                        state: Option<_rt::Vec<u8>>,
+                        params: (_rt::String, _rt::Vec<u8>),
+                    ) -> Result<
+                        (Option<_rt::Vec<u8>>, (Option<_rt::Vec<u8>>,)),
+                        _rt::String,
+                    >;
+                    /// # Handle channel open request
+                    ///
+                    /// Called when another actor requests to open a communication channel.
+                    ///
+                    /// ## Parameters
+                    ///
+                    /// * `state` - The current actor state or None if not initialized
+                    /// * `params` - Tuple containing:
+                    /// * `json` - The initial message payload
+                    ///
+                    /// ## Returns
+                    ///
+                    /// * `Ok((option<list<u8>>, (channel-accept,)))` - Tuple containing:
+                    /// * Updated actor state (or None to retain current state)
+                    /// * Channel acceptance decision
+                    /// * `Err(string)` - Error message if open handling fails
+                    ///
+                    /// ## Example
+                    ///
+                    /// ```rust
+                    /// fn handle_channel_open(state: Option<Value>, params: (Value,))
+                    /// -> Result<(Option<Value>, (channel_accept,)), String> {
+                    /// let (initial_message,) = params;
+                    ///
+                    /// // Decide whether to accept the channel
+                    /// let accept = channel_accept {
+                    /// accept: true,
+                    /// error_message: None,
+                    /// };
+                    ///
+                    /// // Return updated state and acceptance decision
+                    /// Ok((Some(updated_state), (accept,)))
+                    /// }
+                    /// ```
+                    ///
+                    /// ## Security
+                    ///
+                    /// The actor should validate the channel request and only accept channels from
+                    /// trusted sources. The acceptance mechanism provides a security checkpoint.
+                    fn handle_channel_open(
+                        state: Option<_rt::Vec<u8>>,
+                        params: (_rt::String, _rt::Vec<u8>),
+                    ) -> Result<(Option<_rt::Vec<u8>>, (ChannelAccept,)), _rt::String>;
+                    /// # Handle channel message
+                    ///
+                    /// Processes a message received on an established channel.
+                    ///
+                    /// ## Parameters
+                    ///
+                    /// * `state` - The current actor state or None if not initialized
+                    /// * `params` - Tuple containing:
+                    /// * `channel-id` - ID of the channel the message was received on
+                    /// * `json` - The message payload
+                    ///
+                    /// ## Returns
+                    ///
+                    /// * `Ok((option<list<u8>>,))` - Updated actor state (or None to retain current state)
+                    /// * `Err(string)` - Error message if message handling fails
+                    ///
+                    /// ## Example
+                    ///
+                    /// ```rust
+                    /// fn handle_channel_message(state: Option<Value>, params: (channel_id, Value))
+                    /// -> Result<(Option<Value>,), String> {
+                    /// let (channel_id, message) = params;
+                    ///
+                    /// // Process the channel message...
+                    /// println!("Received message on channel {}: {}", channel_id, message);
+                    ///
+                    /// // Return updated state (or None to keep current state)
+                    /// Ok((Some(updated_state),))
+                    /// }
+                    /// ```
+                    fn handle_channel_message(
+                        state: Option<_rt::Vec<u8>>,
+                        params: (ChannelId, _rt::Vec<u8>),
+                    ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
+                    /// # Handle channel close
+                    ///
+                    /// Called when a communication channel is closed.
+                    ///
+                    /// ## Parameters
+                    ///
+                    /// * `state` - The current actor state or None if not initialized
+                    /// * `params` - Tuple containing:
+                    /// * `channel-id` - ID of the channel that was closed
+                    ///
+                    /// ## Returns
+                    ///
+                    /// * `Ok((option<list<u8>>,))` - Updated actor state (or None to retain current state)
+                    /// * `Err(string)` - Error message if close handling fails
+                    ///
+                    /// ## Example
+                    ///
+                    /// ```rust
+                    /// fn handle_channel_close(state: Option<Value>, params: (channel_id,))
+                    /// -> Result<(Option<Value>,), String> {
+                    /// let (channel_id,) = params;
+                    ///
+                    /// // Clean up any resources associated with the channel
+                    /// println!("Channel {} closed", channel_id);
+                    ///
+                    /// // Return updated state (or None to keep current state)
+                    /// Ok((Some(updated_state),))
+                    /// }
+                    /// ```
+                    ///
+                    /// ## Implementation Notes
+                    ///
+                    /// This function should perform any necessary cleanup for the closed channel,
+                    /// such as releasing resources or updating internal state to reflect the channel closure.
+                    fn handle_channel_close(
+                        state: Option<_rt::Vec<u8>>,
+                        params: (ChannelId,),
+                    ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
+                }
                 #[doc(hidden)]
-                #[allow(non_snake_case)]
-                pub unsafe fn _export_handle_request_cabi<T: Guest>(
-                    arg0: i32,
-                    arg1: *mut u8,
-                    arg2: usize,
-                    arg3: *mut u8,
-                    arg4: usize,
-                    arg5: *mut u8,
-                    arg6: usize,
-                ) -> *mut u8 {
-                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
-                    let len1 = arg4;
-                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
-                    let len2 = arg6;
-                    let result3 = T::handle_request(
-                        match arg0 {
-                            0 => None,
-                            1 => {
-                                let e = {
-                                    let len0 = arg2;
-                                    _rt::Vec::from_raw_parts(arg1.cast(), len0, len0)
-                                };
-                                Some(e)
-                            }
-                            _ => _rt::invalid_enum_discriminant(),
-                        },
-                        (
-                            _rt::string_lift(bytes1),
-                            _rt::Vec::from_raw_parts(arg5.cast(), len2, len2),
-                        ),
-                    );
-                    let ptr4 = (&raw mut _RET_AREA.0).cast::<u8>();
-                    match result3 {
-                        Ok(e) => {
-                            *ptr4.add(0).cast::<u8>() = (0i32) as u8;
-                            let (t5_0, t5_1) = e;
-                            match t5_0 {
-                                Some(e) => {
-                                    *ptr4
-                                        .add(::core::mem::size_of::<*const u8>())
-                                        .cast::<u8>() = (1i32) as u8;
-                                    let vec6 = (e).into_boxed_slice();
-                                    let ptr6 = vec6.as_ptr().cast::<u8>();
-                                    let len6 = vec6.len();
-                                    ::core::mem::forget(vec6);
-                                    *ptr4
-                                        .add(3 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<usize>() = len6;
-                                    *ptr4
-                                        .add(2 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<*mut u8>() = ptr6.cast_mut();
-                                }
-                                None => {
-                                    *ptr4
-                                        .add(::core::mem::size_of::<*const u8>())
-                                        .cast::<u8>() = (0i32) as u8;
-                                }
-                            };
-                            let (t7_0,) = t5_1;
-                            match t7_0 {
-                                Some(e) => {
-                                    *ptr4
-                                        .add(4 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<u8>() = (1i32) as u8;
-                                    let vec8 = (e).into_boxed_slice();
-                                    let ptr8 = vec8.as_ptr().cast::<u8>();
-                                    let len8 = vec8.len();
-                                    ::core::mem::forget(vec8);
-                                    *ptr4
-                                        .add(6 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<usize>() = len8;
-                                    *ptr4
-                                        .add(5 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<*mut u8>() = ptr8.cast_mut();
-                                }
-                                None => {
-                                    *ptr4
-                                        .add(4 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<u8>() = (0i32) as u8;
-                                }
-                            };
-                        }
-                        Err(e) => {
-                            *ptr4.add(0).cast::<u8>() = (1i32) as u8;
-                            let vec9 = (e.into_bytes()).into_boxed_slice();
-                            let ptr9 = vec9.as_ptr().cast::<u8>();
-                            let len9 = vec9.len();
-                            ::core::mem::forget(vec9);
-                            *ptr4
-                                .add(2 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>() = len9;
-                            *ptr4
-                                .add(::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>() = ptr9.cast_mut();
-                        }
+                macro_rules! __export_theater_simple_message_server_client_cabi {
+                    ($ty:ident with_types_in $($path_to_types:tt)*) => {
+                        const _ : () = { #[unsafe (export_name =
+                        "theater:simple/message-server-client#handle-send")] unsafe
+                        extern "C" fn export_handle_send(arg0 : i32, arg1 : * mut u8,
+                        arg2 : usize, arg3 : * mut u8, arg4 : usize,) -> * mut u8 {
+                        unsafe { $($path_to_types)*:: _export_handle_send_cabi::<$ty >
+                        (arg0, arg1, arg2, arg3, arg4) } } #[unsafe (export_name =
+                        "cabi_post_theater:simple/message-server-client#handle-send")]
+                        unsafe extern "C" fn _post_return_handle_send(arg0 : * mut u8,) {
+                        unsafe { $($path_to_types)*:: __post_return_handle_send::<$ty >
+                        (arg0) } } #[unsafe (export_name =
+                        "theater:simple/message-server-client#handle-request")] unsafe
+                        extern "C" fn export_handle_request(arg0 : i32, arg1 : * mut u8,
+                        arg2 : usize, arg3 : * mut u8, arg4 : usize, arg5 : * mut u8,
+                        arg6 : usize,) -> * mut u8 { unsafe { $($path_to_types)*::
+                        _export_handle_request_cabi::<$ty > (arg0, arg1, arg2, arg3,
+                        arg4, arg5, arg6) } } #[unsafe (export_name =
+                        "cabi_post_theater:simple/message-server-client#handle-request")]
+                        unsafe extern "C" fn _post_return_handle_request(arg0 : * mut
+                        u8,) { unsafe { $($path_to_types)*::
+                        __post_return_handle_request::<$ty > (arg0) } } #[unsafe
+                        (export_name =
+                        "theater:simple/message-server-client#handle-channel-open")]
+                        unsafe extern "C" fn export_handle_channel_open(arg0 : i32, arg1
+                        : * mut u8, arg2 : usize, arg3 : * mut u8, arg4 : usize, arg5 : *
+                        mut u8, arg6 : usize,) -> * mut u8 { unsafe {
+                        $($path_to_types)*:: _export_handle_channel_open_cabi::<$ty >
+                        (arg0, arg1, arg2, arg3, arg4, arg5, arg6) } } #[unsafe
+                        (export_name =
+                        "cabi_post_theater:simple/message-server-client#handle-channel-open")]
+                        unsafe extern "C" fn _post_return_handle_channel_open(arg0 : *
+                        mut u8,) { unsafe { $($path_to_types)*::
+                        __post_return_handle_channel_open::<$ty > (arg0) } } #[unsafe
+                        (export_name =
+                        "theater:simple/message-server-client#handle-channel-message")]
+                        unsafe extern "C" fn export_handle_channel_message(arg0 : i32,
+                        arg1 : * mut u8, arg2 : usize, arg3 : * mut u8, arg4 : usize,
+                        arg5 : * mut u8, arg6 : usize,) -> * mut u8 { unsafe {
+                        $($path_to_types)*:: _export_handle_channel_message_cabi::<$ty >
+                        (arg0, arg1, arg2, arg3, arg4, arg5, arg6) } } #[unsafe
+                        (export_name =
+                        "cabi_post_theater:simple/message-server-client#handle-channel-message")]
+                        unsafe extern "C" fn _post_return_handle_channel_message(arg0 : *
+                        mut u8,) { unsafe { $($path_to_types)*::
+                        __post_return_handle_channel_message::<$ty > (arg0) } } #[unsafe
+                        (export_name =
+                        "theater:simple/message-server-client#handle-channel-close")]
+                        unsafe extern "C" fn export_handle_channel_close(arg0 : i32, arg1
+                        : * mut u8, arg2 : usize, arg3 : * mut u8, arg4 : usize,) -> *
+                        mut u8 { unsafe { $($path_to_types)*::
+                        _export_handle_channel_close_cabi::<$ty > (arg0, arg1, arg2,
+                        arg3, arg4) } } #[unsafe (export_name =
+                        "cabi_post_theater:simple/message-server-client#handle-channel-close")]
+                        unsafe extern "C" fn _post_return_handle_channel_close(arg0 : *
+                        mut u8,) { unsafe { $($path_to_types)*::
+                        __post_return_handle_channel_close::<$ty > (arg0) } } };
                     };
-                    ptr4
                 }
                 #[doc(hidden)]
-                #[allow(non_snake_case)]
-                pub unsafe fn __post_return_handle_request<T: Guest>(arg0: *mut u8) {
-                    let l0 = i32::from(*arg0.add(0).cast::<u8>());
-                    match l0 {
-                        0 => {
-                            let l1 = i32::from(
-                                *arg0.add(::core::mem::size_of::<*const u8>()).cast::<u8>(),
-                            );
-                            match l1 {
-                                0 => {}
-                                _ => {
-                                    let l2 = *arg0
-                                        .add(2 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<*mut u8>();
-                                    let l3 = *arg0
-                                        .add(3 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<usize>();
-                                    let base4 = l2;
-                                    let len4 = l3;
-                                    _rt::cabi_dealloc(base4, len4 * 1, 1);
-                                }
-                            }
-                            let l5 = i32::from(
-                                *arg0
-                                    .add(4 * ::core::mem::size_of::<*const u8>())
-                                    .cast::<u8>(),
-                            );
-                            match l5 {
-                                0 => {}
-                                _ => {
-                                    let l6 = *arg0
-                                        .add(5 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<*mut u8>();
-                                    let l7 = *arg0
-                                        .add(6 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<usize>();
-                                    let base8 = l6;
-                                    let len8 = l7;
-                                    _rt::cabi_dealloc(base8, len8 * 1, 1);
-                                }
-                            }
-                        }
-                        _ => {
-                            let l9 = *arg0
-                                .add(::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>();
-                            let l10 = *arg0
-                                .add(2 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>();
-                            _rt::cabi_dealloc(l9, l10, 1);
-                        }
-                    }
-                }
+                pub(crate) use __export_theater_simple_message_server_client_cabi;
+                #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                struct _RetArea(
+                    [::core::mem::MaybeUninit<
+                        u8,
+                    >; 8 * ::core::mem::size_of::<*const u8>()],
+                );
+                static mut _RET_AREA: _RetArea = _RetArea(
+                    [::core::mem::MaybeUninit::uninit(); 8
+                        * ::core::mem::size_of::<*const u8>()],
+                );
+            }
+            #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+            pub mod supervisor_handlers {
+                #[used]
+                #[doc(hidden)]
+                static __FORCE_SECTION_REF: fn() = super::super::super::super::__link_custom_section_describing_imports;
+                use super::super::super::super::_rt;
+                pub type WitActorError = super::super::super::super::theater::simple::types::WitActorError;
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn _export_handle_channel_open_cabi<T: Guest>(
+                pub unsafe fn _export_handle_child_error_cabi<T: Guest>(
                     arg0: i32,
                     arg1: *mut u8,
                     arg2: usize,
                     arg3: *mut u8,
                     arg4: usize,
-                    arg5: *mut u8,
-                    arg6: usize,
+                    arg5: i32,
+                    arg6: i32,
+                    arg7: *mut u8,
+                    arg8: usize,
                 ) -> *mut u8 {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
                     let len1 = arg4;
-                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
-                    let len2 = arg6;
-                    let result3 = T::handle_channel_open(
+                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
+                    let result3 = T::handle_child_error(
                         match arg0 {
                             0 => None,
                             1 => {
@@ -2746,14 +5223,29 @@ pub mod exports {
                         },
                         (
                             _rt::string_lift(bytes1),
-                            _rt::Vec::from_raw_parts(arg5.cast(), len2, len2),
+                            super::super::super::super::theater::simple::types::WitActorError {
+                                error_type: super::super::super::super::theater::simple::types::WitErrorType::_lift(
+                                    arg5 as u8,
+                                ),
+                                data: match arg6 {
+                                    0 => None,
+                                    1 => {
+                                        let e = {
+                                            let len2 = arg8;
+                                            _rt::Vec::from_raw_parts(arg7.cast(), len2, len2)
+                                        };
+                                        Some(e)
+                                    }
+                                    _ => _rt::invalid_enum_discriminant(),
+                                },
+                            },
                         ),
                     );
                     let ptr4 = (&raw mut _RET_AREA.0).cast::<u8>();
                     match result3 {
                         Ok(e) => {
                             *ptr4.add(0).cast::<u8>() = (0i32) as u8;
-                            let (t5_0, t5_1) = e;
+                            let (t5_0,) = e;
                             match t5_0 {
                                 Some(e) => {
                                     *ptr4
@@ -2776,61 +5268,26 @@ pub mod exports {
                                         .cast::<u8>() = (0i32) as u8;
                                 }
                             };
-                            let (t7_0,) = t5_1;
-                            let super::super::super::super::theater::simple::types::ChannelAccept {
-                                accepted: accepted8,
-                                message: message8,
-                            } = t7_0;
-                            *ptr4
-                                .add(4 * ::core::mem::size_of::<*const u8>())
-                                .cast::<u8>() = (match accepted8 {
-                                true => 1,
-                                false => 0,
-                            }) as u8;
-                            match message8 {
-                                Some(e) => {
-                                    *ptr4
-                                        .add(5 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<u8>() = (1i32) as u8;
-                                    let vec9 = (e).into_boxed_slice();
-                                    let ptr9 = vec9.as_ptr().cast::<u8>();
-                                    let len9 = vec9.len();
-                                    ::core::mem::forget(vec9);
-                                    *ptr4
-                                        .add(7 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<usize>() = len9;
-                                    *ptr4
-                                        .add(6 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<*mut u8>() = ptr9.cast_mut();
-                                }
-                                None => {
-                                    *ptr4
-                                        .add(5 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<u8>() = (0i32) as u8;
-                                }
-                            };
                         }
                         Err(e) => {
                             *ptr4.add(0).cast::<u8>() = (1i32) as u8;
-                            let vec10 = (e.into_bytes()).into_boxed_slice();
-                            let ptr10 = vec10.as_ptr().cast::<u8>();
-                            let len10 = vec10.len();
-                            ::core::mem::forget(vec10);
+                            let vec7 = (e.into_bytes()).into_boxed_slice();
+                            let ptr7 = vec7.as_ptr().cast::<u8>();
+                            let len7 = vec7.len();
+                            ::core::mem::forget(vec7);
                             *ptr4
                                 .add(2 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>() = len10;
+                                .cast::<usize>() = len7;
                             *ptr4
                                 .add(::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>() = ptr10.cast_mut();
+                                .cast::<*mut u8>() = ptr7.cast_mut();
                         }
                     };
                     ptr4
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn __post_return_handle_channel_open<T: Guest>(
-                    arg0: *mut u8,
-                ) {
+                pub unsafe fn __post_return_handle_child_error<T: Guest>(arg0: *mut u8) {
                     let l0 = i32::from(*arg0.add(0).cast::<u8>());
                     match l0 {
                         0 => {
@@ -2851,53 +5308,34 @@ pub mod exports {
                                     _rt::cabi_dealloc(base4, len4 * 1, 1);
                                 }
                             }
-                            let l5 = i32::from(
-                                *arg0
-                                    .add(5 * ::core::mem::size_of::<*const u8>())
-                                    .cast::<u8>(),
-                            );
-                            match l5 {
-                                0 => {}
-                                _ => {
-                                    let l6 = *arg0
-                                        .add(6 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<*mut u8>();
-                                    let l7 = *arg0
-                                        .add(7 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<usize>();
-                                    let base8 = l6;
-                                    let len8 = l7;
-                                    _rt::cabi_dealloc(base8, len8 * 1, 1);
-                                }
-                            }
                         }
                         _ => {
-                            let l9 = *arg0
+                            let l5 = *arg0
                                 .add(::core::mem::size_of::<*const u8>())
                                 .cast::<*mut u8>();
-                            let l10 = *arg0
+                            let l6 = *arg0
                                 .add(2 * ::core::mem::size_of::<*const u8>())
                                 .cast::<usize>();
-                            _rt::cabi_dealloc(l9, l10, 1);
+                            _rt::cabi_dealloc(l5, l6, 1);
                         }
                     }
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn _export_handle_channel_message_cabi<T: Guest>(
+                pub unsafe fn _export_handle_child_exit_cabi<T: Guest>(
                     arg0: i32,
                     arg1: *mut u8,
                     arg2: usize,
                     arg3: *mut u8,
                     arg4: usize,
-                    arg5: *mut u8,
-                    arg6: usize,
+                    arg5: i32,
+                    arg6: *mut u8,
+                    arg7: usize,
                 ) -> *mut u8 {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
                     let len1 = arg4;
                     let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
-                    let len2 = arg6;
-                    let result3 = T::handle_channel_message(
+                    let result3 = T::handle_child_exit(
                         match arg0 {
                             0 => None,
                             1 => {
@@ -2911,7 +5349,17 @@ pub mod exports {
                         },
                         (
                             _rt::string_lift(bytes1),
-                            _rt::Vec::from_raw_parts(arg5.cast(), len2, len2),
+                            match arg5 {
+                                0 => None,
+                                1 => {
+                                    let e = {
+                                        let len2 = arg7;
+                                        _rt::Vec::from_raw_parts(arg6.cast(), len2, len2)
+                                    };
+                                    Some(e)
+                                }
+                                _ => _rt::invalid_enum_discriminant(),
+                            },
                         ),
                     );
                     let ptr4 = (&raw mut _RET_AREA.0).cast::<u8>();
@@ -2960,9 +5408,7 @@ pub mod exports {
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn __post_return_handle_channel_message<T: Guest>(
-                    arg0: *mut u8,
-                ) {
+                pub unsafe fn __post_return_handle_child_exit<T: Guest>(arg0: *mut u8) {
                     let l0 = i32::from(*arg0.add(0).cast::<u8>());
                     match l0 {
                         0 => {
@@ -2997,7 +5443,7 @@ pub mod exports {
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn _export_handle_channel_close_cabi<T: Guest>(
+                pub unsafe fn _export_handle_child_external_stop_cabi<T: Guest>(
                     arg0: i32,
                     arg1: *mut u8,
                     arg2: usize,
@@ -3007,7 +5453,7 @@ pub mod exports {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
                     let len1 = arg4;
                     let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
-                    let result2 = T::handle_channel_close(
+                    let result2 = T::handle_child_external_stop(
                         match arg0 {
                             0 => None,
                             1 => {
@@ -3067,7 +5513,7 @@ pub mod exports {
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn __post_return_handle_channel_close<T: Guest>(
+                pub unsafe fn __post_return_handle_child_external_stop<T: Guest>(
                     arg0: *mut u8,
                 ) {
                     let l0 = i32::from(*arg0.add(0).cast::<u8>());
@@ -3088,305 +5534,455 @@ pub mod exports {
                                     let base4 = l2;
                                     let len4 = l3;
                                     _rt::cabi_dealloc(base4, len4 * 1, 1);
-                                }
-                            }
-                        }
-                        _ => {
-                            let l5 = *arg0
-                                .add(::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>();
-                            let l6 = *arg0
-                                .add(2 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>();
-                            _rt::cabi_dealloc(l5, l6, 1);
-                        }
-                    }
-                }
-                pub trait Guest {
-                    /// # Handle one-way message
-                    ///
-                    /// Processes a one-way message that doesn't require a response.
-                    ///
-                    /// ## Parameters
-                    ///
-                    /// * `state` - The current actor state or None if not initialized
-                    /// * `params` - Tuple containing:
-                    /// * `json` - The message payload
-                    ///
-                    /// ## Returns
-                    ///
-                    /// * `Ok((option<list<u8>>,))` - Updated actor state (or None to retain current state)
-                    /// * `Err(string)` - Error message if message handling fails
-                    ///
-                    /// ## Example
-                    ///
-                    /// ```rust
-                    /// fn handle_send(state: Option<Value>, params: (Value,)) -> Result<(Option<Value>,), String> {
-                    /// let (message,) = params;
-                    ///
-                    /// // Process the message...
-                    ///
-                    /// // Return updated state (or None to keep current state)
-                    /// Ok((Some(updated_state),))
-                    /// }
-                    /// ```
-                    fn handle_send(
-                        state: Option<_rt::Vec<u8>>,
-                        params: (_rt::Vec<u8>,),
-                    ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
-                    /// # Handle request-response message
-                    ///
-                    /// Processes a request that requires a response.
-                    ///
-                    /// ## Parameters
-                    ///
-                    /// * `state` - The current actor state or None if not initialized
-                    /// * `params` - Tuple containing:
-                    /// * `string` - The request ID
-                    /// * `json` - The request payload
-                    ///
-                    /// ## Returns
-                    ///
-                    /// * `Ok((option<list<u8>>, (option<list<u8>>,)))` - Tuple containing:
-                    /// * Updated actor state (or None)
-                    /// * Response message to send back (or None to send a response yet)
-                    /// * `Err(string)` - Error message if request handling fails
-                    ///
-                    /// ## Example
-                    ///
-                    /// ```rust
-                    /// fn handle_request(state: Option<Vec<u8>>, params: (String, Vec<u8>))
-                    /// -> Result<(Option<Vec<u8>>, (Option<Vec<u8>>,)), String> {
-                    /// let (request_id, request) = params;
-                    ///
-                    /// // Process the request...
-                    /// let response = json!({"status": "success", "data": "result"});
-                    ///
-                    /// // Return updated state and response
-                    /// Ok((Some(updated_state), (Some(response),)))
-                    /// }
-                    /// ```
-                    fn handle_request(
-                        state: Option<_rt::Vec<u8>>,
-                        params: (_rt::String, _rt::Vec<u8>),
-                    ) -> Result<
-                        (Option<_rt::Vec<u8>>, (Option<_rt::Vec<u8>>,)),
-                        _rt::String,
-                    >;
-                    /// # Handle channel open request
-                    ///
-                    /// Called when another actor requests to open a communication channel.
-                    ///
-                    /// ## Parameters
-                    ///
-                    /// * `state` - The current actor state or None if not initialized
-                    /// * `params` - Tuple containing:
-                    /// * `json` - The initial message payload
-                    ///
-                    /// ## Returns
-                    ///
-                    /// * `Ok((option<list<u8>>, (channel-accept,)))` - Tuple containing:
-                    /// * Updated actor state (or None to retain current state)
-                    /// * Channel acceptance decision
-                    /// * `Err(string)` - Error message if open handling fails
-                    ///
-                    /// ## Example
-                    ///
-                    /// ```rust
-                    /// fn handle_channel_open(state: Option<Value>, params: (Value,))
-                    /// -> Result<(Option<Value>, (channel_accept,)), String> {
-                    /// let (initial_message,) = params;
-                    ///
-                    /// // Decide whether to accept the channel
-                    /// let accept = channel_accept {
-                    /// accept: true,
-                    /// error_message: None,
-                    /// };
-                    ///
-                    /// // Return updated state and acceptance decision
-                    /// Ok((Some(updated_state), (accept,)))
-                    /// }
-                    /// ```
-                    ///
-                    /// ## Security
-                    ///
-                    /// The actor should validate the channel request and only accept channels from
-                    /// trusted sources. The acceptance mechanism provides a security checkpoint.
-                    fn handle_channel_open(
-                        state: Option<_rt::Vec<u8>>,
-                        params: (_rt::String, _rt::Vec<u8>),
-                    ) -> Result<(Option<_rt::Vec<u8>>, (ChannelAccept,)), _rt::String>;
-                    /// # Handle channel message
+                                }
+                            }
+                        }
+                        _ => {
+                            let l5 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l6 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l5, l6, 1);
+                        }
+                    }
+                }
+                pub trait Guest {
+                    /// # Handle a child actor error
                     ///
-                    /// Processes a message received on an established channel.
+                    /// Processes an error from a child actor, allowing the parent to react or log the error.
                     ///
                     /// ## Parameters
                     ///
-                    /// * `state` - The current actor state or None if not initialized
-                    /// * `params` - Tuple containing:
-                    /// * `channel-id` - ID of the channel the message was received on
-                    /// * `json` - The message payload
+                    /// * `state` - Optional state of the parent actor (for context)
+                    /// * `params` - Tuple containing the child ID and error data
                     ///
                     /// ## Returns
                     ///
-                    /// * `Ok((option<list<u8>>,))` - Updated actor state (or None to retain current state)
-                    /// * `Err(string)` - Error message if message handling fails
-                    ///
-                    /// ## Example
-                    ///
-                    /// ```rust
-                    /// fn handle_channel_message(state: Option<Value>, params: (channel_id, Value))
-                    /// -> Result<(Option<Value>,), String> {
-                    /// let (channel_id, message) = params;
-                    ///
-                    /// // Process the channel message...
-                    /// println!("Received message on channel {}: {}", channel_id, message);
-                    ///
-                    /// // Return updated state (or None to keep current state)
-                    /// Ok((Some(updated_state),))
-                    /// }
-                    /// ```
-                    fn handle_channel_message(
+                    /// * `Ok(tuple<option<list<u8>>, string>)` - Updated state and result message
+                    /// * `Err(string)` - Error message if handling fails
+                    fn handle_child_error(
                         state: Option<_rt::Vec<u8>>,
-                        params: (ChannelId, _rt::Vec<u8>),
+                        params: (_rt::String, WitActorError),
                     ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
-                    /// # Handle channel close
-                    ///
-                    /// Called when a communication channel is closed.
-                    ///
-                    /// ## Parameters
-                    ///
-                    /// * `state` - The current actor state or None if not initialized
-                    /// * `params` - Tuple containing:
-                    /// * `channel-id` - ID of the channel that was closed
-                    ///
-                    /// ## Returns
-                    ///
-                    /// * `Ok((option<list<u8>>,))` - Updated actor state (or None to retain current state)
-                    /// * `Err(string)` - Error message if close handling fails
-                    ///
-                    /// ## Example
-                    ///
-                    /// ```rust
-                    /// fn handle_channel_close(state: Option<Value>, params: (channel_id,))
-                    /// -> Result<(Option<Value>,), String> {
-                    /// let (channel_id,) = params;
-                    ///
-                    /// // Clean up any resources associated with the channel
-                    /// println!("Channel {} closed", channel_id);
-                    ///
-                    /// // Return updated state (or None to keep current state)
-                    /// Ok((Some(updated_state),))
-                    /// }
-                    /// ```
-                    ///
-                    /// ## Implementation Notes
-                    ///
-                    /// This function should perform any necessary cleanup for the closed channel,
-                    /// such as releasing resources or updating internal state to reflect the channel closure.
-                    fn handle_channel_close(
+                    fn handle_child_exit(
                         state: Option<_rt::Vec<u8>>,
-                        params: (ChannelId,),
+                        params: (_rt::String, Option<_rt::Vec<u8>>),
+                    ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
+                    fn handle_child_external_stop(
+                        state: Option<_rt::Vec<u8>>,
+                        params: (_rt::String,),
                     ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
                 }
                 #[doc(hidden)]
-                macro_rules! __export_theater_simple_message_server_client_cabi {
+                macro_rules! __export_theater_simple_supervisor_handlers_cabi {
                     ($ty:ident with_types_in $($path_to_types:tt)*) => {
                         const _ : () = { #[unsafe (export_name =
-                        "theater:simple/message-server-client#handle-send")] unsafe
-                        extern "C" fn export_handle_send(arg0 : i32, arg1 : * mut u8,
-                        arg2 : usize, arg3 : * mut u8, arg4 : usize,) -> * mut u8 {
-                        unsafe { $($path_to_types)*:: _export_handle_send_cabi::<$ty >
-                        (arg0, arg1, arg2, arg3, arg4) } } #[unsafe (export_name =
-                        "cabi_post_theater:simple/message-server-client#handle-send")]
-                        unsafe extern "C" fn _post_return_handle_send(arg0 : * mut u8,) {
-                        unsafe { $($path_to_types)*:: __post_return_handle_send::<$ty >
-                        (arg0) } } #[unsafe (export_name =
-                        "theater:simple/message-server-client#handle-request")] unsafe
-                        extern "C" fn export_handle_request(arg0 : i32, arg1 : * mut u8,
-                        arg2 : usize, arg3 : * mut u8, arg4 : usize, arg5 : * mut u8,
-                        arg6 : usize,) -> * mut u8 { unsafe { $($path_to_types)*::
-                        _export_handle_request_cabi::<$ty > (arg0, arg1, arg2, arg3,
-                        arg4, arg5, arg6) } } #[unsafe (export_name =
-                        "cabi_post_theater:simple/message-server-client#handle-request")]
-                        unsafe extern "C" fn _post_return_handle_request(arg0 : * mut
+                        "theater:simple/supervisor-handlers#handle-child-error")] unsafe
+                        extern "C" fn export_handle_child_error(arg0 : i32, arg1 : * mut
+                        u8, arg2 : usize, arg3 : * mut u8, arg4 : usize, arg5 : i32, arg6
+                        : i32, arg7 : * mut u8, arg8 : usize,) -> * mut u8 { unsafe {
+                        $($path_to_types)*:: _export_handle_child_error_cabi::<$ty >
+                        (arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8) } }
+                        #[unsafe (export_name =
+                        "cabi_post_theater:simple/supervisor-handlers#handle-child-error")]
+                        unsafe extern "C" fn _post_return_handle_child_error(arg0 : * mut
                         u8,) { unsafe { $($path_to_types)*::
-                        __post_return_handle_request::<$ty > (arg0) } } #[unsafe
-                        (export_name =
-                        "theater:simple/message-server-client#handle-channel-open")]
-                        unsafe extern "C" fn export_handle_channel_open(arg0 : i32, arg1
-                        : * mut u8, arg2 : usize, arg3 : * mut u8, arg4 : usize, arg5 : *
-                        mut u8, arg6 : usize,) -> * mut u8 { unsafe {
-                        $($path_to_types)*:: _export_handle_channel_open_cabi::<$ty >
-                        (arg0, arg1, arg2, arg3, arg4, arg5, arg6) } } #[unsafe
-                        (export_name =
-                        "cabi_post_theater:simple/message-server-client#handle-channel-open")]
-                        unsafe extern "C" fn _post_return_handle_channel_open(arg0 : *
-                        mut u8,) { unsafe { $($path_to_types)*::
-                        __post_return_handle_channel_open::<$ty > (arg0) } } #[unsafe
+                        __post_return_handle_child_error::<$ty > (arg0) } } #[unsafe
                         (export_name =
-                        "theater:simple/message-server-client#handle-channel-message")]
-                        unsafe extern "C" fn export_handle_channel_message(arg0 : i32,
-                        arg1 : * mut u8, arg2 : usize, arg3 : * mut u8, arg4 : usize,
-                        arg5 : * mut u8, arg6 : usize,) -> * mut u8 { unsafe {
-                        $($path_to_types)*:: _export_handle_channel_message_cabi::<$ty >
-                        (arg0, arg1, arg2, arg3, arg4, arg5, arg6) } } #[unsafe
+                        "theater:simple/supervisor-handlers#handle-child-exit")] unsafe
+                        extern "C" fn export_handle_child_exit(arg0 : i32, arg1 : * mut
+                        u8, arg2 : usize, arg3 : * mut u8, arg4 : usize, arg5 : i32, arg6
+                        : * mut u8, arg7 : usize,) -> * mut u8 { unsafe {
+                        $($path_to_types)*:: _export_handle_child_exit_cabi::<$ty >
+                        (arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7) } } #[unsafe
                         (export_name =
-                        "cabi_post_theater:simple/message-server-client#handle-channel-message")]
-                        unsafe extern "C" fn _post_return_handle_channel_message(arg0 : *
-                        mut u8,) { unsafe { $($path_to_types)*::
-                        __post_return_handle_channel_message::<$ty > (arg0) } } #[unsafe
+                        "cabi_post_theater:simple/supervisor-handlers#handle-child-exit")]
+                        unsafe extern "C" fn _post_return_handle_child_exit(arg0 : * mut
+                        u8,) { unsafe { $($path_to_types)*::
+                        __post_return_handle_child_exit::<$ty > (arg0) } } #[unsafe
                         (export_name =
-                        "theater:simple/message-server-client#handle-channel-close")]
-                        unsafe extern "C" fn export_handle_channel_close(arg0 : i32, arg1
-                        : * mut u8, arg2 : usize, arg3 : * mut u8, arg4 : usize,) -> *
-                        mut u8 { unsafe { $($path_to_types)*::
-                        _export_handle_channel_close_cabi::<$ty > (arg0, arg1, arg2,
-                        arg3, arg4) } } #[unsafe (export_name =
-                        "cabi_post_theater:simple/message-server-client#handle-channel-close")]
-                        unsafe extern "C" fn _post_return_handle_channel_close(arg0 : *
-                        mut u8,) { unsafe { $($path_to_types)*::
-                        __post_return_handle_channel_close::<$ty > (arg0) } } };
+                        "theater:simple/supervisor-handlers#handle-child-external-stop")]
+                        unsafe extern "C" fn export_handle_child_external_stop(arg0 :
+                        i32, arg1 : * mut u8, arg2 : usize, arg3 : * mut u8, arg4 :
+                        usize,) -> * mut u8 { unsafe { $($path_to_types)*::
+                        _export_handle_child_external_stop_cabi::<$ty > (arg0, arg1,
+                        arg2, arg3, arg4) } } #[unsafe (export_name =
+                        "cabi_post_theater:simple/supervisor-handlers#handle-child-external-stop")]
+                        unsafe extern "C" fn _post_return_handle_child_external_stop(arg0
+                        : * mut u8,) { unsafe { $($path_to_types)*::
+                        __post_return_handle_child_external_stop::<$ty > (arg0) } } };
+                    };
+                }
+                #[doc(hidden)]
+                pub(crate) use __export_theater_simple_supervisor_handlers_cabi;
+                #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                struct _RetArea(
+                    [::core::mem::MaybeUninit<
+                        u8,
+                    >; 4 * ::core::mem::size_of::<*const u8>()],
+                );
+                static mut _RET_AREA: _RetArea = _RetArea(
+                    [::core::mem::MaybeUninit::uninit(); 4
+                        * ::core::mem::size_of::<*const u8>()],
+                );
+            }
+            #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+            pub mod http_handlers {
+                #[used]
+                #[doc(hidden)]
+                static __FORCE_SECTION_REF: fn() = super::super::super::super::__link_custom_section_describing_imports;
+                use super::super::super::super::_rt;
+                pub type HttpRequest = super::super::super::super::theater::simple::http_types::HttpRequest;
+                pub type HttpResponse = super::super::super::super::theater::simple::http_types::HttpResponse;
+                pub type WebsocketMessage = super::super::super::super::theater::simple::websocket_types::WebsocketMessage;
+                pub type MiddlewareResult = super::super::super::super::theater::simple::http_types::MiddlewareResult;
+                pub type HandlerId = super::super::super::super::theater::simple::http_framework::HandlerId;
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_handle_request_cabi<T: Guest>(
+                    arg0: i32,
+                    arg1: *mut u8,
+                    arg2: usize,
+                    arg3: i64,
+                    arg4: *mut u8,
+                    arg5: usize,
+                    arg6: *mut u8,
+                    arg7: usize,
+                    arg8: *mut u8,
+                    arg9: usize,
+                    arg10: i32,
+                    arg11: *mut u8,
+                    arg12: usize,
+                ) -> *mut u8 {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let len1 = arg5;
+                    let bytes1 = _rt::Vec::from_raw_parts(arg4.cast(), len1, len1);
+                    let len2 = arg7;
+                    let bytes2 = _rt::Vec::from_raw_parts(arg6.cast(), len2, len2);
+                    let base9 = arg8;
+                    let len9 = arg9;
+                    let mut result9 = _rt::Vec::with_capacity(len9);
+                    for i in 0..len9 {
+                        let base = base9
+                            .add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                        let e9 = {
+                            let l3 = *base.add(0).cast::<*mut u8>();
+                            let l4 = *base
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            let len5 = l4;
+                            let bytes5 = _rt::Vec::from_raw_parts(l3.cast(), len5, len5);
+                            let l6 = *base
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l7 = *base
+                                .add(3 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            let len8 = l7;
+                            let bytes8 = _rt::Vec::from_raw_parts(l6.cast(), len8, len8);
+                            (_rt::string_lift(bytes5), _rt::string_lift(bytes8))
+                        };
+                        result9.push(e9);
+                    }
+                    _rt::cabi_dealloc(
+                        base9,
+                        len9 * (4 * ::core::mem::size_of::<*const u8>()),
+                        ::core::mem::size_of::<*const u8>(),
+                    );
+                    let result11 = T::handle_request(
+                        match arg0 {
+                            0 => None,
+                            1 => {
+                                let e = {
+                                    let len0 = arg2;
+                                    _rt::Vec::from_raw_parts(arg1.cast(), len0, len0)
+                                };
+                                Some(e)
+                            }
+                            _ => _rt::invalid_enum_discriminant(),
+                        },
+                        (
+                            arg3 as u64,
+                            super::super::super::super::theater::simple::http_types::HttpRequest {
+                                method: _rt::string_lift(bytes1),
+                                uri: _rt::string_lift(bytes2),
+                                headers: result9,
+                                body: match arg10 {
+                                    0 => None,
+                                    1 => {
+                                        let e = {
+                                            let len10 = arg12;
+                                            _rt::Vec::from_raw_parts(arg11.cast(), len10, len10)
+                                        };
+                                        Some(e)
+                                    }
+                                    _ => _rt::invalid_enum_discriminant(),
+                                },
+                            },
+                        ),
+                    );
+                    let ptr12 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result11 {
+                        Ok(e) => {
+                            *ptr12.add(0).cast::<u8>() = (0i32) as u8;
+                            let (t13_0, t13_1) = e;
+                            match t13_0 {
+                                Some(e) => {
+                                    *ptr12
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec14 = (e).into_boxed_slice();
+                                    let ptr14 = vec14.as_ptr().cast::<u8>();
+                                    let len14 = vec14.len();
+                                    ::core::mem::forget(vec14);
+                                    *ptr12
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len14;
+                                    *ptr12
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr14.cast_mut();
+                                }
+                                None => {
+                                    *ptr12
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                            let (t15_0,) = t13_1;
+                            let super::super::super::super::theater::simple::http_types::HttpResponse {
+                                status: status16,
+                                headers: headers16,
+                                body: body16,
+                            } = t15_0;
+                            *ptr12
+                                .add(4 * ::core::mem::size_of::<*const u8>())
+                                .cast::<u16>() = (_rt::as_i32(status16)) as u16;
+                            let vec20 = headers16;
+                            let len20 = vec20.len();
+                            let layout20 = _rt::alloc::Layout::from_size_align_unchecked(
+                                vec20.len() * (4 * ::core::mem::size_of::<*const u8>()),
+                                ::core::mem::size_of::<*const u8>(),
+                            );
+                            let result20 = if layout20.size() != 0 {
+                                let ptr = _rt::alloc::alloc(layout20).cast::<u8>();
+                                if ptr.is_null() {
+                                    _rt::alloc::handle_alloc_error(layout20);
+                                }
+                                ptr
+                            } else {
+                                ::core::ptr::null_mut()
+                            };
+                            for (i, e) in vec20.into_iter().enumerate() {
+                                let base = result20
+                                    .add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                                {
+                                    let (t17_0, t17_1) = e;
+                                    let vec18 = (t17_0.into_bytes()).into_boxed_slice();
+                                    let ptr18 = vec18.as_ptr().cast::<u8>();
+                                    let len18 = vec18.len();
+                                    ::core::mem::forget(vec18);
+                                    *base
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len18;
+                                    *base.add(0).cast::<*mut u8>() = ptr18.cast_mut();
+                                    let vec19 = (t17_1.into_bytes()).into_boxed_slice();
+                                    let ptr19 = vec19.as_ptr().cast::<u8>();
+                                    let len19 = vec19.len();
+                                    ::core::mem::forget(vec19);
+                                    *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len19;
+                                    *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr19.cast_mut();
+                                }
+                            }
+                            *ptr12
+                                .add(6 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len20;
+                            *ptr12
+                                .add(5 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = result20;
+                            match body16 {
+                                Some(e) => {
+                                    *ptr12
+                                        .add(7 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec21 = (e).into_boxed_slice();
+                                    let ptr21 = vec21.as_ptr().cast::<u8>();
+                                    let len21 = vec21.len();
+                                    ::core::mem::forget(vec21);
+                                    *ptr12
+                                        .add(9 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len21;
+                                    *ptr12
+                                        .add(8 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr21.cast_mut();
+                                }
+                                None => {
+                                    *ptr12
+                                        .add(7 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                        }
+                        Err(e) => {
+                            *ptr12.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec22 = (e.into_bytes()).into_boxed_slice();
+                            let ptr22 = vec22.as_ptr().cast::<u8>();
+                            let len22 = vec22.len();
+                            ::core::mem::forget(vec22);
+                            *ptr12
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len22;
+                            *ptr12
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr22.cast_mut();
+                        }
                     };
+                    ptr12
                 }
                 #[doc(hidden)]
-                pub(crate) use __export_theater_simple_message_server_client_cabi;
-                #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
-                #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
-                struct _RetArea(
-                    [::core::mem::MaybeUninit<
-                        u8,
-                    >; 8 * ::core::mem::size_of::<*const u8>()],
-                );
-                static mut _RET_AREA: _RetArea = _RetArea(
-                    [::core::mem::MaybeUninit::uninit(); 8
-                        * ::core::mem::size_of::<*const u8>()],
-                );
-            }
-            #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
-            pub mod supervisor_handlers {
-                #[used]
-                #[doc(hidden)]
-                static __FORCE_SECTION_REF: fn() = super::super::super::super::__link_custom_section_describing_imports;
-                use super::super::super::super::_rt;
-                pub type WitActorError = super::super::super::super::theater::simple::types::WitActorError;
+                #[allow(non_snake_case)]
+                pub unsafe fn __post_return_handle_request<T: Guest>(arg0: *mut u8) {
+                    let l0 = i32::from(*arg0.add(0).cast::<u8>());
+                    match l0 {
+                        0 => {
+                            let l1 = i32::from(
+                                *arg0.add(::core::mem::size_of::<*const u8>()).cast::<u8>(),
+                            );
+                            match l1 {
+                                0 => {}
+                                _ => {
+                                    let l2 = *arg0
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l3 = *arg0
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base4 = l2;
+                                    let len4 = l3;
+                                    _rt::cabi_dealloc(base4, len4 * 1, 1);
+                                }
+                            }
+                            let l5 = *arg0
+                                .add(5 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l6 = *arg0
+                                .add(6 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            let base11 = l5;
+                            let len11 = l6;
+                            for i in 0..len11 {
+                                let base = base11
+                                    .add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                                {
+                                    let l7 = *base.add(0).cast::<*mut u8>();
+                                    let l8 = *base
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l7, l8, 1);
+                                    let l9 = *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l10 = *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l9, l10, 1);
+                                }
+                            }
+                            _rt::cabi_dealloc(
+                                base11,
+                                len11 * (4 * ::core::mem::size_of::<*const u8>()),
+                                ::core::mem::size_of::<*const u8>(),
+                            );
+                            let l12 = i32::from(
+                                *arg0
+                                    .add(7 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<u8>(),
+                            );
+                            match l12 {
+                                0 => {}
+                                _ => {
+                                    let l13 = *arg0
+                                        .add(8 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l14 = *arg0
+                                        .add(9 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base15 = l13;
+                                    let len15 = l14;
+                                    _rt::cabi_dealloc(base15, len15 * 1, 1);
+                                }
+                            }
+                        }
+                        _ => {
+                            let l16 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l17 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l16, l17, 1);
+                        }
+                    }
+                }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn _export_handle_child_error_cabi<T: Guest>(
+                pub unsafe fn _export_handle_middleware_cabi<T: Guest>(
                     arg0: i32,
                     arg1: *mut u8,
                     arg2: usize,
-                    arg3: *mut u8,
-                    arg4: usize,
-                    arg5: i32,
-                    arg6: i32,
-                    arg7: *mut u8,
-                    arg8: usize,
+                    arg3: i64,
+                    arg4: *mut u8,
+                    arg5: usize,
+                    arg6: *mut u8,
+                    arg7: usize,
+                    arg8: *mut u8,
+                    arg9: usize,
+                    arg10: i32,
+                    arg11: *mut u8,
+                    arg12: usize,
                 ) -> *mut u8 {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
-                    let len1 = arg4;
-                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
-                    let result3 = T::handle_child_error(
+                    let len1 = arg5;
+                    let bytes1 = _rt::Vec::from_raw_parts(arg4.cast(), len1, len1);
+                    let len2 = arg7;
+                    let bytes2 = _rt::Vec::from_raw_parts(arg6.cast(), len2, len2);
+                    let base9 = arg8;
+                    let len9 = arg9;
+                    let mut result9 = _rt::Vec::with_capacity(len9);
+                    for i in 0..len9 {
+                        let base = base9
+                            .add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                        let e9 = {
+                            let l3 = *base.add(0).cast::<*mut u8>();
+                            let l4 = *base
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            let len5 = l4;
+                            let bytes5 = _rt::Vec::from_raw_parts(l3.cast(), len5, len5);
+                            let l6 = *base
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l7 = *base
+                                .add(3 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            let len8 = l7;
+                            let bytes8 = _rt::Vec::from_raw_parts(l6.cast(), len8, len8);
+                            (_rt::string_lift(bytes5), _rt::string_lift(bytes8))
+                        };
+                        result9.push(e9);
+                    }
+                    _rt::cabi_dealloc(
+                        base9,
+                        len9 * (4 * ::core::mem::size_of::<*const u8>()),
+                        ::core::mem::size_of::<*const u8>(),
+                    );
+                    let result11 = T::handle_middleware(
                         match arg0 {
                             0 => None,
                             1 => {
@@ -3399,17 +5995,17 @@ pub mod exports {
                             _ => _rt::invalid_enum_discriminant(),
                         },
                         (
-                            _rt::string_lift(bytes1),
-                            super::super::super::super::theater::simple::types::WitActorError {
-                                error_type: super::super::super::super::theater::simple::types::WitErrorType::_lift(
-                                    arg5 as u8,
-                                ),
-                                data: match arg6 {
+                            arg3 as u64,
+                            super::super::super::super::theater::simple::http_types::HttpRequest {
+                                method: _rt::string_lift(bytes1),
+                                uri: _rt::string_lift(bytes2),
+                                headers: result9,
+                                body: match arg10 {
                                     0 => None,
                                     1 => {
                                         let e = {
-                                            let len2 = arg8;
-                                            _rt::Vec::from_raw_parts(arg7.cast(), len2, len2)
+                                            let len10 = arg12;
+                                            _rt::Vec::from_raw_parts(arg11.cast(), len10, len10)
                                         };
                                         Some(e)
                                     }
@@ -3418,6 +6014,305 @@ pub mod exports {
                             },
                         ),
                     );
+                    let ptr12 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result11 {
+                        Ok(e) => {
+                            *ptr12.add(0).cast::<u8>() = (0i32) as u8;
+                            let (t13_0, t13_1) = e;
+                            match t13_0 {
+                                Some(e) => {
+                                    *ptr12
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec14 = (e).into_boxed_slice();
+                                    let ptr14 = vec14.as_ptr().cast::<u8>();
+                                    let len14 = vec14.len();
+                                    ::core::mem::forget(vec14);
+                                    *ptr12
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len14;
+                                    *ptr12
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr14.cast_mut();
+                                }
+                                None => {
+                                    *ptr12
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                            let (t15_0,) = t13_1;
+                            let super::super::super::super::theater::simple::http_types::MiddlewareResult {
+                                proceed: proceed16,
+                                request: request16,
+                            } = t15_0;
+                            *ptr12
+                                .add(4 * ::core::mem::size_of::<*const u8>())
+                                .cast::<u8>() = (match proceed16 {
+                                true => 1,
+                                false => 0,
+                            }) as u8;
+                            let super::super::super::super::theater::simple::http_types::HttpRequest {
+                                method: method17,
+                                uri: uri17,
+                                headers: headers17,
+                                body: body17,
+                            } = request16;
+                            let vec18 = (method17.into_bytes()).into_boxed_slice();
+                            let ptr18 = vec18.as_ptr().cast::<u8>();
+                            let len18 = vec18.len();
+                            ::core::mem::forget(vec18);
+                            *ptr12
+                                .add(6 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len18;
+                            *ptr12
+                                .add(5 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr18.cast_mut();
+                            let vec19 = (uri17.into_bytes()).into_boxed_slice();
+                            let ptr19 = vec19.as_ptr().cast::<u8>();
+                            let len19 = vec19.len();
+                            ::core::mem::forget(vec19);
+                            *ptr12
+                                .add(8 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len19;
+                            *ptr12
+                                .add(7 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr19.cast_mut();
+                            let vec23 = headers17;
+                            let len23 = vec23.len();
+                            let layout23 = _rt::alloc::Layout::from_size_align_unchecked(
+                                vec23.len() * (4 * ::core::mem::size_of::<*const u8>()),
+                                ::core::mem::size_of::<*const u8>(),
+                            );
+                            let result23 = if layout23.size() != 0 {
+                                let ptr = _rt::alloc::alloc(layout23).cast::<u8>();
+                                if ptr.is_null() {
+                                    _rt::alloc::handle_alloc_error(layout23);
+                                }
+                                ptr
+                            } else {
+                                ::core::ptr::null_mut()
+                            };
+                            for (i, e) in vec23.into_iter().enumerate() {
+                                let base = result23
+                                    .add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                                {
+                                    let (t20_0, t20_1) = e;
+                                    let vec21 = (t20_0.into_bytes()).into_boxed_slice();
+                                    let ptr21 = vec21.as_ptr().cast::<u8>();
+                                    let len21 = vec21.len();
+                                    ::core::mem::forget(vec21);
+                                    *base
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len21;
+                                    *base.add(0).cast::<*mut u8>() = ptr21.cast_mut();
+                                    let vec22 = (t20_1.into_bytes()).into_boxed_slice();
+                                    let ptr22 = vec22.as_ptr().cast::<u8>();
+                                    let len22 = vec22.len();
+                                    ::core::mem::forget(vec22);
+                                    *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len22;
+                                    *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr22.cast_mut();
+                                }
+                            }
+                            *ptr12
+                                .add(10 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len23;
+                            *ptr12
+                                .add(9 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = result23;
+                            match body17 {
+                                Some(e) => {
+                                    *ptr12
+                                        .add(11 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec24 = (e).into_boxed_slice();
+                                    let ptr24 = vec24.as_ptr().cast::<u8>();
+                                    let len24 = vec24.len();
+                                    ::core::mem::forget(vec24);
+                                    *ptr12
+                                        .add(13 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len24;
+                                    *ptr12
+                                        .add(12 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr24.cast_mut();
+                                }
+                                None => {
+                                    *ptr12
+                                        .add(11 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                        }
+                        Err(e) => {
+                            *ptr12.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec25 = (e.into_bytes()).into_boxed_slice();
+                            let ptr25 = vec25.as_ptr().cast::<u8>();
+                            let len25 = vec25.len();
+                            ::core::mem::forget(vec25);
+                            *ptr12
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len25;
+                            *ptr12
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr25.cast_mut();
+                        }
+                    };
+                    ptr12
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn __post_return_handle_middleware<T: Guest>(arg0: *mut u8) {
+                    let l0 = i32::from(*arg0.add(0).cast::<u8>());
+                    match l0 {
+                        0 => {
+                            let l1 = i32::from(
+                                *arg0.add(::core::mem::size_of::<*const u8>()).cast::<u8>(),
+                            );
+                            match l1 {
+                                0 => {}
+                                _ => {
+                                    let l2 = *arg0
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l3 = *arg0
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base4 = l2;
+                                    let len4 = l3;
+                                    _rt::cabi_dealloc(base4, len4 * 1, 1);
+                                }
+                            }
+                            let l5 = *arg0
+                                .add(5 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l6 = *arg0
+                                .add(6 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l5, l6, 1);
+                            let l7 = *arg0
+                                .add(7 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l8 = *arg0
+                                .add(8 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l7, l8, 1);
+                            let l9 = *arg0
+                                .add(9 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l10 = *arg0
+                                .add(10 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            let base15 = l9;
+                            let len15 = l10;
+                            for i in 0..len15 {
+                                let base = base15
+                                    .add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                                {
+                                    let l11 = *base.add(0).cast::<*mut u8>();
+                                    let l12 = *base
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l11, l12, 1);
+                                    let l13 = *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l14 = *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l13, l14, 1);
+                                }
+                            }
+                            _rt::cabi_dealloc(
+                                base15,
+                                len15 * (4 * ::core::mem::size_of::<*const u8>()),
+                                ::core::mem::size_of::<*const u8>(),
+                            );
+                            let l16 = i32::from(
+                                *arg0
+                                    .add(11 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<u8>(),
+                            );
+                            match l16 {
+                                0 => {}
+                                _ => {
+                                    let l17 = *arg0
+                                        .add(12 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l18 = *arg0
+                                        .add(13 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base19 = l17;
+                                    let len19 = l18;
+                                    _rt::cabi_dealloc(base19, len19 * 1, 1);
+                                }
+                            }
+                        }
+                        _ => {
+                            let l20 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l21 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l20, l21, 1);
+                        }
+                    }
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_handle_websocket_connect_cabi<T: Guest>(
+                    arg0: i32,
+                    arg1: *mut u8,
+                    arg2: usize,
+                    arg3: i64,
+                    arg4: i64,
+                    arg5: *mut u8,
+                    arg6: usize,
+                    arg7: i32,
+                    arg8: *mut u8,
+                    arg9: usize,
+                ) -> *mut u8 {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let len1 = arg6;
+                    let bytes1 = _rt::Vec::from_raw_parts(arg5.cast(), len1, len1);
+                    let result3 = T::handle_websocket_connect(
+                        match arg0 {
+                            0 => None,
+                            1 => {
+                                let e = {
+                                    let len0 = arg2;
+                                    _rt::Vec::from_raw_parts(arg1.cast(), len0, len0)
+                                };
+                                Some(e)
+                            }
+                            _ => _rt::invalid_enum_discriminant(),
+                        },
+                        (
+                            arg3 as u64,
+                            arg4 as u64,
+                            _rt::string_lift(bytes1),
+                            match arg7 {
+                                0 => None,
+                                1 => {
+                                    let e = {
+                                        let len2 = arg9;
+                                        let bytes2 = _rt::Vec::from_raw_parts(
+                                            arg8.cast(),
+                                            len2,
+                                            len2,
+                                        );
+                                        _rt::string_lift(bytes2)
+                                    };
+                                    Some(e)
+                                }
+                                _ => _rt::invalid_enum_discriminant(),
+                            },
+                        ),
+                    );
                     let ptr4 = (&raw mut _RET_AREA.0).cast::<u8>();
                     match result3 {
                         Ok(e) => {
@@ -3464,7 +6359,9 @@ pub mod exports {
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn __post_return_handle_child_error<T: Guest>(arg0: *mut u8) {
+                pub unsafe fn __post_return_handle_websocket_connect<T: Guest>(
+                    arg0: *mut u8,
+                ) {
                     let l0 = i32::from(*arg0.add(0).cast::<u8>());
                     match l0 {
                         0 => {
@@ -3499,20 +6396,46 @@ pub mod exports {
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn _export_handle_child_exit_cabi<T: Guest>(
+                pub unsafe fn _export_handle_websocket_message_cabi<T: Guest>(
                     arg0: i32,
                     arg1: *mut u8,
                     arg2: usize,
-                    arg3: *mut u8,
-                    arg4: usize,
+                    arg3: i64,
+                    arg4: i64,
                     arg5: i32,
                     arg6: *mut u8,
                     arg7: usize,
+                    arg8: i32,
+                    arg9: *mut u8,
+                    arg10: usize,
+                    arg11: i32,
+                    arg12: *mut u8,
+                    arg13: usize,
                 ) -> *mut u8 {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
-                    let len1 = arg4;
-                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
-                    let result3 = T::handle_child_exit(
+                    use super::super::super::super::theater::simple::websocket_types::MessageType as V2;
+                    let v2 = match arg5 {
+                        0 => V2::Text,
+                        1 => V2::Binary,
+                        2 => V2::Connect,
+                        3 => V2::Close,
+                        4 => V2::Ping,
+                        5 => V2::Pong,
+                        n => {
+                            debug_assert_eq!(n, 6, "invalid enum discriminant");
+                            let e2 = {
+                                let len1 = arg7;
+                                let bytes1 = _rt::Vec::from_raw_parts(
+                                    arg6.cast(),
+                                    len1,
+                                    len1,
+                                );
+                                _rt::string_lift(bytes1)
+                            };
+                            V2::Other(e2)
+                        }
+                    };
+                    let result5 = T::handle_websocket_message(
                         match arg0 {
                             0 => None,
                             1 => {
@@ -3525,67 +6448,199 @@ pub mod exports {
                             _ => _rt::invalid_enum_discriminant(),
                         },
                         (
-                            _rt::string_lift(bytes1),
-                            match arg5 {
-                                0 => None,
-                                1 => {
-                                    let e = {
-                                        let len2 = arg7;
-                                        _rt::Vec::from_raw_parts(arg6.cast(), len2, len2)
-                                    };
-                                    Some(e)
-                                }
-                                _ => _rt::invalid_enum_discriminant(),
+                            arg3 as u64,
+                            arg4 as u64,
+                            super::super::super::super::theater::simple::websocket_types::WebsocketMessage {
+                                ty: v2,
+                                data: match arg8 {
+                                    0 => None,
+                                    1 => {
+                                        let e = {
+                                            let len3 = arg10;
+                                            _rt::Vec::from_raw_parts(arg9.cast(), len3, len3)
+                                        };
+                                        Some(e)
+                                    }
+                                    _ => _rt::invalid_enum_discriminant(),
+                                },
+                                text: match arg11 {
+                                    0 => None,
+                                    1 => {
+                                        let e = {
+                                            let len4 = arg13;
+                                            let bytes4 = _rt::Vec::from_raw_parts(
+                                                arg12.cast(),
+                                                len4,
+                                                len4,
+                                            );
+                                            _rt::string_lift(bytes4)
+                                        };
+                                        Some(e)
+                                    }
+                                    _ => _rt::invalid_enum_discriminant(),
+                                },
                             },
                         ),
                     );
-                    let ptr4 = (&raw mut _RET_AREA.0).cast::<u8>();
-                    match result3 {
+                    let ptr6 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result5 {
                         Ok(e) => {
-                            *ptr4.add(0).cast::<u8>() = (0i32) as u8;
-                            let (t5_0,) = e;
-                            match t5_0 {
+                            *ptr6.add(0).cast::<u8>() = (0i32) as u8;
+                            let (t7_0, t7_1) = e;
+                            match t7_0 {
                                 Some(e) => {
-                                    *ptr4
+                                    *ptr6
                                         .add(::core::mem::size_of::<*const u8>())
                                         .cast::<u8>() = (1i32) as u8;
-                                    let vec6 = (e).into_boxed_slice();
-                                    let ptr6 = vec6.as_ptr().cast::<u8>();
-                                    let len6 = vec6.len();
-                                    ::core::mem::forget(vec6);
-                                    *ptr4
+                                    let vec8 = (e).into_boxed_slice();
+                                    let ptr8 = vec8.as_ptr().cast::<u8>();
+                                    let len8 = vec8.len();
+                                    ::core::mem::forget(vec8);
+                                    *ptr6
                                         .add(3 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<usize>() = len6;
-                                    *ptr4
+                                        .cast::<usize>() = len8;
+                                    *ptr6
                                         .add(2 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<*mut u8>() = ptr6.cast_mut();
+                                        .cast::<*mut u8>() = ptr8.cast_mut();
                                 }
                                 None => {
-                                    *ptr4
+                                    *ptr6
                                         .add(::core::mem::size_of::<*const u8>())
                                         .cast::<u8>() = (0i32) as u8;
                                 }
                             };
+                            let (t9_0,) = t7_1;
+                            let vec16 = t9_0;
+                            let len16 = vec16.len();
+                            let layout16 = _rt::alloc::Layout::from_size_align_unchecked(
+                                vec16.len() * (9 * ::core::mem::size_of::<*const u8>()),
+                                ::core::mem::size_of::<*const u8>(),
+                            );
+                            let result16 = if layout16.size() != 0 {
+                                let ptr = _rt::alloc::alloc(layout16).cast::<u8>();
+                                if ptr.is_null() {
+                                    _rt::alloc::handle_alloc_error(layout16);
+                                }
+                                ptr
+                            } else {
+                                ::core::ptr::null_mut()
+                            };
+                            for (i, e) in vec16.into_iter().enumerate() {
+                                let base = result16
+                                    .add(i * (9 * ::core::mem::size_of::<*const u8>()));
+                                {
+                                    let super::super::super::super::theater::simple::websocket_types::WebsocketMessage {
+                                        ty: ty10,
+                                        data: data10,
+                                        text: text10,
+                                    } = e;
+                                    match ty10 {
+                                        V2::Text => {
+                                            *base.add(0).cast::<u8>() = (0i32) as u8;
+                                        }
+                                        V2::Binary => {
+                                            *base.add(0).cast::<u8>() = (1i32) as u8;
+                                        }
+                                        V2::Connect => {
+                                            *base.add(0).cast::<u8>() = (2i32) as u8;
+                                        }
+                                        V2::Close => {
+                                            *base.add(0).cast::<u8>() = (3i32) as u8;
+                                        }
+                                        V2::Ping => {
+                                            *base.add(0).cast::<u8>() = (4i32) as u8;
+                                        }
+                                        V2::Pong => {
+                                            *base.add(0).cast::<u8>() = (5i32) as u8;
+                                        }
+                                        V2::Other(e) => {
+                                            *base.add(0).cast::<u8>() = (6i32) as u8;
+                                            let vec12 = (e.into_bytes()).into_boxed_slice();
+                                            let ptr12 = vec12.as_ptr().cast::<u8>();
+                                            let len12 = vec12.len();
+                                            ::core::mem::forget(vec12);
+                                            *base
+                                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<usize>() = len12;
+                                            *base
+                                                .add(::core::mem::size_of::<*const u8>())
+                                                .cast::<*mut u8>() = ptr12.cast_mut();
+                                        }
+                                    }
+                                    match data10 {
+                                        Some(e) => {
+                                            *base
+                                                .add(3 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<u8>() = (1i32) as u8;
+                                            let vec13 = (e).into_boxed_slice();
+                                            let ptr13 = vec13.as_ptr().cast::<u8>();
+                                            let len13 = vec13.len();
+                                            ::core::mem::forget(vec13);
+                                            *base
+                                                .add(5 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<usize>() = len13;
+                                            *base
+                                                .add(4 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<*mut u8>() = ptr13.cast_mut();
+                                        }
+                                        None => {
+                                            *base
+                                                .add(3 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<u8>() = (0i32) as u8;
+                                        }
+                                    };
+                                    match text10 {
+                                        Some(e) => {
+                                            *base
+                                                .add(6 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<u8>() = (1i32) as u8;
+                                            let vec14 = (e.into_bytes()).into_boxed_slice();
+                                            let ptr14 = vec14.as_ptr().cast::<u8>();
+                                            let len14 = vec14.len();
+                                            ::core::mem::forget(vec14);
+                                            *base
+                                                .add(8 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<usize>() = len14;
+                                            *base
+                                                .add(7 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<*mut u8>() = ptr14.cast_mut();
+                                        }
+                                        None => {
+                                            *base
+                                                .add(6 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<u8>() = (0i32) as u8;
+                                        }
+                                    };
+                                }
+                            }
+                            *ptr6
+                                .add(5 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len16;
+                            *ptr6
+                                .add(4 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = result16;
                         }
                         Err(e) => {
-                            *ptr4.add(0).cast::<u8>() = (1i32) as u8;
-                            let vec7 = (e.into_bytes()).into_boxed_slice();
-                            let ptr7 = vec7.as_ptr().cast::<u8>();
-                            let len7 = vec7.len();
-                            ::core::mem::forget(vec7);
-                            *ptr4
+                            *ptr6.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec17 = (e.into_bytes()).into_boxed_slice();
+                            let ptr17 = vec17.as_ptr().cast::<u8>();
+                            let len17 = vec17.len();
+                            ::core::mem::forget(vec17);
+                            *ptr6
                                 .add(2 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>() = len7;
-                            *ptr4
+                                .cast::<usize>() = len17;
+                            *ptr6
                                 .add(::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>() = ptr7.cast_mut();
+                                .cast::<*mut u8>() = ptr17.cast_mut();
                         }
                     };
-                    ptr4
+                    ptr6
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn __post_return_handle_child_exit<T: Guest>(arg0: *mut u8) {
+                pub unsafe fn __post_return_handle_websocket_message<T: Guest>(
+                    arg0: *mut u8,
+                ) {
                     let l0 = i32::from(*arg0.add(0).cast::<u8>());
                     match l0 {
                         0 => {
@@ -3606,31 +6661,102 @@ pub mod exports {
                                     _rt::cabi_dealloc(base4, len4 * 1, 1);
                                 }
                             }
+                            let l5 = *arg0
+                                .add(4 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l6 = *arg0
+                                .add(5 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            let base18 = l5;
+                            let len18 = l6;
+                            for i in 0..len18 {
+                                let base = base18
+                                    .add(i * (9 * ::core::mem::size_of::<*const u8>()));
+                                {
+                                    let l7 = i32::from(*base.add(0).cast::<u8>());
+                                    match l7 {
+                                        0 => {}
+                                        1 => {}
+                                        2 => {}
+                                        3 => {}
+                                        4 => {}
+                                        5 => {}
+                                        _ => {
+                                            let l8 = *base
+                                                .add(::core::mem::size_of::<*const u8>())
+                                                .cast::<*mut u8>();
+                                            let l9 = *base
+                                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<usize>();
+                                            _rt::cabi_dealloc(l8, l9, 1);
+                                        }
+                                    }
+                                    let l10 = i32::from(
+                                        *base
+                                            .add(3 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>(),
+                                    );
+                                    match l10 {
+                                        0 => {}
+                                        _ => {
+                                            let l11 = *base
+                                                .add(4 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<*mut u8>();
+                                            let l12 = *base
+                                                .add(5 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<usize>();
+                                            let base13 = l11;
+                                            let len13 = l12;
+                                            _rt::cabi_dealloc(base13, len13 * 1, 1);
+                                        }
+                                    }
+                                    let l14 = i32::from(
+                                        *base
+                                            .add(6 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>(),
+                                    );
+                                    match l14 {
+                                        0 => {}
+                                        _ => {
+                                            let l15 = *base
+                                                .add(7 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<*mut u8>();
+                                            let l16 = *base
+                                                .add(8 * ::core::mem::size_of::<*const u8>())
+                                                .cast::<usize>();
+                                            _rt::cabi_dealloc(l15, l16, 1);
+                                        }
+                                    }
+                                }
+                            }
+                            _rt::cabi_dealloc(
+                                base18,
+                                len18 * (9 * ::core::mem::size_of::<*const u8>()),
+                                ::core::mem::size_of::<*const u8>(),
+                            );
                         }
                         _ => {
-                            let l5 = *arg0
+                            let l19 = *arg0
                                 .add(::core::mem::size_of::<*const u8>())
                                 .cast::<*mut u8>();
-                            let l6 = *arg0
+                            let l20 = *arg0
                                 .add(2 * ::core::mem::size_of::<*const u8>())
                                 .cast::<usize>();
-                            _rt::cabi_dealloc(l5, l6, 1);
+                            _rt::cabi_dealloc(l19, l20, 1);
                         }
                     }
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn _export_handle_child_external_stop_cabi<T: Guest>(
+                pub unsafe fn _export_handle_websocket_disconnect_cabi<T: Guest>(
                     arg0: i32,
                     arg1: *mut u8,
                     arg2: usize,
-                    arg3: *mut u8,
-                    arg4: usize,
+                    arg3: i64,
+                    arg4: i64,
                 ) -> *mut u8 {
                     #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
-                    let len1 = arg4;
-                    let bytes1 = _rt::Vec::from_raw_parts(arg3.cast(), len1, len1);
-                    let result2 = T::handle_child_external_stop(
+                    let result1 = T::handle_websocket_disconnect(
                         match arg0 {
                             0 => None,
                             1 => {
@@ -3642,55 +6768,55 @@ pub mod exports {
                             }
                             _ => _rt::invalid_enum_discriminant(),
                         },
-                        (_rt::string_lift(bytes1),),
+                        (arg3 as u64, arg4 as u64),
                     );
-                    let ptr3 = (&raw mut _RET_AREA.0).cast::<u8>();
-                    match result2 {
+                    let ptr2 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result1 {
                         Ok(e) => {
-                            *ptr3.add(0).cast::<u8>() = (0i32) as u8;
-                            let (t4_0,) = e;
-                            match t4_0 {
+                            *ptr2.add(0).cast::<u8>() = (0i32) as u8;
+                            let (t3_0,) = e;
+                            match t3_0 {
                                 Some(e) => {
-                                    *ptr3
+                                    *ptr2
                                         .add(::core::mem::size_of::<*const u8>())
                                         .cast::<u8>() = (1i32) as u8;
-                                    let vec5 = (e).into_boxed_slice();
-                                    let ptr5 = vec5.as_ptr().cast::<u8>();
-                                    let len5 = vec5.len();
-                                    ::core::mem::forget(vec5);
-                                    *ptr3
+                                    let vec4 = (e).into_boxed_slice();
+                                    let ptr4 = vec4.as_ptr().cast::<u8>();
+                                    let len4 = vec4.len();
+                                    ::core::mem::forget(vec4);
+                                    *ptr2
                                         .add(3 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<usize>() = len5;
-                                    *ptr3
+                                        .cast::<usize>() = len4;
+                                    *ptr2
                                         .add(2 * ::core::mem::size_of::<*const u8>())
-                                        .cast::<*mut u8>() = ptr5.cast_mut();
+                                        .cast::<*mut u8>() = ptr4.cast_mut();
                                 }
                                 None => {
-                                    *ptr3
+                                    *ptr2
                                         .add(::core::mem::size_of::<*const u8>())
                                         .cast::<u8>() = (0i32) as u8;
                                 }
                             };
                         }
                         Err(e) => {
-                            *ptr3.add(0).cast::<u8>() = (1i32) as u8;
-                            let vec6 = (e.into_bytes()).into_boxed_slice();
-                            let ptr6 = vec6.as_ptr().cast::<u8>();
-                            let len6 = vec6.len();
-                            ::core::mem::forget(vec6);
-                            *ptr3
+                            *ptr2.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec5 = (e.into_bytes()).into_boxed_slice();
+                            let ptr5 = vec5.as_ptr().cast::<u8>();
+                            let len5 = vec5.len();
+                            ::core::mem::forget(vec5);
+                            *ptr2
                                 .add(2 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>() = len6;
-                            *ptr3
+                                .cast::<usize>() = len5;
+                            *ptr2
                                 .add(::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>() = ptr6.cast_mut();
+                                .cast::<*mut u8>() = ptr5.cast_mut();
                         }
                     };
-                    ptr3
+                    ptr2
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
-                pub unsafe fn __post_return_handle_child_external_stop<T: Guest>(
+                pub unsafe fn __post_return_handle_websocket_disconnect<T: Guest>(
                     arg0: *mut u8,
                 ) {
                     let l0 = i32::from(*arg0.add(0).cast::<u8>());
@@ -3726,83 +6852,114 @@ pub mod exports {
                     }
                 }
                 pub trait Guest {
-                    /// # Handle a child actor error
-                    ///
-                    /// Processes an error from a child actor, allowing the parent to react or log the error.
-                    ///
-                    /// ## Parameters
-                    ///
-                    /// * `state` - Optional state of the parent actor (for context)
-                    /// * `params` - Tuple containing the child ID and error data
-                    ///
-                    /// ## Returns
-                    ///
-                    /// * `Ok(tuple<option<list<u8>>, string>)` - Updated state and result message
-                    /// * `Err(string)` - Error message if handling fails
-                    fn handle_child_error(
+                    /// Handle an HTTP request routed to one of the actor's handlers
+                    fn handle_request(
                         state: Option<_rt::Vec<u8>>,
-                        params: (_rt::String, WitActorError),
-                    ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
-                    fn handle_child_exit(
+                        params: (HandlerId, HttpRequest),
+                    ) -> Result<(Option<_rt::Vec<u8>>, (HttpResponse,)), _rt::String>;
+                    /// Inspect or rewrite a request before it reaches its route
+                    fn handle_middleware(
                         state: Option<_rt::Vec<u8>>,
-                        params: (_rt::String, Option<_rt::Vec<u8>>),
+                        params: (HandlerId, HttpRequest),
+                    ) -> Result<(Option<_rt::Vec<u8>>, (MiddlewareResult,)), _rt::String>;
+                    /// Handle a new WebSocket connection
+                    fn handle_websocket_connect(
+                        state: Option<_rt::Vec<u8>>,
+                        params: (HandlerId, u64, _rt::String, Option<_rt::String>),
                     ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
-                    fn handle_child_external_stop(
+                    /// Handle a message on a WebSocket connection
+                    fn handle_websocket_message(
                         state: Option<_rt::Vec<u8>>,
-                        params: (_rt::String,),
+                        params: (HandlerId, u64, WebsocketMessage),
+                    ) -> Result<
+                        (Option<_rt::Vec<u8>>, (_rt::Vec<WebsocketMessage>,)),
+                        _rt::String,
+                    >;
+                    /// Handle a closed WebSocket connection
+                    fn handle_websocket_disconnect(
+                        state: Option<_rt::Vec<u8>>,
+                        params: (HandlerId, u64),
                     ) -> Result<(Option<_rt::Vec<u8>>,), _rt::String>;
                 }
                 #[doc(hidden)]
-                macro_rules! __export_theater_simple_supervisor_handlers_cabi {
+                macro_rules! __export_theater_simple_http_handlers_cabi {
                     ($ty:ident with_types_in $($path_to_types:tt)*) => {
                         const _ : () = { #[unsafe (export_name =
-                        "theater:simple/supervisor-handlers#handle-child-error")] unsafe
-                        extern "C" fn export_handle_child_error(arg0 : i32, arg1 : * mut
-                        u8, arg2 : usize, arg3 : * mut u8, arg4 : usize, arg5 : i32, arg6
-                        : i32, arg7 : * mut u8, arg8 : usize,) -> * mut u8 { unsafe {
-                        $($path_to_types)*:: _export_handle_child_error_cabi::<$ty >
-                        (arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8) } }
-                        #[unsafe (export_name =
-                        "cabi_post_theater:simple/supervisor-handlers#handle-child-error")]
-                        unsafe extern "C" fn _post_return_handle_child_error(arg0 : * mut
+                        "theater:simple/http-handlers#handle-request")] unsafe extern "C"
+                        fn export_handle_request(arg0 : i32, arg1 : * mut u8, arg2 :
+                        usize, arg3 : i64, arg4 : * mut u8, arg5 : usize, arg6 : * mut
+                        u8, arg7 : usize, arg8 : * mut u8, arg9 : usize, arg10 : i32,
+                        arg11 : * mut u8, arg12 : usize,) -> * mut u8 { unsafe {
+                        $($path_to_types)*:: _export_handle_request_cabi::<$ty > (arg0,
+                        arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8, arg9, arg10,
+                        arg11, arg12) } } #[unsafe (export_name =
+                        "cabi_post_theater:simple/http-handlers#handle-request")] unsafe
+                        extern "C" fn _post_return_handle_request(arg0 : * mut u8,) {
+                        unsafe { $($path_to_types)*:: __post_return_handle_request::<$ty
+                        > (arg0) } } #[unsafe (export_name =
+                        "theater:simple/http-handlers#handle-middleware")] unsafe extern
+                        "C" fn export_handle_middleware(arg0 : i32, arg1 : * mut u8, arg2
+                        : usize, arg3 : i64, arg4 : * mut u8, arg5 : usize, arg6 : * mut
+                        u8, arg7 : usize, arg8 : * mut u8, arg9 : usize, arg10 : i32,
+                        arg11 : * mut u8, arg12 : usize,) -> * mut u8 { unsafe {
+                        $($path_to_types)*:: _export_handle_middleware_cabi::<$ty >
+                        (arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8, arg9,
+                        arg10, arg11, arg12) } } #[unsafe (export_name =
+                        "cabi_post_theater:simple/http-handlers#handle-middleware")]
+                        unsafe extern "C" fn _post_return_handle_middleware(arg0 : * mut
                         u8,) { unsafe { $($path_to_types)*::
-                        __post_return_handle_child_error::<$ty > (arg0) } } #[unsafe
-                        (export_name =
-                        "theater:simple/supervisor-handlers#handle-child-exit")] unsafe
-                        extern "C" fn export_handle_child_exit(arg0 : i32, arg1 : * mut
-                        u8, arg2 : usize, arg3 : * mut u8, arg4 : usize, arg5 : i32, arg6
-                        : * mut u8, arg7 : usize,) -> * mut u8 { unsafe {
-                        $($path_to_types)*:: _export_handle_child_exit_cabi::<$ty >
-                        (arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7) } } #[unsafe
+                        __post_return_handle_middleware::<$ty > (arg0) } } #[unsafe
                         (export_name =
-                        "cabi_post_theater:simple/supervisor-handlers#handle-child-exit")]
-                        unsafe extern "C" fn _post_return_handle_child_exit(arg0 : * mut
-                        u8,) { unsafe { $($path_to_types)*::
-                        __post_return_handle_child_exit::<$ty > (arg0) } } #[unsafe
+                        "theater:simple/http-handlers#handle-websocket-connect")] unsafe
+                        extern "C" fn export_handle_websocket_connect(arg0 : i32, arg1 :
+                        * mut u8, arg2 : usize, arg3 : i64, arg4 : i64, arg5 : * mut u8,
+                        arg6 : usize, arg7 : i32, arg8 : * mut u8, arg9 : usize,) -> *
+                        mut u8 { unsafe { $($path_to_types)*::
+                        _export_handle_websocket_connect_cabi::<$ty > (arg0, arg1, arg2,
+                        arg3, arg4, arg5, arg6, arg7, arg8, arg9) } } #[unsafe
                         (export_name =
-                        "theater:simple/supervisor-handlers#handle-child-external-stop")]
-                        unsafe extern "C" fn export_handle_child_external_stop(arg0 :
-                        i32, arg1 : * mut u8, arg2 : usize, arg3 : * mut u8, arg4 :
-                        usize,) -> * mut u8 { unsafe { $($path_to_types)*::
-                        _export_handle_child_external_stop_cabi::<$ty > (arg0, arg1,
+                        "cabi_post_theater:simple/http-handlers#handle-websocket-connect")]
+                        unsafe extern "C" fn _post_return_handle_websocket_connect(arg0 :
+                        * mut u8,) { unsafe { $($path_to_types)*::
+                        __post_return_handle_websocket_connect::<$ty > (arg0) } }
+                        #[unsafe (export_name =
+                        "theater:simple/http-handlers#handle-websocket-message")] unsafe
+                        extern "C" fn export_handle_websocket_message(arg0 : i32, arg1 :
+                        * mut u8, arg2 : usize, arg3 : i64, arg4 : i64, arg5 : i32, arg6
+                        : * mut u8, arg7 : usize, arg8 : i32, arg9 : * mut u8, arg10 :
+                        usize, arg11 : i32, arg12 : * mut u8, arg13 : usize,) -> * mut u8
+                        { unsafe { $($path_to_types)*::
+                        _export_handle_websocket_message_cabi::<$ty > (arg0, arg1, arg2,
+                        arg3, arg4, arg5, arg6, arg7, arg8, arg9, arg10, arg11, arg12,
+                        arg13) } } #[unsafe (export_name =
+                        "cabi_post_theater:simple/http-handlers#handle-websocket-message")]
+                        unsafe extern "C" fn _post_return_handle_websocket_message(arg0 :
+                        * mut u8,) { unsafe { $($path_to_types)*::
+                        __post_return_handle_websocket_message::<$ty > (arg0) } }
+                        #[unsafe (export_name =
+                        "theater:simple/http-handlers#handle-websocket-disconnect")]
+                        unsafe extern "C" fn export_handle_websocket_disconnect(arg0 :
+                        i32, arg1 : * mut u8, arg2 : usize, arg3 : i64, arg4 : i64,) -> *
+                        mut u8 { unsafe { $($path_to_types)*::
+                        _export_handle_websocket_disconnect_cabi::<$ty > (arg0, arg1,
                         arg2, arg3, arg4) } } #[unsafe (export_name =
-                        "cabi_post_theater:simple/supervisor-handlers#handle-child-external-stop")]
-                        unsafe extern "C" fn _post_return_handle_child_external_stop(arg0
+                        "cabi_post_theater:simple/http-handlers#handle-websocket-disconnect")]
+                        unsafe extern "C" fn _post_return_handle_websocket_disconnect(arg0
                         : * mut u8,) { unsafe { $($path_to_types)*::
-                        __post_return_handle_child_external_stop::<$ty > (arg0) } } };
+                        __post_return_handle_websocket_disconnect::<$ty > (arg0) } } };
                     };
                 }
                 #[doc(hidden)]
-                pub(crate) use __export_theater_simple_supervisor_handlers_cabi;
+                pub(crate) use __export_theater_simple_http_handlers_cabi;
                 #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
                 #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
                 struct _RetArea(
                     [::core::mem::MaybeUninit<
                         u8,
-                    >; 4 * ::core::mem::size_of::<*const u8>()],
+                    >; 14 * ::core::mem::size_of::<*const u8>()],
                 );
                 static mut _RET_AREA: _RetArea = _RetArea(
-                    [::core::mem::MaybeUninit::uninit(); 4
+                    [::core::mem::MaybeUninit::uninit(); 14
                         * ::core::mem::size_of::<*const u8>()],
                 );
             }
@@ -3835,6 +6992,99 @@ mod _rt {
         let layout = alloc::Layout::from_size_align_unchecked(size, align);
         alloc::dealloc(ptr, layout);
     }
+    pub fn as_i32<T: AsI32>(t: T) -> i32 {
+        t.as_i32()
+    }
+    pub trait AsI32 {
+        fn as_i32(self) -> i32;
+    }
+    impl<'a, T: Copy + AsI32> AsI32 for &'a T {
+        fn as_i32(self) -> i32 {
+            (*self).as_i32()
+        }
+    }
+    impl AsI32 for i32 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for u32 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for i16 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for u16 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for i8 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for u8 {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for char {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    impl AsI32 for usize {
+        #[inline]
+        fn as_i32(self) -> i32 {
+            self as i32
+        }
+    }
+    pub fn as_i64<T: AsI64>(t: T) -> i64 {
+        t.as_i64()
+    }
+    pub trait AsI64 {
+        fn as_i64(self) -> i64;
+    }
+    impl<'a, T: Copy + AsI64> AsI64 for &'a T {
+        fn as_i64(self) -> i64 {
+            (*self).as_i64()
+        }
+    }
+    impl AsI64 for i64 {
+        #[inline]
+        fn as_i64(self) -> i64 {
+            self as i64
+        }
+    }
+    impl AsI64 for u64 {
+        #[inline]
+        fn as_i64(self) -> i64 {
+            self as i64
+        }
+    }
+    pub unsafe fn bool_lift(val: u8) -> bool {
+        if cfg!(debug_assertions) {
+            match val {
+                0 => false,
+                1 => true,
+                _ => panic!("invalid bool discriminant"),
+            }
+        } else {
+            val != 0
+        }
+    }
     #[cfg(target_arch = "wasm32")]
     pub fn run_ctors_once() {
         wit_bindgen_rt::run_ctors_once();
@@ -3874,7 +7124,9 @@ macro_rules! __export_default_impl {
         exports::theater::simple::message_server_client); $($path_to_types_root)*::
         exports::theater::simple::supervisor_handlers::__export_theater_simple_supervisor_handlers_cabi!($ty
         with_types_in $($path_to_types_root)*::
-        exports::theater::simple::supervisor_handlers);
+        exports::theater::simple::supervisor_handlers); $($path_to_types_root)*::
+        exports::theater::simple::http_handlers::__export_theater_simple_http_handlers_cabi!($ty
+        with_types_in $($path_to_types_root)*:: exports::theater::simple::http_handlers);
     };
 }
 #[doc(inline)]
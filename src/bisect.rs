@@ -0,0 +1,207 @@
+use crate::exec_tools::run_command;
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+
+/// The `bisect` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BisectConfig {
+    /// Shell command that decides each step, as for `git bisect run`: exit code 0 is
+    /// good, 125 is skip, anything else is bad. Without it the user gives the verdicts
+    pub test_command: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BisectVerdict {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl BisectVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BisectVerdict::Good => "good",
+            BisectVerdict::Bad => "bad",
+            BisectVerdict::Skip => "skip",
+        }
+    }
+
+    /// Verdict from a test command's exit code, following `git bisect run`.
+    pub fn from_exit_code(code: i32) -> Self {
+        match code {
+            0 => BisectVerdict::Good,
+            125 => BisectVerdict::Skip,
+            _ => BisectVerdict::Bad,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BisectPhase {
+    /// Waiting for `StartBisect` with the good and bad refs
+    AwaitingRefs,
+    /// A commit is checked out and waits for its verdict
+    Testing,
+    Found,
+    Aborted,
+}
+
+/// A commit bisect checked out, with the verdict once given.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BisectStep {
+    pub sha: String,
+    pub subject: String,
+    pub verdict: Option<BisectVerdict>,
+    /// Tail of the test command's output, when one decided the verdict
+    #[serde(default)]
+    pub test_output: Option<String>,
+}
+
+/// The bisect session of the `bisect` workflow.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BisectState {
+    pub phase: BisectPhase,
+    pub good: Option<String>,
+    pub bad: Option<String>,
+    /// Every step so far, the one being tested last
+    pub steps: Vec<BisectStep>,
+    /// Git's estimate of the steps left, from its last "Bisecting:" line
+    pub steps_left: Option<u32>,
+    pub first_bad: Option<BisectStep>,
+}
+
+impl Default for BisectState {
+    fn default() -> Self {
+        Self {
+            phase: BisectPhase::AwaitingRefs,
+            good: None,
+            bad: None,
+            steps: Vec::new(),
+            steps_left: None,
+            first_bad: None,
+        }
+    }
+}
+
+impl BisectState {
+    /// The step waiting for a verdict.
+    pub fn current(&self) -> Option<&BisectStep> {
+        self.steps
+            .last()
+            .filter(|step| self.phase == BisectPhase::Testing && step.verdict.is_none())
+    }
+}
+
+/// Where git left the bisect after `start` or a verdict.
+pub enum BisectOutcome {
+    /// The next commit to test was checked out
+    Testing { steps_left: Option<u32> },
+    /// git named the first bad commit
+    Found { sha: String },
+}
+
+/// Read the outcome from the output of `git bisect start|good|bad|skip`.
+pub fn parse_outcome(output: &str) -> Result<BisectOutcome, String> {
+    for line in output.lines() {
+        if let Some(sha) = line.trim().strip_suffix(" is the first bad commit") {
+            return Ok(BisectOutcome::Found {
+                sha: sha.trim().to_string(),
+            });
+        }
+    }
+    // "Bisecting: 6 revisions left to test after this (roughly 3 steps)"
+    if let Some(line) = output.lines().find(|line| line.starts_with("Bisecting:")) {
+        let steps_left = line
+            .split("(roughly ")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|steps| steps.parse().ok());
+        return Ok(BisectOutcome::Testing { steps_left });
+    }
+    Err(format!("Unexpected git bisect output: {}", output.trim()))
+}
+
+/// Sha and subject of `HEAD`.
+pub fn checked_out_commit(
+    git_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<BisectStep, String> {
+    let line = run_git(
+        git_actor_id,
+        directory,
+        &["log", "-1", "--format=%H%x1f%s", "HEAD"],
+    )?;
+    let (sha, subject) = line
+        .trim()
+        .split_once('\u{1f}')
+        .ok_or_else(|| format!("Unexpected git log output: {}", line))?;
+    Ok(BisectStep {
+        sha: sha.to_string(),
+        subject: subject.to_string(),
+        verdict: None,
+        test_output: None,
+    })
+}
+
+/// Run the test command on the checked-out commit. Returns the verdict and the tail of
+/// its output.
+pub fn run_test_command(
+    exec_actor_id: &str,
+    directory: Option<&str>,
+    test_command: &str,
+) -> Result<(BisectVerdict, String), String> {
+    let command = format!(
+        "out=$( ({}) 2>&1 ); code=$?; printf '%s\\n' \"$out\" | tail -n 20; echo \"exit:$code\"",
+        test_command
+    );
+    let output = run_command(exec_actor_id, directory, &command)?;
+    let (tail, code) = output
+        .trim_end()
+        .rsplit_once("exit:")
+        .ok_or_else(|| format!("No exit code in the test output: {}", output))?;
+    let code: i32 = code
+        .trim()
+        .parse()
+        .map_err(|e| format!("Unexpected exit code {}: {}", code, e))?;
+    Ok((BisectVerdict::from_exit_code(code), tail.trim().to_string()))
+}
+
+/// Message asking the model to look at the checked-out commit.
+pub fn step_message(bisect: &BisectState, step: &BisectStep, automatic: bool) -> String {
+    let mut message = format!(
+        "Bisect step {}: {} \"{}\" is checked out",
+        bisect.steps.len(),
+        step.sha,
+        step.subject
+    );
+    if let Some(steps_left) = bisect.steps_left {
+        message.push_str(&format!(" (roughly {} steps left)", steps_left));
+    }
+    message.push_str(
+        ". Look at what this commit changed (git show --stat, then the relevant parts of the \
+        diff) and say whether it could have introduced the problem. Don't run git bisect \
+        commands or check out other commits; the assistant drives the bisect.",
+    );
+    if automatic {
+        message.push_str(" The configured test command will decide the verdict.");
+    } else {
+        message.push_str(
+            " Then ask me whether the problem occurs at this commit: good, bad, or skip.",
+        );
+    }
+    message
+}
+
+/// Message asking the model to explain the first bad commit and finish.
+pub fn found_message(bisect: &BisectState, first_bad: &BisectStep) -> String {
+    format!(
+        "Bisect finished after {} steps: {} \"{}\" is the first bad commit. The repository \
+        is back on the branch it started from. Explain what in this commit most likely \
+        caused the problem and how to fix it, then use the task_complete tool.",
+        bisect.steps.len(),
+        first_bad.sha,
+        first_bad.subject
+    )
+}
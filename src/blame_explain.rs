@@ -0,0 +1,182 @@
+//! The `blame-explain` workflow: the user names a file and a line range in the
+//! `StartChat` args, and the model walks their blame and log history to explain why
+//! the code looks the way it does.
+
+use crate::git_tools::run_git;
+use crate::sanitize::{self, TextKind};
+use crate::tool_output::ToolOutputPolicy;
+use serde_json::{Map, Value};
+
+/// Commits of the file's history shown when no line range is given.
+const FILE_HISTORY_LIMIT: &str = "20";
+
+/// What to explain, from `StartChat { args: {"file", "start_line", "end_line"} }`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameTarget {
+    pub file: String,
+    /// Lines from 1, inclusive; `None` explains the whole file's history
+    pub lines: Option<(u64, u64)>,
+}
+
+fn line_arg(args: &Map<String, Value>, name: &str) -> Result<Option<u64>, String> {
+    let line = match args.get(name) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::Number(number)) => number.as_u64(),
+        Some(Value::String(text)) => text.trim().parse().ok(),
+        Some(_) => None,
+    };
+    match line {
+        Some(line) if line > 0 => Ok(Some(line)),
+        _ => Err(format!("{} must be a line number from 1", name)),
+    }
+}
+
+impl BlameTarget {
+    pub fn from_args(args: Option<&Value>) -> Result<Self, String> {
+        let empty = Map::new();
+        let args = args.and_then(Value::as_object).unwrap_or(&empty);
+        let file = match args.get("file") {
+            Some(Value::String(file)) if !file.trim().is_empty() => file.trim().to_string(),
+            _ => {
+                return Err(
+                    "the blame-explain workflow needs a file, e.g. args: {\"file\": \
+                    \"src/lib.rs\", \"start_line\": 10, \"end_line\": 20}"
+                        .to_string(),
+                )
+            }
+        };
+        let lines = match (line_arg(args, "start_line")?, line_arg(args, "end_line")?) {
+            (Some(start), Some(end)) if start > end => {
+                return Err(format!("start_line {} is after end_line {}", start, end))
+            }
+            (Some(start), end) => Some((start, end.unwrap_or(start))),
+            (None, Some(end)) => Some((1, end)),
+            (None, None) => None,
+        };
+        Ok(BlameTarget { file, lines })
+    }
+
+    pub fn describe_range(&self) -> String {
+        match self.lines {
+            Some((start, end)) if start == end => format!("line {} of {}", start, self.file),
+            Some((start, end)) => format!("lines {}-{} of {}", start, end, self.file),
+            None => self.file.clone(),
+        }
+    }
+}
+
+/// Blame of the range and the commits that changed it, or the file's latest commits
+/// when no range is given.
+pub fn fetch_history(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    target: &BlameTarget,
+) -> Result<(Option<String>, String), String> {
+    match target.lines {
+        Some((start, end)) => {
+            let range = format!("{},{}", start, end);
+            let blame = run_git(
+                git_actor_id,
+                directory,
+                &["blame", "--date=short", "-L", &range, "--", &target.file],
+            )?;
+            let log = run_git(
+                git_actor_id,
+                directory,
+                &[
+                    "log",
+                    "--no-patch",
+                    "--format=%h %ad %an: %s",
+                    "--date=short",
+                    &format!("-L{}:{}", range, target.file),
+                ],
+            )?;
+            Ok((Some(blame), log))
+        }
+        None => {
+            let log = run_git(
+                git_actor_id,
+                directory,
+                &[
+                    "log",
+                    "--follow",
+                    "-n",
+                    FILE_HISTORY_LIMIT,
+                    "--format=%h %ad %an: %s",
+                    "--date=short",
+                    "--",
+                    &target.file,
+                ],
+            )?;
+            Ok((None, log))
+        }
+    }
+}
+
+/// Block for the opening message: the blame and the commits, elided like tool output.
+pub fn describe(
+    target: &BlameTarget,
+    blame: Option<&str>,
+    log: &str,
+    policy: &ToolOutputPolicy,
+) -> String {
+    let mut block = format!(
+        "\n\nEXPLAIN: {}",
+        sanitize::escape(&target.describe_range(), TextKind::Name)
+    );
+    if let Some(blame) = blame {
+        block.push_str(&format!(
+            "\nBLAME:\n```\n{}\n```",
+            sanitize::escape(&policy.elide(blame.trim_end()), TextKind::Message)
+        ));
+    }
+    if log.trim().is_empty() {
+        block.push_str("\nNo commits were found; the lines may not be committed yet.");
+    } else {
+        block.push_str(&format!(
+            "\n{}:\n```\n{}\n```",
+            if blame.is_some() {
+                "COMMITS THAT CHANGED THESE LINES, newest first"
+            } else {
+                "LATEST COMMITS OF THE FILE, newest first"
+            },
+            sanitize::escape(&policy.elide(log.trim_end()), TextKind::Message)
+        ));
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn target(args: Value) -> Result<BlameTarget, String> {
+        BlameTarget::from_args(Some(&args))
+    }
+
+    #[test]
+    fn args_name_the_file_and_lines() {
+        let lines =
+            target(json!({"file": "src/lib.rs", "start_line": 10, "end_line": "20"})).unwrap();
+        assert_eq!(lines.lines, Some((10, 20)));
+        assert_eq!(lines.describe_range(), "lines 10-20 of src/lib.rs");
+        let line = target(json!({"file": "a.rs", "start_line": 3})).unwrap();
+        assert_eq!(line.describe_range(), "line 3 of a.rs");
+        assert_eq!(target(json!({"file": "a.rs"})).unwrap().lines, None);
+
+        assert!(target(json!({"start_line": 3})).is_err());
+        assert!(BlameTarget::from_args(None).is_err());
+        assert!(target(json!({"file": "a.rs", "start_line": 9, "end_line": 2})).is_err());
+        assert!(target(json!({"file": "a.rs", "start_line": 0})).is_err());
+
+        let block = describe(
+            &target(json!({"file": "a.rs", "start_line": 1})).unwrap(),
+            Some("4f2c9a1b (Ada 2025-01-02 1) fn main() {}\n"),
+            "4f2c9a1 2025-01-02 Ada: Add main\n",
+            &ToolOutputPolicy::default(),
+        );
+        assert!(block.starts_with("\n\nEXPLAIN: line 1 of a.rs\nBLAME:\n```\n4f2c9a1b (Ada"));
+        assert!(block.ends_with("newest first:\n```\n4f2c9a1 2025-01-02 Ada: Add main\n```"));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::sanitize::{self, TextKind};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The `branch_naming` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BranchNaming {
+    /// Allowed prefixes such as `"feature/"`; any name is accepted when empty
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+    /// Regex the whole branch name must match
+    pub pattern: Option<String>,
+    pub max_length: Option<usize>,
+}
+
+/// A proposed branch name that is still invalid after normalization.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BranchNameError {
+    pub proposed: String,
+    pub normalized: String,
+    pub problems: Vec<String>,
+}
+
+impl BranchNaming {
+    pub fn prompt_fragment(&self) -> String {
+        let mut fragment = String::from("\n\nBRANCH NAMING:");
+        if !self.prefixes.is_empty() {
+            fragment.push_str(&format!(
+                "\n- Branch names start with one of: {}",
+                self.prefixes.join(", ")
+            ));
+        }
+        if let Some(pattern) = &self.pattern {
+            fragment.push_str(&format!(
+                "\n- Branch names match the regular expression: {}",
+                pattern
+            ));
+        }
+        if let Some(max_length) = self.max_length {
+            fragment.push_str(&format!(
+                "\n- Branch names are at most {} characters",
+                max_length
+            ));
+        }
+        fragment.push_str("\n- Use lowercase words separated by hyphens");
+        fragment
+    }
+
+    /// Problems with `name` under the configured conventions.
+    fn check(&self, name: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.prefixes.is_empty()
+            && !self
+                .prefixes
+                .iter()
+                .any(|prefix| name.starts_with(prefix.as_str()))
+        {
+            problems.push(format!(
+                "name must start with one of {}",
+                self.prefixes.join(", ")
+            ));
+        }
+        if let Some(pattern) = &self.pattern {
+            match Regex::new(pattern) {
+                Ok(regex) if !regex.is_match(name) => {
+                    problems.push(format!("name does not match {}", pattern))
+                }
+                Ok(_) => {}
+                Err(e) => problems.push(format!("invalid branch naming pattern: {}", e)),
+            }
+        }
+        if let Some(max_length) = self.max_length {
+            if name.len() > max_length {
+                problems.push(format!(
+                    "name is {} characters, the limit is {}",
+                    name.len(),
+                    max_length
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// Problems with `name` under git's `check-ref-format` rules for branches.
+pub fn ref_format_problems(name: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    if name.is_empty() {
+        problems.push("name is empty".to_string());
+        return problems;
+    }
+    if name == "@" {
+        problems.push("name cannot be \"@\"".to_string());
+    }
+    if name.starts_with('-') {
+        problems.push("name cannot start with '-'".to_string());
+    }
+    if name.starts_with('/') || name.ends_with('/') || name.contains("//") {
+        problems.push("name cannot start or end with '/' or contain \"//\"".to_string());
+    }
+    if name.ends_with('.') {
+        problems.push("name cannot end with '.'".to_string());
+    }
+    if name.contains("..") {
+        problems.push("name cannot contain \"..\"".to_string());
+    }
+    if name.contains("@{") {
+        problems.push("name cannot contain \"@{\"".to_string());
+    }
+    if name
+        .split('/')
+        .any(|component| component.starts_with('.') || component.ends_with(".lock"))
+    {
+        problems.push("path components cannot start with '.' or end with \".lock\"".to_string());
+    }
+    let forbidden: Vec<String> = name
+        .chars()
+        .filter(|c| c.is_ascii_control() || " ~^:?*[\\".contains(*c))
+        .map(|c| format!("{:?}", c))
+        .collect();
+    if !forbidden.is_empty() {
+        problems.push(format!(
+            "name contains forbidden characters: {}",
+            forbidden.join(" ")
+        ));
+    }
+    if !sanitize::find_hidden(name, TextKind::Name).is_empty() {
+        problems.push("name contains hidden Unicode characters".to_string());
+    }
+    problems
+}
+
+/// Slug a proposed name: lowercase ASCII words joined by hyphens, `/` kept as separator.
+pub fn normalize(name: &str, max_length: Option<usize>) -> String {
+    let visible = sanitize::strip(name, TextKind::Name);
+    let components: Vec<String> = visible
+        .split('/')
+        .map(|component| {
+            let mut slug = String::new();
+            for c in component.trim().chars() {
+                let c = c.to_ascii_lowercase();
+                if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                    slug.push(c);
+                } else if !slug.ends_with('-') {
+                    slug.push('-');
+                }
+            }
+            while slug.contains("..") {
+                slug = slug.replace("..", ".");
+            }
+            let mut slug = slug.trim_matches(|c| c == '-' || c == '.').to_string();
+            while let Some(stripped) = slug.strip_suffix(".lock") {
+                slug = stripped.trim_end_matches(['-', '.']).to_string();
+            }
+            slug
+        })
+        .filter(|component| !component.is_empty())
+        .collect();
+
+    let mut normalized = components.join("/");
+    if let Some(max_length) = max_length {
+        normalized.truncate(max_length);
+        normalized = normalized.trim_end_matches(['-', '.', '/']).to_string();
+    }
+    normalized
+}
+
+/// Validate a proposed branch name, normalizing it first when it breaks the rules.
+pub fn prepare_branch_name(
+    proposed: &str,
+    naming: &BranchNaming,
+) -> Result<String, BranchNameError> {
+    let problems: Vec<String> = ref_format_problems(proposed)
+        .into_iter()
+        .chain(naming.check(proposed))
+        .collect();
+    if problems.is_empty() {
+        return Ok(proposed.to_string());
+    }
+
+    let normalized = normalize(proposed, naming.max_length);
+    let remaining: Vec<String> = ref_format_problems(&normalized)
+        .into_iter()
+        .chain(naming.check(&normalized))
+        .collect();
+    if remaining.is_empty() {
+        Ok(normalized)
+    } else {
+        Err(BranchNameError {
+            proposed: proposed.to_string(),
+            normalized,
+            problems: remaining,
+        })
+    }
+}
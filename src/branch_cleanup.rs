@@ -0,0 +1,424 @@
+//! The `branch-cleanup` workflow: merged and stale branches found with git, proposed
+//! for deletion, and deleted by the assistant once `ExecuteBranchCleanup` is confirmed.
+
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The `branch_cleanup` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BranchCleanupConfig {
+    /// Branch merged branches are checked against
+    #[serde(default = "default_base_branch")]
+    pub base_branch: String,
+    /// Days without commits after which an unmerged branch counts as stale; unmerged
+    /// branches are never proposed when unset
+    #[serde(default = "default_stale_after_days")]
+    pub stale_after_days: Option<u64>,
+    /// Branch name patterns that are never deleted; `*` matches any run of characters
+    #[serde(default = "default_protected")]
+    pub protected: Vec<String>,
+    /// Remote whose branches are proposed as well; only local branches when unset
+    #[serde(default)]
+    pub remote: Option<String>,
+}
+
+fn default_base_branch() -> String {
+    "main".to_string()
+}
+
+fn default_stale_after_days() -> Option<u64> {
+    Some(90)
+}
+
+fn default_protected() -> Vec<String> {
+    ["main", "master", "develop", "release/*"]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect()
+}
+
+impl Default for BranchCleanupConfig {
+    fn default() -> Self {
+        Self {
+            base_branch: default_base_branch(),
+            stale_after_days: default_stale_after_days(),
+            protected: default_protected(),
+            remote: None,
+        }
+    }
+}
+
+impl BranchCleanupConfig {
+    /// Whether `branch`, a local branch name or one without its remote, is protected.
+    /// The base branch always is.
+    pub fn is_protected(&self, branch: &str) -> bool {
+        branch == self.base_branch
+            || self
+                .protected
+                .iter()
+                .any(|pattern| matches_pattern(pattern, branch))
+    }
+}
+
+/// `name` against a pattern where `*` matches any run of characters, `/` included.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // No `*`: the whole name must match
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Why a branch is proposed for deletion.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum CleanupReason {
+    /// Every commit is on the base branch
+    Merged,
+    /// No commits for `days` days
+    Stale { days: u64 },
+}
+
+/// A branch proposed for deletion.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BranchCandidate {
+    /// Local branch name, or `<remote>/<branch>` for a remote branch
+    pub branch: String,
+    /// Remote of a remote branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    /// Commit the branch pointed at when it was proposed
+    pub sha: String,
+    pub reason: CleanupReason,
+}
+
+impl BranchCandidate {
+    /// The branch name on its remote, or the local name.
+    pub fn name_on_remote(&self) -> &str {
+        match &self.remote {
+            Some(remote) => self
+                .branch
+                .strip_prefix(remote.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+                .unwrap_or(&self.branch),
+            None => &self.branch,
+        }
+    }
+}
+
+/// A branch that couldn't be deleted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeletionFailure {
+    pub branch: String,
+    pub error: String,
+}
+
+/// The proposal of a `branch-cleanup` workflow and what has been deleted so far.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BranchCleanup {
+    pub candidates: Vec<BranchCandidate>,
+    #[serde(default)]
+    pub deleted: Vec<String>,
+    #[serde(default)]
+    pub failures: Vec<DeletionFailure>,
+}
+
+/// A branch as listed by `git for-each-ref`.
+#[derive(Debug, Clone, PartialEq)]
+struct BranchRef {
+    branch: String,
+    remote: Option<String>,
+    sha: String,
+    committed_at: u64,
+}
+
+/// Merged and stale branches, oldest commit first. Staleness is measured from `now`,
+/// or from the newest branch tip without it. Protected branches, the checked-out
+/// branch, and the base branch are never proposed.
+pub fn find_candidates(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    config: &BranchCleanupConfig,
+    now: Option<u64>,
+) -> Result<Vec<BranchCandidate>, String> {
+    let mut patterns = vec!["refs/heads".to_string()];
+    if let Some(remote) = &config.remote {
+        patterns.push(format!("refs/remotes/{}", remote));
+    }
+    let mut args = vec![
+        "for-each-ref",
+        "--format=%(refname)%1f%(objectname)%1f%(committerdate:unix)",
+    ];
+    args.extend(patterns.iter().map(String::as_str));
+    let refs = parse_refs(&run_git(git_actor_id, directory, &args)?);
+
+    let mut merged = run_git(
+        git_actor_id,
+        directory,
+        &[
+            "for-each-ref",
+            "--format=%(refname)",
+            "--merged",
+            &config.base_branch,
+        ],
+    )?
+    .lines()
+    .filter_map(|line| short_name(line.trim()).map(|(branch, _)| branch))
+    .collect::<Vec<_>>();
+    merged.sort();
+
+    let current = run_git(git_actor_id, directory, &["branch", "--show-current"])?
+        .trim()
+        .to_string();
+    Ok(select(refs, &merged, &current, config, now))
+}
+
+/// `<refname> US <sha> US <unix time>` lines of `git for-each-ref`.
+fn parse_refs(output: &str) -> Vec<BranchRef> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().split('\u{1f}');
+            let (branch, remote) = short_name(fields.next()?)?;
+            Some(BranchRef {
+                branch,
+                remote,
+                sha: fields.next()?.to_string(),
+                committed_at: fields.next()?.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Short name and remote of a branch ref; `None` for other refs and `<remote>/HEAD`.
+fn short_name(refname: &str) -> Option<(String, Option<String>)> {
+    if let Some(branch) = refname.strip_prefix("refs/heads/") {
+        return Some((branch.to_string(), None));
+    }
+    let branch = refname.strip_prefix("refs/remotes/")?;
+    let (remote, name) = branch.split_once('/')?;
+    (name != "HEAD").then(|| (branch.to_string(), Some(remote.to_string())))
+}
+
+fn select(
+    refs: Vec<BranchRef>,
+    merged: &[String],
+    current: &str,
+    config: &BranchCleanupConfig,
+    now: Option<u64>,
+) -> Vec<BranchCandidate> {
+    let now = now.or_else(|| refs.iter().map(|branch| branch.committed_at).max());
+    let mut candidates: Vec<(u64, BranchCandidate)> = refs
+        .into_iter()
+        .filter_map(|branch| {
+            let candidate = BranchCandidate {
+                branch: branch.branch,
+                remote: branch.remote,
+                sha: branch.sha,
+                reason: CleanupReason::Merged,
+            };
+            if (candidate.remote.is_none() && candidate.branch == current)
+                || config.is_protected(candidate.name_on_remote())
+            {
+                return None;
+            }
+            let reason = if merged.binary_search(&candidate.branch).is_ok() {
+                CleanupReason::Merged
+            } else {
+                let days = now?.saturating_sub(branch.committed_at) / SECONDS_PER_DAY;
+                if days < config.stale_after_days? {
+                    return None;
+                }
+                CleanupReason::Stale { days }
+            };
+            Some((
+                branch.committed_at,
+                BranchCandidate {
+                    reason,
+                    ..candidate
+                },
+            ))
+        })
+        .collect();
+    candidates.sort_by_key(|(committed_at, _)| *committed_at);
+    candidates
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Block for the opening message listing the proposed deletions.
+pub fn describe_candidates(config: &BranchCleanupConfig, candidates: &[BranchCandidate]) -> String {
+    let mut block = format!(
+        "\n\nBRANCH CLEANUP: Base branch {}; protected: {}.",
+        config.base_branch,
+        config.protected.join(", ")
+    );
+    if candidates.is_empty() {
+        block.push_str(
+            "\nNo branch is merged or stale. Tell me there is nothing to clean up and use the \
+            task_complete tool.",
+        );
+        return block;
+    }
+    block.push_str(&format!("\nProposed for deletion ({}):", candidates.len()));
+    for candidate in candidates {
+        let reason = match &candidate.reason {
+            CleanupReason::Merged => format!("merged into {}", config.base_branch),
+            CleanupReason::Stale { days } => format!("no commits for {} days", days),
+        };
+        block.push_str(&format!(
+            "\n- {} {} ({})",
+            candidate.branch,
+            &candidate.sha[..candidate.sha.len().min(7)],
+            reason
+        ));
+    }
+    block
+}
+
+/// Delete `candidate`: `git branch -D` for a local branch, a push deleting it on its
+/// remote otherwise.
+pub fn delete(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    candidate: &BranchCandidate,
+) -> Result<(), String> {
+    match &candidate.remote {
+        Some(remote) => run_git(
+            git_actor_id,
+            directory,
+            &["push", remote, "--delete", candidate.name_on_remote()],
+        ),
+        None => run_git(
+            git_actor_id,
+            directory,
+            &["branch", "-D", &candidate.branch],
+        ),
+    }
+    .map(|_| ())
+}
+
+/// Message telling the model what was deleted, so it can wrap up.
+pub fn outcome_message(cleanup: &BranchCleanup) -> String {
+    let mut message = String::from("The branch cleanup was carried out.");
+    if !cleanup.deleted.is_empty() {
+        message.push_str(&format!("\nDeleted: {}", cleanup.deleted.join(", ")));
+    }
+    for failure in &cleanup.failures {
+        message.push_str(&format!(
+            "\nNot deleted: {} ({})",
+            failure.branch, failure.error
+        ));
+    }
+    let kept: Vec<&str> = cleanup
+        .candidates
+        .iter()
+        .map(|candidate| candidate.branch.as_str())
+        .filter(|branch| {
+            !cleanup.deleted.iter().any(|deleted| deleted == branch)
+                && !cleanup
+                    .failures
+                    .iter()
+                    .any(|failure| failure.branch == *branch)
+        })
+        .collect();
+    if !kept.is_empty() {
+        message.push_str(&format!("\nKept: {}", kept.join(", ")));
+    }
+    message.push_str("\nSummarize the cleanup and use the task_complete tool.");
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branch(name: &str, remote: Option<&str>, committed_at: u64) -> BranchRef {
+        BranchRef {
+            branch: name.to_string(),
+            remote: remote.map(str::to_string),
+            sha: format!("{:040}", committed_at),
+            committed_at,
+        }
+    }
+
+    #[test]
+    fn patterns_match_whole_names() {
+        assert!(matches_pattern("release/*", "release/1.2"));
+        assert!(matches_pattern("*-keep", "feature-keep"));
+        assert!(matches_pattern("team/*/main", "team/payments/main"));
+        assert!(!matches_pattern("main", "main-old"));
+        assert!(!matches_pattern("release/*", "hotfix/release/1.2"));
+    }
+
+    #[test]
+    fn merged_and_stale_branches_are_proposed_oldest_first() {
+        let config = BranchCleanupConfig {
+            remote: Some("origin".to_string()),
+            ..BranchCleanupConfig::default()
+        };
+        let day = SECONDS_PER_DAY;
+        let refs = vec![
+            branch("main", None, 200 * day),
+            branch("feature/done", None, 150 * day),
+            branch("feature/old", None, 20 * day),
+            branch("feature/new", None, 190 * day),
+            branch("current", None, 10 * day),
+            branch("origin/release/1.0", Some("origin"), 5 * day),
+            branch("origin/feature/done", Some("origin"), 150 * day),
+        ];
+        let merged = vec![
+            "feature/done".to_string(),
+            "main".to_string(),
+            "origin/feature/done".to_string(),
+        ];
+        let candidates = select(refs, &merged, "current", &config, Some(200 * day));
+        let proposed: Vec<(&str, &CleanupReason)> = candidates
+            .iter()
+            .map(|candidate| (candidate.branch.as_str(), &candidate.reason))
+            .collect();
+        assert_eq!(
+            proposed,
+            vec![
+                ("feature/old", &CleanupReason::Stale { days: 180 }),
+                ("feature/done", &CleanupReason::Merged),
+                ("origin/feature/done", &CleanupReason::Merged),
+            ]
+        );
+        assert_eq!(candidates[2].name_on_remote(), "feature/done");
+    }
+
+    #[test]
+    fn remote_head_and_other_refs_are_skipped() {
+        let refs = parse_refs(
+            "refs/heads/topic\u{1f}abc\u{1f}100\n\
+            refs/remotes/origin/HEAD\u{1f}def\u{1f}100\n\
+            refs/tags/v1\u{1f}123\u{1f}100\n",
+        );
+        assert_eq!(refs, vec![branch_with_sha("topic", "abc", 100)]);
+    }
+
+    fn branch_with_sha(name: &str, sha: &str, committed_at: u64) -> BranchRef {
+        BranchRef {
+            sha: sha.to_string(),
+            ..branch(name, None, committed_at)
+        }
+    }
+}
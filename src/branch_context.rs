@@ -0,0 +1,76 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The `branch_context` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BranchContextConfig {
+    /// Regex matched against the current branch name at init; each named group that
+    /// matches becomes a value, e.g. `(?P<ticket>[A-Z]+-\d+)-(?P<feature>.+)`
+    #[serde(default = "default_pattern")]
+    pub pattern: String,
+}
+
+fn default_pattern() -> String {
+    r"(?P<ticket>[A-Z][A-Z0-9]+-\d+)".to_string()
+}
+
+impl Default for BranchContextConfig {
+    fn default() -> Self {
+        Self {
+            pattern: default_pattern(),
+        }
+    }
+}
+
+/// Values taken from the branch name, by group name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BranchContext {
+    pub branch: String,
+    pub values: BTreeMap<String, String>,
+}
+
+impl BranchContextConfig {
+    /// Match the pattern against `branch`. Returns `None` when nothing was captured,
+    /// e.g. on `main`.
+    pub fn extract(&self, branch: &str) -> Result<Option<BranchContext>, String> {
+        let regex = Regex::new(&self.pattern)
+            .map_err(|e| format!("invalid branch context pattern {}: {}", self.pattern, e))?;
+        let captures = match regex.captures(branch) {
+            Some(captures) => captures,
+            None => return Ok(None),
+        };
+        let values: BTreeMap<String, String> = regex
+            .capture_names()
+            .flatten()
+            .filter_map(|name| {
+                let value = captures.name(name)?.as_str();
+                (!value.is_empty()).then(|| (name.to_string(), value.to_string()))
+            })
+            .collect();
+        if values.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(BranchContext {
+            branch: branch.to_string(),
+            values,
+        }))
+    }
+}
+
+impl BranchContext {
+    pub fn prompt_fragment(&self) -> String {
+        let mut fragment = format!(
+            "\n\nBRANCH CONTEXT: The current branch is {}. From its name:",
+            self.branch
+        );
+        for (name, value) in &self.values {
+            fragment.push_str(&format!("\n- {}: {}", name, value));
+        }
+        fragment.push_str(
+            "\nReference these in commit messages, pull request descriptions, and \
+            summaries where the conventions allow, without asking the user for them.",
+        );
+        fragment
+    }
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// The assistant build, reported at init, by `Describe`, and in every response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BuildInfo {
+    /// Crate version
+    pub version: String,
+    /// Abbreviated commit the component was built from, or `unknown`
+    pub commit: String,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: env!("GIT_COMMIT").to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.version, self.commit)
+    }
+}
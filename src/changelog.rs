@@ -0,0 +1,88 @@
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+
+/// How commits are grouped in the changelog entry.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangelogGrouping {
+    /// By conventional commit type (feat, fix, ...)
+    #[default]
+    Type,
+    /// By conventional commit scope
+    Scope,
+    /// A single flat list
+    None,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangelogFormat {
+    #[default]
+    Markdown,
+    /// <https://keepachangelog.com> sections (Added, Changed, Fixed, ...)
+    KeepAChangelog,
+}
+
+/// The `changelog` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChangelogConfig {
+    /// Glob release tags match, as accepted by `git describe --match` (e.g. `"v*"`)
+    pub tag_pattern: Option<String>,
+    #[serde(default)]
+    pub grouping: ChangelogGrouping,
+    #[serde(default)]
+    pub format: ChangelogFormat,
+}
+
+impl ChangelogConfig {
+    pub fn prompt_fragment(&self) -> String {
+        let mut fragment = String::from("\n\nCHANGELOG SETTINGS:");
+        if let Some(tag_pattern) = &self.tag_pattern {
+            fragment.push_str(&format!(
+                "\n- Release tags match: {} (use git describe --tags --abbrev=0 --match '{}')",
+                tag_pattern, tag_pattern
+            ));
+        }
+        fragment.push_str(match self.grouping {
+            ChangelogGrouping::Type => {
+                "\n- Group entries by commit type (Features, Bug Fixes, Performance, \
+                Documentation, Other), using the conventional commit prefix when present"
+            }
+            ChangelogGrouping::Scope => {
+                "\n- Group entries by commit scope, with unscoped commits under General"
+            }
+            ChangelogGrouping::None => "\n- List entries in a single list, newest first",
+        });
+        fragment.push_str(match self.format {
+            ChangelogFormat::Markdown => {
+                "\n- Format: a markdown section headed by the version and date, with one \
+                bullet per change"
+            }
+            ChangelogFormat::KeepAChangelog => {
+                "\n- Format: Keep a Changelog (https://keepachangelog.com), a \
+                ## [version] - YYYY-MM-DD heading with Added, Changed, Deprecated, Removed, \
+                Fixed, and Security subsections; omit empty subsections"
+            }
+        });
+        fragment
+    }
+}
+
+/// The most recent release tag reachable from `HEAD`, `None` when there is none.
+pub fn fetch_last_tag(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    config: &ChangelogConfig,
+) -> Result<Option<String>, String> {
+    let mut args = vec!["describe", "--tags", "--abbrev=0"];
+    if let Some(tag_pattern) = &config.tag_pattern {
+        args.push("--match");
+        args.push(tag_pattern);
+    }
+    match run_git(git_actor_id, directory, &args) {
+        Ok(tag) => Ok(Some(tag.trim().to_string()).filter(|tag| !tag.is_empty())),
+        // git describe fails when no tag matches
+        Err(e) if e.contains("No names found") || e.contains("cannot describe") => Ok(None),
+        Err(e) => Err(e),
+    }
+}
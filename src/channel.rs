@@ -0,0 +1,292 @@
+//! Framed protocol for Theater channels.
+//!
+//! A channel lets a client follow the assistant's events as they happen and send
+//! requests over the same connection. Every channel message carries one or more frames,
+//! one JSON object per line, each in the versioned envelope of the external protocol.
+//!
+//! The frames the assistant sends belong to a stream that outlives the channel. They
+//! are numbered with `seq` and kept until the client acks them, so a client that loses
+//! its channel can open a new one with `Resume` and receive what it missed.
+//!
+//! Besides plain requests, a client can type into a session with `Input` and answer
+//! approval prompts with `Approval`, which makes a channel enough for an interactive
+//! client: replies, tool activity, approval prompts, and workflow progress come as
+//! events.
+
+use crate::protocol::external::{
+    Envelope, EventKind, GitChatEvent, GitChatRequest, GitChatResponse, PROTOCOL_VERSION,
+};
+use genai_types::messages::Role;
+use genai_types::{Message, MessageContent};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// The `channels` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChannelConfig {
+    /// Frames kept per stream until acked; older ones are dropped
+    #[serde(default = "default_max_unacked_frames")]
+    pub max_unacked_frames: usize,
+    /// Streams kept after their channel closed, for clients to resume
+    #[serde(default = "default_max_detached_streams")]
+    pub max_detached_streams: usize,
+}
+
+fn default_max_unacked_frames() -> usize {
+    256
+}
+
+fn default_max_detached_streams() -> usize {
+    8
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            max_unacked_frames: default_max_unacked_frames(),
+            max_detached_streams: default_max_detached_streams(),
+        }
+    }
+}
+
+/// Frames a client sends on a channel.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum ClientFrame {
+    /// Start a new stream. `events` picks the event kinds, like `Subscribe`; all kinds
+    /// when empty
+    Open {
+        #[serde(default)]
+        events: Vec<EventKind>,
+    },
+    /// Continue `stream_id` on this channel, after the frame numbered `last_seq`
+    Resume { stream_id: String, last_seq: u64 },
+    /// Every frame up to `seq` arrived
+    Ack { seq: u64 },
+    /// A request, numbered by the client. A request resent after a reconnect is only
+    /// handled once
+    Request { seq: u64, request: GitChatRequest },
+    /// Text the user typed, numbered like a request and answered with the response to
+    /// `AddMessage`
+    Input {
+        seq: u64,
+        text: String,
+        /// The default session when absent
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    /// Answer to an `ApprovalRequested` event, numbered like a request
+    Approval {
+        seq: u64,
+        confirmation_id: String,
+        approved: bool,
+    },
+    /// Replace the event kinds the stream gets; all kinds when empty
+    Subscribe {
+        #[serde(default)]
+        events: Vec<EventKind>,
+    },
+}
+
+impl ClientFrame {
+    /// The request a numbered frame stands for, with its `seq`. Input is sent as the
+    /// stream's own client, so a session claimed with `AcquireTurn` by the stream id
+    /// takes it.
+    pub fn request(self, stream_id: &str) -> Option<(u64, GitChatRequest)> {
+        match self {
+            ClientFrame::Request { seq, request } => Some((seq, request)),
+            ClientFrame::Input {
+                seq,
+                text,
+                session_id,
+            } => Some((
+                seq,
+                GitChatRequest::AddMessage {
+                    message: Message {
+                        role: Role::User,
+                        content: vec![MessageContent::Text { text }],
+                    },
+                    session_id,
+                    client: Some(stream_id.to_string()),
+                    overrides: None,
+                },
+            )),
+            ClientFrame::Approval {
+                seq,
+                confirmation_id,
+                approved,
+            } => {
+                let id = confirmation_id;
+                let request = if approved {
+                    GitChatRequest::Confirm { id }
+                } else {
+                    GitChatRequest::RejectConfirmation { id }
+                };
+                Some((seq, request))
+            }
+            ClientFrame::Open { .. }
+            | ClientFrame::Resume { .. }
+            | ClientFrame::Ack { .. }
+            | ClientFrame::Subscribe { .. } => None,
+        }
+    }
+}
+
+/// Frames the assistant sends on a channel.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum ServerFrame {
+    /// Answer to `Open` and `Resume`. `missed` counts frames after `last_seq` that were
+    /// dropped before they were acked
+    Welcome {
+        stream_id: String,
+        next_seq: u64,
+        #[serde(default)]
+        missed: u64,
+    },
+    Event {
+        seq: u64,
+        event: GitChatEvent,
+        /// When the event was published, in seconds since the epoch
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        at: Option<u64>,
+    },
+    /// Answer to the client's request numbered `request_seq`
+    Response {
+        seq: u64,
+        request_seq: u64,
+        response: GitChatResponse,
+    },
+    /// A frame that couldn't be handled; not numbered, and not replayed
+    Error { message: String },
+}
+
+impl ServerFrame {
+    fn seq(&self) -> Option<u64> {
+        match self {
+            ServerFrame::Event { seq, .. } | ServerFrame::Response { seq, .. } => Some(*seq),
+            ServerFrame::Welcome { .. } | ServerFrame::Error { .. } => None,
+        }
+    }
+}
+
+/// The frames of one client, kept across its channels.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Stream {
+    /// Channel the stream is sent on; unset once it closed
+    pub channel_id: Option<String>,
+    pub events: BTreeSet<EventKind>,
+    pub next_seq: u64,
+    /// Sent frames the client hasn't acked, oldest first
+    pub unacked: VecDeque<ServerFrame>,
+    /// Highest `seq` dropped before it was acked
+    pub dropped_through: u64,
+    /// Highest request `seq` handled
+    pub last_request_seq: u64,
+}
+
+impl Stream {
+    pub fn new(channel_id: &str, events: Vec<EventKind>) -> Self {
+        Self {
+            channel_id: Some(channel_id.to_string()),
+            events: events.into_iter().collect(),
+            next_seq: 1,
+            unacked: VecDeque::new(),
+            dropped_through: 0,
+            last_request_seq: 0,
+        }
+    }
+
+    pub fn wants(&self, kind: EventKind) -> bool {
+        kind.wanted_by(&self.events)
+    }
+
+    /// Number the frame built by `frame` and keep it until it's acked.
+    pub fn push(
+        &mut self,
+        max_unacked_frames: usize,
+        frame: impl FnOnce(u64) -> ServerFrame,
+    ) -> ServerFrame {
+        let frame = frame(self.next_seq);
+        self.next_seq += 1;
+        while !self.unacked.is_empty() && self.unacked.len() >= max_unacked_frames {
+            if let Some(seq) = self.unacked.pop_front().and_then(|frame| frame.seq()) {
+                self.dropped_through = seq;
+            }
+        }
+        if max_unacked_frames > 0 {
+            self.unacked.push_back(frame.clone());
+        } else {
+            self.dropped_through = self.next_seq - 1;
+        }
+        frame
+    }
+
+    pub fn ack(&mut self, seq: u64) {
+        self.unacked
+            .retain(|frame| frame.seq().is_none_or(|frame_seq| frame_seq > seq));
+    }
+
+    /// Acks `last_seq` and returns the welcome for a resume, followed by the frames
+    /// still kept after it.
+    pub fn resume(&mut self, stream_id: &str, last_seq: u64) -> Vec<ServerFrame> {
+        self.ack(last_seq);
+        let mut frames = vec![ServerFrame::Welcome {
+            stream_id: stream_id.to_string(),
+            next_seq: self.next_seq,
+            missed: self.dropped_through.saturating_sub(last_seq),
+        }];
+        frames.extend(self.unacked.iter().cloned());
+        frames
+    }
+}
+
+/// Drop the oldest streams whose channel closed once there are more than
+/// `max_detached_streams`. Stream ids are `stream-<n>`, numbered in order.
+pub fn prune_detached(streams: &mut BTreeMap<String, Stream>, max_detached_streams: usize) {
+    let mut detached: Vec<(u64, String)> = streams
+        .iter()
+        .filter(|(_, stream)| stream.channel_id.is_none())
+        .map(|(stream_id, _)| {
+            let number = stream_id
+                .strip_prefix("stream-")
+                .and_then(|number| number.parse().ok())
+                .unwrap_or(0);
+            (number, stream_id.clone())
+        })
+        .collect();
+    detached.sort();
+    let excess = detached.len().saturating_sub(max_detached_streams);
+    for (_, stream_id) in detached.into_iter().take(excess) {
+        streams.remove(&stream_id);
+    }
+}
+
+/// Parse the frames of a channel message, one per line.
+pub fn decode_frames(bytes: &[u8]) -> Result<Vec<ClientFrame>, String> {
+    let text = std::str::from_utf8(bytes).map_err(|e| format!("Failed to read frames: {}", e))?;
+    let mut frames = Vec::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let envelope: Envelope<ClientFrame> =
+            serde_json::from_str(line).map_err(|e| format!("Failed to parse frame: {}", e))?;
+        if envelope.v != PROTOCOL_VERSION {
+            return Err(format!(
+                "Unsupported protocol version {} (expected {})",
+                envelope.v, PROTOCOL_VERSION
+            ));
+        }
+        frames.push(envelope.body);
+    }
+    Ok(frames)
+}
+
+/// Serialize frames into one channel message, one per line.
+pub fn encode_frames(frames: &[ServerFrame]) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for frame in frames {
+        serde_json::to_writer(&mut bytes, &Envelope::new(frame))
+            .map_err(|e| format!("Failed to serialize frame: {}", e))?;
+        bytes.push(b'\n');
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,464 @@
+//! The `cherry-pick` workflow: the user names commits or a branch in the `StartChat`
+//! args, the model plans the order to pick them in, and the assistant picks them one
+//! at a time, handing each conflicted pick to the model to resolve.
+
+use crate::git_tools::{ensure_revision, run_git};
+use crate::repo_state;
+use crate::sanitize::{self, TextKind};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Where the commits to pick come from, from `StartChat { args }`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CherryPickSource {
+    /// `{"commits": ["a1b2c3d", "v1.2.0~1"]}`
+    Commits(Vec<String>),
+    /// `{"branch": "feature"}`: the branch's commits that aren't on `HEAD` yet
+    Branch(String),
+}
+
+impl CherryPickSource {
+    pub fn from_args(args: Option<&Value>) -> Result<Self, String> {
+        let empty = Map::new();
+        let args = args.and_then(Value::as_object).unwrap_or(&empty);
+        let source = match (args.get("commits"), args.get("branch")) {
+            (Some(Value::Array(commits)), None) if !commits.is_empty() => commits
+                .iter()
+                .map(|commit| match commit.as_str().map(str::trim) {
+                    Some(commit) if !commit.is_empty() => Ok(commit.to_string()),
+                    _ => Err("commits must be a list of refs".to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(CherryPickSource::Commits)?,
+            (None, Some(Value::String(branch))) if !branch.trim().is_empty() => {
+                CherryPickSource::Branch(branch.trim().to_string())
+            }
+            _ => {
+                return Err(
+                    "the cherry-pick workflow needs either commits or a branch, e.g. args: \
+                    {\"commits\": [\"a1b2c3d\"]} or {\"branch\": \"feature\"}"
+                        .to_string(),
+                )
+            }
+        };
+        let refs = match &source {
+            CherryPickSource::Commits(commits) => commits.clone(),
+            CherryPickSource::Branch(branch) => vec![branch.clone()],
+        };
+        for git_ref in &refs {
+            ensure_revision("ref", git_ref)?;
+        }
+        Ok(source)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            CherryPickSource::Commits(commits) => commits.join(", "),
+            CherryPickSource::Branch(branch) => format!("branch {}", branch),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CherryPickPhase {
+    /// Waiting for the model's plan
+    Planning,
+    /// Picking the planned commits
+    Picking,
+    /// A pick stopped on conflicts and waits for them to be resolved
+    Conflicted,
+    Finished,
+    /// git refused a pick for a reason other than conflicts, in `error`
+    Failed,
+    Aborted,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PickStatus {
+    Pending,
+    Picked,
+    Skipped,
+    Conflicted,
+}
+
+/// One commit of the cherry-pick and how far it got.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PickedCommit {
+    pub sha: String,
+    pub subject: String,
+    pub status: PickStatus,
+    /// Sha of the new commit, once picked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub picked_sha: Option<String>,
+    /// Why the commit was skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The cherry-pick of the `cherry-pick` workflow, kept after it finishes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CherryPickState {
+    pub phase: CherryPickPhase,
+    /// The commits or branch from the args
+    pub source: String,
+    /// In the planned order once the plan is in
+    pub commits: Vec<PickedCommit>,
+    /// Why the last plan was rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One entry of the model's plan.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct PlannedPick {
+    pub sha: String,
+    #[serde(default)]
+    pub skip: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Where a pick, or its continuation after conflicts, left the repository.
+pub enum PickOutcome {
+    Picked {
+        sha: String,
+    },
+    Conflicted {
+        files: Vec<String>,
+    },
+    /// The changes are already on `HEAD`
+    Empty,
+}
+
+fn parse_commits(output: &str) -> Result<Vec<PickedCommit>, String> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (sha, subject) = line
+                .trim()
+                .split_once('\u{1f}')
+                .ok_or_else(|| format!("Unexpected git log output: {}", line))?;
+            Ok(PickedCommit {
+                sha: sha.to_string(),
+                subject: subject.to_string(),
+                status: PickStatus::Pending,
+                picked_sha: None,
+                reason: None,
+            })
+        })
+        .collect()
+}
+
+/// The commits to pick, oldest first for a branch. Commits of the branch whose changes
+/// are already on `HEAD` are left out.
+pub fn fetch_commits(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    source: &CherryPickSource,
+) -> Result<Vec<PickedCommit>, String> {
+    let commits = match source {
+        CherryPickSource::Commits(refs) => {
+            let mut commits = Vec::new();
+            for git_ref in refs {
+                let output = run_git(
+                    git_actor_id,
+                    directory,
+                    &["log", "-1", "--format=%H%x1f%s", git_ref],
+                )?;
+                commits.extend(parse_commits(&output)?);
+            }
+            commits
+        }
+        CherryPickSource::Branch(branch) => {
+            let range = format!("HEAD...{}", branch);
+            let output = run_git(
+                git_actor_id,
+                directory,
+                &[
+                    "log",
+                    "--reverse",
+                    "--no-merges",
+                    "--cherry-pick",
+                    "--right-only",
+                    "--format=%H%x1f%s",
+                    &range,
+                ],
+            )?;
+            parse_commits(&output)?
+        }
+    };
+    if commits.is_empty() {
+        return Err(format!("{} has nothing to pick", source.describe()));
+    }
+    Ok(commits)
+}
+
+/// Block for the workflow's opening message: the commits and the plan to reply with.
+pub fn describe(state: &CherryPickState) -> String {
+    let mut text = format!(
+        "\n\nCOMMITS TO CHERRY-PICK from {}:",
+        sanitize::escape(&state.source, TextKind::Name)
+    );
+    for commit in &state.commits {
+        text.push_str(&format!(
+            "\n- {} {}",
+            commit.sha,
+            sanitize::escape(&commit.subject, TextKind::Message)
+        ));
+    }
+    text.push_str(
+        "\n\nReply with the plan in a ```json block: {\"picks\": [{\"sha\": \"<sha>\"}, \
+        {\"sha\": \"<sha>\", \"skip\": true, \"reason\": \"<why>\"}]}, listing every commit \
+        above exactly once, in the order to pick them.",
+    );
+    text
+}
+
+/// The plan in the last ```json block of the reply, if there is one.
+pub fn parse_plan(reply: &str) -> Option<Result<Vec<PlannedPick>, String>> {
+    #[derive(Deserialize)]
+    struct Plan {
+        picks: Vec<PlannedPick>,
+    }
+    let block = reply.split("```json").skip(1).last()?;
+    let json = block.split("```").next().unwrap_or_default();
+    Some(
+        serde_json::from_str::<Plan>(json.trim())
+            .map(|plan| plan.picks)
+            .map_err(|e| format!("invalid plan: {}", e)),
+    )
+}
+
+impl CherryPickState {
+    /// Put the commits in the planned order and mark the planned skips. Each commit
+    /// must be named exactly once, by its sha or an unambiguous prefix of it.
+    pub fn apply_plan(&mut self, plan: &[PlannedPick]) -> Result<(), String> {
+        let mut ordered = Vec::new();
+        for planned in plan {
+            let matching: Vec<&PickedCommit> = self
+                .commits
+                .iter()
+                .filter(|commit| planned.sha.len() >= 7 && commit.sha.starts_with(&planned.sha))
+                .collect();
+            let commit = match matching.as_slice() {
+                [commit] => commit,
+                [] => return Err(format!("{} is not one of the commits to pick", planned.sha)),
+                _ => return Err(format!("{} names more than one commit", planned.sha)),
+            };
+            if ordered
+                .iter()
+                .any(|picked: &PickedCommit| picked.sha == commit.sha)
+            {
+                return Err(format!("{} is planned twice", planned.sha));
+            }
+            let mut commit = (*commit).clone();
+            if planned.skip {
+                commit.status = PickStatus::Skipped;
+                commit.reason = Some(
+                    planned
+                        .reason
+                        .clone()
+                        .unwrap_or_else(|| "skipped in the plan".to_string()),
+                );
+            }
+            ordered.push(commit);
+        }
+        if let Some(missing) = self
+            .commits
+            .iter()
+            .find(|commit| !ordered.iter().any(|planned| planned.sha == commit.sha))
+        {
+            return Err(format!("{} is missing from the plan", missing.sha));
+        }
+        self.commits = ordered;
+        self.phase = CherryPickPhase::Picking;
+        self.error = None;
+        Ok(())
+    }
+
+    /// The commit to pick next.
+    pub fn next_pending(&mut self) -> Option<&mut PickedCommit> {
+        self.commits
+            .iter_mut()
+            .find(|commit| commit.status == PickStatus::Pending)
+    }
+
+    /// The pick waiting for its conflicts to be resolved.
+    pub fn conflicted(&mut self) -> Option<&mut PickedCommit> {
+        self.commits
+            .iter_mut()
+            .find(|commit| commit.status == PickStatus::Conflicted)
+    }
+
+    /// One line per commit, for the final summary.
+    pub fn summary(&self) -> String {
+        let count = |status| {
+            self.commits
+                .iter()
+                .filter(|commit| commit.status == status)
+                .count()
+        };
+        let mut summary = format!(
+            "Picked {} of {} commits, skipped {}",
+            count(PickStatus::Picked),
+            self.commits.len(),
+            count(PickStatus::Skipped)
+        );
+        let pending = count(PickStatus::Pending) + count(PickStatus::Conflicted);
+        if pending > 0 {
+            summary.push_str(&format!(", {} not picked", pending));
+        }
+        summary.push(':');
+        for commit in &self.commits {
+            let outcome = match (commit.status, &commit.picked_sha, &commit.reason) {
+                (PickStatus::Picked, Some(picked_sha), _) => format!("picked as {}", picked_sha),
+                (PickStatus::Skipped, _, Some(reason)) => format!("skipped ({})", reason),
+                (PickStatus::Pending | PickStatus::Conflicted, _, _) => "not picked".to_string(),
+                (status, _, _) => format!("{:?}", status).to_lowercase(),
+            };
+            summary.push_str(&format!(
+                "\n- {} {}: {}",
+                commit.sha, commit.subject, outcome
+            ));
+        }
+        summary
+    }
+}
+
+fn picked_head(git_actor_id: &str, directory: Option<&str>) -> Result<PickOutcome, String> {
+    let sha = run_git(git_actor_id, directory, &["rev-parse", "HEAD"])?;
+    Ok(PickOutcome::Picked {
+        sha: sha.trim().to_string(),
+    })
+}
+
+/// Why a pick or continuation that git refused stopped: conflicts, nothing left to
+/// commit, or a failure, after which the pick is aborted.
+fn stopped_pick(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    error: String,
+) -> Result<PickOutcome, String> {
+    let files = repo_state::conflicted_files(git_actor_id, directory)?;
+    if !files.is_empty() {
+        return Ok(PickOutcome::Conflicted { files });
+    }
+    if error.contains("empty") {
+        return Ok(PickOutcome::Empty);
+    }
+    let _ = run_git(git_actor_id, directory, &["cherry-pick", "--abort"]);
+    Err(error)
+}
+
+/// Pick `sha` onto `HEAD`, recording where it came from in the message.
+pub fn pick(git_actor_id: &str, directory: Option<&str>, sha: &str) -> Result<PickOutcome, String> {
+    match run_git(git_actor_id, directory, &["cherry-pick", "-x", sha]) {
+        Ok(_) => picked_head(git_actor_id, directory),
+        Err(e) => stopped_pick(git_actor_id, directory, e),
+    }
+}
+
+/// Commit a conflicted pick once its conflicts are resolved and staged.
+pub fn continue_pick(git_actor_id: &str, directory: Option<&str>) -> Result<PickOutcome, String> {
+    let files = repo_state::conflicted_files(git_actor_id, directory)?;
+    if !files.is_empty() {
+        return Ok(PickOutcome::Conflicted { files });
+    }
+    match run_git(
+        git_actor_id,
+        directory,
+        &["-c", "core.editor=true", "cherry-pick", "--continue"],
+    ) {
+        Ok(_) => picked_head(git_actor_id, directory),
+        Err(e) => stopped_pick(git_actor_id, directory, e),
+    }
+}
+
+/// Message handing a conflicted pick to the model.
+pub fn conflict_message(commit: &PickedCommit, files: &[String]) -> String {
+    format!(
+        "Cherry-picking {} \"{}\" stopped on conflicts in: {}. Explain what the commit \
+        changes and why it conflicts, then resolve each file and stage it with git add. \
+        Don't commit, and don't run git cherry-pick yourself; once nothing is left \
+        unresolved, the assistant continues the pick and moves on to the next commit.",
+        commit.sha,
+        sanitize::escape(&commit.subject, TextKind::Message),
+        files.join(", ")
+    )
+}
+
+/// Message asking the model to fix its plan.
+pub fn invalid_plan_message(error: &str) -> String {
+    format!(
+        "The cherry-pick plan can't be used: {}. Reply with a corrected plan in a ```json \
+        block, listing every commit to pick exactly once.",
+        error
+    )
+}
+
+/// Message asking the model to report on the finished cherry-pick.
+pub fn finished_message(state: &CherryPickState) -> String {
+    format!(
+        "The cherry-pick is finished. {}\n\nSummarize which commits were picked and which \
+        were skipped and why, then use the task_complete tool with that summary.",
+        state.summary()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn commit(sha: &str) -> PickedCommit {
+        PickedCommit {
+            sha: sha.to_string(),
+            subject: format!("Change {}", &sha[..1]),
+            status: PickStatus::Pending,
+            picked_sha: None,
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn the_plan_orders_and_skips_commits() {
+        let source = CherryPickSource::from_args(Some(&json!({"branch": "feature"}))).unwrap();
+        assert_eq!(source, CherryPickSource::Branch("feature".to_string()));
+        assert!(CherryPickSource::from_args(Some(&json!({"commits": []}))).is_err());
+        assert!(CherryPickSource::from_args(Some(&json!({"commits": ["--all"]}))).is_err());
+
+        let mut state = CherryPickState {
+            phase: CherryPickPhase::Planning,
+            source: source.describe(),
+            commits: vec![
+                commit("aaaaaaaa11"),
+                commit("bbbbbbbb22"),
+                commit("cccccccc33"),
+            ],
+            error: None,
+        };
+        let reply = "Plan:\n```json\n{\"picks\": [{\"sha\": \"cccccccc\"}, \
+            {\"sha\": \"aaaaaaaa\", \"skip\": true, \"reason\": \"already fixed\"}, \
+            {\"sha\": \"bbbbbbbb22\"}]}\n```";
+        let plan = parse_plan(reply).unwrap().unwrap();
+        assert_eq!(parse_plan("No plan yet"), None);
+        assert!(state.apply_plan(&plan[..2]).is_err());
+        state.apply_plan(&plan).unwrap();
+        assert_eq!(state.commits[0].sha, "cccccccc33");
+        assert_eq!(state.commits[1].status, PickStatus::Skipped);
+        assert_eq!(state.next_pending().unwrap().sha, "cccccccc33");
+
+        state.commits[0].status = PickStatus::Picked;
+        state.commits[0].picked_sha = Some("dddddddd44".to_string());
+        assert_eq!(
+            state.summary(),
+            "Picked 1 of 3 commits, skipped 1, 1 not picked:\n\
+            - cccccccc33 Change c: picked as dddddddd44\n\
+            - aaaaaaaa11 Change a: skipped (already fixed)\n\
+            - bbbbbbbb22 Change b: not picked"
+        );
+    }
+}
@@ -0,0 +1,9 @@
+//! The host clock. Timestamps are seconds since the epoch, like those of `Tick`.
+//! `Tick` still drives scheduled work; the clock only tells the time.
+
+use crate::bindings::theater::simple::timing;
+
+/// Seconds since the epoch.
+pub fn now() -> u64 {
+    timing::now() / 1000
+}
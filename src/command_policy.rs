@@ -0,0 +1,162 @@
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Git subcommands the git MCP actor exposes as tools of their own, named
+/// `git_<subcommand>` with dashes as underscores. Its generic `git` tool runs any
+/// subcommand, so it is only offered when nothing is restricted.
+const GIT_SUBCOMMAND_TOOLS: &[&str] = &[
+    "status",
+    "log",
+    "diff",
+    "show",
+    "blame",
+    "add",
+    "commit",
+    "push",
+    "pull",
+    "fetch",
+    "branch",
+    "checkout",
+    "switch",
+    "merge",
+    "rebase",
+    "reset",
+    "restore",
+    "stash",
+    "tag",
+    "remote",
+    "cherry-pick",
+    "revert",
+    "bisect",
+];
+
+/// The git subcommand a git MCP tool call runs: `git_cherry_pick` runs `cherry-pick`,
+/// and the generic `git` tool its first argument that isn't an option.
+pub fn tool_subcommand(name: &str, args: Option<&Value>) -> Option<String> {
+    if name == "git" {
+        return args?
+            .get("args")?
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|arg| !arg.starts_with('-'))
+            .map(str::to_string);
+    }
+    let tool = name.strip_prefix("git_")?.replace('_', "-");
+    GIT_SUBCOMMAND_TOOLS
+        .contains(&tool.as_str())
+        .then_some(tool)
+}
+
+/// Allowed and denied git subcommands.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CommandRules {
+    /// Subcommands the model may run; every subcommand when unset
+    pub allowed: Option<Vec<String>>,
+    /// Subcommands the model may never run, even when allowed
+    #[serde(default)]
+    pub denied: Vec<String>,
+}
+
+/// The `command_policy` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CommandPolicy {
+    #[serde(flatten)]
+    pub rules: CommandRules,
+    /// Rules for single workflows, keyed by workflow name. They narrow the top-level
+    /// rules: a subcommand must be allowed by both and denied by neither
+    #[serde(default)]
+    pub workflows: BTreeMap<String, CommandRules>,
+}
+
+impl CommandPolicy {
+    /// Whether the model may run `git <subcommand>` in `workflow`.
+    pub fn permits(&self, workflow: Option<&Workflow>, subcommand: &str) -> bool {
+        self.rules_for(workflow)
+            .iter()
+            .all(|rules| rules.permits(subcommand))
+    }
+
+    /// Whether any rule applies to `workflow`.
+    pub fn restricts(&self, workflow: Option<&Workflow>) -> bool {
+        self.rules_for(workflow)
+            .iter()
+            .any(|rules| rules.allowed.is_some() || !rules.denied.is_empty())
+    }
+
+    /// The git MCP tools the model gets in `workflow`, or `None` for all of them.
+    pub fn git_tools(&self, workflow: Option<&Workflow>) -> Option<Vec<String>> {
+        if !self.restricts(workflow) {
+            return None;
+        }
+        Some(
+            GIT_SUBCOMMAND_TOOLS
+                .iter()
+                .filter(|subcommand| self.permits(workflow, subcommand))
+                .map(|subcommand| format!("git_{}", subcommand.replace('-', "_")))
+                .collect(),
+        )
+    }
+
+    /// System prompt rule for `workflow`, or `None` when nothing is restricted.
+    pub fn prompt_fragment(&self, workflow: Option<&Workflow>) -> Option<String> {
+        if !self.restricts(workflow) {
+            return None;
+        }
+        let mut fragment =
+            String::from("\n\nGIT COMMAND POLICY: Your git tools are limited for this task.");
+        let allowed: Vec<&str> = GIT_SUBCOMMAND_TOOLS
+            .iter()
+            .copied()
+            .filter(|subcommand| self.permits(workflow, subcommand))
+            .collect();
+        if self
+            .rules_for(workflow)
+            .iter()
+            .any(|rules| rules.allowed.is_some())
+        {
+            fragment.push_str(&format!(
+                " You may only run these git subcommands: {}.",
+                allowed.join(", ")
+            ));
+        }
+        let denied: Vec<&str> = self
+            .rules_for(workflow)
+            .iter()
+            .flat_map(|rules| rules.denied.iter().map(String::as_str))
+            .collect();
+        if !denied.is_empty() {
+            fragment.push_str(&format!(
+                " Never run these git subcommands: {}.",
+                denied.join(", ")
+            ));
+        }
+        fragment.push_str(
+            " Don't try to get around this with other tools; if the task needs a \
+            subcommand you can't run, ask the user to run it.",
+        );
+        Some(fragment)
+    }
+
+    fn rules_for(&self, workflow: Option<&Workflow>) -> Vec<&CommandRules> {
+        let mut rules = vec![&self.rules];
+        if let Some(workflow_rules) =
+            workflow.and_then(|workflow| self.workflows.get(workflow.name()))
+        {
+            rules.push(workflow_rules);
+        }
+        rules
+    }
+}
+
+impl CommandRules {
+    fn permits(&self, subcommand: &str) -> bool {
+        let allowed = self
+            .allowed
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|allowed| allowed == subcommand));
+        allowed && !self.denied.iter().any(|denied| denied == subcommand)
+    }
+}
@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Author, committer, and date overrides for commits the assistant creates.
+///
+/// People are given as `"Name <email>"`; dates in any format git accepts
+/// (e.g. `"2024-01-31T12:00:00+01:00"`).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CommitIdentity {
+    pub author: Option<String>,
+    pub committer: Option<String>,
+    pub date: Option<String>,
+}
+
+impl CommitIdentity {
+    pub fn is_empty(&self) -> bool {
+        self.author.is_none() && self.committer.is_none() && self.date.is_none()
+    }
+
+    /// The `author` and `committer` fields that aren't `"Name <email>"`, with their
+    /// values; the config check reports them.
+    pub fn malformed_people(&self) -> Vec<(&'static str, &str)> {
+        [("author", &self.author), ("committer", &self.committer)]
+            .into_iter()
+            .filter_map(|(field, person)| Some((field, person.as_deref()?)))
+            .filter(|(_, person)| split_person(person).is_none())
+            .collect()
+    }
+
+    /// Arguments that must accompany every `git commit` invocation. The committer has
+    /// no flag and is set by `commit_env` alone.
+    pub fn commit_args(&self) -> Vec<String> {
+        let mut args = vec!["commit".to_string()];
+        if let Some(author) = &self.author {
+            args.push(format!("--author={}", author));
+        }
+        if let Some(date) = &self.date {
+            args.push(format!("--date={}", date));
+        }
+        args
+    }
+
+    /// Environment the git tools should apply to commit invocations. Covers the parts
+    /// that have no command-line flag, such as the committer date.
+    pub fn commit_env(&self) -> Value {
+        let mut env = Map::new();
+        if let Some((name, email)) = self.author.as_deref().and_then(split_person) {
+            env.insert("GIT_AUTHOR_NAME".to_string(), Value::from(name));
+            env.insert("GIT_AUTHOR_EMAIL".to_string(), Value::from(email));
+        }
+        if let Some((name, email)) = self.committer.as_deref().and_then(split_person) {
+            env.insert("GIT_COMMITTER_NAME".to_string(), Value::from(name));
+            env.insert("GIT_COMMITTER_EMAIL".to_string(), Value::from(email));
+        }
+        if let Some(date) = &self.date {
+            env.insert("GIT_AUTHOR_DATE".to_string(), Value::from(date.as_str()));
+            env.insert("GIT_COMMITTER_DATE".to_string(), Value::from(date.as_str()));
+        }
+        Value::Object(env)
+    }
+
+    pub fn prompt_fragment(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut fragment = String::from(
+            "\n\nCOMMIT IDENTITY:\nAll commits must be created with these exact overrides:",
+        );
+        if let Some(author) = &self.author {
+            fragment.push_str(&format!("\n- Author: {}", author));
+        }
+        if let Some(committer) = &self.committer {
+            fragment.push_str(&format!("\n- Committer: {}", committer));
+        }
+        if let Some(date) = &self.date {
+            fragment.push_str(&format!("\n- Date: {}", date));
+        }
+        fragment.push_str(&format!(
+            "\nInvoke commits as: git {} -m <message>. Never change these values.",
+            self.commit_args().join(" ")
+        ));
+        fragment
+    }
+}
+
+/// Instructions for the `require_signoff` and `gpg_sign` configs.
+pub fn signing_fragment(require_signoff: bool, gpg_sign: bool) -> String {
+    let mut fragment = String::from("\n\nCOMMIT SIGNING:");
+    if require_signoff {
+        fragment.push_str(
+            "\n- Every commit needs a Signed-off-by trailer: pass -s (--signoff) to git commit.",
+        );
+    }
+    if gpg_sign {
+        fragment.push_str(
+            "\n- Every commit must be GPG-signed: pass -S (--gpg-sign) to git commit. If \
+            signing fails, stop and report it; never fall back to an unsigned commit.",
+        );
+    }
+    fragment.push_str("\nCommits made without them are checked after the workflow and fail it.");
+    fragment
+}
+
+/// Split `"Name <email>"` into its parts.
+fn split_person(person: &str) -> Option<(&str, &str)> {
+    let (name, rest) = person.split_once('<')?;
+    let email = rest.trim_end().strip_suffix('>')?;
+    let (name, email) = (name.trim(), email.trim());
+    (!name.is_empty() && !email.is_empty() && !email.contains(['<', '>'])).then_some((name, email))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn people_are_set_once_and_malformed_ones_reported() {
+        let identity = CommitIdentity {
+            author: Some("Ada Lovelace <ada@example.com>".to_string()),
+            committer: Some("Release Bot <bot@example.com>".to_string()),
+            date: None,
+        };
+        assert!(identity.malformed_people().is_empty());
+        assert_eq!(
+            identity.commit_args(),
+            vec!["commit", "--author=Ada Lovelace <ada@example.com>"]
+        );
+        assert_eq!(
+            identity.commit_env(),
+            json!({
+                "GIT_AUTHOR_NAME": "Ada Lovelace",
+                "GIT_AUTHOR_EMAIL": "ada@example.com",
+                "GIT_COMMITTER_NAME": "Release Bot",
+                "GIT_COMMITTER_EMAIL": "bot@example.com",
+            })
+        );
+
+        let identity = CommitIdentity {
+            author: Some("Ada Lovelace".to_string()),
+            committer: Some("<bot@example.com>".to_string()),
+            date: None,
+        };
+        assert_eq!(
+            identity.malformed_people(),
+            vec![
+                ("author", "Ada Lovelace"),
+                ("committer", "<bot@example.com>")
+            ]
+        );
+    }
+}
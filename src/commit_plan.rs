@@ -0,0 +1,181 @@
+//! Commit splitting in the commit workflow: with `commit_split` set, the model first
+//! proposes how to group the pending changes into commits, in a `commit-plan` block, and
+//! only commits once a client approves the plan, as proposed or edited, with
+//! `ApproveCommitPlan`.
+
+use crate::spelling::{Spellchecker, SpellingIssue};
+use serde::{Deserialize, Serialize};
+
+/// One commit of a plan.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlannedCommit {
+    pub message: String,
+    pub files: Vec<String>,
+    /// Hunks of `files` that go in this commit when a file is split across commits, as
+    /// `<path> @@ -a,b +c,d @@`; the whole files otherwise
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hunks: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommitPlan {
+    /// In the order they are to be made
+    pub commits: Vec<PlannedCommit>,
+    /// Set once `ApproveCommitPlan` approved it
+    #[serde(default)]
+    pub approved: bool,
+    /// Probable misspellings in the messages, with `spellcheck` set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub misspellings: Vec<PlanMisspelling>,
+}
+
+/// A probable misspelling in the message of one of a plan's commits.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlanMisspelling {
+    /// 0-based index of the commit in the plan
+    pub commit: usize,
+    #[serde(flatten)]
+    pub issue: SpellingIssue,
+}
+
+/// Rule of the commit workflow's prompt when `commit_split` is set.
+pub const PROMPT_FRAGMENT: &str = "\n\nCOMMIT PLAN: Before you stage or commit anything, \
+    split the pending changes into focused commits and reply with the plan in a \
+    ```commit-plan block holding JSON: {\"commits\": [{\"message\": \"...\", \"files\": \
+    [\"...\"], \"hunks\": [\"<path> @@ -a,b +c,d @@\"]}]}. List hunks only for files split \
+    across commits. Then stop and wait: you will be told when the plan is approved, possibly \
+    edited. Make exactly the approved commits, in order, with their messages.";
+
+/// The plan in the last `commit-plan` block of `reply`, if it has one.
+pub fn parse(reply: &str) -> Option<Result<CommitPlan, String>> {
+    let block = reply
+        .split("```commit-plan")
+        .skip(1)
+        .filter_map(|block| block.split("```").next())
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+        .last()?;
+    Some(
+        serde_json::from_str::<CommitPlan>(block)
+            .map_err(|e| format!("the commit plan is not valid JSON: {}", e))
+            .and_then(|mut plan| {
+                // Only a client approves a plan
+                plan.approved = false;
+                plan.misspellings.clear();
+                plan.validate().map(|()| plan)
+            }),
+    )
+}
+
+impl CommitPlan {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.commits.is_empty() {
+            return Err("the commit plan has no commits".to_string());
+        }
+        for (index, commit) in self.commits.iter().enumerate() {
+            if commit.message.trim().is_empty() {
+                return Err(format!("commit {} of the plan has no message", index + 1));
+            }
+            if commit.files.is_empty() && commit.hunks.is_empty() {
+                return Err(format!("commit {} of the plan has no files", index + 1));
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the probable misspellings `checker` finds in the messages.
+    pub fn spellcheck(&mut self, checker: &Spellchecker) {
+        self.misspellings = self
+            .commits
+            .iter()
+            .enumerate()
+            .flat_map(|(commit, planned)| {
+                checker
+                    .check(&planned.message)
+                    .into_iter()
+                    .map(move |issue| PlanMisspelling { commit, issue })
+            })
+            .collect();
+    }
+
+    /// The plan as a numbered list, for the model.
+    pub fn describe(&self) -> String {
+        let mut text = String::new();
+        for (index, commit) in self.commits.iter().enumerate() {
+            text.push_str(&format!(
+                "\n{}. {}\n   Files: {}",
+                index + 1,
+                commit.message.trim(),
+                commit.files.join(", ")
+            ));
+            if !commit.hunks.is_empty() {
+                text.push_str(&format!("\n   Hunks: {}", commit.hunks.join(", ")));
+            }
+        }
+        text
+    }
+}
+
+/// Message telling the model the plan was approved.
+pub fn approved_message(plan: &CommitPlan, edited: bool) -> String {
+    format!(
+        "The commit plan was approved{}. Make these {} commits now, in this order, with \
+        these messages:{}\n\nThen use the task_complete tool.",
+        if edited { " with changes" } else { "" },
+        plan.commits.len(),
+        plan.describe()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_last_plan_block_is_parsed_and_checked() {
+        let reply = "Here is the split:\n```commit-plan\n{\"commits\": [\
+            {\"message\": \"fix(parser): handle tabs\", \"files\": [\"src/parser.rs\"]},\
+            {\"message\": \"docs: mention tabs\", \"files\": [\"README.md\"], \
+            \"hunks\": [\"README.md @@ -10,2 +10,3 @@\"]}], \"approved\": true}\n```\nApprove it?";
+        let plan = parse(reply).unwrap().unwrap();
+        assert_eq!(plan.commits.len(), 2);
+        assert!(!plan.approved);
+        assert_eq!(
+            plan.describe(),
+            "\n1. fix(parser): handle tabs\n   Files: src/parser.rs\n\
+            2. docs: mention tabs\n   Files: README.md\n   Hunks: README.md @@ -10,2 +10,3 @@"
+        );
+
+        assert_eq!(parse("No plan yet."), None);
+        assert!(parse("```commit-plan\n{\"commits\": []}\n```")
+            .unwrap()
+            .is_err());
+        assert!(
+            parse("```commit-plan\n{\"commits\": [{\"message\": \"x\", \"files\": []}]}\n```")
+                .unwrap()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn misspellings_are_recorded_per_commit() {
+        let reply = "```commit-plan\n{\"commits\": [\
+            {\"message\": \"fix: handle tabs\", \"files\": [\"src/parser.rs\"]},\
+            {\"message\": \"docs: recieve tabs\", \"files\": [\"README.md\"]}], \
+            \"misspellings\": [{\"commit\": 0, \"word\": \"x\", \"line\": 1, \"suggestion\": \"y\"}]}\n```";
+        let mut plan = parse(reply).unwrap().unwrap();
+        assert!(plan.misspellings.is_empty());
+        plan.spellcheck(&Spellchecker::new(&Default::default(), &[]));
+        assert_eq!(
+            plan.misspellings,
+            vec![PlanMisspelling {
+                commit: 1,
+                issue: SpellingIssue {
+                    word: "recieve".to_string(),
+                    line: 1,
+                    suggestion: "receive".to_string(),
+                },
+            }]
+        );
+    }
+}
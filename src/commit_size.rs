@@ -0,0 +1,147 @@
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+
+/// What happens when changes exceed the commit size limits.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitSizeAction {
+    /// Split the changes into several commits that fit the limits.
+    #[default]
+    Split,
+    /// Ask the user before creating a commit over the limits.
+    Ask,
+    /// Never create a commit over the limits.
+    Block,
+}
+
+/// The `commit_size` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CommitSizeLimits {
+    pub max_files: Option<usize>,
+    pub max_added_lines: Option<u64>,
+    #[serde(default)]
+    pub on_exceed: CommitSizeAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChangeSize {
+    pub files: usize,
+    pub added_lines: u64,
+}
+
+impl ChangeSize {
+    /// Sum the output of `git diff --numstat` (binary files count as files only).
+    pub fn from_numstat(numstat: &str) -> Self {
+        let mut size = ChangeSize::default();
+        for line in numstat.lines() {
+            let mut fields = line.split('\t');
+            let (added, _deleted, path) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(added), Some(deleted), Some(path)) => (added, deleted, path),
+                _ => continue,
+            };
+            if path.is_empty() {
+                continue;
+            }
+            size.files += 1;
+            size.added_lines += added.parse::<u64>().unwrap_or(0);
+        }
+        size
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} files, {} added lines", self.files, self.added_lines)
+    }
+}
+
+impl CommitSizeLimits {
+    /// Limits `size` is over, empty when it fits.
+    pub fn exceeded(&self, size: &ChangeSize) -> Vec<String> {
+        let mut exceeded = Vec::new();
+        if let Some(max_files) = self.max_files {
+            if size.files > max_files {
+                exceeded.push(format!("{} files (limit {})", size.files, max_files));
+            }
+        }
+        if let Some(max_added_lines) = self.max_added_lines {
+            if size.added_lines > max_added_lines {
+                exceeded.push(format!(
+                    "{} added lines (limit {})",
+                    size.added_lines, max_added_lines
+                ));
+            }
+        }
+        exceeded
+    }
+
+    /// Whether commits over the limits are sent back to the model to split.
+    pub fn is_enforced(&self) -> bool {
+        self.on_exceed != CommitSizeAction::Ask
+    }
+
+    pub fn prompt_fragment(&self) -> String {
+        let mut limits = Vec::new();
+        if let Some(max_files) = self.max_files {
+            limits.push(format!("at most {} files", max_files));
+        }
+        if let Some(max_added_lines) = self.max_added_lines {
+            limits.push(format!("at most {} added lines", max_added_lines));
+        }
+        if limits.is_empty() {
+            return String::new();
+        }
+        format!(
+            "\n\nCOMMIT SIZE LIMITS: Every commit may touch {}. {}",
+            limits.join(" and "),
+            match self.on_exceed {
+                CommitSizeAction::Split => {
+                    "When the changes are larger, plan a split into several logical commits \
+                    that each fit the limits before committing anything."
+                }
+                CommitSizeAction::Ask => {
+                    "Before creating a commit over these limits, stop and ask me whether to \
+                    split it or commit it as is."
+                }
+                CommitSizeAction::Block => {
+                    "Never create a commit over these limits; if the changes cannot be split \
+                    to fit, stop and explain why."
+                }
+            }
+        )
+    }
+}
+
+/// Size of the uncommitted changes in the working tree.
+pub fn fetch_worktree_size(
+    git_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<ChangeSize, String> {
+    let numstat = run_git(git_actor_id, directory, &["diff", "--numstat", "HEAD"])?;
+    Ok(ChangeSize::from_numstat(&numstat))
+}
+
+/// Size of every commit after `base`, or only `HEAD` without a base, keyed by sha.
+pub fn fetch_commit_sizes(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: Option<&str>,
+) -> Result<Vec<(String, ChangeSize)>, String> {
+    let format = "--format=%x1e%H";
+    let range = base.map(|base| format!("{}..HEAD", base));
+    let args = match &range {
+        Some(range) => vec!["log", "--numstat", format, range.as_str()],
+        None => vec!["log", "-1", "--numstat", format],
+    };
+    let output = run_git(git_actor_id, directory, &args)?;
+
+    Ok(output
+        .split('\u{1e}')
+        .filter_map(|record| {
+            let (sha, numstat) = record.split_once('\n').unwrap_or((record, ""));
+            let sha = sha.trim();
+            if sha.is_empty() {
+                return None;
+            }
+            Some((sha.to_string(), ChangeSize::from_numstat(numstat)))
+        })
+        .collect())
+}
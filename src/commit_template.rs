@@ -0,0 +1,113 @@
+//! Commit message templates with `{{name}}` placeholders, filled from `template_vars`
+//! and values the user sends with `SetTemplateVars`.
+
+use std::collections::BTreeMap;
+
+/// A `{{name}}` placeholder, or `{{name?}}` for one that may stay empty.
+struct Placeholder<'a> {
+    /// The placeholder as written, braces included
+    text: &'a str,
+    name: &'a str,
+    optional: bool,
+}
+
+/// Placeholders in `text`, in order.
+fn placeholders(text: &str) -> Vec<Placeholder<'_>> {
+    let mut found = Vec::new();
+    let mut rest = text;
+    let mut offset = 0;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end + 2,
+            None => break,
+        };
+        let inner = rest[start + 2..end - 2].trim();
+        let (name, optional) = match inner.strip_suffix('?') {
+            Some(name) => (name, true),
+            None => (inner, false),
+        };
+        let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if valid {
+            found.push(Placeholder {
+                text: &text[offset + start..offset + end],
+                name,
+                optional,
+            });
+        }
+        offset += end;
+        rest = &rest[end..];
+    }
+    found
+}
+
+/// Names of the placeholders in `template` that must have a value, in order.
+pub fn required_placeholders(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for placeholder in placeholders(template) {
+        if !placeholder.optional && !names.iter().any(|name| name == placeholder.name) {
+            names.push(placeholder.name.to_string());
+        }
+    }
+    names
+}
+
+/// Required placeholders of `template` without a value in `values`.
+pub fn missing_values(template: &str, values: &BTreeMap<String, String>) -> Vec<String> {
+    required_placeholders(template)
+        .into_iter()
+        .filter(|name| values.get(name).is_none_or(|value| value.trim().is_empty()))
+        .collect()
+}
+
+/// `template` with the placeholders that have a value filled in. Optional placeholders
+/// without one are dropped, along with lines they leave empty; required ones stay as
+/// they are.
+pub fn render(template: &str, values: &BTreeMap<String, String>) -> String {
+    let mut lines = Vec::new();
+    for line in template.lines() {
+        let mut rendered = line.to_string();
+        let mut dropped = false;
+        for placeholder in placeholders(line) {
+            match values.get(placeholder.name) {
+                Some(value) => rendered = rendered.replacen(placeholder.text, value, 1),
+                None if placeholder.optional => {
+                    rendered = rendered.replacen(placeholder.text, "", 1);
+                    dropped = true;
+                }
+                None => {}
+            }
+        }
+        if dropped && rendered.trim().is_empty() {
+            continue;
+        }
+        lines.push(rendered);
+    }
+    lines.join("\n")
+}
+
+/// System prompt rule for `template`, rendered with the values known at init.
+pub fn prompt_fragment(template: &str, values: &BTreeMap<String, String>) -> String {
+    format!(
+        "\n\nCOMMIT MESSAGE TEMPLATE: Write every commit message following this template. \
+        Text in angle brackets describes what to write there; everything else is copied as \
+        it is. Placeholders like {{{{name}}}} are filled in with the values given in the \
+        task; never invent a value or leave a placeholder in a message.\n{}",
+        render(template, values)
+    )
+}
+
+/// Problems with `message` against `template`, empty when it fills every placeholder.
+pub fn check(template: &str, values: &BTreeMap<String, String>, message: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    for placeholder in placeholders(message) {
+        problems.push(format!("unfilled placeholder {}", placeholder.text));
+    }
+    for name in required_placeholders(template) {
+        if let Some(value) = values.get(&name).filter(|value| !value.trim().is_empty()) {
+            if !message.contains(value.as_str()) {
+                problems.push(format!("missing the {} value \"{}\"", name, value));
+            }
+        }
+    }
+    problems
+}
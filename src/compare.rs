@@ -0,0 +1,174 @@
+use crate::git_tools::run_git;
+use crate::sanitize::{self, TextKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Commits read from each checkout when looking for shared history.
+const HISTORY_DEPTH: &str = "--max-count=2000";
+/// Recent subjects gathered for the conventions comparison.
+const RECENT_SUBJECTS: &str = "-20";
+
+/// What a `CompareRepos` request compares.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompareAspect {
+    /// How far a fork has diverged and where the histories meet
+    Divergence,
+    /// Commit message, branch, and tag conventions
+    Conventions,
+    /// Top-level layout and size of the trees
+    Structure,
+}
+
+/// Facts gathered from one checkout.
+#[derive(Debug, Clone, Default)]
+struct RepoSnapshot {
+    branch: String,
+    head: String,
+    commit_count: String,
+    remotes: String,
+    history: Vec<String>,
+    subjects: String,
+    tags: String,
+    top_level: String,
+    file_count: usize,
+}
+
+fn snapshot(
+    git_actor_id: &str,
+    directory: &str,
+    aspect: CompareAspect,
+) -> Result<RepoSnapshot, String> {
+    let git = |args: &[&str]| {
+        run_git(git_actor_id, Some(directory), args)
+            .map(|output| sanitize::escape(output.trim(), TextKind::Message))
+    };
+    let mut snapshot = RepoSnapshot {
+        branch: git(&["rev-parse", "--abbrev-ref", "HEAD"])?,
+        head: git(&["rev-parse", "HEAD"])?,
+        commit_count: git(&["rev-list", "--count", "HEAD"])?,
+        remotes: git(&["remote", "-v"])?,
+        ..RepoSnapshot::default()
+    };
+    match aspect {
+        CompareAspect::Divergence => {
+            snapshot.history = git(&["rev-list", HISTORY_DEPTH, "HEAD"])?
+                .lines()
+                .map(str::to_string)
+                .collect();
+        }
+        CompareAspect::Conventions => {
+            snapshot.subjects = git(&["log", RECENT_SUBJECTS, "--format=%s"])?;
+            snapshot.tags = git(&["tag", "--sort=-creatordate", "--list"])?
+                .lines()
+                .take(10)
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        CompareAspect::Structure => {
+            snapshot.top_level = git(&["ls-tree", "--name-only", "HEAD"])?;
+            snapshot.file_count = git(&["ls-files"])?.lines().count();
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Newest commit of `ours` that `theirs` also has, with the commits each side has on top.
+fn shared_point(ours: &[String], theirs: &[String]) -> Option<(String, usize, usize)> {
+    let their_commits: BTreeSet<&String> = theirs.iter().collect();
+    let (ahead, sha) = ours
+        .iter()
+        .enumerate()
+        .find(|(_, sha)| their_commits.contains(sha))?;
+    let behind = theirs.iter().position(|their| their == sha)?;
+    Some((sha.clone(), ahead, behind))
+}
+
+fn describe(
+    label: &str,
+    directory: &str,
+    snapshot: &RepoSnapshot,
+    aspect: CompareAspect,
+) -> String {
+    let mut section = format!(
+        "\n\n{} ({}):\n- Branch: {}\n- HEAD: {}\n- Commits: {}\n- Remotes:\n{}",
+        label,
+        sanitize::escape(directory, TextKind::Name),
+        snapshot.branch,
+        snapshot.head,
+        snapshot.commit_count,
+        if snapshot.remotes.is_empty() {
+            "(none)"
+        } else {
+            &snapshot.remotes
+        }
+    );
+    match aspect {
+        CompareAspect::Divergence => {}
+        CompareAspect::Conventions => {
+            section.push_str(&format!(
+                "\n- Recent commit subjects:\n{}\n- Recent tags:\n{}",
+                snapshot.subjects,
+                if snapshot.tags.is_empty() {
+                    "(none)"
+                } else {
+                    &snapshot.tags
+                }
+            ));
+        }
+        CompareAspect::Structure => {
+            section.push_str(&format!(
+                "\n- Tracked files: {}\n- Top-level entries:\n{}",
+                snapshot.file_count, snapshot.top_level
+            ));
+        }
+    }
+    section
+}
+
+/// Gather `aspect` from both checkouts and build the message asking the model to
+/// compare them.
+pub fn build_comparison_message(
+    git_actor_id: &str,
+    directory: &str,
+    other_directory: &str,
+    aspect: CompareAspect,
+) -> Result<String, String> {
+    let ours = snapshot(git_actor_id, directory, aspect)
+        .map_err(|e| format!("Failed to inspect {}: {}", directory, e))?;
+    let theirs = snapshot(git_actor_id, other_directory, aspect)
+        .map_err(|e| format!("Failed to inspect {}: {}", other_directory, e))?;
+
+    let mut message = format!(
+        "Compare these two checkouts ({}). The facts below were gathered with git; use \
+        your git tools on either directory if you need more detail.",
+        match aspect {
+            CompareAspect::Divergence => "divergence and shared history",
+            CompareAspect::Conventions => "conventions",
+            CompareAspect::Structure => "structure",
+        }
+    );
+    message.push_str(&describe("REPOSITORY A", directory, &ours, aspect));
+    message.push_str(&describe("REPOSITORY B", other_directory, &theirs, aspect));
+
+    if aspect == CompareAspect::Divergence {
+        message.push_str("\n\nSHARED HISTORY:\n");
+        match shared_point(&ours.history, &theirs.history) {
+            Some((sha, ahead, behind)) => message.push_str(&format!(
+                "- Newest shared commit: {}\n- A has {} commits on top of it, B has {}",
+                sha, ahead, behind
+            )),
+            None => message.push_str(&format!(
+                "- No shared commit in the latest {} commits of each checkout",
+                HISTORY_DEPTH.trim_start_matches("--max-count=")
+            )),
+        }
+    }
+
+    message.push_str(
+        "\n\nReply with a structured comparison using these sections: \
+        ## Summary, ## Shared History, ## Differences (a table with one row per difference \
+        and columns for A and B), and ## Recommendations. Do not modify either repository.",
+    );
+    Ok(message)
+}
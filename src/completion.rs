@@ -0,0 +1,206 @@
+//! Completion detection: the criteria a workflow completes on, checked after each
+//! generation of the default session. Without the task monitor, the model signals
+//! completion by ending its reply with a sentinel block.
+
+use crate::git_tools::run_git;
+use crate::protocol::ChildNotification;
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const COMPLETE_OPEN: &str = "<task_complete>";
+const COMPLETE_CLOSE: &str = "</task_complete>";
+const FAILED_OPEN: &str = "<task_failed>";
+const FAILED_CLOSE: &str = "</task_failed>";
+
+/// How the assistant learns that the model has finished its task.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompletionDetector {
+    /// The task monitor's `task_complete` tool
+    #[default]
+    TaskMonitor,
+    /// A `<task_complete>` block at the end of the model's reply
+    Sentinel,
+}
+
+/// Something that marks a workflow as complete. A workflow completes on the first of
+/// its criteria that is met.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum CompletionCriterion {
+    /// The model's own signal, through `detector`
+    Signal,
+    /// The model's reply ends with `phrase`
+    Phrase { phrase: String },
+    /// The repository is in `state`
+    RepoState { state: RepoCondition },
+    /// The workflow has taken `turns` turns
+    Turns { turns: u32 },
+}
+
+/// Repository states a workflow can complete on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RepoCondition {
+    /// No changes, staged or not, and no untracked files
+    CleanWorktree,
+    /// Nothing left in the index
+    NoStagedChanges,
+    /// HEAD moved since the workflow started
+    NewCommits,
+}
+
+impl RepoCondition {
+    /// Whether the repository is in this state. `commit_base` is HEAD when the
+    /// workflow started.
+    pub fn holds(
+        self,
+        git_actor_id: &str,
+        directory: Option<&str>,
+        commit_base: Option<&str>,
+    ) -> Result<bool, String> {
+        match self {
+            RepoCondition::CleanWorktree => {
+                let status = run_git(git_actor_id, directory, &["status", "--porcelain"])?;
+                Ok(status.trim().is_empty())
+            }
+            RepoCondition::NoStagedChanges => {
+                let status = run_git(git_actor_id, directory, &["status", "--porcelain"])?;
+                Ok(!status
+                    .lines()
+                    .any(|line| !line.starts_with(' ') && !line.starts_with('?')))
+            }
+            RepoCondition::NewCommits => {
+                let base = match commit_base {
+                    Some(base) => base,
+                    None => return Ok(false),
+                };
+                let head = run_git(git_actor_id, directory, &["rev-parse", "HEAD"])?;
+                Ok(head.trim() != base)
+            }
+        }
+    }
+}
+
+impl CompletionCriterion {
+    /// Whether checking the criterion needs the model's latest reply.
+    pub fn reads_reply(&self, sentinel: bool) -> bool {
+        match self {
+            CompletionCriterion::Signal => sentinel,
+            CompletionCriterion::Phrase { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// The criterion as a condition, for logs and the system prompt.
+    pub fn describe(&self) -> String {
+        match self {
+            CompletionCriterion::Signal => "you signal that the task is complete".to_string(),
+            CompletionCriterion::Phrase { phrase } => {
+                format!("your reply ends with \"{}\"", phrase)
+            }
+            CompletionCriterion::RepoState { state } => match state {
+                RepoCondition::CleanWorktree => "the working tree is clean".to_string(),
+                RepoCondition::NoStagedChanges => "no changes are staged".to_string(),
+                RepoCondition::NewCommits => "a new commit has been made".to_string(),
+            },
+            CompletionCriterion::Turns { turns } => format!("you have taken {} turns", turns),
+        }
+    }
+}
+
+/// The `completion` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CompletionConfig {
+    #[serde(default)]
+    pub detector: CompletionDetector,
+    /// Criteria of every workflow; the model's signal alone when empty
+    #[serde(default)]
+    pub criteria: Vec<CompletionCriterion>,
+    /// Criteria of single workflows, keyed by workflow name, replacing `criteria`
+    #[serde(default)]
+    pub workflows: BTreeMap<String, Vec<CompletionCriterion>>,
+}
+
+impl CompletionConfig {
+    /// The criteria `workflow` completes on.
+    pub fn criteria_for(&self, workflow: &Workflow) -> Vec<CompletionCriterion> {
+        match self.workflows.get(workflow.name()) {
+            Some(criteria) => criteria.clone(),
+            None if self.criteria.is_empty() => vec![CompletionCriterion::Signal],
+            None => self.criteria.clone(),
+        }
+    }
+}
+
+/// The rest of `reply` when it ends with `phrase`.
+pub fn strip_phrase(reply: &str, phrase: &str) -> Option<String> {
+    let phrase = phrase.trim();
+    if phrase.is_empty() {
+        return None;
+    }
+    reply
+        .trim_end()
+        .strip_suffix(phrase)
+        .map(|rest| rest.trim().to_string())
+}
+
+/// The notification a sentinel block at the end of `reply` stands for, if any. Only
+/// the last block counts, and only when nothing but whitespace follows it, so a
+/// sentinel the model quotes along the way isn't taken for the end of the task.
+pub fn parse(reply: &str) -> Option<ChildNotification> {
+    let reply = reply.trim_end();
+    if let Some(summary) = last_block(reply, COMPLETE_OPEN, COMPLETE_CLOSE) {
+        return Some(ChildNotification::TaskComplete {
+            summary: Some(summary).filter(|summary| !summary.is_empty()),
+        });
+    }
+    if let Some(reason) = last_block(reply, FAILED_OPEN, FAILED_CLOSE) {
+        return Some(ChildNotification::TaskFailed { reason });
+    }
+    None
+}
+
+/// Trimmed text between `open` and `close` when `reply` ends with that block.
+fn last_block(reply: &str, open: &str, close: &str) -> Option<String> {
+    let body = reply.strip_suffix(close)?;
+    let start = body.rfind(open)?;
+    Some(body[start + open.len()..].trim().to_string())
+}
+
+/// System prompt rule for criteria other than the model's signal, if there are any.
+pub fn criteria_fragment(criteria: &[CompletionCriterion]) -> Option<String> {
+    if criteria
+        .iter()
+        .all(|criterion| *criterion == CompletionCriterion::Signal)
+    {
+        return None;
+    }
+    let conditions = criteria
+        .iter()
+        .map(CompletionCriterion::describe)
+        .collect::<Vec<_>>()
+        .join("; or ");
+    Some(format!(
+        "\n\nCOMPLETION: The task counts as complete as soon as {}. Make sure the work is \
+        done before you get there.",
+        conditions
+    ))
+}
+
+/// System prompt rule telling the model how to signal the end of its task.
+pub fn prompt_fragment(workflow_active: bool) -> &'static str {
+    if workflow_active {
+        "\n\nIMPORTANT: When you have completed your assigned task, you MUST end your final \
+        reply with a summary of the work between <task_complete> and </task_complete>, with \
+        nothing after it. There is no task_complete tool: wherever you are told to call or use \
+        task_complete, end your reply this way instead, with the summary it asks for. If you \
+        can't complete the task, end your reply with the reason between <task_failed> and \
+        </task_failed>. Don't write either block before the work is done."
+    } else {
+        "\n\nNOTE: When the user explicitly asks you to complete a specific task, or when you \
+        finish a well-defined piece of work, end your reply with a summary between \
+        <task_complete> and </task_complete>."
+    }
+}
@@ -0,0 +1,702 @@
+//! Validation of the initial config, so typos and out-of-range values are reported
+//! instead of silently replaced by defaults.
+
+use crate::artifacts::ArtifactsConfig;
+use crate::backoff::BackoffConfig;
+use crate::bisect::BisectConfig;
+use crate::branch::BranchNaming;
+use crate::branch_cleanup::BranchCleanupConfig;
+use crate::branch_context::BranchContextConfig;
+use crate::changelog::ChangelogConfig;
+use crate::channel::ChannelConfig;
+use crate::command_policy::CommandPolicy;
+use crate::commit::CommitIdentity;
+use crate::commit_size::CommitSizeLimits;
+use crate::completion::CompletionConfig;
+use crate::confirmation::ConfirmationPolicy;
+use crate::context::DiffBudget;
+use crate::convention::CommitConvention;
+use crate::crash_loop::CrashLoopPolicy;
+use crate::dead_man_switch::DeadManSwitch;
+use crate::delegation::Sibling;
+use crate::diff_summary::DiffSummaryConfig;
+use crate::digest::DigestConfig;
+use crate::divergence::DivergenceWatch;
+use crate::exec_tools::run_command;
+use crate::fan_out::FanOutConfig;
+use crate::forge::ForgeConfig;
+use crate::history::PerformanceMode;
+use crate::hook_review::HookReviewConfig;
+use crate::http::HttpConfig;
+use crate::large_files::LargeFilePolicy;
+use crate::locale::LanguageConfig;
+use crate::logging::{LogRedaction, LoggingConfig};
+use crate::mcp_servers;
+use crate::model_fallback::ModelConfig;
+use crate::model_preset::{self, ModelPreset};
+use crate::outbound::GenerationOverlap;
+use crate::pipeline::WorkflowSpec;
+use crate::postcondition::Postcondition;
+use crate::postprocess::PostProcessConfig;
+use crate::pr_description::PrDescriptionConfig;
+use crate::prompts::PromptConfig;
+use crate::release::ReleaseConfig;
+use crate::repo_state::DirtyWorktreePolicy;
+use crate::repo_stats::RepoStatsConfig;
+use crate::review::ReviewConfig;
+use crate::schedule::ScheduleEntry;
+use crate::scope;
+use crate::secret_scan::SecretScanConfig;
+use crate::spelling::SpellcheckConfig;
+use crate::state_codec::StateFormat;
+use crate::state_limits::StateLimits;
+use crate::submodule::SubmoduleConfig;
+use crate::sync::SyncConfig;
+use crate::ticket::TicketConfig;
+use crate::tool_output::ToolOutputPolicy;
+use crate::turn_limit::{TurnLimits, WorkflowTimeouts};
+use crate::usage::Budget;
+use crate::verification::VerificationConfig;
+use crate::webhook::NotificationsConfig;
+use crate::workflow::Workflow;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The config cannot be used; init fails
+    Error,
+    /// The config is used, but probably not as intended
+    Warning,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConfigDiagnostic {
+    /// Path of the offending field, e.g. `mcp_servers[0].actor.manifest_path`
+    pub field: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ConfigDiagnostic {
+    pub fn error(field: &str, message: String) -> Self {
+        Self {
+            field: field.to_string(),
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    pub fn warning(field: &str, message: String) -> Self {
+        Self {
+            field: field.to_string(),
+            severity: Severity::Warning,
+            message,
+        }
+    }
+}
+
+type FieldCheck = fn(&Value) -> Result<(), String>;
+
+fn parses<T: DeserializeOwned>(value: &Value) -> Result<(), String> {
+    serde_json::from_value::<T>(value.clone())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Every top-level field the assistant reads, with the type it must parse as. Other
+/// fields are passed through to the chat-state actor.
+const FIELDS: &[(&str, FieldCheck)] = &[
+    ("current_directory", parses::<Option<String>>),
+    ("worktrees", parses::<Vec<String>>),
+    ("workflow", parses::<Option<WorkflowSpec>>),
+    ("task", parses::<Option<WorkflowSpec>>),
+    (
+        "dirty_worktree_policy",
+        parses::<Option<DirtyWorktreePolicy>>,
+    ),
+    ("commit_identity", parses::<Option<CommitIdentity>>),
+    ("require_signoff", parses::<bool>),
+    ("gpg_sign", parses::<bool>),
+    ("commit_convention", parses::<Option<CommitConvention>>),
+    ("commit_template", parses::<Option<String>>),
+    ("language", parses::<Option<LanguageConfig>>),
+    ("template_vars", parses::<BTreeMap<String, String>>),
+    ("commit_split", parses::<bool>),
+    ("spellcheck", parses::<Option<SpellcheckConfig>>),
+    ("changelog", parses::<Option<ChangelogConfig>>),
+    ("pr_description", parses::<Option<PrDescriptionConfig>>),
+    ("postprocess", parses::<Option<PostProcessConfig>>),
+    ("forge_mcp", parses::<Option<ForgeConfig>>),
+    ("performance_mode", parses::<Option<PerformanceMode>>),
+    ("logging", parses::<Option<LoggingConfig>>),
+    ("log_redaction", parses::<Option<LogRedaction>>),
+    ("http", parses::<Option<HttpConfig>>),
+    ("channels", parses::<Option<ChannelConfig>>),
+    ("notify_actor", parses::<Option<String>>),
+    ("notifications", parses::<Option<NotificationsConfig>>),
+    ("parent_actor_id", parses::<Option<String>>),
+    ("reply_to", parses::<Option<String>>),
+    ("siblings", parses::<Vec<Sibling>>),
+    ("fan_out", parses::<Option<FanOutConfig>>),
+    ("digest", parses::<Option<DigestConfig>>),
+    ("divergence_watch", parses::<Option<DivergenceWatch>>),
+    ("allow_published_rewrite", parses::<bool>),
+    ("enable_debug", parses::<bool>),
+    ("simulated_chat_state", parses::<bool>),
+    ("strict_preflight", parses::<bool>),
+    ("quick_commands", parses::<bool>),
+    ("scratchpad", parses::<bool>),
+    ("suggest_only", parses::<bool>),
+    ("advice_only", parses::<bool>),
+    ("supervise_task_monitor", parses::<bool>),
+    ("branch_naming", parses::<Option<BranchNaming>>),
+    ("branch_cleanup", parses::<Option<BranchCleanupConfig>>),
+    ("release", parses::<Option<ReleaseConfig>>),
+    ("hook_review", parses::<Option<HookReviewConfig>>),
+    ("schedule", parses::<Option<Vec<ScheduleEntry>>>),
+    ("branch_context", parses::<Option<BranchContextConfig>>),
+    ("ticket", parses::<Option<TicketConfig>>),
+    ("commit_size", parses::<Option<CommitSizeLimits>>),
+    ("submodules", parses::<Option<SubmoduleConfig>>),
+    ("large_files", parses::<Option<LargeFilePolicy>>),
+    ("diff_budget", parses::<Option<DiffBudget>>),
+    ("diff_summary", parses::<Option<DiffSummaryConfig>>),
+    ("repo_stats", parses::<Option<RepoStatsConfig>>),
+    ("tool_output", parses::<Option<ToolOutputPolicy>>),
+    ("review", parses::<Option<ReviewConfig>>),
+    ("blocked_commands", parses::<Option<Vec<String>>>),
+    ("scope_paths", parses::<Vec<String>>),
+    ("command_policy", parses::<Option<CommandPolicy>>),
+    ("secret_scan", parses::<Option<SecretScanConfig>>),
+    ("confirmation_policy", parses::<Option<ConfirmationPolicy>>),
+    ("dead_man_switch", parses::<Option<DeadManSwitch>>),
+    ("idle_timeout_secs", parses::<Option<u64>>),
+    ("session_store_id", parses::<Option<String>>),
+    ("max_turns", parses::<Option<TurnLimits>>),
+    ("workflow_timeout_secs", parses::<Option<WorkflowTimeouts>>),
+    ("completion", parses::<Option<CompletionConfig>>),
+    (
+        "postconditions",
+        parses::<BTreeMap<String, Vec<Postcondition>>>,
+    ),
+    ("verification", parses::<Option<VerificationConfig>>),
+    ("artifacts", parses::<Option<ArtifactsConfig>>),
+    ("bisect", parses::<Option<BisectConfig>>),
+    ("sync", parses::<Option<SyncConfig>>),
+    ("model_config", parses::<Option<Value>>),
+    ("model_fallbacks", parses::<Vec<ModelConfig>>),
+    ("model_presets", parses::<BTreeMap<String, ModelPreset>>),
+    (
+        "workflow_presets",
+        parses::<BTreeMap<String, BTreeMap<String, String>>>,
+    ),
+    ("crash_loop", parses::<Option<CrashLoopPolicy>>),
+    ("budget", parses::<Option<Budget>>),
+    ("retry_backoff", parses::<Option<BackoffConfig>>),
+    ("state_codec", parses::<Option<StateFormat>>),
+    ("state_limits", parses::<Option<StateLimits>>),
+    ("generation_overlap", parses::<Option<GenerationOverlap>>),
+    ("temperature", parses::<Option<f64>>),
+    ("max_tokens", parses::<Option<u32>>),
+    ("system_prompt", parses::<Option<String>>),
+    ("prompts", parses::<Option<PromptConfig>>),
+    ("title", parses::<Option<String>>),
+    ("description", parses::<Option<String>>),
+    ("mcp_servers", parses::<Option<Vec<Value>>>),
+    ("extra_mcp_servers", parses::<Vec<Value>>),
+    ("disable_default_mcp_servers", parses::<Vec<String>>),
+];
+
+/// Warn about each name under `path`, a per-workflow section, that isn't a known
+/// workflow. `effect` says what that means for its settings.
+fn unknown_workflows<'a>(
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+    path: &str,
+    names: impl IntoIterator<Item = &'a String>,
+    effect: &str,
+) {
+    for name in Workflow::unknown_names(names) {
+        diagnostics.push(ConfigDiagnostic::warning(
+            &format!("{}.{}", path, name),
+            format!(
+                "unknown workflow `{}`, {}; known workflows: {}",
+                name,
+                effect,
+                Workflow::KNOWN_NAMES.join(", ")
+            ),
+        ));
+    }
+}
+
+/// Check field types, ranges, and workflow names. Manifest paths are checked
+/// separately by [`check_manifest_paths`], which needs an exec actor.
+pub fn validate(raw: &Value) -> Vec<ConfigDiagnostic> {
+    let config = match raw.as_object() {
+        Some(config) => config,
+        None => {
+            return vec![ConfigDiagnostic::error(
+                "",
+                "config must be a JSON object".to_string(),
+            )]
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    for (key, value) in config {
+        match FIELDS.iter().find(|(name, _)| name == key) {
+            Some((_, check)) => {
+                if let Err(e) = check(value) {
+                    diagnostics.push(ConfigDiagnostic::error(key, e));
+                }
+            }
+            None => {
+                // Unknown fields are meant for the chat-state actor unless they look
+                // like a misspelled field of ours
+                if let Some(known) = closest_field(key) {
+                    diagnostics.push(ConfigDiagnostic::warning(
+                        key,
+                        format!("unknown field, did you mean `{}`?", known),
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(temperature) = config.get("temperature").and_then(Value::as_f64) {
+        if !(0.0..=2.0).contains(&temperature) {
+            diagnostics.push(ConfigDiagnostic::error(
+                "temperature",
+                format!("{} is outside the range 0 to 2", temperature),
+            ));
+        }
+    }
+    if let Some(url) = config
+        .get("notifications")
+        .and_then(|notifications| notifications.get("url"))
+        .and_then(Value::as_str)
+    {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            diagnostics.push(ConfigDiagnostic::error(
+                "notifications.url",
+                format!("{} isn't an http or https URL", url),
+            ));
+        }
+    }
+    if config.get("max_tokens").and_then(Value::as_u64) == Some(0) {
+        diagnostics.push(ConfigDiagnostic::error(
+            "max_tokens",
+            "must be greater than 0".to_string(),
+        ));
+    }
+    for key in ["workflow", "task"] {
+        let steps = config
+            .get(key)
+            .and_then(|value| serde_json::from_value::<Option<WorkflowSpec>>(value.clone()).ok())
+            .flatten()
+            .map(WorkflowSpec::steps)
+            .unwrap_or_default();
+        for workflow in steps {
+            if !workflow.is_known() {
+                diagnostics.push(ConfigDiagnostic::warning(
+                    key,
+                    format!(
+                        "unknown workflow `{}`, the generic prompt is used; known workflows: {}",
+                        workflow.name(),
+                        Workflow::KNOWN_NAMES.join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+    if let Some(Ok(Some(identity))) = config
+        .get("commit_identity")
+        .map(|value| serde_json::from_value::<Option<CommitIdentity>>(value.clone()))
+    {
+        for (field, person) in identity.malformed_people() {
+            diagnostics.push(ConfigDiagnostic::error(
+                &format!("commit_identity.{}", field),
+                format!("`{}` isn't of the form `Name <email>`", person),
+            ));
+        }
+    }
+    if let Some(Ok(policy)) = config
+        .get("command_policy")
+        .map(|value| serde_json::from_value::<CommandPolicy>(value.clone()))
+    {
+        unknown_workflows(
+            &mut diagnostics,
+            "command_policy.workflows",
+            policy.workflows.keys(),
+            "its rules never apply",
+        );
+    }
+    if let (Some(Ok(Some(policy))), Some(Ok(Some(review)))) = (
+        config
+            .get("command_policy")
+            .map(|value| serde_json::from_value::<Option<CommandPolicy>>(value.clone())),
+        config
+            .get("review")
+            .map(|value| serde_json::from_value::<Option<ReviewConfig>>(value.clone())),
+    ) {
+        if review.needs_exec_tools() && policy.restricts(Some(&Workflow::Review)) {
+            diagnostics.push(ConfigDiagnostic::warning(
+                "review.benchmark",
+                "the exec tools it gives the review workflow run any command, git \
+                included, so `command_policy` doesn't limit them"
+                    .to_string(),
+            ));
+        }
+    }
+    if let Some(Ok(postprocess)) = config
+        .get("postprocess")
+        .map(|value| serde_json::from_value::<PostProcessConfig>(value.clone()))
+    {
+        unknown_workflows(
+            &mut diagnostics,
+            "postprocess.workflows",
+            postprocess.workflows.keys(),
+            "its steps never apply",
+        );
+    }
+    if let Some(Ok(limits)) = config
+        .get("max_turns")
+        .map(|value| serde_json::from_value::<TurnLimits>(value.clone()))
+    {
+        unknown_workflows(
+            &mut diagnostics,
+            "max_turns.workflows",
+            limits.workflows.keys(),
+            "its limit never applies",
+        );
+    }
+    if let Some(Ok(timeouts)) = config
+        .get("workflow_timeout_secs")
+        .map(|value| serde_json::from_value::<WorkflowTimeouts>(value.clone()))
+    {
+        unknown_workflows(
+            &mut diagnostics,
+            "workflow_timeout_secs.workflows",
+            timeouts.workflows.keys(),
+            "its timeout never applies",
+        );
+    }
+    if let Some(Ok(prompts)) = config
+        .get("prompts")
+        .map(|value| serde_json::from_value::<PromptConfig>(value.clone()))
+    {
+        let effect = "only used by a workflow of that name";
+        unknown_workflows(
+            &mut diagnostics,
+            "prompts.workflows",
+            prompts.workflows.keys(),
+            effect,
+        );
+        unknown_workflows(
+            &mut diagnostics,
+            "prompts.auto_messages",
+            prompts.auto_messages.keys(),
+            effect,
+        );
+        unknown_workflows(
+            &mut diagnostics,
+            "prompts.priming",
+            prompts.priming.keys(),
+            effect,
+        );
+    }
+    if let Some(Ok(postconditions)) = config
+        .get("postconditions")
+        .map(|value| serde_json::from_value::<BTreeMap<String, Vec<Postcondition>>>(value.clone()))
+    {
+        unknown_workflows(
+            &mut diagnostics,
+            "postconditions",
+            postconditions.keys(),
+            "its postconditions never apply",
+        );
+    }
+    if let Some(Ok(Some(entries))) = config
+        .get("schedule")
+        .map(|value| serde_json::from_value::<Option<Vec<ScheduleEntry>>>(value.clone()))
+    {
+        for (index, entry) in entries.iter().enumerate() {
+            if let Err(e) = entry.validate() {
+                diagnostics.push(ConfigDiagnostic::error(&format!("schedule[{}]", index), e));
+            }
+            if !entry.workflow.is_known() {
+                diagnostics.push(ConfigDiagnostic::warning(
+                    &format!("schedule[{}].workflow", index),
+                    format!(
+                        "unknown workflow `{}`, the generic prompt is used; known workflows: {}",
+                        entry.workflow.name(),
+                        Workflow::KNOWN_NAMES.join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+    if let Some(Ok(completion)) = config
+        .get("completion")
+        .map(|value| serde_json::from_value::<CompletionConfig>(value.clone()))
+    {
+        unknown_workflows(
+            &mut diagnostics,
+            "completion.workflows",
+            completion.workflows.keys(),
+            "its criteria never apply",
+        );
+        for (name, criteria) in &completion.workflows {
+            if criteria.is_empty() {
+                diagnostics.push(ConfigDiagnostic::warning(
+                    &format!("completion.workflows.{}", name),
+                    "no criteria, the workflow never completes".to_string(),
+                ));
+            }
+        }
+    }
+    let presets = model_preset::presets(raw);
+    for (name, preset) in &presets {
+        if preset
+            .temperature
+            .is_some_and(|temperature| !(0.0..=2.0).contains(&temperature))
+        {
+            diagnostics.push(ConfigDiagnostic::error(
+                &format!("model_presets.{}.temperature", name),
+                "is outside the range 0 to 2".to_string(),
+            ));
+        }
+        if preset.max_tokens == Some(0) {
+            diagnostics.push(ConfigDiagnostic::error(
+                &format!("model_presets.{}.max_tokens", name),
+                "must be greater than 0".to_string(),
+            ));
+        }
+    }
+    if let Some(name) = config.get("model_config").and_then(Value::as_str) {
+        if !presets.contains_key(name) {
+            diagnostics.push(ConfigDiagnostic::error(
+                "model_config",
+                format!("unknown model preset `{}`", name),
+            ));
+        }
+    }
+    if let Some(Ok(workflow_presets)) = config.get("workflow_presets").map(|value| {
+        serde_json::from_value::<BTreeMap<String, BTreeMap<String, String>>>(value.clone())
+    }) {
+        for (workflow, stages) in &workflow_presets {
+            if !Workflow::from(workflow.clone()).is_known() {
+                diagnostics.push(ConfigDiagnostic::warning(
+                    &format!("workflow_presets.{}", workflow),
+                    format!(
+                        "unknown workflow `{}`, its presets never apply; known workflows: {}",
+                        workflow,
+                        Workflow::KNOWN_NAMES.join(", ")
+                    ),
+                ));
+            }
+            for (stage, preset) in stages {
+                let field = format!("workflow_presets.{}.{}", workflow, stage);
+                if !model_preset::STAGES.contains(&stage.as_str()) {
+                    diagnostics.push(ConfigDiagnostic::warning(
+                        &field,
+                        format!(
+                            "unknown stage `{}`, its preset never applies; stages: {}",
+                            stage,
+                            model_preset::STAGES.join(", ")
+                        ),
+                    ));
+                }
+                if !presets.contains_key(preset) {
+                    diagnostics.push(ConfigDiagnostic::error(
+                        &field,
+                        format!("unknown model preset `{}`", preset),
+                    ));
+                }
+            }
+        }
+    }
+    if let Some(Ok(Some(diff_summary))) = config
+        .get("diff_summary")
+        .map(|value| serde_json::from_value::<Option<DiffSummaryConfig>>(value.clone()))
+    {
+        if let Some(preset) = diff_summary
+            .preset
+            .as_ref()
+            .filter(|preset| !presets.contains_key(*preset))
+        {
+            diagnostics.push(ConfigDiagnostic::error(
+                "diff_summary.preset",
+                format!("unknown model preset `{}`", preset),
+            ));
+        }
+        for workflow in diff_summary.workflows.iter().filter(|w| !w.is_known()) {
+            diagnostics.push(ConfigDiagnostic::warning(
+                "diff_summary.workflows",
+                format!(
+                    "unknown workflow `{}`, its diffs are never summarized; known workflows: {}",
+                    workflow.name(),
+                    Workflow::KNOWN_NAMES.join(", ")
+                ),
+            ));
+        }
+    }
+    if let Some(Ok(budget)) = config
+        .get("budget")
+        .map(|value| serde_json::from_value::<Budget>(value.clone()))
+    {
+        for (index, downgrade) in budget.downgrades.iter().enumerate() {
+            if downgrade.after_tokens.is_none() && downgrade.after_cost.is_none() {
+                diagnostics.push(ConfigDiagnostic::error(
+                    &format!("budget.downgrades.{}", index),
+                    "needs after_tokens or after_cost".to_string(),
+                ));
+            }
+        }
+    }
+    if let Some(Ok(disabled)) = config
+        .get("disable_default_mcp_servers")
+        .map(|value| serde_json::from_value::<Vec<String>>(value.clone()))
+    {
+        for name in mcp_servers::unknown_names(&disabled) {
+            diagnostics.push(ConfigDiagnostic::warning(
+                "disable_default_mcp_servers",
+                format!(
+                    "unknown default server `{}`; default servers: {}",
+                    name,
+                    mcp_servers::DEFAULT_NAMES.join(", ")
+                ),
+            ));
+        }
+        if !disabled.is_empty()
+            && config
+                .get("mcp_servers")
+                .is_some_and(|servers| !servers.is_null())
+        {
+            diagnostics.push(ConfigDiagnostic::warning(
+                "disable_default_mcp_servers",
+                "has no effect, `mcp_servers` replaces the default servers".to_string(),
+            ));
+        }
+    }
+    if let Some(Ok(scope_paths)) = config
+        .get("scope_paths")
+        .map(|value| serde_json::from_value::<Vec<String>>(value.clone()))
+    {
+        for path in scope::invalid_paths(&scope_paths) {
+            diagnostics.push(ConfigDiagnostic::error(
+                "scope_paths",
+                format!(
+                    "`{}` must be relative to the repository root and stay inside it",
+                    path
+                ),
+            ));
+        }
+    }
+    diagnostics
+}
+
+/// `(field, path)` for every manifest path in the configured `mcp_servers`,
+/// `extra_mcp_servers`, and `forge_mcp`.
+pub fn manifest_paths(raw: &Value) -> Vec<(String, String)> {
+    let forge = raw
+        .get("forge_mcp")
+        .and_then(|forge| forge.get("manifest_path"))
+        .and_then(Value::as_str)
+        .map(|path| ("forge_mcp.manifest_path".to_string(), path.to_string()));
+    let fan_out = raw
+        .get("fan_out")
+        .and_then(|fan_out| fan_out.get("manifest_path"))
+        .and_then(Value::as_str)
+        .map(|path| ("fan_out.manifest_path".to_string(), path.to_string()));
+    let mut paths: Vec<(String, String)> = ["mcp_servers", "extra_mcp_servers"]
+        .iter()
+        .filter_map(|field| Some((field, raw.get(*field)?.as_array()?)))
+        .flat_map(|(field, servers)| {
+            servers
+                .iter()
+                .enumerate()
+                .filter_map(move |(index, server)| {
+                    let path = server.get("actor")?.get("manifest_path")?.as_str()?;
+                    Some((
+                        format!("{}[{}].actor.manifest_path", field, index),
+                        path.to_string(),
+                    ))
+                })
+        })
+        .collect();
+    paths.extend(forge);
+    paths.extend(fan_out);
+    paths
+}
+
+/// Check that each manifest path exists, using the exec actor `exec_actor_id`.
+pub fn check_manifest_paths(
+    exec_actor_id: &str,
+    paths: &[(String, String)],
+) -> Vec<ConfigDiagnostic> {
+    let mut diagnostics = Vec::new();
+    for (field, path) in paths {
+        match manifest_present(exec_actor_id, path) {
+            Ok(false) => diagnostics.push(ConfigDiagnostic::error(
+                field,
+                format!("manifest {} does not exist", path),
+            )),
+            Ok(true) => {}
+            Err(e) => diagnostics.push(ConfigDiagnostic::warning(field, e)),
+        }
+    }
+    diagnostics
+}
+
+/// Whether the manifest file at `path` exists, checked through the exec actor.
+pub fn manifest_present(exec_actor_id: &str, path: &str) -> Result<bool, String> {
+    if path.contains('\'') {
+        return Err("could not check a path containing a quote".to_string());
+    }
+    let command = format!("test -f '{}' && echo present || echo missing", path);
+    run_command(exec_actor_id, None, &command)
+        .map(|output| output.trim() != "missing")
+        .map_err(|e| format!("could not check manifest {}: {}", path, e))
+}
+
+/// All diagnostics on one line, for the init error.
+pub fn describe(diagnostics: &[ConfigDiagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            format!(
+                "{:?} in `{}`: {}",
+                diagnostic.severity, diagnostic.field, diagnostic.message
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Known field within two edits of `key`.
+fn closest_field(key: &str) -> Option<&'static str> {
+    FIELDS
+        .iter()
+        .map(|(name, _)| (*name, edit_distance(key, name)))
+        .filter(|(_, distance)| *distance <= 2)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(name, _)| name)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,125 @@
+//! Fingerprints of the chat config the assistant gives its chat-state actors. On resume,
+//! the config is built again by the running build; when its fingerprint differs from
+//! the saved one, e.g. after an upgrade changed the prompts, the chat-state actor gets
+//! the new config and what changed is logged.
+
+use crate::logging;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Stable across runs and builds: object keys are serialized in sorted order.
+pub fn fingerprint(config: &Value) -> String {
+    logging::fingerprint(&config.to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One value that differs between two configs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// Dotted path of the value, e.g. `model_config.model`
+    pub path: String,
+    pub change: ChangeKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// Every value that differs from `old` in `new`. Objects are compared key by key;
+/// anything else, arrays included, as a whole.
+pub fn changes(old: &Value, new: &Value) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    collect(String::new(), old, new, &mut changes);
+    changes
+}
+
+fn collect(path: String, old: &Value, new: &Value, changes: &mut Vec<ConfigChange>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                match new.get(key) {
+                    Some(new_value) => collect(child(key), old_value, new_value, changes),
+                    None => changes.push(ConfigChange {
+                        path: child(key),
+                        change: ChangeKind::Removed,
+                        old: Some(old_value.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for (key, new_value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                changes.push(ConfigChange {
+                    path: child(key),
+                    change: ChangeKind::Added,
+                    old: None,
+                    new: Some(new_value.clone()),
+                });
+            }
+        }
+        (old, new) if old != new => changes.push(ConfigChange {
+            path,
+            change: ChangeKind::Changed,
+            old: Some(old.clone()),
+            new: Some(new.clone()),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn changed_values_are_listed_by_path() {
+        let old = json!({
+            "model_config": {"model": "claude-sonnet-4", "provider": "anthropic"},
+            "temperature": 0.3,
+            "title": "Git Commit Assistant"
+        });
+        // The same config built with its keys in another order
+        let reordered: Value = serde_json::from_str(
+            r#"{"title": "Git Commit Assistant", "temperature": 0.3,
+            "model_config": {"provider": "anthropic", "model": "claude-sonnet-4"}}"#,
+        )
+        .unwrap();
+        assert_eq!(fingerprint(&old), fingerprint(&reordered));
+        assert!(changes(&old, &reordered).is_empty());
+
+        let new = json!({
+            "model_config": {"model": "claude-opus-4", "provider": "anthropic"},
+            "temperature": 0.3,
+            "system_prompt": "You are a git assistant"
+        });
+        assert_ne!(fingerprint(&old), fingerprint(&new));
+        let changes = changes(&old, &new);
+        let summary: Vec<(&str, ChangeKind)> = changes
+            .iter()
+            .map(|change| (change.path.as_str(), change.change))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("model_config.model", ChangeKind::Changed),
+                ("title", ChangeKind::Removed),
+                ("system_prompt", ChangeKind::Added),
+            ]
+        );
+        assert_eq!(changes[0].new, Some(json!("claude-opus-4")));
+    }
+}
@@ -0,0 +1,128 @@
+//! `UpdateConfig`: JSON merge patches (RFC 7396) of the generation parameters of a
+//! running assistant.
+
+use crate::config_check::{self, Severity};
+use crate::model_fallback::ModelConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Fields a patch may set.
+pub const PATCHABLE: &[&str] = &["temperature", "max_tokens", "model_config"];
+
+/// Generation parameters of the chat-state actors, as the chat config holds them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GenerationConfig {
+    pub model_config: ModelConfig,
+    pub temperature: f64,
+    pub max_tokens: u32,
+}
+
+impl GenerationConfig {
+    /// The parameters in a chat config.
+    pub fn from_chat_config(chat_config: &Value) -> Result<Self, String> {
+        let fields: serde_json::Map<String, Value> = PATCHABLE
+            .iter()
+            .map(|field| (field.to_string(), chat_config[*field].clone()))
+            .collect();
+        serde_json::from_value(Value::Object(fields))
+            .map_err(|e| format!("Invalid generation config: {}", e))
+    }
+
+    /// `self` with `patch` merged over it, if the result is valid.
+    pub fn apply(&self, patch: &Value) -> Result<Self, String> {
+        let fields = patch
+            .as_object()
+            .ok_or_else(|| "The patch must be a JSON object".to_string())?;
+        if fields.is_empty() {
+            return Err("The patch is empty".to_string());
+        }
+        for (field, value) in fields {
+            if !PATCHABLE.contains(&field.as_str()) {
+                return Err(format!(
+                    "`{}` can't be updated; patchable fields: {}",
+                    field,
+                    PATCHABLE.join(", ")
+                ));
+            }
+            // Chat-state actors need every parameter
+            if value.is_null() {
+                return Err(format!("`{}` can't be removed", field));
+            }
+        }
+        let mut config = serde_json::to_value(self)
+            .map_err(|e| format!("Failed to serialize generation config: {}", e))?;
+        merge_patch(&mut config, patch);
+        let errors: Vec<String> = config_check::validate(&config)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| format!("{}: {}", diagnostic.field, diagnostic.message))
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors.join("; "));
+        }
+        Self::from_chat_config(&config)
+    }
+}
+
+/// Merge `patch` into `target`: objects merge field by field, `null` removes a field,
+/// anything else replaces it.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn current() -> GenerationConfig {
+        GenerationConfig::from_chat_config(&json!({
+            "model_config": {"model": "claude-sonnet-4", "provider": "anthropic", "top_k": 5},
+            "temperature": 0.7,
+            "max_tokens": 8192,
+            "system_prompt": "You are a git assistant."
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn patches_merge_into_the_model_config() {
+        let updated = current()
+            .apply(&json!({"temperature": 0.2, "model_config": {"model": "claude-haiku-4", "top_k": null}}))
+            .unwrap();
+        assert_eq!(updated.temperature, 0.2);
+        assert_eq!(updated.max_tokens, 8192);
+        assert_eq!(updated.model_config.model, "claude-haiku-4");
+        assert_eq!(updated.model_config.provider, "anthropic");
+        assert!(updated.model_config.other.is_empty());
+    }
+
+    #[test]
+    fn invalid_patches_are_rejected() {
+        assert!(current().apply(&json!({"temperature": 3.0})).is_err());
+        assert!(current().apply(&json!({"max_tokens": 0})).is_err());
+        assert!(current().apply(&json!({"max_tokens": null})).is_err());
+        assert!(current()
+            .apply(&json!({"system_prompt": "Be brief."}))
+            .is_err());
+        assert!(current()
+            .apply(&json!({"model_config": {"provider": null}}))
+            .is_err());
+        assert!(current().apply(&json!({})).is_err());
+    }
+}
@@ -0,0 +1,333 @@
+use crate::protocol::external::{GitChatRequest, PublishMode};
+use crate::undo::UNDO_TURN;
+use serde::{Deserialize, Serialize};
+
+/// How far the effects of a request reach.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    ReadOnly,
+    /// Changes the local repository, e.g. creating a branch or rewriting history
+    LocalMutation,
+    /// Changes something others see, e.g. posting to a pull request
+    RemoteMutation,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfirmationMode {
+    Never,
+    /// The first request for each action in a session waits for `Confirm`
+    OncePerSession,
+    Always,
+}
+
+/// The `confirmation_policy` section of the assistant config. Without it, no request
+/// waits for confirmation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfirmationPolicy {
+    #[serde(default = "default_read_only")]
+    pub read_only: ConfirmationMode,
+    #[serde(default = "default_local_mutation")]
+    pub local_mutation: ConfirmationMode,
+    #[serde(default = "default_remote_mutation")]
+    pub remote_mutation: ConfirmationMode,
+}
+
+fn default_read_only() -> ConfirmationMode {
+    ConfirmationMode::Never
+}
+
+fn default_local_mutation() -> ConfirmationMode {
+    ConfirmationMode::OncePerSession
+}
+
+fn default_remote_mutation() -> ConfirmationMode {
+    ConfirmationMode::Always
+}
+
+impl Default for ConfirmationPolicy {
+    fn default() -> Self {
+        Self {
+            read_only: default_read_only(),
+            local_mutation: default_local_mutation(),
+            remote_mutation: default_remote_mutation(),
+        }
+    }
+}
+
+impl ConfirmationPolicy {
+    pub fn mode(&self, severity: Severity) -> ConfirmationMode {
+        match severity {
+            Severity::ReadOnly => self.read_only,
+            Severity::LocalMutation => self.local_mutation,
+            Severity::RemoteMutation => self.remote_mutation,
+        }
+    }
+}
+
+/// A request held back until the client confirms or rejects it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PendingConfirmation {
+    pub id: String,
+    pub action: String,
+    pub severity: Severity,
+    pub session_id: String,
+    pub description: String,
+    /// Run as sent once confirmed
+    pub request: GitChatRequest,
+    /// When the request was held back, in seconds since the epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_at: Option<u64>,
+}
+
+/// Action name and severity of `request`, or `None` for the requests that manage
+/// confirmations or turns and for keep-alives, which are never held back.
+pub fn classify(request: &GitChatRequest) -> Option<(&'static str, Severity)> {
+    let classified = match request {
+        GitChatRequest::GetPendingConfirmations
+        | GitChatRequest::KeepAlive
+        | GitChatRequest::Ping
+        | GitChatRequest::AcquireTurn { .. }
+        | GitChatRequest::ReleaseTurn { .. }
+        | GitChatRequest::Confirm { .. }
+        | GitChatRequest::RejectConfirmation { .. } => return None,
+        // Workflows have the model commit, rebase, or resolve conflicts on its own
+        GitChatRequest::StartChat { .. } | GitChatRequest::SetWorkflow { start: true, .. } => {
+            ("start-workflow", Severity::LocalMutation)
+        }
+        GitChatRequest::CreateBranch { .. } => ("create-branch", Severity::LocalMutation),
+        GitChatRequest::SetSparsePaths { .. } => ("set-sparse-paths", Severity::LocalMutation),
+        GitChatRequest::ResolveConflict { .. } => ("resolve-conflict", Severity::LocalMutation),
+        GitChatRequest::ExecuteRebasePlan { .. } => {
+            ("execute-rebase-plan", Severity::LocalMutation)
+        }
+        // May push deletions when the cleanup covers a remote
+        GitChatRequest::ExecuteBranchCleanup { .. } => {
+            ("delete-branches", Severity::RemoteMutation)
+        }
+        GitChatRequest::ExecuteRelease { .. } => ("create-tag", Severity::LocalMutation),
+        GitChatRequest::ApproveCommitPlan { .. } => ("commit", Severity::LocalMutation),
+        // The sibling runs a workflow of its own, or this assistant runs one for a sibling
+        GitChatRequest::DelegateTask { .. } => ("delegate-task", Severity::LocalMutation),
+        GitChatRequest::StartFanOut { .. } => ("fan-out", Severity::LocalMutation),
+        GitChatRequest::AcceptDelegation { .. } => ("start-workflow", Severity::LocalMutation),
+        // Both check out commits; one confirmation covers the whole bisect
+        GitChatRequest::StartBisect { .. } | GitChatRequest::SubmitBisectVerdict { .. } => {
+            ("bisect", Severity::LocalMutation)
+        }
+        // Picks the rest of the commits once the conflicted one is skipped
+        GitChatRequest::SkipCherryPick => ("cherry-pick", Severity::LocalMutation),
+        GitChatRequest::ExecuteSync { .. } => ("sync", Severity::LocalMutation),
+        // May unstage files or soft reset commits
+        GitChatRequest::UndoLastTurn => (UNDO_TURN, Severity::LocalMutation),
+        GitChatRequest::PublishReview {
+            mode: PublishMode::DryRun,
+            ..
+        } => ("publish-review", Severity::ReadOnly),
+        GitChatRequest::PublishReview { .. } => ("publish-review", Severity::RemoteMutation),
+        GitChatRequest::CleanArtifacts { .. } => ("clean-artifacts", Severity::LocalMutation),
+        // Each may reset the work tree, undoing a pick, merge, rebase, or checkout
+        GitChatRequest::AbortBisect => ("abort-bisect", Severity::LocalMutation),
+        GitChatRequest::AbortCherryPick => ("abort-cherry-pick", Severity::LocalMutation),
+        GitChatRequest::AbortSync => ("abort-sync", Severity::LocalMutation),
+        // Replaces the session and runs its unfinished workflow again
+        GitChatRequest::RestoreSession { .. } => ("restore-session", Severity::LocalMutation),
+        GitChatRequest::UpdateConfig { .. } | GitChatRequest::SetModelPreset { .. } => {
+            ("update-config", Severity::LocalMutation)
+        }
+        // Listed one by one so a new request is classified on purpose, not read-only
+        // by default
+        GitChatRequest::GetChatStateActorId
+        | GitChatRequest::AddMessage { .. }
+        | GitChatRequest::AddMessages { .. }
+        | GitChatRequest::SendAndAwait { .. }
+        | GitChatRequest::SuggestCommitMessage { .. }
+        | GitChatRequest::SuggestBranchName { .. }
+        | GitChatRequest::SuggestPrDescription { .. }
+        | GitChatRequest::SetWorkflow { start: false, .. }
+        | GitChatRequest::CreateSession { .. }
+        | GitChatRequest::ListSessions
+        | GitChatRequest::CloseSession { .. }
+        | GitChatRequest::SubmitSecurityFindings { .. }
+        | GitChatRequest::GetStatus
+        | GitChatRequest::GetWorkflowProgress
+        | GitChatRequest::VerifyHistory { .. }
+        | GitChatRequest::CheckSpelling { .. }
+        | GitChatRequest::CancelGeneration { .. }
+        | GitChatRequest::RunDigest { .. }
+        | GitChatRequest::CompareRepos { .. }
+        | GitChatRequest::GetHistory { .. }
+        | GitChatRequest::ExportTranscript { .. }
+        | GitChatRequest::SaveSession { .. }
+        | GitChatRequest::GetScratchpad { .. }
+        | GitChatRequest::GetConfigDiagnostics
+        | GitChatRequest::GetDivergence { .. }
+        | GitChatRequest::ListWorktrees
+        | GitChatRequest::SetActiveWorktree { .. }
+        | GitChatRequest::GetSparseCheckout
+        | GitChatRequest::GetConflicts
+        | GitChatRequest::AnalyzeRewrite { .. }
+        | GitChatRequest::ReviewRefs { .. }
+        | GitChatRequest::GetReviewReport { .. }
+        | GitChatRequest::PlanRebase { .. }
+        | GitChatRequest::GetRebase
+        | GitChatRequest::GetBranchCleanup
+        | GitChatRequest::GetRelease
+        | GitChatRequest::GetCommitPlan
+        | GitChatRequest::ScanForSecrets { .. }
+        | GitChatRequest::GetBisect
+        | GitChatRequest::GetCherryPick
+        | GitChatRequest::GetSync
+        | GitChatRequest::RegisterSibling { .. }
+        | GitChatRequest::ListSiblings
+        | GitChatRequest::GetDelegations
+        | GitChatRequest::GetFanOut
+        | GitChatRequest::Subscribe { .. }
+        | GitChatRequest::Unsubscribe { .. }
+        | GitChatRequest::DebugDump { .. }
+        | GitChatRequest::Describe
+        | GitChatRequest::GetUsage
+        | GitChatRequest::GetMetrics { .. }
+        | GitChatRequest::RetryToolAttach
+        | GitChatRequest::GetAuditLog { .. }
+        | GitChatRequest::CompactState
+        | GitChatRequest::FlushQueue
+        | GitChatRequest::SetTemplateVars { .. } => ("read", Severity::ReadOnly),
+    };
+    Some(classified)
+}
+
+/// Whether `request` waits for `Confirm` even without a `confirmation_policy`, and
+/// whatever its tier's mode.
+pub fn always_confirmed(request: &GitChatRequest) -> bool {
+    matches!(
+        request,
+        GitChatRequest::ExecuteBranchCleanup { .. }
+            | GitChatRequest::ExecuteRelease { .. }
+            | GitChatRequest::ExecuteSync { .. }
+    )
+}
+
+/// What a held-back request will do, for the client to show.
+pub fn describe(request: &GitChatRequest) -> String {
+    match request {
+        GitChatRequest::StartChat {
+            workflow: Some(workflow),
+            ..
+        } => format!("Switch to the {} workflow and start it", workflow.name()),
+        GitChatRequest::StartChat { .. } => "Start the configured workflow".to_string(),
+        GitChatRequest::SetWorkflow { workflow, .. } => {
+            format!("Switch to the {} workflow and start it", workflow.name())
+        }
+        GitChatRequest::CreateBranch { name, start_point } => format!(
+            "Create branch {} at {}",
+            name,
+            start_point.as_deref().unwrap_or("HEAD")
+        ),
+        GitChatRequest::SetSparsePaths { paths } if paths.is_empty() => {
+            "Check out only the scope paths".to_string()
+        }
+        GitChatRequest::SetSparsePaths { paths } => {
+            format!("Check out only {}", paths.join(", "))
+        }
+        GitChatRequest::ResolveConflict {
+            file,
+            hunk,
+            resolution,
+        } => format!(
+            "Resolve conflict {} in {} with {}",
+            hunk + 1,
+            file,
+            resolution.name()
+        ),
+        GitChatRequest::ExecuteRebasePlan { plan } => {
+            format!("Rebase {} commits onto {}", plan.steps.len(), plan.onto)
+        }
+        GitChatRequest::StartBisect { good, bad } => format!(
+            "Bisect from good {} to bad {}",
+            good,
+            bad.as_deref().unwrap_or("HEAD")
+        ),
+        GitChatRequest::SubmitBisectVerdict { verdict } => {
+            format!("Mark the checked-out commit {}", verdict.as_str())
+        }
+        GitChatRequest::SkipCherryPick => {
+            "Skip the conflicted commit and pick the rest".to_string()
+        }
+        GitChatRequest::ExecuteSync { strategy: None } => {
+            "Catch up with the upstream by the recommended strategy".to_string()
+        }
+        GitChatRequest::ExecuteSync {
+            strategy: Some(strategy),
+        } => format!("Catch up with the upstream by {}", strategy.name()),
+        GitChatRequest::UndoLastTurn => {
+            "Undo the last turn of the conversation and what it staged or committed".to_string()
+        }
+        GitChatRequest::ExecuteBranchCleanup { branches } if branches.is_empty() => {
+            "Delete every branch the cleanup proposed".to_string()
+        }
+        GitChatRequest::ExecuteBranchCleanup { branches } => {
+            format!("Delete branches {}", branches.join(", "))
+        }
+        GitChatRequest::ExecuteRelease { message: None } => {
+            "Create the proposed release tag with the drafted notes".to_string()
+        }
+        GitChatRequest::ExecuteRelease { message: Some(_) } => {
+            "Create the proposed release tag with the given message".to_string()
+        }
+        GitChatRequest::ApproveCommitPlan { plan: None } => {
+            "Approve the proposed commit plan and make its commits".to_string()
+        }
+        GitChatRequest::ApproveCommitPlan { plan: Some(plan) } => format!(
+            "Approve an edited commit plan and make its {} commits",
+            plan.commits.len()
+        ),
+        GitChatRequest::DelegateTask { actor_id, task } => format!(
+            "Have {} run the {} workflow",
+            actor_id,
+            task.workflow.name()
+        ),
+        GitChatRequest::AcceptDelegation { from, task, .. } => format!(
+            "Run the {} workflow delegated by {}",
+            task.workflow.name(),
+            from
+        ),
+        GitChatRequest::StartFanOut { repositories, task } => format!(
+            "Run the {} workflow in {} repositories",
+            task.workflow.name(),
+            repositories.len()
+        ),
+        GitChatRequest::PublishReview { report_id, mode } => {
+            format!("Post review {} to the forge ({:?})", report_id, mode)
+        }
+        GitChatRequest::CleanArtifacts { kinds, keep } => format!(
+            "Remove {} session artifacts, keeping the newest {}",
+            if kinds.is_empty() {
+                "all".to_string()
+            } else {
+                kinds
+                    .iter()
+                    .map(|kind| kind.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            },
+            keep
+        ),
+        GitChatRequest::AbortBisect => {
+            "Abort the bisect and go back to the original HEAD".to_string()
+        }
+        GitChatRequest::AbortCherryPick => "Abort the cherry-pick".to_string(),
+        GitChatRequest::AbortSync => "Abort the sync in progress".to_string(),
+        GitChatRequest::RestoreSession { id } => {
+            format!("Replace the default session with snapshot {}", id)
+        }
+        GitChatRequest::UpdateConfig { patch } => {
+            format!("Change the generation parameters with {}", patch)
+        }
+        GitChatRequest::SetModelPreset { preset } => {
+            format!("Switch every session to the {} model preset", preset)
+        }
+        other => format!("{:?}", other),
+    }
+}
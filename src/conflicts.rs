@@ -0,0 +1,272 @@
+//! Conflicted hunks of a merge, for `GetConflicts` and `ResolveConflict`: clients pick
+//! ours, theirs, or their own text hunk by hunk, and leave the hard ones to the model.
+
+use crate::exec_tools::{run_command, shell_quote};
+use crate::git_tools::run_git;
+use crate::repo_state;
+use serde::{Deserialize, Serialize};
+
+/// One conflict in a file, between its `<<<<<<<` and `>>>>>>>` markers.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConflictHunk {
+    /// Position among the file's remaining conflicts, from 0
+    pub index: usize,
+    /// Line of the `<<<<<<<` marker, from 1
+    pub line: usize,
+    pub ours: String,
+    /// Set when the file was merged with `merge.conflictStyle` diff3 or zdiff3
+    pub base: Option<String>,
+    pub theirs: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConflictedFile {
+    pub path: String,
+    /// Empty when the conflict isn't in the text, e.g. a file deleted on one side
+    pub hunks: Vec<ConflictHunk>,
+}
+
+/// How to resolve a hunk.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    /// Replace the hunk with `text`
+    Custom {
+        text: String,
+    },
+}
+
+impl Resolution {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resolution::Ours => "ours",
+            Resolution::Theirs => "theirs",
+            Resolution::Custom { .. } => "custom text",
+        }
+    }
+}
+
+/// Where a hunk's markers are, as indices into the file's lines.
+struct HunkSpan {
+    start: usize,
+    base: Option<usize>,
+    separator: usize,
+    end: usize,
+}
+
+fn find_spans(lines: &[&str]) -> Result<Vec<HunkSpan>, String> {
+    let mut spans = Vec::new();
+    let mut open: Option<(usize, Option<usize>, Option<usize>)> = None;
+    for (number, line) in lines.iter().enumerate() {
+        let line = line.trim_end_matches('\r');
+        match &mut open {
+            None if line.starts_with("<<<<<<<") => open = Some((number, None, None)),
+            None => {}
+            Some((_, base, None)) if line.starts_with("|||||||") && base.is_none() => {
+                *base = Some(number)
+            }
+            Some((_, _, separator @ None)) if line == "=======" => *separator = Some(number),
+            Some((start, base, Some(separator))) if line.starts_with(">>>>>>>") => {
+                spans.push(HunkSpan {
+                    start: *start,
+                    base: *base,
+                    separator: *separator,
+                    end: number,
+                });
+                open = None;
+            }
+            Some(_) if line.starts_with("<<<<<<<") => {
+                return Err(format!("Nested conflict marker on line {}", number + 1));
+            }
+            Some(_) => {}
+        }
+    }
+    match open {
+        Some((start, ..)) => Err(format!("Unterminated conflict on line {}", start + 1)),
+        None => Ok(spans),
+    }
+}
+
+fn join_lines(lines: &[&str]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// The conflicts in a file's contents, in order.
+pub fn parse_hunks(contents: &str) -> Result<Vec<ConflictHunk>, String> {
+    let lines: Vec<&str> = contents.lines().collect();
+    Ok(find_spans(&lines)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, span)| {
+            let ours_end = span.base.unwrap_or(span.separator);
+            ConflictHunk {
+                index,
+                line: span.start + 1,
+                ours: join_lines(&lines[span.start + 1..ours_end]),
+                base: span
+                    .base
+                    .map(|base| join_lines(&lines[base + 1..span.separator])),
+                theirs: join_lines(&lines[span.separator + 1..span.end]),
+            }
+        })
+        .collect())
+}
+
+/// `contents` with hunk `index` replaced as `resolution` says.
+pub fn resolve(contents: &str, index: usize, resolution: &Resolution) -> Result<String, String> {
+    let lines: Vec<&str> = contents.lines().collect();
+    let spans = find_spans(&lines)?;
+    let span = spans.get(index).ok_or_else(|| {
+        format!(
+            "There is no hunk {}; the file has {} conflicts left",
+            index,
+            spans.len()
+        )
+    })?;
+    let ours_end = span.base.unwrap_or(span.separator);
+    let replacement = match resolution {
+        Resolution::Ours => join_lines(&lines[span.start + 1..ours_end]),
+        Resolution::Theirs => join_lines(&lines[span.separator + 1..span.end]),
+        Resolution::Custom { text } if text.is_empty() || text.ends_with('\n') => text.clone(),
+        Resolution::Custom { text } => format!("{}\n", text),
+    };
+    let mut resolved = join_lines(&lines[..span.start]);
+    resolved.push_str(&replacement);
+    resolved.push_str(&join_lines(&lines[span.end + 1..]));
+    if !contents.ends_with('\n') {
+        resolved.pop();
+    }
+    Ok(resolved)
+}
+
+/// `text` escaped for `printf '%b'`, so a file's contents pass as one line.
+fn printf_escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\\\".to_string(),
+            '\n' => "\\n".to_string(),
+            '\t' => "\\t".to_string(),
+            '\r' => "\\r".to_string(),
+            c if c.is_control() => format!("\\0{:03o}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+fn read_file(exec_actor_id: &str, directory: Option<&str>, path: &str) -> Result<String, String> {
+    // The marker keeps trailing newlines from being trimmed off the output
+    let command = format!("cat -- {} && printf '%s' '#end'", shell_quote(path));
+    let output = run_command(exec_actor_id, directory, &command)?;
+    output
+        .strip_suffix("#end")
+        .map(str::to_string)
+        .ok_or_else(|| format!("Could not read {}", path))
+}
+
+/// Every conflicted file and its hunks.
+pub fn fetch(
+    git_actor_id: &str,
+    exec_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<Vec<ConflictedFile>, String> {
+    repo_state::conflicted_files(git_actor_id, directory)?
+        .into_iter()
+        .map(|path| {
+            // Deleted on one side: there is no text to pick from
+            let hunks = match read_file(exec_actor_id, directory, &path) {
+                Ok(contents) => parse_hunks(&contents)?,
+                Err(_) => Vec::new(),
+            };
+            Ok(ConflictedFile { path, hunks })
+        })
+        .collect()
+}
+
+/// Resolve hunk `index` of the conflicted file `path`, staging the file once no
+/// conflicts are left in it. Returns the file's remaining hunks.
+pub fn apply(
+    git_actor_id: &str,
+    exec_actor_id: &str,
+    directory: Option<&str>,
+    path: &str,
+    index: usize,
+    resolution: &Resolution,
+) -> Result<ConflictedFile, String> {
+    if !repo_state::conflicted_files(git_actor_id, directory)?
+        .iter()
+        .any(|conflicted| conflicted == path)
+    {
+        return Err(format!("{} has no unresolved conflicts", path));
+    }
+    let contents = read_file(exec_actor_id, directory, path)?;
+    let resolved = resolve(&contents, index, resolution)?;
+    let hunks = parse_hunks(&resolved)?;
+    let command = format!(
+        "printf '%b' {} > {}",
+        shell_quote(&printf_escape(&resolved)),
+        shell_quote(path)
+    );
+    run_command(exec_actor_id, directory, &command)?;
+    if hunks.is_empty() {
+        run_git(git_actor_id, directory, &["add", "--", path])?;
+    }
+    Ok(ConflictedFile {
+        path: path.to_string(),
+        hunks,
+    })
+}
+
+/// Message telling the model a client resolved a hunk.
+pub fn resolved_message(file: &ConflictedFile, index: usize, resolution: &Resolution) -> String {
+    let mut message = format!(
+        "The user resolved conflict {} in {} with {}.",
+        index + 1,
+        file.path,
+        resolution.name()
+    );
+    if file.hunks.is_empty() {
+        message.push_str(" The file has no conflicts left and is staged; leave it as it is.");
+    } else {
+        message.push_str(&format!(
+            " {} conflicts are left in it; don't redo the resolved one.",
+            file.hunks.len()
+        ));
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hunks_are_parsed_and_resolved_one_at_a_time() {
+        let contents = "fn main() {\n\
+            <<<<<<< HEAD\n    run(1);\n||||||| base\n    run(0);\n=======\n    run(2);\n>>>>>>> topic\n\
+            }\n\
+            <<<<<<< HEAD\nmod a;\n=======\nmod b;\n>>>>>>> topic\n";
+        let hunks = parse_hunks(contents).unwrap();
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].line, 2);
+        assert_eq!(hunks[0].base.as_deref(), Some("    run(0);\n"));
+        assert_eq!(hunks[1].base, None);
+        assert_eq!(hunks[1].theirs, "mod b;\n");
+
+        let resolved = resolve(contents, 1, &Resolution::Theirs).unwrap();
+        assert!(resolved.ends_with("}\nmod b;\n"));
+        let resolved = resolve(
+            &resolved,
+            0,
+            &Resolution::Custom {
+                text: "    run(3);".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(resolved, "fn main() {\n    run(3);\n}\nmod b;\n");
+        assert!(resolve(&resolved, 0, &Resolution::Ours).is_err());
+        assert!(parse_hunks("<<<<<<< HEAD\nours\n").is_err());
+        assert_eq!(printf_escape("a\\b\n\tc"), "a\\\\b\\n\\tc");
+    }
+}
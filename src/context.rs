@@ -0,0 +1,249 @@
+//! Keeps diffs within the model's context window: generated and binary files are
+//! summarized, each file's diff is cut after a number of lines, and source files are
+//! kept first when the whole diff goes over its byte cap.
+
+use crate::language::{detect_language, Language};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The `diff_budget` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiffBudget {
+    /// Lines of each file's diff kept before the rest is cut
+    #[serde(default = "default_max_lines_per_file")]
+    pub max_lines_per_file: usize,
+    /// Bytes of diff kept in total
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+    /// Regexes for generated files, e.g. lock files, whose diff is replaced by a count
+    /// of changed lines
+    #[serde(default = "default_summarized_files")]
+    pub summarized_files: Vec<String>,
+    /// Also trim the diffs in messages the assistant forwards to the model, instead of
+    /// only describing the budget in the prompt
+    #[serde(default)]
+    pub enforce: bool,
+}
+
+fn default_max_lines_per_file() -> usize {
+    300
+}
+
+fn default_max_bytes() -> usize {
+    40_000
+}
+
+fn default_summarized_files() -> Vec<String> {
+    vec![
+        r"(?:^|/)(?:Cargo\.lock|package-lock\.json|yarn\.lock|pnpm-lock\.yaml|poetry\.lock|Gemfile\.lock|composer\.lock|go\.sum)$".to_string(),
+        r"\.min\.(?:js|css)$".to_string(),
+        r"\.(?:snap|map)$".to_string(),
+    ]
+}
+
+impl Default for DiffBudget {
+    fn default() -> Self {
+        Self {
+            max_lines_per_file: default_max_lines_per_file(),
+            max_bytes: default_max_bytes(),
+            summarized_files: default_summarized_files(),
+            enforce: false,
+        }
+    }
+}
+
+impl DiffBudget {
+    pub fn prompt_fragment(&self) -> String {
+        format!(
+            "\n\nDIFF BUDGET: Large diffs don't fit in your context. Check the size of a \
+            change with git diff --stat first, then read it file by file (git diff -- \
+            <path>), source files before tests, docs, and config. Read at most about {} \
+            lines of any one file's diff and {} KB of diff in total; past that, read only \
+            the hunks you need. Don't read the diffs of lock files, minified or generated \
+            files, or binaries: their line counts from --stat are enough.",
+            self.max_lines_per_file,
+            self.max_bytes / 1000
+        )
+    }
+}
+
+/// Order in which files keep their diff when the byte cap is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    Source,
+    Other,
+    Summarized,
+}
+
+/// One file's part of a unified diff.
+struct FileDiff<'a> {
+    path: String,
+    lines: Vec<&'a str>,
+}
+
+impl FileDiff<'_> {
+    fn is_binary(&self) -> bool {
+        self.lines
+            .iter()
+            .any(|line| line.starts_with("Binary files ") || *line == "GIT binary patch")
+    }
+
+    fn changed_lines(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|line| {
+                (line.starts_with('+') && !line.starts_with("+++"))
+                    || (line.starts_with('-') && !line.starts_with("---"))
+            })
+            .count()
+    }
+}
+
+pub struct DiffTrimmer {
+    max_lines_per_file: usize,
+    max_bytes: usize,
+    summarized_files: Vec<Regex>,
+}
+
+impl DiffTrimmer {
+    pub fn new(budget: &DiffBudget) -> Result<Self, String> {
+        let summarized_files = budget
+            .summarized_files
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    format!(
+                        "invalid diff_budget.summarized_files pattern {}: {}",
+                        pattern, e
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            max_lines_per_file: budget.max_lines_per_file,
+            max_bytes: budget.max_bytes,
+            summarized_files,
+        })
+    }
+
+    /// `text` with the unified diff in it, if any, cut down to the budget. Text before
+    /// the first `diff --git` line and after a closing code fence is kept as it is.
+    pub fn trim(&self, text: &str) -> String {
+        let lines: Vec<&str> = text.lines().collect();
+        let start = match lines
+            .iter()
+            .position(|line| line.starts_with("diff --git "))
+        {
+            Some(start) => start,
+            None => return text.to_string(),
+        };
+        let end = lines[start..]
+            .iter()
+            .position(|line| line.starts_with("```"))
+            .map_or(lines.len(), |end| start + end);
+
+        let mut files: Vec<FileDiff> = Vec::new();
+        for line in &lines[start..end] {
+            if let Some(header) = line.strip_prefix("diff --git ") {
+                // "diff --git a/<path> b/<path>"
+                let path = header
+                    .split_once(" b/")
+                    .map_or(header, |(_, path)| path)
+                    .to_string();
+                files.push(FileDiff {
+                    path,
+                    lines: Vec::new(),
+                });
+            }
+            if let Some(file) = files.last_mut() {
+                file.lines.push(line);
+            }
+        }
+
+        let rendered: Vec<(Priority, String)> =
+            files.iter().map(|file| self.render_file(file)).collect();
+        // Spend the byte cap on source files first
+        let mut order: Vec<usize> = (0..rendered.len()).collect();
+        order.sort_by_key(|&index| rendered[index].0);
+        let mut kept = vec![false; rendered.len()];
+        let mut bytes = 0;
+        for index in order {
+            let size = rendered[index].1.len() + 1;
+            if bytes + size <= self.max_bytes {
+                bytes += size;
+                kept[index] = true;
+            }
+        }
+
+        let mut trimmed: Vec<String> = lines[..start].iter().map(|line| line.to_string()).collect();
+        let mut omitted = Vec::new();
+        for (index, (_, diff)) in rendered.into_iter().enumerate() {
+            if kept[index] {
+                trimmed.push(diff);
+            } else {
+                omitted.push(files[index].path.as_str());
+            }
+        }
+        if !omitted.is_empty() {
+            trimmed.push(format!(
+                "[Diff budget of {} bytes reached; omitted: {}. Read them with git diff -- \
+                <path> if they matter.]",
+                self.max_bytes,
+                omitted.join(", ")
+            ));
+        }
+        trimmed.extend(lines[end..].iter().map(|line| line.to_string()));
+        let trimmed = trimmed.join("\n");
+        // Leave text that was within budget byte for byte as it was
+        if trimmed == lines.join("\n") {
+            return text.to_string();
+        }
+        trimmed
+    }
+
+    fn render_file(&self, file: &FileDiff) -> (Priority, String) {
+        let summary = if file.is_binary() {
+            Some("binary file")
+        } else if self
+            .summarized_files
+            .iter()
+            .any(|regex| regex.is_match(&file.path))
+        {
+            Some("generated file")
+        } else {
+            None
+        };
+        if let Some(kind) = summary {
+            return (
+                Priority::Summarized,
+                format!(
+                    "{}\n[{}: {}, {} changed lines, diff omitted]",
+                    file.lines[0],
+                    file.path,
+                    kind,
+                    file.changed_lines()
+                ),
+            );
+        }
+
+        let priority = match detect_language(&file.path, None) {
+            Some(Language::Config | Language::Markdown) | None => Priority::Other,
+            Some(_) => Priority::Source,
+        };
+        let mut diff = file
+            .lines
+            .iter()
+            .take(self.max_lines_per_file)
+            .copied()
+            .collect::<Vec<_>>()
+            .join("\n");
+        if file.lines.len() > self.max_lines_per_file {
+            diff.push_str(&format!(
+                "\n[... {} more lines of {} omitted]",
+                file.lines.len() - self.max_lines_per_file,
+                file.path
+            ));
+        }
+        (priority, diff)
+    }
+}
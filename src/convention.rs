@@ -0,0 +1,319 @@
+use crate::git_tools::run_git;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Commit types accepted by the conventional style.
+const CONVENTIONAL_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+const CONVENTIONAL_PATTERN: &str = r"^(?P<type>[a-z]+)(?:\((?P<scope>[^()]+)\))?!?: \S";
+const GITMOJI_PATTERN: &str =
+    r"^(?::[a-z0-9_+-]+:|\p{Extended_Pictographic}\x{FE0F}?)(?: ?\((?P<scope>[^()]+)\):?)? \S";
+
+/// Message style commits must follow.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitStyle {
+    /// `type(scope): subject`, see <https://www.conventionalcommits.org>
+    #[default]
+    Conventional,
+    /// A leading gitmoji, either as `:shortcode:` or the emoji itself
+    Gitmoji,
+    /// Subject must match `pattern`
+    Custom,
+}
+
+/// The `commit_convention` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CommitConvention {
+    #[serde(default)]
+    pub style: CommitStyle,
+    /// Regex the subject line must match, required for the custom style
+    pub pattern: Option<String>,
+    /// Allowed scopes; any scope is accepted when empty
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub max_subject_length: Option<usize>,
+    /// Column body lines must be wrapped at
+    pub body_wrap: Option<usize>,
+    /// Lines every message must end with, with `{name}` placeholders for `variables`,
+    /// e.g. `"Refs: {ticket}"`
+    #[serde(default)]
+    pub trailers: Vec<String>,
+    #[serde(default)]
+    pub variables: BTreeMap<String, TemplateVariable>,
+}
+
+/// Where a trailer placeholder's value comes from. Values are resolved by the assistant
+/// when the workflow starts, so the model only copies them.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateVariable {
+    /// A fixed value, e.g. filled in by the CI job that starts the assistant
+    Value(String),
+    /// An environment variable, read through the exec tools
+    Env(String),
+    /// A regex matched against the current branch name; the first capture group, or
+    /// the whole match without groups
+    BranchPattern(String),
+}
+
+impl TemplateVariable {
+    /// Value of a `branch_pattern` variable for `branch`.
+    pub fn match_branch(pattern: &str, branch: &str) -> Result<String, String> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| format!("invalid branch pattern {}: {}", pattern, e))?;
+        let captures = regex
+            .captures(branch)
+            .ok_or_else(|| format!("branch {} does not match {}", branch, pattern))?;
+        let value = captures.get(1).or_else(|| captures.get(0));
+        Ok(value.map(|m| m.as_str().to_string()).unwrap_or_default())
+    }
+}
+
+/// A commit that doesn't follow the convention and why.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConventionViolation {
+    pub sha: String,
+    pub subject: String,
+    pub problems: Vec<String>,
+}
+
+impl CommitConvention {
+    pub fn prompt_fragment(&self) -> String {
+        let mut fragment = String::from("\n\nCOMMIT MESSAGE CONVENTION:");
+        match self.style {
+            CommitStyle::Conventional => fragment.push_str(&format!(
+                "\n- Subject format: type(scope): description, with type one of {}",
+                CONVENTIONAL_TYPES.join(", ")
+            )),
+            CommitStyle::Gitmoji => fragment.push_str(
+                "\n- Subject format: <gitmoji> description, starting with a gitmoji \
+                (e.g. :sparkles: or ✨), optionally followed by (scope):",
+            ),
+            CommitStyle::Custom => fragment.push_str(&format!(
+                "\n- Subject must match the regular expression: {}",
+                self.pattern.as_deref().unwrap_or_default()
+            )),
+        }
+        if !self.scopes.is_empty() {
+            fragment.push_str(&format!("\n- Allowed scopes: {}", self.scopes.join(", ")));
+        }
+        if let Some(max) = self.max_subject_length {
+            fragment.push_str(&format!("\n- Subject line at most {} characters", max));
+        }
+        if let Some(wrap) = self.body_wrap {
+            fragment.push_str(&format!(
+                "\n- Separate the body from the subject with a blank line and wrap it at {} columns",
+                wrap
+            ));
+        }
+        if !self.trailers.is_empty() {
+            fragment.push_str(
+                "\n- End every message with the trailer lines given in the task, copied exactly",
+            );
+        }
+        fragment.push_str(
+            "\nCommit messages are checked against this convention when the task completes.",
+        );
+        fragment
+    }
+
+    /// Problems with `message`, empty when it follows the convention.
+    pub fn check(&self, message: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut lines = message.trim_end().lines();
+        let subject = lines.next().unwrap_or_default();
+
+        if subject.trim().is_empty() {
+            problems.push("empty subject line".to_string());
+            return problems;
+        }
+
+        if let Some(max) = self.max_subject_length {
+            let length = subject.chars().count();
+            if length > max {
+                problems.push(format!(
+                    "subject is {} characters, the limit is {}",
+                    length, max
+                ));
+            }
+        }
+
+        match self.subject_regex() {
+            Ok(regex) => match regex.captures(subject) {
+                Some(captures) => {
+                    if let Some(commit_type) = captures.name("type") {
+                        if self.style == CommitStyle::Conventional
+                            && !CONVENTIONAL_TYPES.contains(&commit_type.as_str())
+                        {
+                            problems
+                                .push(format!("unknown commit type \"{}\"", commit_type.as_str()));
+                        }
+                    }
+                    if let Some(scope) = captures.name("scope") {
+                        if !self.scopes.is_empty()
+                            && !self.scopes.iter().any(|allowed| allowed == scope.as_str())
+                        {
+                            problems.push(format!(
+                                "scope \"{}\" is not one of {}",
+                                scope.as_str(),
+                                self.scopes.join(", ")
+                            ));
+                        }
+                    }
+                }
+                None => problems.push(format!(
+                    "subject does not follow the {} format",
+                    self.style_name()
+                )),
+            },
+            Err(e) => problems.push(e),
+        }
+
+        if let Some(separator) = lines.next() {
+            if !separator.trim().is_empty() {
+                problems.push("missing blank line between subject and body".to_string());
+            }
+        }
+
+        if let Some(wrap) = self.body_wrap {
+            // Lines without spaces (URLs, paths) can't be wrapped
+            let long_lines = lines
+                .filter(|line| line.chars().count() > wrap && line.trim().contains(' '))
+                .count();
+            if long_lines > 0 {
+                problems.push(format!(
+                    "{} body lines are longer than {} columns",
+                    long_lines, wrap
+                ));
+            }
+        }
+
+        problems
+    }
+
+    /// `trailers` with their placeholders filled in from `values`.
+    pub fn fill_trailers(&self, values: &BTreeMap<String, String>) -> Result<Vec<String>, String> {
+        let placeholder = Regex::new(r"\{([A-Za-z0-9_]+)\}").map_err(|e| e.to_string())?;
+        self.trailers
+            .iter()
+            .map(|trailer| {
+                let mut missing = None;
+                let filled = placeholder.replace_all(trailer, |captures: &regex::Captures| {
+                    match values.get(&captures[1]) {
+                        Some(value) => value.clone(),
+                        None => {
+                            missing = Some(captures[1].to_string());
+                            String::new()
+                        }
+                    }
+                });
+                match missing {
+                    Some(name) => Err(format!(
+                        "trailer \"{}\" uses unknown variable {}",
+                        trailer, name
+                    )),
+                    None => Ok(filled.into_owned()),
+                }
+            })
+            .collect()
+    }
+
+    fn subject_regex(&self) -> Result<Regex, String> {
+        let pattern = match self.style {
+            CommitStyle::Conventional => CONVENTIONAL_PATTERN,
+            CommitStyle::Gitmoji => GITMOJI_PATTERN,
+            CommitStyle::Custom => self
+                .pattern
+                .as_deref()
+                .ok_or_else(|| "custom commit convention has no pattern".to_string())?,
+        };
+        Regex::new(pattern).map_err(|e| format!("invalid commit convention pattern: {}", e))
+    }
+
+    fn style_name(&self) -> &'static str {
+        match self.style {
+            CommitStyle::Conventional => "conventional commit",
+            CommitStyle::Gitmoji => "gitmoji",
+            CommitStyle::Custom => "configured",
+        }
+    }
+}
+
+/// A commit created during the session.
+#[derive(Debug, Clone)]
+pub struct CommitMessage {
+    pub sha: String,
+    pub message: String,
+    /// Committer date in seconds since the epoch
+    pub committed_at: Option<u64>,
+}
+
+impl CommitMessage {
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(12)]
+    }
+
+    pub fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+}
+
+/// Messages of every commit after `base`, or only `HEAD` without a base.
+pub fn fetch_commit_messages(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: Option<&str>,
+) -> Result<Vec<CommitMessage>, String> {
+    // Records are separated so messages can contain blank lines
+    let format = "--format=%H%x1f%ct%x1f%B%x1e";
+    let range = base.map(|base| format!("{}..HEAD", base));
+    let args = match &range {
+        Some(range) => vec!["log", format, range.as_str()],
+        None => vec!["log", "-1", format],
+    };
+    let output = run_git(git_actor_id, directory, &args)?;
+
+    Ok(output
+        .split('\u{1e}')
+        .filter_map(|record| {
+            let (sha, rest) = record.trim_start().split_once('\u{1f}')?;
+            let (committed_at, message) = rest.split_once('\u{1f}')?;
+            Some(CommitMessage {
+                sha: sha.to_string(),
+                message: message.to_string(),
+                committed_at: committed_at.trim().parse().ok(),
+            })
+        })
+        .collect())
+}
+
+/// Commits that don't follow `convention` or lack one of the resolved `trailers`.
+pub fn check_commits(
+    commits: &[CommitMessage],
+    convention: &CommitConvention,
+    trailers: &[String],
+) -> Vec<ConventionViolation> {
+    commits
+        .iter()
+        .filter_map(|commit| {
+            let mut problems = convention.check(&commit.message);
+            for trailer in trailers {
+                if !commit.message.lines().any(|line| line.trim() == trailer) {
+                    problems.push(format!("missing trailer \"{}\"", trailer));
+                }
+            }
+            if problems.is_empty() {
+                return None;
+            }
+            Some(ConventionViolation {
+                sha: commit.sha.clone(),
+                subject: commit.subject().to_string(),
+                problems,
+            })
+        })
+        .collect()
+}
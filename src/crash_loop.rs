@@ -0,0 +1,116 @@
+//! Crash-loop detection for chat-state actors: a session whose actor keeps crashing is
+//! failed instead of being replaced again and again.
+
+use serde::{Deserialize, Serialize};
+
+/// The `crash_loop` config.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CrashLoopPolicy {
+    /// Crashes a session may have within `window_secs`; one more fails it
+    #[serde(default = "default_max_crashes")]
+    pub max_crashes: u32,
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_max_crashes() -> u32 {
+    3
+}
+
+fn default_window_secs() -> u64 {
+    600
+}
+
+impl Default for CrashLoopPolicy {
+    fn default() -> Self {
+        Self {
+            max_crashes: default_max_crashes(),
+            window_secs: default_window_secs(),
+        }
+    }
+}
+
+/// One crash of a session's chat-state actor.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Crash {
+    /// Seconds since the epoch
+    pub at: u64,
+    pub child_id: String,
+    /// `WitErrorType` of the error the child reported, or `exit` for an abnormal exit
+    pub error_type: String,
+    pub error: String,
+}
+
+/// A session given up on after its chat-state actor crashed too often.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionFailure {
+    pub session_id: String,
+    /// Seconds since the epoch
+    pub failed_at: u64,
+    /// The crashes within the window, oldest first
+    pub crashes: Vec<Crash>,
+}
+
+impl CrashLoopPolicy {
+    /// Add `crash` to the session's `crashes`, forgetting those older than the window.
+    /// True when the session now has more crashes than it may.
+    pub fn record(&self, crashes: &mut Vec<Crash>, crash: Crash) -> bool {
+        let since = crash.at.saturating_sub(self.window_secs);
+        crashes.retain(|earlier| earlier.at >= since);
+        crashes.push(crash);
+        crashes.len() > self.max_crashes as usize
+    }
+}
+
+impl SessionFailure {
+    pub fn describe(&self) -> String {
+        let last = self.crashes.last();
+        format!(
+            "session {} failed after its chat-state actor crashed {} times; last error ({}): {}",
+            self.session_id,
+            self.crashes.len(),
+            last.map_or("unknown", |crash| crash.error_type.as_str()),
+            last.map_or("", |crash| crash.error.as_str())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crash(at: u64) -> Crash {
+        Crash {
+            at,
+            child_id: format!("actor-{}", at),
+            error_type: "internal".to_string(),
+            error: "out of fuel".to_string(),
+        }
+    }
+
+    #[test]
+    fn only_crashes_within_the_window_count() {
+        let policy = CrashLoopPolicy {
+            max_crashes: 2,
+            window_secs: 60,
+        };
+        let mut crashes = Vec::new();
+        assert!(!policy.record(&mut crashes, crash(1_000)));
+        assert!(!policy.record(&mut crashes, crash(1_030)));
+        // The first one is out of the window by now
+        assert!(!policy.record(&mut crashes, crash(1_070)));
+        assert_eq!(crashes.len(), 2);
+        assert!(policy.record(&mut crashes, crash(1_080)));
+
+        let failure = SessionFailure {
+            session_id: "default".to_string(),
+            failed_at: 1_080,
+            crashes,
+        };
+        assert_eq!(
+            failure.describe(),
+            "session default failed after its chat-state actor crashed 3 times; last error \
+            (internal): out of fuel"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crate::protocol::external::{GitChatRequest, SessionStatus};
+use serde::{Deserialize, Serialize};
+
+/// The `dead_man_switch` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeadManSwitch {
+    /// Longest gap allowed between `KeepAlive` requests once a workflow has been
+    /// auto-initiated, measured with `Tick` timestamps
+    pub interval_seconds: u64,
+}
+
+impl DeadManSwitch {
+    /// Whether the last keep-alive, at `last_keepalive_at`, is too long ago at `now`.
+    pub fn is_expired(&self, last_keepalive_at: u64, now: u64) -> bool {
+        now.saturating_sub(last_keepalive_at) > self.interval_seconds
+    }
+}
+
+/// What the assistant was doing when keep-alives stopped.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HaltSnapshot {
+    /// `Tick` time the switch tripped at, in seconds since the epoch
+    pub halted_at: u64,
+    pub last_keepalive_at: u64,
+    /// HEAD when halted, to see what the run changed
+    pub head: Option<String>,
+    /// Sessions whose running generation was cancelled
+    pub cancelled_sessions: Vec<String>,
+    pub status: SessionStatus,
+}
+
+/// Requests served while halted: they only read, or stop things.
+pub fn allowed_while_halted(request: &GitChatRequest) -> bool {
+    matches!(
+        request,
+        GitChatRequest::KeepAlive
+            | GitChatRequest::Ping
+            | GitChatRequest::GetStatus
+            | GitChatRequest::GetWorkflowProgress
+            | GitChatRequest::GetChatStateActorId
+            | GitChatRequest::ListSessions
+            | GitChatRequest::CloseSession { .. }
+            | GitChatRequest::CancelGeneration { .. }
+            | GitChatRequest::GetHistory { .. }
+            | GitChatRequest::GetScratchpad { .. }
+            | GitChatRequest::ExportTranscript { .. }
+            | GitChatRequest::SaveSession { .. }
+            | GitChatRequest::GetConfigDiagnostics
+            | GitChatRequest::GetDivergence { .. }
+            | GitChatRequest::ListWorktrees
+            | GitChatRequest::GetSparseCheckout
+            | GitChatRequest::GetConflicts
+            | GitChatRequest::AcquireTurn { .. }
+            | GitChatRequest::ReleaseTurn { .. }
+            | GitChatRequest::AnalyzeRewrite { .. }
+            | GitChatRequest::VerifyHistory { .. }
+            | GitChatRequest::CheckSpelling { .. }
+            | GitChatRequest::GetReviewReport { .. }
+            | GitChatRequest::GetRebase
+            | GitChatRequest::GetRelease
+            | GitChatRequest::GetCommitPlan
+            | GitChatRequest::ListSiblings
+            | GitChatRequest::GetDelegations
+            | GitChatRequest::GetFanOut
+            | GitChatRequest::ScanForSecrets { .. }
+            | GitChatRequest::GetPendingConfirmations
+            | GitChatRequest::RejectConfirmation { .. }
+            | GitChatRequest::GetBisect
+            | GitChatRequest::AbortBisect
+            | GitChatRequest::GetCherryPick
+            | GitChatRequest::AbortCherryPick
+            | GitChatRequest::GetSync
+            | GitChatRequest::AbortSync
+            | GitChatRequest::DebugDump { .. }
+            | GitChatRequest::Subscribe { .. }
+            | GitChatRequest::Unsubscribe { .. }
+            | GitChatRequest::Describe
+            | GitChatRequest::GetUsage
+            | GitChatRequest::GetMetrics { .. }
+            | GitChatRequest::GetAuditLog { .. }
+            | GitChatRequest::CompactState
+    )
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// Events kept for the dump; older ones are dropped.
+const MAX_EVENTS: usize = 50;
+
+/// Parts of the state a `DebugDump` can include.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DebugSection {
+    /// Sessions, their generations in flight, and the fallback models they use
+    Sessions,
+    /// Running children and the tool actors the assistant spawned
+    Children,
+    /// Pending confirmations and confirmed actions
+    Queues,
+    /// Workflow progress and the rebase, bisect, review, and halt state
+    Workflows,
+    Events,
+    Config,
+}
+
+impl DebugSection {
+    pub const ALL: &'static [DebugSection] = &[
+        DebugSection::Sessions,
+        DebugSection::Children,
+        DebugSection::Queues,
+        DebugSection::Workflows,
+        DebugSection::Events,
+        DebugSection::Config,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DebugSection::Sessions => "sessions",
+            DebugSection::Children => "children",
+            DebugSection::Queues => "queues",
+            DebugSection::Workflows => "workflows",
+            DebugSection::Events => "events",
+            DebugSection::Config => "config",
+        }
+    }
+}
+
+/// A request or child notification the assistant handled, recorded while
+/// `enable_debug` is set.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DebugEvent {
+    /// When the event arrived, in seconds since the epoch
+    pub at: Option<u64>,
+    /// `request` or `notification`
+    pub source: String,
+    /// Variant name, e.g. `AddMessage` or `GenerationFailed`
+    pub kind: String,
+    pub session_id: Option<String>,
+}
+
+/// Record an event, dropping the oldest once there are `MAX_EVENTS`.
+pub fn record(events: &mut VecDeque<DebugEvent>, event: DebugEvent) {
+    if events.len() == MAX_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(event);
+}
+
+/// The `"type"` tag of a serialized request or notification.
+pub fn variant_name<T: Serialize>(message: &T) -> String {
+    serde_json::to_value(message)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("type")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
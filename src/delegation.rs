@@ -0,0 +1,282 @@
+//! Tasks delegated to sibling assistants: `DelegateTask` has a registered sibling run a
+//! workflow, the sibling sends back `DelegationFinished` when the workflow completes,
+//! and the result is told to the default session's model.
+
+use crate::protocol::external::{GitChatRequest, WorkflowResult};
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+
+/// Delegations kept for `GetDelegations`; the oldest finished ones are dropped first.
+pub const MAX_DELEGATIONS: usize = 50;
+
+/// Another assistant actor tasks can be delegated to, e.g. one set up to fix tests.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Sibling {
+    pub actor_id: String,
+    /// How the model and the logs refer to it
+    pub name: String,
+    /// What it is good at
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// What a sibling is asked to do: run `workflow`, following `instructions`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DelegatedTask {
+    pub workflow: Workflow,
+    #[serde(default)]
+    pub instructions: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DelegationStatus {
+    Running,
+    /// The sibling holds the task back until it is confirmed there
+    Pending,
+    Completed,
+    Failed,
+}
+
+impl DelegationStatus {
+    /// Whether the sibling may still report back.
+    pub fn is_open(self) -> bool {
+        matches!(self, DelegationStatus::Running | DelegationStatus::Pending)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Delegation {
+    pub id: String,
+    /// Sibling working on the task
+    pub actor_id: String,
+    pub task: DelegatedTask,
+    pub status: DelegationStatus,
+    /// Seconds since the epoch
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    /// The sibling's workflow result, once it reported one; boxed to keep responses small
+    pub result: Option<Box<WorkflowResult>>,
+    /// Why the delegation failed
+    pub error: Option<String>,
+    /// The sibling's confirmation a pending task waits for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation_id: Option<String>,
+}
+
+impl Delegation {
+    /// Record the sibling's outcome: its workflow result, or why there is none.
+    pub fn finish(&mut self, result: Option<WorkflowResult>, error: Option<String>, now: u64) {
+        let error = error.or_else(|| result.as_ref().and_then(|result| result.error.clone()));
+        self.status = match error {
+            Some(_) => DelegationStatus::Failed,
+            None => DelegationStatus::Completed,
+        };
+        self.finished_at = Some(now);
+        self.result = result.map(Box::new);
+        self.error = error;
+    }
+}
+
+/// Where the task a sibling is working on came from, so the result can be sent back.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DelegationSource {
+    pub delegation_id: String,
+    /// The delegating assistant
+    pub actor_id: String,
+    pub task: DelegatedTask,
+    /// The confirmation the start of the task's workflow is held back for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation_id: Option<String>,
+}
+
+/// The delegation that fails when confirmation `id`, held for `request`, is rejected:
+/// a held `AcceptDelegation`, or the start of the `accepted` task when that start is
+/// what `id` holds back.
+pub fn rejected_by(
+    id: &str,
+    request: &GitChatRequest,
+    accepted: Option<&DelegationSource>,
+) -> Option<DelegationSource> {
+    match request {
+        GitChatRequest::AcceptDelegation {
+            delegation_id,
+            from,
+            task,
+        } => Some(DelegationSource {
+            delegation_id: delegation_id.clone(),
+            actor_id: from.clone(),
+            task: task.clone(),
+            confirmation_id: None,
+        }),
+        GitChatRequest::StartChat { .. } => accepted
+            .filter(|source| source.confirmation_id.as_deref() == Some(id))
+            .cloned(),
+        _ => None,
+    }
+}
+
+/// Add `delegation`, dropping the oldest finished delegations past `MAX_DELEGATIONS`.
+pub fn record(delegations: &mut Vec<Delegation>, delegation: Delegation) {
+    delegations.push(delegation);
+    while delegations.len() > MAX_DELEGATIONS {
+        match delegations
+            .iter()
+            .position(|delegation| !delegation.status.is_open())
+        {
+            Some(index) => delegations.remove(index),
+            None => break,
+        };
+    }
+}
+
+/// Appended to the sibling's workflow message: who delegated the task and what to do.
+pub fn task_context(source: &DelegationSource) -> String {
+    let mut context = format!(
+        "\n\nDELEGATED TASK: Another assistant ({}) delegated this task to you; your result \
+        is reported back to it when you complete it.",
+        source.actor_id
+    );
+    if let Some(instructions) = source
+        .task
+        .instructions
+        .as_deref()
+        .filter(|instructions| !instructions.trim().is_empty())
+    {
+        context.push_str(&format!("\nInstructions: {}", instructions.trim()));
+    }
+    context
+}
+
+/// Message telling the default session's model how the delegated task went.
+pub fn fold_message(delegation: &Delegation, sibling_name: &str) -> String {
+    let workflow = delegation.task.workflow.name();
+    let mut message = match &delegation.error {
+        Some(error) => format!(
+            "The {} task delegated to {} failed: {}",
+            workflow, sibling_name, error
+        ),
+        None => format!(
+            "The {} task delegated to {} is done.",
+            workflow, sibling_name
+        ),
+    };
+    if let Some(result) = &delegation.result {
+        if let Some(summary) = &result.summary {
+            message.push_str(&format!("\nSummary: {}", summary));
+        }
+        if !result.commits_created.is_empty() {
+            message.push_str("\nCommits:");
+            for commit in &result.commits_created {
+                message.push_str(&format!("\n- {} {}", commit.sha, commit.subject));
+            }
+        }
+        if !result.files_touched.is_empty() {
+            message.push_str(&format!(
+                "\nFiles touched: {}",
+                result.files_touched.join(", ")
+            ));
+        }
+        for warning in result
+            .verification
+            .iter()
+            .flat_map(|verification| verification.warnings())
+        {
+            message.push_str(&format!("\nWarning: {}", warning));
+        }
+    }
+    message.push_str("\nTake this into account and carry on with your task.");
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delegation(id: &str, status: DelegationStatus) -> Delegation {
+        Delegation {
+            id: id.to_string(),
+            actor_id: "test-fixer".to_string(),
+            task: DelegatedTask {
+                workflow: Workflow::Commit,
+                instructions: None,
+            },
+            status,
+            started_at: 10,
+            finished_at: None,
+            result: None,
+            error: None,
+            confirmation_id: None,
+        }
+    }
+
+    #[test]
+    fn rejecting_a_held_delegation_fails_only_that_delegation() {
+        let task = DelegatedTask {
+            workflow: Workflow::Commit,
+            instructions: None,
+        };
+        let held = GitChatRequest::AcceptDelegation {
+            delegation_id: "delegation-4".to_string(),
+            from: "lead-1".to_string(),
+            task: task.clone(),
+        };
+        let source = rejected_by("confirmation-1", &held, None).unwrap();
+        assert_eq!(
+            (source.delegation_id.as_str(), source.actor_id.as_str()),
+            ("delegation-4", "lead-1")
+        );
+
+        let start = GitChatRequest::StartChat {
+            allow_secrets: false,
+            allow_out_of_scope: false,
+            workflow: None,
+            args: None,
+            suggest_only: false,
+        };
+        let mut accepted = DelegationSource {
+            delegation_id: "delegation-2".to_string(),
+            actor_id: "lead-1".to_string(),
+            task,
+            confirmation_id: Some("confirmation-2".to_string()),
+        };
+        let source = rejected_by("confirmation-2", &start, Some(&accepted)).unwrap();
+        assert_eq!(source.delegation_id, "delegation-2");
+        // A user's own start, held while the delegated workflow runs
+        accepted.confirmation_id = None;
+        assert!(rejected_by("confirmation-3", &start, Some(&accepted)).is_none());
+        assert!(rejected_by("confirmation-2", &start, None).is_none());
+    }
+
+    #[test]
+    fn results_are_folded_and_finished_delegations_dropped_first() {
+        let mut running = delegation("delegation-1", DelegationStatus::Running);
+        let result: WorkflowResult = serde_json::from_value(serde_json::json!({
+            "workflow": "commit",
+            "commits_created": [{"sha": "0123abcd", "subject": "fix: flaky parser test", "committed_at": 20}],
+            "files_touched": ["tests/parser.rs"],
+            "summary": "Fixed the test",
+            "error": null
+        }))
+        .unwrap();
+        running.finish(Some(result), None, 30);
+        assert_eq!(running.status, DelegationStatus::Completed);
+        let message = fold_message(&running, "test fixer");
+        assert!(message.starts_with("The commit task delegated to test fixer is done."));
+        assert!(message.contains("- 0123abcd fix: flaky parser test"));
+
+        let mut delegations: Vec<Delegation> = (0..MAX_DELEGATIONS)
+            .map(|n| delegation(&format!("delegation-{}", n), DelegationStatus::Running))
+            .collect();
+        delegations[2].status = DelegationStatus::Pending;
+        delegations[3].finish(None, Some("it exited".to_string()), 40);
+        assert_eq!(delegations[3].status, DelegationStatus::Failed);
+        record(
+            &mut delegations,
+            delegation("new", DelegationStatus::Running),
+        );
+        assert_eq!(delegations.len(), MAX_DELEGATIONS);
+        assert!(delegations.iter().all(|d| d.id != "delegation-3"));
+        assert!(delegations.iter().any(|d| d.id == "delegation-2"));
+    }
+}
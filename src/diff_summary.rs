@@ -0,0 +1,186 @@
+//! Diff summarization pre-pass for huge changesets: before a workflow's opening message,
+//! a throwaway chat-state actor summarizes the pending diff file by file, and the
+//! workflow's model gets the summaries instead of reading the whole diff.
+
+use crate::context::{DiffBudget, DiffTrimmer};
+use crate::git_tools::run_git;
+use crate::sanitize::{self, TextKind};
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+
+pub const TEMPERATURE: f64 = 0.2;
+
+pub const MAX_TOKENS: u32 = 4096;
+
+pub const SYSTEM_PROMPT: &str = "You summarize diffs. You are given the diff of pending \
+    changes to a git repository. For every file in it, reply with one line of the form \
+    `path: summary`, where the summary says in a sentence or two what changed in the file \
+    and why it seems to have changed. Mention renamed, deleted, and generated files as \
+    such. No preamble, no headings, no code fences.";
+
+/// The `diff_summary` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiffSummaryConfig {
+    /// Bytes the pending diff must be over for the pre-pass to run
+    #[serde(default = "default_min_bytes")]
+    pub min_bytes: usize,
+    /// Bytes of diff the pre-pass reads; each file is cut after `max_lines_per_file`
+    #[serde(default = "default_max_input_bytes")]
+    pub max_input_bytes: usize,
+    #[serde(default = "default_max_lines_per_file")]
+    pub max_lines_per_file: usize,
+    /// Preset of `model_presets` the pre-pass runs on, e.g. `cheap`; the current model
+    /// otherwise
+    #[serde(default)]
+    pub preset: Option<String>,
+    #[serde(default = "default_workflows")]
+    pub workflows: Vec<Workflow>,
+}
+
+fn default_min_bytes() -> usize {
+    40_000
+}
+
+fn default_max_input_bytes() -> usize {
+    400_000
+}
+
+fn default_max_lines_per_file() -> usize {
+    1_000
+}
+
+fn default_workflows() -> Vec<Workflow> {
+    vec![Workflow::Commit, Workflow::Review]
+}
+
+impl Default for DiffSummaryConfig {
+    fn default() -> Self {
+        Self {
+            min_bytes: default_min_bytes(),
+            max_input_bytes: default_max_input_bytes(),
+            max_lines_per_file: default_max_lines_per_file(),
+            preset: None,
+            workflows: default_workflows(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FileSummary {
+    pub path: String,
+    pub summary: String,
+}
+
+/// Summaries the pre-pass wrote, kept for the rest of the workflow.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DiffSummary {
+    /// Size of the pending diff
+    pub diff_bytes: usize,
+    pub files: Vec<FileSummary>,
+}
+
+/// A pre-pass waiting for its throwaway chat-state actor.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DiffSummaryPass {
+    pub chat_actor_id: String,
+    pub workflow: Workflow,
+    pub diff_bytes: usize,
+}
+
+/// The pending diff against HEAD, when it is big enough to summarize first.
+pub fn fetch_large_diff(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    config: &DiffSummaryConfig,
+) -> Result<Option<String>, String> {
+    let diff = run_git(git_actor_id, directory, &["diff", "-M", "-C", "HEAD"])?;
+    Ok((diff.len() > config.min_bytes).then_some(diff))
+}
+
+/// The only message of the pre-pass: the diff, cut down to what the pre-pass reads.
+pub fn prompt(diff: &str, config: &DiffSummaryConfig) -> Result<String, String> {
+    let trimmer = DiffTrimmer::new(&DiffBudget {
+        max_lines_per_file: config.max_lines_per_file,
+        max_bytes: config.max_input_bytes,
+        ..DiffBudget::default()
+    })?;
+    Ok(format!(
+        "Summarize this diff file by file.\n\nDIFF:\n{}",
+        trimmer.trim(diff).trim_end()
+    ))
+}
+
+/// `path: summary` lines of the reply, with list markers and backticks taken off.
+pub fn parse(reply: &str) -> Vec<FileSummary> {
+    reply
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches(['-', '*']).trim_start();
+            let (path, summary) = line.split_once(": ")?;
+            let path = path.trim().trim_matches(['`', '*']).trim();
+            let summary = summary.trim();
+            (!path.is_empty() && !path.contains(' ') && !summary.is_empty()).then(|| FileSummary {
+                path: path.to_string(),
+                summary: summary.to_string(),
+            })
+        })
+        .collect()
+}
+
+impl DiffSummary {
+    /// Block for the workflow's opening message.
+    pub fn prompt_fragment(&self) -> String {
+        let mut fragment = format!(
+            "\n\nDIFF SUMMARY: The pending diff is {} bytes, too big to read whole, so it was \
+            summarized file by file. Work from these summaries, and read the diff of a single \
+            file only when you need its details:",
+            self.diff_bytes
+        );
+        for file in &self.files {
+            fragment.push_str(&format!(
+                "\n- {}: {}",
+                sanitize::escape(&file.path, TextKind::Name),
+                sanitize::escape(&file.summary, TextKind::Message)
+            ));
+        }
+        fragment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_summaries_are_read_from_the_reply() {
+        let reply = "- `src/parser.rs`: Handles tabs in indentation.\n\
+            Cargo.lock: Bumps regex.\n\
+            Here is what changed: nothing else\n\
+            * **README.md**: Documents tabs.";
+        let files = parse(reply);
+        assert_eq!(
+            files,
+            vec![
+                FileSummary {
+                    path: "src/parser.rs".to_string(),
+                    summary: "Handles tabs in indentation.".to_string(),
+                },
+                FileSummary {
+                    path: "Cargo.lock".to_string(),
+                    summary: "Bumps regex.".to_string(),
+                },
+                FileSummary {
+                    path: "README.md".to_string(),
+                    summary: "Documents tabs.".to_string(),
+                },
+            ]
+        );
+        let summary = DiffSummary {
+            diff_bytes: 120_000,
+            files,
+        };
+        assert!(summary
+            .prompt_fragment()
+            .ends_with("\n- README.md: Documents tabs."));
+    }
+}
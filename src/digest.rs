@@ -0,0 +1,167 @@
+use crate::git_tools::run_git;
+use crate::history::HistoryBounds;
+use crate::sanitize::{self, TextKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The `digest` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DigestConfig {
+    /// Minimum time between scheduled digests
+    #[serde(default = "default_interval_minutes")]
+    pub interval_minutes: u64,
+    /// Most commits listed in a digest; the totals still count every commit
+    #[serde(default = "default_max_commits")]
+    pub max_commits: usize,
+    #[serde(default = "default_hot_files")]
+    pub hot_files: usize,
+}
+
+fn default_interval_minutes() -> u64 {
+    24 * 60
+}
+
+fn default_max_commits() -> usize {
+    50
+}
+
+fn default_hot_files() -> usize {
+    10
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            interval_minutes: default_interval_minutes(),
+            max_commits: default_max_commits(),
+            hot_files: default_hot_files(),
+        }
+    }
+}
+
+impl DigestConfig {
+    /// Whether a scheduled digest is due at `now`, both in seconds since the epoch.
+    pub fn is_due(&self, last_digest_at: Option<u64>, now: u64) -> bool {
+        match last_digest_at {
+            Some(last) => now.saturating_sub(last) >= self.interval_minutes * 60,
+            None => true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DigestCommit {
+    pub sha: String,
+    pub author: String,
+    pub subject: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Activity {
+    pub name: String,
+    pub commits: usize,
+}
+
+/// Repository activity in a time window.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RepoDigest {
+    /// Start of the window in seconds since the epoch, `None` for the latest commits
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub total_commits: usize,
+    /// Newest first, at most `max_commits`
+    pub commits: Vec<DigestCommit>,
+    /// Authors by number of commits, most active first
+    pub authors: Vec<Activity>,
+    /// Files by number of commits touching them, most active first
+    pub hot_files: Vec<Activity>,
+    /// When the digest was built, in seconds since the epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<u64>,
+}
+
+/// Fields are separated by a unit separator and commits by a record separator.
+const LOG_FORMAT: &str = "--format=%x1e%H%x1f%an%x1f%s";
+
+/// Summarize the commits in `since..until`. Without `since`, the latest `max_commits`
+/// commits are summarized instead.
+pub fn fetch_digest(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    config: &DigestConfig,
+    since: Option<u64>,
+    until: Option<u64>,
+    bounds: &HistoryBounds,
+) -> Result<RepoDigest, String> {
+    let since_arg = since.map(|since| format!("--since=@{}", since));
+    let until_arg = until.map(|until| format!("--until=@{}", until));
+    let max_count = format!("--max-count={}", config.max_commits);
+    let mut args = vec!["log", "--name-only", LOG_FORMAT];
+    args.extend(bounds.args());
+    args.extend(since_arg.as_deref());
+    args.extend(until_arg.as_deref());
+    if since.is_none() {
+        args.push(&max_count);
+    }
+    let output = run_git(git_actor_id, directory, &args)?;
+    Ok(parse_digest_log(&output, config, since, until))
+}
+
+fn parse_digest_log(
+    output: &str,
+    config: &DigestConfig,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> RepoDigest {
+    let mut commits = Vec::new();
+    let mut authors: BTreeMap<String, usize> = BTreeMap::new();
+    let mut files: BTreeMap<String, usize> = BTreeMap::new();
+
+    for record in output.split('\u{1e}') {
+        let mut lines = record.lines();
+        let header = match lines.next() {
+            Some(header) if !header.trim().is_empty() => header,
+            _ => continue,
+        };
+        let mut fields = header.split('\u{1f}');
+        let sha = fields.next().unwrap_or_default().trim().to_string();
+        let author = fields.next().unwrap_or_default().to_string();
+        let subject = fields.next().unwrap_or_default().to_string();
+
+        *authors.entry(author.clone()).or_default() += 1;
+        for path in lines.map(str::trim).filter(|path| !path.is_empty()) {
+            *files
+                .entry(sanitize::escape(path, TextKind::Name))
+                .or_default() += 1;
+        }
+        commits.push(DigestCommit {
+            sha,
+            author,
+            subject,
+        });
+    }
+
+    let total_commits = commits.len();
+    commits.truncate(config.max_commits);
+    let mut hot_files = ranked(files);
+    hot_files.truncate(config.hot_files);
+    RepoDigest {
+        since,
+        until,
+        total_commits,
+        commits,
+        authors: ranked(authors),
+        hot_files,
+        generated_at: None,
+    }
+}
+
+/// Most commits first, ties by name.
+fn ranked(counts: BTreeMap<String, usize>) -> Vec<Activity> {
+    let mut ranked: Vec<Activity> = counts
+        .into_iter()
+        .map(|(name, commits)| Activity { name, commits })
+        .collect();
+    ranked.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+    ranked
+}
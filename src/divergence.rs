@@ -0,0 +1,126 @@
+use crate::git_tools::{ensure_revision, run_git};
+use crate::history::HistoryBounds;
+use serde::{Deserialize, Serialize};
+
+/// Upstream used when none is given: the current branch's tracking branch.
+pub const DEFAULT_UPSTREAM: &str = "@{upstream}";
+
+/// The `divergence_watch` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DivergenceWatch {
+    /// Branch to compare against; the tracking branch when omitted
+    pub upstream: Option<String>,
+    /// Notify once HEAD is at least this many commits behind
+    pub max_behind: Option<usize>,
+    /// Notify once HEAD is at least this many commits ahead
+    pub max_ahead: Option<usize>,
+}
+
+impl DivergenceWatch {
+    pub fn upstream(&self) -> &str {
+        self.upstream.as_deref().unwrap_or(DEFAULT_UPSTREAM)
+    }
+
+    /// Whether `divergence` has crossed one of the thresholds.
+    pub fn is_exceeded(&self, divergence: &Divergence) -> bool {
+        self.max_behind.is_some_and(|max| divergence.behind >= max)
+            || self.max_ahead.is_some_and(|max| divergence.ahead >= max)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DivergingCommit {
+    pub sha: String,
+    pub subject: String,
+}
+
+/// How `HEAD` and an upstream branch have diverged.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Divergence {
+    pub upstream: String,
+    /// Commits on `HEAD` that the upstream doesn't have
+    pub ahead: usize,
+    /// Commits on the upstream that `HEAD` doesn't have
+    pub behind: usize,
+    /// Last commit both share, `None` for unrelated histories
+    pub merge_base: Option<String>,
+    /// Oldest commit on each side after the merge base
+    pub first_local: Option<DivergingCommit>,
+    pub first_upstream: Option<DivergingCommit>,
+}
+
+impl Divergence {
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "HEAD is {} commits ahead of and {} commits behind {}",
+            self.ahead, self.behind, self.upstream
+        );
+        if let Some(merge_base) = &self.merge_base {
+            description.push_str(&format!(" (merge base {})", merge_base));
+        }
+        for (side, commit) in [
+            ("local", &self.first_local),
+            ("upstream", &self.first_upstream),
+        ] {
+            if let Some(commit) = commit {
+                description.push_str(&format!(
+                    "\n- First {} commit after the merge base: {} {}",
+                    side, commit.sha, commit.subject
+                ));
+            }
+        }
+        description
+    }
+}
+
+/// Compare `HEAD` with `upstream`.
+pub fn fetch_divergence(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    upstream: &str,
+    bounds: &HistoryBounds,
+) -> Result<Divergence, String> {
+    ensure_revision("upstream", upstream)?;
+    let git = |args: &[&str]| run_git(git_actor_id, directory, args);
+
+    let symmetric = format!("{}...HEAD", upstream);
+    let counts = git(&["rev-list", "--left-right", "--count", &symmetric])?;
+    let mut counts = counts.split_whitespace().map(str::parse::<usize>);
+    let (behind, ahead) = match (counts.next(), counts.next()) {
+        (Some(Ok(behind)), Some(Ok(ahead))) => (behind, ahead),
+        _ => return Err(format!("Unexpected rev-list output for {}", symmetric)),
+    };
+
+    // merge-base exits non-zero when the histories are unrelated
+    let merge_base = git(&["merge-base", "HEAD", upstream])
+        .ok()
+        .map(|sha| sha.trim().to_string())
+        .filter(|sha| !sha.is_empty());
+
+    // Counting is cheap with a commit graph; only the commit walks are bounded, so with
+    // bounds the first commits are the oldest within them
+    let first_commit = |range: String| -> Result<Option<DivergingCommit>, String> {
+        let mut args = vec!["log", "--reverse", "--format=%H%x1f%s"];
+        args.extend(bounds.args());
+        args.push(&range);
+        let log = git(&args)?;
+        Ok(log.lines().next().and_then(|line| {
+            let (sha, subject) = line.split_once('\u{1f}')?;
+            Some(DivergingCommit {
+                sha: sha.to_string(),
+                subject: subject.to_string(),
+            })
+        }))
+    };
+    let first_local = first_commit(format!("{}..HEAD", upstream))?;
+    let first_upstream = first_commit(format!("HEAD..{}", upstream))?;
+
+    Ok(Divergence {
+        upstream: upstream.to_string(),
+        ahead,
+        behind,
+        merge_base,
+        first_local,
+        first_upstream,
+    })
+}
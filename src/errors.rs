@@ -0,0 +1,128 @@
+//! Machine-readable codes for `GitChatResponse::Error`, so clients can tell a refused
+//! request from a failed one without matching on the message.
+
+use crate::protocol::external::GitChatResponse;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+    /// The request is malformed or can't be served as sent
+    InvalidRequest,
+    /// The session, review, confirmation, or subscriber it names doesn't exist
+    NotFound,
+    /// Another workflow or generation is already running
+    Busy,
+    WorkflowUnknown,
+    /// A child actor, such as a session's chat-state actor, couldn't be reached
+    ChildUnavailable,
+    /// The request or the saved state couldn't be decoded
+    SerializationFailed,
+    /// The assistant has no state to serve the request from
+    Internal,
+    /// A configured policy refuses the request; the message names the setting
+    PolicyViolation,
+    BudgetExceeded,
+    Halted,
+    SecretDetected,
+    TemplateVarsMissing,
+    DebugDisabled,
+    TurnTaken,
+    OutOfScope,
+    SubmodulePointer,
+    /// Commits add binary or oversized files the `large_files` policy refuses
+    FilePolicy,
+    WorkflowStalled,
+    WorkflowTimedOut,
+    PostconditionsFailed,
+    /// A git, exec, or chat operation the request runs failed
+    OperationFailed,
+    /// The init config was broken, so the assistant only reads until `UpdateConfig`
+    /// fixes it
+    SafeMode,
+    /// A code from a newer assistant
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidRequest => "invalid-request",
+            ErrorCode::NotFound => "not-found",
+            ErrorCode::Busy => "busy",
+            ErrorCode::WorkflowUnknown => "workflow-unknown",
+            ErrorCode::ChildUnavailable => "child-unavailable",
+            ErrorCode::SerializationFailed => "serialization-failed",
+            ErrorCode::Internal => "internal",
+            ErrorCode::PolicyViolation => "policy-violation",
+            ErrorCode::BudgetExceeded => "budget-exceeded",
+            ErrorCode::Halted => "halted",
+            ErrorCode::SecretDetected => "secret-detected",
+            ErrorCode::TemplateVarsMissing => "template-vars-missing",
+            ErrorCode::DebugDisabled => "debug-disabled",
+            ErrorCode::TurnTaken => "turn-taken",
+            ErrorCode::OutOfScope => "out-of-scope",
+            ErrorCode::SubmodulePointer => "submodule-pointer",
+            ErrorCode::FilePolicy => "file-policy",
+            ErrorCode::WorkflowStalled => "workflow-stalled",
+            ErrorCode::WorkflowTimedOut => "workflow-timed-out",
+            ErrorCode::PostconditionsFailed => "postconditions-failed",
+            ErrorCode::OperationFailed => "operation-failed",
+            ErrorCode::SafeMode => "safe-mode",
+            ErrorCode::Unknown => "unknown",
+        }
+    }
+
+    /// The code an error message starts with, as in `policy-violation: ...`, for errors
+    /// that pass through `Result<_, String>` before they become a response.
+    pub fn prefix_of(message: &str) -> Option<ErrorCode> {
+        let (prefix, _) = message.split_once(": ")?;
+        match serde_json::from_value(serde_json::Value::String(prefix.to_string())) {
+            Ok(ErrorCode::Unknown) | Err(_) => None,
+            Ok(code) => Some(code),
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+pub fn error(code: ErrorCode, message: impl Into<String>) -> GitChatResponse {
+    GitChatResponse::Error {
+        message: message.into(),
+        code: Some(code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_kebab_case_and_read_from_prefixes() {
+        for code in [
+            ErrorCode::InvalidRequest,
+            ErrorCode::WorkflowTimedOut,
+            ErrorCode::OperationFailed,
+        ] {
+            assert_eq!(
+                serde_json::to_value(code).unwrap(),
+                serde_json::Value::String(code.to_string())
+            );
+        }
+        let code: ErrorCode = serde_json::from_str("\"rate-limited\"").unwrap();
+        assert_eq!(code, ErrorCode::Unknown);
+
+        assert_eq!(
+            ErrorCode::prefix_of("policy-violation: Cannot start rebase workflow"),
+            Some(ErrorCode::PolicyViolation)
+        );
+        assert_eq!(ErrorCode::prefix_of("Failed to commit: boom"), None);
+        assert_eq!(ErrorCode::prefix_of("unknown: boom"), None);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::bindings::theater::simple::message_server_host::request;
+use crate::bindings::theater::simple::supervisor::spawn;
+use crate::git_tools::tool_result_text;
+use crate::logging;
+use crate::protocol::{McpActorRequest, McpResponse};
+use crate::sanitize::{self, TextKind};
+use serde_json::{from_slice, to_vec, Value};
+
+/// Name of the exec MCP tool that runs a shell command.
+const EXEC_COMMAND_TOOL: &str = "execute";
+
+/// Spawn an exec MCP actor owned by the assistant itself, for commands the assistant
+/// runs on its own (such as API diffs) rather than through the model.
+pub fn spawn_exec_tools_actor(manifest_path: &str) -> Result<String, String> {
+    logging::debug("Spawning exec tools actor...");
+    match spawn(manifest_path, None) {
+        Ok(actor_id) => {
+            logging::info(&format!("Exec tools actor spawned: {}", actor_id));
+            Ok(actor_id)
+        }
+        Err(e) => {
+            logging::error(&format!("Failed to spawn exec tools actor: {:?}", e));
+            Err(format!("Spawn failed: {:?}", e))
+        }
+    }
+}
+
+/// `text` quoted for the shell.
+pub fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Run `command` through the exec MCP actor and return the tool's text output.
+pub fn run_command(
+    actor_id: &str,
+    directory: Option<&str>,
+    command: &str,
+) -> Result<String, String> {
+    logging::debug(&format!("Running exec tool: {}", command));
+
+    sanitize::ensure_visible(command, TextKind::Name)
+        .map_err(|e| format!("Refusing to run a command containing {}", e))?;
+
+    let call = McpActorRequest::ToolsCall {
+        name: EXEC_COMMAND_TOOL.to_string(),
+        args: serde_json::json!({
+            "command": command,
+            "directory": directory,
+        }),
+    };
+    let call_bytes =
+        to_vec(&call).map_err(|e| format!("Failed to serialize exec tool call: {}", e))?;
+
+    let response_bytes =
+        request(actor_id, &call_bytes).map_err(|e| format!("Exec tool request failed: {}", e))?;
+    let response: McpResponse = from_slice(&response_bytes)
+        .map_err(|e| format!("Failed to parse exec tool response: {}", e))?;
+
+    if let Some(error) = response.error {
+        return Err(format!("Exec tool error {}: {}", error.code, error.message));
+    }
+
+    let result = response.result.unwrap_or(Value::Null);
+    let text = tool_result_text(&result);
+    if result.get("isError").and_then(Value::as_bool) == Some(true) {
+        return Err(format!("{} failed: {}", command, text));
+    }
+    Ok(text)
+}
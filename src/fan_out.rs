@@ -0,0 +1,251 @@
+//! Fan-out across repositories: `StartFanOut` spawns one child assistant per repository,
+//! hands each the same task as a delegation, and gathers what the children report back
+//! into one `FanOutResult`.
+
+use crate::delegation::{DelegatedTask, DelegationStatus};
+use crate::protocol::external::WorkflowResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+fn default_max_repositories() -> usize {
+    20
+}
+
+/// Children are spawned from `manifest_path`, a git-chat-assistant manifest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FanOutConfig {
+    pub manifest_path: String,
+    /// Most repositories one fan-out may cover
+    #[serde(default = "default_max_repositories")]
+    pub max_repositories: usize,
+}
+
+/// Config fields a child doesn't inherit: it serves no clients, reaches no actors but
+/// the parent, and runs nothing on a timer. Nobody could confirm its requests, and
+/// `StartFanOut` already went through the parent's `confirmation_policy`.
+const PARENT_ONLY_FIELDS: &[&str] = &[
+    "fan_out",
+    "http",
+    "channels",
+    "notify_actor",
+    "notifications",
+    "parent_actor_id",
+    "reply_to",
+    "siblings",
+    "digest",
+    "divergence_watch",
+    "schedule",
+    "dead_man_switch",
+    "idle_timeout_secs",
+    "session_store_id",
+    "worktrees",
+    "pipeline",
+    "workflow",
+    "confirmation_policy",
+];
+
+/// One repository of a fan-out and how its child did.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RepoRun {
+    pub repository: String,
+    /// Unset when the child could not be spawned
+    #[serde(default)]
+    pub actor_id: Option<String>,
+    pub delegation_id: String,
+    pub status: DelegationStatus,
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    /// The child's workflow result; boxed to keep responses small
+    #[serde(default)]
+    pub result: Option<Box<WorkflowResult>>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A fan-out and the runs of its repositories, in the order they were given.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FanOutResult {
+    pub id: String,
+    pub task: DelegatedTask,
+    /// Seconds since the epoch
+    pub started_at: u64,
+    /// Set once every repository's run finished
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    pub runs: Vec<RepoRun>,
+    #[serde(default)]
+    pub completed: usize,
+    #[serde(default)]
+    pub failed: usize,
+}
+
+/// The init state of the child for `repository`: the parent's config, working in
+/// `repository`, without the fields only the parent uses.
+pub fn child_config(parent_config: &Value, repository: &str) -> Value {
+    let mut config = parent_config.clone();
+    if let Some(fields) = config.as_object_mut() {
+        for field in PARENT_ONLY_FIELDS {
+            fields.remove(*field);
+        }
+        fields.insert(
+            "current_directory".to_string(),
+            Value::String(repository.to_string()),
+        );
+    }
+    config
+}
+
+/// Repositories once each, in the order given; blank entries are an error.
+pub fn validate_repositories(
+    repositories: &[String],
+    config: &FanOutConfig,
+) -> Result<Vec<String>, String> {
+    let mut unique: Vec<String> = Vec::new();
+    for repository in repositories {
+        let repository = repository.trim();
+        if repository.is_empty() {
+            return Err("A repository path is empty".to_string());
+        }
+        if !unique.iter().any(|seen| seen == repository) {
+            unique.push(repository.to_string());
+        }
+    }
+    if unique.is_empty() {
+        return Err("No repositories to fan out to".to_string());
+    }
+    if unique.len() > config.max_repositories {
+        return Err(format!(
+            "{} repositories is more than max_repositories ({})",
+            unique.len(),
+            config.max_repositories
+        ));
+    }
+    Ok(unique)
+}
+
+impl RepoRun {
+    pub fn finish(&mut self, result: Option<WorkflowResult>, error: Option<String>, now: u64) {
+        let error = error.or_else(|| result.as_ref().and_then(|result| result.error.clone()));
+        self.status = match error {
+            Some(_) => DelegationStatus::Failed,
+            None => DelegationStatus::Completed,
+        };
+        self.finished_at = Some(now);
+        self.result = result.map(Box::new);
+        self.error = error;
+    }
+}
+
+impl FanOutResult {
+    pub fn is_running(&self) -> bool {
+        self.finished_at.is_none()
+    }
+
+    /// Count the finished runs, and mark the fan-out finished at `now` once none is
+    /// running. Returns whether it just finished.
+    pub fn tally(&mut self, now: u64) -> bool {
+        let count = |status| self.runs.iter().filter(|run| run.status == status).count();
+        self.completed = count(DelegationStatus::Completed);
+        self.failed = count(DelegationStatus::Failed);
+        let done = self.completed + self.failed == self.runs.len();
+        if done && self.finished_at.is_none() {
+            self.finished_at = Some(now);
+            return true;
+        }
+        false
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "The {} fan-out over {} repositories finished: {} completed, {} failed",
+            self.task.workflow.name(),
+            self.runs.len(),
+            self.completed,
+            self.failed
+        );
+        for run in &self.runs {
+            let outcome = match (&run.error, run.result.as_ref()) {
+                (Some(error), _) => format!("failed: {}", error),
+                (None, Some(result)) => result
+                    .summary
+                    .clone()
+                    .unwrap_or_else(|| format!("{} commits", result.commits_created.len())),
+                (None, None) => "completed".to_string(),
+            };
+            summary.push_str(&format!("\n- {}: {}", run.repository, outcome));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::Workflow;
+    use serde_json::json;
+
+    #[test]
+    fn children_work_in_their_repository_and_runs_are_tallied() {
+        let parent = json!({
+            "current_directory": "/work/parent",
+            "fan_out": {"manifest_path": "/actors/git-chat/manifest.toml"},
+            "notify_actor": "observer-1",
+            "confirmation_policy": {"local_mutation": "always"},
+            "commit_convention": {"style": "conventional"},
+        });
+        assert_eq!(
+            child_config(&parent, "/work/api"),
+            json!({
+                "current_directory": "/work/api",
+                "commit_convention": {"style": "conventional"},
+            })
+        );
+
+        let config = FanOutConfig {
+            manifest_path: "manifest.toml".to_string(),
+            max_repositories: 2,
+        };
+        let repositories = vec![
+            "/work/api".to_string(),
+            " /work/web ".to_string(),
+            "/work/api".to_string(),
+        ];
+        assert_eq!(
+            validate_repositories(&repositories, &config).unwrap(),
+            vec!["/work/api", "/work/web"]
+        );
+        assert!(validate_repositories(&[], &config).is_err());
+        assert!(validate_repositories(&["a".into(), "b".into(), "c".into()], &config).is_err());
+
+        let run = |repository: &str| RepoRun {
+            repository: repository.to_string(),
+            actor_id: Some(format!("actor-{}", repository)),
+            delegation_id: format!("fan-out-1-{}", repository),
+            status: DelegationStatus::Running,
+            finished_at: None,
+            result: None,
+            error: None,
+        };
+        let mut fan_out = FanOutResult {
+            id: "fan-out-1".to_string(),
+            task: DelegatedTask {
+                workflow: Workflow::Commit,
+                instructions: None,
+            },
+            started_at: 100,
+            finished_at: None,
+            runs: vec![run("api"), run("web")],
+            completed: 0,
+            failed: 0,
+        };
+        fan_out.runs[0].finish(None, None, 110);
+        assert!(!fan_out.tally(110));
+        fan_out.runs[1].finish(None, Some("nothing to commit".to_string()), 120);
+        assert!(fan_out.tally(120));
+        assert_eq!((fan_out.completed, fan_out.failed), (1, 1));
+        assert_eq!(fan_out.finished_at, Some(120));
+        assert!(fan_out
+            .summary()
+            .ends_with("- api: completed\n- web: failed: nothing to commit"));
+    }
+}
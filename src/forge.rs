@@ -0,0 +1,118 @@
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeProvider {
+    Github,
+    Gitlab,
+}
+
+impl ForgeProvider {
+    fn name(&self) -> &'static str {
+        match self {
+            ForgeProvider::Github => "GitHub",
+            ForgeProvider::Gitlab => "GitLab",
+        }
+    }
+
+    /// What the provider calls a pull request.
+    pub fn change_request(&self) -> &'static str {
+        match self {
+            ForgeProvider::Github => "pull request",
+            ForgeProvider::Gitlab => "merge request",
+        }
+    }
+}
+
+/// The `forge_mcp` section of the assistant config: an MCP actor exposing the
+/// repository's forge (pull requests, review comments, issues) to the model.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ForgeConfig {
+    pub manifest_path: String,
+    pub provider: ForgeProvider,
+    /// Repository on the forge, as `owner/name` (a project path on GitLab)
+    pub repo: String,
+    /// Passed to the forge actor as its init state
+    pub init_state: Option<Value>,
+    /// Push and open a pull request once the commit workflow has committed
+    #[serde(default)]
+    pub open_pr_after_commit: bool,
+    /// Regenerate the changelog section of the branch's open pull request whenever the
+    /// assistant has committed
+    #[serde(default)]
+    pub update_pr_changelog: bool,
+    /// Most review comments `PublishReview` posts at once; the rest wait for the next call
+    #[serde(default = "default_max_review_comments")]
+    pub max_review_comments: usize,
+}
+
+fn default_max_review_comments() -> usize {
+    30
+}
+
+impl ForgeConfig {
+    /// Entry for the chat-state actor's MCP server list.
+    pub fn mcp_server(&self) -> Value {
+        let mut actor = serde_json::json!({
+            "manifest_path": self.manifest_path,
+        });
+        if let Some(init_state) = &self.init_state {
+            actor["init_state"] = init_state.clone();
+        }
+        serde_json::json!({
+            "actor_id": null,
+            "actor": actor,
+            "tools": null
+        })
+    }
+
+    pub fn prompt_fragment(&self, workflow: Option<&Workflow>) -> String {
+        let change_request = self.provider.change_request();
+        let mut fragment = format!(
+            "\n\nFORGE: The repository is hosted on {} as {}. Your forge tools can open \
+            {}s, read their review comments, and look up issues.\n\
+            - Link related issues in commit messages and {} descriptions (e.g. \"Fixes #123\"); \
+            look them up first instead of guessing numbers\n\
+            - Never merge or close a {} or issue unless explicitly asked",
+            self.provider.name(),
+            self.repo,
+            change_request,
+            change_request,
+            change_request
+        );
+        if self.update_pr_changelog {
+            fragment.push_str(&format!(
+                "\n- The {} description's Changelog section is kept up to date \
+                automatically after each commit; don't edit it yourself",
+                change_request
+            ));
+        }
+        match workflow {
+            Some(Workflow::Commit) if self.open_pr_after_commit => {
+                fragment.push_str(&format!(
+                    "\n- After the commits are created, push the branch and open a {} \
+                    for it before calling task_complete",
+                    change_request
+                ));
+            }
+            Some(Workflow::Review) => {
+                fragment.push_str(&format!(
+                    "\n- Before reviewing, fetch the review comments already left on this \
+                    branch's {} and don't repeat them",
+                    change_request
+                ));
+            }
+            Some(Workflow::PrDescription) => {
+                fragment.push_str(&format!(
+                    "\n- Look up the issues the branch addresses and link them in the \
+                    description; don't open the {} yourself",
+                    change_request
+                ));
+            }
+            _ => {}
+        }
+        fragment
+    }
+}
@@ -0,0 +1,262 @@
+use crate::bindings::theater::simple::message_server_host::request;
+use crate::bindings::theater::simple::supervisor::spawn;
+use crate::forge::{ForgeConfig, ForgeProvider};
+use crate::git_tools::tool_result_text;
+use crate::logging;
+use crate::protocol::{McpActorRequest, McpResponse};
+use crate::review_report::ReviewComment;
+use serde_json::{from_slice, from_str, json, to_vec, Value};
+
+/// Comments sent in one forge tool call (one review on GitHub); larger sets are split.
+pub const COMMENTS_PER_BATCH: usize = 10;
+
+/// Spawn a forge MCP actor owned by the assistant itself, for posting reviews.
+pub fn spawn_forge_tools_actor(config: &ForgeConfig) -> Result<String, String> {
+    logging::debug("Spawning forge tools actor...");
+    let init_state = match &config.init_state {
+        Some(init_state) => Some(
+            to_vec(init_state).map_err(|e| format!("Failed to serialize forge state: {}", e))?,
+        ),
+        None => None,
+    };
+    match spawn(&config.manifest_path, init_state.as_deref()) {
+        Ok(actor_id) => {
+            logging::info(&format!("Forge tools actor spawned: {}", actor_id));
+            Ok(actor_id)
+        }
+        Err(e) => {
+            logging::error(&format!("Failed to spawn forge tools actor: {:?}", e));
+            Err(format!("Spawn failed: {:?}", e))
+        }
+    }
+}
+
+/// Call the forge tool `name` and return its text output.
+fn call_tool(actor_id: &str, name: &str, args: Value) -> Result<String, String> {
+    logging::debug(&format!("Calling forge tool: {}", name));
+    let call = McpActorRequest::ToolsCall {
+        name: name.to_string(),
+        args,
+    };
+    let call_bytes =
+        to_vec(&call).map_err(|e| format!("Failed to serialize forge tool call: {}", e))?;
+
+    let response_bytes =
+        request(actor_id, &call_bytes).map_err(|e| format!("Forge tool request failed: {}", e))?;
+    let response: McpResponse = from_slice(&response_bytes)
+        .map_err(|e| format!("Failed to parse forge tool response: {}", e))?;
+
+    if let Some(error) = response.error {
+        return Err(format!(
+            "Forge tool error {}: {}",
+            error.code, error.message
+        ));
+    }
+
+    let result = response.result.unwrap_or(Value::Null);
+    let text = tool_result_text(&result);
+    if result.get("isError").and_then(Value::as_bool) == Some(true) {
+        return Err(format!("{} failed: {}", name, text));
+    }
+    Ok(text)
+}
+
+/// `owner` and `name` of a GitHub repository.
+fn github_repo(config: &ForgeConfig) -> Result<(&str, &str), String> {
+    config
+        .repo
+        .split_once('/')
+        .ok_or_else(|| format!("Expected owner/name, got {}", config.repo))
+}
+
+/// Number of the open pull request (iid of the merge request) for `branch`.
+pub fn find_change_request(
+    actor_id: &str,
+    config: &ForgeConfig,
+    branch: &str,
+) -> Result<u64, String> {
+    let (tool, args, number_field) = match config.provider {
+        ForgeProvider::Github => {
+            let (owner, repo) = github_repo(config)?;
+            (
+                "list_pull_requests",
+                json!({
+                    "owner": owner,
+                    "repo": repo,
+                    "head": format!("{}:{}", owner, branch),
+                    "state": "open",
+                }),
+                "number",
+            )
+        }
+        ForgeProvider::Gitlab => (
+            "list_merge_requests",
+            json!({
+                "project_id": config.repo,
+                "source_branch": branch,
+                "state": "opened",
+            }),
+            "iid",
+        ),
+    };
+    let output = call_tool(actor_id, tool, args)?;
+    let listed: Value =
+        from_str(&output).map_err(|e| format!("Unexpected {} output: {}", tool, e))?;
+    listed
+        .as_array()
+        .and_then(|requests| requests.first())
+        .and_then(|first| first.get(number_field))
+        .and_then(Value::as_u64)
+        .ok_or_else(|| {
+            format!(
+                "No open {} for branch {}",
+                config.provider.change_request(),
+                branch
+            )
+        })
+}
+
+/// Post one batch of comments: those with a line inline on `commit`, the rest together
+/// as one general comment. `base` is the commit the branch was reviewed against.
+pub fn post_review_batch(
+    actor_id: &str,
+    config: &ForgeConfig,
+    number: u64,
+    base: &str,
+    commit: &str,
+    comments: &[ReviewComment],
+) -> Result<(), String> {
+    let (inline, general): (Vec<&ReviewComment>, Vec<&ReviewComment>) =
+        comments.iter().partition(|comment| comment.line.is_some());
+    let body = general
+        .iter()
+        .map(|comment| format!("`{}`: {}", comment.path, comment.body))
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n");
+
+    match config.provider {
+        ForgeProvider::Github => {
+            let (owner, repo) = github_repo(config)?;
+            let comments: Vec<Value> = inline
+                .iter()
+                .map(|comment| {
+                    json!({
+                        "path": comment.path,
+                        "line": comment.line,
+                        "body": comment.body,
+                    })
+                })
+                .collect();
+            call_tool(
+                actor_id,
+                "create_pull_request_review",
+                json!({
+                    "owner": owner,
+                    "repo": repo,
+                    "pullNumber": number,
+                    "commitId": commit,
+                    "event": "COMMENT",
+                    "body": body,
+                    "comments": comments,
+                }),
+            )?;
+        }
+        // GitLab has no batched review call; each inline comment is its own thread
+        ForgeProvider::Gitlab => {
+            for comment in inline {
+                call_tool(
+                    actor_id,
+                    "create_merge_request_thread",
+                    json!({
+                        "project_id": config.repo,
+                        "merge_request_iid": number,
+                        "body": comment.body,
+                        "position": {
+                            "position_type": "text",
+                            "base_sha": base,
+                            "start_sha": base,
+                            "head_sha": commit,
+                            "new_path": comment.path,
+                            "new_line": comment.line,
+                        },
+                    }),
+                )?;
+            }
+            if !body.is_empty() {
+                call_tool(
+                    actor_id,
+                    "create_merge_request_note",
+                    json!({
+                        "project_id": config.repo,
+                        "merge_request_iid": number,
+                        "body": body,
+                    }),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Description of pull request (merge request) `number`.
+pub fn fetch_change_request_body(
+    actor_id: &str,
+    config: &ForgeConfig,
+    number: u64,
+) -> Result<String, String> {
+    let (tool, args, body_field) = match config.provider {
+        ForgeProvider::Github => {
+            let (owner, repo) = github_repo(config)?;
+            (
+                "get_pull_request",
+                json!({"owner": owner, "repo": repo, "pullNumber": number}),
+                "body",
+            )
+        }
+        ForgeProvider::Gitlab => (
+            "get_merge_request",
+            json!({"project_id": config.repo, "merge_request_iid": number}),
+            "description",
+        ),
+    };
+    let output = call_tool(actor_id, tool, args)?;
+    let change_request: Value =
+        from_str(&output).map_err(|e| format!("Unexpected {} output: {}", tool, e))?;
+    // An empty description comes back as null
+    Ok(change_request
+        .get(body_field)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Replace the description of pull request (merge request) `number`.
+pub fn update_change_request_body(
+    actor_id: &str,
+    config: &ForgeConfig,
+    number: u64,
+    body: &str,
+) -> Result<(), String> {
+    match config.provider {
+        ForgeProvider::Github => {
+            let (owner, repo) = github_repo(config)?;
+            call_tool(
+                actor_id,
+                "update_pull_request",
+                json!({"owner": owner, "repo": repo, "pullNumber": number, "body": body}),
+            )?;
+        }
+        ForgeProvider::Gitlab => {
+            call_tool(
+                actor_id,
+                "update_merge_request",
+                json!({
+                    "project_id": config.repo,
+                    "merge_request_iid": number,
+                    "description": body,
+                }),
+            )?;
+        }
+    }
+    Ok(())
+}
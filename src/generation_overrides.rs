@@ -0,0 +1,98 @@
+//! Generation parameters for a single `AddMessage`, e.g. from a temperature slider: the
+//! session's chat-state actor runs the generation the message starts on them, and gets
+//! the session's own parameters back once it ends. The session config isn't changed.
+
+use crate::config_patch::GenerationConfig;
+use crate::model_preset::ModelPreset;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct GenerationOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// One of the `model_presets`; `temperature` and `max_tokens` apply on top of it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+}
+
+impl GenerationOverrides {
+    /// `current` with these overrides, if the result is valid.
+    pub fn apply(
+        &self,
+        current: &GenerationConfig,
+        presets: &BTreeMap<String, ModelPreset>,
+    ) -> Result<GenerationConfig, String> {
+        let base = match &self.preset {
+            Some(name) => presets
+                .get(name)
+                .ok_or_else(|| format!("unknown model preset {}", name))?
+                .generation_config(current),
+            None => current.clone(),
+        };
+        let mut patch = Map::new();
+        if let Some(temperature) = self.temperature {
+            patch.insert("temperature".to_string(), serde_json::json!(temperature));
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            patch.insert("max_tokens".to_string(), serde_json::json!(max_tokens));
+        }
+        if patch.is_empty() {
+            return match self.preset {
+                Some(_) => Ok(base),
+                None => Err("the overrides are empty".to_string()),
+            };
+        }
+        base.apply(&Value::Object(patch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn overrides_apply_over_the_preset() {
+        let current = GenerationConfig::from_chat_config(&json!({
+            "model_config": {"model": "claude-sonnet-4", "provider": "anthropic"},
+            "temperature": 0.7,
+            "max_tokens": 8192
+        }))
+        .unwrap();
+        let presets: BTreeMap<String, ModelPreset> = serde_json::from_value(json!({
+            "fast": {
+                "model_config": {"model": "claude-haiku-4", "provider": "anthropic"},
+                "max_tokens": 2048
+            }
+        }))
+        .unwrap();
+
+        let overrides = GenerationOverrides {
+            temperature: Some(0.1),
+            preset: Some("fast".to_string()),
+            ..GenerationOverrides::default()
+        };
+        let config = overrides.apply(&current, &presets).unwrap();
+        assert_eq!(config.model_config.model, "claude-haiku-4");
+        assert_eq!(config.temperature, 0.1);
+        assert_eq!(config.max_tokens, 2048);
+
+        let too_hot = GenerationOverrides {
+            temperature: Some(3.0),
+            ..GenerationOverrides::default()
+        };
+        assert!(too_hot.apply(&current, &presets).is_err());
+        let unknown = GenerationOverrides {
+            preset: Some("quality".to_string()),
+            ..GenerationOverrides::default()
+        };
+        assert!(unknown.apply(&current, &presets).is_err());
+        assert!(GenerationOverrides::default()
+            .apply(&current, &presets)
+            .is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::bindings::theater::simple::message_server_host::request;
+use crate::bindings::theater::simple::supervisor::spawn;
+use crate::logging;
+use crate::protocol::{McpActorRequest, McpResponse};
+use crate::sanitize::{self, TextKind};
+use serde_json::{from_slice, to_vec, Value};
+
+/// Name of the git MCP tool that runs a git subcommand with the given arguments.
+const GIT_COMMAND_TOOL: &str = "git";
+
+/// Spawn a git MCP actor owned by the assistant itself.
+///
+/// The chat-state actor gets its own git tools for the model, unless `tool_output`
+/// routes them through the assistant. The assistant's own actor is used for checks it
+/// enforces before handing control to the model.
+pub fn spawn_git_tools_actor(
+    manifest_path: &str,
+    init_state: Option<&Value>,
+) -> Result<String, String> {
+    logging::debug("Spawning git tools actor...");
+    let init_state = match init_state {
+        Some(init_state) => Some(
+            to_vec(init_state)
+                .map_err(|e| format!("Failed to serialize git tools state: {}", e))?,
+        ),
+        None => None,
+    };
+    match spawn(manifest_path, init_state.as_deref()) {
+        Ok(actor_id) => {
+            logging::info(&format!("Git tools actor spawned: {}", actor_id));
+            Ok(actor_id)
+        }
+        Err(e) => {
+            logging::error(&format!("Failed to spawn git tools actor: {:?}", e));
+            Err(format!("Spawn failed: {:?}", e))
+        }
+    }
+}
+
+/// Run `git <args>` through the git MCP actor and return the tool's text output.
+pub fn run_git(actor_id: &str, directory: Option<&str>, args: &[&str]) -> Result<String, String> {
+    logging::debug(&format!("Running git tool: git {}", args.join(" ")));
+
+    // Arguments may carry repository or model content; never let hidden characters through
+    for arg in args {
+        sanitize::ensure_visible(arg, TextKind::Message)
+            .map_err(|e| format!("Refusing to run git with an argument containing {}", e))?;
+    }
+
+    let call = McpActorRequest::ToolsCall {
+        name: GIT_COMMAND_TOOL.to_string(),
+        args: serde_json::json!({
+            "args": args,
+            "directory": directory,
+        }),
+    };
+    let call_bytes =
+        to_vec(&call).map_err(|e| format!("Failed to serialize git tool call: {}", e))?;
+
+    let response_bytes =
+        request(actor_id, &call_bytes).map_err(|e| format!("Git tool request failed: {}", e))?;
+    let response: McpResponse = from_slice(&response_bytes)
+        .map_err(|e| format!("Failed to parse git tool response: {}", e))?;
+
+    if let Some(error) = response.error {
+        return Err(format!("Git tool error {}: {}", error.code, error.message));
+    }
+
+    let result = response.result.unwrap_or(Value::Null);
+    let text = tool_result_text(&result);
+    if result.get("isError").and_then(Value::as_bool) == Some(true) {
+        return Err(format!("git {} failed: {}", args.join(" "), text));
+    }
+    Ok(text)
+}
+
+/// Refuse a revision, range, ref or path a client named that git would read as an
+/// option, e.g. a range of `--output=<file>`. `kind` names it in the error.
+pub fn ensure_revision(kind: &str, value: &str) -> Result<(), String> {
+    if value.starts_with('-') {
+        return Err(format!("Invalid {}: {}", kind, value));
+    }
+    Ok(())
+}
+
+/// Concatenate the text content blocks of an MCP tool result.
+pub fn tool_result_text(result: &Value) -> String {
+    result
+        .get("content")
+        .and_then(Value::as_array)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter_map(|block| block.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn option_like_revisions_are_refused() {
+        assert!(ensure_revision("range", "main..feature").is_ok());
+        assert!(ensure_revision("range", "HEAD~3").is_ok());
+        assert_eq!(
+            ensure_revision("range", "--output=/tmp/x").unwrap_err(),
+            "Invalid range: --output=/tmp/x"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Bounds and caching for history queries, so analysis stays responsive on
+//! repositories with very long histories.
+
+use crate::git_tools::{ensure_revision, run_git};
+use crate::logging;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// The `performance_mode` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PerformanceMode {
+    /// Most commits any history query walks
+    #[serde(default = "default_max_commits")]
+    pub max_commits: usize,
+    /// Only walk commits from the last this many days
+    pub max_age_days: Option<u64>,
+    /// Parsed query results kept, keyed by the commits they were computed from
+    #[serde(default = "default_cache_entries")]
+    pub cache_entries: usize,
+    /// Write a commit graph when the repository doesn't have one
+    #[serde(default = "default_write_commit_graph")]
+    pub write_commit_graph: bool,
+}
+
+fn default_max_commits() -> usize {
+    10_000
+}
+
+fn default_cache_entries() -> usize {
+    32
+}
+
+fn default_write_commit_graph() -> bool {
+    true
+}
+
+impl Default for PerformanceMode {
+    fn default() -> Self {
+        Self {
+            max_commits: default_max_commits(),
+            max_age_days: None,
+            cache_entries: default_cache_entries(),
+            write_commit_graph: default_write_commit_graph(),
+        }
+    }
+}
+
+impl PerformanceMode {
+    pub fn bounds(&self) -> HistoryBounds {
+        let mut args = vec![format!("--max-count={}", self.max_commits)];
+        if let Some(days) = self.max_age_days {
+            args.push(format!("--since={}.days.ago", days));
+        }
+        HistoryBounds { args }
+    }
+}
+
+/// Extra `git log` / `git rev-list` arguments limiting how much history a query walks.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryBounds {
+    args: Vec<String>,
+}
+
+impl HistoryBounds {
+    pub fn args(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().map(String::as_str)
+    }
+}
+
+/// Write a commit graph unless the repository already has a valid one. Returns whether
+/// one was written.
+pub fn ensure_commit_graph(git_actor_id: &str, directory: Option<&str>) -> Result<bool, String> {
+    if run_git(git_actor_id, directory, &["commit-graph", "verify"]).is_ok() {
+        return Ok(false);
+    }
+    run_git(
+        git_actor_id,
+        directory,
+        &["commit-graph", "write", "--reachable", "--changed-paths"],
+    )?;
+    logging::info("Wrote commit graph");
+    Ok(true)
+}
+
+/// Key for a query over `revisions`, resolved to commit ids so moved refs miss the cache.
+pub fn cache_key(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    query: &str,
+    revisions: &[&str],
+) -> Result<String, String> {
+    let mut key = query.to_string();
+    for revision in revisions {
+        ensure_revision("revision", revision)?;
+        let resolved = run_git(git_actor_id, directory, &["rev-parse", revision])?;
+        key.push('\u{1f}');
+        key.push_str(&resolved.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+    Ok(key)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CacheEntry {
+    key: String,
+    value: Value,
+}
+
+/// Least-recently-used cache of parsed query results, kept in the actor state.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HistoryCache {
+    entries: VecDeque<CacheEntry>,
+}
+
+impl HistoryCache {
+    pub fn get<T: DeserializeOwned>(&mut self, key: &str) -> Option<T> {
+        let index = self.entries.iter().position(|entry| entry.key == key)?;
+        let entry = self.entries.remove(index)?;
+        let value = serde_json::from_value(entry.value.clone()).ok();
+        self.entries.push_back(entry);
+        value
+    }
+
+    pub fn insert<T: Serialize>(&mut self, key: String, value: &T, capacity: usize) {
+        let value = match serde_json::to_value(value) {
+            Ok(value) => value,
+            Err(e) => {
+                logging::warn(&format!("Not caching {}: {}", key, e));
+                return;
+            }
+        };
+        self.entries.retain(|entry| entry.key != key);
+        self.entries.push_back(CacheEntry { key, value });
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Drop every entry; returns how many there were.
+    pub fn clear(&mut self) -> usize {
+        let dropped = self.entries.len();
+        self.entries.clear();
+        dropped
+    }
+}
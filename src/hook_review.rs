@@ -0,0 +1,118 @@
+//! The `hook-review` workflow: the model is given the output of failing git hooks, fixes
+//! what they complain about, and stages the fixes, e.g. when a hook wrapper invokes the
+//! assistant after `pre-commit` fails.
+
+use crate::git_tools::run_git;
+use crate::sanitize::{self, TextKind};
+use crate::tool_output::ToolOutputPolicy;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HookReviewConfig {
+    /// Output of the failing hooks; without it the model asks for it
+    #[serde(default)]
+    pub output: Option<String>,
+    /// Hook that failed, as the model is told
+    #[serde(default = "default_hook")]
+    pub hook: String,
+}
+
+fn default_hook() -> String {
+    "pre-commit".to_string()
+}
+
+impl Default for HookReviewConfig {
+    fn default() -> Self {
+        Self {
+            output: None,
+            hook: default_hook(),
+        }
+    }
+}
+
+fn list_paths(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Files staged for the commit the hooks ran on.
+pub fn fetch_staged(git_actor_id: &str, directory: Option<&str>) -> Result<Vec<String>, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--cached", "--name-only"],
+    )?;
+    Ok(list_paths(&output))
+}
+
+/// Files of `staged` with changes that aren't staged, i.e. fixes left out of the commit.
+pub fn unstaged_fixes(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    staged: &[String],
+) -> Result<Vec<String>, String> {
+    let output = run_git(git_actor_id, directory, &["diff", "--name-only"])?;
+    Ok(list_paths(&output)
+        .into_iter()
+        .filter(|path| staged.contains(path))
+        .collect())
+}
+
+/// Block for the opening message: the hook output, elided like tool output, and the
+/// staged files.
+pub fn describe(config: &HookReviewConfig, staged: &[String], policy: &ToolOutputPolicy) -> String {
+    let mut block = match config
+        .output
+        .as_deref()
+        .filter(|output| !output.trim().is_empty())
+    {
+        Some(output) => format!(
+            "\n\nHOOK OUTPUT: The {} hook failed with:\n```\n{}\n```",
+            config.hook,
+            sanitize::escape(&policy.elide(output.trim_end()), TextKind::Message)
+        ),
+        None => format!(
+            "\n\nHOOK OUTPUT: none was given. Ask me to paste the output of the failing {} \
+            hook before diagnosing anything.",
+            config.hook
+        ),
+    };
+    if staged.is_empty() {
+        block.push_str("\nNo files are staged.");
+    } else {
+        block.push_str("\nSTAGED FILES:");
+        for path in staged {
+            block.push_str(&format!("\n- {}", sanitize::escape(path, TextKind::Name)));
+        }
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_output_and_staged_files_are_described() {
+        let config = HookReviewConfig {
+            output: Some("ruff....Failed\nsrc/app.py:3:1: F401 unused import\n".to_string()),
+            ..HookReviewConfig::default()
+        };
+        let staged = vec!["src/app.py".to_string()];
+        let block = describe(&config, &staged, &ToolOutputPolicy::default());
+        assert!(block.contains("The pre-commit hook failed with:\n```\nruff....Failed\n"));
+        assert!(block.ends_with("STAGED FILES:\n- src/app.py"));
+
+        let block = describe(
+            &HookReviewConfig::default(),
+            &[],
+            &ToolOutputPolicy::default(),
+        );
+        assert!(block.contains("Ask me to paste the output"));
+        assert!(block.ends_with("No files are staged."));
+    }
+}
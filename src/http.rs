@@ -0,0 +1,258 @@
+//! Optional HTTP transport, enabled with the `http` config section.
+//!
+//! It exposes the same protocol as the message server, so web frontends can talk to
+//! the assistant without a bridge actor:
+//!
+//! - `POST /requests` takes a request envelope as its body and answers with the
+//!   response envelope, exactly like `handle_request`.
+//! - `GET /events` answers with the buffered events as a `text/event-stream`. Handlers
+//!   can't hold a response open, so each call returns what is buffered after the
+//!   `Last-Event-ID` header and ends; `EventSource` reconnects after the `retry` delay
+//!   and sends the id of the last event it saw.
+
+use crate::bindings::theater::simple::http_framework::{
+    add_route, create_server, register_handler, start_server,
+};
+use crate::bindings::theater::simple::http_types::{HttpRequest, HttpResponse, ServerConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Handler name the runtime passes `POST /requests` to.
+const REQUESTS_HANDLER: &str = "requests";
+/// Handler name the runtime passes `GET /events` to.
+const EVENTS_HANDLER: &str = "events";
+
+/// How long `EventSource` waits before asking for more events, in milliseconds.
+const RETRY_MILLIS: u64 = 1000;
+
+/// The `http` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HttpConfig {
+    /// Port to listen on; the runtime picks one when unset
+    pub port: Option<u16>,
+    #[serde(default = "default_host")]
+    pub host: String,
+    /// Events kept for `GET /events`; older ones are dropped
+    #[serde(default = "default_max_buffered_events")]
+    pub max_buffered_events: usize,
+    /// Origin allowed to call the endpoints from a browser, e.g.
+    /// `http://localhost:5173`; requests with any other `Origin` are refused, and every
+    /// request with one when unset
+    pub allow_origin: Option<String>,
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_max_buffered_events() -> usize {
+    100
+}
+
+/// The running server and the handlers its routes call.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HttpServer {
+    pub server_id: u64,
+    pub port: u16,
+    pub requests_handler: u64,
+    pub events_handler: u64,
+}
+
+/// A published event, kept for `GET /events`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BufferedEvent {
+    pub id: u64,
+    /// The event's kind, used as the SSE event name
+    pub kind: String,
+    /// The event envelope as JSON
+    pub data: String,
+}
+
+/// Create and start the server described by `config`.
+pub fn start(config: &HttpConfig) -> Result<HttpServer, String> {
+    let server_id = create_server(&ServerConfig {
+        port: config.port,
+        host: Some(config.host.clone()),
+        tls_config: None,
+    })
+    .map_err(|e| format!("Failed to create HTTP server: {}", e))?;
+    let requests_handler = register_handler(REQUESTS_HANDLER)
+        .map_err(|e| format!("Failed to register {} handler: {}", REQUESTS_HANDLER, e))?;
+    let events_handler = register_handler(EVENTS_HANDLER)
+        .map_err(|e| format!("Failed to register {} handler: {}", EVENTS_HANDLER, e))?;
+
+    let mut routes = vec![
+        ("/requests", "POST", requests_handler),
+        ("/events", "GET", events_handler),
+    ];
+    // Browsers send a preflight before cross-origin JSON posts
+    if config.allow_origin.is_some() {
+        routes.push(("/requests", "OPTIONS", requests_handler));
+    }
+    for (path, method, handler) in routes {
+        add_route(server_id, path, method, handler)
+            .map_err(|e| format!("Failed to add route {} {}: {}", method, path, e))?;
+    }
+
+    let port =
+        start_server(server_id).map_err(|e| format!("Failed to start HTTP server: {}", e))?;
+    Ok(HttpServer {
+        server_id,
+        port,
+        requests_handler,
+        events_handler,
+    })
+}
+
+/// Buffer an event, dropping the oldest once there are `max_buffered_events`.
+pub fn buffer_event(
+    events: &mut VecDeque<BufferedEvent>,
+    max_buffered_events: usize,
+    event: BufferedEvent,
+) {
+    while !events.is_empty() && events.len() >= max_buffered_events {
+        events.pop_front();
+    }
+    if max_buffered_events > 0 {
+        events.push_back(event);
+    }
+}
+
+/// Value of the first `name` header of `request`, ignoring case.
+pub fn header<'a>(request: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    request
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// The refusal of a request a browser sent from another origin than `allow_origin`, or
+/// of a `POST` whose body isn't declared JSON. A page can send a form or `text/plain`
+/// post to any origin without a preflight; only the response is kept from it, so such
+/// requests must not run at all.
+pub fn refusal(request: &HttpRequest, config: &HttpConfig) -> Option<HttpResponse> {
+    if let Some(origin) = header(request, "Origin") {
+        if config.allow_origin.as_deref() != Some(origin) {
+            return Some(text_response(403, "Origin not allowed", config));
+        }
+    }
+    let is_json = header(request, "Content-Type").is_some_and(|content_type| {
+        content_type
+            .split(';')
+            .next()
+            .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"))
+    });
+    if request.method.eq_ignore_ascii_case("POST") && !is_json {
+        return Some(text_response(
+            415,
+            "Requests must be sent as application/json",
+            config,
+        ));
+    }
+    None
+}
+
+/// The events after `Last-Event-ID`, as an SSE stream.
+pub fn events_response(
+    events: &VecDeque<BufferedEvent>,
+    request: &HttpRequest,
+    config: &HttpConfig,
+) -> HttpResponse {
+    let last_event_id = header(request, "Last-Event-ID").and_then(|id| id.trim().parse().ok());
+    let mut body = format!("retry: {}\n\n", RETRY_MILLIS);
+    for event in events
+        .iter()
+        .filter(|event| last_event_id.is_none_or(|last| event.id > last))
+    {
+        body.push_str(&format!(
+            "id: {}\nevent: {}\ndata: {}\n\n",
+            event.id, event.kind, event.data
+        ));
+    }
+    response(200, "text/event-stream", body.into_bytes(), config)
+}
+
+/// Answer to a CORS preflight for `POST /requests`.
+pub fn preflight_response(config: &HttpConfig) -> HttpResponse {
+    let mut response = response(204, "text/plain", Vec::new(), config);
+    response.headers.push((
+        "Access-Control-Allow-Methods".to_string(),
+        "POST, OPTIONS".to_string(),
+    ));
+    response.headers.push((
+        "Access-Control-Allow-Headers".to_string(),
+        "Content-Type, X-Request-Id".to_string(),
+    ));
+    response
+}
+
+pub fn json_response(status: u16, body: Vec<u8>, config: &HttpConfig) -> HttpResponse {
+    response(status, "application/json", body, config)
+}
+
+pub fn text_response(status: u16, text: &str, config: &HttpConfig) -> HttpResponse {
+    response(status, "text/plain", text.as_bytes().to_vec(), config)
+}
+
+fn response(status: u16, content_type: &str, body: Vec<u8>, config: &HttpConfig) -> HttpResponse {
+    let mut headers = vec![
+        ("Content-Type".to_string(), content_type.to_string()),
+        ("Cache-Control".to_string(), "no-cache".to_string()),
+    ];
+    if let Some(origin) = &config.allow_origin {
+        headers.push(("Access-Control-Allow-Origin".to_string(), origin.clone()));
+    }
+    HttpResponse {
+        status,
+        headers,
+        body: Some(body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, headers: &[(&str, &str)]) -> HttpRequest {
+        HttpRequest {
+            method: method.to_string(),
+            uri: "/requests".to_string(),
+            headers: headers
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            body: Some(b"{\"v\":1,\"type\":\"StartChat\"}".to_vec()),
+        }
+    }
+
+    #[test]
+    fn foreign_origins_and_non_json_posts_are_refused() {
+        let mut config = HttpConfig {
+            port: None,
+            host: default_host(),
+            max_buffered_events: default_max_buffered_events(),
+            allow_origin: None,
+        };
+        let json = ("Content-Type", "application/json; charset=utf-8");
+        let status = |request: &HttpRequest, config: &HttpConfig| {
+            refusal(request, config).map(|response| response.status)
+        };
+
+        // Clients that aren't browsers send no origin
+        assert_eq!(status(&request("POST", &[json]), &config), None);
+        let from_page = request("POST", &[json, ("Origin", "https://evil.example")]);
+        assert_eq!(status(&from_page, &config), Some(403));
+        let simple_post = request("POST", &[("Content-Type", "text/plain")]);
+        assert_eq!(status(&simple_post, &config), Some(415));
+        assert_eq!(status(&request("POST", &[]), &config), Some(415));
+
+        config.allow_origin = Some("http://localhost:5173".to_string());
+        let allowed = request("POST", &[json, ("origin", "http://localhost:5173")]);
+        assert_eq!(status(&allowed, &config), None);
+        assert_eq!(status(&from_page, &config), Some(403));
+        let events = request("GET", &[("Origin", "https://evil.example")]);
+        assert_eq!(status(&events, &config), Some(403));
+        assert_eq!(status(&request("GET", &[]), &config), None);
+    }
+}
@@ -0,0 +1,38 @@
+//! The `idle_timeout_secs` config: an assistant nobody has talked to for that long, with
+//! no workflow or generation under way, closes its sessions and shuts down at the next
+//! `Tick`, rather than living on as a long-lived idle actor.
+
+use crate::protocol::external::GitChatRequest;
+
+/// Whether `request` counts as a user interaction. `Ping` and `KeepAlive` are
+/// heartbeats of the host, and `GetMetrics` a scrape of its monitoring, which would
+/// otherwise keep an idle assistant alive.
+pub fn is_interaction(request: &GitChatRequest) -> bool {
+    !matches!(
+        request,
+        GitChatRequest::Ping | GitChatRequest::KeepAlive | GitChatRequest::GetMetrics { .. }
+    )
+}
+
+/// Seconds idle at `now` since `since`, the last interaction or the start, when they
+/// reach `timeout_secs`.
+pub fn expired(timeout_secs: u64, since: u64, now: u64) -> Option<u64> {
+    let idle_secs = now.saturating_sub(since);
+    (idle_secs >= timeout_secs).then_some(idle_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heartbeats_do_not_keep_the_assistant_alive() {
+        assert!(!is_interaction(&GitChatRequest::Ping));
+        assert!(!is_interaction(&GitChatRequest::KeepAlive));
+        assert!(is_interaction(&GitChatRequest::GetStatus));
+
+        assert_eq!(expired(600, 1_760_000_000, 1_760_000_599), None);
+        assert_eq!(expired(600, 1_760_000_000, 1_760_000_600), Some(600));
+        assert_eq!(expired(600, 1_760_000_900, 1_760_000_000), None);
+    }
+}
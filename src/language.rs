@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    Java,
+    C,
+    Cpp,
+    Ruby,
+    Php,
+    Shell,
+    Sql,
+    Html,
+    Css,
+    Config,
+    Markdown,
+}
+
+impl Language {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Rust => "Rust",
+            Language::Python => "Python",
+            Language::JavaScript => "JavaScript",
+            Language::TypeScript => "TypeScript",
+            Language::Go => "Go",
+            Language::Java => "Java",
+            Language::C => "C",
+            Language::Cpp => "C++",
+            Language::Ruby => "Ruby",
+            Language::Php => "PHP",
+            Language::Shell => "Shell",
+            Language::Sql => "SQL",
+            Language::Html => "HTML",
+            Language::Css => "CSS",
+            Language::Config => "Configuration",
+            Language::Markdown => "Markdown",
+        }
+    }
+
+    /// Review checklist for changes in this language.
+    pub fn review_checklist(&self) -> &'static str {
+        match self {
+            Language::Rust => "unsafe blocks and their invariants, unwrap/expect that can panic, integer overflow, blocking calls in async code",
+            Language::Python => "eval/exec, subprocess with shell=True, pickle of untrusted data, mutable default arguments, broad except clauses",
+            Language::JavaScript | Language::TypeScript => "XSS via innerHTML or dangerouslySetInnerHTML, eval, prototype pollution, unhandled promise rejections, any-typed escapes",
+            Language::Go => "ignored errors, goroutine leaks, data races on shared state, nil pointer dereferences, defer in loops",
+            Language::Java => "unsafe deserialization, resource leaks without try-with-resources, null handling, thread safety",
+            Language::C | Language::Cpp => "buffer overflows and bounds checks, use-after-free, integer overflow, uninitialized memory, format strings",
+            Language::Ruby => "SQL and command injection, mass assignment, unsafe YAML loading, N+1 queries",
+            Language::Php => "SQL injection, XSS, file inclusion, unvalidated input, weak comparison operators",
+            Language::Shell => "unquoted variables, missing set -euo pipefail, command injection, unsafe temporary files",
+            Language::Sql => "SQL injection through string concatenation, missing indexes, destructive migrations without rollback, locking on large tables",
+            Language::Html => "XSS, inline scripts, missing alt text and accessibility attributes",
+            Language::Css => "specificity conflicts, unused selectors, layout regressions",
+            Language::Config => "committed secrets or credentials, environment-specific values, breaking changes to defaults",
+            Language::Markdown => "accuracy against the code changes, broken links",
+        }
+    }
+}
+
+/// Detect a file's language from its extension, falling back to a shebang line.
+pub fn detect_language(path: &str, first_line: Option<&str>) -> Option<Language> {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+
+    let by_extension = match extension.as_deref() {
+        Some("rs") => Some(Language::Rust),
+        Some("py") | Some("pyi") => Some(Language::Python),
+        Some("js") | Some("jsx") | Some("mjs") | Some("cjs") => Some(Language::JavaScript),
+        Some("ts") | Some("tsx") => Some(Language::TypeScript),
+        Some("go") => Some(Language::Go),
+        Some("java") | Some("kt") => Some(Language::Java),
+        Some("c") | Some("h") => Some(Language::C),
+        Some("cc") | Some("cpp") | Some("cxx") | Some("hpp") | Some("hh") => Some(Language::Cpp),
+        Some("rb") => Some(Language::Ruby),
+        Some("php") => Some(Language::Php),
+        Some("sh") | Some("bash") | Some("zsh") => Some(Language::Shell),
+        Some("sql") => Some(Language::Sql),
+        Some("html") | Some("htm") => Some(Language::Html),
+        Some("css") | Some("scss") | Some("sass") => Some(Language::Css),
+        Some("toml") | Some("yaml") | Some("yml") | Some("json") | Some("ini") | Some("env") => {
+            Some(Language::Config)
+        }
+        Some("md") | Some("markdown") => Some(Language::Markdown),
+        _ => None,
+    };
+
+    by_extension.or_else(|| first_line.and_then(detect_shebang))
+}
+
+fn detect_shebang(line: &str) -> Option<Language> {
+    let interpreter_line = line.strip_prefix("#!")?;
+    let mut words = interpreter_line.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+    match interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => Some(Language::Shell),
+        "python" => Some(Language::Python),
+        "node" | "deno" => Some(Language::JavaScript),
+        "ruby" => Some(Language::Ruby),
+        "php" => Some(Language::Php),
+        _ => None,
+    }
+}
+
+/// Group changed paths by detected language. `first_line` is consulted for files
+/// without a recognised extension.
+pub fn group_by_language<'a>(
+    paths: impl Iterator<Item = &'a str>,
+    mut first_line: impl FnMut(&str) -> Option<String>,
+) -> BTreeMap<Language, Vec<String>> {
+    let mut groups: BTreeMap<Language, Vec<String>> = BTreeMap::new();
+    for path in paths {
+        let language = detect_language(path, None)
+            .or_else(|| detect_language(path, first_line(path).as_deref()));
+        if let Some(language) = language {
+            groups.entry(language).or_default().push(path.to_string());
+        }
+    }
+    groups
+}
+
+/// Render the per-language annotation appended to review context.
+pub fn render_annotation(groups: &BTreeMap<Language, Vec<String>>) -> String {
+    if groups.is_empty() {
+        return String::new();
+    }
+    let mut block = String::from("LANGUAGES IN THIS CHANGE:");
+    for (language, paths) in groups {
+        block.push_str(&format!(
+            "\n- {} ({} files): {}\n  Check for: {}",
+            language.name(),
+            paths.len(),
+            paths.join(", "),
+            language.review_checklist()
+        ));
+    }
+    block
+}
@@ -0,0 +1,341 @@
+//! The `large_files` policy: what the commit workflow does with binary files and files
+//! over a size limit. The model is told the rules; commits it makes through the
+//! assistant are refused while the staged files break them, and the commits are checked
+//! again when the workflow completes.
+
+use crate::command_policy;
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Files whose size is read for one check; the rest are only checked for being binary.
+const MAX_SIZED_FILES: usize = 200;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileAction {
+    /// Commit them like any other file
+    #[default]
+    Ignore,
+    /// Commit them, but only name them in the message rather than describe their content
+    MentionOnly,
+    /// Never commit them
+    Block,
+    /// Commit them only through Git LFS
+    RequireLfs,
+}
+
+impl FileAction {
+    fn enforced(self) -> bool {
+        matches!(self, FileAction::Block | FileAction::RequireLfs)
+    }
+
+    fn rule(self, files: &str) -> Option<String> {
+        match self {
+            FileAction::Ignore => None,
+            FileAction::MentionOnly => Some(format!(
+                "{} may be committed, but only name them in the commit message; don't \
+                describe or guess their content.",
+                files
+            )),
+            FileAction::Block => Some(format!(
+                "Never stage or commit {}; leave them in the working tree and tell me which \
+                ones you left out.",
+                files
+            )),
+            FileAction::RequireLfs => Some(format!(
+                "Commit {} only through Git LFS: when a file isn't tracked by LFS yet, stop \
+                and ask me to run git lfs track for it rather than committing it.",
+                files
+            )),
+        }
+    }
+}
+
+/// The `large_files` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LargeFilePolicy {
+    #[serde(default)]
+    pub binary: FileAction,
+    /// Files over `max_bytes`, binary or not
+    #[serde(default)]
+    pub oversized: FileAction,
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: u64,
+}
+
+fn default_max_bytes() -> u64 {
+    5 * 1024 * 1024
+}
+
+impl Default for LargeFilePolicy {
+    fn default() -> Self {
+        Self {
+            binary: FileAction::default(),
+            oversized: FileAction::default(),
+            max_bytes: default_max_bytes(),
+        }
+    }
+}
+
+/// A file added or changed by the staged changes or the commits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedFile {
+    pub path: String,
+    pub binary: bool,
+    /// Size of the blob in bytes, when it was read
+    pub size: Option<u64>,
+}
+
+/// A file the policy refuses, as relayed in `FilePolicyViolated`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FileViolation {
+    pub path: String,
+    pub binary: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// `block` or `require-lfs`
+    pub action: FileAction,
+}
+
+impl FileViolation {
+    pub fn describe(&self) -> String {
+        let mut kind = Vec::new();
+        if self.binary {
+            kind.push("binary".to_string());
+        }
+        if let Some(size) = self.size {
+            kind.push(format!("{} bytes", size));
+        }
+        let rule = match self.action {
+            FileAction::RequireLfs => "must be committed through Git LFS",
+            _ => "may not be committed",
+        };
+        format!("{} ({}) {}", self.path, kind.join(", "), rule)
+    }
+}
+
+/// Added or modified files in `git diff --numstat --no-renames` output, binary ones
+/// marked with `-` counts.
+fn parse_numstat(output: &str) -> Vec<ChangedFile> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (added, deleted, path) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(ChangedFile {
+                path: path.to_string(),
+                binary: added == "-" && deleted == "-",
+                size: None,
+            })
+        })
+        .collect()
+}
+
+/// Blob sizes by path in `git ls-tree -r -l` output.
+fn parse_tree_sizes(output: &str) -> Vec<(String, u64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            // <mode> <type> <object> <size>\t<path>
+            let (meta, path) = line.split_once('\t')?;
+            let size = meta.split_whitespace().nth(3)?.parse().ok()?;
+            Some((path.to_string(), size))
+        })
+        .collect()
+}
+
+/// Files the staged changes add or modify.
+pub fn fetch_staged(
+    git_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<Vec<ChangedFile>, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &[
+            "diff",
+            "--cached",
+            "--numstat",
+            "--no-renames",
+            "--diff-filter=AM",
+        ],
+    )?;
+    let mut files = parse_numstat(&output);
+    for file in files.iter_mut().take(MAX_SIZED_FILES) {
+        let size = run_git(
+            git_actor_id,
+            directory,
+            &["cat-file", "-s", &format!(":{}", file.path)],
+        )?;
+        file.size = size.trim().parse().ok();
+    }
+    Ok(files)
+}
+
+/// Files the commits between `base` and HEAD add or modify, as HEAD has them.
+pub fn fetch_committed(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: &str,
+) -> Result<Vec<ChangedFile>, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &[
+            "diff",
+            "--numstat",
+            "--no-renames",
+            "--diff-filter=AM",
+            base,
+            "HEAD",
+        ],
+    )?;
+    let mut files = parse_numstat(&output);
+    if files.is_empty() {
+        return Ok(files);
+    }
+    let mut args = vec!["ls-tree", "-r", "-l", "HEAD", "--"];
+    args.extend(
+        files
+            .iter()
+            .take(MAX_SIZED_FILES)
+            .map(|file| file.path.as_str()),
+    );
+    let sizes = parse_tree_sizes(&run_git(git_actor_id, directory, &args)?);
+    for file in files.iter_mut() {
+        file.size = sizes
+            .iter()
+            .find(|(path, _)| *path == file.path)
+            .map(|(_, size)| *size);
+    }
+    Ok(files)
+}
+
+/// Whether a model's git tool call creates a commit: `git_commit`, or `commit` run
+/// through the generic `git` tool.
+pub fn is_commit_call(name: &str, args: &Value) -> bool {
+    command_policy::tool_subcommand(name, Some(args)).as_deref() == Some("commit")
+}
+
+impl LargeFilePolicy {
+    /// Whether the policy refuses any file, so commits need checking.
+    pub fn is_enforced(&self) -> bool {
+        self.binary.enforced() || self.oversized.enforced()
+    }
+
+    /// The files of `files` the policy refuses. Files tracked by Git LFS are stored as
+    /// small text pointers, so a binary or oversized blob is never an LFS one.
+    pub fn violations(&self, files: &[ChangedFile]) -> Vec<FileViolation> {
+        files
+            .iter()
+            .filter_map(|file| {
+                let oversized = file.size.is_some_and(|size| size > self.max_bytes);
+                let action = [(file.binary, self.binary), (oversized, self.oversized)]
+                    .into_iter()
+                    .filter(|(applies, action)| *applies && action.enforced())
+                    .map(|(_, action)| action)
+                    .max_by_key(|action| *action == FileAction::Block)?;
+                Some(FileViolation {
+                    path: file.path.clone(),
+                    binary: file.binary,
+                    size: file.size,
+                    action,
+                })
+            })
+            .collect()
+    }
+
+    /// Commit workflow rules; empty when every file is committed like any other.
+    pub fn prompt_fragment(&self) -> String {
+        let rules: Vec<String> = [
+            self.binary.rule("binary files"),
+            self.oversized
+                .rule(&format!("files over {} bytes", self.max_bytes)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if rules.is_empty() {
+            return String::new();
+        }
+        let mut fragment = String::from("\n\nBINARY AND LARGE FILES:");
+        for rule in rules {
+            fragment.push_str(&format!(" {}", rule));
+        }
+        fragment.push_str(
+            " Check the staged files with git diff --cached --numstat before committing; \
+            binary files show - for their line counts.",
+        );
+        fragment
+    }
+}
+
+/// The commit refusal the model sees as the result of its tool call.
+pub fn refusal(violations: &[FileViolation]) -> String {
+    let mut message =
+        String::from("Commit refused by the large_files policy. These staged files break it:");
+    for violation in violations {
+        message.push_str(&format!("\n- {}", violation.describe()));
+    }
+    message.push_str(
+        "\nUnstage them (git restore --staged <path>) and commit the rest, or stop and ask me \
+        what to do.",
+    );
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn binary_and_oversized_files_are_refused_by_policy() {
+        let mut files = parse_numstat("3\t1\tsrc/lib.rs\n-\t-\tassets/logo.png\n12\t0\tdata.csv\n");
+        assert!(files[1].binary && !files[0].binary);
+        let sizes = parse_tree_sizes(
+            "100644 blob 4f2c9a1 2048\tsrc/lib.rs\n100644 blob 9b8e7d6 90000\tassets/logo.png\n\
+            100644 blob 1a2b3c4 7000000\tdata.csv\n",
+        );
+        for file in files.iter_mut() {
+            file.size = sizes
+                .iter()
+                .find(|(path, _)| *path == file.path)
+                .map(|(_, size)| *size);
+        }
+
+        let policy = LargeFilePolicy {
+            binary: FileAction::RequireLfs,
+            oversized: FileAction::Block,
+            ..LargeFilePolicy::default()
+        };
+        let violations = policy.violations(&files);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].action, FileAction::RequireLfs);
+        assert_eq!(
+            violations[1].describe(),
+            "data.csv (7000000 bytes) may not be committed"
+        );
+        assert!(policy.prompt_fragment().contains("only through Git LFS"));
+
+        let mention = LargeFilePolicy {
+            binary: FileAction::MentionOnly,
+            ..LargeFilePolicy::default()
+        };
+        assert!(!mention.is_enforced());
+        assert!(mention.violations(&files).is_empty());
+        assert!(LargeFilePolicy::default().prompt_fragment().is_empty());
+
+        assert!(is_commit_call("git_commit", &json!({"message": "fix"})));
+        assert!(is_commit_call(
+            "git",
+            &json!({"args": ["commit", "-m", "fix"]})
+        ));
+        assert!(!is_commit_call(
+            "git",
+            &json!({"args": ["log", "--grep", "commit"]})
+        ));
+        assert!(!is_commit_call("git_add", &json!({})));
+    }
+}
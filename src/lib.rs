@@ -1,17 +1,222 @@
+mod advice_only;
+mod amend;
+mod api_diff;
+mod artifacts;
+mod audit;
+mod backoff;
 #[allow(warnings)]
 mod bindings;
-mod protocol;
+mod bisect;
+mod blame_explain;
+mod branch;
+mod branch_cleanup;
+mod branch_context;
+mod build_info;
+mod changelog;
+mod channel;
+mod cherry_pick;
+mod clock;
+mod command_policy;
+mod commit;
+mod commit_plan;
+mod commit_size;
+mod commit_template;
+mod compare;
+mod completion;
+mod config_check;
+mod config_fingerprint;
+mod config_patch;
+mod confirmation;
+mod conflicts;
+mod context;
+mod convention;
+mod crash_loop;
+mod dead_man_switch;
+mod debug_dump;
+mod delegation;
+mod diff_summary;
+mod digest;
+mod divergence;
+mod errors;
+mod exec_tools;
+mod fan_out;
+mod forge;
+mod forge_tools;
+mod generation_overrides;
+mod git_tools;
+mod history;
+mod hook_review;
+mod http;
+mod idle;
+mod language;
+mod large_files;
+mod locale;
+mod logging;
+mod mcp_servers;
+mod metrics;
+mod model_fallback;
+mod model_preset;
+mod onboard;
+mod outbound;
+mod pipeline;
+mod postcondition;
+mod postprocess;
+mod pr_description;
+mod preflight;
+mod progress;
+mod prompts;
+pub mod protocol;
+mod quick_command;
+mod rebase_plan;
+mod release;
+mod renames;
+mod repo_config;
+mod repo_info;
+mod repo_state;
+mod repo_stats;
+mod resume;
+mod review;
+mod review_report;
+mod rewrite_safety;
+mod safe_mode;
+mod sanitize;
+mod schedule;
+mod scope;
+mod scratchpad;
+mod secret_scan;
+mod session_store;
+mod signatures;
+mod simulated;
+mod sparse;
+mod spelling;
+mod stash;
+mod state_codec;
+mod state_limits;
+mod submodule;
+mod suggest_branch;
+mod suggest_commit;
+mod suggest_only;
+mod suggest_pr;
+mod summary;
+mod sync;
+mod task_monitor;
+mod ticket;
+mod tool_output;
+mod transcript;
+mod turn;
+mod turn_limit;
+mod undo;
+mod usage;
+mod verification;
+mod webhook;
+mod workflow;
+mod worktree;
 
+use advice_only::ToolOutage;
+use artifacts::{ArtifactKind, ArtifactsConfig};
+use audit::{Approval, AuditEntry, AuditLog, AuditOutcome, AuditSource};
+use backoff::{BackoffConfig, Randomness};
 use bindings::exports::theater::simple::actor::Guest;
+use bindings::exports::theater::simple::http_handlers::Guest as HttpHandlers;
 use bindings::exports::theater::simple::message_server_client::Guest as MessageServerClient;
 use bindings::exports::theater::simple::supervisor_handlers::Guest as SupervisorHandlers;
-use bindings::theater::simple::message_server_host::send;
-use bindings::theater::simple::runtime::{log, shutdown};
-use bindings::theater::simple::supervisor::spawn;
+use bindings::theater::simple::http_types::{HttpRequest, HttpResponse, MiddlewareResult};
+use bindings::theater::simple::message_server_host::{
+    request, respond_to_request, send, send_on_channel,
+};
+use bindings::theater::simple::runtime::shutdown;
+use bindings::theater::simple::supervisor::{list_children, spawn, stop_child};
 use bindings::theater::simple::types::{ChannelAccept, Event, WitActorError, WitErrorType};
+use bindings::theater::simple::websocket_types::WebsocketMessage;
+use bisect::{BisectConfig, BisectOutcome, BisectPhase, BisectState, BisectStep, BisectVerdict};
+use branch::BranchNaming;
+use branch_cleanup::{BranchCleanup, BranchCleanupConfig, DeletionFailure};
+use branch_context::{BranchContext, BranchContextConfig};
+use build_info::BuildInfo;
+use changelog::ChangelogConfig;
+use channel::{ChannelConfig, ClientFrame, ServerFrame, Stream};
+use cherry_pick::{CherryPickPhase, CherryPickSource, CherryPickState, PickOutcome, PickStatus};
+use command_policy::CommandPolicy;
+use commit::CommitIdentity;
+use commit_plan::CommitPlan;
+use commit_size::CommitSizeLimits;
+use completion::{CompletionConfig, CompletionCriterion, CompletionDetector};
+use config_patch::GenerationConfig;
+use confirmation::{
+    ConfirmationMode, ConfirmationPolicy, PendingConfirmation, Severity as ActionSeverity,
+};
+use conflicts::{ConflictedFile, Resolution};
+use context::{DiffBudget, DiffTrimmer};
+use convention::{CommitConvention, ConventionViolation, TemplateVariable};
+use crash_loop::{Crash, CrashLoopPolicy, SessionFailure};
+use dead_man_switch::{DeadManSwitch, HaltSnapshot};
+use debug_dump::{DebugEvent, DebugSection};
+use delegation::{DelegatedTask, Delegation, DelegationSource, Sibling};
+use diff_summary::{DiffSummary, DiffSummaryConfig, DiffSummaryPass};
+use digest::DigestConfig;
+use divergence::DivergenceWatch;
+use errors::ErrorCode;
+use fan_out::{FanOutConfig, FanOutResult, RepoRun};
+use forge::ForgeConfig;
 use genai_types::Message;
+use generation_overrides::GenerationOverrides;
+use history::{HistoryBounds, HistoryCache, PerformanceMode};
+use hook_review::HookReviewConfig;
+use http::{BufferedEvent, HttpConfig, HttpServer};
+use large_files::{FileViolation, LargeFilePolicy};
+use locale::LanguageConfig;
+use logging::{LogRedaction, LoggingConfig};
+use metrics::{Metrics, MetricsFormat, MetricsSnapshot};
+use model_fallback::ModelConfig;
+use model_preset::ModelPreset;
+use outbound::{GenerationOverlap, HeldMessage, QueuedSend};
+use pipeline::PipelineRun;
+use postcondition::Postcondition;
+use postprocess::PostProcessConfig;
+use pr_description::PrDescriptionConfig;
+use progress::{ProgressPhase, WorkflowProgress};
+use prompts::{PrimingRole, PromptConfig};
+use protocol::external::{
+    decode_request, encode_event, encode_notification, encode_response, CreatedCommit, Envelope,
+    EventKind, GitChatEvent, GitChatNotification, GitChatRequest, GitChatResponse, SessionInfo,
+    SessionStatus, ToolPhase, WorkflowResult, WorkflowState, PROTOCOL_VERSION,
+};
+use protocol::{McpActorRequest, McpError, McpResponse, Provenance};
+use quick_command::{Parsed, QuickCommand, QuickCommandResult};
+use rebase_plan::{RebasePlan, RebaseState, RebaseStatus};
+use release::{Release, ReleaseConfig};
+use repo_info::RepoInfo;
+use repo_state::DirtyWorktreePolicy;
+use repo_stats::{RepoStats, RepoStatsConfig};
+use resume::{GitOperation, ResumeAction, ResumeReport, WorkflowCheckpoint, WorkflowPhase};
+use review::{BenchmarkFinding, ReviewConfig, ReviewFinding};
+use review_report::{PublishMode, PublishResult, ReviewReport, ReviewRound};
+use safe_mode::SafeMode;
+use schedule::{ScheduleEntry, ScheduledRun};
+use scratchpad::Scratchpad;
+use secret_scan::{SecretFinding, SecretScanConfig, SecretScanner};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{from_slice, to_vec, Value};
+use session_store::{SavedWorkflow, SessionSnapshot, SnapshotInfo};
+use sparse::SparseCheckout;
+use spelling::SpellcheckConfig;
+use stash::StashEntry;
+use state_codec::StateFormat;
+use state_limits::{CompactionReport, ConfigOverrides, StateLimits};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use submodule::{PointerPolicy, SubmoduleConfig};
+use sync::{SyncConfig, SyncOutcome, SyncPhase, SyncState, SyncStrategy};
+use ticket::{Ticket, TicketConfig};
+use tool_output::ToolOutputPolicy;
+use turn::TurnClaim;
+use turn_limit::{TurnLimits, WorkflowStall, WorkflowTimeouts};
+use undo::{TurnMark, TurnUndo};
+use usage::{Budget, Usage, UsageReport};
+use verification::{CompletionStatus, Expectations, VerificationConfig};
+use webhook::NotificationsConfig;
+use workflow::Workflow;
+use worktree::Worktree;
 
 struct Component;
 
@@ -22,36 +227,211 @@ const TASK_MONITOR_MANIFEST_PATH: &str =
     "https://github.com/colinrozzi/task-monitor-mcp-actor/releases/latest/download/manifest.toml";
 const GIT_MCP_MANIFEST_PATH: &str =
     "https://github.com/colinrozzi/git-mcp-actor/releases/latest/download/manifest.toml";
+const EXEC_MCP_MANIFEST_PATH: &str =
+    "https://github.com/colinrozzi/exec-mcp-actor/releases/latest/download/manifest.toml";
 
-// Protocol types for external communication
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "type")]
-enum GitChatRequest {
-    GetChatStateActorId,
-    AddMessage { message: Message },
-    StartChat,
-}
+/// Model used when `model_config` doesn't name one.
+const DEFAULT_MODEL: &str = "claude-sonnet-4-20250514";
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "type")]
-enum GitChatResponse {
-    ChatStateActorId { actor_id: String },
-    Success,
-    Error { message: String },
-}
+/// Session id of the chat-state actor spawned at init.
+const DEFAULT_SESSION_ID: &str = "default";
+
+/// Upper bound on files fetched to look for a shebang when detecting languages.
+const MAX_SHEBANG_LOOKUPS: usize = 10;
+
+/// How often the model is asked to fix commit messages before the session ends anyway.
+const MAX_CONVENTION_REPROMPTS: u32 = 2;
+
+/// How often a workflow is retried in a new chat-state actor after the old one exited
+/// on a failure.
+const MAX_EXIT_RESPAWNS: u32 = 2;
 
 // Configuration for git assistant
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct GitAssistantConfig {
     current_directory: Option<String>,
-    task: Option<String>,
+    /// Paths of other worktrees or checkouts of the repository `SetActiveWorktree` may
+    /// switch to, besides those `git worktree list` shows
+    #[serde(default)]
+    worktrees: Vec<String>,
+    #[serde(alias = "task")]
+    workflow: Option<Workflow>,
+    /// Workflows run one after the other, from a `workflow` sequence; the first one is
+    /// `workflow`
+    #[serde(default)]
+    pipeline: Vec<Workflow>,
+    dirty_worktree_policy: Option<DirtyWorktreePolicy>,
+    commit_identity: Option<CommitIdentity>,
+    /// Commits need a `Signed-off-by` trailer; checked when the commit workflow ends
+    #[serde(default)]
+    require_signoff: bool,
+    /// Commits need a GPG signature; checked when the commit workflow ends
+    #[serde(default)]
+    gpg_sign: bool,
+    commit_convention: Option<CommitConvention>,
+    /// Commit message template with `{{name}}` placeholders
+    commit_template: Option<String>,
+    /// Values for the placeholders of `commit_template`
+    #[serde(default)]
+    template_vars: BTreeMap<String, String>,
+    /// Natural language of commit messages and replies
+    language: Option<LanguageConfig>,
+    /// Have the commit workflow propose a plan of commits and wait for
+    /// `ApproveCommitPlan` before committing
+    #[serde(default)]
+    commit_split: bool,
+    spellcheck: Option<SpellcheckConfig>,
+    changelog: Option<ChangelogConfig>,
+    pr_description: Option<PrDescriptionConfig>,
+    /// Steps applied to the model's replies and task summaries, per workflow
+    postprocess: Option<PostProcessConfig>,
+    forge_mcp: Option<ForgeConfig>,
+    performance_mode: Option<PerformanceMode>,
+    logging: Option<LoggingConfig>,
+    log_redaction: Option<LogRedaction>,
+    /// Serve the protocol over HTTP as well as through the message server
+    http: Option<HttpConfig>,
+    /// Limits of the framed protocol spoken on channels
+    channels: Option<ChannelConfig>,
+    /// Actor that receives `WorkflowCompleted` and `Digest` notifications
+    notify_actor: Option<String>,
+    /// Webhook that events are posted to
+    notifications: Option<NotificationsConfig>,
+    /// Actor that spawned this one to orchestrate it. It gets the notifications
+    /// `notify_actor` gets, the default event kinds, and `Ready` and `FatalError`
+    #[serde(default, alias = "reply_to")]
+    parent_actor_id: Option<String>,
+    /// Assistants tasks can be delegated to with `DelegateTask`, besides those added
+    /// with `RegisterSibling`
+    #[serde(default)]
+    siblings: Vec<Sibling>,
+    /// Where `StartFanOut` spawns a child assistant per repository from
+    #[serde(default)]
+    fan_out: Option<FanOutConfig>,
+    digest: Option<DigestConfig>,
+    divergence_watch: Option<DivergenceWatch>,
+    /// Let history-rewriting workflows rewrite commits that are already on a remote
+    #[serde(default)]
+    allow_published_rewrite: bool,
+    /// Allow `DebugDump` and record recent events for it
+    #[serde(default)]
+    enable_debug: bool,
+    /// Answer sessions with an echoing stand-in instead of spawning chat-state actors;
+    /// for tests only
+    #[serde(default)]
+    simulated_chat_state: bool,
+    /// Fail init when a preflight check of the environment fails
+    #[serde(default)]
+    strict_preflight: bool,
+    /// Answer slash commands in `AddMessage` text without a model turn
+    #[serde(default)]
+    quick_commands: bool,
+    /// Let the model keep notes to itself in `<scratchpad>` blocks, for `GetScratchpad`
+    #[serde(default)]
+    scratchpad: bool,
+    /// Give the model no git tools; it suggests commands for the client to run instead
+    #[serde(default)]
+    suggest_only: bool,
+    /// Give the model no git tools and have it advise rather than act; set on its own
+    /// when the git tools can't be attached at init
+    #[serde(default)]
+    advice_only: bool,
+    /// Spawn the task monitor once and supervise it, rather than leaving each session's
+    /// chat-state actor to spawn its own
+    #[serde(default)]
+    supervise_task_monitor: bool,
+    branch_naming: Option<BranchNaming>,
+    /// Staleness rules and protected branches of the `branch-cleanup` workflow
+    branch_cleanup: Option<BranchCleanupConfig>,
+    /// Tag prefix and version bump rules of the `tag-and-release` workflow
+    release: Option<ReleaseConfig>,
+    /// Hook output given to the `hook-review` workflow
+    hook_review: Option<HookReviewConfig>,
+    /// Workflows started on a schedule, driven by `Tick`
+    schedule: Option<Vec<ScheduleEntry>>,
+    branch_context: Option<BranchContextConfig>,
+    /// Ticket the work belongs to, given or taken from the branch name
+    ticket: Option<TicketConfig>,
+    commit_size: Option<CommitSizeLimits>,
+    /// What the commit workflow does with submodule pointer bumps
+    submodules: Option<SubmoduleConfig>,
+    /// What the commit workflow does with binary and oversized files
+    large_files: Option<LargeFilePolicy>,
+    diff_budget: Option<DiffBudget>,
+    /// Summarize huge diffs file by file before a workflow's opening message
+    diff_summary: Option<DiffSummaryConfig>,
+    /// Send repository statistics before the opening message of some workflows
+    repo_stats: Option<RepoStatsConfig>,
+    /// Elide oversized git tool output before the model sees it
+    tool_output: Option<ToolOutputPolicy>,
+    review: Option<ReviewConfig>,
+    /// Commands the model is told never to run, usually set per repository
+    blocked_commands: Option<Vec<String>>,
+    /// Subdirectories of a monorepo the assistant is limited to; the whole repository
+    /// when empty
+    #[serde(default)]
+    scope_paths: Vec<String>,
+    command_policy: Option<CommandPolicy>,
+    secret_scan: Option<SecretScanConfig>,
+    confirmation_policy: Option<ConfirmationPolicy>,
+    dead_man_switch: Option<DeadManSwitch>,
+    /// Seconds without a user interaction, and with nothing under way, before the
+    /// assistant closes its sessions and shuts down
+    idle_timeout_secs: Option<u64>,
+    /// Store `SaveSession` writes to and `RestoreSession` reads from; one is created on
+    /// the first save when unset
+    session_store_id: Option<String>,
+    /// Turns an auto-initiated workflow may take before the model is told to wrap up
+    max_turns: Option<TurnLimits>,
+    /// Seconds an auto-initiated workflow may run before it is stopped
+    workflow_timeout_secs: Option<WorkflowTimeouts>,
+    /// How the model signals that its task is done
+    completion: Option<CompletionConfig>,
+    /// Repository states each workflow must leave behind, keyed by workflow name
+    #[serde(default)]
+    postconditions: BTreeMap<String, Vec<Postcondition>>,
+    /// How workflows that change the repository are verified once they report done
+    verification: Option<VerificationConfig>,
+    /// Session logs, plans, and backups kept in the git directory
+    artifacts: Option<ArtifactsConfig>,
+    bisect: Option<BisectConfig>,
+    sync: Option<SyncConfig>,
     model_config: Option<Value>,
+    /// Models tried in order when generations fail with provider errors
+    #[serde(default)]
+    model_fallbacks: Vec<ModelConfig>,
+    /// Named model configs with their generation parameters, e.g. `fast` and `quality`
+    #[serde(default)]
+    model_presets: BTreeMap<String, ModelPreset>,
+    /// Preset to switch to at each stage of a workflow, keyed by workflow name
+    #[serde(default)]
+    workflow_presets: BTreeMap<String, BTreeMap<String, String>>,
+    /// When a session whose chat-state actor keeps crashing is given up on
+    crash_loop: Option<CrashLoopPolicy>,
+    budget: Option<Budget>,
+    /// Delays between retries of requests to chat-state actors
+    retry_backoff: Option<BackoffConfig>,
+    /// Codec the actor state is saved with
+    state_codec: Option<StateFormat>,
+    /// When the saved state is compacted; never unless set
+    state_limits: Option<StateLimits>,
+    /// What a user's message does while its session is generating; queued by default
+    generation_overlap: Option<GenerationOverlap>,
     temperature: Option<f64>,
     max_tokens: Option<u32>,
     system_prompt: Option<String>,
+    /// Overrides of single system prompt templates
+    prompts: Option<PromptConfig>,
     title: Option<String>,
     description: Option<String>,
+    /// Replaces the default MCP servers as a whole
     mcp_servers: Option<Value>,
+    /// Servers appended to the default ones, or to `mcp_servers`
+    #[serde(default)]
+    extra_mcp_servers: Vec<Value>,
+    /// Default servers left out by name, e.g. `task-monitor`
+    #[serde(default)]
+    disable_default_mcp_servers: Vec<String>,
     #[serde(flatten)]
     other: Value,
 }
@@ -60,48 +440,656 @@ impl Default for GitAssistantConfig {
     fn default() -> Self {
         Self {
             current_directory: None,
-            task: None,
+            worktrees: Vec::new(),
+            workflow: None,
+            pipeline: Vec::new(),
+            dirty_worktree_policy: None,
+            commit_identity: None,
+            require_signoff: false,
+            gpg_sign: false,
+            commit_convention: None,
+            commit_template: None,
+            language: None,
+            template_vars: BTreeMap::new(),
+            commit_split: false,
+            spellcheck: None,
+            changelog: None,
+            pr_description: None,
+            postprocess: None,
+            forge_mcp: None,
+            performance_mode: None,
+            logging: None,
+            log_redaction: None,
+            http: None,
+            channels: None,
+            notify_actor: None,
+            notifications: None,
+            parent_actor_id: None,
+            siblings: Vec::new(),
+            fan_out: None,
+            digest: None,
+            divergence_watch: None,
+            allow_published_rewrite: false,
+            enable_debug: false,
+            simulated_chat_state: false,
+            strict_preflight: false,
+            quick_commands: false,
+            scratchpad: false,
+            suggest_only: false,
+            advice_only: false,
+            supervise_task_monitor: false,
+            branch_naming: None,
+            branch_cleanup: None,
+            release: None,
+            hook_review: None,
+            schedule: None,
+            branch_context: None,
+            ticket: None,
+            commit_size: None,
+            submodules: None,
+            large_files: None,
+            diff_budget: None,
+            diff_summary: None,
+            repo_stats: None,
+            tool_output: None,
+            review: None,
+            blocked_commands: None,
+            scope_paths: Vec::new(),
+            command_policy: None,
+            secret_scan: None,
+            confirmation_policy: None,
+            dead_man_switch: None,
+            idle_timeout_secs: None,
+            session_store_id: None,
+            max_turns: None,
+            workflow_timeout_secs: None,
+            completion: None,
+            postconditions: BTreeMap::new(),
+            verification: None,
+            artifacts: None,
+            bisect: None,
+            sync: None,
             model_config: None,
+            model_fallbacks: Vec::new(),
+            model_presets: BTreeMap::new(),
+            workflow_presets: BTreeMap::new(),
+            crash_loop: None,
+            budget: None,
+            retry_backoff: None,
+            state_codec: None,
+            state_limits: None,
+            generation_overlap: None,
             temperature: None,
             max_tokens: None,
             system_prompt: None,
+            prompts: None,
             title: None,
             description: None,
             mcp_servers: None,
+            extra_mcp_servers: Vec::new(),
+            disable_default_mcp_servers: Vec::new(),
             other: serde_json::json!({}),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct TaskComplete;
+/// A suggestion request answered once its throwaway chat-state actor replies.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct PendingSuggestion {
+    request_id: String,
+    kind: SuggestionKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum SuggestionKind {
+    CommitMessage,
+    BranchName,
+    PrDescription,
+}
 
 // State management
 #[derive(Serialize, Deserialize, Debug)]
 struct GitChatState {
     actor_id: String,
     chat_state_actor_id: Option<String>,
+    /// Saved as `config_overrides` instead once the state is compacted
     original_config: Value,
+    /// `original_config` as overrides of the config the assistant builds; only in saved
+    /// state, and put back into `original_config` when it is decoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    config_overrides: Option<ConfigOverrides>,
+    /// Whether the state was compacted, by `state_limits` or `CompactState`
+    #[serde(default)]
+    config_compacted: bool,
+    /// Fingerprint of `original_config`, compared on resume with that of the config the
+    /// running build makes
+    #[serde(default)]
+    config_fingerprint: Option<String>,
+    /// Set while the init config is broken; see `safe_mode.rs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    safe_mode: Option<SafeMode>,
+    /// Set while the git tools couldn't be attached; see `advice_only.rs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_outage: Option<ToolOutage>,
+    /// Parsed init config, used to build the chat config of additional sessions
+    assistant_config: GitAssistantConfig,
     current_directory: Option<String>,
-    task: Option<String>,
+    workflow: Option<Workflow>,
+    /// Additional sessions created with `CreateSession`, keyed by session id
+    sessions: BTreeMap<String, SessionInfo>,
+    next_session_number: u64,
+    dirty_worktree_policy: DirtyWorktreePolicy,
+    git_tools_actor_id: Option<String>,
+    exec_tools_actor_id: Option<String>,
+    #[serde(default)]
+    forge_tools_actor_id: Option<String>,
+    /// Git tools actor the model's calls to the default git tools are forwarded to, see
+    /// `proxy_git_tool_call`
+    #[serde(default)]
+    model_git_tools_actor_id: Option<String>,
+    /// Task monitor the sessions share when `supervise_task_monitor` is set
+    #[serde(default)]
+    task_monitor_actor_id: Option<String>,
+    #[serde(default)]
+    task_monitor_restarts: u32,
+    auto_initiated: bool,
+    messages_forwarded: u64,
+    generations_requested: u64,
+    last_error: Option<String>,
+    tool_invocations: u64,
+    generations_finished: u64,
+    /// Counters and timings for `GetMetrics`
+    #[serde(default)]
+    metrics: Metrics,
+    /// Sessions with a generation that hasn't finished yet
+    generations_in_flight: BTreeSet<String>,
+    /// Generation parameters from `AddMessage` overrides for each session's next
+    /// generation
+    #[serde(default)]
+    pending_overrides: BTreeMap<String, GenerationConfig>,
+    /// Sessions whose generation runs on overridden parameters, to be given theirs back
+    /// when it ends
+    #[serde(default)]
+    overridden_generations: BTreeSet<String>,
+    /// Id of each session's latest generation, e.g. `gen-7`
+    #[serde(default)]
+    generation_ids: BTreeMap<String, String>,
+    #[serde(default)]
+    next_generation_number: u64,
+    /// Cancelled generations whose end the chat-state actor may still report, by
+    /// session; that report is ignored rather than taken for the next generation's
+    #[serde(default)]
+    stale_generations: BTreeMap<String, String>,
+    /// Set once the workflow's completion has been accepted
+    workflow_finished: bool,
+    /// Security findings that passed CWE validation
+    security_findings: Vec<review::SecurityFinding>,
+    /// Findings the model submitted with `submit_review` since the workflow started
+    #[serde(default)]
+    review_findings: Vec<ReviewFinding>,
+    /// Well-formed findings of a benchmarked performance review since the workflow
+    /// started
+    #[serde(default)]
+    benchmark_findings: Vec<BenchmarkFinding>,
+    /// HEAD when the workflow started; commits after it are checked against the
+    /// commit convention and reported in the workflow result
+    commit_base: Option<String>,
+    /// Branch checked out when the workflow started, for its verification
+    #[serde(default)]
+    workflow_branch: Option<String>,
+    convention_reprompts: u32,
+    /// Generations of the default session since the workflow was auto-initiated
+    #[serde(default)]
+    workflow_turns: u32,
+    /// `workflow_turns` when the model was told to wrap up
+    #[serde(default)]
+    wrap_up_sent_at: Option<u32>,
+    /// When the auto-initiated workflow started, in seconds since the epoch
+    #[serde(default)]
+    workflow_started_at: Option<u64>,
+    /// Phase of the auto-initiated workflow, read from the model's git tool calls
+    #[serde(default)]
+    workflow_phase: ProgressPhase,
+    /// Set when the workflow was stopped for going past `max_turns` or
+    /// `workflow_timeout_secs`
+    #[serde(default)]
+    workflow_aborted: Option<WorkflowStall>,
+    /// Progress of the configured pipeline, until it finishes or `SetWorkflow` leaves it
+    #[serde(default)]
+    pipeline: Option<PipelineRun>,
+    /// Spelling suggestions already offered, as `<sha>:<word>`, so declined ones aren't
+    /// raised again
+    offered_spelling: BTreeSet<String>,
+    /// When the last scheduled digest was sent, in seconds since the epoch
+    last_digest_at: Option<u64>,
+    /// Last and next run of each `schedule` entry, in order
+    #[serde(default)]
+    scheduled_runs: Vec<ScheduledRun>,
+    /// Warnings found while validating the initial config
+    #[serde(default)]
+    config_diagnostics: Vec<config_check::ConfigDiagnostic>,
+    /// Environment checks run at init
+    #[serde(default)]
+    preflight: Option<preflight::PreflightReport>,
+    /// Set while the divergence watch threshold is exceeded, so it notifies once
+    /// per crossing
+    #[serde(default)]
+    divergence_alerted: bool,
+    /// Parsed history query results, used in performance mode
+    #[serde(default)]
+    history_cache: HistoryCache,
+    #[serde(default)]
+    commit_graph_checked: bool,
+    /// Incremental reviews from `ReviewRefs`, keyed by branch
+    #[serde(default)]
+    review_reports: BTreeMap<String, ReviewReport>,
+    /// Branch of the latest `ReviewRefs`; submitted findings are merged into its report
+    #[serde(default)]
+    active_review: Option<String>,
+    #[serde(default)]
+    next_review_number: u64,
+    /// How many of `model_fallbacks` each session has gone through
+    #[serde(default)]
+    fallbacks_used: BTreeMap<String, usize>,
+    /// Exit state of each session whose chat-state actor exited, keyed by session id
+    #[serde(default)]
+    closed_sessions: BTreeMap<String, protocol::ChatStateExit>,
+    /// Times the default session's workflow was retried after its actor exited
+    #[serde(default)]
+    exit_respawns: u32,
+    /// Recent crashes of each session's chat-state actor, within the `crash_loop` window
+    #[serde(default)]
+    crashes: BTreeMap<String, Vec<Crash>>,
+    /// Sessions given up on after a crash loop, keyed by session id
+    #[serde(default)]
+    failed_sessions: BTreeMap<String, SessionFailure>,
+    /// Model each session switched to with a fallback or a downgrade
+    #[serde(default)]
+    session_models: BTreeMap<String, String>,
+    /// Model preset in use, until `UpdateConfig` changes the model
+    #[serde(default)]
+    model_preset: Option<String>,
+    /// How many of `budget.downgrades` the usage has reached
+    #[serde(default)]
+    downgrade_level: usize,
+    /// Downgrade level each session's chat-state actor runs at
+    #[serde(default)]
+    session_downgrades: BTreeMap<String, usize>,
+    /// Fingerprints of the findings `PublishReview` has posted, keyed by report id
+    #[serde(default)]
+    posted_review_comments: BTreeMap<String, BTreeSet<String>>,
+    /// HEAD when the pull request changelog was last brought up to date
+    #[serde(default)]
+    pr_changelog_synced_at: Option<String>,
+    /// `commit_convention.trailers` with their variables resolved when the workflow started
+    #[serde(default)]
+    commit_trailers: Vec<String>,
+    /// Commit template values sent with `SetTemplateVars`
+    #[serde(default)]
+    template_vars: BTreeMap<String, String>,
+    /// The rebase from `PlanRebase`, kept after it finishes until the next one
+    #[serde(default)]
+    rebase: Option<RebaseState>,
+    /// Values extracted from the branch name at init, by `branch_context`
+    #[serde(default)]
+    branch_context: Option<BranchContext>,
+    /// Ticket from the `ticket` config, or found in the branch name at init
+    #[serde(default)]
+    ticket: Option<Ticket>,
+    /// The running workflow as of the last call, for resuming it after a restart
+    #[serde(default)]
+    checkpoint: Option<WorkflowCheckpoint>,
+    /// The repository found at init when no `current_directory` was given
+    #[serde(default)]
+    repo_info: Option<RepoInfo>,
+    /// Set by `StartChat` to let the commit workflow go ahead despite secret findings
+    #[serde(default)]
+    secrets_allowed: bool,
+    /// Set by `StartChat` to let the commit workflow touch files outside `scope_paths`
+    #[serde(default)]
+    out_of_scope_allowed: bool,
+    /// Args of the running workflow, from `StartChat`, kept for later pipeline steps
+    #[serde(default)]
+    workflow_args: Option<Value>,
+    /// Requests waiting for `Confirm`, keyed by confirmation id
+    #[serde(default)]
+    pending_confirmations: BTreeMap<String, PendingConfirmation>,
+    #[serde(default)]
+    next_confirmation_number: u64,
+    /// `<session>:<action>` pairs confirmed once, for `once-per-session` tiers
+    #[serde(default)]
+    confirmed_actions: BTreeSet<String>,
+    /// Timestamp of the latest `Tick`, which drives scheduled work
+    #[serde(default)]
+    last_tick_at: Option<u64>,
+    /// When the actor was initialized, by the host clock
+    #[serde(default)]
+    started_at: Option<u64>,
+    /// Whether the default chat-state actor answered the latest `Ping` probe
+    #[serde(default)]
+    child_alive: Option<bool>,
+    /// Tick time of the latest `KeepAlive`
+    #[serde(default)]
+    last_keepalive_at: Option<u64>,
+    /// When a client last sent a request other than a heartbeat, by the host clock
+    #[serde(default)]
+    last_interaction_at: Option<u64>,
+    /// Set while halted by the dead-man switch
+    #[serde(default)]
+    halt: Option<HaltSnapshot>,
+    /// The bisect of the `bisect` workflow or of `StartBisect`, kept after it finishes
+    /// until the next one
+    #[serde(default)]
+    bisect: Option<BisectState>,
+    /// The cherry-pick of the `cherry-pick` workflow, kept after it finishes until the
+    /// next one
+    #[serde(default)]
+    cherry_pick: Option<CherryPickState>,
+    /// The sync of the `sync` workflow, kept after it finishes until the next one
+    #[serde(default)]
+    sync: Option<SyncState>,
+    /// Where the repository stood before the user's latest turn in the default session,
+    /// for `UndoLastTurn`
+    #[serde(default)]
+    last_turn: Option<TurnMark>,
+    /// Latest requests and child notifications, recorded with `enable_debug`
+    #[serde(default)]
+    recent_events: VecDeque<DebugEvent>,
+    /// Actors registered with `Subscribe` and the event kinds they want; all kinds
+    /// when empty
+    #[serde(default)]
+    subscribers: BTreeMap<String, BTreeSet<EventKind>>,
+    /// The server started for the `http` config
+    #[serde(default)]
+    http_server: Option<HttpServer>,
+    /// Latest events, for `GET /events`
+    #[serde(default)]
+    http_events: VecDeque<BufferedEvent>,
+    #[serde(default)]
+    next_http_event_id: u64,
+    /// Tokens used and their cost, keyed by session id
+    #[serde(default)]
+    usage: BTreeMap<String, Usage>,
+    /// Set once the `budget` is spent; no generation starts after that
+    #[serde(default)]
+    budget_exceeded: bool,
+    /// Frame streams of channel clients, keyed by stream id
+    #[serde(default)]
+    channel_streams: BTreeMap<String, Stream>,
+    #[serde(default)]
+    next_stream_number: u64,
+    /// Requests to chat-state actors that couldn't be sent, oldest first
+    #[serde(default)]
+    outbound_queue: VecDeque<QueuedSend>,
+    /// Source of the jitter in retry delays
+    #[serde(default)]
+    randomness: Randomness,
+    /// Stashes listed when the stash-triage workflow started
+    #[serde(default)]
+    stash_triage: Option<Vec<StashEntry>>,
+    /// Whether the model was already asked about stashes left without a decision
+    #[serde(default)]
+    stash_reprompted: bool,
+    /// Files staged when the hook-review workflow started
+    #[serde(default)]
+    hook_review_files: Option<Vec<String>>,
+    /// Whether the model was already asked to stage the fixes it left unstaged
+    #[serde(default)]
+    hook_review_reprompted: bool,
+    /// `SendAndAwait` requests waiting for their generation, keyed by request id, with
+    /// the session they wait on
+    #[serde(default)]
+    pending_replies: BTreeMap<String, String>,
+    /// `SuggestCommitMessage`, `SuggestBranchName`, and `SuggestPrDescription` requests
+    /// waiting for their generation, keyed by the throwaway chat-state actor working on them
+    #[serde(default)]
+    suggestions: BTreeMap<String, PendingSuggestion>,
+    /// The model's notes to itself, by session
+    #[serde(default)]
+    scratchpads: BTreeMap<String, Scratchpad>,
+    /// Messages waiting for their session's generation to end, by session
+    #[serde(default)]
+    held_messages: BTreeMap<String, Vec<HeldMessage>>,
+    /// Proposal of the branch-cleanup workflow and the branches deleted so far
+    #[serde(default)]
+    branch_cleanup: Option<BranchCleanup>,
+    /// Release the tag-and-release workflow proposed, with the notes drafted so far
+    #[serde(default)]
+    release: Option<Release>,
+    /// Commit plan the commit workflow proposed, with `commit_split`
+    #[serde(default)]
+    commit_plan: Option<CommitPlan>,
+    /// File summaries of the pending diff, from the `diff_summary` pre-pass
+    #[serde(default)]
+    diff_summary: Option<DiffSummary>,
+    /// Pre-pass waiting for its chat-state actor before the opening message goes out
+    #[serde(default)]
+    diff_summary_pass: Option<DiffSummaryPass>,
+    /// Statistics gathered for `repo_stats`, reused until they are `ttl_secs` old
+    #[serde(default)]
+    repo_stats: Option<RepoStats>,
+    /// Assistants tasks can be delegated to
+    #[serde(default)]
+    siblings: Vec<Sibling>,
+    /// Tasks delegated to siblings, oldest first
+    #[serde(default)]
+    delegations: Vec<Delegation>,
+    #[serde(default)]
+    next_delegation_number: u64,
+    /// The assistant that delegated the running workflow, told its result when it
+    /// completes
+    #[serde(default)]
+    delegated_by: Option<DelegationSource>,
+    /// The latest `StartFanOut`, kept after it finishes until the next one
+    #[serde(default)]
+    fan_out: Option<FanOutResult>,
+    #[serde(default)]
+    next_fan_out_number: u64,
+    /// Conflicts left as of the last `GetConflicts` or `ResolveConflict`
+    #[serde(default)]
+    conflicts: Vec<ConflictedFile>,
+    /// Sessions claimed with `AcquireTurn`, keyed by session id
+    #[serde(default)]
+    turns: BTreeMap<String, TurnClaim>,
+    /// Tool calls and held requests, for `GetAuditLog` and workflow results
+    #[serde(default)]
+    audit_log: AuditLog,
+    /// Id of the first audit log entry of the current workflow
+    #[serde(default)]
+    audit_base: u64,
+    /// Store created for session snapshots when `session_store_id` isn't configured
+    #[serde(default)]
+    session_store_id: Option<String>,
 }
 
 impl GitChatState {
-    fn new(
-        actor_id: String,
-        config: Value,
-        current_directory: Option<String>,
-        task: Option<String>,
-    ) -> Self {
+    fn new(actor_id: String, config: Value, assistant_config: &GitAssistantConfig) -> Self {
+        let fingerprint = config_fingerprint::fingerprint(&config);
         Self {
             actor_id,
             chat_state_actor_id: None,
             original_config: config,
-            current_directory,
-            task,
+            config_overrides: None,
+            config_compacted: false,
+            config_fingerprint: Some(fingerprint),
+            safe_mode: None,
+            tool_outage: None,
+            assistant_config: assistant_config.clone(),
+            current_directory: assistant_config.current_directory.clone(),
+            workflow: assistant_config.workflow.clone(),
+            sessions: BTreeMap::new(),
+            next_session_number: 1,
+            dirty_worktree_policy: assistant_config.dirty_worktree_policy.unwrap_or_default(),
+            git_tools_actor_id: None,
+            exec_tools_actor_id: None,
+            model_git_tools_actor_id: None,
+            forge_tools_actor_id: None,
+            task_monitor_actor_id: None,
+            task_monitor_restarts: 0,
+            auto_initiated: false,
+            messages_forwarded: 0,
+            generations_requested: 0,
+            last_error: None,
+            tool_invocations: 0,
+            generations_finished: 0,
+            metrics: Metrics::default(),
+            generations_in_flight: BTreeSet::new(),
+            pending_overrides: BTreeMap::new(),
+            overridden_generations: BTreeSet::new(),
+            generation_ids: BTreeMap::new(),
+            next_generation_number: 0,
+            stale_generations: BTreeMap::new(),
+            workflow_finished: false,
+            security_findings: Vec::new(),
+            review_findings: Vec::new(),
+            benchmark_findings: Vec::new(),
+            commit_base: None,
+            workflow_branch: None,
+            convention_reprompts: 0,
+            workflow_turns: 0,
+            wrap_up_sent_at: None,
+            workflow_started_at: None,
+            workflow_phase: ProgressPhase::default(),
+            workflow_aborted: None,
+            pipeline: (!assistant_config.pipeline.is_empty())
+                .then(|| PipelineRun::new(assistant_config.pipeline.clone())),
+            offered_spelling: BTreeSet::new(),
+            last_digest_at: None,
+            scheduled_runs: Vec::new(),
+            config_diagnostics: Vec::new(),
+            preflight: None,
+            divergence_alerted: false,
+            history_cache: HistoryCache::default(),
+            commit_graph_checked: false,
+            review_reports: BTreeMap::new(),
+            active_review: None,
+            next_review_number: 1,
+            fallbacks_used: BTreeMap::new(),
+            closed_sessions: BTreeMap::new(),
+            exit_respawns: 0,
+            crashes: BTreeMap::new(),
+            failed_sessions: BTreeMap::new(),
+            session_models: BTreeMap::new(),
+            model_preset: None,
+            downgrade_level: 0,
+            session_downgrades: BTreeMap::new(),
+            posted_review_comments: BTreeMap::new(),
+            pr_changelog_synced_at: None,
+            commit_trailers: Vec::new(),
+            template_vars: BTreeMap::new(),
+            rebase: None,
+            branch_context: None,
+            ticket: None,
+            checkpoint: None,
+            repo_info: None,
+            secrets_allowed: false,
+            out_of_scope_allowed: false,
+            workflow_args: None,
+            pending_confirmations: BTreeMap::new(),
+            next_confirmation_number: 1,
+            confirmed_actions: BTreeSet::new(),
+            last_tick_at: None,
+            started_at: Some(clock::now()),
+            child_alive: None,
+            last_keepalive_at: None,
+            last_interaction_at: None,
+            halt: None,
+            bisect: None,
+            cherry_pick: None,
+            sync: None,
+            last_turn: None,
+            recent_events: VecDeque::new(),
+            subscribers: BTreeMap::new(),
+            http_server: None,
+            http_events: VecDeque::new(),
+            next_http_event_id: 1,
+            usage: BTreeMap::new(),
+            budget_exceeded: false,
+            channel_streams: BTreeMap::new(),
+            next_stream_number: 1,
+            stash_triage: None,
+            pending_replies: BTreeMap::new(),
+            suggestions: BTreeMap::new(),
+            scratchpads: BTreeMap::new(),
+            held_messages: BTreeMap::new(),
+            branch_cleanup: None,
+            release: None,
+            commit_plan: None,
+            diff_summary: None,
+            diff_summary_pass: None,
+            repo_stats: None,
+            siblings: assistant_config.siblings.clone(),
+            delegations: Vec::new(),
+            next_delegation_number: 1,
+            delegated_by: None,
+            fan_out: None,
+            next_fan_out_number: 1,
+            conflicts: Vec::new(),
+            turns: BTreeMap::new(),
+            audit_log: AuditLog::default(),
+            audit_base: 0,
+            session_store_id: None,
+            stash_reprompted: false,
+            hook_review_files: None,
+            hook_review_reprompted: false,
+            outbound_queue: VecDeque::new(),
+            randomness: Randomness::new(
+                assistant_config
+                    .retry_backoff
+                    .as_ref()
+                    .and_then(|backoff| backoff.seed),
+            ),
+        }
+    }
+
+    fn status(&self) -> SessionStatus {
+        SessionStatus {
+            chat_state_actor_id: self.chat_state_actor_id.clone(),
+            workflow: self.workflow.clone(),
+            current_directory: self.current_directory.clone(),
+            auto_initiated: self.auto_initiated,
+            messages_forwarded: self.messages_forwarded,
+            generations_requested: self.generations_requested,
+            last_error: self.last_error.clone(),
+            tool_invocations: self.tool_invocations,
+            generations_finished: self.generations_finished,
+            workflow_finished: self.workflow_finished,
+            queued_sends: self.outbound_queue.len(),
+            child_alive: self.child_alive,
+            started_at: self.started_at,
+            workflow_aborted: self.workflow_aborted.is_some(),
+            elapsed: self
+                .started_at
+                .map(|started_at| clock::now().saturating_sub(started_at)),
+            schedule: self.scheduled_runs.clone(),
+            failed_sessions: self.failed_sessions.values().cloned().collect(),
+            model_preset: self.model_preset.clone(),
+            safe_mode: self
+                .safe_mode
+                .as_ref()
+                .map(|safe_mode| safe_mode.error.clone()),
+            ticket_id: self.ticket.as_ref().map(|ticket| ticket.id.clone()),
+            config_fingerprint: self.config_fingerprint.clone(),
+            tool_outage: self.tool_outage.clone().map(Box::new),
         }
     }
 
+    /// Seconds to wait before retry `retry` of a failed send.
+    fn retry_delay(&mut self, retry: u32) -> u64 {
+        self.assistant_config
+            .retry_backoff
+            .clone()
+            .unwrap_or_default()
+            .delay(retry, &mut self.randomness)
+    }
+
+    /// Replace the chat config chat-state actors are spawned with.
+    fn set_original_config(&mut self, config: Value) {
+        self.config_fingerprint = Some(config_fingerprint::fingerprint(&config));
+        self.original_config = config;
+    }
+
     fn set_chat_state_actor_id(&mut self, chat_actor_id: String) {
         self.chat_state_actor_id = Some(chat_actor_id);
     }
@@ -111,70 +1099,671 @@ impl GitChatState {
             .as_ref()
             .ok_or_else(|| "Chat state actor not initialized".to_string())
     }
+
+    /// Chat-state actor for `session_id`, or the default session when `None`.
+    fn chat_actor_for_session(&self, session_id: Option<&str>) -> Result<String, String> {
+        if let Some(failure) = self
+            .failed_sessions
+            .get(session_id.unwrap_or(DEFAULT_SESSION_ID))
+        {
+            return Err(failure.describe());
+        }
+        match session_id {
+            None | Some(DEFAULT_SESSION_ID) => self.get_chat_state_actor_id().cloned(),
+            Some(id) => self
+                .sessions
+                .get(id)
+                .map(|session| session.chat_state_actor_id.clone())
+                .ok_or_else(|| format!("Unknown session: {}", id)),
+        }
+    }
+
+    /// Session id of a chat-state actor, if it belongs to this assistant.
+    fn session_for_chat_actor(&self, chat_actor_id: &str) -> Option<String> {
+        if self.chat_state_actor_id.as_deref() == Some(chat_actor_id) {
+            return Some(DEFAULT_SESSION_ID.to_string());
+        }
+        self.sessions
+            .values()
+            .find(|session| session.chat_state_actor_id == chat_actor_id)
+            .map(|session| session.session_id.clone())
+    }
+
+    fn session_list(&self) -> Vec<SessionInfo> {
+        let default_session =
+            self.chat_state_actor_id
+                .clone()
+                .map(|chat_state_actor_id| SessionInfo {
+                    session_id: DEFAULT_SESSION_ID.to_string(),
+                    chat_state_actor_id,
+                    current_directory: self.current_directory.clone(),
+                    workflow: self.workflow.clone(),
+                });
+        default_session
+            .into_iter()
+            .chain(self.sessions.values().cloned())
+            .collect()
+    }
+
+    /// Re-attach to the children that survived a restart of this actor. Sessions whose
+    /// chat-state actor is gone are dropped; the default session is respawned.
+    fn reattach(&mut self, actor_id: String) -> Result<(), String> {
+        self.actor_id = actor_id;
+        let children = list_children();
+        let is_running =
+            |child_id: &String| children.contains(child_id) || simulated::is_running(child_id);
+
+        self.sessions.retain(|session_id, session| {
+            let running = is_running(&session.chat_state_actor_id);
+            if !running {
+                logging::info(&format!("Session {} is no longer running", session_id));
+            }
+            running
+        });
+        let sessions = &self.sessions;
+        self.outbound_queue.retain(|queued| {
+            queued.session_id == DEFAULT_SESSION_ID || sessions.contains_key(&queued.session_id)
+        });
+        // Tool actors are spawned again on first use
+        if !self.git_tools_actor_id.as_ref().is_some_and(is_running) {
+            self.git_tools_actor_id = None;
+        }
+        if !self.exec_tools_actor_id.as_ref().is_some_and(is_running) {
+            self.exec_tools_actor_id = None;
+        }
+        if !self.forge_tools_actor_id.as_ref().is_some_and(is_running) {
+            self.forge_tools_actor_id = None;
+        }
+        if !self
+            .model_git_tools_actor_id
+            .as_ref()
+            .is_some_and(is_running)
+        {
+            self.model_git_tools_actor_id = None;
+        }
+        // Sessions still point at the old task monitor, so it is replaced right away
+        if let Some(task_monitor_actor_id) = self.task_monitor_actor_id.clone() {
+            if !is_running(&task_monitor_actor_id) {
+                restart_task_monitor(self)?;
+            }
+        }
+
+        match &self.chat_state_actor_id {
+            Some(chat_actor_id) if is_running(chat_actor_id) => {
+                logging::info(&format!(
+                    "Re-attached to chat state actor: {}",
+                    chat_actor_id
+                ));
+            }
+            _ => {
+                logging::warn(
+                    "Chat state actor is gone, spawning a new one; the conversation starts over",
+                );
+                let chat_actor_id = spawn_chat_state_actor(&self.original_config)?;
+                self.set_chat_state_actor_id(chat_actor_id);
+                self.auto_initiated = false;
+                self.metrics.count_restart("chat-state");
+            }
+        }
+        Ok(())
+    }
+
+    /// Id of the assistant's own git tools actor, spawning it on first use.
+    fn git_tools_actor_id(&mut self) -> Result<String, String> {
+        if let Some(actor_id) = &self.git_tools_actor_id {
+            return Ok(actor_id.clone());
+        }
+        let actor_id = git_tools::spawn_git_tools_actor(GIT_MCP_MANIFEST_PATH, None)?;
+        self.git_tools_actor_id = Some(actor_id.clone());
+        Ok(actor_id)
+    }
+
+    /// Id of the git tools actor behind the model's git tools, spawning it on first use.
+    /// Unlike the assistant's own, it commits with the configured identity.
+    fn model_git_tools_actor_id(&mut self) -> Result<String, String> {
+        if let Some(actor_id) = &self.model_git_tools_actor_id {
+            return Ok(actor_id.clone());
+        }
+        let init_state = git_mcp_init_state(&self.assistant_config);
+        let actor_id =
+            git_tools::spawn_git_tools_actor(GIT_MCP_MANIFEST_PATH, init_state.as_ref())?;
+        self.model_git_tools_actor_id = Some(actor_id.clone());
+        Ok(actor_id)
+    }
+
+    /// Bounds for history queries; unbounded outside performance mode. The first call in
+    /// performance mode also makes sure the repository has a commit graph.
+    fn history_bounds(&mut self, git_actor_id: &str) -> HistoryBounds {
+        let mode = match &self.assistant_config.performance_mode {
+            Some(mode) => mode.clone(),
+            None => return HistoryBounds::default(),
+        };
+        if mode.write_commit_graph && !self.commit_graph_checked {
+            if let Err(e) =
+                history::ensure_commit_graph(git_actor_id, self.current_directory.as_deref())
+            {
+                logging::warn(&format!("Could not write commit graph: {}", e));
+            }
+            self.commit_graph_checked = true;
+        }
+        mode.bounds()
+    }
+
+    /// Id of the assistant's own exec tools actor, spawning it on first use.
+    fn exec_tools_actor_id(&mut self) -> Result<String, String> {
+        if let Some(actor_id) = &self.exec_tools_actor_id {
+            return Ok(actor_id.clone());
+        }
+        let actor_id = exec_tools::spawn_exec_tools_actor(EXEC_MCP_MANIFEST_PATH)?;
+        self.exec_tools_actor_id = Some(actor_id.clone());
+        Ok(actor_id)
+    }
+
+    /// Id of the assistant's own forge tools actor, spawning it on first use.
+    fn forge_tools_actor_id(&mut self, config: &ForgeConfig) -> Result<String, String> {
+        if let Some(actor_id) = &self.forge_tools_actor_id {
+            return Ok(actor_id.clone());
+        }
+        let actor_id = forge_tools::spawn_forge_tools_actor(config)?;
+        self.forge_tools_actor_id = Some(actor_id.clone());
+        Ok(actor_id)
+    }
 }
 
 impl Guest for Component {
     fn init(state: Option<Vec<u8>>, params: (String,)) -> Result<(Option<Vec<u8>>,), String> {
-        log("Git chat assistant actor initializing...");
+        logging::info(&format!(
+            "Git chat assistant {} initializing...",
+            BuildInfo::current()
+        ));
 
         let (self_id,) = params;
 
-        // Parse initial configuration if provided
-        let (git_config, current_directory, task) = if let Some(state_bytes) = state {
-            match from_slice::<GitAssistantConfig>(&state_bytes) {
-                Ok(config) => {
-                    log(&format!(
-                        "Parsed initial config with current_directory: {:?}, task: {:?}",
-                        config.current_directory, config.task
-                    ));
-                    let git_config = create_git_optimized_config(
-                        &self_id,
-                        config.current_directory.as_deref(),
-                        &config,
+        // State persisted by a previous run of this actor, rather than an initial config
+        if let Some(Ok(mut git_state)) = state.as_deref().map(decode_state) {
+            logging::configure(
+                git_state.assistant_config.logging.as_ref(),
+                git_state.assistant_config.log_redaction.unwrap_or_default(),
+            );
+            simulated::configure(git_state.assistant_config.simulated_chat_state, &self_id);
+            logging::info("Resuming from persisted state");
+            let chat_actor_id = git_state.chat_state_actor_id.clone();
+            git_state
+                .reattach(self_id)
+                .map_err(|e| format!("Failed to resume chat session: {}", e))?;
+            // A chat-state actor replaced for a changed config gets the conversation
+            let conversation_kept = git_state.chat_state_actor_id == chat_actor_id;
+            if let Err(e) = refresh_chat_config(&mut git_state) {
+                logging::warn(&format!("Could not apply the rebuilt chat config: {}", e));
+            }
+            flush_outbound_queue(&mut git_state, true);
+            if let Some(checkpoint) = git_state.checkpoint.clone() {
+                resume_workflow(&mut git_state, checkpoint, conversation_kept);
+            }
+            // The previous run's server went away with it
+            start_http_server(&mut git_state);
+            let state_bytes = encode_state(&mut git_state)
+                .map_err(|e| format!("Failed to serialize git state: {}", e))?;
+            notify_parent(&git_state, &ready_notification(&git_state, true));
+            logging::info("Git chat assistant actor resumed successfully");
+            return Ok((Some(state_bytes),));
+        }
+
+        // Parse and validate the initial configuration if provided
+        let raw_config: Result<Value, String> = match state {
+            Some(state_bytes) => from_slice(&state_bytes)
+                .map_err(|e| format!("Initial config is not valid JSON: {}", e)),
+            None => {
+                logging::info("No initial state provided, using default configuration");
+                Ok(Value::Object(Default::default()))
+            }
+        };
+        // Known before anything can fail, so the parent hears about failures too
+        let parent_actor_id = ["parent_actor_id", "reply_to"]
+            .iter()
+            .find_map(|field| raw_config.as_ref().ok()?.get(*field)?.as_str())
+            .map(str::to_string);
+        match init_from_config(raw_config, self_id, true) {
+            Ok(state_bytes) => Ok((Some(state_bytes),)),
+            Err(e) => {
+                if let Some(parent_actor_id) = &parent_actor_id {
+                    send_notification(
+                        parent_actor_id,
+                        &GitChatNotification::FatalError {
+                            session_id: None,
+                            error: e.clone(),
+                        },
                     );
-                    (git_config, config.current_directory, config.task)
-                }
-                Err(e) => {
-                    log(&format!(
-                        "Failed to parse initial config, using defaults: {}",
-                        e
-                    ));
-                    let git_config =
-                        create_git_optimized_config(&self_id, None, &GitAssistantConfig::default());
-                    (git_config, None, None)
                 }
+                Err(e)
             }
-        } else {
-            log("No initial state provided, using default configuration");
-            let git_config =
-                create_git_optimized_config(&self_id, None, &GitAssistantConfig::default());
-            (git_config, None, None)
+        }
+    }
+}
+
+/// Start a fresh assistant from its initial config. Returns the encoded state. With
+/// `safe_mode_on_error`, a config that can't be parsed or validated starts the assistant
+/// in safe mode rather than failing init.
+fn init_from_config(
+    raw_config: Result<Value, String>,
+    self_id: String,
+    safe_mode_on_error: bool,
+) -> Result<Vec<u8>, String> {
+    let mut diagnostics = Vec::new();
+    let mut exec_tools_actor_id = None;
+    // Config that isn't even JSON is patched from scratch in safe mode
+    let (raw_config, unreadable) = match raw_config {
+        Ok(raw_config) => (raw_config, None),
+        Err(e) => (Value::Object(Default::default()), Some(e)),
+    };
+    let (raw_config, repo_info) = discover_repository(raw_config, &mut exec_tools_actor_id);
+    let parsed = match unreadable {
+        Some(e) => Err(e),
+        None => parse_init_config(
+            raw_config.clone(),
+            &mut exec_tools_actor_id,
+            &mut diagnostics,
+        ),
+    };
+    let (raw_config, config, model_preset, safe_mode) = match parsed {
+        Ok((parsed_config, config, model_preset)) => (parsed_config, config, model_preset, None),
+        Err(e) if safe_mode_on_error => {
+            logging::error(&format!("Starting in safe mode: {}", e));
+            let safe_config = safe_mode::config(&raw_config);
+            let config: GitAssistantConfig = serde_json::from_value(safe_config.clone())
+                .map_err(|e| format!("Failed to parse safe mode config: {}", e))?;
+            let safe_mode = SafeMode {
+                error: e,
+                raw_config,
+                entered_at: clock::now(),
+            };
+            (safe_config, config, None, Some(safe_mode))
+        }
+        Err(e) => return Err(e),
+    };
+    // The errors that sent the assistant into safe mode are reported, not failed on
+    let config_errors = if safe_mode.is_some() {
+        std::mem::take(&mut diagnostics)
+    } else {
+        Vec::new()
+    };
+    logging::configure(
+        config.logging.as_ref(),
+        config.log_redaction.unwrap_or_default(),
+    );
+    simulated::configure(config.simulated_chat_state, &self_id);
+    logging::debug(&format!(
+        "Parsed initial config with current_directory: {:?}, workflow: {:?}",
+        config.current_directory, config.workflow
+    ));
+
+    let branch_context = read_branch_context(&config, &mut exec_tools_actor_id);
+    let ticket = read_ticket(&config, &mut exec_tools_actor_id);
+    let task_monitor_actor_id = if supervises_task_monitor(&config) {
+        Some(
+            task_monitor::spawn_task_monitor(TASK_MONITOR_MANIFEST_PATH, &self_id)
+                .map_err(|e| format!("Failed to spawn task monitor: {}", e))?,
+        )
+    } else {
+        None
+    };
+    let git_config = create_git_optimized_config(
+        &self_id,
+        config.current_directory.as_deref(),
+        &config,
+        branch_context.as_ref(),
+        ticket.as_ref(),
+        repo_info.as_ref(),
+        task_monitor_actor_id.as_deref(),
+    );
+
+    logging::debug_value("Using git config", "config", &git_config);
+
+    // Create our state
+    let mut git_state = GitChatState::new(self_id, git_config.clone(), &config);
+    git_state.exec_tools_actor_id = exec_tools_actor_id;
+    git_state.branch_context = branch_context;
+    git_state.ticket = ticket;
+    git_state.repo_info = repo_info;
+    git_state.task_monitor_actor_id = task_monitor_actor_id;
+    git_state.model_preset = model_preset;
+    // The parent gets the default event kinds, as if it had subscribed
+    if let Some(parent_actor_id) = &config.parent_actor_id {
+        git_state
+            .subscribers
+            .entry(parent_actor_id.clone())
+            .or_default();
+    }
+
+    let report = run_preflight(&mut git_state, &raw_config, &mut diagnostics);
+    fail_on_config_errors(&diagnostics)?;
+    for diagnostic in &diagnostics {
+        logging::warn(&format!("Config warning: {:?}", diagnostic));
+    }
+    for check in report.failures() {
+        logging::warn(&format!("Preflight check failed: {:?}", check));
+    }
+    if config.strict_preflight && !report.failures().is_empty() {
+        let error_msg = format!("Preflight failed: {}", report.describe_failures());
+        logging::error(&error_msg);
+        return Err(error_msg);
+    }
+    git_state.config_diagnostics = diagnostics;
+    git_state.config_diagnostics.extend(config_errors);
+    // Without git tools the assistant still starts, advising rather than acting
+    let mut git_config = git_config;
+    if let Some(outage) = advice_only::outage(&report) {
+        logging::error(&format!(
+            "Starting in advice-only mode, the git tools are unavailable: {}",
+            outage.error
+        ));
+        git_state.assistant_config.advice_only = true;
+        git_config = base_chat_config(&git_state);
+        git_state.set_original_config(git_config.clone());
+        git_state.tool_outage = Some(outage);
+    }
+    git_state.preflight = Some(report);
+    git_state.safe_mode = safe_mode;
+
+    // Spawn the chat-state actor with the git config
+    match spawn_chat_state_actor(&git_config) {
+        Ok(chat_actor_id) => {
+            logging::info(&format!("Chat state actor spawned: {}", chat_actor_id));
+            git_state.set_chat_state_actor_id(chat_actor_id);
+        }
+        Err(e) => {
+            let error_msg = format!("Failed to spawn chat state actor: {}", e);
+            logging::error(&error_msg);
+            return Err(error_msg);
+        }
+    }
+
+    start_http_server(&mut git_state);
+    if let Some(outage) = &git_state.tool_outage {
+        let event = GitChatEvent::ToolsAvailabilityChanged {
+            available: false,
+            error: Some(outage.error.clone()),
         };
+        publish(&mut git_state, &event);
+    }
 
-        log(&format!("Using git config: {}", git_config));
+    // Serialize our state
+    let state_bytes = encode_state(&mut git_state)
+        .map_err(|e| format!("Failed to serialize git state: {}", e))?;
 
-        // Create our state
-        let mut git_state = GitChatState::new(self_id, git_config.clone(), current_directory, task);
+    notify_parent(&git_state, &ready_notification(&git_state, false));
+    logging::info("Git chat assistant actor initialized successfully");
+    Ok(state_bytes)
+}
+
+/// Apply the repository file to the init config, validate it, and parse it. Returns the
+/// config as parsed, the parsed config, and the model preset it picked.
+fn parse_init_config(
+    raw_config: Value,
+    exec_tools_actor_id: &mut Option<String>,
+    diagnostics: &mut Vec<config_check::ConfigDiagnostic>,
+) -> Result<(Value, GitAssistantConfig, Option<String>), String> {
+    let raw_config = apply_repo_config(raw_config, exec_tools_actor_id, diagnostics);
+    diagnostics.extend(config_check::validate(&raw_config));
+    fail_on_config_errors(diagnostics)?;
+    let raw_config = pipeline::split_sequence(raw_config);
+    let (raw_config, model_preset) = model_preset::resolve(raw_config)?;
+    let config: GitAssistantConfig = serde_json::from_value(raw_config.clone())
+        .map_err(|e| format!("Failed to parse initial config: {}", e))?;
+    Ok((raw_config, config, model_preset))
+}
+
+/// The exec actor used during init, spawned the first time one is needed.
+fn init_exec_actor(exec_tools_actor_id: &mut Option<String>) -> Result<String, String> {
+    match exec_tools_actor_id {
+        Some(actor_id) => Ok(actor_id.clone()),
+        None => {
+            let actor_id = exec_tools::spawn_exec_tools_actor(EXEC_MCP_MANIFEST_PATH)?;
+            Ok(exec_tools_actor_id.insert(actor_id).clone())
+        }
+    }
+}
 
-        // Spawn the chat-state actor with the git config
-        match spawn_chat_state_actor(&git_config) {
-            Ok(chat_actor_id) => {
-                log(&format!("Chat state actor spawned: {}", chat_actor_id));
-                git_state.set_chat_state_actor_id(chat_actor_id);
+/// Without a `current_directory`, find the repository the assistant runs in and use its
+/// root. Returns the config and what was found; failures are logged and leave the config
+/// as it is.
+fn discover_repository(
+    mut raw_config: Value,
+    exec_tools_actor_id: &mut Option<String>,
+) -> (Value, Option<RepoInfo>) {
+    if raw_config
+        .get("current_directory")
+        .is_some_and(|dir| !dir.is_null())
+    {
+        return (raw_config, None);
+    }
+    let discovered = init_exec_actor(exec_tools_actor_id)
+        .and_then(|actor_id| repo_info::discover(&actor_id, None));
+    match discovered {
+        Ok(repo_info) => {
+            logging::info(&format!(
+                "No current_directory given; using the repository at {}",
+                repo_info.root
+            ));
+            if let Value::Object(fields) = &mut raw_config {
+                fields.insert(
+                    "current_directory".to_string(),
+                    Value::String(repo_info.root.clone()),
+                );
             }
-            Err(e) => {
-                let error_msg = format!("Failed to spawn chat state actor: {}", e);
-                log(&error_msg);
-                return Err(error_msg);
+            (raw_config, Some(repo_info))
+        }
+        Err(e) => {
+            logging::warn(&format!("Could not find a repository to work in: {}", e));
+            (raw_config, None)
+        }
+    }
+}
+
+/// Merge the repository config file in `current_directory`, if there is one, under the
+/// init config, spawning the exec actor to read the file.
+fn apply_repo_config(
+    raw_config: Value,
+    exec_tools_actor_id: &mut Option<String>,
+    diagnostics: &mut Vec<config_check::ConfigDiagnostic>,
+) -> Value {
+    let directory = match raw_config.get("current_directory").and_then(Value::as_str) {
+        Some(directory) => directory.to_string(),
+        None => return raw_config,
+    };
+    let exec_actor_id = match init_exec_actor(exec_tools_actor_id) {
+        Ok(actor_id) => actor_id,
+        Err(e) => {
+            diagnostics.push(config_check::ConfigDiagnostic::warning(
+                repo_config::REPO_CONFIG_FILES[0],
+                format!("could not look for a repository config file: {}", e),
+            ));
+            return raw_config;
+        }
+    };
+    let (name, contents) = match repo_config::read_repo_config(&exec_actor_id, &directory) {
+        Ok(Some(file)) => file,
+        Ok(None) => return raw_config,
+        Err(e) => {
+            diagnostics.push(config_check::ConfigDiagnostic::warning(
+                repo_config::REPO_CONFIG_FILES[0],
+                format!("could not read the repository config file: {}", e),
+            ));
+            return raw_config;
+        }
+    };
+    match repo_config::parse_repo_config(name, &contents) {
+        Ok(repo) => {
+            logging::debug(&format!("Merging repository config from {}", name));
+            let (merged, warnings) = repo_config::merge_under(&raw_config, &repo, name);
+            diagnostics.extend(warnings);
+            merged
+        }
+        Err(e) => {
+            diagnostics.push(config_check::ConfigDiagnostic::error(
+                name,
+                format!("could not parse: {}", e),
+            ));
+            raw_config
+        }
+    }
+}
+
+/// Extract the `branch_context` values from the current branch, spawning the exec actor
+/// if reading the repository config didn't. Failures are logged and leave no context.
+fn read_branch_context(
+    config: &GitAssistantConfig,
+    exec_tools_actor_id: &mut Option<String>,
+) -> Option<BranchContext> {
+    let branch_context = config.branch_context.as_ref()?;
+    let branch = read_branch_name(config, exec_tools_actor_id)?;
+    match branch_context.extract(&branch) {
+        Ok(Some(context)) => {
+            logging::info(&format!(
+                "Branch context from {}: {:?}",
+                branch, context.values
+            ));
+            Some(context)
+        }
+        Ok(None) => {
+            logging::debug(&format!("Branch {} has no context to extract", branch));
+            None
+        }
+        Err(e) => {
+            logging::warn(&format!("Could not extract branch context: {}", e));
+            None
+        }
+    }
+}
+
+/// The `ticket_id` override, or the ticket found in the current branch name. Failures
+/// are logged and leave no ticket.
+fn read_ticket(
+    config: &GitAssistantConfig,
+    exec_tools_actor_id: &mut Option<String>,
+) -> Option<Ticket> {
+    let ticket_config = config.ticket.as_ref()?;
+    if let Some(ticket) = ticket_config.configured() {
+        return Some(ticket);
+    }
+    let branch = read_branch_name(config, exec_tools_actor_id)?;
+    match ticket_config.extract(&branch) {
+        Ok(Some(ticket)) => {
+            logging::info(&format!("Ticket {} from branch {}", ticket.id, branch));
+            Some(ticket)
+        }
+        Ok(None) => {
+            logging::debug(&format!("Branch {} names no ticket", branch));
+            None
+        }
+        Err(e) => {
+            logging::warn(&format!("Could not find the ticket: {}", e));
+            None
+        }
+    }
+}
+
+/// The current branch of the config's directory, spawning the exec actor if needed.
+fn read_branch_name(
+    config: &GitAssistantConfig,
+    exec_tools_actor_id: &mut Option<String>,
+) -> Option<String> {
+    let exec_actor_id = match init_exec_actor(exec_tools_actor_id) {
+        Ok(actor_id) => actor_id,
+        Err(e) => {
+            logging::warn(&format!("Could not read the branch name: {}", e));
+            return None;
+        }
+    };
+    match exec_tools::run_command(
+        &exec_actor_id,
+        config.current_directory.as_deref(),
+        "git rev-parse --abbrev-ref HEAD",
+    ) {
+        Ok(branch) => Some(branch.trim().to_string()),
+        Err(e) => {
+            logging::warn(&format!("Could not read the branch name: {}", e));
+            None
+        }
+    }
+}
+
+/// Check the environment before the session starts. Manifest paths can only be checked
+/// from outside the sandbox; missing configured ones are config errors as well.
+fn run_preflight(
+    git_state: &mut GitChatState,
+    raw_config: &Value,
+    diagnostics: &mut Vec<config_check::ConfigDiagnostic>,
+) -> preflight::PreflightReport {
+    let exec_actor = git_state.exec_tools_actor_id();
+    let git_actor = git_state.git_tools_actor_id();
+    let mut checks = vec![
+        preflight::actor_check("exec_tools", &exec_actor),
+        preflight::actor_check("git_tools", &git_actor),
+    ];
+
+    let manifest_paths = config_check::manifest_paths(raw_config);
+    match &exec_actor {
+        Ok(exec_actor_id) => {
+            let found = config_check::check_manifest_paths(exec_actor_id, &manifest_paths);
+            checks.extend(preflight::manifest_checks(&manifest_paths, &found));
+            diagnostics.extend(found);
+            if !git_state.assistant_config.simulated_chat_state {
+                checks.push(preflight::builtin_manifest_check(
+                    "chat_state",
+                    CHAT_STATE_MANIFEST_PATH,
+                    |path| config_check::manifest_present(exec_actor_id, path),
+                ));
             }
         }
+        Err(e) if !manifest_paths.is_empty() => {
+            diagnostics.push(config_check::ConfigDiagnostic::warning(
+                "mcp_servers",
+                format!("could not check manifest paths: {}", e),
+            ))
+        }
+        Err(_) => {}
+    }
+
+    checks.push(preflight::repository_check(
+        git_actor.as_deref().ok(),
+        git_state.assistant_config.current_directory.as_deref(),
+    ));
+    preflight::PreflightReport {
+        checked_at: clock::now(),
+        checks,
+    }
+}
 
-        // Serialize our state
-        let state_bytes =
-            to_vec(&git_state).map_err(|e| format!("Failed to serialize git state: {}", e))?;
+/// Fail init with every error found in the config.
+fn fail_on_config_errors(diagnostics: &[config_check::ConfigDiagnostic]) -> Result<(), String> {
+    let errors: Vec<config_check::ConfigDiagnostic> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == config_check::Severity::Error)
+        .cloned()
+        .collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let error_msg = format!("Invalid config: {}", config_check::describe(&errors));
+    logging::error(&error_msg);
+    Err(error_msg)
+}
 
-        log("Git chat assistant actor initialized successfully");
-        Ok((Some(state_bytes),))
+/// Start the server for the `http` config, if there is one. The assistant still works
+/// through the message server when it can't be started.
+fn start_http_server(git_state: &mut GitChatState) {
+    git_state.http_server = None;
+    let config = match &git_state.assistant_config.http {
+        Some(config) => config,
+        None => return,
+    };
+    match http::start(config) {
+        Ok(server) => {
+            logging::info(&format!("Serving HTTP on {}:{}", config.host, server.port));
+            git_state.http_server = Some(server);
+        }
+        Err(e) => logging::error(&format!("HTTP transport disabled: {}", e)),
     }
 }
 
@@ -199,12 +1788,12 @@ pub struct ChainEvent {
 
 impl SupervisorHandlers for Component {
     fn handle_child_error(
-        state: Option<Vec<u8>>,
+        mut state: Option<Vec<u8>>,
         params: (String, WitActorError),
     ) -> Result<(Option<Vec<u8>>,), String> {
         let (child, error) = params;
 
-        log(&format!(
+        logging::warn(&format!(
             "Child {} encountered an error: {:?}",
             child, error
         ));
@@ -214,26 +1803,57 @@ impl SupervisorHandlers for Component {
                 error_type: WitErrorType::Internal,
                 data,
             } => {
-                log("Internal error type");
+                logging::debug("Internal error type");
                 let error_event: ChainEvent = match from_slice(&data.unwrap()) {
                     Ok(event) => event,
                     Err(e) => {
                         let error_msg = format!("Failed to parse internal error data: {}", e);
-                        log(&error_msg);
+                        logging::error(&error_msg);
                         return Err(error_msg);
                     }
                 };
 
-                log(&format!("Internal error event: {:?}", error_event));
+                logging::error(&format!("Internal error event: {:?}", error_event));
 
-                let error_str = String::from_utf8_lossy(&error_event.data);
+                let error_str = String::from_utf8_lossy(&error_event.data).to_string();
+                publish_child_crash(&mut state, &child, &error_str);
+                fail_suggestion_after(&mut state, &child, &error_str);
+                fail_delegations_after(&mut state, &child, &error_str);
+                if fail_repo_run_after(&mut state, &child, &error_str) {
+                    return Ok((state,));
+                }
+                if record_crash_after(&mut state, &child, "internal", &error_str) {
+                    return Ok((state,));
+                }
+                if let Some(state) = restart_task_monitor_after(&state, &child)? {
+                    return Ok((Some(state),));
+                }
+                if let Some(state) = fall_back_after_child_error(&state, &child, &error_str)? {
+                    return Ok((Some(state),));
+                }
                 Err(format!("Internal error in child {}: {}", child, error_str))
             }
             _ => {
-                log("Other error type");
+                logging::debug("Other error type");
                 let data = error.data.unwrap();
-                log(&format!("Error data: {:?}", data));
-                let error_str = String::from_utf8_lossy(&data);
+                logging::debug(&format!("Error data: {:?}", data));
+                let error_str = String::from_utf8_lossy(&data).to_string();
+                publish_child_crash(&mut state, &child, &error_str);
+                fail_suggestion_after(&mut state, &child, &error_str);
+                fail_delegations_after(&mut state, &child, &error_str);
+                if fail_repo_run_after(&mut state, &child, &error_str) {
+                    return Ok((state,));
+                }
+                let error_type = error_type_name(&error.error_type);
+                if record_crash_after(&mut state, &child, error_type, &error_str) {
+                    return Ok((state,));
+                }
+                if let Some(state) = restart_task_monitor_after(&state, &child)? {
+                    return Ok((Some(state),));
+                }
+                if let Some(state) = fall_back_after_child_error(&state, &child, &error_str)? {
+                    return Ok((Some(state),));
+                }
                 Err(format!("Other error in child {}: {}", child, error_str))
             }
         }
@@ -243,9 +1863,31 @@ impl SupervisorHandlers for Component {
         state: Option<Vec<u8>>,
         params: (String, Option<Vec<u8>>),
     ) -> Result<(Option<Vec<u8>>,), String> {
-        let (child_id, _exit_state) = params;
-        log(&format!("Child exit: {}", child_id));
-        Ok((state,))
+        let (child_id, exit_state) = params;
+        logging::debug(&format!("Child exit: {}", child_id));
+        let mut state = state;
+        fail_suggestion_after(&mut state, &child_id, "it exited");
+        fail_delegations_after(&mut state, &child_id, "it exited");
+        if fail_repo_run_after(&mut state, &child_id, "it exited") {
+            return Ok((state,));
+        }
+        if let Some(state) = restart_task_monitor_after(&state, &child_id)? {
+            return Ok((Some(state),));
+        }
+        let mut git_state: GitChatState = match state.as_deref().map(decode_state) {
+            Some(Ok(git_state)) => git_state,
+            _ => return Ok((state,)),
+        };
+        // Only chat-state actors leave a session behind; tool actors are spawned again
+        let session_id = match git_state.session_for_chat_actor(&child_id) {
+            Some(session_id) => session_id,
+            None => return Ok((state,)),
+        };
+        let exit = protocol::ChatStateExit::parse(exit_state.as_deref());
+        close_exited_session(&mut git_state, &session_id, &child_id, exit);
+        let new_state = encode_state(&mut git_state)
+            .map_err(|e| format!("Failed to serialize git state: {}", e))?;
+        Ok((Some(new_state),))
     }
 
     fn handle_child_external_stop(
@@ -253,7 +1895,7 @@ impl SupervisorHandlers for Component {
         params: (String,),
     ) -> Result<(Option<Vec<u8>>,), String> {
         let (child_id,) = params;
-        log(&format!("Child external stop: {}", child_id));
+        logging::debug(&format!("Child external stop: {}", child_id));
         Ok((state,))
     }
 }
@@ -263,38 +1905,108 @@ impl MessageServerClient for Component {
         state: Option<Vec<u8>>,
         params: (Vec<u8>,),
     ) -> Result<(Option<Vec<u8>>,), String> {
-        log("Git chat assistant handling send message");
+        logging::debug("Git chat assistant handling send message");
 
-        let parsed_state: GitChatState = match state {
-            Some(state_bytes) => match from_slice(&state_bytes) {
+        let mut parsed_state: GitChatState = match state {
+            Some(state_bytes) => match decode_state(&state_bytes) {
                 Ok(state) => state,
                 Err(e) => {
                     let error_msg = format!("Failed to deserialize git state: {}", e);
-                    log(&error_msg);
+                    logging::error(&error_msg);
                     return Err(error_msg);
                 }
             },
             None => {
                 let error_msg = "No state available";
-                log(error_msg);
+                logging::error(error_msg);
                 return Err(error_msg.to_string());
             }
         };
 
-        match from_slice::<TaskComplete>(&params.0) {
-            Ok(msg) => {
-                log(&format!("Received task completion message: {:?}", msg));
-
-                let _ = shutdown(None);
-            }
-            Err(e) => {
-                let error_msg = format!("Failed to parse message: {}", e);
-                log(&error_msg);
-                return Err(error_msg);
+        logging::configure(
+            parsed_state.assistant_config.logging.as_ref(),
+            parsed_state
+                .assistant_config
+                .log_redaction
+                .unwrap_or_default(),
+        );
+        simulated::configure(
+            parsed_state.assistant_config.simulated_chat_state,
+            &parsed_state.actor_id,
+        );
+        match from_slice::<protocol::ChildNotification>(&params.0) {
+            Ok(notification) => {
+                let session_id = match &notification {
+                    protocol::ChildNotification::GenerationFinished {
+                        chat_state_actor_id: Some(chat_actor_id),
+                        ..
+                    }
+                    | protocol::ChildNotification::GenerationFailed {
+                        chat_state_actor_id: Some(chat_actor_id),
+                        ..
+                    } => parsed_state.session_for_chat_actor(chat_actor_id),
+                    _ => None,
+                };
+                logging::set_scope(None, session_id.as_deref());
+                logging::trace_value(
+                    "Received child notification",
+                    "notification",
+                    &serde_json::to_value(&notification).unwrap_or_default(),
+                );
+                // Ticks would push everything else out
+                let is_tick = matches!(notification, protocol::ChildNotification::Tick { .. });
+                if parsed_state.assistant_config.enable_debug && !is_tick {
+                    debug_dump::record(
+                        &mut parsed_state.recent_events,
+                        DebugEvent {
+                            at: Some(clock::now()),
+                            source: "notification".to_string(),
+                            kind: debug_dump::variant_name(&notification),
+                            session_id: session_id.clone(),
+                        },
+                    );
+                }
+                handle_child_notification(&mut parsed_state, notification);
             }
+            Err(e) => match from_slice::<protocol::ChatStateNotification>(&params.0) {
+                Ok(notification) => {
+                    let session_id = notification
+                        .chat_state_actor_id()
+                        .and_then(|chat_actor_id| {
+                            parsed_state.session_for_chat_actor(chat_actor_id)
+                        });
+                    logging::set_scope(None, session_id.as_deref());
+                    logging::trace_value(
+                        "Received chat-state notification",
+                        "notification",
+                        &serde_json::to_value(&notification).unwrap_or_default(),
+                    );
+                    // Chunks would push everything else out
+                    let is_chunk = matches!(
+                        notification,
+                        protocol::ChatStateNotification::GenerationChunk { .. }
+                    );
+                    if parsed_state.assistant_config.enable_debug && !is_chunk {
+                        debug_dump::record(
+                            &mut parsed_state.recent_events,
+                            DebugEvent {
+                                at: Some(clock::now()),
+                                source: "chat-state".to_string(),
+                                kind: debug_dump::variant_name(&notification),
+                                session_id,
+                            },
+                        );
+                    }
+                    handle_chat_state_notification(&mut parsed_state, notification);
+                }
+                Err(_) => {
+                    // Unknown messages must not take the assistant down
+                    logging::warn(&format!("Ignoring unrecognized message: {}", e));
+                }
+            },
         };
 
-        let updated_state = to_vec(&parsed_state)
+        let updated_state = encode_state(&mut parsed_state)
             .map_err(|e| format!("Failed to serialize updated state: {}", e))?;
         Ok((Some(updated_state),))
     }
@@ -303,459 +2015,10539 @@ impl MessageServerClient for Component {
         state: Option<Vec<u8>>,
         params: (String, Vec<u8>),
     ) -> Result<(Option<Vec<u8>>, (Option<Vec<u8>>,)), String> {
-        log("Git chat assistant handling request message");
+        logging::debug("Git chat assistant handling request message");
 
-        let (_request_id, data) = params;
+        let (request_id, data) = params;
+        logging::set_scope(Some(&request_id), None);
 
         // Deserialize our state
-        let git_state: GitChatState = match state {
-            Some(state_bytes) => match from_slice(&state_bytes) {
+        let mut git_state: GitChatState = match state {
+            Some(state_bytes) => match decode_state(&state_bytes) {
                 Ok(state) => state,
                 Err(e) => {
                     let error_msg = format!("Failed to deserialize git state: {}", e);
-                    log(&error_msg);
-                    let error_response = GitChatResponse::Error { message: error_msg };
-                    let response_bytes = to_vec(&error_response)
-                        .map_err(|e| format!("Failed to serialize error response: {}", e))?;
+                    logging::error(&error_msg);
+                    let response_bytes =
+                        encode_response(&errors::error(ErrorCode::SerializationFailed, error_msg))?;
                     return Ok((None, (Some(response_bytes),)));
                 }
             },
             None => {
                 let error_msg = "No state available";
-                log(error_msg);
-                let error_response = GitChatResponse::Error {
-                    message: error_msg.to_string(),
-                };
-                let response_bytes = to_vec(&error_response)
-                    .map_err(|e| format!("Failed to serialize error response: {}", e))?;
+                logging::error(error_msg);
+                let response_bytes =
+                    encode_response(&errors::error(ErrorCode::Internal, error_msg.to_string()))?;
                 return Ok((None, (Some(response_bytes),)));
             }
         };
 
+        logging::configure(
+            git_state.assistant_config.logging.as_ref(),
+            git_state.assistant_config.log_redaction.unwrap_or_default(),
+        );
+        simulated::configure(
+            git_state.assistant_config.simulated_chat_state,
+            &git_state.actor_id,
+        );
+
+        // The model's git tool calls, which are routed through the assistant
+        if from_slice::<McpActorRequest>(&data).is_ok() {
+            let response_bytes = proxy_git_tool_call(&mut git_state, &data)?;
+            let current_state_bytes = encode_state(&mut git_state)
+                .map_err(|e| format!("Failed to serialize current state: {}", e))?;
+            return Ok((Some(current_state_bytes), (Some(response_bytes),)));
+        }
+
         // Parse the request
-        let request: GitChatRequest = match from_slice(&data) {
+        let request: GitChatRequest = match decode_request(&data) {
             Ok(req) => {
-                log(&format!("Parsed request: {:?}", req));
+                logging::set_scope(Some(&request_id), req.session_id());
+                logging::trace_value(
+                    "Parsed request",
+                    "request",
+                    &serde_json::to_value(&req).unwrap_or_default(),
+                );
+                if git_state.assistant_config.enable_debug {
+                    debug_dump::record(
+                        &mut git_state.recent_events,
+                        DebugEvent {
+                            at: Some(clock::now()),
+                            source: "request".to_string(),
+                            kind: debug_dump::variant_name(&req),
+                            session_id: req.session_id().map(str::to_string),
+                        },
+                    );
+                }
+                if idle::is_interaction(&req) {
+                    git_state.last_interaction_at = Some(clock::now());
+                }
+                git_state
+                    .metrics
+                    .count_request(&debug_dump::variant_name(&req));
                 req
             }
-            Err(e) => {
-                let error_msg = format!("Failed to parse request: {}", e);
-                log(&error_msg);
-                let error_response = GitChatResponse::Error { message: error_msg };
-                let response_bytes = to_vec(&error_response)
-                    .map_err(|e| format!("Failed to serialize error response: {}", e))?;
+            Err(error_msg) => {
+                logging::error(&error_msg);
+                let response_bytes =
+                    encode_response(&errors::error(ErrorCode::InvalidRequest, error_msg))?;
                 return Ok((
-                    Some(to_vec(&git_state).unwrap_or_default()),
+                    Some(encode_state(&mut git_state).unwrap_or_default()),
                     (Some(response_bytes),),
                 ));
             }
         };
 
-        // Handle the request
-        let response = match request {
-            GitChatRequest::StartChat => {
-                log("Starting task session...");
-
-                // Check if we have a task that requires auto-initiation
-                if let Some(task) = &git_state.task {
-                    log(&format!("Auto-initiating task: {}", task));
-
-                    let auto_message = match task.as_str() {
-                        "commit" => "Please analyze the repository and commit any pending changes with appropriate commit messages. Start by checking git status to see what files have changed.",
-                        "review" => "Please perform a comprehensive code review of the current changes. Start by examining what has been modified.",
-                        "rebase" => "Please help me clean up the git history through an interactive rebase. Start by showing the current commit history.",
-                        "analyze" => "Please provide a comprehensive analysis of this repository. Start by examining the overall structure and recent activity.",
-                        "cleanup" => "Please help clean up and organize this repository. Start by identifying what needs attention.",
-                        _ => "Please proceed with the assigned task. Let me know if you need clarification on what should be done.",
-                    };
+        // Requests that failed to send earlier go out before anything this one sends
+        if !matches!(request, GitChatRequest::FlushQueue) {
+            flush_outbound_queue(&mut git_state, false);
+        }
 
-                    match git_state.get_chat_state_actor_id() {
-                        Ok(chat_actor_id) => {
-                            let auto_task_message = protocol::ChatStateRequest::AddMessage {
-                                message: Message {
-                                    role: genai_types::messages::Role::User,
-                                    content: vec![genai_types::MessageContent::Text {
-                                        text: auto_message.to_string(),
-                                    }],
-                                },
-                            };
-
-                            let message_bytes = to_vec(&auto_task_message)
-                                .map_err(|e| format!("Failed to serialize auto message: {}", e))?;
-
-                            match send(chat_actor_id, &message_bytes) {
-                                Ok(_) => {
-                                    log("Auto task message sent successfully");
-
-                                    // Request generation from chat-state actor
-                                    let generation_request =
-                                        protocol::ChatStateRequest::GenerateCompletion;
-                                    let generation_request_bytes = to_vec(&generation_request)
-                                        .map_err(|e| {
-                                            format!("Failed to serialize generation request: {}", e)
-                                        })?;
-
-                                    match send(chat_actor_id, &generation_request_bytes) {
-                                        Ok(_) => {
-                                            log("Auto generation request sent successfully");
-                                        }
-                                        Err(e) => {
-                                            let error_msg = format!(
-                                                "Failed to send auto generation request: {:?}",
-                                                e
-                                            );
-                                            log(&error_msg);
-                                            return Ok((
-                                                Some(to_vec(&git_state).unwrap_or_default()),
-                                                (Some(
-                                                    to_vec(&GitChatResponse::Error {
-                                                        message: error_msg,
-                                                    })
-                                                    .unwrap_or_default(),
-                                                ),),
-                                            ));
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    let error_msg =
-                                        format!("Failed to send auto task message: {:?}", e);
-                                    log(&error_msg);
-                                    return Ok((
-                                        Some(to_vec(&git_state).unwrap_or_default()),
-                                        (Some(
-                                            to_vec(&GitChatResponse::Error { message: error_msg })
-                                                .unwrap_or_default(),
-                                        ),),
-                                    ));
-                                }
-                            }
+        // A patch that fixes the config leaves safe mode; not while halted, though
+        let response = if let (None, Some(_), GitChatRequest::UpdateConfig { patch }) =
+            (&git_state.halt, &git_state.safe_mode, &request)
+        {
+            match leave_safe_mode(&mut git_state, patch) {
+                Ok(config) => GitChatResponse::ConfigUpdated { config },
+                Err(e) => errors::error(ErrorCode::SafeMode, e),
+            }
+        } else if let Some(refusal) = blocked(&git_state, &request) {
+            refusal
+        } else {
+            // Hold back requests the confirmation policy doesn't let through yet
+            match hold_for_confirmation(&mut git_state, &request) {
+                Some(confirmation) => GitChatResponse::ConfirmationRequired { confirmation },
+                // Answered with `respond_to_request` once the generation finishes
+                None if matches!(request, GitChatRequest::SendAndAwait { .. }) => {
+                    match send_and_await(&mut git_state, &request_id, request) {
+                        Ok(Some(response)) => response,
+                        Ok(None) => {
+                            let current_state_bytes = encode_state(&mut git_state)
+                                .map_err(|e| format!("Failed to serialize current state: {}", e))?;
+                            return Ok((Some(current_state_bytes), (None,)));
                         }
                         Err(e) => {
-                            let error_msg =
-                                format!("Chat state actor not available for auto task: {}", e);
-                            log(&error_msg);
-                            return Ok((
-                                Some(to_vec(&git_state).unwrap_or_default()),
-                                (Some(
-                                    to_vec(&GitChatResponse::Error { message: error_msg })
-                                        .unwrap_or_default(),
-                                ),),
-                            ));
+                            logging::error(&format!("Error forwarding message: {}", e));
+                            errors::error(ErrorCode::OperationFailed, e)
                         }
                     }
-                } else {
-                    log("No task specified, starting normal chat session");
                 }
-
-                GitChatResponse::Success
-            }
-            GitChatRequest::GetChatStateActorId => match git_state.get_chat_state_actor_id() {
-                Ok(actor_id) => {
-                    log(&format!("Returning chat state actor ID: {}", actor_id));
-                    GitChatResponse::ChatStateActorId {
-                        actor_id: actor_id.clone(),
+                None if matches!(request, GitChatRequest::SuggestCommitMessage { .. }) => {
+                    match suggest_commit_message(&mut git_state, &request_id, request) {
+                        Ok(()) => {
+                            let current_state_bytes = encode_state(&mut git_state)
+                                .map_err(|e| format!("Failed to serialize current state: {}", e))?;
+                            return Ok((Some(current_state_bytes), (None,)));
+                        }
+                        Err(e) => {
+                            logging::error(&format!("Error suggesting a commit message: {}", e));
+                            errors::error(ErrorCode::OperationFailed, e)
+                        }
                     }
                 }
-                Err(e) => {
-                    log(&format!("Error getting chat state actor ID: {}", e));
-                    GitChatResponse::Error { message: e }
-                }
-            },
-            GitChatRequest::AddMessage { message } => {
-                match git_state.get_chat_state_actor_id() {
-                    Ok(chat_actor_id) => {
-                        log(&format!(
-                            "Forwarding message to chat state actor: {}",
-                            chat_actor_id
-                        ));
-
-                        let add_message = protocol::ChatStateRequest::AddMessage {
-                            message: message.clone(),
-                        };
-
-                        // Forward the message to the chat-state actor
-                        let message_bytes = to_vec(&add_message)
-                            .map_err(|e| format!("Failed to serialize message: {}", e))?;
-
-                        match send(chat_actor_id, &message_bytes) {
-                            Ok(_) => {
-                                log("Message forwarded successfully");
-
-                                // Request generation from chat-state actor
-                                let generation_request_message =
-                                    protocol::ChatStateRequest::GenerateCompletion;
-                                let generation_request_bytes = to_vec(&generation_request_message)
-                                    .map_err(|e| {
-                                        format!("Failed to serialize generation request: {}", e)
-                                    })?;
-
-                                match send(chat_actor_id, &generation_request_bytes) {
-                                    Ok(_) => {
-                                        log("Generation request sent successfully");
-                                        GitChatResponse::Success
-                                    }
-                                    Err(e) => {
-                                        let error_msg =
-                                            format!("Failed to send generation request: {:?}", e);
-                                        log(&error_msg);
-                                        GitChatResponse::Error { message: error_msg }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                let error_msg = format!("Failed to forward message: {:?}", e);
-                                log(&error_msg);
-                                GitChatResponse::Error { message: error_msg }
-                            }
+                None if matches!(request, GitChatRequest::SuggestBranchName { .. }) => {
+                    match suggest_branch_name(&mut git_state, &request_id, request) {
+                        Ok(()) => {
+                            let current_state_bytes = encode_state(&mut git_state)
+                                .map_err(|e| format!("Failed to serialize current state: {}", e))?;
+                            return Ok((Some(current_state_bytes), (None,)));
+                        }
+                        Err(e) => {
+                            logging::error(&format!("Error suggesting a branch name: {}", e));
+                            errors::error(ErrorCode::OperationFailed, e)
                         }
                     }
-                    Err(e) => {
-                        log(&format!("Error forwarding message: {}", e));
-                        GitChatResponse::Error { message: e }
+                }
+                None if matches!(request, GitChatRequest::SuggestPrDescription { .. }) => {
+                    match suggest_pr_description(&mut git_state, &request_id, request) {
+                        Ok(()) => {
+                            let current_state_bytes = encode_state(&mut git_state)
+                                .map_err(|e| format!("Failed to serialize current state: {}", e))?;
+                            return Ok((Some(current_state_bytes), (None,)));
+                        }
+                        Err(e) => {
+                            logging::error(&format!(
+                                "Error suggesting a pull request description: {}",
+                                e
+                            ));
+                            errors::error(ErrorCode::OperationFailed, e)
+                        }
                     }
                 }
+                None => handle_git_chat_request(&mut git_state, request),
             }
         };
 
+        if let GitChatResponse::Error { message, code } = &response {
+            git_state.last_error = Some(message.clone());
+            let code = code.unwrap_or(ErrorCode::Unknown);
+            git_state.metrics.count_error(code.as_str());
+        }
+
         // Serialize the response
-        let response_bytes =
-            to_vec(&response).map_err(|e| format!("Failed to serialize response: {}", e))?;
+        let response_bytes = encode_response(&response)?;
 
-        // Keep the same state (no changes needed)
-        let current_state_bytes =
-            to_vec(&git_state).map_err(|e| format!("Failed to serialize current state: {}", e))?;
+        // Persist the state (requests may have updated it)
+        let current_state_bytes = encode_state(&mut git_state)
+            .map_err(|e| format!("Failed to serialize current state: {}", e))?;
 
         Ok((Some(current_state_bytes), (Some(response_bytes),)))
     }
 
     fn handle_channel_open(
         state: Option<Vec<u8>>,
-        _params: (String, Vec<u8>),
+        params: (String, Vec<u8>),
     ) -> Result<(Option<Vec<u8>>, (ChannelAccept,)), String> {
-        log("Git chat assistant: Channel open request");
+        let (channel_id, initial_message) = params;
+        logging::debug(&format!(
+            "Git chat assistant: Channel open request: {}",
+            channel_id
+        ));
+        let mut git_state: GitChatState = match state.as_deref().map(decode_state) {
+            Some(Ok(git_state)) => git_state,
+            Some(Err(e)) => return Err(format!("Failed to deserialize git state: {}", e)),
+            None => return Err("No state available".to_string()),
+        };
+
+        // The initial message opens or resumes a stream, and nothing else, so that the
+        // welcome is the first frame on the channel
+        let frames = match channel::decode_frames(&initial_message).and_then(|frames| {
+            match <[ClientFrame; 1]>::try_from(frames) {
+                Ok([frame]) => Ok(frame),
+                Err(_) => Err("The initial message must be one Open or Resume frame".to_string()),
+            }
+        }) {
+            Ok(ClientFrame::Open { events }) => {
+                let stream_id = format!("stream-{}", git_state.next_stream_number);
+                git_state.next_stream_number += 1;
+                let stream = Stream::new(&channel_id, events);
+                let welcome = ServerFrame::Welcome {
+                    stream_id: stream_id.clone(),
+                    next_seq: stream.next_seq,
+                    missed: 0,
+                };
+                git_state.channel_streams.insert(stream_id, stream);
+                vec![welcome]
+            }
+            Ok(ClientFrame::Resume {
+                stream_id,
+                last_seq,
+            }) => match git_state.channel_streams.get_mut(&stream_id) {
+                Some(stream) => {
+                    stream.channel_id = Some(channel_id);
+                    stream.resume(&stream_id, last_seq)
+                }
+                None => {
+                    return Ok((
+                        state,
+                        (refuse_channel(&format!("Unknown stream {}", stream_id)),),
+                    ))
+                }
+            },
+            Ok(_) => {
+                return Ok((
+                    state,
+                    (refuse_channel("The initial frame must be Open or Resume"),),
+                ))
+            }
+            Err(e) => return Ok((state, (refuse_channel(&e),))),
+        };
+
+        let message = channel::encode_frames(&frames)?;
+        let new_state = encode_state(&mut git_state)
+            .map_err(|e| format!("Failed to serialize git state: {}", e))?;
         Ok((
-            state,
+            Some(new_state),
             (ChannelAccept {
                 accepted: true,
-                message: None,
+                message: Some(message),
+            },),
+        ))
+    }
+
+    fn handle_channel_close(
+        state: Option<Vec<u8>>,
+        params: (String,),
+    ) -> Result<(Option<Vec<u8>>,), String> {
+        let (channel_id,) = params;
+        logging::debug(&format!(
+            "Git chat assistant: Channel closed: {}",
+            channel_id
+        ));
+        let mut git_state: GitChatState = match state.as_deref().map(decode_state) {
+            Some(Ok(git_state)) => git_state,
+            _ => return Ok((state,)),
+        };
+        // Keep the stream so the client can resume it on a new channel
+        detach_channel(&mut git_state, &channel_id);
+        let max_detached_streams = git_state
+            .assistant_config
+            .channels
+            .clone()
+            .unwrap_or_default()
+            .max_detached_streams;
+        channel::prune_detached(&mut git_state.channel_streams, max_detached_streams);
+        let new_state = encode_state(&mut git_state)
+            .map_err(|e| format!("Failed to serialize git state: {}", e))?;
+        Ok((Some(new_state),))
+    }
+
+    fn handle_channel_message(
+        state: Option<Vec<u8>>,
+        params: (String, Vec<u8>),
+    ) -> Result<(Option<Vec<u8>>,), String> {
+        let (channel_id, message) = params;
+        logging::debug(&format!(
+            "Git chat assistant: Received channel message on: {}",
+            channel_id
+        ));
+        let stream_id = match state.as_deref().map(decode_state) {
+            Some(Ok(git_state)) => git_state
+                .channel_streams
+                .iter()
+                .find(|(_, stream)| stream.channel_id.as_deref() == Some(channel_id.as_str()))
+                .map(|(stream_id, _)| stream_id.clone()),
+            Some(Err(e)) => return Err(format!("Failed to deserialize git state: {}", e)),
+            None => return Err("No state available".to_string()),
+        };
+        let stream_id = match stream_id {
+            Some(stream_id) => stream_id,
+            None => {
+                send_frames(
+                    &channel_id,
+                    &[ServerFrame::Error {
+                        message: "No stream is open on this channel".to_string(),
+                    }],
+                );
+                return Ok((state,));
+            }
+        };
+        let frames = match channel::decode_frames(&message) {
+            Ok(frames) => frames,
+            Err(e) => {
+                send_frames(&channel_id, &[ServerFrame::Error { message: e }]);
+                return Ok((state,));
+            }
+        };
+
+        let mut state = state;
+        for frame in frames {
+            match frame {
+                ClientFrame::Ack { seq } => {
+                    update_stream(&mut state, &stream_id, |stream, _| stream.ack(seq))?;
+                }
+                ClientFrame::Subscribe { events } => {
+                    update_stream(&mut state, &stream_id, |stream, _| {
+                        stream.events = events.into_iter().collect();
+                    })?;
+                }
+                frame @ (ClientFrame::Request { .. }
+                | ClientFrame::Input { .. }
+                | ClientFrame::Approval { .. }) => {
+                    let Some((seq, request)) = frame.request(&stream_id) else {
+                        continue;
+                    };
+                    // A request resent after a reconnect was answered already; its
+                    // response is replayed until acked
+                    let is_new = update_stream(&mut state, &stream_id, |stream, _| {
+                        let is_new = seq > stream.last_request_seq;
+                        stream.last_request_seq = stream.last_request_seq.max(seq);
+                        is_new
+                    })?;
+                    if !is_new {
+                        continue;
+                    }
+                    // Nothing could answer it on this stream once its generation finished
+                    let response = if request.defers_response() {
+                        needs_message_server(&request)
+                    } else {
+                        let body = serde_json::to_vec(&protocol::external::Envelope::new(&request))
+                            .map_err(|e| format!("Failed to serialize request: {}", e))?;
+                        let (new_state, (response,)) =
+                            <Component as MessageServerClient>::handle_request(
+                                state,
+                                (format!("{}-{}", stream_id, seq), body),
+                            )?;
+                        state = new_state;
+                        response
+                            .ok_or_else(|| "No response".to_string())
+                            .and_then(|bytes| {
+                                from_slice::<protocol::external::Envelope<GitChatResponse>>(&bytes)
+                                    .map_err(|e| format!("Failed to parse response: {}", e))
+                            })?
+                            .body
+                    };
+                    let frame = update_stream(&mut state, &stream_id, |stream, max_unacked| {
+                        stream.push(max_unacked, |frame_seq| ServerFrame::Response {
+                            seq: frame_seq,
+                            request_seq: seq,
+                            response,
+                        })
+                    })?;
+                    send_frames(&channel_id, &[frame]);
+                }
+                ClientFrame::Open { .. } | ClientFrame::Resume { .. } => send_frames(
+                    &channel_id,
+                    &[ServerFrame::Error {
+                        message: "A stream is already open on this channel".to_string(),
+                    }],
+                ),
+            }
+        }
+        Ok((state,))
+    }
+}
+
+fn refuse_channel(reason: &str) -> ChannelAccept {
+    logging::warn(&format!("Refusing channel: {}", reason));
+    ChannelAccept {
+        accepted: false,
+        message: channel::encode_frames(&[ServerFrame::Error {
+            message: reason.to_string(),
+        }])
+        .ok(),
+    }
+}
+
+/// Send `frames` on a channel, logging failures; unacked frames are replayed on resume.
+fn send_frames(channel_id: &str, frames: &[ServerFrame]) {
+    let sent = channel::encode_frames(frames).and_then(|bytes| send_on_channel(channel_id, &bytes));
+    if let Err(e) = sent {
+        logging::warn(&format!("Failed to send frames on {}: {}", channel_id, e));
+    }
+}
+
+fn detach_channel(git_state: &mut GitChatState, channel_id: &str) {
+    for stream in git_state.channel_streams.values_mut() {
+        if stream.channel_id.as_deref() == Some(channel_id) {
+            stream.channel_id = None;
+        }
+    }
+}
+
+/// Apply `f` to a stream in the serialized `state`, with the configured
+/// `max_unacked_frames`.
+fn update_stream<T>(
+    state: &mut Option<Vec<u8>>,
+    stream_id: &str,
+    f: impl FnOnce(&mut Stream, usize) -> T,
+) -> Result<T, String> {
+    let mut git_state: GitChatState = match state.as_deref().map(decode_state) {
+        Some(Ok(git_state)) => git_state,
+        Some(Err(e)) => return Err(format!("Failed to deserialize git state: {}", e)),
+        None => return Err("No state available".to_string()),
+    };
+    let max_unacked_frames = git_state
+        .assistant_config
+        .channels
+        .clone()
+        .unwrap_or_default()
+        .max_unacked_frames;
+    let stream = git_state
+        .channel_streams
+        .get_mut(stream_id)
+        .ok_or_else(|| format!("Unknown stream {}", stream_id))?;
+    let result = f(stream, max_unacked_frames);
+    *state = Some(
+        encode_state(&mut git_state)
+            .map_err(|e| format!("Failed to serialize git state: {}", e))?,
+    );
+    Ok(result)
+}
+
+impl HttpHandlers for Component {
+    fn handle_request(
+        state: Option<Vec<u8>>,
+        params: (u64, HttpRequest),
+    ) -> Result<(Option<Vec<u8>>, (HttpResponse,)), String> {
+        let (handler_id, http_request) = params;
+        logging::debug(&format!(
+            "Git chat assistant handling HTTP {} {}",
+            http_request.method, http_request.uri
+        ));
+
+        let git_state: GitChatState = match state.as_deref().map(decode_state) {
+            Some(Ok(git_state)) => git_state,
+            Some(Err(e)) => return Err(format!("Failed to deserialize git state: {}", e)),
+            None => return Err("No state available".to_string()),
+        };
+        logging::configure(
+            git_state.assistant_config.logging.as_ref(),
+            git_state.assistant_config.log_redaction.unwrap_or_default(),
+        );
+        let (server, config) = match (&git_state.http_server, &git_state.assistant_config.http) {
+            (Some(server), Some(config)) => (server, config),
+            _ => return Err("HTTP transport is not enabled".to_string()),
+        };
+
+        // Preflights carry an origin too; they get the allowed one back either way
+        if !http_request.method.eq_ignore_ascii_case("OPTIONS") {
+            if let Some(response) = http::refusal(&http_request, config) {
+                logging::warn(&format!(
+                    "Refused HTTP {} {}: {}",
+                    http_request.method, http_request.uri, response.status
+                ));
+                return Ok((state, (response,)));
+            }
+        }
+        if handler_id == server.events_handler {
+            let response = http::events_response(&git_state.http_events, &http_request, config);
+            return Ok((state, (response,)));
+        }
+        if handler_id != server.requests_handler {
+            let response = http::text_response(404, "Unknown handler", config);
+            return Ok((state, (response,)));
+        }
+        if http_request.method.eq_ignore_ascii_case("OPTIONS") {
+            return Ok((state, (http::preflight_response(config),)));
+        }
+
+        // Same path as requests from the message server, errors included
+        let config = config.clone();
+        let request_id = http::header(&http_request, "X-Request-Id")
+            .unwrap_or("http")
+            .to_string();
+        let body = http_request.body.unwrap_or_default();
+        // The HTTP response goes back before any generation finishes
+        if let Some(request) = decode_request(&body)
+            .ok()
+            .filter(GitChatRequest::defers_response)
+        {
+            let bytes = encode_response(&needs_message_server(&request))?;
+            return Ok((state, (http::json_response(200, bytes, &config),)));
+        }
+        let (state, (response,)) =
+            <Component as MessageServerClient>::handle_request(state, (request_id, body))?;
+        let response = match response {
+            Some(bytes) => http::json_response(200, bytes, &config),
+            None => http::text_response(500, "No response", &config),
+        };
+        Ok((state, (response,)))
+    }
+
+    // No middleware or WebSocket routes are registered, so these are never called
+    fn handle_middleware(
+        state: Option<Vec<u8>>,
+        params: (u64, HttpRequest),
+    ) -> Result<(Option<Vec<u8>>, (MiddlewareResult,)), String> {
+        let (_handler_id, request) = params;
+        Ok((
+            state,
+            (MiddlewareResult {
+                proceed: true,
+                request,
             },),
         ))
     }
 
-    fn handle_channel_close(
-        state: Option<Vec<u8>>,
-        params: (String,),
-    ) -> Result<(Option<Vec<u8>>,), String> {
-        let (channel_id,) = params;
-        log(&format!(
-            "Git chat assistant: Channel closed: {}",
-            channel_id
+    fn handle_websocket_connect(
+        state: Option<Vec<u8>>,
+        _params: (u64, u64, String, Option<String>),
+    ) -> Result<(Option<Vec<u8>>,), String> {
+        Ok((state,))
+    }
+
+    fn handle_websocket_message(
+        state: Option<Vec<u8>>,
+        _params: (u64, u64, WebsocketMessage),
+    ) -> Result<(Option<Vec<u8>>, (Vec<WebsocketMessage>,)), String> {
+        Ok((state, (Vec::new(),)))
+    }
+
+    fn handle_websocket_disconnect(
+        state: Option<Vec<u8>>,
+        _params: (u64, u64),
+    ) -> Result<(Option<Vec<u8>>,), String> {
+        Ok((state,))
+    }
+}
+
+// Helper functions
+
+/// Encode the state with the configured codec.
+fn encode_state(git_state: &mut GitChatState) -> Result<Vec<u8>, String> {
+    git_state.checkpoint = workflow_checkpoint(git_state);
+    let bytes = encode_compacted(git_state)?;
+    let Some(limits) = git_state.assistant_config.state_limits.clone() else {
+        return Ok(bytes);
+    };
+    if bytes.len() <= limits.max_bytes {
+        return Ok(bytes);
+    }
+    let mut report = compact_state(git_state, &limits);
+    let compacted = encode_compacted(git_state)?;
+    report.bytes_before = bytes.len();
+    report.bytes_after = compacted.len();
+    logging::warn(&format!(
+        "State was over its limit of {} bytes and was compacted: {}",
+        limits.max_bytes,
+        report.describe()
+    ));
+    Ok(compacted)
+}
+
+/// Encode the state, with `original_config` as overrides once compacted.
+fn encode_compacted(git_state: &mut GitChatState) -> Result<Vec<u8>, String> {
+    let format = git_state.assistant_config.state_codec.unwrap_or_default();
+    let compact_config = git_state.config_compacted
+        || git_state
+            .assistant_config
+            .state_limits
+            .as_ref()
+            .is_some_and(|limits| limits.compact_config);
+    if !compact_config {
+        return state_codec::encode(format, git_state);
+    }
+    let config = std::mem::take(&mut git_state.original_config);
+    git_state.config_overrides = Some(state_limits::compact_config(
+        &base_chat_config(git_state),
+        &config,
+    ));
+    let encoded = state_codec::encode(format, git_state);
+    git_state.original_config = config;
+    git_state.config_overrides = None;
+    encoded
+}
+
+/// Drop the oldest audit entries and debug events past `limits`, and the history cache.
+/// Byte counts are left for the caller to fill in.
+fn compact_state(git_state: &mut GitChatState, limits: &StateLimits) -> CompactionReport {
+    let events_dropped = git_state
+        .recent_events
+        .len()
+        .saturating_sub(limits.recent_events);
+    git_state.recent_events.drain(..events_dropped);
+    git_state.config_compacted = true;
+    CompactionReport {
+        audit_entries_dropped: git_state.audit_log.prune(limits.audit_entries),
+        events_dropped,
+        cache_entries_dropped: git_state.history_cache.clear(),
+        config_compacted: true,
+        ..CompactionReport::default()
+    }
+}
+
+/// Compact the state with `state_limits`, or their defaults, whatever its size.
+fn compact_state_now(git_state: &mut GitChatState) -> Result<CompactionReport, String> {
+    let limits = git_state
+        .assistant_config
+        .state_limits
+        .clone()
+        .unwrap_or_default();
+    let bytes_before = encode_compacted(git_state)?.len();
+    let mut report = compact_state(git_state, &limits);
+    report.bytes_before = bytes_before;
+    report.bytes_after = encode_compacted(git_state)?.len();
+    logging::info(&format!("Compacted the state: {}", report.describe()));
+    Ok(report)
+}
+
+/// The chat config the assistant builds from its config and what it found at init,
+/// before runtime changes like model switches.
+fn base_chat_config(git_state: &GitChatState) -> Value {
+    create_git_optimized_config(
+        &git_state.actor_id,
+        git_state.current_directory.as_deref(),
+        &git_state.assistant_config,
+        git_state.branch_context.as_ref(),
+        git_state.ticket.as_ref(),
+        git_state.repo_info.as_ref(),
+        git_state.task_monitor_actor_id.as_deref(),
+    )
+}
+
+/// On resume, build the chat config the way the running build does and compare its
+/// fingerprint with the saved one. When they differ, e.g. after an upgrade changed the
+/// prompts, what changed is logged and the default session's chat-state actor is
+/// replaced by one with the new config, with the conversation replayed. Otherwise the
+/// running one is kept.
+fn refresh_chat_config(git_state: &mut GitChatState) -> Result<(), String> {
+    let mut chat_config = base_chat_config(git_state);
+    // Changed at runtime by model switches and presets rather than by the build
+    for key in ["model_config", "temperature", "max_tokens"] {
+        if let Some(value) = git_state.original_config.get(key) {
+            chat_config[key] = value.clone();
+        }
+    }
+    let fingerprint = config_fingerprint::fingerprint(&chat_config);
+    // State saved before fingerprints were kept has only the config
+    let saved = git_state
+        .config_fingerprint
+        .clone()
+        .unwrap_or_else(|| config_fingerprint::fingerprint(&git_state.original_config));
+    if fingerprint == saved {
+        logging::info(&format!(
+            "Chat config unchanged ({}), keeping the chat-state actor",
+            fingerprint
+        ));
+        git_state.config_fingerprint = Some(fingerprint);
+        return Ok(());
+    }
+    let changes = config_fingerprint::changes(&git_state.original_config, &chat_config);
+    logging::info_value(
+        &format!("Chat config changed from {} to {}", saved, fingerprint),
+        "changes",
+        &serde_json::to_value(&changes).unwrap_or_default(),
+    );
+
+    let old_actor_id = git_state.chat_actor_for_session(None)?;
+    // The old actor may be gone; the new one then starts without the conversation
+    let history = match fetch_history(&old_actor_id, None, None) {
+        Ok(messages) => messages,
+        Err(e) => {
+            logging::warn(&format!(
+                "Could not fetch the conversation to replay: {}",
+                e
+            ));
+            Vec::new()
+        }
+    };
+    let chat_actor_id = spawn_chat_state_actor(&chat_config)?;
+    for entry in history {
+        send_to_chat_state(
+            &chat_actor_id,
+            &protocol::ChatStateRequest::AddMessage {
+                message: entry.message,
+                provenance: entry.provenance,
+            },
+        )?;
+    }
+    logging::info(&format!(
+        "Chat-state actor {} replaces {} with the new config",
+        chat_actor_id, old_actor_id
+    ));
+    git_state.set_chat_state_actor_id(chat_actor_id);
+    if let Err(e) = stop_chat_state_actor(&old_actor_id) {
+        logging::warn(&format!("Failed to stop {}: {:?}", old_actor_id, e));
+    }
+    git_state.set_original_config(chat_config);
+    Ok(())
+}
+
+/// Decode state saved with any codec, or before codecs were recorded with it.
+fn decode_state(bytes: &[u8]) -> Result<GitChatState, String> {
+    let mut git_state: GitChatState = state_codec::decode(bytes)?;
+    let dropped = state_codec::take_dropped();
+    if !dropped.is_empty() {
+        logging::warn(&format!(
+            "Redacted state values were lost with the previous actor instance; using the \
+            defaults of {}",
+            dropped.join(", ")
+        ));
+    }
+    if let Some(saved) = git_state.config_overrides.take() {
+        let (config, same_base) = state_limits::expand_config(base_chat_config(&git_state), &saved);
+        if !same_base {
+            logging::warn(
+                "The chat config the assistant builds changed since the state was saved; \
+                applying the saved overrides to the new one",
+            );
+        }
+        git_state.original_config = config;
+    }
+    Ok(git_state)
+}
+
+/// Handle a request that the confirmation policy let through.
+fn handle_git_chat_request(
+    git_state: &mut GitChatState,
+    request: GitChatRequest,
+) -> GitChatResponse {
+    match request {
+        GitChatRequest::StartChat {
+            workflow: Some(workflow),
+            args,
+            allow_secrets,
+            allow_out_of_scope,
+            suggest_only,
+        } => match handle_git_chat_request(
+            git_state,
+            GitChatRequest::SetWorkflow {
+                workflow,
+                start: false,
+            },
+        ) {
+            GitChatResponse::Success => handle_git_chat_request(
+                git_state,
+                GitChatRequest::StartChat {
+                    workflow: None,
+                    args,
+                    allow_secrets,
+                    allow_out_of_scope,
+                    suggest_only,
+                },
+            ),
+            error => error,
+        },
+        GitChatRequest::StartChat {
+            workflow: None,
+            args,
+            allow_secrets,
+            allow_out_of_scope,
+            suggest_only,
+        } => {
+            logging::info("Starting task session...");
+            if suggest_only && !git_state.assistant_config.suggest_only {
+                if let Err(e) = enter_suggest_only(git_state) {
+                    let error_msg = format!("Failed to switch to suggest-only mode: {}", e);
+                    logging::error(&error_msg);
+                    return errors::error(
+                        ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::OperationFailed),
+                        error_msg,
+                    );
+                }
+            }
+            git_state.secrets_allowed = allow_secrets;
+            git_state.out_of_scope_allowed = allow_out_of_scope;
+            git_state.workflow_args = args;
+
+            // Check if we have a workflow that requires auto-initiation
+            if let Some(workflow) = git_state.workflow.clone() {
+                logging::info(&format!("Auto-initiating workflow: {}", workflow.name()));
+
+                let secrets = pending_secrets(git_state);
+                let missing_vars = match (
+                    &workflow,
+                    git_state.assistant_config.commit_template.clone(),
+                ) {
+                    (Workflow::Commit, Some(template)) => {
+                        let values = template_values(git_state);
+                        let missing = commit_template::missing_values(&template, &values);
+                        if !missing.is_empty() {
+                            // Clients on a channel see the request and can answer it
+                            publish(
+                                git_state,
+                                &GitChatEvent::TemplateVarsRequested {
+                                    missing: missing.clone(),
+                                    template: commit_template::render(&template, &values),
+                                },
+                            );
+                        }
+                        missing
+                    }
+                    _ => Vec::new(),
+                };
+                if !missing_vars.is_empty() {
+                    let error_msg = format!(
+                        "The commit template needs values for {}; send them with \
+                        SetTemplateVars and start again",
+                        missing_vars.join(", ")
+                    );
+                    logging::warn(&error_msg);
+                    errors::error(ErrorCode::TemplateVarsMissing, error_msg)
+                } else if !secrets.is_empty() && !allow_secrets {
+                    let error_msg = format!(
+                        "The pending changes look like they contain credentials; remove them \
+                        or start again with allow_secrets:\n{}",
+                        secret_scan::describe(&secrets)
+                    );
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::SecretDetected, error_msg)
+                } else if let Some(confirmation) = hold_published_amend(
+                    git_state,
+                    &workflow,
+                    &GitChatRequest::StartChat {
+                        allow_secrets,
+                        allow_out_of_scope,
+                        workflow: None,
+                        args: git_state.workflow_args.clone(),
+                        suggest_only: false,
+                    },
+                ) {
+                    GitChatResponse::ConfirmationRequired { confirmation }
+                } else if let Some(confirmation) = hold_submodule_bumps(
+                    git_state,
+                    &workflow,
+                    &GitChatRequest::StartChat {
+                        allow_secrets,
+                        allow_out_of_scope,
+                        workflow: None,
+                        args: git_state.workflow_args.clone(),
+                        suggest_only: false,
+                    },
+                ) {
+                    GitChatResponse::ConfirmationRequired { confirmation }
+                } else {
+                    use_stage_preset(git_state, &workflow, "start");
+                    // Huge diffs are summarized first; the opening message waits for it
+                    let result = match start_diff_summary(git_state, &workflow) {
+                        Ok(true) => Ok(()),
+                        Ok(false) => send_opening_message(git_state, &workflow),
+                        Err(e) => {
+                            logging::warn(&format!("Could not summarize the diff first: {}", e));
+                            send_opening_message(git_state, &workflow)
+                        }
+                    };
+                    match result {
+                        Ok(()) => {
+                            logging::info(
+                                "Auto workflow message and generation request sent successfully",
+                            );
+                            git_state.auto_initiated = true;
+                            git_state.workflow_turns = 0;
+                            git_state.wrap_up_sent_at = None;
+                            git_state.workflow_started_at = Some(clock::now());
+                            git_state.workflow_phase = ProgressPhase::default();
+                            git_state.workflow_aborted = None;
+                            // The dead-man switch counts from the start of the run
+                            git_state.last_keepalive_at = git_state.last_tick_at;
+                            publish(
+                                git_state,
+                                &GitChatEvent::WorkflowStateChanged {
+                                    workflow: Some(workflow),
+                                    state: WorkflowState::Started,
+                                },
+                            );
+                            GitChatResponse::Success
+                        }
+                        Err(e) => {
+                            let error_msg = format!("Failed to auto-initiate workflow: {}", e);
+                            logging::error(&error_msg);
+                            let code =
+                                ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::OperationFailed);
+                            errors::error(code, error_msg)
+                        }
+                    }
+                }
+            } else {
+                logging::info("No workflow specified, starting normal chat session");
+                GitChatResponse::Success
+            }
+        }
+        GitChatRequest::SetWorkflow { workflow, start } => {
+            if !workflow.is_known() {
+                errors::error(
+                    ErrorCode::WorkflowUnknown,
+                    format!("Unknown workflow: {}", workflow.name()),
+                )
+            } else {
+                // Choosing a workflow by hand leaves the configured pipeline
+                git_state.pipeline = None;
+                match switch_workflow(git_state, workflow) {
+                    Ok(()) if start => handle_git_chat_request(
+                        git_state,
+                        GitChatRequest::StartChat {
+                            allow_secrets: false,
+                            allow_out_of_scope: false,
+                            workflow: None,
+                            args: None,
+                            suggest_only: false,
+                        },
+                    ),
+                    Ok(()) => GitChatResponse::Success,
+                    Err(e) => {
+                        let error_msg = format!("Failed to switch workflow: {}", e);
+                        logging::error(&error_msg);
+                        errors::error(ErrorCode::OperationFailed, error_msg)
+                    }
+                }
+            }
+        }
+        GitChatRequest::GetStatus => {
+            logging::info("Returning session status");
+            GitChatResponse::Status {
+                status: git_state.status(),
+            }
+        }
+        GitChatRequest::GetWorkflowProgress => GitChatResponse::WorkflowProgress {
+            progress: workflow_progress(git_state),
+        },
+        GitChatRequest::GetChatStateActorId => match git_state.get_chat_state_actor_id() {
+            Ok(actor_id) => {
+                logging::info(&format!("Returning chat state actor ID: {}", actor_id));
+                GitChatResponse::ChatStateActorId {
+                    actor_id: actor_id.clone(),
+                }
+            }
+            Err(e) => {
+                logging::error(&format!("Error getting chat state actor ID: {}", e));
+                errors::error(ErrorCode::ChildUnavailable, e)
+            }
+        },
+        GitChatRequest::CheckSpelling { text } => {
+            let spellcheck = git_state
+                .assistant_config
+                .spellcheck
+                .clone()
+                .unwrap_or_default();
+            let issues = load_spellchecker(git_state, &spellcheck).check(&text);
+            logging::info(&format!("Found {} possible misspellings", issues.len()));
+            GitChatResponse::Spelling { issues }
+        }
+        GitChatRequest::CreateBranch { name, start_point } => {
+            let naming = git_state
+                .assistant_config
+                .branch_naming
+                .clone()
+                .unwrap_or_default();
+            match branch::prepare_branch_name(&name, &naming) {
+                Ok(branch_name) => {
+                    let mut args = vec!["branch", branch_name.as_str()];
+                    args.extend(start_point.as_deref());
+                    let created = start_point
+                        .as_deref()
+                        .map_or(Ok(()), |start| {
+                            git_tools::ensure_revision("start point", start)
+                        })
+                        .and_then(|()| git_state.git_tools_actor_id())
+                        .and_then(|git_actor_id| {
+                            git_tools::run_git(
+                                &git_actor_id,
+                                git_state.current_directory.as_deref(),
+                                &args,
+                            )
+                        });
+                    match created {
+                        Ok(_) => {
+                            logging::info(&format!("Created branch {}", branch_name));
+                            GitChatResponse::BranchCreated {
+                                normalized_from: (branch_name != name).then_some(name),
+                                name: branch_name,
+                            }
+                        }
+                        Err(e) => {
+                            let error_msg = format!("Failed to create branch: {}", e);
+                            logging::error(&error_msg);
+                            errors::error(ErrorCode::OperationFailed, error_msg)
+                        }
+                    }
+                }
+                Err(error) => {
+                    logging::warn(&format!("Invalid branch name: {:?}", error));
+                    GitChatResponse::InvalidBranchName { error }
+                }
+            }
+        }
+        GitChatRequest::CancelGeneration { session_id } => {
+            let session_key = session_id.as_deref().unwrap_or(DEFAULT_SESSION_ID);
+            if !git_state.generations_in_flight.contains(session_key) {
+                errors::error(
+                    ErrorCode::NotFound,
+                    format!("No generation is running in session {}", session_key),
+                )
+            } else {
+                let cancelled = git_state
+                    .chat_actor_for_session(session_id.as_deref())
+                    .and_then(|chat_actor_id| {
+                        send_to_chat_state(
+                            &chat_actor_id,
+                            &protocol::ChatStateRequest::CancelGeneration,
+                        )
+                    });
+                match cancelled {
+                    Ok(()) => {
+                        logging::info(&format!("Cancelled generation in session {}", session_key));
+                        mark_generation_cancelled(git_state, session_key);
+                        let reply = Err("The generation was cancelled".to_string());
+                        answer_pending_replies(git_state, session_key, reply);
+                        release_held_messages(git_state, session_key);
+                        GitChatResponse::Success
+                    }
+                    Err(e) => {
+                        let error_msg = format!("Failed to cancel generation: {}", e);
+                        logging::error(&error_msg);
+                        errors::error(ErrorCode::ChildUnavailable, error_msg)
+                    }
+                }
+            }
+        }
+        GitChatRequest::RunDigest { since } => {
+            let config = git_state
+                .assistant_config
+                .digest
+                .clone()
+                .unwrap_or_default();
+            let query = format!("digest {:?}", since);
+            let digest = cached_history_query(
+                git_state,
+                &query,
+                &["HEAD"],
+                |git_actor_id, directory, bounds| {
+                    digest::fetch_digest(git_actor_id, directory, &config, since, None, bounds)
+                },
+            );
+            match digest {
+                Ok(mut digest) => {
+                    digest.generated_at = Some(clock::now());
+                    GitChatResponse::Digest { digest }
+                }
+                Err(e) => {
+                    let error_msg = format!("Failed to build digest: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::CompareRepos {
+            other_directory,
+            aspect,
+        } => {
+            let message = match git_state.current_directory.clone() {
+                Some(directory) => {
+                    sanitize::ensure_visible(&other_directory, sanitize::TextKind::Name)
+                        .map_err(|e| format!("Refusing directory with {}", e))
+                        .and_then(|()| git_state.git_tools_actor_id())
+                        .and_then(|git_actor_id| {
+                            compare::build_comparison_message(
+                                &git_actor_id,
+                                &directory,
+                                &other_directory,
+                                aspect,
+                            )
+                        })
+                }
+                None => Err("Comparing repositories requires current_directory".to_string()),
+            };
+            match message.and_then(|message| {
+                forward_and_generate(git_state, None, user_message(message), Provenance::Workflow)
+            }) {
+                Ok(()) => {
+                    logging::info(&format!(
+                        "Requested {:?} comparison with {}",
+                        aspect, other_directory
+                    ));
+                    GitChatResponse::Success
+                }
+                Err(e) => {
+                    let error_msg = format!("Failed to compare repositories: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::GetHistory {
+            limit,
+            before,
+            session_id,
+        } => {
+            let history = git_state
+                .chat_actor_for_session(session_id.as_deref())
+                .and_then(|chat_actor_id| fetch_history(&chat_actor_id, limit, before));
+            match history {
+                Ok(messages) => GitChatResponse::History { messages },
+                Err(e) => {
+                    let error_msg = format!("Failed to get history: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::ChildUnavailable, error_msg)
+                }
+            }
+        }
+        GitChatRequest::GetScratchpad { session_id } => {
+            match git_state.chat_actor_for_session(session_id.as_deref()) {
+                Ok(_) => {
+                    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+                    GitChatResponse::Scratchpad {
+                        scratchpad: git_state.scratchpads.get(&session_id).cloned(),
+                        session_id,
+                    }
+                }
+                Err(e) => {
+                    let error_msg = format!("Failed to get the scratchpad: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::NotFound, error_msg)
+                }
+            }
+        }
+        GitChatRequest::ExportTranscript {
+            format,
+            session_id,
+            exclude_injected,
+        } => {
+            let document = git_state
+                .chat_actor_for_session(session_id.as_deref())
+                .and_then(|chat_actor_id| fetch_history(&chat_actor_id, None, None))
+                .and_then(|mut messages| {
+                    scratchpad::strip_messages(&mut messages);
+                    if exclude_injected {
+                        messages
+                            .retain(|entry| !entry.source().is_some_and(Provenance::is_injected));
+                    }
+                    transcript::render(format, &messages)
+                });
+            match document {
+                Ok(document) => GitChatResponse::Transcript {
+                    format,
+                    media_type: format.media_type().to_string(),
+                    document,
+                },
+                Err(e) => {
+                    let error_msg = format!("Failed to export transcript: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::SaveSession { label } => match save_session(git_state, &label) {
+            Ok(snapshot) => GitChatResponse::SessionSnapshot { snapshot },
+            Err(e) => {
+                let error_msg = format!("Failed to save the session: {}", e);
+                logging::error(&error_msg);
+                errors::error(
+                    ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::OperationFailed),
+                    error_msg,
+                )
+            }
+        },
+        GitChatRequest::RestoreSession { id } => match restore_session(git_state, &id) {
+            Ok(snapshot) => GitChatResponse::SessionSnapshot { snapshot },
+            Err(e) => {
+                let error_msg = format!("Failed to restore the session: {}", e);
+                logging::error(&error_msg);
+                errors::error(
+                    ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::OperationFailed),
+                    error_msg,
+                )
+            }
+        },
+        GitChatRequest::GetConfigDiagnostics => GitChatResponse::ConfigDiagnostics {
+            diagnostics: git_state.config_diagnostics.clone(),
+            preflight: git_state.preflight.clone(),
+        },
+        GitChatRequest::ListWorktrees => {
+            let worktrees = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+                worktree::list(
+                    &git_actor_id,
+                    git_state.current_directory.as_deref(),
+                    &git_state.assistant_config.worktrees,
+                )
+            });
+            match worktrees {
+                Ok(worktrees) => GitChatResponse::Worktrees { worktrees },
+                Err(e) => {
+                    let error_msg = format!("Failed to list worktrees: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::SetActiveWorktree { path } => match set_active_worktree(git_state, &path) {
+            Ok(worktree) => GitChatResponse::ActiveWorktreeSet { worktree },
+            Err(e) => {
+                let error_msg = format!("Failed to switch worktrees: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::GetSparseCheckout => {
+            let sparse = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+                sparse::read(&git_actor_id, git_state.current_directory.as_deref())
+            });
+            match sparse {
+                Ok(sparse) => GitChatResponse::SparseCheckout { sparse },
+                Err(e) => {
+                    let error_msg = format!("Failed to read the sparse checkout: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::GetConflicts => {
+            let conflicts = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+                let exec_actor_id = git_state.exec_tools_actor_id()?;
+                conflicts::fetch(
+                    &git_actor_id,
+                    &exec_actor_id,
+                    git_state.current_directory.as_deref(),
+                )
+            });
+            match conflicts {
+                Ok(files) => {
+                    git_state.conflicts = files.clone();
+                    GitChatResponse::Conflicts { files }
+                }
+                Err(e) => {
+                    let error_msg = format!("Failed to read the conflicts: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::ResolveConflict {
+            file,
+            hunk,
+            resolution,
+        } => match resolve_conflict(git_state, &file, hunk, &resolution) {
+            Ok(files) => GitChatResponse::Conflicts { files },
+            Err(e) => {
+                let error_msg = format!("Failed to resolve the conflict: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::SetSparsePaths { paths } => match set_sparse_paths(git_state, &paths) {
+            Ok(sparse) => GitChatResponse::SparseCheckout { sparse },
+            Err(e) => {
+                let error_msg = format!("Failed to set the sparse paths: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::GetDivergence { upstream } => {
+            let upstream = upstream.unwrap_or_else(|| divergence::DEFAULT_UPSTREAM.to_string());
+            let query = format!("divergence {}", upstream);
+            let divergence = cached_history_query(
+                git_state,
+                &query,
+                &[&upstream, "HEAD"],
+                |git_actor_id, directory, bounds| {
+                    divergence::fetch_divergence(git_actor_id, directory, &upstream, bounds)
+                },
+            );
+            match divergence {
+                Ok(divergence) => GitChatResponse::Divergence { divergence },
+                Err(e) => {
+                    let error_msg = format!("Failed to compute divergence: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::AnalyzeRewrite { range } => {
+            let query = format!("rewrite {}", range);
+            let analysis = cached_history_query(
+                git_state,
+                &query,
+                &[&range],
+                |git_actor_id, directory, _| {
+                    rewrite_safety::analyze_rewrite(git_actor_id, directory, &range)
+                },
+            );
+            match analysis {
+                Ok(analysis) => GitChatResponse::RewriteAnalysis { analysis },
+                Err(e) => {
+                    let error_msg = format!("Failed to analyze rewrite: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::ReviewRefs { branch, base } => match review_refs(git_state, branch, base) {
+            Ok(report) => GitChatResponse::ReviewStarted { report },
+            Err(e) => {
+                let error_msg = format!("Failed to start review: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::GetReviewReport { branch: None } => GitChatResponse::ReviewFindings {
+            findings: git_state.review_findings.clone(),
+            benchmark_findings: git_state.benchmark_findings.clone(),
+        },
+        GitChatRequest::GetReviewReport {
+            branch: Some(branch),
+        } => match git_state.review_reports.get(&branch) {
+            Some(report) => GitChatResponse::ReviewReport {
+                report: report.clone(),
+            },
+            None => errors::error(
+                ErrorCode::NotFound,
+                format!("No review of branch {}", branch),
+            ),
+        },
+        GitChatRequest::PublishReview { report_id, mode } => {
+            match publish_review(git_state, &report_id, mode) {
+                Ok(result) => GitChatResponse::ReviewPublished { result },
+                Err(e) => {
+                    let error_msg = format!("Failed to publish review {}: {}", report_id, e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::PlanRebase { onto, range } => match plan_rebase(git_state, onto, range) {
+            Ok(rebase) => GitChatResponse::RebasePlanning { rebase },
+            Err(e) => {
+                let error_msg = format!("Failed to plan rebase: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::ExecuteRebasePlan { plan } => {
+            let result = hold_published_rebase(git_state, &plan).and_then(|held| match held {
+                Some(confirmation) => Ok(GitChatResponse::ConfirmationRequired { confirmation }),
+                None => execute_rebase_plan(git_state, plan)
+                    .map(|rebase| GitChatResponse::RebaseExecuting { rebase }),
+            });
+            match result {
+                Ok(response) => response,
+                Err(e) => {
+                    let error_msg = format!("Failed to execute rebase plan: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::GetRebase => GitChatResponse::Rebase {
+            rebase: git_state.rebase.clone(),
+        },
+        GitChatRequest::ExecuteBranchCleanup { branches } => {
+            match execute_branch_cleanup(git_state, &branches) {
+                Ok(cleanup) => GitChatResponse::BranchCleanup {
+                    cleanup: Some(cleanup),
+                },
+                Err(e) => {
+                    let error_msg = format!("Failed to clean up branches: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::GetBranchCleanup => GitChatResponse::BranchCleanup {
+            cleanup: git_state.branch_cleanup.clone(),
+        },
+        GitChatRequest::ExecuteRelease { message } => {
+            match execute_release(git_state, message.as_deref()) {
+                Ok(release) => GitChatResponse::Release {
+                    release: Some(release),
+                },
+                Err(e) => {
+                    let error_msg = format!("Failed to create the release tag: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::GetRelease => GitChatResponse::Release {
+            release: git_state.release.clone(),
+        },
+        GitChatRequest::ApproveCommitPlan { plan } => match approve_commit_plan(git_state, plan) {
+            Ok(plan) => GitChatResponse::CommitPlan { plan: Some(plan) },
+            Err(e) => {
+                let error_msg = format!("Failed to approve the commit plan: {}", e);
+                logging::error(&error_msg);
+                let code = ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::OperationFailed);
+                errors::error(code, error_msg)
+            }
+        },
+        GitChatRequest::GetCommitPlan => GitChatResponse::CommitPlan {
+            plan: git_state.commit_plan.clone(),
+        },
+        GitChatRequest::ScanForSecrets { diff } => {
+            match scan_for_secrets(git_state, diff.as_deref()) {
+                Ok(findings) => GitChatResponse::SecretScan { findings },
+                Err(e) => {
+                    let error_msg = format!("Failed to scan for secrets: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::VerifyHistory { range } => {
+            logging::info(&format!("Verifying commit signatures for range: {}", range));
+            let query = format!("signatures {}", range);
+            let report = cached_history_query(
+                git_state,
+                &query,
+                &[&range],
+                |git_actor_id, directory, _| {
+                    signatures::verify_history(git_actor_id, directory, &range)
+                },
+            );
+            match report {
+                Ok(report) => {
+                    logging::info(&format!(
+                        "Signature report: {} signed, {} unsigned, {} bad, {} unverifiable",
+                        report.signed, report.unsigned, report.bad, report.unverifiable
+                    ));
+                    GitChatResponse::HistoryVerification { report }
+                }
+                Err(e) => {
+                    logging::error(&format!("Error verifying history: {}", e));
+                    errors::error(ErrorCode::OperationFailed, e)
+                }
+            }
+        }
+        GitChatRequest::CreateSession {
+            session_id,
+            current_directory,
+            workflow,
+        } => match create_session(git_state, session_id, current_directory, workflow) {
+            Ok(session) => {
+                git_state.failed_sessions.remove(&session.session_id);
+                git_state.crashes.remove(&session.session_id);
+                logging::info(&format!(
+                    "Created session {} with chat state actor {}",
+                    session.session_id, session.chat_state_actor_id
+                ));
+                GitChatResponse::SessionCreated { session }
+            }
+            Err(e) => {
+                logging::error(&format!("Error creating session: {}", e));
+                errors::error(ErrorCode::OperationFailed, e)
+            }
+        },
+        GitChatRequest::ListSessions => GitChatResponse::Sessions {
+            sessions: git_state.session_list(),
+        },
+        GitChatRequest::CloseSession { session_id } => {
+            if session_id == DEFAULT_SESSION_ID {
+                errors::error(
+                    ErrorCode::InvalidRequest,
+                    "The default session cannot be closed",
+                )
+            } else {
+                git_state.generations_in_flight.remove(&session_id);
+                git_state.pending_overrides.remove(&session_id);
+                git_state.overridden_generations.remove(&session_id);
+                git_state.generation_ids.remove(&session_id);
+                git_state.stale_generations.remove(&session_id);
+                git_state
+                    .outbound_queue
+                    .retain(|queued| queued.session_id != session_id);
+                let reply = Err(format!("Session {} was closed", session_id));
+                answer_pending_replies(git_state, &session_id, reply);
+                git_state.scratchpads.remove(&session_id);
+                git_state.held_messages.remove(&session_id);
+                git_state.turns.remove(&session_id);
+                match git_state.sessions.remove(&session_id) {
+                    Some(session) => {
+                        logging::info(&format!("Closing session {}", session_id));
+                        match stop_chat_state_actor(&session.chat_state_actor_id) {
+                            Ok(_) => GitChatResponse::Success,
+                            Err(e) => {
+                                let error_msg = format!(
+                                    "Session {} removed but its chat state actor failed to stop: {:?}",
+                                    session_id, e
+                                );
+                                logging::error(&error_msg);
+                                errors::error(ErrorCode::ChildUnavailable, error_msg)
+                            }
+                        }
+                    }
+                    None => errors::error(
+                        ErrorCode::NotFound,
+                        format!("Unknown session: {}", session_id),
+                    ),
+                }
+            }
+        }
+        GitChatRequest::SubmitSecurityFindings { findings } => {
+            let (accepted, rejected) = record_security_findings(git_state, findings);
+            GitChatResponse::SecurityFindings { accepted, rejected }
+        }
+        GitChatRequest::AddMessage {
+            mut message,
+            session_id,
+            client,
+            overrides,
+        } => match check_turn(git_state, session_id.as_deref(), client.as_deref())
+            .or_else(|| answer_quick_command(git_state, &mut message))
+        {
+            Some(response) => response,
+            None => match add_message(
+                git_state,
+                session_id.as_deref(),
+                message,
+                overrides.as_ref(),
+            ) {
+                Ok(()) => {
+                    logging::info("Message forwarded and generation requested successfully");
+                    GitChatResponse::Success
+                }
+                Err(e) => {
+                    logging::error(&format!("Error forwarding message: {}", e));
+                    let code = ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::OperationFailed);
+                    errors::error(code, e)
+                }
+            },
+        },
+        GitChatRequest::AddMessages {
+            messages,
+            session_id,
+            client,
+        } => match check_turn(git_state, session_id.as_deref(), client.as_deref()) {
+            Some(response) => response,
+            None if messages.is_empty() => {
+                errors::error(ErrorCode::InvalidRequest, "AddMessages needs a message")
+            }
+            None => {
+                let count = messages.len();
+                match forward_all_and_generate(
+                    git_state,
+                    session_id.as_deref(),
+                    messages,
+                    Provenance::User,
+                ) {
+                    Ok(()) => {
+                        logging::info(&format!(
+                            "{} messages forwarded with one generation requested",
+                            count
+                        ));
+                        GitChatResponse::Success
+                    }
+                    Err(e) => {
+                        logging::error(&format!("Error forwarding messages: {}", e));
+                        let code = ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::OperationFailed);
+                        errors::error(code, e)
+                    }
+                }
+            }
+        },
+        // Only the message server's `request` can be answered later
+        GitChatRequest::AcquireTurn {
+            session_id,
+            client,
+            lease_secs,
+        } => match git_state.chat_actor_for_session(session_id.as_deref()) {
+            Ok(_) => {
+                let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+                let lease_secs = lease_secs.unwrap_or(turn::DEFAULT_LEASE_SECS);
+                match turn::acquire(
+                    &mut git_state.turns,
+                    &session_id,
+                    &client,
+                    lease_secs,
+                    clock::now(),
+                ) {
+                    Ok(turn) => {
+                        logging::info(&format!(
+                            "{} holds the turn in session {} until {}",
+                            turn.client, turn.session_id, turn.expires_at
+                        ));
+                        GitChatResponse::TurnAcquired { turn }
+                    }
+                    Err(e) => errors::error(ErrorCode::TurnTaken, e),
+                }
+            }
+            Err(e) => errors::error(
+                ErrorCode::NotFound,
+                format!("Failed to acquire the turn: {}", e),
+            ),
+        },
+        GitChatRequest::ReleaseTurn { session_id, client } => {
+            let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+            match turn::release(&mut git_state.turns, &session_id, &client, clock::now()) {
+                Ok(()) => {
+                    logging::info(&format!(
+                        "{} released the turn in session {}",
+                        client, session_id
+                    ));
+                    GitChatResponse::Success
+                }
+                Err(e) => errors::error(ErrorCode::TurnTaken, e),
+            }
+        }
+        GitChatRequest::SendAndAwait { .. }
+        | GitChatRequest::SuggestCommitMessage { .. }
+        | GitChatRequest::SuggestBranchName { .. }
+        | GitChatRequest::SuggestPrDescription { .. } => needs_message_server(&request),
+        GitChatRequest::KeepAlive => {
+            logging::debug("Keep-alive received");
+            git_state.last_keepalive_at = git_state.last_tick_at;
+            match git_state.halt.take() {
+                Some(snapshot) => {
+                    logging::info("Keep-alives resumed, lifting the halt");
+                    let session_ids: Vec<String> =
+                        git_state.held_messages.keys().cloned().collect();
+                    for session_id in session_ids {
+                        release_held_messages(git_state, &session_id);
+                    }
+                    publish(
+                        git_state,
+                        &GitChatEvent::WorkflowStateChanged {
+                            workflow: git_state.workflow.clone(),
+                            state: WorkflowState::Resumed,
+                        },
+                    );
+                    GitChatResponse::Resumed {
+                        snapshot: Box::new(snapshot),
+                    }
+                }
+                None => GitChatResponse::Success,
+            }
+        }
+        GitChatRequest::StartBisect { good, bad } => match start_bisect(git_state, good, bad) {
+            Ok(bisect) => GitChatResponse::Bisect {
+                bisect: Some(bisect),
+            },
+            Err(e) => {
+                let error_msg = format!("Failed to start bisect: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::SubmitBisectVerdict { verdict } => {
+            match submit_bisect_verdict(git_state, verdict, None) {
+                Ok(bisect) => GitChatResponse::Bisect {
+                    bisect: Some(bisect),
+                },
+                Err(e) => {
+                    let error_msg = format!("Failed to submit bisect verdict: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::AbortBisect => match abort_bisect(git_state) {
+            Ok(bisect) => GitChatResponse::Bisect {
+                bisect: Some(bisect),
+            },
+            Err(e) => {
+                let error_msg = format!("Failed to abort bisect: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::GetBisect => GitChatResponse::Bisect {
+            bisect: git_state.bisect.clone(),
+        },
+        GitChatRequest::SkipCherryPick => match skip_cherry_pick(git_state) {
+            Ok(cherry_pick) => GitChatResponse::CherryPick {
+                cherry_pick: Some(cherry_pick),
+            },
+            Err(e) => {
+                let error_msg = format!("Failed to skip the cherry-pick: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::AbortCherryPick => match abort_cherry_pick(git_state) {
+            Ok(cherry_pick) => GitChatResponse::CherryPick {
+                cherry_pick: Some(cherry_pick),
+            },
+            Err(e) => {
+                let error_msg = format!("Failed to abort the cherry-pick: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::GetCherryPick => GitChatResponse::CherryPick {
+            cherry_pick: git_state.cherry_pick.clone(),
+        },
+        GitChatRequest::ExecuteSync { strategy } => {
+            let result =
+                hold_published_sync(git_state, &request, strategy).and_then(|held| match held {
+                    Some(confirmation) => {
+                        Ok(GitChatResponse::ConfirmationRequired { confirmation })
+                    }
+                    None => execute_sync(git_state, strategy)
+                        .map(|sync| GitChatResponse::Sync { sync: Some(sync) }),
+                });
+            match result {
+                Ok(response) => response,
+                Err(e) => {
+                    let error_msg = format!("Failed to sync: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::AbortSync => match abort_sync(git_state) {
+            Ok(sync) => GitChatResponse::Sync { sync: Some(sync) },
+            Err(e) => {
+                let error_msg = format!("Failed to abort the sync: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::OperationFailed, error_msg)
+            }
+        },
+        GitChatRequest::GetSync => GitChatResponse::Sync {
+            sync: git_state.sync.clone(),
+        },
+        GitChatRequest::UndoLastTurn => match undo_last_turn(git_state, &request) {
+            Ok(response) => response,
+            Err(e) => {
+                let error_msg = format!("Failed to undo the last turn: {}", e);
+                logging::error(&error_msg);
+                let code = ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::OperationFailed);
+                errors::error(code, error_msg)
+            }
+        },
+        GitChatRequest::RegisterSibling { sibling } => {
+            if sibling.actor_id == git_state.actor_id {
+                errors::error(
+                    ErrorCode::InvalidRequest,
+                    "An assistant can't be its own sibling",
+                )
+            } else {
+                logging::info(&format!(
+                    "Registered sibling {} ({})",
+                    sibling.name, sibling.actor_id
+                ));
+                git_state
+                    .siblings
+                    .retain(|registered| registered.actor_id != sibling.actor_id);
+                git_state.siblings.push(sibling);
+                GitChatResponse::Success
+            }
+        }
+        GitChatRequest::ListSiblings => GitChatResponse::Siblings {
+            siblings: git_state.siblings.clone(),
+        },
+        GitChatRequest::DelegateTask { actor_id, task } => {
+            match delegate_task(git_state, &actor_id, task) {
+                Ok(delegation) => GitChatResponse::DelegationStarted { delegation },
+                Err(e) => {
+                    let error_msg = format!("Failed to delegate the task: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::AcceptDelegation {
+            delegation_id,
+            from,
+            task,
+        } => accept_delegation(
+            git_state,
+            DelegationSource {
+                delegation_id,
+                actor_id: from,
+                task,
+                confirmation_id: None,
+            },
+        ),
+        GitChatRequest::GetDelegations => GitChatResponse::Delegations {
+            delegations: git_state.delegations.clone(),
+        },
+        GitChatRequest::StartFanOut { repositories, task } => {
+            match start_fan_out(git_state, &repositories, task) {
+                Ok(fan_out) => GitChatResponse::FanOut {
+                    fan_out: Some(fan_out),
+                },
+                Err(e) => {
+                    let error_msg = format!("Failed to start the fan-out: {}", e);
+                    logging::error(&error_msg);
+                    let code = ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::OperationFailed);
+                    errors::error(code, error_msg)
+                }
+            }
+        }
+        GitChatRequest::GetFanOut => GitChatResponse::FanOut {
+            fan_out: git_state.fan_out.clone(),
+        },
+        GitChatRequest::Subscribe { actor_id, events } => {
+            logging::info(&format!("{} subscribed to {:?}", actor_id, events));
+            git_state
+                .subscribers
+                .insert(actor_id, events.into_iter().collect());
+            GitChatResponse::Success
+        }
+        GitChatRequest::Unsubscribe { actor_id } => match git_state.subscribers.remove(&actor_id) {
+            Some(_) => {
+                logging::info(&format!("{} unsubscribed", actor_id));
+                GitChatResponse::Success
+            }
+            None => errors::error(
+                ErrorCode::NotFound,
+                format!("{} is not subscribed", actor_id),
+            ),
+        },
+        GitChatRequest::DebugDump { include } => {
+            if git_state.assistant_config.enable_debug {
+                GitChatResponse::DebugDump {
+                    dump: build_debug_dump(git_state, &include),
+                }
+            } else {
+                errors::error(
+                    ErrorCode::DebugDisabled,
+                    "DebugDump needs enable_debug in the config",
+                )
+            }
+        }
+        GitChatRequest::Ping => {
+            let child_alive = probe_chat_state(git_state);
+            let uptime = git_state
+                .started_at
+                .map(|started_at| clock::now().saturating_sub(started_at));
+            GitChatResponse::Pong {
+                uptime,
+                child_alive,
+            }
+        }
+        GitChatRequest::GetMetrics { format } => {
+            let snapshot = MetricsSnapshot {
+                actor_id: git_state.actor_id.clone(),
+                uptime_secs: git_state
+                    .started_at
+                    .map(|started_at| clock::now().saturating_sub(started_at)),
+                messages_forwarded: git_state.messages_forwarded,
+                generations_requested: git_state.generations_requested,
+                generations_finished: git_state.generations_finished,
+                metrics: git_state.metrics.clone(),
+            };
+            let (metrics, text) = match format {
+                MetricsFormat::Json => (Some(snapshot), None),
+                MetricsFormat::Prometheus => (None, Some(metrics::prometheus(&snapshot))),
+            };
+            GitChatResponse::Metrics {
+                format,
+                media_type: format.media_type().to_string(),
+                metrics,
+                text,
+            }
+        }
+        GitChatRequest::RetryToolAttach => match retry_tool_attach(git_state) {
+            Ok(git_tools_actor_id) => GitChatResponse::ToolsAttached { git_tools_actor_id },
+            Err(e) => {
+                let error_msg = format!("Failed to attach the git tools: {}", e);
+                logging::error(&error_msg);
+                let code = ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::ChildUnavailable);
+                errors::error(code, error_msg)
+            }
+        },
+        GitChatRequest::Describe => GitChatResponse::Description {
+            build: BuildInfo::current(),
+            protocol_version: PROTOCOL_VERSION,
+            workflows: Workflow::KNOWN_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        },
+        GitChatRequest::FlushQueue => {
+            let delivered = flush_outbound_queue(git_state, true);
+            GitChatResponse::QueueFlushed {
+                delivered,
+                queued: git_state.outbound_queue.len(),
+            }
+        }
+        GitChatRequest::CleanArtifacts { kinds, keep } => {
+            let cleaned = git_state.exec_tools_actor_id().and_then(|exec_actor_id| {
+                let dir = artifacts::artifacts_dir(
+                    &exec_actor_id,
+                    git_state.current_directory.as_deref(),
+                )?;
+                artifacts::clean(&exec_actor_id, &dir, &kinds, keep)
+            });
+            match cleaned {
+                Ok((removed, kept)) => {
+                    logging::info(&format!("Removed {} session artifacts", removed.len()));
+                    GitChatResponse::ArtifactsCleaned { removed, kept }
+                }
+                Err(e) => {
+                    let error_msg = format!("Failed to clean artifacts: {}", e);
+                    logging::error(&error_msg);
+                    errors::error(ErrorCode::OperationFailed, error_msg)
+                }
+            }
+        }
+        GitChatRequest::SetTemplateVars { vars } => {
+            git_state.template_vars.extend(vars);
+            let missing = match git_state.assistant_config.commit_template.clone() {
+                Some(template) => {
+                    commit_template::missing_values(&template, &template_values(git_state))
+                }
+                None => Vec::new(),
+            };
+            GitChatResponse::TemplateVarsSet { missing }
+        }
+        GitChatRequest::UpdateConfig { patch } => match update_config(git_state, &patch) {
+            Ok(config) => {
+                if patch.get("model_config").is_some() {
+                    git_state.model_preset = None;
+                }
+                GitChatResponse::ConfigUpdated { config }
+            }
+            Err(e) => {
+                let error_msg = format!("Failed to update the config: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::InvalidRequest, error_msg)
+            }
+        },
+        GitChatRequest::SetModelPreset { preset } => match use_model_preset(git_state, &preset) {
+            Ok(config) => GitChatResponse::ConfigUpdated { config },
+            Err(e) => {
+                let error_msg = format!("Failed to switch to model preset {}: {}", preset, e);
+                logging::error(&error_msg);
+                let code = ErrorCode::prefix_of(&e).unwrap_or(ErrorCode::InvalidRequest);
+                errors::error(code, error_msg)
+            }
+        },
+        GitChatRequest::GetUsage => {
+            let budget = git_state
+                .assistant_config
+                .budget
+                .clone()
+                .unwrap_or_default();
+            GitChatResponse::Usage {
+                usage: UsageReport {
+                    sessions: git_state.usage.clone(),
+                    total: usage::total(&git_state.usage),
+                    max_tokens: budget.max_tokens,
+                    max_cost: budget.max_cost,
+                    budget_exceeded: git_state.budget_exceeded,
+                },
+            }
+        }
+        GitChatRequest::GetAuditLog { limit } => GitChatResponse::AuditLog {
+            entries: git_state.audit_log.newest(limit),
+        },
+        GitChatRequest::CompactState => match compact_state_now(git_state) {
+            Ok(report) => GitChatResponse::StateCompacted { report },
+            Err(e) => {
+                let error_msg = format!("Failed to compact the state: {}", e);
+                logging::error(&error_msg);
+                errors::error(ErrorCode::Internal, error_msg)
+            }
+        },
+        GitChatRequest::GetPendingConfirmations => GitChatResponse::PendingConfirmations {
+            confirmations: git_state.pending_confirmations.values().cloned().collect(),
+        },
+        GitChatRequest::Confirm { id } => {
+            let refusal = git_state
+                .pending_confirmations
+                .get(&id)
+                .and_then(|pending| blocked(git_state, &pending.request));
+            match (git_state.pending_confirmations.remove(&id), refusal) {
+                // It stays pending until it can run
+                (Some(pending), Some(refusal)) => {
+                    git_state.pending_confirmations.insert(id, pending);
+                    refusal
+                }
+                (Some(pending), None) => {
+                    logging::info(&format!("Confirmed {}: {}", id, pending.description));
+                    git_state
+                        .confirmed_actions
+                        .insert(format!("{}:{}", pending.session_id, pending.action));
+                    let response = handle_git_chat_request(git_state, pending.request);
+                    let outcome = match &response {
+                        GitChatResponse::Error { message, .. } => {
+                            AuditOutcome::new(false, Some(message))
+                        }
+                        _ => AuditOutcome::new(true, None),
+                    };
+                    git_state
+                        .audit_log
+                        .settle(&id, Approval::Confirmed, Some(outcome));
+                    response
+                }
+                (None, _) => errors::error(
+                    ErrorCode::NotFound,
+                    format!("No pending confirmation {}", id),
+                ),
+            }
+        }
+        GitChatRequest::RejectConfirmation { id } => {
+            match git_state.pending_confirmations.remove(&id) {
+                Some(pending) => {
+                    logging::info(&format!("Rejected {}: {}", id, pending.description));
+                    git_state.audit_log.settle(&id, Approval::Rejected, None);
+                    // A delegated task held back won't run; its delegator hears so
+                    if let Some(source) = delegation::rejected_by(
+                        &id,
+                        &pending.request,
+                        git_state.delegated_by.as_ref(),
+                    ) {
+                        if git_state.delegated_by.as_ref().is_some_and(|accepted| {
+                            accepted.delegation_id == source.delegation_id
+                                && accepted.actor_id == source.actor_id
+                        }) {
+                            git_state.delegated_by = None;
+                        }
+                        let error = format!("Confirmation {} was rejected", id);
+                        report_delegation(&source, None, Some(error));
+                    }
+                    GitChatResponse::Success
+                }
+                None => errors::error(
+                    ErrorCode::NotFound,
+                    format!("No pending confirmation {}", id),
+                ),
+            }
+        }
+    }
+}
+
+/// Queue `request` when the confirmation policy requires it to be confirmed first.
+fn hold_for_confirmation(
+    git_state: &mut GitChatState,
+    request: &GitChatRequest,
+) -> Option<PendingConfirmation> {
+    let (action, severity) = confirmation::classify(request)?;
+    let mode = if confirmation::always_confirmed(request) {
+        ConfirmationMode::Always
+    } else {
+        git_state
+            .assistant_config
+            .confirmation_policy
+            .as_ref()?
+            .mode(severity)
+    };
+    // Without a workflow, StartChat only opens the chat
+    if matches!(request, GitChatRequest::StartChat { workflow: None, .. })
+        && git_state.workflow.is_none()
+    {
+        return None;
+    }
+    let session_id = request
+        .session_id()
+        .unwrap_or(DEFAULT_SESSION_ID)
+        .to_string();
+    match mode {
+        ConfirmationMode::Never => return None,
+        ConfirmationMode::OncePerSession
+            if git_state
+                .confirmed_actions
+                .contains(&format!("{}:{}", session_id, action)) =>
+        {
+            return None
+        }
+        _ => {}
+    }
+
+    let description = confirmation::describe(request);
+    Some(hold_request(
+        git_state,
+        request,
+        action,
+        severity,
+        session_id,
+        description,
+    ))
+}
+
+/// Queue `request` for `Confirm` as `action`.
+fn hold_request(
+    git_state: &mut GitChatState,
+    request: &GitChatRequest,
+    action: &str,
+    severity: ActionSeverity,
+    session_id: String,
+    description: String,
+) -> PendingConfirmation {
+    let pending = PendingConfirmation {
+        id: format!("confirmation-{}", git_state.next_confirmation_number),
+        action: action.to_string(),
+        severity,
+        session_id,
+        description,
+        request: request.clone(),
+        requested_at: Some(clock::now()),
+    };
+    git_state.next_confirmation_number += 1;
+    logging::info(&format!(
+        "Holding {} ({:?}) for confirmation as {}",
+        action, severity, pending.id
+    ));
+    git_state.audit_log.record(AuditEntry {
+        id: 0,
+        at: clock::now(),
+        source: AuditSource::Request,
+        session_id: Some(pending.session_id.clone()),
+        command: pending.action.clone(),
+        arguments: logging::sanitize(&serde_json::to_value(request).unwrap_or_default()),
+        approval: Approval::Pending,
+        outcome: None,
+        confirmation_id: Some(pending.id.clone()),
+    });
+    git_state
+        .pending_confirmations
+        .insert(pending.id.clone(), pending.clone());
+    publish(
+        git_state,
+        &GitChatEvent::ApprovalRequested {
+            confirmation: pending.clone(),
+        },
+    );
+    pending
+}
+
+/// Hold the start of the `amend` workflow for `Confirm` when the last commit is already
+/// on a remote and amending it wasn't approved.
+fn hold_published_amend(
+    git_state: &mut GitChatState,
+    workflow: &Workflow,
+    request: &GitChatRequest,
+) -> Option<PendingConfirmation> {
+    if *workflow != Workflow::Amend || amend_approved(git_state) {
+        return None;
+    }
+    let remote_branches = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        amend::fetch_remote_branches(&git_actor_id, git_state.current_directory.as_deref())
+    });
+    match remote_branches {
+        Ok(branches) if branches.is_empty() => None,
+        Ok(branches) => {
+            let description = format!(
+                "Amend the last commit, which is already on {}; publishing it needs a force push",
+                branches.join(", ")
+            );
+            Some(hold_request(
+                git_state,
+                request,
+                amend::AMEND_PUBLISHED,
+                ActionSeverity::RemoteMutation,
+                DEFAULT_SESSION_ID.to_string(),
+                description,
+            ))
+        }
+        // Building the opening message checks again and fails on the same error
+        Err(e) => {
+            logging::warn(&format!("Could not check for a published commit: {}", e));
+            None
+        }
+    }
+}
+
+/// Check again which of the planned commits are already on a remote, and hold
+/// executing the plan for `Confirm` when some are and rewriting them wasn't approved.
+fn hold_published_rebase(
+    git_state: &mut GitChatState,
+    plan: &RebasePlan,
+) -> Result<Option<PendingConfirmation>, String> {
+    // Anything else is refused by `execute_rebase_plan`
+    let (onto, range) = match &git_state.rebase {
+        Some(rebase)
+            if matches!(
+                rebase.status,
+                RebaseStatus::Planning | RebaseStatus::Proposed
+            ) =>
+        {
+            (rebase.onto.clone(), rebase.range.clone())
+        }
+        _ => return Ok(None),
+    };
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let analysis = rewrite_safety::analyze_rewrite(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &range,
+    )?;
+    if let Some(rebase) = git_state.rebase.as_mut() {
+        rebase.rewrite = Some(analysis.clone());
+    }
+    if analysis.is_safe() || published_rebase_approved(git_state) {
+        return Ok(None);
+    }
+    let description = format!(
+        "Execute the rebase plan onto {}, which rewrites published history: {}",
+        onto,
+        analysis.describe()
+    );
+    Ok(Some(hold_request(
+        git_state,
+        &GitChatRequest::ExecuteRebasePlan { plan: plan.clone() },
+        rebase_plan::REBASE_PUBLISHED,
+        ActionSeverity::RemoteMutation,
+        DEFAULT_SESSION_ID.to_string(),
+        description,
+    )))
+}
+
+/// Whether a planned rebase may rewrite commits that are already on a remote.
+fn published_rebase_approved(git_state: &GitChatState) -> bool {
+    git_state.assistant_config.allow_published_rewrite
+        || git_state.confirmed_actions.contains(&format!(
+            "{}:{}",
+            DEFAULT_SESSION_ID,
+            rebase_plan::REBASE_PUBLISHED
+        ))
+}
+
+/// Whether the `amend` workflow may amend a commit that is already on a remote.
+fn amend_approved(git_state: &GitChatState) -> bool {
+    git_state.assistant_config.allow_published_rewrite
+        || git_state.confirmed_actions.contains(&format!(
+            "{}:{}",
+            DEFAULT_SESSION_ID,
+            amend::AMEND_PUBLISHED
+        ))
+}
+
+/// Hold the start of the commit workflow for `Confirm` when submodule pointers moved and
+/// the `submodules` policy wants bumps confirmed.
+fn hold_submodule_bumps(
+    git_state: &mut GitChatState,
+    workflow: &Workflow,
+    request: &GitChatRequest,
+) -> Option<PendingConfirmation> {
+    let policy = git_state
+        .assistant_config
+        .submodules
+        .as_ref()
+        .map(|config| config.pointer_bumps);
+    if *workflow != Workflow::Commit
+        || policy != Some(PointerPolicy::Confirm)
+        || submodule_bumps_allowed(git_state)
+    {
+        return None;
+    }
+    let changes = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        submodule::fetch_changes(&git_actor_id, git_state.current_directory.as_deref())
+    });
+    let moved: Vec<String> = match changes {
+        Ok(changes) => changes
+            .into_iter()
+            .filter(|change| change.pointer_moved)
+            .map(|change| change.path)
+            .collect(),
+        Err(e) => {
+            logging::warn(&format!("Could not check submodules: {}", e));
+            return None;
+        }
+    };
+    if moved.is_empty() {
+        return None;
+    }
+    let description = format!(
+        "Start the commit workflow and let it commit the pointer bumps of submodules {}",
+        moved.join(", ")
+    );
+    Some(hold_request(
+        git_state,
+        request,
+        submodule::POINTER_BUMPS,
+        ActionSeverity::LocalMutation,
+        DEFAULT_SESSION_ID.to_string(),
+        description,
+    ))
+}
+
+/// Whether the commit workflow may commit submodule pointer bumps.
+fn submodule_bumps_allowed(git_state: &GitChatState) -> bool {
+    let policy = git_state
+        .assistant_config
+        .submodules
+        .as_ref()
+        .map(|config| config.pointer_bumps)
+        .unwrap_or_default();
+    match policy {
+        PointerPolicy::Include => true,
+        PointerPolicy::Exclude => false,
+        PointerPolicy::Confirm => git_state.confirmed_actions.contains(&format!(
+            "{}:{}",
+            DEFAULT_SESSION_ID,
+            submodule::POINTER_BUMPS
+        )),
+    }
+}
+
+/// Sanitized snapshot of the `include` sections of the state, or of every section when
+/// it is empty, for troubleshooting stuck sessions.
+fn build_debug_dump(git_state: &GitChatState, include: &[DebugSection]) -> Value {
+    let sections = if include.is_empty() {
+        DebugSection::ALL
+    } else {
+        include
+    };
+    let mut dump = serde_json::Map::new();
+    for section in sections {
+        let value = match section {
+            DebugSection::Sessions => serde_json::json!({
+                "sessions": git_state.session_list(),
+                "generations_in_flight": git_state.generations_in_flight,
+                "fallbacks_used": git_state.fallbacks_used,
+                "session_models": git_state.session_models,
+                "closed_sessions": git_state.closed_sessions,
+                "session_downgrades": git_state.session_downgrades,
+            }),
+            DebugSection::Children => serde_json::json!({
+                "running": list_children(),
+                "git_tools_actor_id": git_state.git_tools_actor_id,
+                "exec_tools_actor_id": git_state.exec_tools_actor_id,
+                "forge_tools_actor_id": git_state.forge_tools_actor_id,
+                "model_git_tools_actor_id": git_state.model_git_tools_actor_id,
+                "task_monitor_actor_id": git_state.task_monitor_actor_id,
+                "task_monitor_restarts": git_state.task_monitor_restarts,
+            }),
+            DebugSection::Queues => serde_json::json!({
+                "pending_confirmations": git_state.pending_confirmations.values().collect::<Vec<_>>(),
+                "confirmed_actions": git_state.confirmed_actions,
+                "pending_replies": git_state.pending_replies,
+                "suggestions": git_state.suggestions,
+                "diff_summary_pass": git_state.diff_summary_pass,
+                "held_messages": git_state
+                    .held_messages
+                    .iter()
+                    .map(|(session_id, held)| (session_id.clone(), held.len()))
+                    .collect::<BTreeMap<_, _>>(),
+            }),
+            DebugSection::Workflows => serde_json::json!({
+                "status": git_state.status(),
+                "commit_base": git_state.commit_base,
+                "convention_reprompts": git_state.convention_reprompts,
+                "workflow_turns": git_state.workflow_turns,
+                "wrap_up_sent_at": git_state.wrap_up_sent_at,
+                "pipeline": git_state.pipeline,
+                "rebase": git_state.rebase,
+                "bisect": git_state.bisect,
+                "cherry_pick": git_state.cherry_pick,
+                "sync": git_state.sync,
+                "active_review": git_state.active_review,
+                "review_reports": git_state.review_reports.keys().collect::<Vec<_>>(),
+                "halt": git_state.halt,
+                "last_tick_at": git_state.last_tick_at,
+                "last_keepalive_at": git_state.last_keepalive_at,
+            }),
+            DebugSection::Events => serde_json::json!(git_state.recent_events),
+            DebugSection::Config => serde_json::json!({
+                "config": git_state.assistant_config,
+                "diagnostics": git_state.config_diagnostics,
+            }),
+        };
+        dump.insert(section.name().to_string(), value);
+    }
+    logging::sanitize(&Value::Object(dump))
+}
+
+/// Update the session for a notification from a chat-state actor. Those standing for
+/// notifications the assistant already handles go through [`handle_child_notification`].
+fn handle_chat_state_notification(
+    git_state: &mut GitChatState,
+    notification: protocol::ChatStateNotification,
+) {
+    let chat_actor_id = notification.chat_state_actor_id().map(str::to_string);
+    let session_id = match &chat_actor_id {
+        Some(chat_actor_id) => git_state.session_for_chat_actor(chat_actor_id),
+        None => Some(DEFAULT_SESSION_ID.to_string()),
+    };
+    for child_notification in notification.child_notifications() {
+        handle_child_notification(git_state, child_notification);
+    }
+    match notification {
+        protocol::ChatStateNotification::MessageAdded {
+            message_id, role, ..
+        } => logging::debug(&format!(
+            "Message {} ({:?}) added in session {:?}",
+            message_id, role, session_id
+        )),
+        protocol::ChatStateNotification::GenerationStarted { .. } => {
+            // Generations the actor starts itself, e.g. to go on after tool calls
+            if let Some(session_id) = session_id {
+                // Whatever the actor reports from now on is about this generation
+                git_state.stale_generations.remove(&session_id);
+                if git_state.generations_in_flight.insert(session_id.clone()) {
+                    let generation_id = start_generation_id(git_state, &session_id);
+                    publish(
+                        git_state,
+                        &GitChatEvent::GenerationStarted {
+                            session_id,
+                            generation_id: Some(generation_id),
+                        },
+                    );
+                }
+            }
+        }
+        protocol::ChatStateNotification::GenerationChunk { text, .. } => {
+            if let Some(session_id) = session_id {
+                let generation_id = git_state.generation_ids.get(&session_id).cloned();
+                publish(
+                    git_state,
+                    &GitChatEvent::GenerationChunk {
+                        session_id,
+                        generation_id,
+                        text,
+                    },
+                );
+            }
+        }
+        protocol::ChatStateNotification::Error { error, .. } => {
+            let generating = session_id
+                .as_ref()
+                .is_some_and(|session_id| git_state.generations_in_flight.contains(session_id));
+            let message = format!("{}: {}", error.code, error.message);
+            if generating {
+                handle_child_notification(
+                    git_state,
+                    protocol::ChildNotification::GenerationFailed {
+                        error: message,
+                        chat_state_actor_id: chat_actor_id,
+                    },
+                );
+            } else {
+                logging::warn(&format!("Chat-state actor reported an error: {}", message));
+                git_state.last_error = Some(format!("Chat-state actor error: {}", message));
+            }
+        }
+        protocol::ChatStateNotification::GenerationCompleted { .. }
+        | protocol::ChatStateNotification::ToolCallStarted { .. }
+        | protocol::ChatStateNotification::ToolCallFinished { .. } => {}
+    }
+}
+
+/// Update the session for a notification from a child, shutting down once the active
+/// workflow is finished.
+fn handle_child_notification(
+    git_state: &mut GitChatState,
+    notification: protocol::ChildNotification,
+) {
+    match notification {
+        protocol::ChildNotification::TaskComplete { summary } => {
+            let summary = summary.map(|summary| postprocess(git_state, &summary));
+            if let Some(summary) = &summary {
+                logging::info(&format!("Task summary: {}", summary));
+            }
+            if git_state.workflow.is_none() || !git_state.auto_initiated {
+                // Interactive sessions keep running after a task is marked complete
+                logging::info("No active workflow, staying up");
+                return;
+            }
+            if !completion_criteria(git_state).contains(&CompletionCriterion::Signal) {
+                logging::info("The workflow completes on its completion criteria, not the signal");
+                return;
+            }
+            complete_task(git_state, summary);
+        }
+        protocol::ChildNotification::ReviewSubmitted { findings, .. } => {
+            record_review_findings(git_state, findings);
+        }
+        protocol::ChildNotification::TaskFailed { reason } => {
+            // Leave the session up so the user can step in
+            logging::warn(&format!("Task failed: {}", reason));
+            git_state.last_error = Some(format!("Task failed: {}", reason));
+        }
+        protocol::ChildNotification::ToolInvoked {
+            tool,
+            arguments,
+            chat_state_actor_id,
+        } => {
+            logging::debug(&format!("Model invoked tool: {}", tool));
+            git_state.tool_invocations += 1;
+            git_state.metrics.count_tool_call(&tool);
+            let session_id = match &chat_state_actor_id {
+                Some(chat_actor_id) => git_state.session_for_chat_actor(chat_actor_id),
+                None => Some(DEFAULT_SESSION_ID.to_string()),
+            };
+            // Workflows are run in the default session
+            if session_id.as_deref() == Some(DEFAULT_SESSION_ID) {
+                if let Some(phase) = ProgressPhase::of_tool_call(&tool, arguments.as_ref()) {
+                    set_workflow_phase(git_state, phase);
+                }
+            }
+            publish_tool_activity(
+                git_state,
+                &tool,
+                ToolPhase::Started,
+                None,
+                chat_state_actor_id.as_deref(),
+            );
+            let entry = tool_audit_entry(git_state, tool, arguments, chat_state_actor_id);
+            git_state.audit_log.record(entry);
+        }
+        protocol::ChildNotification::ToolFinished {
+            tool,
+            is_error,
+            output,
+            chat_state_actor_id,
+        } => {
+            logging::debug(&format!("Tool finished: {}", tool));
+            publish_tool_activity(
+                git_state,
+                &tool,
+                ToolPhase::Finished,
+                Some(!is_error),
+                chat_state_actor_id.as_deref(),
+            );
+            let entry = tool_audit_entry(git_state, tool, None, chat_state_actor_id);
+            let outcome = AuditOutcome::new(!is_error, output.as_deref());
+            git_state.audit_log.finish_tool(entry, outcome);
+        }
+        protocol::ChildNotification::GenerationFinished {
+            stop_reason,
+            chat_state_actor_id,
+        } => {
+            logging::debug(&format!("Generation finished: {:?}", stop_reason));
+            git_state.generations_finished += 1;
+            if let Some(chat_actor_id) = &chat_state_actor_id {
+                if finish_one_turn(git_state, chat_actor_id, Ok(())) {
+                    return;
+                }
+            }
+            let session_id = match &chat_state_actor_id {
+                Some(chat_actor_id) => git_state.session_for_chat_actor(chat_actor_id),
+                None => Some(DEFAULT_SESSION_ID.to_string()),
+            };
+            if ignore_stale_generation(git_state, session_id.as_deref()) {
+                return;
+            }
+            let chat_actor_id = match chat_state_actor_id {
+                Some(chat_actor_id) => Some(chat_actor_id),
+                None => git_state.chat_state_actor_id.clone(),
+            };
+            // Ask for the reply rather than assuming it is the latest message a client sees
+            let message_id = chat_actor_id.as_deref().and_then(|chat_actor_id| {
+                match fetch_head(chat_actor_id) {
+                    Ok(head) => head,
+                    Err(e) => {
+                        logging::warn(&format!("Could not fetch the reply: {}", e));
+                        None
+                    }
+                }
+            });
+            publish(
+                git_state,
+                &GitChatEvent::GenerationFinished {
+                    session_id: session_id.clone(),
+                    generation_id: generation_id_of(git_state, session_id.as_deref()),
+                    stop_reason,
+                    error: None,
+                    message_id: message_id.clone(),
+                },
+            );
+            if let Some(session_id) = &session_id {
+                let reply = match (&chat_actor_id, &message_id) {
+                    (Some(chat_actor_id), Some(message_id)) => {
+                        fetch_message(chat_actor_id, message_id).map(|reply| reply.message)
+                    }
+                    _ => Err("the reply couldn't be fetched".to_string()),
+                };
+                if let Ok(reply) = &reply {
+                    track_scratchpad(git_state, session_id, reply);
+                    track_suggested_commands(git_state, session_id, reply);
+                    publish_assistant_output(git_state, session_id, message_id.clone(), reply);
+                }
+                answer_pending_replies(git_state, session_id, reply);
+            }
+            if let Some(session_id) = session_id {
+                git_state.generations_in_flight.remove(&session_id);
+                end_generation_overrides(git_state, &session_id);
+                // Rebases and bisects are run in the default session
+                if session_id == DEFAULT_SESSION_ID {
+                    track_rebase(git_state);
+                    track_bisect(git_state);
+                    track_cherry_pick(git_state);
+                    track_sync(git_state);
+                    track_release(git_state);
+                    track_commit_plan(git_state);
+                    track_security_findings(git_state);
+                    track_benchmark_findings(git_state);
+                    if workflow_running(git_state) {
+                        git_state.workflow_turns += 1;
+                        publish_workflow_progress(git_state);
+                    }
+                    check_completion(git_state);
+                    check_turn_limit(git_state);
+                    check_workflow_timeout(git_state, clock::now());
+                }
+                release_held_messages(git_state, &session_id);
+            }
+            sync_pr_changelog(git_state);
+            // Downgrades wait for the session's generation to finish
+            apply_model_downgrades(git_state);
+        }
+        protocol::ChildNotification::GenerationFailed {
+            error,
+            chat_state_actor_id,
+        } => {
+            logging::warn(&format!("Generation failed: {}", error));
+            if let Some(chat_actor_id) = &chat_state_actor_id {
+                let reply = Err(format!("Generation failed: {}", error));
+                if finish_one_turn(git_state, chat_actor_id, reply) {
+                    return;
+                }
+            }
+            let chat_actor_id = match chat_state_actor_id {
+                Some(chat_actor_id) => Some(chat_actor_id),
+                None => git_state.chat_state_actor_id.clone(),
+            };
+            let session_id = chat_actor_id
+                .as_deref()
+                .and_then(|chat_actor_id| git_state.session_for_chat_actor(chat_actor_id));
+            if ignore_stale_generation(git_state, session_id.as_deref()) {
+                return;
+            }
+            if let Some(session_id) = &session_id {
+                git_state.generations_in_flight.remove(session_id);
+                end_generation_overrides(git_state, session_id);
+            }
+            publish(
+                git_state,
+                &GitChatEvent::GenerationFinished {
+                    session_id: session_id.clone(),
+                    generation_id: generation_id_of(git_state, session_id.as_deref()),
+                    stop_reason: None,
+                    error: Some(logging::sanitize_text(&error)),
+                    message_id: None,
+                },
+            );
+            let retried = match (chat_actor_id, session_id.clone()) {
+                (Some(chat_actor_id), Some(session_id)) => {
+                    fall_back_to_next_model(git_state, &session_id, &chat_actor_id, &error)
+                }
+                _ => Ok(false),
+            };
+            if !matches!(retried, Ok(true)) {
+                if let Some(session_id) = &session_id {
+                    let reply = Err(format!("Generation failed: {}", error));
+                    answer_pending_replies(git_state, session_id, reply);
+                    release_held_messages(git_state, session_id);
+                }
+            }
+            match retried {
+                Ok(true) => {}
+                Ok(false) => git_state.last_error = Some(format!("Generation failed: {}", error)),
+                Err(e) => {
+                    let error_msg =
+                        format!("Generation failed ({}) and fallback failed: {}", error, e);
+                    logging::error(&error_msg);
+                    git_state.last_error = Some(error_msg);
+                }
+            }
+        }
+        protocol::ChildNotification::UsageReported {
+            input_tokens,
+            output_tokens,
+            model,
+            chat_state_actor_id,
+        } => {
+            let session_id = match chat_state_actor_id {
+                Some(chat_actor_id) => git_state.session_for_chat_actor(&chat_actor_id),
+                None => Some(DEFAULT_SESSION_ID.to_string()),
+            };
+            let session_id = match session_id {
+                Some(session_id) => session_id,
+                None => {
+                    logging::warn("Ignoring usage reported by an unknown chat-state actor");
+                    return;
+                }
+            };
+            let model = model.unwrap_or_else(|| session_model(git_state, &session_id));
+            let budget = git_state
+                .assistant_config
+                .budget
+                .clone()
+                .unwrap_or_default();
+            let price = budget.price(&model);
+            if price.is_none() {
+                logging::debug(&format!("No price known for {}, cost not counted", model));
+            }
+            git_state
+                .usage
+                .entry(session_id)
+                .or_default()
+                .add(&Usage::measure(input_tokens, output_tokens, price));
+            let total = usage::total(&git_state.usage);
+            if !git_state.budget_exceeded && budget.is_exceeded(&total) {
+                stop_for_budget(git_state);
+            }
+            let level = budget.downgrade_level(&total);
+            if level > git_state.downgrade_level {
+                logging::info(&format!(
+                    "Usage reached {} tokens (${:.2}), downgrading models",
+                    total.total_tokens(),
+                    total.cost
+                ));
+                git_state.downgrade_level = level;
+            }
+            apply_model_downgrades(git_state);
+        }
+        protocol::ChildNotification::DelegationFinished {
+            delegation_id,
+            result,
+            error,
+        } => match fan_out_child(git_state, &delegation_id) {
+            Some(actor_id) => {
+                finish_repo_run(git_state, &delegation_id, result, error);
+                // Each fan-out spawns children of its own
+                if let Err(e) = stop_child(&actor_id) {
+                    logging::warn(&format!("Failed to stop {}: {:?}", actor_id, e));
+                }
+            }
+            None => finish_delegation(git_state, &delegation_id, result, error),
+        },
+        protocol::ChildNotification::Tick { timestamp } => {
+            git_state.last_tick_at = Some(timestamp);
+            // Retries whose backoff has run out
+            flush_outbound_queue(git_state, false);
+            if let Some(switch) = git_state.assistant_config.dead_man_switch.clone() {
+                check_dead_man_switch(git_state, &switch, timestamp);
+            }
+            check_workflow_timeout(git_state, timestamp);
+            if let Some(timeout_secs) = git_state.assistant_config.idle_timeout_secs {
+                check_idle_timeout(git_state, timeout_secs, timestamp);
+            }
+            if let Some(config) = git_state.assistant_config.digest.clone() {
+                run_scheduled_digest(git_state, &config, timestamp);
+            }
+            run_scheduled_workflows(git_state, timestamp);
+            if let Some(watch) = git_state.assistant_config.divergence_watch.clone() {
+                check_divergence_watch(git_state, &watch);
+            }
+        }
+    }
+}
+
+/// Audit log entry for a tool call of the model in the session of `chat_state_actor_id`,
+/// or of the default session.
+fn tool_audit_entry(
+    git_state: &GitChatState,
+    tool: String,
+    arguments: Option<Value>,
+    chat_state_actor_id: Option<String>,
+) -> AuditEntry {
+    let session_id = match chat_state_actor_id {
+        Some(chat_actor_id) => git_state.session_for_chat_actor(&chat_actor_id),
+        None => Some(DEFAULT_SESSION_ID.to_string()),
+    };
+    AuditEntry {
+        id: 0,
+        at: clock::now(),
+        source: AuditSource::Tool,
+        session_id,
+        command: tool,
+        arguments: arguments
+            .map(|arguments| logging::sanitize(&arguments))
+            .unwrap_or_default(),
+        approval: Approval::Automatic,
+        outcome: None,
+        confirmation_id: None,
+    }
+}
+
+/// Regenerate the changelog section of the branch's open pull request when HEAD has
+/// moved since the last update. Failures are logged; they never interrupt the session.
+fn sync_pr_changelog(git_state: &mut GitChatState) {
+    let forge = match &git_state.assistant_config.forge_mcp {
+        Some(forge) if forge.update_pr_changelog && git_state.halt.is_none() => forge.clone(),
+        _ => return,
+    };
+    if let Err(e) = try_sync_pr_changelog(git_state, &forge) {
+        logging::warn(&format!(
+            "Could not update the pull request changelog: {}",
+            e
+        ));
+    }
+}
+
+fn try_sync_pr_changelog(git_state: &mut GitChatState, forge: &ForgeConfig) -> Result<(), String> {
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.clone();
+    let git = |args: &[&str]| git_tools::run_git(&git_actor_id, directory.as_deref(), args);
+    let head = git(&["rev-parse", "HEAD"])?.trim().to_string();
+    let synced_at = git_state
+        .pr_changelog_synced_at
+        .clone()
+        .or_else(|| git_state.commit_base.clone());
+    match synced_at {
+        Some(synced_at) if synced_at != head => {}
+        // Nothing committed yet; later commits are compared with the current HEAD
+        _ => {
+            git_state.pr_changelog_synced_at = Some(head);
+            return Ok(());
+        }
+    }
+
+    let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"])?
+        .trim()
+        .to_string();
+    let base = git_state
+        .assistant_config
+        .pr_description
+        .clone()
+        .unwrap_or_default()
+        .base_branch;
+    let section =
+        pr_description::fetch_changelog_section(&git_actor_id, directory.as_deref(), &base)?;
+    let forge_actor_id = git_state.forge_tools_actor_id(forge)?;
+    let number = forge_tools::find_change_request(&forge_actor_id, forge, &branch)?;
+    let body = forge_tools::fetch_change_request_body(&forge_actor_id, forge, number)?;
+    let updated = pr_description::replace_changelog_section(&body, &section);
+    if updated != body {
+        forge_tools::update_change_request_body(&forge_actor_id, forge, number, &updated)?;
+        logging::info(&format!(
+            "Updated the changelog of {} {}",
+            forge.provider.change_request(),
+            number
+        ));
+    }
+    git_state.pr_changelog_synced_at = Some(head);
+    Ok(())
+}
+
+/// Send a digest to the notify actor when one is due.
+fn run_scheduled_digest(git_state: &mut GitChatState, config: &DigestConfig, timestamp: u64) {
+    if !config.is_due(git_state.last_digest_at, timestamp) {
+        return;
+    }
+    // The first digest covers one interval
+    let since = git_state
+        .last_digest_at
+        .unwrap_or_else(|| timestamp.saturating_sub(config.interval_minutes * 60));
+    let digest = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        let bounds = git_state.history_bounds(&git_actor_id);
+        digest::fetch_digest(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            config,
+            Some(since),
+            Some(timestamp),
+            &bounds,
+        )
+    });
+    match digest {
+        Ok(mut digest) => {
+            digest.generated_at = Some(clock::now());
+            logging::info(&format!(
+                "Digest: {} commits by {} authors",
+                digest.total_commits,
+                digest.authors.len()
+            ));
+            notify(git_state, &GitChatNotification::Digest { digest });
+            git_state.last_digest_at = Some(timestamp);
+        }
+        Err(e) => {
+            logging::warn(&format!("Could not build digest: {}", e));
+            git_state.last_error = Some(e);
+        }
+    }
+}
+
+/// Start the first `schedule` entry that is due, once no workflow or generation is
+/// running; entries that come due while the assistant is busy wait for it.
+fn run_scheduled_workflows(git_state: &mut GitChatState, timestamp: u64) {
+    let entries = git_state
+        .assistant_config
+        .schedule
+        .clone()
+        .unwrap_or_default();
+    schedule::sync(&entries, &mut git_state.scheduled_runs);
+    for (index, entry) in entries.iter().enumerate() {
+        let run = &mut git_state.scheduled_runs[index];
+        if run.next_run_at.is_none() && run.last_outcome.is_none() {
+            run.next_run_at = entry.next_run(None, timestamp);
+        }
+    }
+    let busy = workflow_running(git_state)
+        || git_state.halt.is_some()
+        || git_state.budget_exceeded
+        || git_state.generations_in_flight.contains(DEFAULT_SESSION_ID);
+    if busy {
+        return;
+    }
+    let Some(index) = git_state
+        .scheduled_runs
+        .iter()
+        .position(|run| run.next_run_at.is_some_and(|next| next <= timestamp))
+    else {
+        return;
+    };
+    let entry = entries[index].clone();
+    logging::info(&format!(
+        "Starting the scheduled {} workflow",
+        entry.workflow.name()
+    ));
+    // A scheduled run stands on its own, outside any configured pipeline
+    git_state.pipeline = None;
+    let response = match switch_workflow(git_state, entry.workflow.clone()) {
+        Ok(()) => handle_git_chat_request(
+            git_state,
+            GitChatRequest::StartChat {
+                allow_secrets: false,
+                allow_out_of_scope: false,
+                workflow: None,
+                args: None,
+                suggest_only: false,
+            },
+        ),
+        Err(e) => errors::error(
+            ErrorCode::OperationFailed,
+            format!("Failed to switch workflow: {}", e),
+        ),
+    };
+    let outcome = match response {
+        GitChatResponse::Error { message, .. } => {
+            logging::warn(&format!(
+                "Scheduled {} workflow didn't start: {}",
+                entry.workflow.name(),
+                message
+            ));
+            git_state.last_error = Some(message.clone());
+            message
+        }
+        GitChatResponse::ConfirmationRequired { confirmation } => {
+            format!("waiting for confirmation {}", confirmation.id)
+        }
+        _ => "started".to_string(),
+    };
+    let run = &mut git_state.scheduled_runs[index];
+    run.last_run_at = Some(timestamp);
+    run.next_run_at = entry.next_run(Some(timestamp), timestamp);
+    run.last_outcome = Some(outcome);
+}
+
+/// Halt an auto-initiated run when no keep-alive arrived within the interval: cancel
+/// running generations, record a snapshot, and refuse mutating requests until the next
+/// `KeepAlive`.
+fn check_dead_man_switch(git_state: &mut GitChatState, switch: &DeadManSwitch, timestamp: u64) {
+    if !git_state.auto_initiated || git_state.workflow_finished || git_state.halt.is_some() {
+        return;
+    }
+    let last_keepalive_at = match git_state.last_keepalive_at {
+        Some(last_keepalive_at) => last_keepalive_at,
+        // No tick had arrived when the run started; count from this one
+        None => {
+            git_state.last_keepalive_at = Some(timestamp);
+            return;
+        }
+    };
+    if !switch.is_expired(last_keepalive_at, timestamp) {
+        return;
+    }
+    logging::warn(&format!(
+        "No keep-alive for {} seconds, halting",
+        timestamp.saturating_sub(last_keepalive_at)
+    ));
+
+    let cancelled_sessions = cancel_generations(git_state);
+    let head = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        git_tools::run_git(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &["rev-parse", "HEAD"],
+        )
+    });
+
+    let snapshot = HaltSnapshot {
+        halted_at: timestamp,
+        last_keepalive_at,
+        head: head.ok().map(|head| head.trim().to_string()),
+        cancelled_sessions,
+        status: git_state.status(),
+    };
+    git_state.halt = Some(snapshot.clone());
+    notify(git_state, &GitChatNotification::Halted { snapshot });
+    publish(
+        git_state,
+        &GitChatEvent::WorkflowStateChanged {
+            workflow: git_state.workflow.clone(),
+            state: WorkflowState::Halted,
+        },
+    );
+}
+
+/// Cancel every generation in flight and return the sessions they ran in.
+fn cancel_generations(git_state: &mut GitChatState) -> Vec<String> {
+    let mut cancelled_sessions = Vec::new();
+    for session_id in git_state.generations_in_flight.clone() {
+        let cancelled = git_state
+            .chat_actor_for_session(Some(&session_id))
+            .and_then(|chat_actor_id| {
+                send_to_chat_state(
+                    &chat_actor_id,
+                    &protocol::ChatStateRequest::CancelGeneration,
+                )
+            });
+        match cancelled {
+            Ok(()) => {
+                mark_generation_cancelled(git_state, &session_id);
+                let reply = Err("The generation was cancelled".to_string());
+                answer_pending_replies(git_state, &session_id, reply);
+                cancelled_sessions.push(session_id);
+            }
+            Err(e) => logging::warn(&format!(
+                "Failed to cancel the generation in session {}: {}",
+                session_id, e
+            )),
+        }
+    }
+    cancelled_sessions
+}
+
+/// Stop generating once the budget is spent. It stays spent for the assistant's
+/// lifetime.
+fn stop_for_budget(git_state: &mut GitChatState) {
+    let total = usage::total(&git_state.usage);
+    let error_msg = format!(
+        "Token budget spent ({} tokens, ${:.2}), stopping generation",
+        total.total_tokens(),
+        total.cost
+    );
+    logging::warn(&error_msg);
+    git_state.budget_exceeded = true;
+    git_state.last_error = Some(error_msg);
+    let cancelled_sessions = cancel_generations(git_state);
+    if !cancelled_sessions.is_empty() {
+        logging::info(&format!(
+            "Cancelled generations in: {}",
+            cancelled_sessions.join(", ")
+        ));
+    }
+}
+
+/// The model a session currently generates with: the one it last switched to, or the
+/// configured model.
+fn session_model(git_state: &GitChatState, session_id: &str) -> String {
+    if let Some(model) = git_state.session_models.get(session_id) {
+        return model.clone();
+    }
+    // States saved before models were recorded per session
+    let used = git_state
+        .fallbacks_used
+        .get(session_id)
+        .copied()
+        .unwrap_or(0);
+    if let Some(fallback) = used
+        .checked_sub(1)
+        .and_then(|index| git_state.assistant_config.model_fallbacks.get(index))
+    {
+        return fallback.model.clone();
+    }
+    git_state
+        .assistant_config
+        .model_config
+        .as_ref()
+        .and_then(|model_config| model_config.get("model"))
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_MODEL)
+        .to_string()
+}
+
+/// Notify once when the divergence from the watched upstream crosses a threshold.
+fn check_divergence_watch(git_state: &mut GitChatState, watch: &DivergenceWatch) {
+    let divergence = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        let bounds = git_state.history_bounds(&git_actor_id);
+        divergence::fetch_divergence(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            watch.upstream(),
+            &bounds,
+        )
+    });
+    match divergence {
+        Ok(divergence) => {
+            let exceeded = watch.is_exceeded(&divergence);
+            if exceeded && !git_state.divergence_alerted {
+                logging::info(&format!(
+                    "Divergence threshold crossed: {}",
+                    divergence.describe()
+                ));
+                notify(
+                    git_state,
+                    &GitChatNotification::DivergenceExceeded { divergence },
+                );
+            }
+            git_state.divergence_alerted = exceeded;
+        }
+        Err(e) => {
+            logging::warn(&format!("Could not check divergence: {}", e));
+            git_state.last_error = Some(e);
+        }
+    }
+}
+
+/// Run a history query with the configured bounds. In performance mode the parsed result
+/// is cached and reused while `revisions` resolve to the same commits.
+fn cached_history_query<T, F>(
+    git_state: &mut GitChatState,
+    query: &str,
+    revisions: &[&str],
+    run: F,
+) -> Result<T, String>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(&str, Option<&str>, &HistoryBounds) -> Result<T, String>,
+{
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let bounds = git_state.history_bounds(&git_actor_id);
+    let directory = git_state.current_directory.clone();
+    let capacity = match &git_state.assistant_config.performance_mode {
+        Some(mode) => mode.cache_entries,
+        None => return run(&git_actor_id, directory.as_deref(), &bounds),
+    };
+
+    let key = history::cache_key(&git_actor_id, directory.as_deref(), query, revisions)?;
+    if let Some(cached) = git_state.history_cache.get(&key) {
+        logging::debug(&format!("Using cached result for {}", query));
+        return Ok(cached);
+    }
+    let result = run(&git_actor_id, directory.as_deref(), &bounds)?;
+    git_state.history_cache.insert(key, &result, capacity);
+    Ok(result)
+}
+
+/// Send `notification` to the configured notify actor and parent actor, if there are
+/// any.
+fn notify(git_state: &GitChatState, notification: &GitChatNotification) {
+    let config = &git_state.assistant_config;
+    let mut recipients: Vec<&String> = config.notify_actor.iter().collect();
+    if let Some(parent_actor_id) = &config.parent_actor_id {
+        if !recipients.contains(&parent_actor_id) {
+            recipients.push(parent_actor_id);
+        }
+    }
+    if recipients.is_empty() {
+        logging::info("No notify_actor configured, dropping notification");
+        return;
+    }
+    for actor_id in recipients {
+        send_notification(actor_id, notification);
+    }
+}
+
+/// Send a lifecycle `notification` to the parent actor only, if there is one.
+fn notify_parent(git_state: &GitChatState, notification: &GitChatNotification) {
+    if let Some(parent_actor_id) = &git_state.assistant_config.parent_actor_id {
+        send_notification(parent_actor_id, notification);
+    }
+}
+
+fn send_notification(actor_id: &str, notification: &GitChatNotification) {
+    match encode_notification(notification, clock::now()) {
+        Ok(bytes) => {
+            if let Err(e) = send(actor_id, &bytes) {
+                logging::warn(&format!("Failed to notify {}: {:?}", actor_id, e));
+            }
+        }
+        Err(e) => logging::error(&e),
+    }
+}
+
+/// `Ready` for the parent actor, once init is done.
+fn ready_notification(git_state: &GitChatState, resumed: bool) -> GitChatNotification {
+    GitChatNotification::Ready {
+        resumed,
+        workflow: git_state.workflow.clone(),
+        chat_state_actor_id: git_state.chat_state_actor_id.clone(),
+        build: BuildInfo::current(),
+        safe_mode: git_state
+            .safe_mode
+            .as_ref()
+            .map(|safe_mode| safe_mode.error.clone()),
+    }
+}
+
+/// Whether an event of `kind` reaches anyone: a subscriber, a channel stream, or the
+/// HTTP server's event buffer.
+fn is_published(git_state: &GitChatState, kind: EventKind) -> bool {
+    let buffered = git_state.http_server.is_some()
+        && git_state.assistant_config.http.is_some()
+        && kind.wanted_by(&BTreeSet::new());
+    let posted = git_state
+        .assistant_config
+        .notifications
+        .as_ref()
+        .is_some_and(|config| config.events.contains(&kind));
+    buffered
+        || posted
+        || git_state
+            .subscribers
+            .values()
+            .any(|kinds| kind.wanted_by(kinds))
+        || git_state
+            .channel_streams
+            .values()
+            .any(|stream| stream.wants(kind))
+}
+
+/// Send `event` to every subscriber that asked for its kind, and buffer it for
+/// `GET /events` while the HTTP transport is up.
+fn publish(git_state: &mut GitChatState, event: &GitChatEvent) {
+    let kind = event.kind();
+    // HTTP clients don't pick kinds, so they get those observers get by default
+    let max_buffered_events = match (&git_state.http_server, &git_state.assistant_config.http) {
+        (Some(_), Some(config)) if kind.wanted_by(&BTreeSet::new()) => {
+            Some(config.max_buffered_events)
+        }
+        _ => None,
+    };
+    if !is_published(git_state, kind) {
+        return;
+    }
+    let subscribers: Vec<&String> = git_state
+        .subscribers
+        .iter()
+        .filter(|(_, kinds)| kind.wanted_by(kinds))
+        .map(|(actor_id, _)| actor_id)
+        .collect();
+    let at = clock::now();
+    let bytes = match encode_event(event, at) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            logging::error(&e);
+            return;
+        }
+    };
+    for actor_id in subscribers {
+        if let Err(e) = send(actor_id, &bytes) {
+            logging::warn(&format!("Failed to send event to {}: {:?}", actor_id, e));
+        }
+    }
+
+    let max_unacked_frames = git_state
+        .assistant_config
+        .channels
+        .clone()
+        .unwrap_or_default()
+        .max_unacked_frames;
+    for stream in git_state.channel_streams.values_mut() {
+        if !stream.wants(kind) {
+            continue;
+        }
+        let frame = stream.push(max_unacked_frames, |seq| ServerFrame::Event {
+            seq,
+            event: event.clone(),
+            at: Some(at),
+        });
+        if let Some(channel_id) = &stream.channel_id {
+            send_frames(channel_id, &[frame]);
+        }
+    }
+
+    if let Some(config) = &git_state.assistant_config.notifications {
+        if config.events.contains(&kind) {
+            if let Err(e) = config.post(event, &bytes) {
+                logging::warn(&format!("Failed to post event to the webhook: {}", e));
+            }
+        }
+    }
+
+    if let Some(max_buffered_events) = max_buffered_events {
+        let id = git_state.next_http_event_id;
+        git_state.next_http_event_id += 1;
+        http::buffer_event(
+            &mut git_state.http_events,
+            max_buffered_events,
+            BufferedEvent {
+                id,
+                kind: serde_json::to_value(kind)
+                    .ok()
+                    .and_then(|kind| kind.as_str().map(str::to_string))
+                    .unwrap_or_default(),
+                data: String::from_utf8_lossy(&bytes).to_string(),
+            },
+        );
+    }
+}
+
+/// Accept the completion of the auto-initiated workflow: check its commits, then
+/// finish it unless the model was asked to revisit its work.
+fn complete_task(git_state: &mut GitChatState, summary: Option<String>) {
+    set_workflow_phase(git_state, ProgressPhase::Verifying);
+    let checked = check_commit_plan(git_state).and_then(|reprompted| {
+        Ok(reprompted
+            || check_new_commits(git_state)?
+            || check_stash_triage(git_state, summary.as_deref())?
+            || check_hook_review(git_state)?)
+    });
+    let error = match checked {
+        Ok(true) => {
+            logging::info("Asked the model to revisit its work");
+            return;
+        }
+        Ok(false) => None,
+        Err(e) => {
+            logging::warn(&format!("Could not check commit messages: {}", e));
+            git_state.last_error = Some(e.clone());
+            Some(e)
+        }
+    };
+    git_state.workflow_finished = true;
+    sync_pr_changelog(git_state);
+    finish_workflow(git_state, summary, error);
+}
+
+/// Whether an auto-initiated workflow is under way and may still complete.
+fn workflow_running(git_state: &GitChatState) -> bool {
+    git_state.auto_initiated
+        && git_state.workflow.is_some()
+        && !git_state.workflow_finished
+        && git_state.halt.is_none()
+}
+
+/// Checkpoint of the running workflow, or `None` when none is running.
+fn workflow_checkpoint(git_state: &GitChatState) -> Option<WorkflowCheckpoint> {
+    if !workflow_running(git_state) {
+        return None;
+    }
+    let workflow = git_state.workflow.clone()?;
+    let rebase = git_state.rebase.as_ref().map(|rebase| rebase.status);
+    let commit_plan = git_state.commit_plan.as_ref().map(|plan| plan.approved);
+    let picking = git_state.cherry_pick.as_ref().is_some_and(|cherry_pick| {
+        matches!(
+            cherry_pick.phase,
+            CherryPickPhase::Picking | CherryPickPhase::Conflicted
+        )
+    });
+    let sync = git_state.sync.as_ref().map(|sync| sync.phase);
+    let phase = if rebase == Some(RebaseStatus::Executing)
+        || commit_plan == Some(true)
+        || picking
+        || sync == Some(SyncPhase::Conflicted)
+    {
+        WorkflowPhase::Executing
+    } else if rebase == Some(RebaseStatus::Proposed)
+        || commit_plan == Some(false)
+        || sync == Some(SyncPhase::Proposed)
+        || !git_state.pending_confirmations.is_empty()
+    {
+        WorkflowPhase::AwaitingApproval
+    } else {
+        WorkflowPhase::Working
+    };
+    let mut pending = blocking_approvals(git_state);
+    if let Some(held) = git_state
+        .held_messages
+        .get(DEFAULT_SESSION_ID)
+        .filter(|held| !held.is_empty())
+    {
+        pending.push(format!("{} held messages", held.len()));
+    }
+    Some(WorkflowCheckpoint {
+        workflow,
+        phase,
+        generating: git_state.generations_in_flight.contains(DEFAULT_SESSION_ID),
+        pending,
+    })
+}
+
+/// Pick up the workflow the host interrupted by restarting the actor. The repository
+/// is checked for the commits made since the workflow started. When the conversation
+/// was lost, a git operation left half done is aborted, and a new conversation goes on
+/// from the workflow's phase, told what was found.
+fn resume_workflow(
+    git_state: &mut GitChatState,
+    checkpoint: WorkflowCheckpoint,
+    conversation_kept: bool,
+) {
+    logging::info(&format!(
+        "Resuming the {} workflow, interrupted while {:?} with {} pending actions",
+        checkpoint.workflow.name(),
+        checkpoint.phase,
+        checkpoint.pending.len()
+    ));
+    let mut phase = checkpoint.phase;
+    let mut commits = Vec::new();
+    let mut rolled_back = None;
+    match git_state.git_tools_actor_id() {
+        Ok(git_actor_id) => {
+            let directory = git_state.current_directory.clone();
+            if let Some(base) = git_state.commit_base.clone() {
+                match convention::fetch_commit_messages(
+                    &git_actor_id,
+                    directory.as_deref(),
+                    Some(&base),
+                ) {
+                    Ok(found) => {
+                        commits = found
+                            .iter()
+                            .map(|commit| format!("{} {}", commit.short_sha(), commit.subject()))
+                            .collect();
+                    }
+                    Err(e) => logging::warn(&format!("Could not list the new commits: {}", e)),
+                }
+            }
+            // Without the conversation, nobody knows how to finish the operation
+            let operation = (!conversation_kept)
+                .then(|| resume::operation_in_progress(&git_actor_id, directory.as_deref()))
+                .flatten();
+            if let Some(operation) = operation {
+                match resume::abort(&git_actor_id, directory.as_deref(), operation) {
+                    Ok(()) => {
+                        logging::info(&format!(
+                            "Aborted the {} left half done",
+                            operation.command()
+                        ));
+                        rolled_back = Some(operation);
+                    }
+                    Err(e) => logging::warn(&format!(
+                        "Could not abort the {} left half done: {}",
+                        operation.command(),
+                        e
+                    )),
+                }
+            }
+        }
+        Err(e) => logging::warn(&format!("Could not check the repository: {}", e)),
+    }
+    // An aborted `ExecuteRebasePlan` can be executed again
+    if rolled_back == Some(GitOperation::Rebase) {
+        if let Some(rebase) = git_state
+            .rebase
+            .as_mut()
+            .filter(|rebase| rebase.status == RebaseStatus::Executing)
+        {
+            rebase.status = RebaseStatus::Proposed;
+            phase = WorkflowPhase::AwaitingApproval;
+        }
+    }
+    let report = ResumeReport {
+        workflow: checkpoint.workflow.clone(),
+        phase,
+        action: resume::action(phase, conversation_kept),
+        commits,
+        rolled_back,
+    };
+    let resumed = match report.action {
+        ResumeAction::Continued => Ok(()),
+        ResumeAction::Waiting => forward_message(
+            git_state,
+            DEFAULT_SESSION_ID,
+            user_message(report.message().trim_start().to_string()),
+            Provenance::Supervisor,
+        ),
+        ResumeAction::Restarted => {
+            // The workflow, and the commits it made, started before the restart
+            let commit_base = git_state.commit_base.clone();
+            let workflow_branch = git_state.workflow_branch.clone();
+            build_auto_message(git_state, &checkpoint.workflow).and_then(|mut auto_message| {
+                git_state.commit_base = commit_base;
+                git_state.workflow_branch = workflow_branch;
+                auto_message.push_str(&report.message());
+                open_workflow(git_state, &checkpoint.workflow, auto_message)
+            })
+        }
+    };
+    match resumed {
+        Ok(()) => git_state.auto_initiated = true,
+        Err(e) => {
+            let error_msg = format!(
+                "Could not resume the {} workflow: {}",
+                checkpoint.workflow.name(),
+                e
+            );
+            logging::error(&error_msg);
+            git_state.last_error = Some(error_msg);
+        }
+    }
+    publish(
+        git_state,
+        &GitChatEvent::WorkflowStateChanged {
+            workflow: Some(checkpoint.workflow),
+            state: WorkflowState::Resumed,
+        },
+    );
+    notify(
+        git_state,
+        &GitChatNotification::WorkflowResumed { resume: report },
+    );
+}
+
+/// Completion criteria of the active workflow.
+fn completion_criteria(git_state: &GitChatState) -> Vec<CompletionCriterion> {
+    match &git_state.workflow {
+        Some(workflow) => git_state
+            .assistant_config
+            .completion
+            .clone()
+            .unwrap_or_default()
+            .criteria_for(workflow),
+        None => Vec::new(),
+    }
+}
+
+/// Check the active workflow's completion criteria after a generation in the default
+/// session. A completion sentinel is handled like the task monitor's notification; any
+/// other criterion that is met completes the workflow without a summary, or with the
+/// rest of the reply for a phrase.
+fn check_completion(git_state: &mut GitChatState) {
+    if !workflow_running(git_state) {
+        return;
+    }
+    let criteria = completion_criteria(git_state);
+    let sentinel = uses_completion_sentinel(&git_state.assistant_config);
+    let reply = if criteria
+        .iter()
+        .any(|criterion| criterion.reads_reply(sentinel))
+    {
+        match latest_message_text(git_state) {
+            Ok(reply) => Some(reply),
+            Err(e) => {
+                logging::warn(&format!(
+                    "Could not read the reply for completion criteria: {}",
+                    e
+                ));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    for criterion in &criteria {
+        let summary = match criterion {
+            CompletionCriterion::Signal => {
+                let notification = reply
+                    .as_deref()
+                    .filter(|_| sentinel)
+                    .and_then(completion::parse);
+                if let Some(notification) = notification {
+                    logging::info("The model's reply ends with a completion sentinel");
+                    handle_child_notification(git_state, notification);
+                    return;
+                }
+                continue;
+            }
+            CompletionCriterion::Phrase { phrase } => {
+                match reply
+                    .as_deref()
+                    .and_then(|reply| completion::strip_phrase(reply, phrase))
+                {
+                    Some(rest) => Some(rest).filter(|rest| !rest.is_empty()),
+                    None => continue,
+                }
+            }
+            CompletionCriterion::RepoState { state } => {
+                let holds = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+                    state.holds(
+                        &git_actor_id,
+                        git_state.current_directory.as_deref(),
+                        git_state.commit_base.as_deref(),
+                    )
+                });
+                match holds {
+                    Ok(true) => None,
+                    Ok(false) => continue,
+                    Err(e) => {
+                        logging::warn(&format!("Could not check the repository state: {}", e));
+                        continue;
+                    }
+                }
+            }
+            CompletionCriterion::Turns { turns } => {
+                if git_state.workflow_turns < *turns {
+                    continue;
+                }
+                None
+            }
+        };
+        logging::info(&format!(
+            "Completion criterion met: {}",
+            criterion.describe()
+        ));
+        let summary = summary.map(|summary| postprocess(git_state, &summary));
+        complete_task(git_state, summary);
+        return;
+    }
+}
+
+/// Check the turns of the auto-initiated workflow against `max_turns`. Past the limit,
+/// the model is told to wrap up; if it keeps going for `wrap_up_turns` more, the
+/// workflow is stopped.
+fn check_turn_limit(git_state: &mut GitChatState) {
+    if !workflow_running(git_state) {
+        return;
+    }
+    let (workflow, limits) = match (&git_state.workflow, &git_state.assistant_config.max_turns) {
+        (Some(workflow), Some(limits)) => (workflow.clone(), limits.clone()),
+        _ => return,
+    };
+    let max_turns = match limits.limit(&workflow) {
+        Some(max_turns) => max_turns,
+        None => return,
+    };
+    let turns = git_state.workflow_turns;
+    match git_state.wrap_up_sent_at {
+        None if turns >= max_turns => {
+            logging::info(&format!(
+                "The {} workflow reached {} turns, asking the model to wrap up",
+                workflow.name(),
+                turns
+            ));
+            let message = turn_limit::wrap_up_message(max_turns);
+            match forward_and_generate(
+                git_state,
+                None,
+                user_message(message),
+                Provenance::Supervisor,
+            ) {
+                Ok(()) => git_state.wrap_up_sent_at = Some(turns),
+                Err(e) => {
+                    logging::warn(&format!("Could not ask the model to wrap up: {}", e));
+                    stall_workflow(git_state, workflow, max_turns);
+                }
+            }
+        }
+        Some(sent_at) if turns >= sent_at + limits.wrap_up_turns => {
+            stall_workflow(git_state, workflow, max_turns);
+        }
+        _ => {}
+    }
+}
+
+/// Stop a workflow that went past its turn limit and report it as stalled.
+fn stall_workflow(git_state: &mut GitChatState, workflow: Workflow, max_turns: u32) {
+    let stall = WorkflowStall {
+        workflow: workflow.clone(),
+        turns: git_state.workflow_turns,
+        max_turns: Some(max_turns),
+        elapsed_secs: None,
+        timeout_secs: None,
+    };
+    let error_msg = format!(
+        "{}: the {} workflow took {} turns (limit {}) without completing",
+        ErrorCode::WorkflowStalled,
+        workflow.name(),
+        stall.turns,
+        max_turns
+    );
+    abort_workflow(git_state, stall, error_msg);
+}
+
+/// Stop the auto-initiated workflow once it has run longer than its
+/// `workflow_timeout_secs`, at `now` in seconds since the epoch.
+fn check_workflow_timeout(git_state: &mut GitChatState, now: u64) {
+    if !workflow_running(git_state) {
+        return;
+    }
+    let (workflow, started_at) = match (&git_state.workflow, git_state.workflow_started_at) {
+        (Some(workflow), Some(started_at)) => (workflow.clone(), started_at),
+        _ => return,
+    };
+    let timeout_secs = match git_state
+        .assistant_config
+        .workflow_timeout_secs
+        .as_ref()
+        .and_then(|timeouts| timeouts.limit(&workflow))
+    {
+        Some(timeout_secs) => timeout_secs,
+        None => return,
+    };
+    let elapsed_secs = now.saturating_sub(started_at);
+    if elapsed_secs < timeout_secs {
+        return;
+    }
+    let stall = WorkflowStall {
+        workflow: workflow.clone(),
+        turns: git_state.workflow_turns,
+        max_turns: None,
+        elapsed_secs: Some(elapsed_secs),
+        timeout_secs: Some(timeout_secs),
+    };
+    let error_msg = format!(
+        "{}: the {} workflow ran for {}s (limit {}s) without completing",
+        ErrorCode::WorkflowTimedOut,
+        workflow.name(),
+        elapsed_secs,
+        timeout_secs
+    );
+    abort_workflow(git_state, stall, error_msg);
+}
+
+/// Close every session and shut down once no client has interacted for `timeout_secs`,
+/// at `now` in seconds since the epoch. A running workflow or generation keeps the
+/// assistant alive, however long it takes.
+fn check_idle_timeout(git_state: &mut GitChatState, timeout_secs: u64, now: u64) {
+    if workflow_running(git_state) || !git_state.generations_in_flight.is_empty() {
+        return;
+    }
+    let Some(since) = git_state.last_interaction_at.or(git_state.started_at) else {
+        return;
+    };
+    let Some(idle_secs) = idle::expired(timeout_secs, since, now) else {
+        return;
+    };
+    logging::info(&format!(
+        "Idle for {}s (limit {}s), closing sessions and shutting down",
+        idle_secs, timeout_secs
+    ));
+    let mut sessions: Vec<(String, String)> = git_state
+        .sessions
+        .iter()
+        .map(|(session_id, session)| (session_id.clone(), session.chat_state_actor_id.clone()))
+        .collect();
+    if let Some(chat_actor_id) = git_state.chat_state_actor_id.take() {
+        sessions.insert(0, (DEFAULT_SESSION_ID.to_string(), chat_actor_id));
+    }
+    git_state.sessions.clear();
+    git_state.outbound_queue.clear();
+    for (session_id, chat_actor_id) in sessions {
+        let error = stop_chat_state_actor(&chat_actor_id).err();
+        if let Some(e) = &error {
+            logging::warn(&format!(
+                "Could not stop chat-state actor {}: {}",
+                chat_actor_id, e
+            ));
+        }
+        publish(
+            git_state,
+            &GitChatEvent::SessionClosed {
+                session_id,
+                abnormal: false,
+                summary: Some(format!("Closed after {}s idle", idle_secs)),
+                error,
+                respawned: false,
+            },
+        );
+    }
+    let _ = shutdown(None);
+}
+
+/// Stop a workflow that went past one of its limits: cancel its generations, report it
+/// as stalled, and finish it with `error_msg`.
+fn abort_workflow(git_state: &mut GitChatState, stall: WorkflowStall, error_msg: String) {
+    let workflow = stall.workflow.clone();
+    logging::warn(&error_msg);
+    git_state.workflow_aborted = Some(stall.clone());
+    cancel_generations(git_state);
+    git_state.held_messages.remove(DEFAULT_SESSION_ID);
+    git_state.workflow_finished = true;
+    git_state.last_error = Some(error_msg.clone());
+    notify(git_state, &GitChatNotification::WorkflowStalled { stall });
+    publish(
+        git_state,
+        &GitChatEvent::WorkflowStateChanged {
+            workflow: Some(workflow),
+            state: WorkflowState::Stalled,
+        },
+    );
+    finish_workflow(git_state, None, Some(error_msg));
+}
+
+/// Record the exit of a session's chat-state actor and tell subscribers. When it exited
+/// on a failure during the default session's workflow, the workflow is retried in a new
+/// actor instead.
+fn close_exited_session(
+    git_state: &mut GitChatState,
+    session_id: &str,
+    chat_actor_id: &str,
+    exit: protocol::ChatStateExit,
+) {
+    match &exit.error {
+        Some(error) => logging::warn(&format!(
+            "Chat-state actor of session {} exited on a failure: {}",
+            session_id, error
+        )),
+        None => logging::info(&format!(
+            "Chat-state actor of session {} exited",
+            session_id
+        )),
+    }
+    git_state.generations_in_flight.remove(session_id);
+    git_state.overridden_generations.remove(session_id);
+    if let Some(held) = git_state.held_messages.remove(session_id) {
+        logging::warn(&format!(
+            "Dropped {} messages held for session {}",
+            held.len(),
+            session_id
+        ));
+    }
+    let workflow_active = session_id == DEFAULT_SESSION_ID
+        && git_state.auto_initiated
+        && !git_state.workflow_finished
+        && git_state.halt.is_none();
+    let crash_looping = exit.is_abnormal() && {
+        let error = exit.error.clone().unwrap_or_default();
+        record_crash(git_state, session_id, chat_actor_id, "exit", &error)
+    };
+    let respawned = if exit.is_abnormal() && workflow_active && !crash_looping {
+        match respawn_default_session(git_state) {
+            Ok(()) => true,
+            Err(e) => {
+                let error_msg = format!("Failed to retry the workflow: {}", e);
+                logging::error(&error_msg);
+                git_state.last_error = Some(error_msg);
+                false
+            }
+        }
+    } else {
+        false
+    };
+    if !respawned {
+        if session_id == DEFAULT_SESSION_ID {
+            git_state.chat_state_actor_id = None;
+        } else {
+            git_state.sessions.remove(session_id);
+            git_state
+                .outbound_queue
+                .retain(|queued| queued.session_id != session_id);
+        }
+        // Otherwise the failure of the crash loop is the last error
+        if let (Some(error), false) = (&exit.error, crash_looping) {
+            git_state.last_error = Some(format!("Session {} exited: {}", session_id, error));
+        }
+    }
+    publish(
+        git_state,
+        &GitChatEvent::SessionClosed {
+            session_id: session_id.to_string(),
+            abnormal: exit.is_abnormal(),
+            summary: exit.summary.clone(),
+            error: exit.error.as_deref().map(logging::sanitize_text),
+            respawned,
+        },
+    );
+    git_state
+        .closed_sessions
+        .insert(session_id.to_string(), exit);
+}
+
+/// Give the default session a new chat-state actor and start its workflow over. The
+/// commit base is kept, so commits from the failed attempt are still checked.
+fn respawn_default_session(git_state: &mut GitChatState) -> Result<(), String> {
+    if git_state.exit_respawns >= MAX_EXIT_RESPAWNS {
+        return Err(format!(
+            "the workflow was already retried {} times",
+            MAX_EXIT_RESPAWNS
+        ));
+    }
+    let workflow = git_state
+        .workflow
+        .clone()
+        .ok_or_else(|| "no workflow to retry".to_string())?;
+    let chat_actor_id = spawn_chat_state_actor(&git_state.original_config)?;
+    logging::info(&format!(
+        "Retrying the {} workflow in {}",
+        workflow.name(),
+        chat_actor_id
+    ));
+    git_state.set_chat_state_actor_id(chat_actor_id);
+    git_state.exit_respawns += 1;
+    git_state.metrics.count_restart("chat-state");
+
+    let commit_base = git_state.commit_base.clone();
+    let workflow_branch = git_state.workflow_branch.clone();
+    let auto_message = build_auto_message(git_state, &workflow);
+    if commit_base.is_some() {
+        git_state.commit_base = commit_base;
+        git_state.workflow_branch = workflow_branch;
+    }
+    let auto_message = format!(
+        "{}\n\nNOTE: An earlier attempt at this task stopped unexpectedly. Check the \
+        repository for work it already did before you continue.",
+        auto_message?
+    );
+    open_workflow(git_state, &workflow, auto_message)
+}
+
+/// Name of a `WitErrorType`, as recorded in a [`Crash`].
+fn error_type_name(error_type: &WitErrorType) -> &'static str {
+    match error_type {
+        WitErrorType::OperationTimeout => "operation-timeout",
+        WitErrorType::ChannelClosed => "channel-closed",
+        WitErrorType::ShuttingDown => "shutting-down",
+        WitErrorType::FunctionNotFound => "function-not-found",
+        WitErrorType::TypeMismatch => "type-mismatch",
+        WitErrorType::Internal => "internal",
+        WitErrorType::SerializationError => "serialization-error",
+        WitErrorType::UpdateComponentError => "update-component-error",
+        WitErrorType::Paused => "paused",
+    }
+}
+
+/// Record a crash of `child` when it is a session's chat-state actor. True when the
+/// session crashed too often and was failed, so it must not be given a new actor.
+fn record_crash_after(
+    state: &mut Option<Vec<u8>>,
+    child: &str,
+    error_type: &str,
+    error: &str,
+) -> bool {
+    let Some(Ok(mut git_state)) = state.as_deref().map(decode_state) else {
+        return false;
+    };
+    let Some(session_id) = git_state.session_for_chat_actor(child) else {
+        return false;
+    };
+    let failed = record_crash(&mut git_state, &session_id, child, error_type, error);
+    match encode_state(&mut git_state) {
+        Ok(bytes) => *state = Some(bytes),
+        Err(e) => logging::error(&format!("Failed to serialize git state: {}", e)),
+    }
+    failed
+}
+
+/// Record a crash of a session's chat-state actor, failing the session when it is
+/// crashing in a loop. True when it was failed.
+fn record_crash(
+    git_state: &mut GitChatState,
+    session_id: &str,
+    child: &str,
+    error_type: &str,
+    error: &str,
+) -> bool {
+    let policy = git_state
+        .assistant_config
+        .crash_loop
+        .clone()
+        .unwrap_or_default();
+    let crash = Crash {
+        at: clock::now(),
+        child_id: child.to_string(),
+        error_type: error_type.to_string(),
+        error: logging::sanitize_text(error),
+    };
+    let crashes = git_state.crashes.entry(session_id.to_string()).or_default();
+    if !policy.record(crashes, crash) {
+        return false;
+    }
+    let failure = SessionFailure {
+        session_id: session_id.to_string(),
+        failed_at: clock::now(),
+        crashes: git_state.crashes.remove(session_id).unwrap_or_default(),
+    };
+    fail_session(git_state, failure);
+    true
+}
+
+/// Give up on a session whose chat-state actor crashed in a loop: stop it, drop what
+/// was waiting for it, and fail its workflow. Requests to the session fail until a new
+/// session takes its id.
+fn fail_session(git_state: &mut GitChatState, failure: SessionFailure) {
+    let error_msg = failure.describe();
+    logging::error(&error_msg);
+    let session_id = failure.session_id.clone();
+    let chat_actor_id = match session_id.as_str() {
+        DEFAULT_SESSION_ID => git_state.chat_state_actor_id.take(),
+        _ => git_state
+            .sessions
+            .remove(&session_id)
+            .map(|session| session.chat_state_actor_id),
+    };
+    // An actor that reported an error may still be running
+    if let Some(chat_actor_id) = chat_actor_id {
+        if list_children().contains(&chat_actor_id) {
+            if let Err(e) = stop_chat_state_actor(&chat_actor_id) {
+                logging::warn(&format!("Could not stop {}: {}", chat_actor_id, e));
+            }
+        }
+    }
+    git_state.generations_in_flight.remove(&session_id);
+    git_state.overridden_generations.remove(&session_id);
+    git_state.pending_overrides.remove(&session_id);
+    git_state.held_messages.remove(&session_id);
+    git_state
+        .outbound_queue
+        .retain(|queued| queued.session_id != session_id);
+    answer_pending_replies(git_state, &session_id, Err(error_msg.clone()));
+    git_state.last_error = Some(error_msg.clone());
+    git_state
+        .failed_sessions
+        .insert(session_id.clone(), failure.clone());
+    publish(git_state, &GitChatEvent::SessionFailed { failure });
+    if session_id == DEFAULT_SESSION_ID {
+        notify_parent(
+            git_state,
+            &GitChatNotification::FatalError {
+                session_id: Some(session_id.clone()),
+                error: error_msg.clone(),
+            },
+        );
+    }
+    let workflow_active = session_id == DEFAULT_SESSION_ID
+        && git_state.auto_initiated
+        && !git_state.workflow_finished;
+    if workflow_active {
+        git_state.workflow_finished = true;
+        finish_workflow(
+            git_state,
+            None,
+            Some(format!("{}: {}", ErrorCode::ChildUnavailable, error_msg)),
+        );
+    }
+}
+
+/// Tell subscribers a child failed. `state` is updated with the buffered event; the
+/// rest of the handler's state update, if any, comes from the fallback.
+fn publish_tool_activity(
+    git_state: &mut GitChatState,
+    tool: &str,
+    phase: ToolPhase,
+    ok: Option<bool>,
+    chat_state_actor_id: Option<&str>,
+) {
+    let session_id = match chat_state_actor_id {
+        Some(chat_actor_id) => git_state.session_for_chat_actor(chat_actor_id),
+        None => Some(DEFAULT_SESSION_ID.to_string()),
+    };
+    publish(
+        git_state,
+        &GitChatEvent::ToolActivity {
+            session_id,
+            tool: tool.to_string(),
+            phase,
+            ok,
+        },
+    );
+}
+
+/// Publish the text of a reply, without the scratchpad the model keeps in it.
+fn publish_assistant_output(
+    git_state: &mut GitChatState,
+    session_id: &str,
+    message_id: Option<String>,
+    reply: &Message,
+) {
+    let text = reply
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            genai_types::MessageContent::Text { text } => Some(scratchpad::strip(text)),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.trim().is_empty() {
+        return;
+    }
+    publish(
+        git_state,
+        &GitChatEvent::AssistantOutput {
+            session_id: session_id.to_string(),
+            message_id,
+            text,
+        },
+    );
+}
+
+/// Approvals the running workflow waits for: held requests, and a proposed commit or
+/// rebase plan.
+fn blocking_approvals(git_state: &GitChatState) -> Vec<String> {
+    let mut approvals: Vec<String> = git_state
+        .pending_confirmations
+        .values()
+        .map(|confirmation| format!("confirmation {}: {}", confirmation.id, confirmation.action))
+        .collect();
+    if git_state
+        .commit_plan
+        .as_ref()
+        .is_some_and(|plan| !plan.approved)
+    {
+        approvals.push("commit plan".to_string());
+    }
+    if git_state
+        .rebase
+        .as_ref()
+        .is_some_and(|rebase| rebase.status == RebaseStatus::Proposed)
+    {
+        approvals.push("rebase plan".to_string());
+    }
+    approvals
+}
+
+/// Progress of the auto-initiated workflow, or `None` when none is running.
+fn workflow_progress(git_state: &mut GitChatState) -> Option<WorkflowProgress> {
+    if !workflow_running(git_state) {
+        return None;
+    }
+    let workflow = git_state.workflow.clone()?;
+    let max_turns = git_state
+        .assistant_config
+        .max_turns
+        .as_ref()
+        .and_then(|limits| limits.limit(&workflow));
+    let files = git_state
+        .git_tools_actor_id()
+        .and_then(|git_actor_id| {
+            progress::fetch_file_counts(
+                &git_actor_id,
+                git_state.current_directory.as_deref(),
+                git_state.commit_base.as_deref(),
+            )
+        })
+        .unwrap_or_else(|e| {
+            logging::warn(&format!("Could not count the workflow's files: {}", e));
+            Default::default()
+        });
+    Some(WorkflowProgress {
+        workflow,
+        turns: git_state.workflow_turns,
+        max_turns,
+        phase: git_state.workflow_phase,
+        elapsed_secs: git_state
+            .workflow_started_at
+            .map(|started_at| clock::now().saturating_sub(started_at))
+            .unwrap_or_default(),
+        files_committed: files.committed,
+        files_staged: files.staged,
+        files_pending: files.pending,
+        blocked_on: blocking_approvals(git_state),
+    })
+}
+
+fn publish_workflow_progress(git_state: &mut GitChatState) {
+    // Counting the files takes git calls, so only when someone listens
+    if !is_published(git_state, EventKind::WorkflowProgress) {
+        return;
+    }
+    if let Some(progress) = workflow_progress(git_state) {
+        publish(git_state, &GitChatEvent::WorkflowProgress { progress });
+    }
+}
+
+/// Move the running workflow to `phase`, telling subscribers when it changed.
+fn set_workflow_phase(git_state: &mut GitChatState, phase: ProgressPhase) {
+    if !workflow_running(git_state) || git_state.workflow_phase == phase {
+        return;
+    }
+    logging::info(&format!("Workflow phase: {:?}", phase));
+    git_state.workflow_phase = phase;
+    publish_workflow_progress(git_state);
+}
+
+fn publish_child_crash(state: &mut Option<Vec<u8>>, child: &str, error: &str) {
+    if let Some(Ok(mut git_state)) = state.as_deref().map(decode_state) {
+        publish(
+            &mut git_state,
+            &GitChatEvent::ChildCrashed {
+                child_id: child.to_string(),
+                error: logging::sanitize_text(error),
+            },
+        );
+        match encode_state(&mut git_state) {
+            Ok(bytes) => *state = Some(bytes),
+            Err(e) => logging::error(&format!("Failed to serialize git state: {}", e)),
+        }
+    }
+}
+
+/// Answer the suggestion request `child` was working on with an error, or go on without
+/// the diff summary it was writing, when it was working on one.
+fn fail_suggestion_after(state: &mut Option<Vec<u8>>, child: &str, error: &str) {
+    if let Some(Ok(mut git_state)) = state.as_deref().map(decode_state) {
+        let outcome = Err(format!("Chat-state actor {} failed: {}", child, error));
+        if finish_one_turn(&mut git_state, child, outcome) {
+            match encode_state(&mut git_state) {
+                Ok(bytes) => *state = Some(bytes),
+                Err(e) => logging::error(&format!("Failed to serialize git state: {}", e)),
+            }
+        }
+    }
+}
+
+/// Fail the tasks delegated to `child`, a sibling spawned as a child that crashed or
+/// exited before reporting back.
+fn fail_delegations_after(state: &mut Option<Vec<u8>>, child: &str, error: &str) {
+    if let Some(Ok(mut git_state)) = state.as_deref().map(decode_state) {
+        let running: Vec<String> = git_state
+            .delegations
+            .iter()
+            .filter(|delegation| delegation.actor_id == child && delegation.status.is_open())
+            .map(|delegation| delegation.id.clone())
+            .collect();
+        if running.is_empty() {
+            return;
+        }
+        for delegation_id in running {
+            let error = format!("Sibling {} stopped: {}", child, error);
+            finish_delegation(&mut git_state, &delegation_id, None, Some(error));
+        }
+        match encode_state(&mut git_state) {
+            Ok(bytes) => *state = Some(bytes),
+            Err(e) => logging::error(&format!("Failed to serialize git state: {}", e)),
+        }
+    }
+}
+
+/// Fail the fan-out run of the child `child` after it crashed or exited. Returns whether
+/// `child` was spawned by a fan-out.
+fn fail_repo_run_after(state: &mut Option<Vec<u8>>, child: &str, error: &str) -> bool {
+    let mut git_state = match state.as_deref().map(decode_state) {
+        Some(Ok(git_state)) => git_state,
+        _ => return false,
+    };
+    let delegation_id = match git_state.fan_out.as_ref().and_then(|fan_out| {
+        fan_out
+            .runs
+            .iter()
+            .find(|run| run.actor_id.as_deref() == Some(child))
+    }) {
+        Some(run) if run.status == delegation::DelegationStatus::Running => {
+            run.delegation_id.clone()
+        }
+        Some(_) => return true,
+        None => return false,
+    };
+    let error = format!("Child {} stopped: {}", child, error);
+    finish_repo_run(&mut git_state, &delegation_id, None, Some(error));
+    match encode_state(&mut git_state) {
+        Ok(bytes) => *state = Some(bytes),
+        Err(e) => logging::error(&format!("Failed to serialize git state: {}", e)),
+    }
+    true
+}
+
+/// Make `workflow` the default session's workflow and give its chat-state actor the
+/// system prompt built for it. The transcript and the MCP servers are kept, so tools
+/// chosen for the old workflow at init stay available.
+fn switch_workflow(git_state: &mut GitChatState, workflow: Workflow) -> Result<(), String> {
+    logging::info(&format!("Switching to the {} workflow", workflow.name()));
+    if let Some(source) = git_state.delegated_by.take() {
+        let error = format!("Switched to the {} workflow instead", workflow.name());
+        report_delegation(&source, None, Some(error));
+    }
+    let mut config = git_state.assistant_config.clone();
+    config.workflow = Some(workflow.clone());
+    let chat_config = create_git_optimized_config(
+        &git_state.actor_id,
+        git_state.current_directory.as_deref(),
+        &config,
+        git_state.branch_context.as_ref(),
+        git_state.ticket.as_ref(),
+        git_state.repo_info.as_ref(),
+        git_state.task_monitor_actor_id.as_deref(),
+    );
+    let system_prompt = chat_config["system_prompt"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    send_or_queue(
+        git_state,
+        DEFAULT_SESSION_ID,
+        protocol::ChatStateRequest::UpdateSystemPrompt { system_prompt },
+    )?;
+
+    // A chat-state actor spawned again after a restart or a fallback gets the new prompt
+    git_state.set_original_config(chat_config);
+    git_state.assistant_config = config;
+    git_state.workflow = Some(workflow);
+    git_state.auto_initiated = false;
+    git_state.workflow_finished = false;
+    git_state.convention_reprompts = 0;
+    git_state.workflow_turns = 0;
+    git_state.wrap_up_sent_at = None;
+    git_state.workflow_started_at = None;
+    git_state.workflow_phase = ProgressPhase::default();
+    git_state.workflow_aborted = None;
+    git_state.stash_triage = None;
+    git_state.stash_reprompted = false;
+    git_state.hook_review_files = None;
+    git_state.hook_review_reprompted = false;
+    git_state.branch_cleanup = None;
+    git_state.release = None;
+    git_state.commit_plan = None;
+    git_state.diff_summary = None;
+    stop_diff_summary_pass(git_state);
+    git_state.audit_base = git_state.audit_log.next_id();
+    git_state.review_findings = Vec::new();
+    git_state.benchmark_findings = Vec::new();
+    git_state.commit_base = None;
+    git_state.workflow_branch = None;
+    git_state.commit_trailers = Vec::new();
+    Ok(())
+}
+
+/// Make the worktree at `path` the default session's directory and give its chat-state
+/// actor the system prompt built for it. The transcript is kept.
+fn set_active_worktree(git_state: &mut GitChatState, path: &str) -> Result<Worktree, String> {
+    if workflow_running(git_state) {
+        return Err("a workflow is running in the current worktree".to_string());
+    }
+    if git_state.generations_in_flight.contains(DEFAULT_SESSION_ID) {
+        return Err("a generation is running in the default session".to_string());
+    }
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let worktrees = worktree::list(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &git_state.assistant_config.worktrees,
+    )?;
+    let mut worktree = worktrees
+        .iter()
+        .find(|worktree| worktree::normalize_path(&worktree.path) == worktree::normalize_path(path))
+        .cloned()
+        .ok_or_else(|| {
+            format!(
+                "{} is not a worktree of the repository or in `worktrees`; known: {}",
+                path,
+                worktrees
+                    .iter()
+                    .map(|worktree| worktree.path.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+    if let Some(unusable) = &worktree.unusable {
+        return Err(format!("{} can't be used: {}", worktree.path, unusable));
+    }
+    logging::info(&format!("Switching to the worktree at {}", worktree.path));
+
+    let mut config = git_state.assistant_config.clone();
+    config.current_directory = Some(worktree.path.clone());
+    // The branch, and with it the branch context, differs between worktrees
+    let branch_context = read_branch_context(&config, &mut git_state.exec_tools_actor_id);
+    let ticket = read_ticket(&config, &mut git_state.exec_tools_actor_id);
+    let chat_config = create_git_optimized_config(
+        &git_state.actor_id,
+        config.current_directory.as_deref(),
+        &config,
+        branch_context.as_ref(),
+        ticket.as_ref(),
+        None,
+        git_state.task_monitor_actor_id.as_deref(),
+    );
+    let system_prompt = chat_config["system_prompt"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    send_or_queue(
+        git_state,
+        DEFAULT_SESSION_ID,
+        protocol::ChatStateRequest::UpdateSystemPrompt { system_prompt },
+    )?;
+
+    // A chat-state actor spawned again after a restart or a fallback gets the new prompt
+    git_state.set_original_config(chat_config);
+    git_state.current_directory = config.current_directory.clone();
+    git_state.assistant_config = config;
+    git_state.branch_context = branch_context;
+    git_state.ticket = ticket;
+    // What discovery found describes the checkout the assistant started in
+    git_state.repo_info = None;
+    git_state.repo_stats = None;
+    worktree.active = true;
+    Ok(worktree)
+}
+
+/// Store of session snapshots: the configured one, or one created on first use.
+fn session_store_id(git_state: &mut GitChatState) -> Result<String, String> {
+    let configured = git_state.assistant_config.session_store_id.clone();
+    if let Some(store_id) = configured.or_else(|| git_state.session_store_id.clone()) {
+        return Ok(store_id);
+    }
+    let store_id = session_store::create_store()?;
+    logging::info(&format!("Created session store {}", store_id));
+    git_state.session_store_id = Some(store_id.clone());
+    Ok(store_id)
+}
+
+/// Save the default session to the session store under `label`.
+fn save_session(git_state: &mut GitChatState, label: &str) -> Result<SnapshotInfo, String> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(format!("{}: the label is empty", ErrorCode::InvalidRequest));
+    }
+    let chat_actor_id = git_state.chat_actor_for_session(None)?;
+    let transcript = fetch_history(&chat_actor_id, None, None)?;
+    let config = serde_json::to_value(&git_state.assistant_config)
+        .map_err(|e| format!("Failed to serialize the config: {}", e))?;
+    let workflow = SavedWorkflow {
+        workflow: git_state.workflow.clone(),
+        args: git_state.workflow_args.clone(),
+        auto_initiated: git_state.auto_initiated,
+        finished: git_state.workflow_finished,
+        turns: git_state.workflow_turns,
+        phase: git_state.workflow_phase,
+        commit_base: git_state.commit_base.clone(),
+        branch: git_state.workflow_branch.clone(),
+        audit_base: git_state.audit_base,
+    };
+    let snapshot = SessionSnapshot::new(
+        label.to_string(),
+        clock::now(),
+        git_state.current_directory.clone(),
+        config,
+        transcript,
+        workflow,
+        git_state.audit_log.clone(),
+    );
+    let store_id = session_store_id(git_state)?;
+    let id = session_store::save(&store_id, &snapshot)?;
+    logging::info(&format!("Saved the session as {} ({})", label, id));
+    Ok(snapshot.info(id, store_id))
+}
+
+/// Replace the default session with snapshot `id`: its config, with the credentials the
+/// assistant has now, and its workflow and audit log. The transcript is replayed into a
+/// new chat-state actor, which takes the place of the current one.
+fn restore_session(git_state: &mut GitChatState, id: &str) -> Result<SnapshotInfo, String> {
+    if workflow_running(git_state) {
+        return Err(format!("{}: a workflow is running", ErrorCode::Busy));
+    }
+    if git_state.generations_in_flight.contains(DEFAULT_SESSION_ID) {
+        return Err(format!(
+            "{}: a generation is running in the default session",
+            ErrorCode::Busy
+        ));
+    }
+    let store_id = session_store_id(git_state)?;
+    let (hash, snapshot) = session_store::load(&store_id, id)?;
+    let current = serde_json::to_value(&git_state.assistant_config)
+        .map_err(|e| format!("Failed to serialize the config: {}", e))?;
+    let mut config = snapshot.config.clone();
+    session_store::restore_credentials(&mut config, &current);
+    let config: GitAssistantConfig = serde_json::from_value(config)
+        .map_err(|e| format!("the config of snapshot {} doesn't parse: {}", id, e))?;
+    logging::info(&format!(
+        "Restoring the session {} ({})",
+        snapshot.label, hash
+    ));
+
+    let branch_context = read_branch_context(&config, &mut git_state.exec_tools_actor_id);
+    let ticket = read_ticket(&config, &mut git_state.exec_tools_actor_id);
+    let chat_config = create_git_optimized_config(
+        &git_state.actor_id,
+        snapshot.current_directory.as_deref(),
+        &config,
+        branch_context.as_ref(),
+        ticket.as_ref(),
+        None,
+        git_state.task_monitor_actor_id.as_deref(),
+    );
+    let chat_actor_id = spawn_chat_state_actor(&chat_config)?;
+    for entry in &snapshot.transcript {
+        send_to_chat_state(
+            &chat_actor_id,
+            &protocol::ChatStateRequest::AddMessage {
+                message: entry.message.clone(),
+                provenance: entry.provenance,
+            },
+        )?;
+    }
+    if let Some(old_actor_id) = git_state.chat_state_actor_id.take() {
+        if let Err(e) = stop_chat_state_actor(&old_actor_id) {
+            logging::warn(&format!("Failed to stop {}: {:?}", old_actor_id, e));
+        }
+    }
+    git_state.set_chat_state_actor_id(chat_actor_id);
+    git_state
+        .outbound_queue
+        .retain(|queued| queued.session_id != DEFAULT_SESSION_ID);
+    git_state.held_messages.remove(DEFAULT_SESSION_ID);
+    git_state.scratchpads.remove(DEFAULT_SESSION_ID);
+    git_state.last_turn = None;
+
+    // A chat-state actor spawned again after a restart or a fallback gets the new prompt
+    git_state.set_original_config(chat_config);
+    git_state.current_directory = snapshot.current_directory.clone();
+    git_state.assistant_config = config;
+    git_state.branch_context = branch_context;
+    git_state.ticket = ticket;
+    git_state.repo_info = None;
+    git_state.repo_stats = None;
+
+    let saved = snapshot.workflow.clone();
+    git_state.workflow = saved.workflow;
+    git_state.workflow_args = saved.args;
+    git_state.auto_initiated = saved.auto_initiated;
+    git_state.workflow_finished = saved.finished;
+    git_state.workflow_turns = saved.turns;
+    git_state.workflow_phase = saved.phase;
+    git_state.commit_base = saved.commit_base;
+    git_state.workflow_branch = saved.branch;
+    git_state.audit_base = saved.audit_base;
+    // A restored workflow gets its time limit afresh
+    git_state.workflow_started_at = workflow_running(git_state).then(clock::now);
+    git_state.audit_log = snapshot.audit_log.clone();
+    Ok(snapshot.info(hash, store_id))
+}
+
+/// Check out only `paths`, or the scope paths when empty, and tell the model of a
+/// running workflow about the new set.
+fn set_sparse_paths(
+    git_state: &mut GitChatState,
+    paths: &[String],
+) -> Result<SparseCheckout, String> {
+    let scope = scope::normalize(&git_state.assistant_config.scope_paths);
+    let requested = if paths.is_empty() { &scope } else { paths };
+    if requested.is_empty() {
+        return Err("No paths given and no scope_paths configured".to_string());
+    }
+    let paths = sparse::prepare_paths(requested, &scope)?;
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let sparse = sparse::set_paths(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &paths,
+    )?;
+    logging::info(&format!("Sparse checkout set to {}", paths.join(", ")));
+    if workflow_running(git_state) {
+        let message = sparse::prompt_fragment(&sparse).trim().to_string();
+        if let Err(e) = forward_message(
+            git_state,
+            DEFAULT_SESSION_ID,
+            user_message(message),
+            Provenance::Supervisor,
+        ) {
+            logging::warn(&format!(
+                "Could not tell the model about the sparse paths: {}",
+                e
+            ));
+        }
+    }
+    Ok(sparse)
+}
+
+/// Apply a client's resolution of one hunk and tell the model, when a workflow is running,
+/// so it leaves that hunk alone. Returns the conflicts left.
+fn resolve_conflict(
+    git_state: &mut GitChatState,
+    path: &str,
+    hunk: usize,
+    resolution: &Resolution,
+) -> Result<Vec<ConflictedFile>, String> {
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let exec_actor_id = git_state.exec_tools_actor_id()?;
+    let file = conflicts::apply(
+        &git_actor_id,
+        &exec_actor_id,
+        git_state.current_directory.as_deref(),
+        path,
+        hunk,
+        resolution,
+    )?;
+    logging::info(&format!(
+        "Resolved conflict {} in {} with {}; {} left in the file",
+        hunk + 1,
+        path,
+        resolution.name(),
+        file.hunks.len()
+    ));
+    if workflow_running(git_state) {
+        let message = conflicts::resolved_message(&file, hunk, resolution);
+        if let Err(e) = forward_message(
+            git_state,
+            DEFAULT_SESSION_ID,
+            user_message(message),
+            Provenance::Supervisor,
+        ) {
+            logging::warn(&format!(
+                "Could not tell the model about the resolved conflict: {}",
+                e
+            ));
+        }
+    }
+    let conflicts = &mut git_state.conflicts;
+    match conflicts.iter().position(|known| known.path == path) {
+        Some(index) if file.hunks.is_empty() => {
+            conflicts.remove(index);
+        }
+        Some(index) => conflicts[index] = file,
+        None if file.hunks.is_empty() => {}
+        None => conflicts.push(file),
+    }
+    Ok(git_state.conflicts.clone())
+}
+
+/// Report the workflow's result to the notify actor and shut down with it as exit state.
+fn finish_workflow(git_state: &mut GitChatState, summary: Option<String>, error: Option<String>) {
+    let workflow = match git_state.workflow.clone() {
+        Some(workflow) => workflow,
+        None => return,
+    };
+    let mut result = build_workflow_result(git_state, workflow, summary, error);
+    if result.error.is_none() {
+        check_postconditions(git_state, &mut result);
+    }
+    if result.error.is_none() {
+        verify_completion(git_state, &mut result);
+    }
+    result.status = verification::status(result.error.as_deref(), result.verification.as_ref());
+    if let Some(started_at) = git_state.workflow_started_at {
+        git_state.metrics.record_workflow(
+            result.workflow.name(),
+            clock::now().saturating_sub(started_at),
+            result.error.is_some(),
+        );
+    }
+    logging::info(&format!("Workflow result: {:?}", result));
+    save_session_log(git_state, &result);
+    let state = if result.postcondition_failures.is_empty() {
+        WorkflowState::Finished
+    } else {
+        WorkflowState::Failed
+    };
+
+    notify(
+        git_state,
+        &GitChatNotification::WorkflowCompleted {
+            result: result.clone(),
+        },
+    );
+    publish(
+        git_state,
+        &GitChatEvent::WorkflowStateChanged {
+            workflow: Some(result.workflow.clone()),
+            state,
+        },
+    );
+
+    // The assistant that delegated the workflow may delegate more, so stay up
+    if let Some(source) = git_state.delegated_by.take() {
+        report_delegation(&source, Some(result), None);
+        logging::info("Delegated workflow finished, staying up");
+        return;
+    }
+
+    if let Some(mut run) = git_state.pipeline.take() {
+        run.results.push(result.clone());
+        let error = match (&result.error, run.next_step().cloned()) {
+            (Some(_), _) => Some(format!("The {} step failed", result.workflow.name())),
+            (None, Some(next)) => {
+                git_state.pipeline = Some(run);
+                match start_pipeline_step(git_state, next.clone()) {
+                    Ok(()) => return,
+                    Err(e) => {
+                        run = match git_state.pipeline.take() {
+                            Some(run) => run,
+                            None => return,
+                        };
+                        Some(format!("Failed to start the {} step: {}", next.name(), e))
+                    }
+                }
+            }
+            (None, None) => None,
+        };
+        let result = run.finish(error);
+        logging::info(&format!("Pipeline result: {:?}", result));
+        notify(
+            git_state,
+            &GitChatNotification::PipelineCompleted {
+                result: result.clone(),
+            },
+        );
+        logging::info("Pipeline finished, shutting down");
+        let exit_state = serde_json::to_vec(&result).ok();
+        let _ = shutdown(exit_state.as_deref());
+        return;
+    }
+
+    logging::info("Workflow finished, shutting down");
+    let exit_state = serde_json::to_vec(&result).ok();
+    let _ = shutdown(exit_state.as_deref());
+}
+
+/// Save `contents` as a session artifact when `artifacts` is configured. Failures are
+/// only logged; artifacts never hold up the work they record.
+fn save_artifact(
+    git_state: &mut GitChatState,
+    kind: ArtifactKind,
+    label: &str,
+    contents: &impl Serialize,
+) {
+    let config = match &git_state.assistant_config.artifacts {
+        Some(config) => config.clone(),
+        None => return,
+    };
+    let saved = serde_json::to_value(contents)
+        .map(|mut contents| {
+            if let Some(fields) = contents.as_object_mut() {
+                fields.insert("saved_at".to_string(), clock::now().into());
+            }
+            contents.to_string()
+        })
+        .map_err(|e| format!("Failed to serialize artifact: {}", e))
+        .and_then(|contents| {
+            let exec_actor_id = git_state.exec_tools_actor_id()?;
+            let dir =
+                artifacts::artifacts_dir(&exec_actor_id, git_state.current_directory.as_deref())?;
+            artifacts::write(&exec_actor_id, &dir, &config, kind, label, &contents)
+        });
+    match saved {
+        Ok(name) => logging::info(&format!("Saved session artifact {}", name)),
+        Err(e) => logging::warn(&format!("Could not save {} artifact: {}", kind.name(), e)),
+    }
+}
+
+/// Save the workflow result with the default session's transcript. The oldest messages
+/// are left out when the log wouldn't fit in a file.
+fn save_session_log(git_state: &mut GitChatState, result: &WorkflowResult) {
+    let max_file_bytes = match &git_state.assistant_config.artifacts {
+        Some(config) => config.max_file_bytes,
+        None => return,
+    };
+    let mut transcript = match git_state
+        .chat_actor_for_session(None)
+        .and_then(|chat_actor_id| fetch_history(&chat_actor_id, None, None))
+    {
+        Ok(messages) => messages,
+        Err(e) => {
+            logging::warn(&format!("Session log without transcript: {}", e));
+            Vec::new()
+        }
+    };
+    let mut log = serde_json::json!({"result": result, "transcript": transcript});
+    while !transcript.is_empty() && log.to_string().len() > max_file_bytes {
+        transcript.remove(0);
+        log = serde_json::json!({"result": result, "transcript": transcript});
+    }
+    save_artifact(
+        git_state,
+        ArtifactKind::SessionLog,
+        result.workflow.name(),
+        &log,
+    );
+}
+
+/// Check the workflow's postconditions, failing `result` with the ones that aren't met.
+fn check_postconditions(git_state: &mut GitChatState, result: &mut WorkflowResult) {
+    let config = &git_state.assistant_config;
+    let mut postconditions =
+        postcondition::for_workflow(&config.postconditions, &result.workflow).to_vec();
+    if result.workflow == Workflow::Commit {
+        for signing in postcondition::signing(config.require_signoff, config.gpg_sign) {
+            if !postconditions.contains(&signing) {
+                postconditions.push(signing);
+            }
+        }
+    }
+    if postconditions.is_empty() {
+        return;
+    }
+    let failures = match git_state.git_tools_actor_id() {
+        Ok(git_actor_id) => postcondition::check(
+            &postconditions,
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            git_state.commit_base.as_deref(),
+        ),
+        Err(e) => postconditions
+            .into_iter()
+            .map(|postcondition| postcondition::PostconditionFailure {
+                postcondition,
+                detail: format!("could not be checked: {}", e),
+            })
+            .collect(),
+    };
+    if failures.is_empty() {
+        logging::info("Every postcondition is met");
+        return;
+    }
+    let error_msg = postcondition::describe(&result.workflow, &failures);
+    logging::warn(&error_msg);
+    git_state.last_error = Some(error_msg.clone());
+    result.error = Some(error_msg);
+    result.postcondition_failures = failures;
+}
+
+/// Verify a workflow that changed the repository, once it completed: discrepancies are
+/// recorded in `result` as warnings, without failing it.
+fn verify_completion(git_state: &mut GitChatState, result: &mut WorkflowResult) {
+    let config = git_state
+        .assistant_config
+        .verification
+        .clone()
+        .unwrap_or_default();
+    if !config.enabled || !result.workflow.changes_repository() {
+        return;
+    }
+    let git_actor_id = match git_state.git_tools_actor_id() {
+        Ok(git_actor_id) => git_actor_id,
+        Err(e) => {
+            logging::warn(&format!("Could not verify the workflow: {}", e));
+            return;
+        }
+    };
+    let is_commit = result.workflow == Workflow::Commit;
+    let expectations = Expectations {
+        branch: git_state.workflow_branch.as_deref(),
+        commits: is_commit.then_some(result.commits_created.as_slice()),
+        plan: git_state.commit_plan.as_ref().filter(|_| is_commit),
+    };
+    let verification = verification::verify(
+        &config,
+        &expectations,
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        clock::now(),
+    );
+    let warnings = verification.warnings();
+    if warnings.is_empty() {
+        logging::info("The workflow passed verification");
+    } else {
+        logging::warn(&format!(
+            "The {} workflow completed with warnings: {}",
+            result.workflow.name(),
+            warnings.join("; ")
+        ));
+    }
+    result.verification = Some(verification);
+}
+
+/// Switch the default session to the next step of the pipeline and auto-initiate it,
+/// as `StartChat` would.
+fn start_pipeline_step(git_state: &mut GitChatState, workflow: Workflow) -> Result<(), String> {
+    logging::info(&format!("Starting pipeline step: {}", workflow.name()));
+    switch_workflow(git_state, workflow)?;
+    let response = handle_git_chat_request(
+        git_state,
+        GitChatRequest::StartChat {
+            allow_secrets: git_state.secrets_allowed,
+            allow_out_of_scope: git_state.out_of_scope_allowed,
+            workflow: None,
+            args: git_state.workflow_args.clone(),
+            suggest_only: false,
+        },
+    );
+    match response {
+        GitChatResponse::Error { message, .. } => Err(message),
+        _ => Ok(()),
+    }
+}
+
+/// Collect the commits and files a workflow produced. Git failures leave the lists empty
+/// rather than holding up the shutdown.
+fn build_workflow_result(
+    git_state: &mut GitChatState,
+    workflow: Workflow,
+    summary: Option<String>,
+    error: Option<String>,
+) -> WorkflowResult {
+    let mut result = WorkflowResult {
+        workflow,
+        commits_created: Vec::new(),
+        files_touched: Vec::new(),
+        summary,
+        error,
+        status: CompletionStatus::Completed,
+        postcondition_failures: Vec::new(),
+        audit_log: git_state.audit_log.since(git_state.audit_base),
+        review_findings: git_state.review_findings.clone(),
+        verification: None,
+    };
+    // Without a base there is no telling which commits are new
+    let base = match git_state.commit_base.clone() {
+        Some(base) => base,
+        None => return result,
+    };
+    let git_actor_id = match git_state.git_tools_actor_id() {
+        Ok(git_actor_id) => git_actor_id,
+        Err(e) => {
+            logging::warn(&format!("Could not collect workflow result: {}", e));
+            return result;
+        }
+    };
+    let directory = git_state.current_directory.as_deref();
+
+    match convention::fetch_commit_messages(&git_actor_id, directory, Some(&base)) {
+        Ok(commits) => {
+            result.commits_created = commits
+                .iter()
+                .map(|commit| CreatedCommit {
+                    sha: commit.sha.clone(),
+                    subject: commit.subject().to_string(),
+                    committed_at: commit.committed_at,
+                })
+                .collect();
+        }
+        Err(e) => logging::warn(&format!("Could not list created commits: {}", e)),
+    }
+    // Diffing against the working tree covers committed and uncommitted changes
+    match git_tools::run_git(&git_actor_id, directory, &["diff", "--name-only", &base]) {
+        Ok(files) => {
+            result.files_touched = files
+                .lines()
+                .map(str::trim)
+                .filter(|file| !file.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        Err(e) => logging::warn(&format!("Could not list touched files: {}", e)),
+    }
+    result
+}
+
+/// Findings in the pending changes that keep the commit workflow from starting. Scan
+/// failures are logged and don't block the workflow.
+fn pending_secrets(git_state: &mut GitChatState) -> Vec<SecretFinding> {
+    let config = git_state
+        .assistant_config
+        .secret_scan
+        .clone()
+        .unwrap_or_default();
+    if git_state.workflow != Some(Workflow::Commit) || !config.enabled {
+        return Vec::new();
+    }
+    let findings = SecretScanner::new(&config).and_then(|scanner| {
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        secret_scan::scan_pending_changes(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &scanner,
+        )
+    });
+    match findings {
+        Ok(findings) => findings,
+        Err(e) => {
+            logging::warn(&format!(
+                "Could not scan the pending changes for secrets: {}",
+                e
+            ));
+            Vec::new()
+        }
+    }
+}
+
+/// Scan `diff`, or the uncommitted changes when there is none.
+fn scan_for_secrets(
+    git_state: &mut GitChatState,
+    diff: Option<&str>,
+) -> Result<Vec<SecretFinding>, String> {
+    let config = git_state
+        .assistant_config
+        .secret_scan
+        .clone()
+        .unwrap_or_default();
+    let scanner = SecretScanner::new(&config)?;
+    match diff {
+        Some(diff) => Ok(scanner.scan_diff(diff)),
+        None => {
+            let git_actor_id = git_state.git_tools_actor_id()?;
+            secret_scan::scan_pending_changes(
+                &git_actor_id,
+                git_state.current_directory.as_deref(),
+                &scanner,
+            )
+        }
+    }
+}
+
+/// Check the commits made during a commit workflow against the configured convention
+/// and spellcheck. Returns `Ok(true)` when the model was asked to revisit the messages
+/// and the session should keep running.
+fn check_new_commits(git_state: &mut GitChatState) -> Result<bool, String> {
+    if git_state.workflow != Some(Workflow::Commit) {
+        return Ok(false);
+    }
+    let commit_convention = git_state.assistant_config.commit_convention.clone();
+    let spellcheck = git_state.assistant_config.spellcheck.clone();
+    let commit_size = git_state.assistant_config.commit_size.clone();
+
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.clone();
+    let commits = convention::fetch_commit_messages(
+        &git_actor_id,
+        directory.as_deref(),
+        git_state.commit_base.as_deref(),
+    )?;
+
+    let mut violations = commit_convention
+        .map(|commit_convention| {
+            convention::check_commits(&commits, &commit_convention, &git_state.commit_trailers)
+        })
+        .unwrap_or_default();
+    let commit_language = git_state
+        .assistant_config
+        .language
+        .as_ref()
+        .and_then(LanguageConfig::commits);
+    let template = git_state.assistant_config.commit_template.clone();
+    if template.is_some() || commit_language.is_some() {
+        let values = template_values(git_state);
+        for commit in &commits {
+            let mut problems = template
+                .as_deref()
+                .map(|template| commit_template::check(template, &values, &commit.message))
+                .unwrap_or_default();
+            problems
+                .extend(commit_language.and_then(|locale| locale::check(&commit.message, locale)));
+            if problems.is_empty() {
+                continue;
+            }
+            match violations
+                .iter_mut()
+                .find(|violation| violation.sha == commit.sha)
+            {
+                Some(violation) => violation.problems.extend(problems),
+                None => violations.push(ConventionViolation {
+                    sha: commit.sha.clone(),
+                    subject: commit.subject().to_string(),
+                    problems,
+                }),
+            }
+        }
+    }
+
+    // Hidden characters can smuggle instructions or spoof what a reviewer sees
+    let hidden: Vec<_> = commits
+        .iter()
+        .filter_map(|commit| {
+            let found = sanitize::find_hidden(&commit.message, sanitize::TextKind::Message);
+            (!found.is_empty()).then(|| (commit, sanitize::describe(&found)))
+        })
+        .collect();
+
+    let mut oversized = Vec::new();
+    if let Some(commit_size) = commit_size.filter(CommitSizeLimits::is_enforced) {
+        for (sha, size) in commit_size::fetch_commit_sizes(
+            &git_actor_id,
+            directory.as_deref(),
+            git_state.commit_base.as_deref(),
+        )? {
+            let exceeded = commit_size.exceeded(&size);
+            if !exceeded.is_empty() {
+                oversized.push((sha, exceeded));
+            }
+        }
+    }
+
+    let secret_scan = git_state
+        .assistant_config
+        .secret_scan
+        .clone()
+        .unwrap_or_default();
+    let mut secrets = Vec::new();
+    if let (true, false, Some(base)) = (
+        secret_scan.enabled,
+        git_state.secrets_allowed,
+        git_state.commit_base.as_deref(),
+    ) {
+        let scanner = SecretScanner::new(&secret_scan)?;
+        secrets = secret_scan::scan_commits(&git_actor_id, directory.as_deref(), base, &scanner)?;
+    }
+
+    let scope_paths = scope::normalize(&git_state.assistant_config.scope_paths);
+    let mut out_of_scope = Vec::new();
+    if let (false, false, Some(base)) = (
+        scope_paths.is_empty(),
+        git_state.out_of_scope_allowed,
+        git_state.commit_base.as_deref(),
+    ) {
+        out_of_scope =
+            scope::files_outside(&git_actor_id, directory.as_deref(), base, &scope_paths)?;
+    }
+
+    let mut excluded_bumps = Vec::new();
+    if let (false, Some(base)) = (
+        submodule_bumps_allowed(git_state),
+        git_state.commit_base.as_deref(),
+    ) {
+        excluded_bumps = submodule::pointers_moved(&git_actor_id, directory.as_deref(), base)?;
+    }
+
+    let file_policy = git_state
+        .assistant_config
+        .large_files
+        .clone()
+        .filter(LargeFilePolicy::is_enforced);
+    let mut refused_files = Vec::new();
+    if let (Some(policy), Some(base)) = (file_policy, git_state.commit_base.as_deref()) {
+        let files = large_files::fetch_committed(&git_actor_id, directory.as_deref(), base)?;
+        refused_files = policy.violations(&files);
+    }
+    if !refused_files.is_empty() {
+        notify(
+            git_state,
+            &GitChatNotification::FilePolicyViolated {
+                violations: refused_files.clone(),
+                committed: true,
+            },
+        );
+    }
+
+    let mut misspellings = Vec::new();
+    if let Some(spellcheck) = spellcheck {
+        let checker =
+            spelling::Spellchecker::load(&git_actor_id, directory.as_deref(), &spellcheck);
+        for commit in &commits {
+            for issue in checker.check(&commit.message) {
+                let key = format!("{}:{}", commit.sha, issue.word.to_lowercase());
+                if git_state.offered_spelling.insert(key) {
+                    misspellings.push((commit.short_sha().to_string(), issue));
+                }
+            }
+        }
+    }
+
+    if violations.is_empty()
+        && hidden.is_empty()
+        && oversized.is_empty()
+        && secrets.is_empty()
+        && out_of_scope.is_empty()
+        && excluded_bumps.is_empty()
+        && refused_files.is_empty()
+        && misspellings.is_empty()
+    {
+        logging::info("All commit messages pass the configured checks");
+        return Ok(false);
+    }
+    logging::info(&format!(
+        "Commit checks: {} convention violations, {} with hidden characters, {} oversized, {} possible secrets, {} files out of scope, {} submodule pointer bumps left out by policy, {} files refused by the large file policy, {} possible misspellings",
+        violations.len(),
+        hidden.len(),
+        oversized.len(),
+        secrets.len(),
+        out_of_scope.len(),
+        excluded_bumps.len(),
+        refused_files.len(),
+        misspellings.len()
+    ));
+
+    if git_state.convention_reprompts >= MAX_CONVENTION_REPROMPTS {
+        if violations.is_empty()
+            && hidden.is_empty()
+            && oversized.is_empty()
+            && secrets.is_empty()
+            && out_of_scope.is_empty()
+            && excluded_bumps.is_empty()
+            && refused_files.is_empty()
+        {
+            // Spelling suggestions are advisory and never fail the workflow
+            return Ok(false);
+        }
+        if !secrets.is_empty() {
+            return Err(format!(
+                "{}: after {} attempts, the commits still contain possible credentials:\n{}",
+                ErrorCode::SecretDetected,
+                MAX_CONVENTION_REPROMPTS,
+                secret_scan::describe(&secrets)
+            ));
+        }
+        if !out_of_scope.is_empty() {
+            return Err(format!(
+                "{}: after {} attempts, the commits still touch files outside {}: {}",
+                ErrorCode::OutOfScope,
+                MAX_CONVENTION_REPROMPTS,
+                scope_paths.join(", "),
+                out_of_scope.join(", ")
+            ));
+        }
+        if !excluded_bumps.is_empty() {
+            return Err(format!(
+                "{}: after {} attempts, the commits still move the pointers of submodules {}",
+                ErrorCode::SubmodulePointer,
+                MAX_CONVENTION_REPROMPTS,
+                excluded_bumps.join(", ")
+            ));
+        }
+        if !refused_files.is_empty() {
+            return Err(format!(
+                "{}: after {} attempts, the commits still add files the large_files policy refuses: {}",
+                ErrorCode::FilePolicy,
+                MAX_CONVENTION_REPROMPTS,
+                refused_files
+                    .iter()
+                    .map(FileViolation::describe)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ));
+        }
+        return Err(format!(
+            "After {} attempts, {} commits still violate the commit convention, {} contain hidden characters, and {} are over the size limits",
+            MAX_CONVENTION_REPROMPTS,
+            violations.len(),
+            hidden.len(),
+            oversized.len()
+        ));
+    }
+
+    let mut reprompt = String::new();
+    if !violations.is_empty() {
+        reprompt.push_str("These commits do not follow the commit message convention:");
+        for violation in &violations {
+            reprompt.push_str(&format!(
+                "\n- {} \"{}\": {}",
+                &violation.sha[..violation.sha.len().min(12)],
+                violation.subject,
+                violation.problems.join("; ")
+            ));
+        }
+        reprompt.push_str(
+            "\nReword them so they comply (git commit --amend for the latest commit, a rebase \
+            with reword for earlier ones).",
+        );
+    }
+    if !hidden.is_empty() {
+        if !reprompt.is_empty() {
+            reprompt.push_str("\n\n");
+        }
+        reprompt.push_str("These commit messages contain hidden or reordering characters:");
+        for (commit, description) in &hidden {
+            reprompt.push_str(&format!(
+                "\n- {}: {}. Visible subject: \"{}\"",
+                commit.short_sha(),
+                description,
+                sanitize::strip(commit.subject(), sanitize::TextKind::Message)
+            ));
+        }
+        reprompt.push_str("\nReword them using only the visible text.");
+    }
+    if !oversized.is_empty() {
+        if !reprompt.is_empty() {
+            reprompt.push_str("\n\n");
+        }
+        reprompt.push_str("These commits are over the commit size limits:");
+        for (sha, exceeded) in &oversized {
+            reprompt.push_str(&format!(
+                "\n- {}: {}",
+                &sha[..sha.len().min(12)],
+                exceeded.join(", ")
+            ));
+        }
+        reprompt.push_str(
+            "\nSplit them into smaller logical commits (for example git reset --soft to the \
+            commit before, then stage and commit the parts separately).",
+        );
+    }
+    if !secrets.is_empty() {
+        if !reprompt.is_empty() {
+            reprompt.push_str("\n\n");
+        }
+        reprompt.push_str("These commits look like they contain credentials:\n");
+        reprompt.push_str(&secret_scan::describe(&secrets));
+        reprompt.push_str(
+            "\nRemove them from the commits (amend or rebase so no commit contains them, and \
+            add the files to .gitignore where it fits), then tell me which credentials to rotate.",
+        );
+    }
+    if !out_of_scope.is_empty() {
+        if !reprompt.is_empty() {
+            reprompt.push_str("\n\n");
+        }
+        reprompt.push_str(&format!(
+            "These files are outside the scope ({}) but were changed by the commits:",
+            scope_paths.join(", ")
+        ));
+        for file in &out_of_scope {
+            reprompt.push_str(&format!("\n- {}", file));
+        }
+        reprompt.push_str(
+            "\nTake their changes out of the commits (rebase or reset --soft and commit again) \
+            and leave them in the working tree.",
+        );
+    }
+    if !excluded_bumps.is_empty() {
+        if !reprompt.is_empty() {
+            reprompt.push_str("\n\n");
+        }
+        reprompt.push_str("These submodule pointer bumps were committed, but aren't allowed:");
+        for path in &excluded_bumps {
+            reprompt.push_str(&format!("\n- {}", path));
+        }
+        reprompt.push_str(
+            "\nTake them out of the commits (rebase or reset --soft and commit again) and \
+            leave them in the working tree.",
+        );
+    }
+    if !refused_files.is_empty() {
+        if !reprompt.is_empty() {
+            reprompt.push_str("\n\n");
+        }
+        reprompt.push_str("These committed files break the binary and large file rules:");
+        for violation in &refused_files {
+            reprompt.push_str(&format!("\n- {}", violation.describe()));
+        }
+        reprompt.push_str(
+            "\nTake them out of the commits (rebase or reset --soft and commit again) and \
+            leave them in the working tree, or stop and ask me when they must be committed.",
+        );
+    }
+    if !misspellings.is_empty() {
+        if !reprompt.is_empty() {
+            reprompt.push_str("\n\n");
+        }
+        reprompt.push_str("Possible misspellings in the commit messages:");
+        for (sha, issue) in &misspellings {
+            reprompt.push_str(&format!(
+                "\n- {} line {}: \"{}\" -> \"{}\"",
+                sha, issue.line, issue.word, issue.suggestion
+            ));
+        }
+        reprompt.push_str(
+            "\nThese are suggestions only: show them to me and reword only the ones I accept.",
+        );
+    }
+    reprompt.push_str("\nCall task_complete again when you are done.");
+
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(reprompt),
+        Provenance::Supervisor,
+    )?;
+    git_state.convention_reprompts += 1;
+    Ok(true)
+}
+
+/// Check that every stash listed when the stash-triage workflow started was applied,
+/// dropped, or named as kept in the summary. The model is asked once more about the
+/// rest; after that, they fail the workflow.
+fn check_stash_triage(git_state: &mut GitChatState, summary: Option<&str>) -> Result<bool, String> {
+    let listed = match (&git_state.workflow, &git_state.stash_triage) {
+        (Some(Workflow::StashTriage), Some(listed)) => listed.clone(),
+        _ => return Ok(false),
+    };
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let current = stash::fetch_stashes(&git_actor_id, git_state.current_directory.as_deref())?;
+    let undecided = stash::undecided(&listed, &current, summary);
+    if undecided.is_empty() {
+        logging::info("Every stash has a decision");
+        return Ok(false);
+    }
+    let list = undecided
+        .iter()
+        .map(|stash| format!("{} \"{}\"", stash.short_sha(), stash.message))
+        .collect::<Vec<_>>()
+        .join(", ");
+    logging::info(&format!("Stashes without a decision: {}", list));
+
+    if git_state.stash_reprompted {
+        return Err(format!(
+            "{} stashes were left without a decision: {}",
+            undecided.len(),
+            list
+        ));
+    }
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(format!(
+            "These stashes are still there, but your summary doesn't say they are kept: {}.\n\
+            Decide on each of them with me, then call task_complete again and name the short \
+            sha of every stash we keep.",
+            list
+        )),
+        Provenance::Supervisor,
+    )?;
+    git_state.stash_reprompted = true;
+    Ok(true)
+}
+
+/// Check that the hook-review workflow staged its fixes to the files that were staged.
+/// The model is asked once to stage the rest; after that, they fail the workflow.
+fn check_hook_review(git_state: &mut GitChatState) -> Result<bool, String> {
+    let staged = match (&git_state.workflow, &git_state.hook_review_files) {
+        (Some(Workflow::HookReview), Some(staged)) => staged.clone(),
+        _ => return Ok(false),
+    };
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let unstaged = hook_review::unstaged_fixes(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &staged,
+    )?;
+    if unstaged.is_empty() {
+        logging::info("Every fix to the staged files is staged");
+        return Ok(false);
+    }
+    logging::info(&format!("Unstaged fixes: {}", unstaged.join(", ")));
+
+    if git_state.hook_review_reprompted {
+        return Err(format!(
+            "{} files have fixes that aren't staged: {}",
+            unstaged.len(),
+            unstaged.join(", ")
+        ));
+    }
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(format!(
+            "These files were staged, but some of their changes aren't: {}.\n\
+            Stage them with git add -- <file> if they are your fixes, or tell me why not, \
+            then call task_complete again.",
+            unstaged.join(", ")
+        )),
+        Provenance::Supervisor,
+    )?;
+    git_state.hook_review_reprompted = true;
+    Ok(true)
+}
+
+/// Public-API diff block for a Rust review, when an API diff is configured.
+fn fetch_api_diff_context(git_state: &mut GitChatState) -> Option<String> {
+    let api_diff_config = git_state
+        .assistant_config
+        .review
+        .as_ref()
+        .and_then(|review| review.api_diff.clone())?;
+    let api_diff = git_state.exec_tools_actor_id().and_then(|exec_actor_id| {
+        api_diff::fetch_api_diff(
+            &exec_actor_id,
+            git_state.current_directory.as_deref(),
+            &api_diff_config,
+        )
+    });
+    match api_diff {
+        Ok(api_diff) => {
+            logging::info(&format!(
+                "Public API diff requires a {:?} bump: {:?}",
+                api_diff.required_bump(),
+                api_diff
+            ));
+            Some(api_diff.render())
+        }
+        Err(e) => {
+            // Not every Rust change is in a library; the review goes on without the diff
+            logging::warn(&format!("Could not diff the public API: {}", e));
+            None
+        }
+    }
+}
+
+fn user_message(text: String) -> Message {
+    Message {
+        role: genai_types::messages::Role::User,
+        content: vec![genai_types::MessageContent::Text { text }],
+    }
+}
+
+/// Spawn an additional chat-state actor for another directory or workflow.
+fn create_session(
+    git_state: &mut GitChatState,
+    session_id: Option<String>,
+    current_directory: Option<String>,
+    workflow: Option<Workflow>,
+) -> Result<SessionInfo, String> {
+    let session_id = match session_id {
+        Some(id) => id,
+        None => {
+            let id = format!("session-{}", git_state.next_session_number);
+            git_state.next_session_number += 1;
+            id
+        }
+    };
+    if session_id == DEFAULT_SESSION_ID || git_state.sessions.contains_key(&session_id) {
+        return Err(format!("Session already exists: {}", session_id));
+    }
+
+    // Sessions inherit the init config, overriding directory and workflow
+    let mut config = git_state.assistant_config.clone();
+    if current_directory.is_some() {
+        config.current_directory = current_directory;
+    }
+    if workflow.is_some() {
+        config.workflow = workflow;
+    }
+    // Branch context and the ticket are only read for the init directory
+    let ticket = config.ticket.as_ref().and_then(TicketConfig::configured);
+    let mut chat_config = create_git_optimized_config(
+        &git_state.actor_id,
+        config.current_directory.as_deref(),
+        &config,
+        None,
+        ticket.as_ref(),
+        None,
+        git_state.task_monitor_actor_id.as_deref(),
+    );
+    // New sessions start on the model the budget has downgraded to
+    let downgrade = config
+        .budget
+        .as_ref()
+        .and_then(|budget| budget.downgrade_model(git_state.downgrade_level));
+    if let Some(model) = downgrade {
+        chat_config["model_config"] = serde_json::to_value(model)
+            .map_err(|e| format!("Failed to serialize model config: {}", e))?;
+    }
+    let chat_state_actor_id = spawn_chat_state_actor(&chat_config)?;
+    if let Some(model) = downgrade {
+        git_state
+            .session_models
+            .insert(session_id.clone(), model.model.clone());
+        git_state
+            .session_downgrades
+            .insert(session_id.clone(), git_state.downgrade_level);
+    }
+
+    let session = SessionInfo {
+        session_id: session_id.clone(),
+        chat_state_actor_id,
+        current_directory: config.current_directory,
+        workflow: config.workflow,
+    };
+    git_state.sessions.insert(session_id, session.clone());
+    Ok(session)
+}
+
+fn send_to_chat_state(
+    chat_actor_id: &str,
+    request: &protocol::ChatStateRequest,
+) -> Result<(), String> {
+    if simulated::is_simulated(chat_actor_id) {
+        return simulated::handle(chat_actor_id, request).map(|_| ());
+    }
+    let request_bytes =
+        to_vec(request).map_err(|e| format!("Failed to serialize chat-state request: {}", e))?;
+    send(chat_actor_id, &request_bytes)
+        .map_err(|e| format!("Failed to send to chat-state actor: {:?}", e))
+}
+
+/// Send `request` to a session's chat-state actor. When the send fails, or earlier
+/// requests of the session are still queued, the request is queued for a retry
+/// instead of failing, so it isn't lost or sent out of order.
+fn send_or_queue(
+    git_state: &mut GitChatState,
+    session_id: &str,
+    request: protocol::ChatStateRequest,
+) -> Result<(), String> {
+    let chat_actor_id = git_state.chat_actor_for_session(Some(session_id))?;
+    let sent = if git_state
+        .outbound_queue
+        .iter()
+        .any(|queued| queued.session_id == session_id)
+    {
+        Err("earlier requests of the session are still queued".to_string())
+    } else {
+        send_to_chat_state(&chat_actor_id, &request)
+    };
+    if let Err(e) = sent {
+        logging::warn(&format!(
+            "Queued a request to session {} for a retry: {}",
+            session_id, e
+        ));
+        let queued = QueuedSend {
+            session_id: session_id.to_string(),
+            request,
+            attempts: 1,
+            last_error: e,
+            retry_at: Some(clock::now() + git_state.retry_delay(1)),
+        };
+        if let Some(dropped) = outbound::enqueue(&mut git_state.outbound_queue, queued) {
+            logging::warn(&format!(
+                "Outbound queue full, dropped a request to session {}",
+                dropped.session_id
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Retry the queued requests in order and return how many were sent. Once a request
+/// of a session fails again, or isn't due yet, the rest of that session's requests
+/// wait for a later flush. Retries back off as `retry_backoff` sets, unless `force`
+/// sends every request now. Requests are given up on after `MAX_SEND_ATTEMPTS`.
+fn flush_outbound_queue(git_state: &mut GitChatState, force: bool) -> usize {
+    if git_state.outbound_queue.is_empty() || git_state.halt.is_some() {
+        return 0;
+    }
+    let now = clock::now();
+    let mut delivered = 0;
+    let mut waiting = BTreeSet::new();
+    let mut remaining = VecDeque::new();
+    for mut queued in std::mem::take(&mut git_state.outbound_queue) {
+        let due = force || queued.retry_at.is_none_or(|retry_at| retry_at <= now);
+        if waiting.contains(&queued.session_id) || !due {
+            waiting.insert(queued.session_id.clone());
+            remaining.push_back(queued);
+            continue;
+        }
+        let sent = git_state
+            .chat_actor_for_session(Some(&queued.session_id))
+            .and_then(|chat_actor_id| send_to_chat_state(&chat_actor_id, &queued.request));
+        match sent {
+            Ok(()) => delivered += 1,
+            Err(e) => {
+                queued.attempts += 1;
+                queued.last_error = e;
+                queued.retry_at = Some(now + git_state.retry_delay(queued.attempts));
+                waiting.insert(queued.session_id.clone());
+                if queued.attempts >= outbound::MAX_SEND_ATTEMPTS {
+                    let error_msg = format!(
+                        "Gave up sending to session {} after {} attempts: {}",
+                        queued.session_id, queued.attempts, queued.last_error
+                    );
+                    logging::error(&error_msg);
+                    git_state.last_error = Some(error_msg);
+                } else {
+                    remaining.push_back(queued);
+                }
+            }
+        }
+    }
+    if delivered > 0 {
+        logging::info(&format!(
+            "Sent {} queued requests, {} still queued",
+            delivered,
+            remaining.len()
+        ));
+    }
+    git_state.outbound_queue = remaining;
+    delivered
+}
+
+/// Send `request` to a chat-state actor and wait for its response.
+fn request_from_chat_state(
+    chat_actor_id: &str,
+    chat_request: &protocol::ChatStateRequest,
+) -> Result<protocol::ChatStateResponse, String> {
+    if simulated::is_simulated(chat_actor_id) {
+        return simulated::handle(chat_actor_id, chat_request);
+    }
+    let request_bytes = to_vec(chat_request)
+        .map_err(|e| format!("Failed to serialize chat-state request: {}", e))?;
+    let response_bytes = request(chat_actor_id, &request_bytes)
+        .map_err(|e| format!("Chat-state request failed: {}", e))?;
+    from_slice(&response_bytes).map_err(|e| format!("Failed to parse chat-state response: {}", e))
+}
+
+/// Ping the default session's chat-state actor and record whether it answered. Any
+/// answer counts, so actors that don't know `ping` yet still pass; a failed request,
+/// including one the runtime timed out, marks the actor unhealthy.
+fn probe_chat_state(git_state: &mut GitChatState) -> bool {
+    let probe = git_state
+        .chat_state_actor_id
+        .clone()
+        .ok_or_else(|| "no chat-state actor is running".to_string())
+        .and_then(|chat_actor_id| {
+            request_from_chat_state(&chat_actor_id, &protocol::ChatStateRequest::Ping)
+        });
+    let alive = match probe {
+        Ok(_) => true,
+        Err(e) => {
+            logging::warn(&format!("Chat-state actor failed its health check: {}", e));
+            git_state.last_error = Some(format!("Chat-state health check failed: {}", e));
+            false
+        }
+    };
+    if git_state.child_alive == Some(true) && !alive {
+        logging::error("The chat-state actor stopped answering");
+    }
+    git_state.child_alive = Some(alive);
+    alive
+}
+
+/// Turn a chat-state response into the data `expected` names, failing on errors and on
+/// responses meant for another request.
+fn expect_chat_state_response<T>(
+    response: protocol::ChatStateResponse,
+    expected: &str,
+    extract: impl FnOnce(protocol::ChatStateResponse) -> Option<T>,
+) -> Result<T, String> {
+    if let protocol::ChatStateResponse::Error { error } = &response {
+        return Err(format!(
+            "Chat-state actor failed: {} ({})",
+            error.message, error.code
+        ));
+    }
+    let description = format!("{:?}", response);
+    extract(response).ok_or_else(|| {
+        format!(
+            "Expected {} from the chat-state actor, got {}",
+            expected, description
+        )
+    })
+}
+
+/// The latest `limit` messages of a chat-state actor's transcript, before message
+/// `before` when set.
+fn fetch_history(
+    chat_actor_id: &str,
+    limit: Option<usize>,
+    before: Option<String>,
+) -> Result<Vec<protocol::HistoryMessage>, String> {
+    let response = request_from_chat_state(
+        chat_actor_id,
+        &protocol::ChatStateRequest::GetHistory { limit, before },
+    )?;
+    expect_chat_state_response(response, "history", |response| match response {
+        protocol::ChatStateResponse::History { messages } => Some(messages),
+        _ => None,
+    })
+}
+
+/// Id of the latest message of a chat-state actor's transcript.
+fn fetch_head(chat_actor_id: &str) -> Result<Option<String>, String> {
+    let response = request_from_chat_state(chat_actor_id, &protocol::ChatStateRequest::GetHead)?;
+    expect_chat_state_response(response, "the head", |response| match response {
+        protocol::ChatStateResponse::Head { head } => Some(head),
+        _ => None,
+    })
+}
+
+fn fetch_message(
+    chat_actor_id: &str,
+    message_id: &str,
+) -> Result<protocol::HistoryMessage, String> {
+    let response = request_from_chat_state(
+        chat_actor_id,
+        &protocol::ChatStateRequest::GetMessage {
+            message_id: message_id.to_string(),
+        },
+    )?;
+    expect_chat_state_response(response, "a message", |response| match response {
+        protocol::ChatStateResponse::ChatMessage { message } => Some(message),
+        _ => None,
+    })
+}
+
+/// Stop a session's chat-state actor.
+fn stop_chat_state_actor(chat_actor_id: &str) -> Result<(), String> {
+    if simulated::is_simulated(chat_actor_id) {
+        return simulated::stop(chat_actor_id);
+    }
+    stop_child(chat_actor_id).map_err(|e| format!("{:?}", e))
+}
+
+/// Chat config a session's chat-state actor was spawned with.
+fn chat_config_for_session(git_state: &GitChatState, session_id: &str) -> Value {
+    let session = match git_state.sessions.get(session_id) {
+        Some(session) => session,
+        None => return git_state.original_config.clone(),
+    };
+    let mut config = git_state.assistant_config.clone();
+    config.current_directory = session.current_directory.clone();
+    config.workflow = session.workflow.clone();
+    let ticket = config.ticket.as_ref().and_then(TicketConfig::configured);
+    create_git_optimized_config(
+        &git_state.actor_id,
+        config.current_directory.as_deref(),
+        &config,
+        None,
+        ticket.as_ref(),
+        None,
+        git_state.task_monitor_actor_id.as_deref(),
+    )
+}
+
+/// Replace a session's chat-state actor after a provider error with one using the next
+/// model in `model_fallbacks`, replay the conversation, and retry the generation.
+/// Returns `false` when the error isn't a provider error or no fallback is left.
+fn fall_back_to_next_model(
+    git_state: &mut GitChatState,
+    session_id: &str,
+    failed_actor_id: &str,
+    error: &str,
+) -> Result<bool, String> {
+    if !model_fallback::is_provider_error(error)
+        || git_state.halt.is_some()
+        || git_state.budget_exceeded
+    {
+        return Ok(false);
+    }
+    let used = git_state
+        .fallbacks_used
+        .get(session_id)
+        .copied()
+        .unwrap_or(0);
+    let fallback = match git_state.assistant_config.model_fallbacks.get(used) {
+        Some(fallback) => fallback.clone(),
+        None => {
+            logging::warn(&format!(
+                "No model fallbacks left for session {}",
+                session_id
+            ));
+            return Ok(false);
+        }
+    };
+    logging::info(&format!(
+        "Falling back to {} ({}) in session {}",
+        fallback.model, fallback.provider, session_id
+    ));
+
+    let chat_actor_id = switch_session_model(git_state, session_id, failed_actor_id, &fallback)?;
+    git_state.metrics.count_restart("chat-state");
+    // The replacement runs on the fallback model, not on the overrides
+    git_state.overridden_generations.remove(session_id);
+    git_state
+        .fallbacks_used
+        .insert(session_id.to_string(), used + 1);
+    send_to_chat_state(
+        &chat_actor_id,
+        &protocol::ChatStateRequest::GenerateCompletion,
+    )?;
+    git_state.generations_requested += 1;
+    git_state
+        .generations_in_flight
+        .insert(session_id.to_string());
+    // The retry answers the same messages, so it keeps the generation's id
+    publish(
+        git_state,
+        &GitChatEvent::GenerationStarted {
+            session_id: session_id.to_string(),
+            generation_id: git_state.generation_ids.get(session_id).cloned(),
+        },
+    );
+    // Requests the failed actor never got go to its replacement
+    flush_outbound_queue(git_state, true);
+    Ok(true)
+}
+
+/// Replace a session's chat-state actor with one running `model`, and replay the
+/// conversation into it. Returns the new actor's id.
+fn switch_session_model(
+    git_state: &mut GitChatState,
+    session_id: &str,
+    old_actor_id: &str,
+    model: &ModelConfig,
+) -> Result<String, String> {
+    // The old actor may be gone; the new one then starts without the conversation
+    let history = match fetch_history(old_actor_id, None, None) {
+        Ok(messages) => messages,
+        Err(e) => {
+            logging::warn(&format!(
+                "Could not fetch the conversation to replay: {}",
+                e
+            ));
+            Vec::new()
+        }
+    };
+
+    let mut chat_config = chat_config_for_session(git_state, session_id);
+    chat_config["model_config"] = serde_json::to_value(model)
+        .map_err(|e| format!("Failed to serialize model config: {}", e))?;
+    let chat_actor_id = spawn_chat_state_actor(&chat_config)?;
+    for entry in history {
+        send_to_chat_state(
+            &chat_actor_id,
+            &protocol::ChatStateRequest::AddMessage {
+                message: entry.message,
+                provenance: entry.provenance,
+            },
+        )?;
+    }
+
+    match git_state.sessions.get_mut(session_id) {
+        Some(session) => session.chat_state_actor_id = chat_actor_id.clone(),
+        None => {
+            // Respawns after a restart use the new model too
+            git_state.set_original_config(chat_config);
+            git_state.set_chat_state_actor_id(chat_actor_id.clone());
+        }
+    }
+    git_state
+        .session_models
+        .insert(session_id.to_string(), model.model.clone());
+    if let Err(e) = stop_chat_state_actor(old_actor_id) {
+        logging::warn(&format!("Failed to stop {}: {:?}", old_actor_id, e));
+    }
+    Ok(chat_actor_id)
+}
+
+/// Move every session that isn't generating to the model of the downgrade level usage
+/// has reached, and tell subscribers. Sessions that are generating move once they're
+/// done.
+fn apply_model_downgrades(git_state: &mut GitChatState) {
+    let level = git_state.downgrade_level;
+    let model = match git_state
+        .assistant_config
+        .budget
+        .as_ref()
+        .and_then(|budget| budget.downgrade_model(level))
+    {
+        Some(model) => model.clone(),
+        None => return,
+    };
+    let mut session_ids = vec![DEFAULT_SESSION_ID.to_string()];
+    session_ids.extend(git_state.sessions.keys().cloned());
+    for session_id in session_ids {
+        let current = git_state
+            .session_downgrades
+            .get(&session_id)
+            .copied()
+            .unwrap_or(0);
+        if current >= level || git_state.generations_in_flight.contains(&session_id) {
+            continue;
+        }
+        let chat_actor_id = match git_state.chat_actor_for_session(Some(&session_id)) {
+            Ok(chat_actor_id) => chat_actor_id,
+            Err(_) => continue,
+        };
+        match switch_session_model(git_state, &session_id, &chat_actor_id, &model) {
+            Ok(_) => {
+                logging::info(&format!(
+                    "Session {} now generates with {}",
+                    session_id, model.model
+                ));
+                git_state
+                    .session_downgrades
+                    .insert(session_id.clone(), level);
+                publish(
+                    git_state,
+                    &GitChatEvent::ModelDowngraded {
+                        session_id,
+                        model: model.model.clone(),
+                    },
+                );
+            }
+            Err(e) => logging::warn(&format!(
+                "Failed to downgrade the model of session {}: {}",
+                session_id, e
+            )),
+        }
+    }
+    // Requests sent to the old actors go to their replacements
+    flush_outbound_queue(git_state, true);
+}
+
+/// Try the next fallback model when `child` is a chat-state actor that failed with a
+/// provider error. Returns the updated state when the generation was retried.
+fn fall_back_after_child_error(
+    state: &Option<Vec<u8>>,
+    child: &str,
+    error: &str,
+) -> Result<Option<Vec<u8>>, String> {
+    let mut git_state: GitChatState = match state.as_deref().map(decode_state) {
+        Some(Ok(git_state)) => git_state,
+        _ => return Ok(None),
+    };
+    let session_id = match git_state.session_for_chat_actor(child) {
+        Some(session_id) => session_id,
+        None => return Ok(None),
+    };
+    git_state.generations_in_flight.remove(&session_id);
+    match fall_back_to_next_model(&mut git_state, &session_id, child, error) {
+        Ok(true) => encode_state(&mut git_state)
+            .map(Some)
+            .map_err(|e| format!("Failed to serialize git state: {}", e)),
+        Ok(false) => Ok(None),
+        Err(e) => {
+            logging::warn(&format!("Model fallback failed: {}", e));
+            Ok(None)
+        }
+    }
+}
+
+/// Replace the supervised task monitor after `child`, the monitor, failed or exited.
+/// `None` when `child` isn't the task monitor.
+fn restart_task_monitor_after(
+    state: &Option<Vec<u8>>,
+    child: &str,
+) -> Result<Option<Vec<u8>>, String> {
+    let mut git_state: GitChatState = match state.as_deref().map(decode_state) {
+        Some(Ok(git_state)) => git_state,
+        _ => return Ok(None),
+    };
+    if git_state.task_monitor_actor_id.as_deref() != Some(child) {
+        return Ok(None);
+    }
+    if let Err(e) = restart_task_monitor(&mut git_state) {
+        let error_msg = format!("Task monitor {} is gone: {}", child, e);
+        logging::error(&error_msg);
+        git_state.last_error = Some(error_msg);
+    }
+    encode_state(&mut git_state)
+        .map(Some)
+        .map_err(|e| format!("Failed to serialize git state: {}", e))
+}
+
+/// Spawn a new task monitor and point the sessions at it. Running chat-state actors
+/// get their new MCP servers with `update_mcp_servers`; later ones are spawned with
+/// them.
+fn restart_task_monitor(git_state: &mut GitChatState) -> Result<(), String> {
+    let old = git_state.task_monitor_actor_id.take().unwrap_or_default();
+    if git_state.task_monitor_restarts >= task_monitor::MAX_RESTARTS {
+        return Err(format!(
+            "it was already replaced {} times; the model can't signal completion",
+            task_monitor::MAX_RESTARTS
+        ));
+    }
+    // A monitor that reported an error may still be running
+    if list_children().contains(&old) {
+        if let Err(e) = stop_child(&old) {
+            logging::warn(&format!("Could not stop task monitor {}: {:?}", old, e));
+        }
+    }
+    let new = task_monitor::spawn_task_monitor(TASK_MONITOR_MANIFEST_PATH, &git_state.actor_id)?;
+    logging::info(&format!("Replaced task monitor {} with {}", old, new));
+    git_state.task_monitor_actor_id = Some(new.clone());
+    git_state.task_monitor_restarts += 1;
+    git_state.metrics.count_restart("task-monitor");
+    let mut chat_config = git_state.original_config.clone();
+    task_monitor::replace_actor_id(&mut chat_config["mcp_servers"], &old, &new);
+    git_state.set_original_config(chat_config);
+
+    let session_ids: Vec<String> = git_state
+        .session_list()
+        .into_iter()
+        .map(|session| session.session_id)
+        .collect();
+    for session_id in session_ids {
+        let mcp_servers = chat_config_for_session(git_state, &session_id)["mcp_servers"].clone();
+        if let Err(e) = send_or_queue(
+            git_state,
+            &session_id,
+            protocol::ChatStateRequest::UpdateMcpServers { mcp_servers },
+        ) {
+            logging::warn(&format!(
+                "Could not give session {} the new task monitor: {}",
+                session_id, e
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Apply `patch` to the generation parameters of the init config and send the result
+/// to every session's chat-state actor. Sessions spawned later, including after a
+/// restart, start with it.
+fn update_config(git_state: &mut GitChatState, patch: &Value) -> Result<GenerationConfig, String> {
+    let config = GenerationConfig::from_chat_config(&git_state.original_config)?.apply(patch)?;
+    apply_generation_config(git_state, config)
+}
+
+/// Start over from the init config that sent the assistant into safe mode, patched with
+/// `patch`. The safe mode sessions are stopped once the new ones are up; subscribers
+/// and channel streams carry over. While the patched config is still broken, the
+/// assistant stays in safe mode with the new error.
+fn leave_safe_mode(
+    git_state: &mut GitChatState,
+    patch: &Value,
+) -> Result<GenerationConfig, String> {
+    let Some(safe_mode) = git_state.safe_mode.as_mut() else {
+        return Err("Not in safe mode".to_string());
+    };
+    let mut raw_config = safe_mode.raw_config.clone();
+    config_patch::merge_patch(&mut raw_config, patch);
+    let state_bytes =
+        match init_from_config(Ok(raw_config.clone()), git_state.actor_id.clone(), false) {
+            Ok(state_bytes) => state_bytes,
+            Err(e) => {
+                logging::warn(&format!("Staying in safe mode: {}", e));
+                safe_mode.raw_config = raw_config;
+                safe_mode.error = e.clone();
+                return Err(e);
+            }
+        };
+    let mut new_state = decode_state(&state_bytes)?;
+    for session in git_state.session_list() {
+        if let Err(e) = stop_chat_state_actor(&session.chat_state_actor_id) {
+            logging::warn(&format!(
+                "Failed to stop the safe mode session {}: {}",
+                session.session_id, e
+            ));
+        }
+    }
+    if let Some(exec_tools_actor_id) = &git_state.exec_tools_actor_id {
+        if let Err(e) = stop_child(exec_tools_actor_id) {
+            logging::warn(&format!("Failed to stop the safe mode exec actor: {:?}", e));
+        }
+    }
+    new_state
+        .subscribers
+        .extend(std::mem::take(&mut git_state.subscribers));
+    new_state.channel_streams = std::mem::take(&mut git_state.channel_streams);
+    new_state.next_stream_number = git_state.next_stream_number;
+    logging::info("Left safe mode with the patched init config");
+    *git_state = new_state;
+    GenerationConfig::from_chat_config(&git_state.original_config)
+}
+
+/// Switch every session to the model preset `name`, like `UpdateConfig` does.
+fn use_model_preset(git_state: &mut GitChatState, name: &str) -> Result<GenerationConfig, String> {
+    let preset = git_state
+        .assistant_config
+        .model_presets
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("{}: unknown model preset {}", ErrorCode::NotFound, name))?;
+    let current = GenerationConfig::from_chat_config(&git_state.original_config)?;
+    let config = apply_generation_config(git_state, preset.generation_config(&current))?;
+    git_state.model_preset = Some(name.to_string());
+    Ok(config)
+}
+
+/// Switch to the preset `workflow_presets` names for `stage` of `workflow`, if any.
+/// A failed switch is logged; the workflow goes on with the current model.
+fn use_stage_preset(git_state: &mut GitChatState, workflow: &Workflow, stage: &str) {
+    let Some(name) = model_preset::for_stage(
+        &git_state.assistant_config.workflow_presets,
+        workflow,
+        stage,
+    )
+    .map(str::to_string) else {
+        return;
+    };
+    if git_state.model_preset.as_deref() == Some(name.as_str()) {
+        return;
+    }
+    match use_model_preset(git_state, &name) {
+        Ok(_) => logging::info(&format!(
+            "Switched to model preset {} at the {} stage of the {} workflow",
+            name,
+            stage,
+            workflow.name()
+        )),
+        Err(e) => logging::warn(&format!(
+            "Failed to switch to model preset {} at the {} stage: {}",
+            name, stage, e
+        )),
+    }
+}
+
+/// Make `config` the generation parameters of the init config and of every session's
+/// chat-state actor.
+fn apply_generation_config(
+    git_state: &mut GitChatState,
+    config: GenerationConfig,
+) -> Result<GenerationConfig, String> {
+    let model_config = serde_json::to_value(&config.model_config)
+        .map_err(|e| format!("Failed to serialize model config: {}", e))?;
+    let mut chat_config = git_state.original_config.clone();
+    chat_config["model_config"] = model_config.clone();
+    chat_config["temperature"] = serde_json::json!(config.temperature);
+    chat_config["max_tokens"] = serde_json::json!(config.max_tokens);
+    git_state.set_original_config(chat_config);
+    git_state.assistant_config.model_config = Some(model_config);
+    git_state.assistant_config.temperature = Some(config.temperature);
+    git_state.assistant_config.max_tokens = Some(config.max_tokens);
+    logging::info(&format!("Updated the generation config: {:?}", config));
+
+    let session_ids: Vec<String> = git_state
+        .session_list()
+        .into_iter()
+        .map(|session| session.session_id)
+        .collect();
+    for session_id in session_ids {
+        send_or_queue(
+            git_state,
+            &session_id,
+            protocol::ChatStateRequest::UpdateConfig {
+                config: config.clone(),
+            },
+        )?;
+    }
+    Ok(config)
+}
+
+/// Forward `message` to a session's chat-state actor, tagged with who wrote it, and
+/// request a completion for it. While the session is generating, the message is held
+/// instead and goes out with the next generation, unless `generation_overlap` says a
+/// user's message interrupts the generation or is refused.
+fn forward_and_generate(
+    git_state: &mut GitChatState,
+    session_id: Option<&str>,
+    message: Message,
+    provenance: Provenance,
+) -> Result<(), String> {
+    forward_all_and_generate(git_state, session_id, vec![message], provenance)
+}
+
+/// Like `forward_and_generate`, for several messages in order with one completion
+/// request for them all.
+fn forward_all_and_generate(
+    git_state: &mut GitChatState,
+    session_id: Option<&str>,
+    messages: Vec<Message>,
+    provenance: Provenance,
+) -> Result<(), String> {
+    if git_state.halt.is_some() {
+        return Err("halted until the next KeepAlive".to_string());
+    }
+    if git_state.budget_exceeded {
+        return Err("the token budget is spent".to_string());
+    }
+    let messages: Vec<Message> = match &git_state.assistant_config.diff_budget {
+        Some(budget) if budget.enforce => messages
+            .into_iter()
+            .map(|message| trim_diffs(budget, message))
+            .collect(),
+        _ => messages,
+    };
+    let chat_actor_id = git_state.chat_actor_for_session(session_id)?;
+    logging::debug(&format!(
+        "Forwarding message to chat state actor: {}",
+        chat_actor_id
+    ));
+    let session_id = session_id.unwrap_or(DEFAULT_SESSION_ID).to_string();
+
+    if git_state.generations_in_flight.contains(&session_id) {
+        // The assistant's own messages always wait for the generation
+        let overlap = match provenance {
+            Provenance::User => git_state
+                .assistant_config
+                .generation_overlap
+                .unwrap_or_default(),
+            _ => GenerationOverlap::Queue,
+        };
+        match overlap {
+            GenerationOverlap::Queue => {}
+            GenerationOverlap::Reject => {
+                return Err(format!(
+                    "{}: session {} is generating; send the message once it finishes",
+                    ErrorCode::Busy,
+                    session_id
+                ));
+            }
+            GenerationOverlap::Interrupt => {
+                send_to_chat_state(
+                    &chat_actor_id,
+                    &protocol::ChatStateRequest::CancelGeneration,
+                )?;
+                logging::info(&format!(
+                    "Interrupted the generation in session {} for a new message",
+                    session_id
+                ));
+                mark_generation_cancelled(git_state, &session_id);
+                let reply = Err("The generation was interrupted by a new message".to_string());
+                answer_pending_replies(git_state, &session_id, reply);
+                // Messages held for the cancelled generation go out with these
+                git_state
+                    .held_messages
+                    .entry(session_id.clone())
+                    .or_default()
+                    .extend(messages.into_iter().map(|message| HeldMessage {
+                        message,
+                        provenance,
+                    }));
+                release_held_messages(git_state, &session_id);
+                return Ok(());
+            }
+        }
+    }
+    if git_state.generations_in_flight.contains(&session_id) {
+        let held = git_state
+            .held_messages
+            .entry(session_id.clone())
+            .or_default();
+        if held.len() + messages.len() > outbound::MAX_HELD_MESSAGES {
+            return Err(format!(
+                "{} messages are already waiting for the generation in session {}",
+                held.len(),
+                session_id
+            ));
+        }
+        held.extend(messages.into_iter().map(|message| HeldMessage {
+            message,
+            provenance,
+        }));
+        logging::info(&format!(
+            "Holding messages for session {} until its generation ends ({} held)",
+            session_id,
+            held.len()
+        ));
+        return Ok(());
+    }
+    if provenance == Provenance::User && session_id == DEFAULT_SESSION_ID {
+        mark_turn(git_state);
+    }
+    for message in messages {
+        forward_message(git_state, &session_id, message, provenance)?;
+    }
+    request_generation(git_state, session_id)
+}
+
+/// Remember where the repository stands before a turn of the user, for `UndoLastTurn`.
+fn mark_turn(git_state: &mut GitChatState) {
+    let mark = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        undo::mark(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            clock::now(),
+        )
+    });
+    match mark {
+        Ok(mark) => git_state.last_turn = Some(mark),
+        Err(e) => {
+            // The turn can still be dropped from the transcript
+            logging::debug(&format!(
+                "Could not mark the repository for the turn: {}",
+                e
+            ));
+            git_state.last_turn = None;
+        }
+    }
+}
+
+/// Drop the user's last turn from the default session's transcript. What the turn
+/// staged or committed is backed out first, once `Confirm`ed.
+fn undo_last_turn(
+    git_state: &mut GitChatState,
+    request: &GitChatRequest,
+) -> Result<GitChatResponse, String> {
+    if git_state.generations_in_flight.contains(DEFAULT_SESSION_ID) {
+        return Err(format!(
+            "{}: the turn is still generating; cancel it first",
+            ErrorCode::Busy
+        ));
+    }
+    let chat_actor_id = git_state.chat_actor_for_session(None)?;
+    let history = fetch_history(&chat_actor_id, None, None)?;
+    let (index, message_id) = match undo::last_user_turn(&history) {
+        Some((index, message)) => (index, message.id.clone()),
+        None => {
+            return Err(format!(
+                "{}: the conversation has no turn of the user",
+                ErrorCode::NotFound
+            ))
+        }
+    };
+
+    let compensation = match git_state.last_turn.clone() {
+        Some(mark) => {
+            let git_actor_id = git_state.git_tools_actor_id()?;
+            let undo_commits = git_state.workflow == Some(Workflow::Commit);
+            let compensation = undo::compensation(
+                &git_actor_id,
+                git_state.current_directory.as_deref(),
+                &mark,
+                undo_commits,
+            )?;
+            Some(compensation).filter(|compensation| !compensation.is_empty())
+        }
+        None => None,
+    };
+    let confirmed = format!("{}:{}", DEFAULT_SESSION_ID, undo::UNDO_TURN);
+    if let Some(compensation) = &compensation {
+        if !git_state.confirmed_actions.contains(&confirmed) {
+            let description = format!(
+                "Undo the last turn of the conversation and {}",
+                compensation.describe()
+            );
+            let confirmation = hold_request(
+                git_state,
+                request,
+                undo::UNDO_TURN,
+                ActionSeverity::LocalMutation,
+                DEFAULT_SESSION_ID.to_string(),
+                description,
+            );
+            return Ok(GitChatResponse::ConfirmationRequired { confirmation });
+        }
+        // The transcript is left alone when the repository can't be backed out
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        compensation.run(&git_actor_id, git_state.current_directory.as_deref())?;
+    }
+    // Each undo that touches the repository is confirmed on its own
+    git_state.confirmed_actions.remove(&confirmed);
+
+    let response = request_from_chat_state(
+        &chat_actor_id,
+        &protocol::ChatStateRequest::TruncateHistory {
+            from: message_id.clone(),
+        },
+    )?;
+    if let protocol::ChatStateResponse::Error { error } = response {
+        return Err(format!(
+            "Failed to truncate the transcript: {}",
+            error.message
+        ));
+    }
+    git_state.last_turn = None;
+    let undo = TurnUndo {
+        message_id,
+        messages_removed: history.len() - index,
+        compensation,
+    };
+    logging::info(&format!(
+        "Undid the last turn: {} messages removed{}",
+        undo.messages_removed,
+        undo.compensation
+            .as_ref()
+            .map(|compensation| format!(", {}", compensation.describe()))
+            .unwrap_or_default()
+    ));
+    Ok(GitChatResponse::TurnUndone { undo })
+}
+
+/// Send `message` to a session's chat-state actor without requesting a completion.
+fn forward_message(
+    git_state: &mut GitChatState,
+    session_id: &str,
+    message: Message,
+    provenance: Provenance,
+) -> Result<(), String> {
+    send_or_queue(
+        git_state,
+        session_id,
+        protocol::ChatStateRequest::AddMessage {
+            message,
+            provenance: Some(provenance),
+        },
+    )?;
+    git_state.messages_forwarded += 1;
+    publish(
+        git_state,
+        &GitChatEvent::MessageAdded {
+            session_id: session_id.to_string(),
+        },
+    );
+    Ok(())
+}
+
+fn request_generation(git_state: &mut GitChatState, session_id: String) -> Result<(), String> {
+    if let Some(config) = git_state.pending_overrides.remove(&session_id) {
+        send_or_queue(
+            git_state,
+            &session_id,
+            protocol::ChatStateRequest::UpdateConfig { config },
+        )?;
+        git_state.overridden_generations.insert(session_id.clone());
+    }
+    send_or_queue(
+        git_state,
+        &session_id,
+        protocol::ChatStateRequest::GenerateCompletion,
+    )?;
+    git_state.generations_requested += 1;
+    git_state.generations_in_flight.insert(session_id.clone());
+    let generation_id = start_generation_id(git_state, &session_id);
+    publish(
+        git_state,
+        &GitChatEvent::GenerationStarted {
+            session_id,
+            generation_id: Some(generation_id),
+        },
+    );
+    Ok(())
+}
+
+/// Give a session's new generation the next id, and return it.
+fn start_generation_id(git_state: &mut GitChatState, session_id: &str) -> String {
+    git_state.next_generation_number += 1;
+    let generation_id = format!("gen-{}", git_state.next_generation_number);
+    git_state
+        .generation_ids
+        .insert(session_id.to_string(), generation_id.clone());
+    generation_id
+}
+
+fn generation_id_of(git_state: &GitChatState, session_id: Option<&str>) -> Option<String> {
+    session_id.and_then(|session_id| git_state.generation_ids.get(session_id).cloned())
+}
+
+/// Forward a user's `AddMessage`. With `overrides`, the generation it starts, or the
+/// next one when the message is held, runs on them.
+fn add_message(
+    git_state: &mut GitChatState,
+    session_id: Option<&str>,
+    message: Message,
+    overrides: Option<&GenerationOverrides>,
+) -> Result<(), String> {
+    let session_key = session_id.unwrap_or(DEFAULT_SESSION_ID).to_string();
+    if let Some(overrides) = overrides {
+        let current = GenerationConfig::from_chat_config(&git_state.original_config)?;
+        let config = overrides
+            .apply(&current, &git_state.assistant_config.model_presets)
+            .map_err(|e| format!("{}: invalid overrides: {}", ErrorCode::InvalidRequest, e))?;
+        logging::debug(&format!(
+            "Overriding the next generation of session {}: {:?}",
+            session_key, config
+        ));
+        git_state
+            .pending_overrides
+            .insert(session_key.clone(), config);
+    }
+    let forwarded = forward_and_generate(git_state, session_id, message, Provenance::User);
+    if forwarded.is_err() {
+        git_state.pending_overrides.remove(&session_key);
+    }
+    forwarded
+}
+
+/// Give a session's chat-state actor the session's own generation parameters back
+/// after a generation on overridden ones.
+fn end_generation_overrides(git_state: &mut GitChatState, session_id: &str) {
+    if !git_state.overridden_generations.remove(session_id) {
+        return;
+    }
+    let restored =
+        GenerationConfig::from_chat_config(&git_state.original_config).and_then(|config| {
+            send_or_queue(
+                git_state,
+                session_id,
+                protocol::ChatStateRequest::UpdateConfig { config },
+            )
+        });
+    if let Err(e) = restored {
+        logging::warn(&format!(
+            "Failed to restore the generation config of session {}: {}",
+            session_id, e
+        ));
+    }
+}
+
+/// Once a session's generation is cancelled, the chat-state actor may still report its
+/// end; remember it so that report isn't taken for the next generation's.
+fn mark_generation_cancelled(git_state: &mut GitChatState, session_id: &str) {
+    git_state.generations_in_flight.remove(session_id);
+    end_generation_overrides(git_state, session_id);
+    if let Some(generation_id) = git_state.generation_ids.remove(session_id) {
+        git_state
+            .stale_generations
+            .insert(session_id.to_string(), generation_id);
+    }
+}
+
+/// Whether the end just reported for `session_id` is a cancelled generation's.
+fn ignore_stale_generation(git_state: &mut GitChatState, session_id: Option<&str>) -> bool {
+    let Some(generation_id) =
+        session_id.and_then(|session_id| git_state.stale_generations.remove(session_id))
+    else {
+        return false;
+    };
+    logging::info(&format!(
+        "Ignoring the end of cancelled generation {} in session {}",
+        generation_id,
+        session_id.unwrap_or(DEFAULT_SESSION_ID)
+    ));
+    true
+}
+
+/// Forward the messages held for `session_id` once it isn't generating, and request
+/// one completion for them all. They wait while the assistant is halted, and are
+/// dropped once the budget is spent.
+fn release_held_messages(git_state: &mut GitChatState, session_id: &str) {
+    if git_state.generations_in_flight.contains(session_id) || git_state.halt.is_some() {
+        return;
+    }
+    let Some(held) = git_state.held_messages.remove(session_id) else {
+        return;
+    };
+    if git_state.budget_exceeded {
+        logging::warn(&format!(
+            "Dropped {} messages held for session {}: the token budget is spent",
+            held.len(),
+            session_id
+        ));
+        return;
+    }
+    logging::info(&format!(
+        "Forwarding {} held messages to session {} for one generation",
+        held.len(),
+        session_id
+    ));
+    let released = held
+        .into_iter()
+        .try_for_each(|held| forward_message(git_state, session_id, held.message, held.provenance))
+        .and_then(|()| request_generation(git_state, session_id.to_string()));
+    if let Err(e) = released {
+        let error_msg = format!(
+            "Failed to forward the messages held for session {}: {}",
+            session_id, e
+        );
+        logging::error(&error_msg);
+        git_state.last_error = Some(error_msg);
+    }
+}
+
+/// Forward a `SendAndAwait` message and remember `request_id`, to answer once the
+/// generation finishes. One reply is awaited per session at a time. Quick commands
+/// are answered right away instead.
+fn send_and_await(
+    git_state: &mut GitChatState,
+    request_id: &str,
+    request: GitChatRequest,
+) -> Result<Option<GitChatResponse>, String> {
+    let (mut message, session_id, client) = match request {
+        GitChatRequest::SendAndAwait {
+            message,
+            session_id,
+            client,
+        } => (message, session_id, client),
+        _ => return Err("not a SendAndAwait request".to_string()),
+    };
+    if let Some(response) = check_turn(git_state, session_id.as_deref(), client.as_deref())
+        .or_else(|| answer_quick_command(git_state, &mut message))
+    {
+        return Ok(Some(response));
+    }
+    let session_key = session_id.as_deref().unwrap_or(DEFAULT_SESSION_ID);
+    if git_state.generations_in_flight.contains(session_key) {
+        return Ok(Some(errors::error(
+            ErrorCode::Busy,
+            format!("A generation is already running in session {}", session_key),
+        )));
+    }
+    forward_and_generate(git_state, session_id.as_deref(), message, Provenance::User)?;
+    logging::info(&format!(
+        "Awaiting the reply in session {} for {}",
+        session_key, request_id
+    ));
+    git_state
+        .pending_replies
+        .insert(request_id.to_string(), session_key.to_string());
+    Ok(None)
+}
+
+/// The `turn-taken` error for a message from `client` while another client holds the
+/// session's turn.
+fn check_turn(
+    git_state: &GitChatState,
+    session_id: Option<&str>,
+    client: Option<&str>,
+) -> Option<GitChatResponse> {
+    let session_id = session_id.unwrap_or(DEFAULT_SESSION_ID);
+    turn::check(&git_state.turns, session_id, client, clock::now())
+        .err()
+        .map(|message| errors::error(ErrorCode::TurnTaken, message))
+}
+
+/// Answer a slash command in `message` when `quick_commands` is set, or drop the extra
+/// slash of an escaped one. `None` leaves the message to the model.
+fn answer_quick_command(
+    git_state: &mut GitChatState,
+    message: &mut Message,
+) -> Option<GitChatResponse> {
+    if !git_state.assistant_config.quick_commands {
+        return None;
+    }
+    let text = match message.content.as_mut_slice() {
+        [genai_types::MessageContent::Text { text }] => text,
+        _ => return None,
+    };
+    let command = match quick_command::parse(text) {
+        Parsed::Command(command) => command,
+        Parsed::Invalid(usage) => return Some(errors::error(ErrorCode::InvalidRequest, usage)),
+        Parsed::Escaped(escaped) => {
+            *text = escaped;
+            return None;
+        }
+        Parsed::Message => return None,
+    };
+    logging::info(&format!("Answering quick command {:?}", command));
+    Some(match run_quick_command(git_state, command) {
+        Ok(result) => GitChatResponse::QuickCommand { result },
+        Err(e) => {
+            let error_msg = format!("Quick command failed: {}", e);
+            logging::error(&error_msg);
+            errors::error(ErrorCode::OperationFailed, error_msg)
+        }
+    })
+}
+
+fn run_quick_command(
+    git_state: &mut GitChatState,
+    command: QuickCommand,
+) -> Result<QuickCommandResult, String> {
+    match command {
+        QuickCommand::Status => {
+            let git_actor_id = git_state.git_tools_actor_id()?;
+            quick_command::status(&git_actor_id, git_state.current_directory.as_deref())
+        }
+        QuickCommand::Diff { target } => {
+            let git_actor_id = git_state.git_tools_actor_id()?;
+            let policy = git_state
+                .assistant_config
+                .tool_output
+                .clone()
+                .unwrap_or_default();
+            quick_command::diff(
+                &git_actor_id,
+                git_state.current_directory.as_deref(),
+                target.as_deref(),
+                &policy,
+            )
+        }
+        // Only commits of the current workflow, which nobody else has seen yet
+        QuickCommand::Undo => {
+            let git_actor_id = git_state.git_tools_actor_id()?;
+            quick_command::undo(
+                &git_actor_id,
+                git_state.current_directory.as_deref(),
+                git_state.commit_base.as_deref(),
+            )
+        }
+        QuickCommand::Plan => {
+            let (steps, completed) = match &git_state.pipeline {
+                Some(run) => (run.steps.clone(), run.results.len()),
+                None => (Vec::new(), 0),
+            };
+            Ok(QuickCommandResult::Plan {
+                workflow: git_state.workflow.clone(),
+                steps,
+                completed,
+                rebase: git_state
+                    .rebase
+                    .as_ref()
+                    .and_then(|rebase| rebase.plan.clone()),
+            })
+        }
+        QuickCommand::Help => Ok(quick_command::help()),
+    }
+}
+
+/// Write a commit message for `SuggestCommitMessage` in a throwaway chat-state actor.
+fn suggest_commit_message(
+    git_state: &mut GitChatState,
+    request_id: &str,
+    request: GitChatRequest,
+) -> Result<(), String> {
+    let staged_only = match request {
+        GitChatRequest::SuggestCommitMessage { staged_only } => staged_only,
+        _ => return Err("not a SuggestCommitMessage request".to_string()),
+    };
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let policy = git_state
+        .assistant_config
+        .tool_output
+        .clone()
+        .unwrap_or_default();
+    let (stat, diff) = suggest_commit::fetch_changes(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        staged_only,
+        &policy,
+    )?;
+
+    let config = &git_state.assistant_config;
+    let mut system_prompt = suggest_commit::SYSTEM_PROMPT.to_string();
+    if let Some(commit_convention) = &config.commit_convention {
+        system_prompt.push_str(&commit_convention.prompt_fragment());
+    }
+    if let Some(template) = &config.commit_template {
+        system_prompt.push_str(&commit_template::prompt_fragment(
+            template,
+            &template_values(git_state),
+        ));
+    }
+    if let Some(ticket) = &git_state.ticket {
+        system_prompt.push_str(&ticket.prompt_fragment());
+    }
+    start_suggestion(
+        git_state,
+        request_id,
+        SuggestionKind::CommitMessage,
+        system_prompt,
+        suggest_commit::prompt(&stat, &diff),
+    )
+}
+
+/// Name a branch for `SuggestBranchName` in a throwaway chat-state actor.
+fn suggest_branch_name(
+    git_state: &mut GitChatState,
+    request_id: &str,
+    request: GitChatRequest,
+) -> Result<(), String> {
+    let description = match request {
+        GitChatRequest::SuggestBranchName { description } => description,
+        _ => return Err("not a SuggestBranchName request".to_string()),
+    };
+    if description.trim().is_empty() {
+        return Err("The description is empty".to_string());
+    }
+    let naming = git_state
+        .assistant_config
+        .branch_naming
+        .clone()
+        .unwrap_or_default();
+    let system_prompt = format!(
+        "{}{}",
+        suggest_branch::SYSTEM_PROMPT,
+        naming.prompt_fragment()
+    );
+    start_suggestion(
+        git_state,
+        request_id,
+        SuggestionKind::BranchName,
+        system_prompt,
+        suggest_branch::prompt(&description),
+    )
+}
+
+/// Describe the branch's pull request for `SuggestPrDescription` in a throwaway
+/// chat-state actor.
+fn suggest_pr_description(
+    git_state: &mut GitChatState,
+    request_id: &str,
+    request: GitChatRequest,
+) -> Result<(), String> {
+    let base = match request {
+        GitChatRequest::SuggestPrDescription { base } => base,
+        _ => return Err("not a SuggestPrDescription request".to_string()),
+    };
+    let config = git_state
+        .assistant_config
+        .pr_description
+        .clone()
+        .unwrap_or_default();
+    let base = base.unwrap_or_else(|| config.base_branch.clone());
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let policy = git_state
+        .assistant_config
+        .tool_output
+        .clone()
+        .unwrap_or_default();
+    let changes = suggest_pr::fetch_changes(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &base,
+        &policy,
+    )?;
+    let mut system_prompt = suggest_pr::SYSTEM_PROMPT.to_string();
+    if let Some(ticket) = &git_state.ticket {
+        system_prompt.push_str(&ticket.prompt_fragment());
+    }
+    start_suggestion(
+        git_state,
+        request_id,
+        SuggestionKind::PrDescription,
+        system_prompt,
+        suggest_pr::prompt(&base, &changes, &config),
+    )
+}
+
+/// Spawn a chat-state actor without tools for one turn on `prompt`, and remember
+/// `request_id`, to answer once its generation finishes.
+fn start_suggestion(
+    git_state: &mut GitChatState,
+    request_id: &str,
+    kind: SuggestionKind,
+    system_prompt: String,
+    prompt: String,
+) -> Result<(), String> {
+    let (temperature, max_tokens, title) = match kind {
+        SuggestionKind::CommitMessage => (
+            suggest_commit::TEMPERATURE,
+            suggest_commit::MAX_TOKENS,
+            "Commit Message",
+        ),
+        SuggestionKind::BranchName => (
+            suggest_branch::TEMPERATURE,
+            suggest_branch::MAX_TOKENS,
+            "Branch Name",
+        ),
+        SuggestionKind::PrDescription => (
+            suggest_pr::TEMPERATURE,
+            suggest_pr::MAX_TOKENS,
+            "Pull Request Description",
+        ),
+    };
+    let model_config = git_state
+        .assistant_config
+        .model_config
+        .clone()
+        .unwrap_or_else(|| {
+            serde_json::json!({
+                "model": DEFAULT_MODEL,
+                "provider": "anthropic"
+            })
+        });
+    let chat_config = serde_json::json!({
+        "model_config": model_config,
+        "temperature": temperature,
+        "max_tokens": max_tokens,
+        "system_prompt": system_prompt,
+        "title": title,
+        "description": "Answers one request and is stopped",
+        "mcp_servers": []
+    });
+    let chat_actor_id = start_one_turn(git_state, &chat_config, prompt)?;
+    logging::info(&format!(
+        "Suggesting a {} in {} for {}",
+        title.to_lowercase(),
+        chat_actor_id,
+        request_id
+    ));
+    git_state.suggestions.insert(
+        chat_actor_id,
+        PendingSuggestion {
+            request_id: request_id.to_string(),
+            kind,
+        },
+    );
+    Ok(())
+}
+
+/// Spawn a chat-state actor for `chat_config` and have it generate one reply to
+/// `prompt`. Returns its id; it is stopped again when it can't be asked.
+fn start_one_turn(
+    git_state: &mut GitChatState,
+    chat_config: &Value,
+    prompt: String,
+) -> Result<String, String> {
+    let chat_actor_id = spawn_chat_state_actor(chat_config)?;
+    let sent = send_to_chat_state(
+        &chat_actor_id,
+        &protocol::ChatStateRequest::AddMessage {
+            message: user_message(prompt),
+            provenance: Some(Provenance::Workflow),
+        },
+    )
+    .and_then(|()| {
+        send_to_chat_state(
+            &chat_actor_id,
+            &protocol::ChatStateRequest::GenerateCompletion,
+        )
+    });
+    if let Err(e) = sent {
+        if let Err(stop_error) = stop_chat_state_actor(&chat_actor_id) {
+            logging::warn(&format!(
+                "Could not stop chat-state actor {}: {}",
+                chat_actor_id, stop_error
+            ));
+        }
+        return Err(e);
+    }
+    git_state.generations_requested += 1;
+    Ok(chat_actor_id)
+}
+
+/// Finish the one-turn job of the throwaway chat-state actor `chat_actor_id`, a
+/// suggestion or a diff summary. Returns whether it had one.
+fn finish_one_turn(
+    git_state: &mut GitChatState,
+    chat_actor_id: &str,
+    outcome: Result<(), String>,
+) -> bool {
+    finish_diff_summary(git_state, chat_actor_id, outcome.clone())
+        || finish_suggestion(git_state, chat_actor_id, outcome)
+}
+
+/// Start the `diff_summary` pre-pass of `workflow` when it has one and the pending diff
+/// is big enough. True when it was started; the opening message then goes out once it
+/// finishes.
+fn start_diff_summary(git_state: &mut GitChatState, workflow: &Workflow) -> Result<bool, String> {
+    git_state.diff_summary = None;
+    let Some(config) = git_state.assistant_config.diff_summary.clone() else {
+        return Ok(false);
+    };
+    if !config.workflows.contains(workflow) {
+        return Ok(false);
+    }
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let Some(diff) = diff_summary::fetch_large_diff(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &config,
+    )?
+    else {
+        return Ok(false);
+    };
+    let preset = match &config.preset {
+        Some(name) => Some(
+            git_state
+                .assistant_config
+                .model_presets
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("unknown model preset {}", name))?,
+        ),
+        None => None,
+    };
+    let model_config = match &preset {
+        Some(preset) => serde_json::to_value(&preset.model_config)
+            .map_err(|e| format!("Failed to serialize model config: {}", e))?,
+        None => git_state.original_config["model_config"].clone(),
+    };
+    let chat_config = serde_json::json!({
+        "model_config": model_config,
+        "temperature": diff_summary::TEMPERATURE,
+        "max_tokens": preset
+            .and_then(|preset| preset.max_tokens)
+            .unwrap_or(diff_summary::MAX_TOKENS),
+        "system_prompt": diff_summary::SYSTEM_PROMPT,
+        "title": "Diff Summary",
+        "description": "Answers one request and is stopped",
+        "mcp_servers": []
+    });
+    let prompt = diff_summary::prompt(&diff, &config)?;
+    let chat_actor_id = start_one_turn(git_state, &chat_config, prompt)?;
+    logging::info(&format!(
+        "Summarizing a {}-byte diff in {} before the {} workflow",
+        diff.len(),
+        chat_actor_id,
+        workflow.name()
+    ));
+    git_state.diff_summary_pass = Some(DiffSummaryPass {
+        chat_actor_id,
+        workflow: workflow.clone(),
+        diff_bytes: diff.len(),
+    });
+    Ok(true)
+}
+
+/// Keep the file summaries the pre-pass on `chat_actor_id` wrote, unless `outcome` is
+/// an error, stop it, and send the workflow's opening message. Returns whether
+/// `chat_actor_id` was writing them.
+fn finish_diff_summary(
+    git_state: &mut GitChatState,
+    chat_actor_id: &str,
+    outcome: Result<(), String>,
+) -> bool {
+    if git_state
+        .diff_summary_pass
+        .as_ref()
+        .is_none_or(|pass| pass.chat_actor_id != chat_actor_id)
+    {
+        return false;
+    }
+    let Some(pass) = git_state.diff_summary_pass.take() else {
+        return false;
+    };
+    let files = outcome
+        .and_then(|()| fetch_reply_text(chat_actor_id))
+        .map(|reply| diff_summary::parse(&reply));
+    match files {
+        Ok(files) if !files.is_empty() => {
+            logging::info(&format!("Summarized the diff in {} files", files.len()));
+            git_state.diff_summary = Some(DiffSummary {
+                diff_bytes: pass.diff_bytes,
+                files,
+            });
+        }
+        // The workflow still runs, on the diff itself
+        Ok(_) => logging::warn("The diff summary had no file summaries in it"),
+        Err(e) => logging::warn(&format!("Could not summarize the diff: {}", e)),
+    }
+    if let Err(e) = stop_chat_state_actor(chat_actor_id) {
+        logging::warn(&format!(
+            "Could not stop chat-state actor {}: {}",
+            chat_actor_id, e
+        ));
+    }
+    if let Err(e) = send_opening_message(git_state, &pass.workflow) {
+        let error_msg = format!("Failed to auto-initiate workflow: {}", e);
+        logging::error(&error_msg);
+        git_state.last_error = Some(error_msg.clone());
+        finish_workflow(git_state, None, Some(error_msg));
+    }
+    true
+}
+
+/// Stop a pre-pass the workflow no longer waits for.
+fn stop_diff_summary_pass(git_state: &mut GitChatState) {
+    if let Some(pass) = git_state.diff_summary_pass.take() {
+        if let Err(e) = stop_chat_state_actor(&pass.chat_actor_id) {
+            logging::warn(&format!(
+                "Could not stop chat-state actor {}: {}",
+                pass.chat_actor_id, e
+            ));
+        }
+    }
+}
+
+/// Take the git tools away from the default session for a `StartChat` that asks for
+/// suggest-only mode: its chat-state actor is replaced by one spawned without them, and
+/// the conversation so far is replayed into it.
+fn enter_suggest_only(git_state: &mut GitChatState) -> Result<(), String> {
+    if git_state.generations_in_flight.contains(DEFAULT_SESSION_ID) {
+        return Err(format!(
+            "{}: a generation is running in the default session",
+            ErrorCode::Busy
+        ));
+    }
+    let mut config = git_state.assistant_config.clone();
+    config.suggest_only = true;
+    let chat_config = create_git_optimized_config(
+        &git_state.actor_id,
+        git_state.current_directory.as_deref(),
+        &config,
+        git_state.branch_context.as_ref(),
+        git_state.ticket.as_ref(),
+        git_state.repo_info.as_ref(),
+        git_state.task_monitor_actor_id.as_deref(),
+    );
+    let old_actor_id = git_state.chat_actor_for_session(None)?;
+    let history = fetch_history(&old_actor_id, None, None)?;
+    let chat_actor_id = spawn_chat_state_actor(&chat_config)?;
+    for entry in history {
+        send_to_chat_state(
+            &chat_actor_id,
+            &protocol::ChatStateRequest::AddMessage {
+                message: entry.message,
+                provenance: entry.provenance,
+            },
+        )?;
+    }
+    logging::info(&format!(
+        "Suggest-only mode: {} replaces {}",
+        chat_actor_id, old_actor_id
+    ));
+    git_state.set_chat_state_actor_id(chat_actor_id);
+    if let Err(e) = stop_chat_state_actor(&old_actor_id) {
+        logging::warn(&format!("Failed to stop {}: {:?}", old_actor_id, e));
+    }
+    // A chat-state actor spawned again after a restart or a fallback has no git tools either
+    git_state.set_original_config(chat_config);
+    git_state.assistant_config = config;
+    Ok(())
+}
+
+/// Spawn the git tools again after they couldn't be attached. Once they spawn,
+/// advice-only mode ends: every session gets the git tools and its usual prompt back,
+/// and chat-state actors spawned later start with them.
+fn retry_tool_attach(git_state: &mut GitChatState) -> Result<String, String> {
+    let Some(mut outage) = git_state.tool_outage.take() else {
+        return Err(format!(
+            "{}: the git tools are already attached",
+            ErrorCode::InvalidRequest
+        ));
+    };
+    let git_actor_id = match git_state.git_tools_actor_id() {
+        Ok(actor_id) => actor_id,
+        Err(e) => {
+            outage.retry_failed(e.clone(), clock::now());
+            git_state.tool_outage = Some(outage);
+            return Err(e);
+        }
+    };
+    logging::info(&format!(
+        "Git tools attached after {} failed retries: {}",
+        outage.retries, git_actor_id
+    ));
+    git_state.assistant_config.advice_only = false;
+    // Model switches and presets changed the rest of the chat config at runtime
+    let attached = base_chat_config(git_state);
+    let mut chat_config = git_state.original_config.clone();
+    for key in ["mcp_servers", "system_prompt"] {
+        chat_config[key] = attached[key].clone();
+    }
+    git_state.set_original_config(chat_config);
+
+    let session_ids: Vec<String> = git_state
+        .session_list()
+        .into_iter()
+        .map(|session| session.session_id)
+        .collect();
+    for session_id in session_ids {
+        let chat_config = chat_config_for_session(git_state, &session_id);
+        let system_prompt = chat_config["system_prompt"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let updates = [
+            protocol::ChatStateRequest::UpdateMcpServers {
+                mcp_servers: chat_config["mcp_servers"].clone(),
+            },
+            protocol::ChatStateRequest::UpdateSystemPrompt { system_prompt },
+        ];
+        for update in updates {
+            if let Err(e) = send_or_queue(git_state, &session_id, update) {
+                logging::warn(&format!(
+                    "Could not give session {} the git tools: {}",
+                    session_id, e
+                ));
+            }
+        }
+    }
+    publish(
+        git_state,
+        &GitChatEvent::ToolsAvailabilityChanged {
+            available: true,
+            error: None,
+        },
+    );
+    Ok(git_actor_id)
+}
+
+/// Build `workflow`'s opening message and have the default session answer it.
+fn send_opening_message(git_state: &mut GitChatState, workflow: &Workflow) -> Result<(), String> {
+    let auto_message = build_auto_message(git_state, workflow)?;
+    open_workflow(git_state, workflow, auto_message)
+}
+
+/// Send `workflow`'s priming messages to the default session, in order and without
+/// generating, then `auto_message`, which the one completion answers. The repository
+/// statistics go first when `repo_stats` covers the workflow.
+fn open_workflow(
+    git_state: &mut GitChatState,
+    workflow: &Workflow,
+    auto_message: String,
+) -> Result<(), String> {
+    if let Some(stats) = repo_stats_for(git_state, workflow) {
+        forward_message(
+            git_state,
+            DEFAULT_SESSION_ID,
+            user_message(stats.describe()),
+            Provenance::Workflow,
+        )?;
+    }
+    let prompts = git_state
+        .assistant_config
+        .prompts
+        .clone()
+        .unwrap_or_default();
+    let values = prompts.values(git_state.current_directory.as_deref(), Some(workflow));
+    for priming in prompts.priming(workflow, &values, git_state.workflow_args.as_ref()) {
+        let role = match priming.role {
+            PrimingRole::User => genai_types::messages::Role::User,
+            PrimingRole::Assistant => genai_types::messages::Role::Assistant,
+        };
+        let message = Message {
+            role,
+            content: vec![genai_types::MessageContent::Text { text: priming.text }],
+        };
+        forward_message(git_state, DEFAULT_SESSION_ID, message, Provenance::Workflow)?;
+    }
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(auto_message),
+        Provenance::Workflow,
+    )
+}
+
+/// The repository statistics for `workflow`, when `repo_stats` covers it: the ones in
+/// state while they are fresh, or else read again. A failed read is logged; the
+/// workflow starts without them.
+fn repo_stats_for(git_state: &mut GitChatState, workflow: &Workflow) -> Option<RepoStats> {
+    let config = git_state.assistant_config.repo_stats.clone()?;
+    if !config.workflows.contains(workflow) {
+        return None;
+    }
+    let now = clock::now();
+    if let Some(stats) = &git_state.repo_stats {
+        if stats.is_fresh(config.ttl_secs, now) {
+            return Some(stats.clone());
+        }
+    }
+    let directory = git_state.current_directory.clone();
+    match git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        repo_stats::fetch(&git_actor_id, directory.as_deref(), &config, now)
+    }) {
+        Ok(stats) => {
+            logging::info(&format!(
+                "Gathered repository statistics: {} commits in {} days",
+                stats.commits, stats.since_days
+            ));
+            git_state.repo_stats = Some(stats.clone());
+            Some(stats)
+        }
+        Err(e) => {
+            logging::warn(&format!("Failed to gather repository statistics: {}", e));
+            None
+        }
+    }
+}
+
+/// Answer the suggestion request `chat_actor_id` was working on, from its reply unless
+/// `outcome` is an error, and stop the actor. Returns whether `chat_actor_id` was
+/// working on one.
+fn finish_suggestion(
+    git_state: &mut GitChatState,
+    chat_actor_id: &str,
+    outcome: Result<(), String>,
+) -> bool {
+    let Some(pending) = git_state.suggestions.remove(chat_actor_id) else {
+        return false;
+    };
+    let reply = outcome.and_then(|()| fetch_reply_text(chat_actor_id));
+    let response = match (pending.kind, reply) {
+        (SuggestionKind::CommitMessage, Ok(reply)) => {
+            let message = suggest_commit::extract_message(&reply);
+            if message.is_empty() {
+                Err("The model replied without a commit message".to_string())
+            } else {
+                let problems = lint_commit_message(git_state, &message);
+                let misspellings = spellcheck_generated(git_state, &message);
+                Ok(GitChatResponse::CommitMessageSuggested {
+                    message,
+                    problems,
+                    misspellings,
+                })
+            }
+        }
+        (SuggestionKind::BranchName, Ok(reply)) => {
+            let naming = git_state
+                .assistant_config
+                .branch_naming
+                .clone()
+                .unwrap_or_default();
+            match suggest_branch::candidates(&reply, &naming) {
+                (candidates, _) if !candidates.is_empty() => {
+                    Ok(GitChatResponse::BranchNamesSuggested { candidates })
+                }
+                (_, rejected) => Err(format!(
+                    "None of the model's branch names are valid: {}",
+                    rejected
+                        .iter()
+                        .map(|error| format!("{} ({})", error.proposed, error.problems.join(", ")))
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                )),
+            }
+        }
+        (SuggestionKind::PrDescription, Ok(reply)) => {
+            let config = git_state
+                .assistant_config
+                .pr_description
+                .clone()
+                .unwrap_or_default();
+            suggest_pr::parse(&reply, &config).map(|suggestion| {
+                let misspellings = spellcheck_generated(
+                    git_state,
+                    &format!("{}\n{}", suggestion.title, suggestion.body),
+                );
+                GitChatResponse::PrDescriptionSuggested {
+                    title: suggestion.title,
+                    body: suggestion.body,
+                    labels: suggestion.labels,
+                    misspellings,
+                }
+            })
+        }
+        (_, Err(e)) => Err(e),
+    };
+    let response = response.unwrap_or_else(|e| {
+        logging::error(&format!("Could not make the suggestion: {}", e));
+        errors::error(ErrorCode::OperationFailed, e)
+    });
+    match encode_response(&response) {
+        // The caller may have timed out in the meantime
+        Ok(response_bytes) => {
+            if let Err(e) = respond_to_request(&pending.request_id, &response_bytes) {
+                logging::warn(&format!("Could not answer {}: {}", pending.request_id, e));
+            }
+        }
+        Err(e) => logging::error(&format!("Failed to encode the suggestion: {}", e)),
+    }
+    if let Err(e) = stop_chat_state_actor(chat_actor_id) {
+        logging::warn(&format!(
+            "Could not stop chat-state actor {}: {}",
+            chat_actor_id, e
+        ));
+    }
+    true
+}
+
+/// Text of the reply of a throwaway chat-state actor.
+fn fetch_reply_text(chat_actor_id: &str) -> Result<String, String> {
+    let message_id =
+        fetch_head(chat_actor_id)?.ok_or_else(|| "The model didn't reply".to_string())?;
+    let reply = fetch_message(chat_actor_id, &message_id)?;
+    let text: Vec<&str> = reply
+        .message
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            genai_types::MessageContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    Ok(text.join("\n"))
+}
+
+/// Spellchecker with `spellcheck` and the project word list, or without the list when
+/// the git tools are unavailable.
+fn load_spellchecker(
+    git_state: &mut GitChatState,
+    spellcheck: &SpellcheckConfig,
+) -> spelling::Spellchecker {
+    match git_state.git_tools_actor_id() {
+        Ok(git_actor_id) => spelling::Spellchecker::load(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            spellcheck,
+        ),
+        Err(e) => {
+            logging::info(&format!(
+                "Checking spelling without the project word list: {}",
+                e
+            ));
+            spelling::Spellchecker::new(spellcheck, &[])
+        }
+    }
+}
+
+/// Probable misspellings in generated `text`, when `spellcheck` is configured.
+fn spellcheck_generated(git_state: &mut GitChatState, text: &str) -> Vec<spelling::SpellingIssue> {
+    match git_state.assistant_config.spellcheck.clone() {
+        Some(spellcheck) => load_spellchecker(git_state, &spellcheck).check(text),
+        None => Vec::new(),
+    }
+}
+
+/// Where `message` breaks the configured commit convention and template.
+fn lint_commit_message(git_state: &GitChatState, message: &str) -> Vec<String> {
+    let mut problems = git_state
+        .assistant_config
+        .commit_convention
+        .as_ref()
+        .map(|commit_convention| commit_convention.check(message))
+        .unwrap_or_default();
+    if let Some(template) = &git_state.assistant_config.commit_template {
+        problems.extend(commit_template::check(
+            template,
+            &template_values(git_state),
+            message,
+        ));
+    }
+    problems
+}
+
+/// Keep the last scratchpad block of the model's reply in `session_id`, when the
+/// scratchpad is enabled.
+fn track_scratchpad(git_state: &mut GitChatState, session_id: &str, reply: &Message) {
+    if !git_state.assistant_config.scratchpad {
+        return;
+    }
+    let Some(content) = reply
+        .content
+        .iter()
+        .rev()
+        .find_map(|content| match content {
+            genai_types::MessageContent::Text { text } => scratchpad::parse(text),
+            _ => None,
+        })
+    else {
+        return;
+    };
+    let scratchpad =
+        Scratchpad::write(git_state.scratchpads.get(session_id), content, clock::now());
+    if scratchpad.truncated {
+        logging::warn(&format!(
+            "The scratchpad of session {} was cut off at {} bytes",
+            session_id,
+            scratchpad::MAX_BYTES
+        ));
+    }
+    logging::debug(&format!(
+        "Scratchpad of session {} updated ({} bytes)",
+        session_id,
+        scratchpad.content.len()
+    ));
+    git_state
+        .scratchpads
+        .insert(session_id.to_string(), scratchpad);
+}
+
+/// Publish the commands the model suggested in `session_id`'s reply, in suggest-only
+/// mode.
+fn track_suggested_commands(git_state: &mut GitChatState, session_id: &str, reply: &Message) {
+    if !git_state.assistant_config.suggest_only {
+        return;
+    }
+    let Some(suggestions) = reply
+        .content
+        .iter()
+        .rev()
+        .find_map(|content| match content {
+            genai_types::MessageContent::Text { text } => suggest_only::parse(text),
+            _ => None,
+        })
+    else {
+        return;
+    };
+    for rejected in &suggestions.rejected {
+        logging::warn(&format!(
+            "Rejected a suggested command ({}): {}",
+            rejected.reason, rejected.cmd
+        ));
+    }
+    publish(
+        git_state,
+        &GitChatEvent::CommandsSuggested {
+            session_id: session_id.to_string(),
+            commands: suggestions.commands,
+            rejected: suggestions.rejected,
+        },
+    );
+}
+
+/// Why `request` can't run now: the assistant is halted, is in safe mode, or has spent
+/// its token budget. Requests are checked on arrival, and again when confirmed.
+fn blocked(git_state: &GitChatState, request: &GitChatRequest) -> Option<GitChatResponse> {
+    if git_state.halt.is_some() && !dead_man_switch::allowed_while_halted(request) {
+        Some(errors::error(
+            ErrorCode::Halted,
+            "Halted because keep-alives stopped; send KeepAlive to resume",
+        ))
+    } else if git_state.safe_mode.is_some() && !safe_mode::allows(request) {
+        Some(errors::error(
+            ErrorCode::SafeMode,
+            "The init config is broken, so nothing is changed; send UpdateConfig with a \
+            patch that fixes it",
+        ))
+    } else if git_state.budget_exceeded && usage::starts_generation(request) {
+        Some(errors::error(
+            ErrorCode::BudgetExceeded,
+            "The token budget is spent",
+        ))
+    } else {
+        None
+    }
+}
+
+/// The answer to a request that `defers_response` and didn't come through the message
+/// server, e.g. over a channel or HTTP.
+fn needs_message_server(request: &GitChatRequest) -> GitChatResponse {
+    let hint = match request {
+        GitChatRequest::SendAndAwait { .. } => "; use AddMessage",
+        _ => "",
+    };
+    errors::error(
+        ErrorCode::InvalidRequest,
+        format!(
+            "{} needs a request through the message server{}",
+            debug_dump::variant_name(request),
+            hint
+        ),
+    )
+}
+
+/// Answer the `SendAndAwait` requests waiting on `session_id` with `reply`.
+fn answer_pending_replies(
+    git_state: &mut GitChatState,
+    session_id: &str,
+    reply: Result<Message, String>,
+) {
+    let request_ids: Vec<String> = git_state
+        .pending_replies
+        .iter()
+        .filter(|(_, waiting_on)| waiting_on.as_str() == session_id)
+        .map(|(request_id, _)| request_id.clone())
+        .collect();
+    if request_ids.is_empty() {
+        return;
+    }
+    let response = match reply {
+        Ok(message) => GitChatResponse::Completion { message },
+        Err(e) => errors::error(ErrorCode::OperationFailed, e),
+    };
+    let response_bytes = match encode_response(&response) {
+        Ok(response_bytes) => response_bytes,
+        Err(e) => {
+            logging::error(&format!("Failed to encode the awaited reply: {}", e));
+            return;
+        }
+    };
+    for request_id in request_ids {
+        git_state.pending_replies.remove(&request_id);
+        // The caller may have timed out in the meantime
+        if let Err(e) = respond_to_request(&request_id, &response_bytes) {
+            logging::warn(&format!("Could not answer {}: {}", request_id, e));
+        }
+    }
+}
+
+/// Cut the diffs in `message` down to the diff budget. An invalid budget leaves the
+/// message as it is.
+fn trim_diffs(budget: &DiffBudget, mut message: Message) -> Message {
+    let trimmer = match DiffTrimmer::new(budget) {
+        Ok(trimmer) => trimmer,
+        Err(e) => {
+            logging::warn(&format!("Could not apply the diff budget: {}", e));
+            return message;
+        }
+    };
+    for content in &mut message.content {
+        if let genai_types::MessageContent::Text { text } = content {
+            let trimmed = trimmer.trim(text);
+            if trimmed.len() != text.len() {
+                logging::info(&format!(
+                    "Trimmed a diff from {} to {} bytes",
+                    text.len(),
+                    trimmed.len()
+                ));
+                *text = trimmed;
+            }
+        }
+    }
+    message
+}
+
+/// Ask the model for a rebase plan. The plan is parsed from its reply when the
+/// generation finishes.
+fn plan_rebase(
+    git_state: &mut GitChatState,
+    onto: String,
+    range: Option<String>,
+) -> Result<RebaseState, String> {
+    if let Some(rebase) = &git_state.rebase {
+        if rebase.status == RebaseStatus::Executing {
+            return Err(format!("A rebase onto {} is still running", rebase.onto));
+        }
+    }
+    git_tools::ensure_revision("onto", &onto)?;
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.clone();
+    let directory = directory.as_deref();
+    if rebase_plan::rebase_in_progress(&git_actor_id, directory) {
+        return Err("A rebase is already in progress in the repository".to_string());
+    }
+    let range = range.unwrap_or_else(|| format!("{}..HEAD", onto));
+    let commits = rebase_plan::fetch_range_commits(&git_actor_id, directory, &range)?;
+    if commits.is_empty() {
+        return Err(format!("No commits in {}", range));
+    }
+    let original_head = git_tools::run_git(&git_actor_id, directory, &["rev-parse", "HEAD"])?
+        .trim()
+        .to_string();
+    let rewrite = rewrite_safety::analyze_rewrite(&git_actor_id, directory, &range)
+        .map_err(|e| format!("Could not check for published commits: {}", e))?;
+    if !rewrite.is_safe() {
+        logging::warn(&format!(
+            "Planning a rebase of published commits: {}",
+            rewrite.describe()
+        ));
+    }
+
+    let rebase = RebaseState {
+        onto,
+        range,
+        status: RebaseStatus::Planning,
+        commits,
+        original_head,
+        plan: None,
+        error: None,
+        rewrite: Some(rewrite),
+    };
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(rebase_plan::planning_message(&rebase)),
+        Provenance::Workflow,
+    )?;
+    git_state.rebase = Some(rebase.clone());
+    Ok(rebase)
+}
+
+/// Validate `plan` against the planned commits and ask the model to carry it out.
+fn execute_rebase_plan(
+    git_state: &mut GitChatState,
+    mut plan: RebasePlan,
+) -> Result<RebaseState, String> {
+    let mut rebase = match &git_state.rebase {
+        Some(rebase)
+            if matches!(
+                rebase.status,
+                RebaseStatus::Planning | RebaseStatus::Proposed
+            ) =>
+        {
+            rebase.clone()
+        }
+        Some(rebase) => {
+            return Err(format!(
+                "The rebase onto {} is {:?}; plan a new one first",
+                rebase.onto, rebase.status
+            ))
+        }
+        None => return Err("No rebase has been planned".to_string()),
+    };
+    plan.validate(&rebase.commits)?;
+
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.clone();
+    let head = git_tools::run_git(&git_actor_id, directory.as_deref(), &["rev-parse", "HEAD"])?;
+    if head.trim() != rebase.original_head {
+        return Err(format!(
+            "HEAD moved from {} since the plan was made; plan again",
+            rebase.original_head
+        ));
+    }
+    let branch = git_tools::run_git(
+        &git_actor_id,
+        directory.as_deref(),
+        &["rev-parse", "--abbrev-ref", "HEAD"],
+    )
+    .map(|branch| branch.trim().to_string())
+    .ok();
+    save_artifact(
+        git_state,
+        ArtifactKind::Backup,
+        "rebase",
+        &serde_json::json!({
+            "branch": branch,
+            "head": rebase.original_head,
+            "onto": rebase.onto,
+            "plan": plan,
+        }),
+    );
+
+    let mut message = rebase_plan::execution_message(&plan, &rebase.original_head);
+    if let Some(rewrite) = rebase.rewrite.as_ref().filter(|rewrite| !rewrite.is_safe()) {
+        message.push_str(&format!(
+            "\n\nNOTE: {}. Rewriting them was approved, but publishing the result needs a \
+            force push; mention this in your summary.",
+            rewrite.describe()
+        ));
+    }
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(message),
+        Provenance::Supervisor,
+    )?;
+    rebase.plan = Some(plan);
+    rebase.status = RebaseStatus::Executing;
+    rebase.error = None;
+    git_state.rebase = Some(rebase.clone());
+    Ok(rebase)
+}
+
+/// Delete `branches` from the branch-cleanup proposal, or all proposed branches, and
+/// tell the model the outcome. Protected branches are refused even when proposed.
+fn execute_branch_cleanup(
+    git_state: &mut GitChatState,
+    branches: &[String],
+) -> Result<BranchCleanup, String> {
+    let mut cleanup = git_state
+        .branch_cleanup
+        .clone()
+        .ok_or("No branch cleanup has been proposed")?;
+    let config = git_state
+        .assistant_config
+        .branch_cleanup
+        .clone()
+        .unwrap_or_default();
+    let selected = if branches.is_empty() {
+        cleanup.candidates.clone()
+    } else {
+        branches
+            .iter()
+            .map(|branch| {
+                cleanup
+                    .candidates
+                    .iter()
+                    .find(|candidate| candidate.branch == *branch)
+                    .cloned()
+                    .ok_or_else(|| format!("{} wasn't proposed for deletion", branch))
+            })
+            .collect::<Result<Vec<_>, String>>()?
+    };
+
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.clone();
+    for candidate in selected {
+        if cleanup.deleted.contains(&candidate.branch) {
+            continue;
+        }
+        cleanup
+            .failures
+            .retain(|failure| failure.branch != candidate.branch);
+        let deleted = if config.is_protected(candidate.name_on_remote()) {
+            Err("the branch is protected".to_string())
+        } else {
+            branch_cleanup::delete(&git_actor_id, directory.as_deref(), &candidate)
+        };
+        match deleted {
+            Ok(()) => {
+                logging::info(&format!("Deleted branch {}", candidate.branch));
+                cleanup.deleted.push(candidate.branch);
+            }
+            Err(error) => {
+                logging::warn(&format!(
+                    "Could not delete branch {}: {}",
+                    candidate.branch, error
+                ));
+                cleanup.failures.push(DeletionFailure {
+                    branch: candidate.branch,
+                    error,
+                });
+            }
+        }
+    }
+    git_state.branch_cleanup = Some(cleanup.clone());
+
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(branch_cleanup::outcome_message(&cleanup)),
+        Provenance::Supervisor,
+    )?;
+    Ok(cleanup)
+}
+
+/// Create the proposed release's annotated tag with `message`, or the drafted notes,
+/// and tell the model the outcome.
+fn execute_release(git_state: &mut GitChatState, message: Option<&str>) -> Result<Release, String> {
+    let mut release = git_state
+        .release
+        .clone()
+        .ok_or("No release has been proposed")?;
+    if release.tagged {
+        return Err(format!("{} has already been created", release.tag));
+    }
+    let message = match message.or(release.notes.as_deref()) {
+        Some(message) if !message.trim().is_empty() => message.trim().to_string(),
+        _ => return Err("No release notes have been drafted; pass a message".to_string()),
+    };
+
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    match release::create_tag(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &release,
+        &message,
+    ) {
+        Ok(()) => {
+            logging::info(&format!("Created tag {}", release.tag));
+            release.notes = Some(message);
+            release.tagged = true;
+            release.error = None;
+        }
+        Err(error) => {
+            logging::warn(&format!("Could not create tag {}: {}", release.tag, error));
+            release.error = Some(error);
+        }
+    }
+    git_state.release = Some(release.clone());
+
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(release::outcome_message(&release)),
+        Provenance::Supervisor,
+    )?;
+    Ok(release)
+}
+
+/// Pick up release notes the model drafted in the default session, and send the
+/// release again when they change.
+fn track_release(git_state: &mut GitChatState) {
+    let mut release = match &git_state.release {
+        Some(release) if !release.tagged => release.clone(),
+        _ => return,
+    };
+    let notes = match latest_reply(git_state) {
+        Ok(reply) => release::parse_notes(&reply),
+        Err(e) => {
+            logging::warn(&format!("Could not read the release notes: {}", e));
+            return;
+        }
+    };
+    if notes.is_none() || notes == release.notes {
+        return;
+    }
+    release.notes = notes;
+    logging::info(&format!("Release notes drafted for {}", release.tag));
+    git_state.release = Some(release.clone());
+    notify(git_state, &GitChatNotification::ReleaseProposed { release });
+}
+
+/// Whether the commit workflow is running with `commit_split`, so it must have its
+/// plan approved before committing.
+fn splitting_commits(git_state: &GitChatState) -> bool {
+    git_state.workflow == Some(Workflow::Commit)
+        && git_state.assistant_config.commit_split
+        && workflow_running(git_state)
+}
+
+/// Pick up a commit plan the model proposed in the default session, and send it to
+/// `notify_actor` and subscribers when it changes.
+fn track_commit_plan(git_state: &mut GitChatState) {
+    if !splitting_commits(git_state)
+        || git_state
+            .commit_plan
+            .as_ref()
+            .is_some_and(|plan| plan.approved)
+    {
+        return;
+    }
+    let mut plan = match latest_reply(git_state).map(|reply| commit_plan::parse(&reply)) {
+        Ok(Some(Ok(plan))) => plan,
+        Ok(Some(Err(e))) => {
+            logging::warn(&format!("Could not read the commit plan: {}", e));
+            let message = format!("I couldn't read your commit plan: {}. Send it again.", e);
+            if let Err(e) = forward_and_generate(
+                git_state,
+                None,
+                user_message(message),
+                Provenance::Supervisor,
+            ) {
+                git_state.last_error = Some(format!("Failed to ask for the plan again: {}", e));
+            }
+            return;
+        }
+        Ok(None) => return,
+        Err(e) => {
+            logging::warn(&format!("Could not read the commit plan: {}", e));
+            return;
+        }
+    };
+    if let Some(spellcheck) = git_state.assistant_config.spellcheck.clone() {
+        plan.spellcheck(&load_spellchecker(git_state, &spellcheck));
+    }
+    if git_state.commit_plan.as_ref() == Some(&plan) {
+        return;
+    }
+    logging::info(&format!(
+        "Commit plan proposed with {} commits",
+        plan.commits.len()
+    ));
+    git_state.commit_plan = Some(plan.clone());
+    notify(
+        git_state,
+        &GitChatNotification::CommitPlanProposed { plan: plan.clone() },
+    );
+    publish(git_state, &GitChatEvent::CommitPlanProposed { plan });
+}
+
+/// Approve the proposed commit plan, or `edited` in its place, and have the model
+/// make its commits.
+fn approve_commit_plan(
+    git_state: &mut GitChatState,
+    edited: Option<CommitPlan>,
+) -> Result<CommitPlan, String> {
+    if !splitting_commits(git_state) {
+        return Err(format!(
+            "{}: no commit workflow with commit_split is running",
+            ErrorCode::InvalidRequest
+        ));
+    }
+    let proposed = git_state
+        .commit_plan
+        .clone()
+        .ok_or_else(|| format!("{}: no commit plan has been proposed", ErrorCode::NotFound))?;
+    if proposed.approved {
+        return Err(format!(
+            "{}: the commit plan was already approved",
+            ErrorCode::InvalidRequest
+        ));
+    }
+    let edited = edited.filter(|plan| plan.commits != proposed.commits);
+    let mut plan = edited.clone().unwrap_or(proposed);
+    plan.validate()
+        .map_err(|e| format!("{}: {}", ErrorCode::InvalidRequest, e))?;
+    plan.approved = true;
+    use_stage_preset(git_state, &Workflow::Commit, "plan-approved");
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(commit_plan::approved_message(&plan, edited.is_some())),
+        Provenance::User,
+    )?;
+    logging::info(&format!(
+        "Commit plan approved with {} commits",
+        plan.commits.len()
+    ));
+    git_state.commit_plan = Some(plan.clone());
+    Ok(plan)
+}
+
+/// Check that the commit workflow made the approved plan's commits, and only once it
+/// was approved. True while the plan still waits for approval.
+fn check_commit_plan(git_state: &mut GitChatState) -> Result<bool, String> {
+    if !splitting_commits(git_state) {
+        return Ok(false);
+    }
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let commits = convention::fetch_commit_messages(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        git_state.commit_base.as_deref(),
+    )?;
+    match &git_state.commit_plan {
+        Some(plan) if plan.approved && plan.commits.len() != commits.len() => Err(format!(
+            "the approved commit plan has {} commits, but {} were made",
+            plan.commits.len(),
+            commits.len()
+        )),
+        Some(plan) if plan.approved => Ok(false),
+        _ if !commits.is_empty() => Err(format!(
+            "{}: {} commits were made before the commit plan was approved",
+            ErrorCode::PolicyViolation,
+            commits.len()
+        )),
+        Some(_) => {
+            logging::info("The commit plan is waiting for ApproveCommitPlan");
+            Ok(true)
+        }
+        None => {
+            forward_and_generate(
+                git_state,
+                None,
+                user_message(
+                    "Don't complete the task yet: propose the commit plan in a ```commit-plan \
+                    block first, and wait until it is approved."
+                        .to_string(),
+                ),
+                Provenance::Supervisor,
+            )?;
+            Ok(true)
+        }
+    }
+}
+
+/// Have the registered sibling `actor_id` start `task`, and record the delegation.
+fn delegate_task(
+    git_state: &mut GitChatState,
+    actor_id: &str,
+    task: DelegatedTask,
+) -> Result<Delegation, String> {
+    if !task.workflow.is_known() {
+        return Err(format!("Unknown workflow: {}", task.workflow.name()));
+    }
+    if !git_state
+        .siblings
+        .iter()
+        .any(|sibling| sibling.actor_id == actor_id)
+    {
+        return Err(format!("{} is not a registered sibling", actor_id));
+    }
+    let id = format!("delegation-{}", git_state.next_delegation_number);
+    let accept = GitChatRequest::AcceptDelegation {
+        delegation_id: id.clone(),
+        from: git_state.actor_id.clone(),
+        task: task.clone(),
+    };
+    let (status, confirmation_id) = match request_from_sibling(actor_id, &accept)? {
+        GitChatResponse::Success => {
+            logging::info(&format!("{} accepted {}", actor_id, id));
+            (delegation::DelegationStatus::Running, None)
+        }
+        // It starts once confirmed there, and reports back either way
+        GitChatResponse::ConfirmationRequired { confirmation } => {
+            logging::info(&format!(
+                "{} holds {} back for confirmation {}",
+                actor_id, id, confirmation.id
+            ));
+            (delegation::DelegationStatus::Pending, Some(confirmation.id))
+        }
+        GitChatResponse::Error { message, .. } => {
+            return Err(format!("{} refused the task: {}", actor_id, message))
+        }
+        response => {
+            return Err(format!(
+                "{} answered the task with {}",
+                actor_id,
+                debug_dump::variant_name(&response)
+            ))
+        }
+    };
+    git_state.next_delegation_number += 1;
+    let delegation = Delegation {
+        id,
+        actor_id: actor_id.to_string(),
+        task,
+        status,
+        started_at: clock::now(),
+        finished_at: None,
+        result: None,
+        error: None,
+        confirmation_id,
+    };
+    delegation::record(&mut git_state.delegations, delegation.clone());
+    Ok(delegation)
+}
+
+/// Send `sibling_request` to the sibling assistant `actor_id` and wait for its response.
+fn request_from_sibling(
+    actor_id: &str,
+    sibling_request: &GitChatRequest,
+) -> Result<GitChatResponse, String> {
+    let request_bytes = to_vec(&Envelope::new(sibling_request))
+        .map_err(|e| format!("Failed to serialize sibling request: {}", e))?;
+    let response_bytes = request(actor_id, &request_bytes)
+        .map_err(|e| format!("Request to {} failed: {}", actor_id, e))?;
+    let envelope: Envelope<GitChatResponse> = from_slice(&response_bytes)
+        .map_err(|e| format!("Failed to parse the response of {}: {}", actor_id, e))?;
+    Ok(envelope.body)
+}
+
+/// Spawn a child assistant for each of `repositories` and have each run `task` there.
+/// Children that can't be spawned or refuse the task fail their run right away.
+fn start_fan_out(
+    git_state: &mut GitChatState,
+    repositories: &[String],
+    task: DelegatedTask,
+) -> Result<FanOutResult, String> {
+    let config = git_state.assistant_config.fan_out.clone().ok_or_else(|| {
+        format!(
+            "{}: StartFanOut needs fan_out in the config",
+            ErrorCode::InvalidRequest
+        )
+    })?;
+    if !task.workflow.is_known() {
+        return Err(format!(
+            "{}: Unknown workflow: {}",
+            ErrorCode::WorkflowUnknown,
+            task.workflow.name()
+        ));
+    }
+    if let Some(running) = git_state
+        .fan_out
+        .as_ref()
+        .filter(|fan_out| fan_out.is_running())
+    {
+        return Err(format!(
+            "{}: {} is still running",
+            ErrorCode::Busy,
+            running.id
+        ));
+    }
+    let repositories = fan_out::validate_repositories(repositories, &config)
+        .map_err(|e| format!("{}: {}", ErrorCode::InvalidRequest, e))?;
+    let parent_config = serde_json::to_value(&git_state.assistant_config)
+        .map_err(|e| format!("Failed to serialize the config: {}", e))?;
+
+    let id = format!("fan-out-{}", git_state.next_fan_out_number);
+    git_state.next_fan_out_number += 1;
+    let now = clock::now();
+    let mut fan_out = FanOutResult {
+        id: id.clone(),
+        task: task.clone(),
+        started_at: now,
+        finished_at: None,
+        runs: Vec::new(),
+        completed: 0,
+        failed: 0,
+    };
+    for (index, repository) in repositories.into_iter().enumerate() {
+        let mut run = RepoRun {
+            repository,
+            actor_id: None,
+            delegation_id: format!("{}-{}", id, index + 1),
+            status: delegation::DelegationStatus::Running,
+            finished_at: None,
+            result: None,
+            error: None,
+        };
+        if let Err(e) = start_repo_run(git_state, &config, &parent_config, &task, &mut run) {
+            logging::warn(&format!("{} failed in {}: {}", id, run.repository, e));
+            if let Some(actor_id) = &run.actor_id {
+                if let Err(e) = stop_child(actor_id) {
+                    logging::warn(&format!("Failed to stop {}: {:?}", actor_id, e));
+                }
+            }
+            run.finish(None, Some(e), now);
+        }
+        fan_out.runs.push(run);
+    }
+    logging::info(&format!(
+        "Started {}: the {} workflow in {} repositories",
+        id,
+        task.workflow.name(),
+        fan_out.runs.len()
+    ));
+    let finished = fan_out.tally(now);
+    git_state.fan_out = Some(fan_out.clone());
+    if finished {
+        report_fan_out(git_state, &fan_out);
+    }
+    Ok(fan_out)
+}
+
+/// Spawn the child assistant for `run`'s repository and hand it `task`.
+fn start_repo_run(
+    git_state: &GitChatState,
+    config: &FanOutConfig,
+    parent_config: &Value,
+    task: &DelegatedTask,
+    run: &mut RepoRun,
+) -> Result<(), String> {
+    let init_state = to_vec(&fan_out::child_config(parent_config, &run.repository))
+        .map_err(|e| format!("Failed to serialize the child's config: {}", e))?;
+    let actor_id = spawn(&config.manifest_path, Some(&init_state))
+        .map_err(|e| format!("Spawn failed: {:?}", e))?;
+    logging::info(&format!(
+        "Spawned assistant {} for {}",
+        actor_id, run.repository
+    ));
+    run.actor_id = Some(actor_id.clone());
+    let accept = GitChatRequest::AcceptDelegation {
+        delegation_id: run.delegation_id.clone(),
+        from: git_state.actor_id.clone(),
+        task: task.clone(),
+    };
+    match request_from_sibling(&actor_id, &accept)? {
+        GitChatResponse::Success => Ok(()),
+        GitChatResponse::Error { message, .. } => {
+            Err(format!("The child refused the task: {}", message))
+        }
+        GitChatResponse::ConfirmationRequired { confirmation } => Err(format!(
+            "The child held the task back for confirmation {}",
+            confirmation.id
+        )),
+        response => Err(format!(
+            "The child answered the task with {}",
+            debug_dump::variant_name(&response)
+        )),
+    }
+}
+
+/// Child of the running fan-out working on `delegation_id`, if any.
+fn fan_out_child(git_state: &GitChatState, delegation_id: &str) -> Option<String> {
+    git_state
+        .fan_out
+        .as_ref()?
+        .runs
+        .iter()
+        .find(|run| {
+            run.delegation_id == delegation_id
+                && run.status == delegation::DelegationStatus::Running
+        })?
+        .actor_id
+        .clone()
+}
+
+/// Record how a repository's run went, and report the fan-out once every run finished.
+fn finish_repo_run(
+    git_state: &mut GitChatState,
+    delegation_id: &str,
+    result: Option<WorkflowResult>,
+    error: Option<String>,
+) {
+    let now = clock::now();
+    let fan_out = match git_state.fan_out.as_mut() {
+        Some(fan_out) => fan_out,
+        None => return,
+    };
+    if let Some(run) = fan_out
+        .runs
+        .iter_mut()
+        .find(|run| run.delegation_id == delegation_id)
+    {
+        run.finish(result, error, now);
+        logging::info(&format!(
+            "{} finished in {}: {:?}",
+            delegation_id, run.repository, run.status
+        ));
+    }
+    if fan_out.tally(now) {
+        let fan_out = fan_out.clone();
+        report_fan_out(git_state, &fan_out);
+    }
+}
+
+fn report_fan_out(git_state: &GitChatState, fan_out: &FanOutResult) {
+    logging::info(&fan_out.summary());
+    notify(
+        git_state,
+        &GitChatNotification::FanOutFinished {
+            fan_out: fan_out.clone(),
+        },
+    );
+}
+
+/// Start the workflow of a task another assistant delegated, which hears back when the
+/// workflow completes.
+fn accept_delegation(git_state: &mut GitChatState, source: DelegationSource) -> GitChatResponse {
+    let workflow = source.task.workflow.clone();
+    if !workflow.is_known() {
+        return errors::error(
+            ErrorCode::WorkflowUnknown,
+            format!("Unknown workflow: {}", workflow.name()),
+        );
+    }
+    if workflow_running(git_state) {
+        return errors::error(
+            ErrorCode::Busy,
+            format!(
+                "Busy with the {} workflow",
+                git_state.workflow.as_ref().map_or("", Workflow::name)
+            ),
+        );
+    }
+    logging::info(&format!(
+        "Accepting {} from {}: the {} workflow",
+        source.delegation_id,
+        source.actor_id,
+        workflow.name()
+    ));
+    // A delegated task runs on its own, outside any configured pipeline
+    git_state.pipeline = None;
+    if let Err(e) = switch_workflow(git_state, workflow) {
+        let error_msg = format!("Failed to switch workflow: {}", e);
+        logging::error(&error_msg);
+        return errors::error(ErrorCode::OperationFailed, error_msg);
+    }
+    git_state.delegated_by = Some(source);
+    let response = handle_git_chat_request(
+        git_state,
+        GitChatRequest::StartChat {
+            allow_secrets: false,
+            allow_out_of_scope: false,
+            workflow: None,
+            args: None,
+            suggest_only: false,
+        },
+    );
+    match &response {
+        GitChatResponse::Error { .. } => git_state.delegated_by = None,
+        // Rejecting it fails the delegation
+        GitChatResponse::ConfirmationRequired { confirmation } => {
+            if let Some(source) = git_state.delegated_by.as_mut() {
+                source.confirmation_id = Some(confirmation.id.clone());
+            }
+        }
+        _ => {}
+    }
+    response
+}
+
+/// Tell the assistant that delegated `source` how its task went.
+fn report_delegation(
+    source: &DelegationSource,
+    result: Option<WorkflowResult>,
+    error: Option<String>,
+) {
+    let message = protocol::ChildNotification::DelegationFinished {
+        delegation_id: source.delegation_id.clone(),
+        result,
+        error,
+    };
+    match to_vec(&message) {
+        Ok(bytes) => {
+            if let Err(e) = send(&source.actor_id, &bytes) {
+                logging::warn(&format!(
+                    "Failed to report {} to {}: {:?}",
+                    source.delegation_id, source.actor_id, e
+                ));
+            }
+        }
+        Err(e) => logging::error(&format!("Failed to serialize delegation result: {}", e)),
+    }
+}
+
+/// Record how a delegated task went, tell the default session's model, and notify.
+fn finish_delegation(
+    git_state: &mut GitChatState,
+    delegation_id: &str,
+    result: Option<WorkflowResult>,
+    error: Option<String>,
+) {
+    let delegation = match git_state
+        .delegations
+        .iter_mut()
+        .find(|delegation| delegation.id == delegation_id && delegation.status.is_open())
+    {
+        Some(delegation) => {
+            delegation.finish(result, error, clock::now());
+            delegation.clone()
+        }
+        None => {
+            logging::warn(&format!(
+                "Ignoring the result of unknown delegation {}",
+                delegation_id
+            ));
+            return;
+        }
+    };
+    logging::info(&format!(
+        "Delegation {} finished: {:?}",
+        delegation.id, delegation.status
+    ));
+    let sibling_name = git_state
+        .siblings
+        .iter()
+        .find(|sibling| sibling.actor_id == delegation.actor_id)
+        .map_or(delegation.actor_id.clone(), |sibling| sibling.name.clone());
+    let message = delegation::fold_message(&delegation, &sibling_name);
+    if let Err(e) = forward_and_generate(
+        git_state,
+        None,
+        user_message(message),
+        Provenance::Supervisor,
+    ) {
+        logging::warn(&format!(
+            "Could not tell the model the delegation result: {}",
+            e
+        ));
+    }
+    notify(
+        git_state,
+        &GitChatNotification::DelegationFinished { delegation },
+    );
+}
+
+/// Move the tracked rebase along after a generation in the default session: parse the
+/// proposed plan, or check whether the executed one has finished.
+fn track_rebase(git_state: &mut GitChatState) {
+    let mut rebase = match &git_state.rebase {
+        Some(rebase) => rebase.clone(),
+        None => return,
+    };
+    match rebase.status {
+        RebaseStatus::Planning => {
+            match latest_reply(git_state)
+                .and_then(|reply| rebase_plan::parse_plan(&reply, &rebase.onto))
+            {
+                Ok(mut plan) => match plan.validate(&rebase.commits) {
+                    Ok(()) => {
+                        let postprocess_config = git_state
+                            .assistant_config
+                            .postprocess
+                            .clone()
+                            .unwrap_or_default();
+                        for message in plan
+                            .steps
+                            .iter_mut()
+                            .filter_map(|step| step.message.as_mut())
+                        {
+                            *message = postprocess_config
+                                .apply_to_commit_message(git_state.workflow.as_ref(), message);
+                        }
+                        rebase.plan = Some(plan);
+                    }
+                    Err(e) => rebase.error = Some(format!("The proposed plan is invalid: {}", e)),
+                },
+                Err(e) => rebase.error = Some(format!("No plan found: {}", e)),
+            }
+            rebase.status = RebaseStatus::Proposed;
+            logging::info(&format!("Rebase plan proposed for {}", rebase.range));
+            notify(
+                git_state,
+                &GitChatNotification::RebasePlanProposed {
+                    rebase: rebase.clone(),
+                },
+            );
+            save_artifact(git_state, ArtifactKind::Plan, "rebase", &rebase);
+        }
+        RebaseStatus::Executing => match check_rebase_finished(git_state, &mut rebase) {
+            Ok(true) => {
+                match &rebase.error {
+                    Some(error) => {
+                        logging::warn(&format!("Rebase onto {} failed: {}", rebase.onto, error))
+                    }
+                    None => logging::info(&format!("Rebase onto {} completed", rebase.onto)),
+                }
+                notify(
+                    git_state,
+                    &GitChatNotification::RebaseFinished {
+                        rebase: rebase.clone(),
+                    },
+                );
+            }
+            Ok(false) => return,
+            Err(e) => {
+                logging::warn(&format!("Could not check the rebase: {}", e));
+                return;
+            }
+        },
+        _ => return,
+    }
+    git_state.rebase = Some(rebase);
+}
+
+/// Text of the newest message in the default session.
+fn latest_reply(git_state: &GitChatState) -> Result<String, String> {
+    Ok(postprocess(git_state, &latest_message_text(git_state)?))
+}
+
+/// Text of the newest message in the default session, as the model wrote it.
+fn latest_message_text(git_state: &GitChatState) -> Result<String, String> {
+    let chat_actor_id = git_state.chat_actor_for_session(None)?;
+    let head = fetch_head(&chat_actor_id)?.ok_or("the conversation is empty")?;
+    let message = fetch_message(&chat_actor_id, &head)?.message;
+    let text = message
+        .content
+        .into_iter()
+        .filter_map(|content| match content {
+            genai_types::MessageContent::Text { text } => Some(text),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(text)
+}
+
+/// Run the active workflow's post-processing steps on text from the model.
+fn postprocess(git_state: &GitChatState, text: &str) -> String {
+    git_state
+        .assistant_config
+        .postprocess
+        .clone()
+        .unwrap_or_default()
+        .apply(git_state.workflow.as_ref(), text)
+}
+
+/// Set the outcome of an executing rebase once the model has left the repository
+/// out of a rebase. Returns `false` while it is still under way.
+fn check_rebase_finished(
+    git_state: &mut GitChatState,
+    rebase: &mut RebaseState,
+) -> Result<bool, String> {
+    let plan = match &rebase.plan {
+        Some(plan) => plan.clone(),
+        None => return Err("the executing rebase has no plan".to_string()),
+    };
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.as_deref();
+    if rebase_plan::rebase_in_progress(&git_actor_id, directory) {
+        logging::info("Rebase stopped part way; waiting for it to be continued");
+        return Ok(false);
+    }
+    let head = git_tools::run_git(&git_actor_id, directory, &["rev-parse", "HEAD"])?;
+    let rewrites = plan
+        .steps
+        .iter()
+        .any(|step| step.action != rebase_plan::RebaseAction::Pick);
+    if head.trim() == rebase.original_head && rewrites {
+        // The model hasn't started, e.g. it asked a question first
+        return Ok(false);
+    }
+    let range = format!("{}..HEAD", plan.onto);
+    let count = git_tools::run_git(&git_actor_id, directory, &["rev-list", "--count", &range])?;
+    let count: usize = count
+        .trim()
+        .parse()
+        .map_err(|e| format!("Unexpected rev-list output: {}", e))?;
+    let expected = plan.expected_commits();
+    if count == expected {
+        rebase.status = RebaseStatus::Completed;
+    } else {
+        rebase.status = RebaseStatus::Failed;
+        rebase.error = Some(format!(
+            "expected {} commits on {} after the rebase, found {}; the branch was at {}",
+            expected, plan.onto, count, rebase.original_head
+        ));
+    }
+    Ok(true)
+}
+
+/// Start bisecting between `good` and `bad` and have the model look at the first
+/// commit to test.
+fn start_bisect(
+    git_state: &mut GitChatState,
+    good: String,
+    bad: Option<String>,
+) -> Result<BisectState, String> {
+    if let Some(bisect) = &git_state.bisect {
+        if bisect.phase == BisectPhase::Testing {
+            return Err("A bisect is already running; abort it first".to_string());
+        }
+    }
+    let bad = bad.unwrap_or_else(|| "HEAD".to_string());
+    for git_ref in [&good, &bad] {
+        git_tools::ensure_revision("ref", git_ref)?;
+    }
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let output = git_tools::run_git(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &["bisect", "start", &bad, &good],
+    )?;
+    logging::info(&format!("Bisecting from {} to {}", good, bad));
+
+    let mut bisect = BisectState {
+        phase: BisectPhase::Testing,
+        good: Some(good),
+        bad: Some(bad),
+        ..BisectState::default()
+    };
+    // Keep the state even when the first step fails, so AbortBisect can reset
+    let result = advance_bisect(git_state, &mut bisect, &output);
+    git_state.bisect = Some(bisect.clone());
+    result.map(|()| bisect)
+}
+
+/// Mark the checked-out commit with `verdict` and move on to the next step.
+fn submit_bisect_verdict(
+    git_state: &mut GitChatState,
+    verdict: BisectVerdict,
+    test_output: Option<String>,
+) -> Result<BisectState, String> {
+    let mut bisect = match &git_state.bisect {
+        Some(bisect) if bisect.current().is_some() => bisect.clone(),
+        _ => return Err("No bisect step is waiting for a verdict".to_string()),
+    };
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let output = git_tools::run_git(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &["bisect", verdict.as_str()],
+    )?;
+    if let Some(step) = bisect.steps.last_mut() {
+        logging::info(&format!("Marked {} {}", step.sha, verdict.as_str()));
+        step.verdict = Some(verdict);
+        step.test_output = test_output;
+    }
+    let result = advance_bisect(git_state, &mut bisect, &output);
+    git_state.bisect = Some(bisect.clone());
+    result.map(|()| bisect)
+}
+
+/// Record where `git bisect` went, from its `output`, and tell the model: the next
+/// commit to look at, or the first bad commit once found.
+fn advance_bisect(
+    git_state: &mut GitChatState,
+    bisect: &mut BisectState,
+    output: &str,
+) -> Result<(), String> {
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.clone();
+    let directory = directory.as_deref();
+    match bisect::parse_outcome(output)? {
+        BisectOutcome::Testing { steps_left } => {
+            let step = bisect::checked_out_commit(&git_actor_id, directory)?;
+            bisect.steps_left = steps_left;
+            bisect.steps.push(step.clone());
+            let automatic = git_state
+                .assistant_config
+                .bisect
+                .as_ref()
+                .is_some_and(|config| config.test_command.is_some());
+            forward_and_generate(
+                git_state,
+                None,
+                user_message(bisect::step_message(bisect, &step, automatic)),
+                Provenance::Supervisor,
+            )
+        }
+        BisectOutcome::Found { sha } => {
+            let first_bad = match bisect.steps.iter().find(|step| step.sha == sha) {
+                Some(step) => step.clone(),
+                None => {
+                    let subject = git_tools::run_git(
+                        &git_actor_id,
+                        directory,
+                        &["log", "-1", "--format=%s", &sha],
+                    )?;
+                    BisectStep {
+                        sha,
+                        subject: subject.trim().to_string(),
+                        verdict: Some(BisectVerdict::Bad),
+                        test_output: None,
+                    }
+                }
+            };
+            // Back to the commit the bisect started from
+            git_tools::run_git(&git_actor_id, directory, &["bisect", "reset"])?;
+            bisect.phase = BisectPhase::Found;
+            bisect.steps_left = Some(0);
+            bisect.first_bad = Some(first_bad.clone());
+            logging::info(&format!("First bad commit: {}", first_bad.sha));
+            notify(
+                git_state,
+                &GitChatNotification::BisectFinished {
+                    bisect: bisect.clone(),
+                },
+            );
+            forward_and_generate(
+                git_state,
+                None,
+                user_message(bisect::found_message(bisect, &first_bad)),
+                Provenance::Supervisor,
+            )
+        }
+    }
+}
+
+/// Stop the bisect and go back to the commit it started from.
+fn abort_bisect(git_state: &mut GitChatState) -> Result<BisectState, String> {
+    let mut bisect = match &git_state.bisect {
+        Some(bisect)
+            if matches!(
+                bisect.phase,
+                BisectPhase::AwaitingRefs | BisectPhase::Testing
+            ) =>
+        {
+            bisect.clone()
+        }
+        _ => return Err("No bisect is running".to_string()),
+    };
+    if bisect.phase == BisectPhase::Testing {
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        git_tools::run_git(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &["bisect", "reset"],
+        )?;
+    }
+    logging::info("Bisect aborted");
+    bisect.phase = BisectPhase::Aborted;
+    git_state.bisect = Some(bisect.clone());
+    Ok(bisect)
+}
+
+/// Run the configured test command on the commit the model just looked at and apply
+/// its verdict. Without a test command, the verdict waits for `SubmitBisectVerdict`.
+fn track_bisect(git_state: &mut GitChatState) {
+    let test_command = match &git_state.assistant_config.bisect {
+        Some(BisectConfig {
+            test_command: Some(test_command),
+        }) => test_command.clone(),
+        _ => return,
+    };
+    let waiting = git_state
+        .bisect
+        .as_ref()
+        .is_some_and(|bisect| bisect.current().is_some());
+    if !waiting || git_state.halt.is_some() {
+        return;
+    }
+    let result = git_state.exec_tools_actor_id().and_then(|exec_actor_id| {
+        bisect::run_test_command(
+            &exec_actor_id,
+            git_state.current_directory.as_deref(),
+            &test_command,
+        )
+    });
+    let (verdict, output) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            logging::warn(&format!("Could not run the bisect test command: {}", e));
+            git_state.last_error = Some(format!("Bisect test command failed: {}", e));
+            return;
+        }
+    };
+    logging::info(&format!("Bisect test command says {}", verdict.as_str()));
+    if let Err(e) = submit_bisect_verdict(git_state, verdict, Some(output)) {
+        let error_msg = format!("Failed to submit bisect verdict: {}", e);
+        logging::error(&error_msg);
+        git_state.last_error = Some(error_msg);
+    }
+}
+
+/// Pick the planned commits once the model's plan is in, or continue the pick whose
+/// conflicts the model resolved.
+fn track_cherry_pick(git_state: &mut GitChatState) {
+    if git_state.workflow != Some(Workflow::CherryPick) || git_state.halt.is_some() {
+        return;
+    }
+    let mut cherry_pick = match &git_state.cherry_pick {
+        Some(cherry_pick) => cherry_pick.clone(),
+        None => return,
+    };
+    let result = match cherry_pick.phase {
+        CherryPickPhase::Planning => {
+            let plan = match latest_reply(git_state) {
+                Ok(reply) => cherry_pick::parse_plan(&reply),
+                Err(e) => {
+                    logging::warn(&format!("Could not read the cherry-pick plan: {}", e));
+                    return;
+                }
+            };
+            // No plan yet; the model may still be reading or asking
+            let Some(plan) = plan else {
+                return;
+            };
+            match plan.and_then(|plan| cherry_pick.apply_plan(&plan)) {
+                Ok(()) => {
+                    logging::info(&format!(
+                        "Cherry-pick plan accepted for {}",
+                        cherry_pick.source
+                    ));
+                    run_cherry_picks(git_state, &mut cherry_pick)
+                }
+                Err(e) => {
+                    logging::info(&format!("Cherry-pick plan rejected: {}", e));
+                    let message = cherry_pick::invalid_plan_message(&e);
+                    cherry_pick.error = Some(e);
+                    git_state.cherry_pick = Some(cherry_pick);
+                    forward_and_generate(
+                        git_state,
+                        None,
+                        user_message(message),
+                        Provenance::Supervisor,
+                    )
+                    .map_err(|e| format!("Failed to ask for a new cherry-pick plan: {}", e))
+                }
+            }
+        }
+        CherryPickPhase::Conflicted => continue_cherry_pick(git_state, &mut cherry_pick),
+        CherryPickPhase::Picking
+        | CherryPickPhase::Finished
+        | CherryPickPhase::Failed
+        | CherryPickPhase::Aborted => return,
+    };
+    if let Err(e) = result {
+        let error_msg = format!("Cherry-pick failed: {}", e);
+        logging::error(&error_msg);
+        git_state.last_error = Some(error_msg);
+    }
+}
+
+/// Commit the conflicted pick if nothing is left unresolved, then pick the rest.
+fn continue_cherry_pick(
+    git_state: &mut GitChatState,
+    cherry_pick: &mut CherryPickState,
+) -> Result<(), String> {
+    let sha = match cherry_pick.conflicted() {
+        Some(commit) => commit.sha.clone(),
+        None => return run_cherry_picks(git_state, cherry_pick),
+    };
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let outcome = cherry_pick::continue_pick(&git_actor_id, git_state.current_directory.as_deref());
+    if record_pick(git_state, cherry_pick, &sha, outcome)?.is_some() {
+        // Still conflicted; the model may be waiting for the user
+        git_state.cherry_pick = Some(cherry_pick.clone());
+        return Ok(());
+    }
+    run_cherry_picks(git_state, cherry_pick)
+}
+
+/// Pick the pending commits in order until one stops on conflicts, which the model is
+/// asked to resolve. Once every commit is picked or skipped, the model reports.
+fn run_cherry_picks(
+    git_state: &mut GitChatState,
+    cherry_pick: &mut CherryPickState,
+) -> Result<(), String> {
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.clone();
+    while let Some(commit) = cherry_pick.next_pending() {
+        let sha = commit.sha.clone();
+        let outcome = cherry_pick::pick(&git_actor_id, directory.as_deref(), &sha);
+        if let Some(files) = record_pick(git_state, cherry_pick, &sha, outcome)? {
+            git_state.cherry_pick = Some(cherry_pick.clone());
+            let message = match cherry_pick.conflicted() {
+                Some(commit) => cherry_pick::conflict_message(commit, &files),
+                None => return Ok(()),
+            };
+            return forward_and_generate(
+                git_state,
+                None,
+                user_message(message),
+                Provenance::Supervisor,
+            );
+        }
+    }
+    cherry_pick.phase = CherryPickPhase::Finished;
+    logging::info(&format!("Cherry-pick finished: {}", cherry_pick.summary()));
+    git_state.cherry_pick = Some(cherry_pick.clone());
+    notify(
+        git_state,
+        &GitChatNotification::CherryPickFinished {
+            cherry_pick: cherry_pick.clone(),
+        },
+    );
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(cherry_pick::finished_message(cherry_pick)),
+        Provenance::Supervisor,
+    )
+}
+
+/// Record where picking `sha` left it. Returns the conflicted files when it stopped on
+/// conflicts. A pick git refused for any other reason fails the cherry-pick.
+fn record_pick(
+    git_state: &mut GitChatState,
+    cherry_pick: &mut CherryPickState,
+    sha: &str,
+    outcome: Result<PickOutcome, String>,
+) -> Result<Option<Vec<String>>, String> {
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let Some(commit) = cherry_pick
+        .commits
+        .iter_mut()
+        .find(|commit| commit.sha == sha)
+    else {
+        return Err(format!("{} is not one of the commits to pick", sha));
+    };
+    match outcome {
+        Ok(PickOutcome::Picked { sha: picked_sha }) => {
+            logging::info(&format!("Picked {} as {}", commit.sha, picked_sha));
+            commit.status = PickStatus::Picked;
+            commit.picked_sha = Some(picked_sha);
+            cherry_pick.phase = CherryPickPhase::Picking;
+            Ok(None)
+        }
+        Ok(PickOutcome::Empty) => {
+            git_tools::run_git(
+                &git_actor_id,
+                git_state.current_directory.as_deref(),
+                &["cherry-pick", "--skip"],
+            )?;
+            logging::info(&format!("Skipped {}: already on the branch", commit.sha));
+            commit.status = PickStatus::Skipped;
+            commit.reason = Some("its changes are already on the branch".to_string());
+            cherry_pick.phase = CherryPickPhase::Picking;
+            Ok(None)
+        }
+        Ok(PickOutcome::Conflicted { files }) => {
+            logging::info(&format!(
+                "Picking {} stopped on conflicts in {}",
+                commit.sha,
+                files.join(", ")
+            ));
+            commit.status = PickStatus::Conflicted;
+            cherry_pick.phase = CherryPickPhase::Conflicted;
+            Ok(Some(files))
+        }
+        Err(e) => {
+            commit.status = PickStatus::Pending;
+            cherry_pick.phase = CherryPickPhase::Failed;
+            cherry_pick.error = Some(format!("Failed to pick {}: {}", sha, e));
+            git_state.cherry_pick = Some(cherry_pick.clone());
+            notify(
+                git_state,
+                &GitChatNotification::CherryPickFinished {
+                    cherry_pick: cherry_pick.clone(),
+                },
+            );
+            Err(format!("Failed to pick {}: {}", sha, e))
+        }
+    }
+}
+
+/// Skip the pick that stopped on conflicts and pick the rest.
+fn skip_cherry_pick(git_state: &mut GitChatState) -> Result<CherryPickState, String> {
+    let mut cherry_pick = match &git_state.cherry_pick {
+        Some(cherry_pick) if cherry_pick.phase == CherryPickPhase::Conflicted => {
+            cherry_pick.clone()
+        }
+        _ => return Err("No cherry-pick is stopped on conflicts".to_string()),
+    };
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    git_tools::run_git(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &["cherry-pick", "--skip"],
+    )?;
+    if let Some(commit) = cherry_pick.conflicted() {
+        logging::info(&format!("Skipped {} at the user's request", commit.sha));
+        commit.status = PickStatus::Skipped;
+        commit.reason = Some("skipped by the user".to_string());
+    }
+    cherry_pick.phase = CherryPickPhase::Picking;
+    let result = run_cherry_picks(git_state, &mut cherry_pick);
+    result.map(|()| cherry_pick)
+}
+
+/// Stop the cherry-pick. Commits already picked stay; a pick stopped on conflicts is
+/// undone.
+fn abort_cherry_pick(git_state: &mut GitChatState) -> Result<CherryPickState, String> {
+    let mut cherry_pick = match &git_state.cherry_pick {
+        Some(cherry_pick)
+            if matches!(
+                cherry_pick.phase,
+                CherryPickPhase::Planning | CherryPickPhase::Picking | CherryPickPhase::Conflicted
+            ) =>
+        {
+            cherry_pick.clone()
+        }
+        _ => return Err("No cherry-pick is running".to_string()),
+    };
+    if let Some(commit) = cherry_pick.conflicted() {
+        commit.status = PickStatus::Pending;
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        git_tools::run_git(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &["cherry-pick", "--abort"],
+        )?;
+    }
+    logging::info("Cherry-pick aborted");
+    cherry_pick.phase = CherryPickPhase::Aborted;
+    git_state.cherry_pick = Some(cherry_pick.clone());
+    Ok(cherry_pick)
+}
+
+/// Fetch, compare `HEAD` with the upstream, and pick the strategy the `sync` policy
+/// recommends.
+fn propose_sync(git_state: &mut GitChatState) -> Result<SyncState, String> {
+    let config = git_state.assistant_config.sync.clone().unwrap_or_default();
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.clone();
+    sync::fetch(
+        &git_actor_id,
+        directory.as_deref(),
+        config.remote.as_deref(),
+    )
+    .map_err(|e| format!("Failed to fetch: {}", e))?;
+    let upstream = sync::resolve_upstream(&git_actor_id, directory.as_deref(), config.upstream())
+        .map_err(|e| format!("{}: {}", ErrorCode::InvalidRequest, e))?;
+    let bounds = git_state.history_bounds(&git_actor_id);
+    let divergence =
+        divergence::fetch_divergence(&git_actor_id, directory.as_deref(), &upstream, &bounds)
+            .map_err(|e| format!("Failed to compute divergence: {}", e))?;
+    // Only a rebase would rewrite them, and only when there is something to catch up with
+    let published = if divergence.ahead > 0 && divergence.behind > 0 {
+        !rewrite_safety::analyze_rewrite(
+            &git_actor_id,
+            directory.as_deref(),
+            &format!("{}..HEAD", upstream),
+        )?
+        .is_safe()
+    } else {
+        false
+    };
+    let (recommended, reason) = sync::recommend(&config, &divergence, published);
+    logging::info(&format!(
+        "Sync with {}: {} ahead, {} behind, recommending {}",
+        upstream,
+        divergence.ahead,
+        divergence.behind,
+        recommended
+            .map(|strategy| strategy.name())
+            .unwrap_or("nothing")
+    ));
+    Ok(SyncState {
+        phase: match recommended {
+            Some(_) => SyncPhase::Proposed,
+            None => SyncPhase::Finished,
+        },
+        upstream,
+        divergence,
+        published,
+        recommended,
+        reason,
+        strategy: None,
+        original_head: sync::head(&git_actor_id, directory.as_deref())?,
+        conflicted_files: Vec::new(),
+        head: None,
+        error: None,
+    })
+}
+
+/// Hold `ExecuteSync` for `Confirm` when it would rebase published commits and
+/// rewriting them wasn't approved.
+fn hold_published_sync(
+    git_state: &mut GitChatState,
+    request: &GitChatRequest,
+    strategy: Option<SyncStrategy>,
+) -> Result<Option<PendingConfirmation>, String> {
+    let sync = match &git_state.sync {
+        Some(sync) if sync.phase == SyncPhase::Proposed => sync.clone(),
+        // Anything else is refused by `execute_sync`
+        _ => return Ok(None),
+    };
+    if strategy.or(sync.recommended) != Some(SyncStrategy::Rebase)
+        || !sync.published
+        || published_rebase_approved(git_state)
+    {
+        return Ok(None);
+    }
+    let description = format!(
+        "Rebase onto {}, which rewrites local commits that are already on a remote",
+        sync.upstream
+    );
+    Ok(Some(hold_request(
+        git_state,
+        request,
+        rebase_plan::REBASE_PUBLISHED,
+        ActionSeverity::RemoteMutation,
+        DEFAULT_SESSION_ID.to_string(),
+        description,
+    )))
+}
+
+/// Catch up with the upstream by `strategy`, or the recommended one. A stop on conflicts
+/// is handed to the model; otherwise the model reports on the finished sync.
+fn execute_sync(
+    git_state: &mut GitChatState,
+    strategy: Option<SyncStrategy>,
+) -> Result<SyncState, String> {
+    let mut sync = match &git_state.sync {
+        Some(sync) if sync.phase == SyncPhase::Proposed => sync.clone(),
+        _ => return Err("No sync is waiting to be executed".to_string()),
+    };
+    let strategy = strategy
+        .or(sync.recommended)
+        .ok_or_else(|| "HEAD is already up to date".to_string())?;
+    if strategy == SyncStrategy::FastForward && sync.divergence.ahead > 0 {
+        return Err(format!(
+            "HEAD has {} local commits and can't be fast-forwarded",
+            sync.divergence.ahead
+        ));
+    }
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    logging::info(&format!(
+        "Syncing with {} by {}",
+        sync.upstream,
+        strategy.name()
+    ));
+    sync.strategy = Some(strategy);
+    let outcome = sync::run(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        strategy,
+        &sync.upstream,
+    );
+    record_sync(git_state, &mut sync, outcome)?;
+    Ok(sync)
+}
+
+/// Continue the sync whose conflicts the model resolved.
+fn track_sync(git_state: &mut GitChatState) {
+    if git_state.workflow != Some(Workflow::Sync) || git_state.halt.is_some() {
+        return;
+    }
+    let mut sync = match &git_state.sync {
+        Some(sync) if sync.phase == SyncPhase::Conflicted => sync.clone(),
+        _ => return,
+    };
+    let Some(strategy) = sync.strategy else {
+        return;
+    };
+    let result = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        let outcome = sync::continue_sync(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            strategy,
+        );
+        record_sync(git_state, &mut sync, outcome)
+    });
+    if let Err(e) = result {
+        let error_msg = format!("Sync failed: {}", e);
+        logging::error(&error_msg);
+        git_state.last_error = Some(error_msg);
+    }
+}
+
+/// Record where running or continuing the sync left it, and tell the model: the files
+/// to resolve, or that the sync is finished. A merge or rebase git refused for any
+/// other reason fails the sync.
+fn record_sync(
+    git_state: &mut GitChatState,
+    sync: &mut SyncState,
+    outcome: Result<SyncOutcome, String>,
+) -> Result<(), String> {
+    let message = match outcome {
+        Ok(SyncOutcome::Conflicted { files }) => {
+            logging::info(&format!("Sync stopped on conflicts in {:?}", files));
+            // The model already has these files; it may be waiting for the user
+            let unchanged = sync.phase == SyncPhase::Conflicted && sync.conflicted_files == files;
+            sync.phase = SyncPhase::Conflicted;
+            sync.conflicted_files = files;
+            git_state.sync = Some(sync.clone());
+            if unchanged {
+                return Ok(());
+            }
+            sync::conflict_message(sync)
+        }
+        Ok(SyncOutcome::Done { head }) => {
+            logging::info(&format!("Sync with {} finished at {}", sync.upstream, head));
+            sync.phase = SyncPhase::Finished;
+            sync.conflicted_files.clear();
+            sync.head = Some(head);
+            git_state.sync = Some(sync.clone());
+            sync::finished_message(sync)
+        }
+        Err(e) => {
+            sync.phase = SyncPhase::Failed;
+            sync.error = Some(e.clone());
+            git_state.sync = Some(sync.clone());
+            return Err(e);
+        }
+    };
+    forward_and_generate(
+        git_state,
+        None,
+        user_message(message),
+        Provenance::Supervisor,
+    )
+}
+
+/// Stop the sync. A merge or rebase stopped on conflicts is undone, back to the
+/// original `HEAD`.
+fn abort_sync(git_state: &mut GitChatState) -> Result<SyncState, String> {
+    let mut sync = match &git_state.sync {
+        Some(sync) if matches!(sync.phase, SyncPhase::Proposed | SyncPhase::Conflicted) => {
+            sync.clone()
+        }
+        _ => return Err("No sync is running".to_string()),
+    };
+    if let (SyncPhase::Conflicted, Some(strategy)) = (sync.phase, sync.strategy) {
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        sync::abort(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            strategy,
+        )?;
+    }
+    logging::info("Sync aborted");
+    sync.phase = SyncPhase::Aborted;
+    sync.conflicted_files.clear();
+    git_state.sync = Some(sync.clone());
+    Ok(sync)
+}
+
+/// Keep the security findings with a known CWE id and an exploitability note, and merge
+/// them into the report of the latest `ReviewRefs`.
+fn record_security_findings(
+    git_state: &mut GitChatState,
+    findings: Vec<review::SecurityFinding>,
+) -> (Vec<review::SecurityFinding>, Vec<review::RejectedFinding>) {
+    let (accepted, rejected) = review::validate_findings(findings);
+    logging::info(&format!(
+        "Validated security findings: {} accepted, {} rejected",
+        accepted.len(),
+        rejected.len()
+    ));
+    for rejection in &rejected {
+        logging::warn(&format!("Rejected finding: {}", rejection.reason));
+    }
+    for finding in &accepted {
+        if !git_state
+            .security_findings
+            .iter()
+            .any(|existing| existing.fingerprint() == finding.fingerprint())
+        {
+            git_state.security_findings.push(finding.clone());
+        }
+    }
+    if let Some(report) = git_state
+        .active_review
+        .clone()
+        .and_then(|branch| git_state.review_reports.get_mut(&branch))
+    {
+        let added = report.merge_findings(&accepted);
+        logging::info(&format!("Merged {} new findings into {}", added, report.id));
+    }
+    (accepted, rejected)
+}
+
+/// Check the findings a security review's reply ends with, as `SubmitSecurityFindings`
+/// does.
+fn track_security_findings(git_state: &mut GitChatState) {
+    let profile = git_state
+        .assistant_config
+        .review
+        .as_ref()
+        .map(|review| review.profile);
+    if git_state.workflow != Some(Workflow::Review)
+        || profile != Some(review::ReviewProfile::Security)
+    {
+        return;
+    }
+    let findings = match latest_reply(git_state) {
+        Ok(reply) => review::parse_security_findings(&reply),
+        Err(e) => {
+            logging::warn(&format!("Could not read the security findings: {}", e));
+            return;
+        }
+    };
+    match findings {
+        Some(Ok(findings)) => {
+            record_security_findings(git_state, findings);
+        }
+        Some(Err(e)) => logging::warn(&format!("Ignoring the security findings: {}", e)),
+        // The review isn't written up yet
+        None => {}
+    }
+}
+
+/// Keep the well-formed findings a benchmarked performance review's reply ends with, and
+/// merge them into the report of the latest `ReviewRefs`.
+fn track_benchmark_findings(git_state: &mut GitChatState) {
+    let benchmarked = git_state
+        .assistant_config
+        .review
+        .as_ref()
+        .is_some_and(ReviewConfig::needs_exec_tools);
+    if git_state.workflow != Some(Workflow::Review) || !benchmarked {
+        return;
+    }
+    let findings = match latest_reply(git_state) {
+        Ok(reply) => review::parse_benchmark_findings(&reply),
+        Err(e) => {
+            logging::warn(&format!("Could not read the benchmark findings: {}", e));
+            return;
+        }
+    };
+    let findings = match findings {
+        Some(Ok(findings)) => findings,
+        Some(Err(e)) => {
+            logging::warn(&format!("Ignoring the benchmark findings: {}", e));
+            return;
+        }
+        // The review isn't written up yet
+        None => return,
+    };
+    let (accepted, rejected) = review::check_benchmark_findings(findings);
+    for reason in &rejected {
+        logging::warn(&format!("Dropped benchmark finding: {}", reason));
+    }
+    for finding in &accepted {
+        if !git_state
+            .benchmark_findings
+            .iter()
+            .any(|existing| existing.fingerprint() == finding.fingerprint())
+        {
+            git_state.benchmark_findings.push(finding.clone());
+        }
+    }
+    logging::info(&format!(
+        "Benchmark findings: {} accepted, {} dropped",
+        accepted.len(),
+        rejected.len()
+    ));
+    if let Some(report) = git_state
+        .active_review
+        .clone()
+        .and_then(|branch| git_state.review_reports.get_mut(&branch))
+    {
+        let added = report.merge_benchmark_findings(&accepted);
+        logging::info(&format!(
+            "Merged {} new benchmark findings into {}",
+            added, report.id
+        ));
+    }
+}
+
+/// Keep the findings the model submitted with `submit_review`, and merge them into the
+/// report of the latest `ReviewRefs`.
+fn record_review_findings(git_state: &mut GitChatState, findings: Vec<ReviewFinding>) {
+    let (accepted, rejected) = review::check_review_findings(findings);
+    for reason in &rejected {
+        logging::warn(&format!("Dropped review finding: {}", reason));
+    }
+    for finding in &accepted {
+        if !git_state
+            .review_findings
+            .iter()
+            .any(|existing| existing.fingerprint() == finding.fingerprint())
+        {
+            git_state.review_findings.push(finding.clone());
+        }
+    }
+    logging::info(&format!(
+        "Review findings submitted: {} accepted, {} dropped",
+        accepted.len(),
+        rejected.len()
+    ));
+    if let Some(report) = git_state
+        .active_review
+        .clone()
+        .and_then(|branch| git_state.review_reports.get_mut(&branch))
+    {
+        let added = report.merge_review_findings(&accepted);
+        logging::info(&format!(
+            "Merged {} new review findings into {}",
+            added, report.id
+        ));
+    }
+}
+
+/// Review `branch` (the current branch when omitted) against `base`. A branch reviewed
+/// before is only reviewed from the last reviewed commit, unless it was rewritten since.
+fn review_refs(
+    git_state: &mut GitChatState,
+    branch: Option<String>,
+    base: Option<String>,
+) -> Result<ReviewReport, String> {
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let directory = git_state.current_directory.clone();
+    let directory = directory.as_deref();
+    let branch = match branch {
+        Some(branch) => branch,
+        None => git_tools::run_git(
+            &git_actor_id,
+            directory,
+            &["rev-parse", "--abbrev-ref", "HEAD"],
+        )?
+        .trim()
+        .to_string(),
+    };
+    let previous = git_state.review_reports.get(&branch).cloned();
+    let base = base
+        .or_else(|| previous.as_ref().map(|report| report.base.clone()))
+        .unwrap_or_else(|| review_report::DEFAULT_REVIEW_BASE.to_string());
+    git_tools::ensure_revision("base", &base)?;
+
+    let scope = review_report::review_scope(&git_actor_id, directory, &branch, previous.as_ref())?;
+    if let Some(previous) = previous
+        .as_ref()
+        .filter(|report| report.last_reviewed_sha == scope.tip)
+    {
+        logging::info(&format!(
+            "Branch {} has no commits since its last review",
+            branch
+        ));
+        git_state.active_review = Some(branch);
+        return Ok(previous.clone());
+    }
+
+    let range = match &scope.from {
+        Some(from) => format!("{}..{}", from, scope.tip),
+        None => format!("{}..{}", base, scope.tip),
+    };
+    let (commits, overview) =
+        review_report::fetch_range_overview(&git_actor_id, directory, &range)?;
+    let message =
+        review_report::review_message(&branch, &range, &overview, &scope, previous.as_ref());
+    forward_and_generate(git_state, None, user_message(message), Provenance::Workflow)?;
+
+    let mut report = match previous {
+        Some(report) => report,
+        None => {
+            let id = format!("review-{}", git_state.next_review_number);
+            git_state.next_review_number += 1;
+            ReviewReport {
+                id,
+                branch: branch.clone(),
+                base: base.clone(),
+                last_reviewed_sha: String::new(),
+                rounds: Vec::new(),
+                findings: Vec::new(),
+                review_findings: Vec::new(),
+                benchmark_findings: Vec::new(),
+            }
+        }
+    };
+    report.base = base;
+    report.rounds.push(ReviewRound {
+        from: scope.from,
+        to: scope.tip.clone(),
+        commits,
+        after_force_push: scope.after_force_push,
+    });
+    report.last_reviewed_sha = scope.tip;
+    logging::info(&format!(
+        "Started review round {} of {} ({}, {} commits)",
+        report.rounds.len(),
+        branch,
+        range,
+        commits
+    ));
+    git_state
+        .review_reports
+        .insert(branch.clone(), report.clone());
+    git_state.active_review = Some(branch);
+    Ok(report)
+}
+
+/// Post the findings of review report `report_id` that haven't been posted yet to the
+/// branch's pull request, up to the forge's comment limit.
+fn publish_review(
+    git_state: &mut GitChatState,
+    report_id: &str,
+    mode: PublishMode,
+) -> Result<PublishResult, String> {
+    let forge = git_state
+        .assistant_config
+        .forge_mcp
+        .clone()
+        .ok_or("Publishing reviews requires forge_mcp")?;
+    let report = git_state
+        .review_reports
+        .values()
+        .find(|report| report.id == report_id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown review report: {}", report_id))?;
+    let posted = git_state
+        .posted_review_comments
+        .get(report_id)
+        .cloned()
+        .unwrap_or_default();
+    let (comments, already_posted, remaining) =
+        review_report::pending_comments(&report, &posted, mode, forge.max_review_comments);
+    let mut result = PublishResult {
+        report_id: report_id.to_string(),
+        mode,
+        change_request: None,
+        comments: Vec::new(),
+        already_posted,
+        remaining,
+    };
+    if mode == PublishMode::DryRun || comments.is_empty() {
+        result.comments = comments;
+        return Ok(result);
+    }
+
+    let git_actor_id = git_state.git_tools_actor_id()?;
+    let base = git_tools::run_git(
+        &git_actor_id,
+        git_state.current_directory.as_deref(),
+        &["merge-base", &report.base, &report.last_reviewed_sha],
+    )?
+    .trim()
+    .to_string();
+    let forge_actor_id = git_state.forge_tools_actor_id(&forge)?;
+    let number = forge_tools::find_change_request(&forge_actor_id, &forge, &report.branch)?;
+    result.change_request = Some(number);
+
+    // Record each batch as it goes out, so a failure part way doesn't post it twice
+    for batch in comments.chunks(forge_tools::COMMENTS_PER_BATCH) {
+        forge_tools::post_review_batch(
+            &forge_actor_id,
+            &forge,
+            number,
+            &base,
+            &report.last_reviewed_sha,
+            batch,
+        )?;
+        git_state
+            .posted_review_comments
+            .entry(report_id.to_string())
+            .or_default()
+            .extend(batch.iter().map(|comment| comment.fingerprint.clone()));
+        result.comments.extend(batch.iter().cloned());
+    }
+    logging::info(&format!(
+        "Posted {} review comments to {} {}, {} remaining",
+        result.comments.len(),
+        forge.provider.change_request(),
+        number,
+        remaining
+    ));
+    Ok(result)
+}
+
+/// Values for the commit template: branch context values, overridden by
+/// `template_vars`, overridden by those sent with `SetTemplateVars`.
+fn template_values(git_state: &GitChatState) -> BTreeMap<String, String> {
+    let mut values = git_state
+        .branch_context
+        .as_ref()
+        .map(|context| context.values.clone())
+        .unwrap_or_default();
+    values.extend(git_state.assistant_config.template_vars.clone());
+    values.extend(git_state.template_vars.clone());
+    values
+}
+
+/// Resolve the variables of `commit_convention` and fill in its trailers.
+fn resolve_commit_trailers(
+    git_state: &mut GitChatState,
+    commit_convention: &CommitConvention,
+) -> Result<Vec<String>, String> {
+    let directory = git_state.current_directory.clone();
+    // Branch context values can be used without declaring them; declared variables win
+    let mut values = git_state
+        .branch_context
+        .as_ref()
+        .map(|context| context.values.clone())
+        .unwrap_or_default();
+    for (name, variable) in &commit_convention.variables {
+        let value = match variable {
+            TemplateVariable::Value(value) => value.clone(),
+            TemplateVariable::Env(env) => {
+                // The name goes into a shell command
+                let valid = !env.is_empty()
+                    && !env.starts_with(|c: char| c.is_ascii_digit())
+                    && env.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !valid {
+                    return Err(format!("Invalid environment variable name: {}", env));
+                }
+                let exec_actor_id = git_state.exec_tools_actor_id()?;
+                let value = exec_tools::run_command(
+                    &exec_actor_id,
+                    directory.as_deref(),
+                    &format!("printenv {} || true", env),
+                )?;
+                let value = value.trim().to_string();
+                if value.is_empty() {
+                    return Err(format!("Environment variable {} is not set", env));
+                }
+                value
+            }
+            TemplateVariable::BranchPattern(pattern) => {
+                let git_actor_id = git_state.git_tools_actor_id()?;
+                let branch = git_tools::run_git(
+                    &git_actor_id,
+                    directory.as_deref(),
+                    &["rev-parse", "--abbrev-ref", "HEAD"],
+                )?;
+                TemplateVariable::match_branch(pattern, branch.trim())?
+            }
+        };
+        logging::debug(&format!("Resolved trailer variable {}: {}", name, value));
+        values.insert(name.clone(), value);
+    }
+    commit_convention.fill_trailers(&values)
+}
+
+/// Build the auto-initiation message for `workflow`, running the repository checks
+/// the workflow depends on first.
+fn build_auto_message(git_state: &mut GitChatState, workflow: &Workflow) -> Result<String, String> {
+    let prompts = git_state
+        .assistant_config
+        .prompts
+        .clone()
+        .unwrap_or_default();
+    let values = prompts.values(git_state.current_directory.as_deref(), Some(workflow));
+    let mut auto_message = prompts
+        .auto_message(workflow, &values, git_state.workflow_args.as_ref())
+        .map_err(|e| format!("{}: {}", ErrorCode::InvalidRequest, e))?;
+
+    // A pipeline step hears what the step before it did
+    if let Some(context) = git_state
+        .pipeline
+        .as_ref()
+        .and_then(PipelineRun::previous_step_context)
+    {
+        auto_message.push_str(&context);
+    }
+    if let Some(source) = &git_state.delegated_by {
+        auto_message.push_str(&delegation::task_context(source));
+    }
+
+    // Files outside a sparse checkout aren't there to list or stage
+    let sparse = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        sparse::read(&git_actor_id, git_state.current_directory.as_deref())
+    });
+    match sparse {
+        Ok(sparse) if sparse.enabled => auto_message.push_str(&sparse::prompt_fragment(&sparse)),
+        Ok(_) => {}
+        Err(e) => logging::warn(&format!("Could not read the sparse checkout: {}", e)),
+    }
+
+    // Remember where the session started so new commits can be checked and reported
+    let head = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        git_tools::run_git(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &["rev-parse", "HEAD"],
+        )
+    });
+    match head {
+        Ok(head) => git_state.commit_base = Some(head.trim().to_string()),
+        // Repositories without commits have no HEAD yet
+        Err(e) => logging::warn(&format!("Could not record commit base: {}", e)),
+    }
+    let branch = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        git_tools::run_git(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &["rev-parse", "--abbrev-ref", "HEAD"],
+        )
+    });
+    git_state.workflow_branch = branch.ok().map(|branch| branch.trim().to_string());
+
+    // Metadata the commits must carry, resolved here so the model only copies it
+    if let (Workflow::Commit, Some(commit_convention)) = (
+        workflow,
+        git_state.assistant_config.commit_convention.clone(),
+    ) {
+        if !commit_convention.trailers.is_empty() {
+            let trailers = resolve_commit_trailers(git_state, &commit_convention)
+                .map_err(|e| format!("Failed to resolve commit trailers: {}", e))?;
+            auto_message.push_str(&format!(
+                "\n\nCOMMIT TRAILERS: End every commit message with these lines, exactly as \
+                written:\n{}",
+                trailers.join("\n")
+            ));
+            git_state.commit_trailers = trailers;
+        }
+    }
+
+    // The template with every placeholder filled, so the model only copies the values
+    if let (Workflow::Commit, Some(template)) =
+        (workflow, git_state.assistant_config.commit_template.clone())
+    {
+        auto_message.push_str(&format!(
+            "\n\nCOMMIT TEMPLATE: With its values filled in, the template is:\n{}",
+            commit_template::render(&template, &template_values(git_state))
         ));
-        Ok((state,))
     }
 
-    fn handle_channel_message(
-        state: Option<Vec<u8>>,
-        params: (String, Vec<u8>),
-    ) -> Result<(Option<Vec<u8>>,), String> {
-        let (channel_id, _message) = params;
-        log(&format!(
-            "Git chat assistant: Received channel message on: {}",
-            channel_id
+    // Warn up front when the pending changes can't go into a single commit
+    if let (Workflow::Commit, Some(commit_size)) =
+        (workflow, git_state.assistant_config.commit_size.clone())
+    {
+        let size = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+            commit_size::fetch_worktree_size(&git_actor_id, git_state.current_directory.as_deref())
+        });
+        match size {
+            Ok(size) => {
+                let exceeded = commit_size.exceeded(&size);
+                if !exceeded.is_empty() {
+                    logging::info(&format!(
+                        "Pending changes exceed commit size limits: {:?}",
+                        exceeded
+                    ));
+                    auto_message.push_str(&format!(
+                        "\n\nNOTE: The pending changes ({}) are over the commit size limits: {}. \
+                        Follow the commit size rules in your instructions.",
+                        size.describe(),
+                        exceeded.join(", ")
+                    ));
+                }
+            }
+            Err(e) => logging::warn(&format!("Could not measure pending changes: {}", e)),
+        }
+    }
+
+    // Submodules need explaining, and maybe committing inside, before their pointers move
+    if *workflow == Workflow::Commit {
+        let changes = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+            submodule::fetch_changes(&git_actor_id, git_state.current_directory.as_deref())
+        });
+        match changes {
+            Ok(changes) if !changes.is_empty() => {
+                logging::info(&format!("{} submodules changed", changes.len()));
+                let config = git_state
+                    .assistant_config
+                    .submodules
+                    .clone()
+                    .unwrap_or_default();
+                auto_message.push_str(&submodule::prompt_fragment(
+                    &changes,
+                    &config,
+                    submodule_bumps_allowed(git_state),
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => logging::warn(&format!("Could not check submodules: {}", e)),
+        }
+    }
+
+    // Enforce the dirty-worktree policy before handing over to the model
+    if workflow.requires_clean_tree() {
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        if let Some(note) = repo_state::preflight(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            workflow,
+            git_state.dirty_worktree_policy,
+        )? {
+            auto_message.push_str(&note);
+        }
+    }
+
+    // The bisect starts once the user has given the refs
+    if *workflow == Workflow::Bisect {
+        match &git_state.bisect {
+            Some(bisect) if bisect.phase == BisectPhase::Testing => {
+                return Err("A bisect is already running; abort it first".to_string())
+            }
+            _ => git_state.bisect = Some(BisectState::default()),
+        }
+    }
+
+    // List the commits to pick; the model plans the order before anything is picked
+    if *workflow == Workflow::CherryPick {
+        if let Some(cherry_pick) = &git_state.cherry_pick {
+            if cherry_pick.phase == CherryPickPhase::Conflicted {
+                return Err("A cherry-pick is stopped on conflicts; abort it first".to_string());
+            }
+        }
+        let source = CherryPickSource::from_args(git_state.workflow_args.as_ref())
+            .map_err(|e| format!("{}: {}", ErrorCode::InvalidRequest, e))?;
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        let commits = cherry_pick::fetch_commits(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &source,
+        )
+        .map_err(|e| format!("Failed to list the commits to pick: {}", e))?;
+        logging::info(&format!(
+            "Cherry-picking {} commits from {}",
+            commits.len(),
+            source.describe()
         ));
-        Ok((state,))
+        let cherry_pick = CherryPickState {
+            phase: CherryPickPhase::Planning,
+            source: source.describe(),
+            commits,
+            error: None,
+        };
+        auto_message.push_str(&cherry_pick::describe(&cherry_pick));
+        git_state.cherry_pick = Some(cherry_pick);
+    }
+
+    // Fetch and recommend a strategy; nothing changes until ExecuteSync is confirmed
+    if *workflow == Workflow::Sync {
+        if let Some(sync) = &git_state.sync {
+            if sync.phase == SyncPhase::Conflicted {
+                return Err("A sync is stopped on conflicts; abort it first".to_string());
+            }
+        }
+        let sync = propose_sync(git_state)?;
+        auto_message.push_str(&sync::describe(&sync));
+        git_state.sync = Some(sync);
+    }
+
+    // List the stashes to triage; the same list is checked when the workflow completes
+    if *workflow == Workflow::StashTriage {
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        let directory = git_state.current_directory.clone();
+        let stashes = stash::fetch_stashes(&git_actor_id, directory.as_deref())
+            .map_err(|e| format!("Failed to list stashes: {}", e))?;
+        logging::info(&format!("Triaging {} stashes", stashes.len()));
+        auto_message.push_str(&stash::describe_stashes(
+            &git_actor_id,
+            directory.as_deref(),
+            &stashes,
+        ));
+        git_state.stash_triage = Some(stashes);
+        git_state.stash_reprompted = false;
+    }
+
+    // Hand over the hook output; the staged files are checked when the workflow completes
+    if *workflow == Workflow::HookReview {
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        let staged =
+            hook_review::fetch_staged(&git_actor_id, git_state.current_directory.as_deref())
+                .map_err(|e| format!("Failed to list the staged files: {}", e))?;
+        let config = git_state
+            .assistant_config
+            .hook_review
+            .clone()
+            .unwrap_or_default();
+        let policy = git_state
+            .assistant_config
+            .tool_output
+            .clone()
+            .unwrap_or_default();
+        logging::info(&format!(
+            "Reviewing {} hook failures for {} staged files",
+            config.hook,
+            staged.len()
+        ));
+        auto_message.push_str(&hook_review::describe(&config, &staged, &policy));
+        git_state.hook_review_files = Some(staged);
+        git_state.hook_review_reprompted = false;
+    }
+
+    // Hand over the blame and history of the lines to explain
+    if *workflow == Workflow::BlameExplain {
+        let target = blame_explain::BlameTarget::from_args(git_state.workflow_args.as_ref())
+            .map_err(|e| format!("{}: {}", ErrorCode::InvalidRequest, e))?;
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        let (blame, log) = blame_explain::fetch_history(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &target,
+        )
+        .map_err(|e| format!("Failed to read the history of {}: {}", target.file, e))?;
+        let policy = git_state
+            .assistant_config
+            .tool_output
+            .clone()
+            .unwrap_or_default();
+        logging::info(&format!("Explaining {}", target.describe_range()));
+        auto_message.push_str(&blame_explain::describe(
+            &target,
+            blame.as_deref(),
+            &log,
+            &policy,
+        ));
+    }
+
+    // Hand over what the history says about the repository
+    if *workflow == Workflow::Onboard {
+        let since = onboard::since_from_args(git_state.workflow_args.as_ref());
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        let overview = onboard::fetch_overview(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &since,
+        )
+        .map_err(|e| format!("Failed to read the repository history: {}", e))?;
+        logging::info(&format!(
+            "Onboarding from {} commits since {}",
+            overview.commits, since
+        ));
+        auto_message.push_str(&overview.describe());
+    }
+
+    // Show the model the commit and what could be folded into it
+    if *workflow == Workflow::Amend {
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        let context =
+            amend::fetch_amend_context(&git_actor_id, git_state.current_directory.as_deref())
+                .map_err(|e| format!("Failed to read the last commit: {}", e))?;
+        if context.is_published() && !amend_approved(git_state) {
+            return Err(format!(
+                "{}: Cannot amend {}: it is already on {}; confirm the amend or set \
+                allow_published_rewrite",
+                ErrorCode::PolicyViolation,
+                context.short_sha(),
+                context.remote_branches.join(", ")
+            ));
+        }
+        auto_message.push_str(&context.render());
+    }
+
+    // Propose the branches to delete; only these can be deleted later
+    if *workflow == Workflow::BranchCleanup {
+        let config = git_state
+            .assistant_config
+            .branch_cleanup
+            .clone()
+            .unwrap_or_default();
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        let candidates = branch_cleanup::find_candidates(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &config,
+            Some(clock::now()),
+        )
+        .map_err(|e| format!("Failed to list branches: {}", e))?;
+        logging::info(&format!(
+            "Proposing {} branches for deletion",
+            candidates.len()
+        ));
+        auto_message.push_str(&branch_cleanup::describe_candidates(&config, &candidates));
+        let cleanup = BranchCleanup {
+            candidates,
+            deleted: Vec::new(),
+            failures: Vec::new(),
+        };
+        git_state.branch_cleanup = Some(cleanup.clone());
+        notify(
+            git_state,
+            &GitChatNotification::BranchCleanupProposed { cleanup },
+        );
     }
+
+    // Propose the next version; only this tag can be created later
+    if *workflow == Workflow::TagAndRelease {
+        let config = git_state
+            .assistant_config
+            .release
+            .clone()
+            .unwrap_or_default();
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        let release = release::plan(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &config,
+        )
+        .map_err(|e| format!("Failed to plan the release: {}", e))?;
+        logging::info(&format!(
+            "Proposing release {} from {} commits",
+            release.tag,
+            release.commits.len()
+        ));
+        auto_message.push_str(&release::describe(&release));
+        git_state.release = Some(release.clone());
+        notify(git_state, &GitChatNotification::ReleaseProposed { release });
+    }
+
+    // Give the model the branch's commits so it knows what the PR covers
+    if *workflow == Workflow::PrDescription {
+        let base = git_state
+            .assistant_config
+            .pr_description
+            .clone()
+            .unwrap_or_default()
+            .base_branch;
+        let git_actor_id = git_state.git_tools_actor_id()?;
+        let overview = pr_description::fetch_branch_overview(
+            &git_actor_id,
+            git_state.current_directory.as_deref(),
+            &base,
+        )
+        .map_err(|e| format!("Failed to compare the branch with {}: {}", base, e))?;
+        auto_message.push_str(&overview);
+    }
+
+    // Point the model at the release the changelog starts from
+    if *workflow == Workflow::Changelog {
+        let changelog_config = git_state
+            .assistant_config
+            .changelog
+            .clone()
+            .unwrap_or_default();
+        let last_tag = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+            changelog::fetch_last_tag(
+                &git_actor_id,
+                git_state.current_directory.as_deref(),
+                &changelog_config,
+            )
+        });
+        match last_tag {
+            Ok(Some(tag)) => {
+                logging::info(&format!("Drafting changelog since {}", tag));
+                auto_message.push_str(&format!(
+                    "\n\nLAST RELEASE TAG: {} (read git log {}..HEAD)",
+                    tag, tag
+                ));
+            }
+            Ok(None) => {
+                logging::info("No release tag found");
+                auto_message
+                    .push_str("\n\nNOTE: No release tag was found; cover the whole history.");
+            }
+            Err(e) => logging::warn(&format!("Could not find the last release tag: {}", e)),
+        }
+    }
+
+    let upstream = git_state
+        .assistant_config
+        .divergence_watch
+        .as_ref()
+        .map(|watch| watch.upstream().to_string())
+        .unwrap_or_else(|| divergence::DEFAULT_UPSTREAM.to_string());
+
+    // Refuse to rewrite commits others may have pulled unless explicitly allowed
+    if workflow.rewrites_history() {
+        let range = format!("{}..HEAD", upstream);
+        let analysis = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+            rewrite_safety::analyze_rewrite(
+                &git_actor_id,
+                git_state.current_directory.as_deref(),
+                &range,
+            )
+        });
+        match analysis {
+            Ok(analysis) if analysis.is_safe() => {}
+            Ok(analysis) if git_state.assistant_config.allow_published_rewrite => {
+                logging::info(&format!(
+                    "Rewriting published commits: {}",
+                    analysis.describe()
+                ));
+                auto_message.push_str(&format!(
+                    "\n\nNOTE: {}. Rewriting them is allowed, but publishing the result \
+                    needs a force push; mention this in your summary.",
+                    analysis.describe()
+                ));
+            }
+            Ok(analysis) => {
+                return Err(format!(
+                    "{}: Cannot start {} workflow: {}; set allow_published_rewrite to override",
+                    ErrorCode::PolicyViolation,
+                    workflow.name(),
+                    analysis.describe()
+                ))
+            }
+            // Without an upstream there is no range to check
+            Err(e) => logging::warn(&format!("Could not check for published commits: {}", e)),
+        }
+    }
+
+    // Tell the model how far the branch is from the branch it will be rebased onto
+    if *workflow == Workflow::Rebase {
+        let divergence = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+            let bounds = git_state.history_bounds(&git_actor_id);
+            divergence::fetch_divergence(
+                &git_actor_id,
+                git_state.current_directory.as_deref(),
+                &upstream,
+                &bounds,
+            )
+        });
+        match divergence {
+            Ok(divergence) => {
+                auto_message.push_str(&format!("\n\nDIVERGENCE:\n{}", divergence.describe()))
+            }
+            // Branches without an upstream are common before the first push
+            Err(e) => logging::warn(&format!(
+                "Could not compute divergence from {}: {}",
+                upstream, e
+            )),
+        }
+    }
+
+    // Give the model the conflicted files up front so it doesn't have to discover them
+    if workflow.needs_conflict_context() {
+        let conflicts = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+            repo_state::conflicted_files(&git_actor_id, git_state.current_directory.as_deref())
+        });
+        match conflicts {
+            Ok(files) if files.is_empty() => {
+                logging::info("No conflicted files found");
+                auto_message.push_str("\n\nNOTE: git reports no conflicted files right now.");
+            }
+            Ok(files) => {
+                logging::info(&format!("Injecting {} conflicted files", files.len()));
+                auto_message.push_str("\n\nCONFLICTED FILES:");
+                for file in files {
+                    let file = sanitize::escape(&file, sanitize::TextKind::Name);
+                    auto_message.push_str(&format!("\n- {}", file));
+                }
+            }
+            Err(e) => {
+                // The model can still discover the conflicts itself
+                logging::warn(&format!("Could not list conflicted files: {}", e));
+            }
+        }
+    }
+
+    // A compact summary replaces the model dumping the full status and diff stats
+    if workflow.wants_change_summary() {
+        let change_summary = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+            let directory = git_state.current_directory.as_deref();
+            summary::fetch_change_summary(&git_actor_id, directory)
+                .map(|change_summary| (git_actor_id, change_summary))
+        });
+        match change_summary {
+            Ok((git_actor_id, change_summary)) => {
+                logging::info(&format!(
+                    "Injecting change summary for {} files",
+                    change_summary.file_count()
+                ));
+                auto_message.push_str("\n\n");
+                auto_message.push_str(&change_summary.render());
+                auto_message.push_str(
+                    "\nUse this summary instead of dumping the full status or diff; \
+                    only request diffs for the files you need to inspect.",
+                );
+
+                if workflow.wants_language_annotation() {
+                    let directory = git_state.current_directory.as_deref();
+                    let mut lookups = 0;
+                    let groups = language::group_by_language(change_summary.paths(), |path| {
+                        // Files without an extension may still declare a shebang
+                        if lookups >= MAX_SHEBANG_LOOKUPS {
+                            return None;
+                        }
+                        lookups += 1;
+                        let blob = format!(":{}", path);
+                        git_tools::run_git(&git_actor_id, directory, &["show", &blob])
+                            .ok()
+                            .and_then(|content| content.lines().next().map(str::to_string))
+                    });
+                    logging::info(&format!("Detected {} languages in changes", groups.len()));
+                    let annotation = language::render_annotation(&groups);
+                    if !annotation.is_empty() {
+                        auto_message.push_str("\n\n");
+                        auto_message.push_str(&annotation);
+                    }
+
+                    if groups.contains_key(&language::Language::Rust) {
+                        if let Some(api_diff) = fetch_api_diff_context(git_state) {
+                            auto_message.push_str("\n\n");
+                            auto_message.push_str(&api_diff);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                logging::warn(&format!("Could not summarize changes: {}", e));
+            }
+        }
+    }
+
+    // The pre-pass's file summaries stand in for reading a huge diff
+    if let Some(diff_summary) = &git_state.diff_summary {
+        auto_message.push_str(&diff_summary.prompt_fragment());
+    }
+
+    Ok(auto_message)
+}
+
+/// Whether completion is read from a sentinel in the model's replies: when configured,
+/// or when the configured MCP servers leave out the task monitor.
+fn uses_completion_sentinel(config: &GitAssistantConfig) -> bool {
+    let detector = config.completion.clone().unwrap_or_default().detector;
+    let is_task_monitor = |server: &Value| {
+        server["actor"]["manifest_path"].as_str() == Some(TASK_MONITOR_MANIFEST_PATH)
+    };
+    let has_task_monitor = match config.mcp_servers.as_ref().and_then(Value::as_array) {
+        Some(servers) => servers.iter().any(is_task_monitor),
+        None => !mcp_servers::is_disabled(
+            &config.disable_default_mcp_servers,
+            mcp_servers::TASK_MONITOR,
+        ),
+    } || config.extra_mcp_servers.iter().any(is_task_monitor);
+    detector == CompletionDetector::Sentinel || !has_task_monitor
+}
+
+/// Whether the assistant spawns the task monitor itself: when configured to, and the
+/// sessions use the default one.
+fn supervises_task_monitor(config: &GitAssistantConfig) -> bool {
+    config.supervise_task_monitor
+        && config.mcp_servers.is_none()
+        && !uses_completion_sentinel(config)
+}
+
+/// Init state of the model's git tools actor: the commit identity overrides, if any.
+fn git_mcp_init_state(config: &GitAssistantConfig) -> Option<Value> {
+    let commit_identity = config.commit_identity.clone().unwrap_or_default();
+    (!commit_identity.is_empty()).then(|| {
+        serde_json::json!({
+            "commit_env": commit_identity.commit_env(),
+        })
+    })
+}
+
+/// Answer to a git tool call the assistant refuses, given to the model as the tool's
+/// error.
+fn tool_refusal(text: &str) -> McpResponse {
+    McpResponse {
+        jsonrpc: "2.0".to_string(),
+        id: String::new(),
+        result: Some(serde_json::json!({
+            "content": [{"type": "text", "text": text}],
+            "isError": true
+        })),
+        error: None,
+    }
+}
+
+/// Refusal of a commit or branch the model would write hidden characters into.
+fn refuse_hidden_characters(name: &str, args: &Value) -> Option<McpResponse> {
+    let e = sanitize::check_git_tool_call(name, args).err()?;
+    logging::warn(&format!("Refusing a {} call with {}", name, e));
+    Some(tool_refusal(&format!(
+        "Refused: the arguments contain {}. Use only visible characters.",
+        e
+    )))
+}
+
+/// Refusal of a commit while the staged files break the `large_files` policy, relayed
+/// to `notify_actor` and the parent as `FilePolicyViolated`.
+fn refuse_large_files(
+    git_state: &mut GitChatState,
+    name: &str,
+    args: &Value,
+) -> Option<McpResponse> {
+    let policy = git_state
+        .assistant_config
+        .large_files
+        .clone()
+        .filter(LargeFilePolicy::is_enforced)?;
+    if !large_files::is_commit_call(name, args) {
+        return None;
+    }
+    let staged = git_state.git_tools_actor_id().and_then(|git_actor_id| {
+        large_files::fetch_staged(&git_actor_id, git_state.current_directory.as_deref())
+    });
+    let violations = match staged {
+        Ok(staged) => policy.violations(&staged),
+        Err(e) => {
+            logging::warn(&format!("Could not check the staged files: {}", e));
+            return None;
+        }
+    };
+    if violations.is_empty() {
+        return None;
+    }
+    logging::info(&format!(
+        "Refusing a commit of {} files the large_files policy refuses",
+        violations.len()
+    ));
+    let text = large_files::refusal(&violations);
+    notify(
+        git_state,
+        &GitChatNotification::FilePolicyViolated {
+            violations,
+            committed: false,
+        },
+    );
+    Some(tool_refusal(&text))
+}
+
+/// Forward a git tool call from a chat-state actor to the model's git tools actor,
+/// eliding oversized output in the result. Failures are answered as MCP errors.
+fn proxy_git_tool_call(git_state: &mut GitChatState, data: &[u8]) -> Result<Vec<u8>, String> {
+    let call = match from_slice::<McpActorRequest>(data) {
+        Ok(McpActorRequest::ToolsCall { name, args }) => Some((name, args)),
+        _ => None,
+    };
+    let refused = call.as_ref().and_then(|(name, args)| {
+        refuse_hidden_characters(name, args).or_else(|| refuse_large_files(git_state, name, args))
+    });
+    let response = match refused {
+        Some(response) => Ok(response),
+        None => git_state
+            .model_git_tools_actor_id()
+            .and_then(|actor_id| {
+                request(&actor_id, data).map_err(|e| format!("Git tool request failed: {}", e))
+            })
+            .and_then(|response_bytes| {
+                from_slice::<McpResponse>(&response_bytes)
+                    .map_err(|e| format!("Failed to parse git tool response: {}", e))
+            }),
+    };
+    let policy = git_state.assistant_config.tool_output.clone();
+    let response = match response {
+        Ok(mut response) => {
+            if let (Some(policy), Some(result)) = (&policy, response.result.as_mut()) {
+                let removed = policy.elide_result(result);
+                if removed > 0 {
+                    logging::info(&format!("Elided {} bytes of git tool output", removed));
+                }
+            }
+            response
+        }
+        Err(e) => {
+            logging::error(&e);
+            McpResponse {
+                jsonrpc: "2.0".to_string(),
+                id: String::new(),
+                result: None,
+                error: Some(McpError {
+                    code: -32603,
+                    message: e,
+                    data: None,
+                }),
+            }
+        }
+    };
+    if let Some((name, args)) = call {
+        let outcome = match (&response.error, &response.result) {
+            (Some(error), _) => AuditOutcome::new(false, Some(&error.message)),
+            (None, Some(result)) => AuditOutcome::new(
+                result.get("isError").and_then(Value::as_bool) != Some(true),
+                Some(&git_tools::tool_result_text(result)),
+            ),
+            (None, None) => AuditOutcome::new(true, None),
+        };
+        let entry = AuditEntry {
+            session_id: None,
+            ..tool_audit_entry(git_state, name, Some(args), None)
+        };
+        git_state.audit_log.finish_tool(entry, outcome);
+    }
+    to_vec(&response).map_err(|e| format!("Failed to serialize git tool response: {}", e))
 }
 
-// Helper functions
 fn create_git_optimized_config(
     self_id: &str,
     current_directory: Option<&str>,
     config: &GitAssistantConfig,
+    branch_context: Option<&BranchContext>,
+    ticket: Option<&Ticket>,
+    repo_info: Option<&RepoInfo>,
+    task_monitor_actor_id: Option<&str>,
 ) -> Value {
-    log("Creating task-oriented git configuration...");
+    logging::info("Creating task-oriented git configuration...");
+
+    let prompts = config.prompts.clone().unwrap_or_default();
+    let prompt_values = prompts.values(current_directory, config.workflow.as_ref());
 
     // Build directory context if provided
     let directory_context = match current_directory {
         Some(dir) => {
-            log(&format!("Including current directory context: {}", dir));
-            format!("\n\nWORKING DIRECTORY: {}\nAll git operations should be performed in this directory.", dir)
+            logging::info(&format!("Including current directory context: {}", dir));
+            prompts.directory(&prompt_values)
         }
         None => {
-            log("No current directory specified");
+            logging::info("No current directory specified");
             String::new()
         }
     };
 
-    // Build task context if provided
-    let task_context = match config.task.as_deref() {
-        Some("commit") => {
-            log("Adding commit task context");
-            "\n\nTASK: AUTOMATED COMMIT\n\
-            Your task is to analyze the current repository and create appropriate commits:\n\
-            \n\
-            STEPS:\n\
-            1. Check git status to identify changed files\n\
-            2. Review changes using git diff to understand what was modified\n\
-            3. Stage appropriate files for logical commits\n\
-            4. Create meaningful, conventional commit messages\n\
-            5. Execute commits with clear explanations\n\
-            6. When all commits are complete, use the task_complete tool\n\
-            \n\
-            GOAL: Create clean, atomic commits with descriptive messages. \
-            If there are multiple logical changes, create separate commits. \
-            Always explain your reasoning and call task_complete when finished."
-        }
-        Some("review") => {
-            log("Adding review task context");
-            "\n\nTASK: CODE REVIEW\n\
-            Your task is to thoroughly review the current code changes:\n\
-            \n\
-            STEPS:\n\
-            1. Check git status and diff to understand all changes\n\
-            2. Analyze code quality, style, and architecture\n\
-            3. Identify potential bugs, security issues, or performance problems\n\
-            4. Suggest specific improvements with examples\n\
-            5. Provide constructive feedback on implementation choices\n\
-            6. When review is complete, use the task_complete tool\n\
-            \n\
-            GOAL: Provide thorough, constructive code review that helps improve \
-            code quality. Focus on being educational and actionable."
-        }
-        Some("rebase") => {
-            log("Adding rebase task context");
-            "\n\nTASK: INTERACTIVE REBASE\n\
-            Your task is to help clean up the git history through rebase:\n\
-            \n\
-            STEPS:\n\
-            1. Analyze current branch history and commit structure\n\
-            2. Plan an appropriate rebase strategy\n\
-            3. Guide through interactive rebase steps\n\
-            4. Help resolve any merge conflicts that arise\n\
-            5. Verify the final history is clean and logical\n\
-            6. When rebase is complete, use the task_complete tool\n\
-            \n\
-            GOAL: Achieve a clean, linear git history while preserving \
-            all important changes and maintaining code integrity."
-        }
-        Some("analyze") => {
-            log("Adding analyze task context");
-            "\n\nTASK: REPOSITORY ANALYSIS\n\
-            Your task is to provide a comprehensive analysis of the repository:\n\
-            \n\
-            STEPS:\n\
-            1. Examine repository structure and organization\n\
-            2. Analyze recent commit history and patterns\n\
-            3. Review current branch state and outstanding changes\n\
-            4. Identify potential issues or improvements\n\
-            5. Provide actionable recommendations\n\
-            6. When analysis is complete, use the task_complete tool\n\
-            \n\
-            GOAL: Provide valuable insights about the repository state, \
-            development patterns, and potential improvements."
-        }
-        Some("cleanup") => {
-            log("Adding cleanup task context");
-            "\n\nTASK: REPOSITORY CLEANUP\n\
-            Your task is to clean up and organize the repository:\n\
-            \n\
-            STEPS:\n\
-            1. Identify untracked files, stale branches, and clutter\n\
-            2. Review .gitignore and suggest improvements\n\
-            3. Clean up unnecessary files or directories\n\
-            4. Organize commits if needed (squash, reorder)\n\
-            5. Update documentation if outdated\n\
-            6. When cleanup is complete, use the task_complete tool\n\
-            \n\
-            GOAL: Leave the repository in a clean, organized state \
-            that follows best practices and is easy to navigate."
-        }
-        Some(task) => {
-            log(&format!(
-                "Unknown task type: {}, using default behavior",
-                task
-            ));
-            ""
+    // Build workflow context if provided
+    let mut task_context = match &config.workflow {
+        Some(workflow)
+            if workflow.is_known() || prompts.workflows.contains_key(workflow.name()) =>
+        {
+            logging::debug(&format!("Adding {} workflow context", workflow.name()));
+            prompts.workflow(workflow, &prompt_values)
+        }
+        Some(workflow) => {
+            logging::warn(&format!(
+                "Unknown workflow type: {}, using default behavior",
+                workflow.name()
+            ));
+            String::new()
         }
         None => {
-            log("No task specified");
-            ""
+            logging::info("No workflow specified");
+            String::new()
         }
     };
 
+    // Review profile only shapes the review workflow
+    if config.workflow == Some(Workflow::Review) {
+        let review_config = config.review.clone().unwrap_or_default();
+        logging::debug(&format!(
+            "Using review profile: {:?}",
+            review_config.profile
+        ));
+        task_context.push_str(&review_config.prompt_fragment());
+        task_context.push_str(review::SUBMIT_REVIEW_PROMPT);
+    }
+
+    if config.workflow == Some(Workflow::Changelog) {
+        let changelog_config = config.changelog.clone().unwrap_or_default();
+        logging::debug(&format!("Using changelog settings: {:?}", changelog_config));
+        task_context.push_str(&changelog_config.prompt_fragment());
+    }
+
+    if config.workflow == Some(Workflow::PrDescription) {
+        let pr_config = config.pr_description.clone().unwrap_or_default();
+        logging::debug(&format!("Using pull request settings: {:?}", pr_config));
+        task_context.push_str(&pr_config.prompt_fragment());
+    }
+
+    if let Some(commit_size) = &config.commit_size {
+        logging::debug(&format!("Adding commit size limits: {:?}", commit_size));
+        task_context.push_str(&commit_size.prompt_fragment());
+    }
+
+    if let Some(large_files) = &config.large_files {
+        logging::debug(&format!("Adding large file policy: {:?}", large_files));
+        task_context.push_str(&large_files.prompt_fragment());
+    }
+
+    if let Some(diff_budget) = &config.diff_budget {
+        logging::debug(&format!("Adding diff budget: {:?}", diff_budget));
+        task_context.push_str(&diff_budget.prompt_fragment());
+    }
+
+    if let Some(branch_naming) = &config.branch_naming {
+        logging::debug(&format!("Adding branch naming rules: {:?}", branch_naming));
+        task_context.push_str(&branch_naming.prompt_fragment());
+    }
+
+    if let Some(branch_context) = branch_context {
+        logging::debug(&format!("Adding branch context: {:?}", branch_context));
+        task_context.push_str(&branch_context.prompt_fragment());
+    }
+
+    if let Some(ticket) = ticket {
+        logging::debug(&format!("Adding ticket: {}", ticket.id));
+        task_context.push_str(&ticket.prompt_fragment());
+    }
+
+    if let Some(repo_info) = repo_info {
+        logging::debug(&format!("Adding discovered repository: {:?}", repo_info));
+        task_context.push_str(&repo_info.prompt_fragment());
+    }
+
+    // Commit identity overrides apply to every commit the model creates
+    let commit_identity = config.commit_identity.clone().unwrap_or_default();
+    if !commit_identity.is_empty() {
+        logging::debug(&format!(
+            "Adding commit identity overrides: {:?}",
+            commit_identity
+        ));
+        task_context.push_str(&commit_identity.prompt_fragment());
+    }
+
+    if config.require_signoff || config.gpg_sign {
+        task_context.push_str(&commit::signing_fragment(
+            config.require_signoff,
+            config.gpg_sign,
+        ));
+    }
+
+    if let Some(commit_convention) = &config.commit_convention {
+        logging::debug(&format!(
+            "Adding commit convention: {:?}",
+            commit_convention
+        ));
+        task_context.push_str(&commit_convention.prompt_fragment());
+    }
+
+    if let Some(language) = &config.language {
+        logging::debug(&format!("Adding language: {:?}", language));
+        task_context.push_str(&prompts.language(language, &prompt_values));
+    }
+
+    if config.workflow == Some(Workflow::Commit) && config.commit_split {
+        task_context.push_str(commit_plan::PROMPT_FRAGMENT);
+    }
+
+    if let (Some(Workflow::Commit), Some(template)) = (&config.workflow, &config.commit_template) {
+        logging::debug(&format!("Adding commit template: {:?}", template));
+        let mut values = branch_context
+            .map(|context| context.values.clone())
+            .unwrap_or_default();
+        values.extend(config.template_vars.clone());
+        task_context.push_str(&commit_template::prompt_fragment(template, &values));
+    }
+
+    if let Some(commands) = config.blocked_commands.as_ref().filter(|c| !c.is_empty()) {
+        logging::debug(&format!("Adding blocked commands: {:?}", commands));
+        task_context.push_str(&repo_config::blocked_commands_fragment(commands));
+    }
+
+    let scope_paths = scope::normalize(&config.scope_paths);
+    if !scope_paths.is_empty() {
+        logging::debug(&format!("Adding monorepo scope: {:?}", scope_paths));
+        task_context.push_str(&scope::prompt_fragment(&scope_paths));
+    }
+
+    let command_policy = config.command_policy.clone().unwrap_or_default();
+    if let Some(fragment) = command_policy.prompt_fragment(config.workflow.as_ref()) {
+        logging::debug(&format!("Adding git command policy: {:?}", command_policy));
+        task_context.push_str(&fragment);
+    }
+
+    if !config.allow_published_rewrite {
+        task_context.push_str(rewrite_safety::prompt_fragment());
+    }
+
+    let secret_scan = config.secret_scan.clone().unwrap_or_default();
+    if config.workflow == Some(Workflow::Commit) && secret_scan.enabled {
+        task_context.push_str(secret_scan::prompt_fragment());
+    }
+
+    if config.scratchpad {
+        task_context.push_str(scratchpad::prompt_fragment());
+    }
+
+    if config.suggest_only {
+        task_context.push_str(suggest_only::PROMPT_FRAGMENT);
+    } else if config.advice_only {
+        task_context.push_str(advice_only::PROMPT_FRAGMENT);
+    }
+
+    // Forge context only makes sense when the model has forge tools
+    if let Some(forge) = &config.forge_mcp {
+        logging::debug(&format!("Adding forge context: {:?}", forge.provider));
+        task_context.push_str(&forge.prompt_fragment(config.workflow.as_ref()));
+    }
+
     // Build completion instruction
-    let completion_instruction = if config.task.is_some() {
-        "\n\nIMPORTANT: When you have completed your assigned task, you MUST call the 'task_complete' tool \
-        to signal that the work is finished. This allows the system to properly conclude the task session."
+    let completion_sentinel = uses_completion_sentinel(config);
+    let completion_instruction = if completion_sentinel {
+        completion::prompt_fragment(config.workflow.is_some())
+    } else if config.workflow.is_some() {
+        prompts::TASK_COMPLETE_REQUIRED
     } else {
-        "\n\nNOTE: You have access to a 'task_complete' tool. Use it if the user explicitly asks you \
-        to complete a specific task or when you finish a well-defined piece of work."
-    };
-
-    // Default git system prompt
-    let default_git_system_prompt = format!(
-        "You are a Git Task Assistant with access to git tools. You specialize in completing \
-        specific git-related tasks efficiently and thoroughly.\n\
-        \n\
-        AVAILABLE CAPABILITIES:\n\
-        - Git repository operations (status, diff, log, branch management)\n\
-        - File staging and commit creation\n\
-        - Branch operations and history analysis\n\
-        - Code review and quality assessment\n\
-        - Repository cleanup and organization\n\
-        - Task completion signaling\n\
-        \n\
-        APPROACH:\n\
-        - Always start by understanding the current repository state\n\
-        - Break down complex tasks into clear steps\n\
-        - Provide explanations for all git operations\n\
-        - Follow git best practices and conventions\n\
-        - Signal completion when tasks are finished{}{}{}",
-        directory_context, task_context, completion_instruction
-    );
-
-    // Use custom system prompt if provided, otherwise use default with directory and task context
-    let final_system_prompt = match &config.system_prompt {
-        Some(custom_prompt) => {
-            log("Using custom system prompt with context");
-            format!(
-                "{}{}{}{}",
-                custom_prompt, directory_context, task_context, completion_instruction
-            )
-        }
-        None => {
-            log("Using default git system prompt with task context");
-            default_git_system_prompt
-        }
+        prompts::TASK_COMPLETE_AVAILABLE
     };
+    let mut completion_instruction = prompts.completion(completion_instruction, &prompt_values);
+    if let Some(workflow) = &config.workflow {
+        let criteria = config
+            .completion
+            .clone()
+            .unwrap_or_default()
+            .criteria_for(workflow);
+        if let Some(fragment) = completion::criteria_fragment(&criteria) {
+            logging::debug(&format!("Adding completion criteria: {:?}", criteria));
+            completion_instruction.push_str(&fragment);
+        }
+    }
+
+    // A custom system prompt replaces the base template; the context still follows it
+    if config.system_prompt.is_some() {
+        logging::debug("Using custom system prompt with context");
+    } else {
+        logging::debug("Using default git system prompt with task context");
+    }
+    let final_system_prompt = prompts::compose(
+        &prompts.base(config.system_prompt.as_deref(), &prompt_values),
+        &[&directory_context, &task_context, &completion_instruction],
+    );
 
     // Default model config
     let default_model_config = serde_json::json!({
-        "model": "claude-sonnet-4-20250514",
+        "model": DEFAULT_MODEL,
         "provider": "anthropic"
     });
 
-    // Default MCP servers (git tools)
-    let default_mcp_servers = serde_json::json!([
-        {
-            "actor_id": null,
-            "actor": {
-                "manifest_path": GIT_MCP_MANIFEST_PATH,
-            },
-            "tools": null
-        },
-        {
+    // Git tools, with commit environment overrides when configured
+    let mut git_mcp_actor = serde_json::json!({
+        "manifest_path": GIT_MCP_MANIFEST_PATH,
+    });
+    if let Some(init_state) = git_mcp_init_state(config) {
+        git_mcp_actor["init_state"] = init_state;
+    }
+
+    // Default MCP servers (git tools, and the task monitor unless the sentinel is used)
+    let mut default_mcp_servers = vec![mcp_servers::DefaultServer {
+        name: mcp_servers::GIT,
+        server: serde_json::json!({
             "actor_id": null,
-            "actor": {
-                "manifest_path": TASK_MONITOR_MANIFEST_PATH,
-                "init_state": {
-                    "management_actor": self_id,
-                }
-            },
+            "actor": git_mcp_actor,
             "tools": null
-        }
-    ]);
+        }),
+    }];
+    if !completion_sentinel {
+        default_mcp_servers.push(mcp_servers::DefaultServer {
+            name: mcp_servers::TASK_MONITOR,
+            // A supervised monitor is shared; otherwise each session spawns its own
+            server: serde_json::json!({
+                "actor_id": task_monitor_actor_id,
+                "actor": {
+                    "manifest_path": TASK_MONITOR_MANIFEST_PATH,
+                    "init_state": {
+                        "management_actor": self_id,
+                    }
+                },
+                "tools": null
+            }),
+        });
+    }
+
+    // Exec tools only when the model has to run a benchmark
+    let needs_exec_tools = config.workflow == Some(Workflow::Review)
+        && config
+            .review
+            .as_ref()
+            .is_some_and(ReviewConfig::needs_exec_tools);
+    if needs_exec_tools {
+        logging::debug("Adding exec tools for the review benchmark");
+        default_mcp_servers.push(mcp_servers::DefaultServer {
+            name: mcp_servers::EXEC,
+            server: serde_json::json!({
+                "actor_id": null,
+                "actor": {
+                    "manifest_path": EXEC_MCP_MANIFEST_PATH,
+                },
+                "tools": null
+            }),
+        });
+    }
 
     // Build the configuration with overrides
     let model_config = config
@@ -763,45 +12555,95 @@ fn create_git_optimized_config(
         .as_ref()
         .unwrap_or(&default_model_config);
 
-    // Adjust temperature based on task type
-    let default_temperature = match config.task.as_deref() {
-        Some("commit") => 0.3,  // More deterministic for commit messages
-        Some("review") => 0.5,  // Balanced for analysis
-        Some("rebase") => 0.2,  // Very precise for history operations
-        Some("analyze") => 0.6, // Slightly creative for insights
-        Some("cleanup") => 0.3, // Methodical approach
-        _ => 0.7,               // Default for general assistance
-    };
+    // Adjust temperature based on workflow type
+    let default_temperature = config
+        .workflow
+        .as_ref()
+        .map(Workflow::default_temperature)
+        .unwrap_or(0.7); // Default for general assistance
 
     let temperature = config.temperature.unwrap_or(default_temperature);
     let max_tokens = config.max_tokens.unwrap_or(8192);
 
-    // Update title based on task
-    let default_title = match config.task.as_deref() {
-        Some("commit") => "Git Commit Assistant",
-        Some("review") => "Git Code Review Assistant",
-        Some("rebase") => "Git Rebase Assistant",
-        Some("analyze") => "Git Analysis Assistant",
-        Some("cleanup") => "Git Cleanup Assistant",
-        Some(_) => "Git Task Assistant",
-        None => "Git Assistant",
-    };
+    // Update title based on workflow
+    let default_title = config
+        .workflow
+        .as_ref()
+        .map(Workflow::default_title)
+        .unwrap_or("Git Assistant");
 
     let title = config.title.as_deref().unwrap_or(default_title);
     let default_description = format!(
         "AI assistant for git {} tasks",
-        config.task.as_deref().unwrap_or("management")
+        config
+            .workflow
+            .as_ref()
+            .map(Workflow::name)
+            .unwrap_or("management")
     );
     let description = config
         .description
         .as_deref()
         .unwrap_or(&default_description);
-    let mcp_servers = config.mcp_servers.as_ref().unwrap_or(&default_mcp_servers);
+    let mut mcp_servers = mcp_servers::base(
+        default_mcp_servers,
+        config.mcp_servers.as_ref(),
+        &config.disable_default_mcp_servers,
+    );
+    // The assistant stands in for the default git tools to check commits and branches
+    // and elide what they return
+    if let (None, Some(servers)) = (&config.mcp_servers, mcp_servers.as_array_mut()) {
+        for server in servers.iter_mut().filter(|server| {
+            server["actor"]["manifest_path"].as_str() == Some(GIT_MCP_MANIFEST_PATH)
+        }) {
+            server["actor_id"] = serde_json::json!(self_id);
+        }
+    }
+    mcp_servers::append(&mut mcp_servers, &config.extra_mcp_servers);
+    // Leave out the git tools the policy doesn't allow, including a custom git server's
+    if let (Some(tools), Some(servers)) = (
+        command_policy.git_tools(config.workflow.as_ref()),
+        mcp_servers.as_array_mut(),
+    ) {
+        for server in servers.iter_mut().filter(|server| {
+            server["actor"]["manifest_path"].as_str() == Some(GIT_MCP_MANIFEST_PATH)
+        }) {
+            server["tools"] = serde_json::json!(tools);
+        }
+    }
+    // The git server gets the scope too, including a custom one
+    if let (false, Some(servers)) = (scope_paths.is_empty(), mcp_servers.as_array_mut()) {
+        for server in servers.iter_mut().filter(|server| {
+            server["actor"]["manifest_path"].as_str() == Some(GIT_MCP_MANIFEST_PATH)
+        }) {
+            server["config"]["scope_paths"] = serde_json::json!(scope_paths);
+        }
+    }
+    if let (Some(forge), Some(servers)) = (&config.forge_mcp, mcp_servers.as_array_mut()) {
+        logging::debug(&format!("Adding forge tools for {}", forge.repo));
+        servers.push(forge.mcp_server());
+    }
+    // In suggest-only mode the model runs nothing, through a custom server either
+    if let (true, Some(servers)) = (config.suggest_only, mcp_servers.as_array_mut()) {
+        logging::debug("Suggest-only mode: leaving out the git and exec tools");
+        servers.retain(|server| {
+            let manifest_path = server["actor"]["manifest_path"].as_str();
+            manifest_path != Some(GIT_MCP_MANIFEST_PATH)
+                && manifest_path != Some(EXEC_MCP_MANIFEST_PATH)
+        });
+    }
+    // Without git tools, a server spawned from their manifest would fail the spawn too
+    if let (true, Some(servers)) = (config.advice_only, mcp_servers.as_array_mut()) {
+        logging::debug("Advice-only mode: leaving out the git tools");
+        servers.retain(|server| {
+            server["actor"]["manifest_path"].as_str() != Some(GIT_MCP_MANIFEST_PATH)
+        });
+    }
 
-    log(&format!("Using model: {:?}", model_config));
-    log(&format!("Using temperature: {}", temperature));
-    log(&format!("Using max_tokens: {}", max_tokens));
-    log(&format!("Using title: {}", title));
+    logging::debug_value("Using model", "model_config", model_config);
+    logging::debug(&format!("Using temperature: {}", temperature));
+    logging::debug(&format!("Using max_tokens: {}", max_tokens));
+    logging::debug(&format!("Using title: {}", title));
 
     // Build the final configuration
     let mut final_config = serde_json::json!({
@@ -825,12 +12667,17 @@ fn create_git_optimized_config(
         }
     }
 
-    log(&format!("Created final git config: {}", final_config));
+    logging::debug_value("Created final git config", "config", &final_config);
     final_config
 }
 
 fn spawn_chat_state_actor(chat_config: &Value) -> Result<String, String> {
-    log("Spawning chat-state actor...");
+    logging::debug("Spawning chat-state actor...");
+    if simulated::is_enabled() {
+        let actor_id = simulated::spawn();
+        logging::info(&format!("Using simulated chat-state actor: {}", actor_id));
+        return Ok(actor_id);
+    }
 
     // Create initial state for chat-state actor
     let initial_state = serde_json::json!({
@@ -843,14 +12690,14 @@ fn spawn_chat_state_actor(chat_config: &Value) -> Result<String, String> {
     // Spawn the actor
     match spawn(CHAT_STATE_MANIFEST_PATH, Some(&initial_state_bytes)) {
         Ok(actor_id) => {
-            log(&format!(
+            logging::info(&format!(
                 "Successfully spawned chat-state actor: {}",
                 actor_id
             ));
             Ok(actor_id)
         }
         Err(e) => {
-            log(&format!("Failed to spawn chat-state actor: {:?}", e));
+            logging::error(&format!("Failed to spawn chat-state actor: {:?}", e));
             Err(format!("Spawn failed: {:?}", e))
         }
     }
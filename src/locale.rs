@@ -0,0 +1,302 @@
+//! The `language` config: the natural language the model writes commit messages and
+//! replies in, and a heuristic check that commit messages are written in it. Not to be
+//! confused with `language.rs`, which detects the programming language of files.
+
+use serde::{Deserialize, Serialize};
+
+/// Languages the assistant has prompt fragments for and can recognize.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    En,
+    De,
+    Fr,
+    Es,
+    It,
+    Pt,
+    Nl,
+    Ru,
+    Ja,
+    Zh,
+    Ko,
+}
+
+/// Latin-script locales, with words and letters that give them away.
+const LATIN_MARKERS: &[(Locale, &[&str], &[char])] = &[
+    (
+        Locale::En,
+        &[
+            "the", "and", "for", "with", "from", "to", "of", "in", "on", "when", "into", "that",
+            "this", "is", "are", "add", "fix", "remove", "update", "use",
+        ],
+        &[],
+    ),
+    (
+        Locale::De,
+        &[
+            "der",
+            "die",
+            "das",
+            "und",
+            "für",
+            "mit",
+            "von",
+            "zu",
+            "im",
+            "bei",
+            "wenn",
+            "nicht",
+            "ist",
+            "den",
+            "dem",
+            "ein",
+            "eine",
+            "hinzufügen",
+            "entfernen",
+            "beheben",
+        ],
+        &['ä', 'ö', 'ü', 'ß'],
+    ),
+    (
+        Locale::Fr,
+        &[
+            "le", "la", "les", "des", "du", "et", "pour", "avec", "dans", "sur", "une", "est",
+            "pas", "ajout", "ajoute", "corrige", "supprime",
+        ],
+        &['é', 'è', 'ê', 'à', 'ç', 'ù'],
+    ),
+    (
+        Locale::Es,
+        &[
+            "el", "los", "las", "del", "y", "para", "con", "en", "por", "una", "es", "añade",
+            "agrega", "corrige", "elimina",
+        ],
+        &['ñ', '¿', '¡'],
+    ),
+    (
+        Locale::It,
+        &[
+            "il", "lo", "gli", "della", "delle", "e", "per", "con", "nel", "una", "è", "non",
+            "aggiunge", "corregge", "rimuove",
+        ],
+        &['ò', 'ì'],
+    ),
+    (
+        Locale::Pt,
+        &[
+            "o", "os", "as", "do", "da", "dos", "e", "para", "com", "em", "uma", "não", "adiciona",
+            "corrige", "remove",
+        ],
+        &['ã', 'õ'],
+    ),
+    (
+        Locale::Nl,
+        &[
+            "de",
+            "het",
+            "een",
+            "en",
+            "voor",
+            "met",
+            "van",
+            "bij",
+            "niet",
+            "naar",
+            "toevoegen",
+            "verwijderen",
+            "repareren",
+        ],
+        &['ĳ'],
+    ),
+];
+
+impl Locale {
+    /// English name, for logs and reprompts.
+    pub fn name(self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::De => "German",
+            Locale::Fr => "French",
+            Locale::Es => "Spanish",
+            Locale::It => "Italian",
+            Locale::Pt => "Portuguese",
+            Locale::Nl => "Dutch",
+            Locale::Ru => "Russian",
+            Locale::Ja => "Japanese",
+            Locale::Zh => "Chinese",
+            Locale::Ko => "Korean",
+        }
+    }
+
+    fn is_latin(self) -> bool {
+        LATIN_MARKERS.iter().any(|(locale, _, _)| *locale == self)
+    }
+}
+
+/// The `language` config: one locale for everything, or one per kind of text.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum LanguageConfig {
+    All(Locale),
+    Split {
+        /// Commit messages the model writes
+        #[serde(default)]
+        commits: Option<Locale>,
+        /// Replies and explanations
+        #[serde(default)]
+        replies: Option<Locale>,
+    },
+}
+
+impl LanguageConfig {
+    pub fn commits(&self) -> Option<Locale> {
+        match self {
+            LanguageConfig::All(locale) => Some(*locale),
+            LanguageConfig::Split { commits, .. } => *commits,
+        }
+    }
+
+    pub fn replies(&self) -> Option<Locale> {
+        match self {
+            LanguageConfig::All(locale) => Some(*locale),
+            LanguageConfig::Split { replies, .. } => *replies,
+        }
+    }
+}
+
+/// Words of a commit message worth judging its language by: no trailers, code spans,
+/// paths, URLs, or conventional commit type.
+fn prose_words(message: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for (index, line) in message.lines().enumerate() {
+        let line = line.trim();
+        let is_trailer = line
+            .split_once(": ")
+            .is_some_and(|(key, _)| !key.is_empty() && !key.contains(' ') && key.contains('-'));
+        if is_trailer || line.starts_with('#') {
+            continue;
+        }
+        // `feat(parser): ...`
+        let line = match (index, line.split_once(": ")) {
+            (0, Some((kind, rest))) if !kind.contains(' ') => rest,
+            _ => line,
+        };
+        // Every other part is inside a code span
+        for part in line.split('`').step_by(2) {
+            for word in part.split_whitespace() {
+                if word.contains('/') || word.contains("::") || word.contains('_') {
+                    continue;
+                }
+                let word: String = word
+                    .trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_lowercase();
+                if !word.is_empty() {
+                    words.push(word);
+                }
+            }
+        }
+    }
+    words
+}
+
+/// Best guess at the language of `message`; `None` when it is too short or mixed to
+/// tell.
+pub fn detect(message: &str) -> Option<Locale> {
+    let words = prose_words(message);
+    let text = words.join(" ");
+    let count = |test: fn(char) -> bool| text.chars().filter(|c| test(*c)).count();
+    let kana = count(|c| ('\u{3040}'..='\u{30ff}').contains(&c));
+    let hangul = count(|c| ('\u{ac00}'..='\u{d7af}').contains(&c));
+    let han = count(|c| ('\u{4e00}'..='\u{9fff}').contains(&c));
+    let cyrillic = count(|c| ('\u{0400}'..='\u{04ff}').contains(&c));
+    let latin = count(|c| c.is_ascii_alphabetic());
+
+    let script = [
+        (kana, Locale::Ja),
+        (hangul, Locale::Ko),
+        (han, Locale::Zh),
+        (cyrillic, Locale::Ru),
+    ]
+    .into_iter()
+    .filter(|(letters, _)| *letters > 0)
+    .max_by_key(|(letters, _)| *letters);
+    match script {
+        // Japanese mixes kanji with kana
+        Some((_, Locale::Zh)) if kana > 0 => return Some(Locale::Ja),
+        Some((letters, locale)) if letters * 2 >= latin => return Some(locale),
+        _ => {}
+    }
+
+    let scores = LATIN_MARKERS.iter().map(|(locale, markers, letters)| {
+        let word_hits = words
+            .iter()
+            .filter(|word| markers.contains(&word.as_str()))
+            .count();
+        let letter_hits = text.chars().filter(|c| letters.contains(c)).count();
+        (*locale, word_hits + letter_hits)
+    });
+    let mut scores: Vec<(Locale, usize)> = scores.collect();
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    match scores.as_slice() {
+        [(best, score), (_, runner_up), ..] if *score >= 2 && score > runner_up => Some(*best),
+        _ => None,
+    }
+}
+
+/// Why `message` doesn't look like it is written in `wanted`; `None` when it does, or
+/// when it can't be told.
+pub fn check(message: &str, wanted: Locale) -> Option<String> {
+    let words = prose_words(message);
+    match detect(message) {
+        Some(found) if found != wanted => Some(format!(
+            "written in {} rather than {}",
+            found.name(),
+            wanted.name()
+        )),
+        // Plain ASCII words where another script was asked for
+        None if !wanted.is_latin()
+            && words.len() >= 3
+            && words
+                .iter()
+                .all(|word| word.chars().all(|c| c.is_ascii_alphanumeric())) =>
+        {
+            Some(format!("not written in {}", wanted.name()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_messages_are_checked_against_the_wanted_language() {
+        let english = "feat(lexer): add support for tabs in the indentation\n\n\
+            Tabs are expanded to the next stop when the `indent_width` is set.\n\n\
+            Signed-off-by: Ann Example <ann@example.com>";
+        let german = "feat(lexer): Unterstützung für Tabs in der Einrückung hinzufügen";
+        assert_eq!(detect(english), Some(Locale::En));
+        assert_eq!(detect(german), Some(Locale::De));
+        assert_eq!(detect("タブのインデントに対応する"), Some(Locale::Ja));
+        assert_eq!(detect("fix: typo"), None);
+
+        assert_eq!(check(english, Locale::En), None);
+        assert_eq!(
+            check(english, Locale::De).as_deref(),
+            Some("written in English rather than German")
+        );
+        assert_eq!(check(german, Locale::De), None);
+        assert_eq!(
+            check("Handle tabs in indentation", Locale::Ja).as_deref(),
+            Some("not written in Japanese")
+        );
+
+        let config: LanguageConfig =
+            serde_json::from_value(serde_json::json!({"replies": "fr"})).unwrap();
+        assert_eq!(config.commits(), None);
+        assert_eq!(config.replies(), Some(Locale::Fr));
+        let config: LanguageConfig = serde_json::from_value(serde_json::json!("ko")).unwrap();
+        assert_eq!(config.commits(), Some(Locale::Ko));
+    }
+}
@@ -0,0 +1,316 @@
+//! Leveled, structured logging. Each entry goes to the runtime log as one JSON object
+//! with the level, the time, the message, and the ids of the request and session being
+//! handled.
+
+use crate::bindings::theater::simple::runtime;
+use crate::clock;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// The `logging` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoggingConfig {
+    /// Most verbose level written
+    #[serde(default = "default_level")]
+    pub level: Level,
+    /// Longer messages and field values are truncated
+    #[serde(default = "default_max_value_length")]
+    pub max_value_length: usize,
+}
+
+fn default_level() -> Level {
+    Level::Info
+}
+
+fn default_max_value_length() -> usize {
+    1000
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_level(),
+            max_value_length: default_max_value_length(),
+        }
+    }
+}
+
+/// The `log_redaction` policy: how much prompt and repository content reaches log
+/// entries and debug event records. Secrets are redacted at every level.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRedaction {
+    /// Content is logged as it is, up to `max_value_length`
+    #[default]
+    None,
+    /// Content fields, and the details after a message's first ": ", are cut to a
+    /// short prefix followed by their length and fingerprint
+    Partial,
+    /// Content is replaced by its length and fingerprint
+    Full,
+}
+
+/// Characters of content kept under `partial` redaction.
+const PARTIAL_LENGTH: usize = 80;
+
+/// Words in the keys of fields holding prompts, messages, or repository content, which
+/// `log_redaction` applies to.
+const CONTENT_KEYS: &[&str] = &[
+    "prompt",
+    "message",
+    "content",
+    "text",
+    "diff",
+    "patch",
+    "directory",
+    "path",
+    "files",
+    "title",
+    "description",
+    "summary",
+    "subject",
+    "body",
+];
+
+/// Key fragments of fields whose values never reach the log, besides keys ending in
+/// `token` (but not `max_tokens`).
+const SECRET_KEYS: &[&str] = &[
+    "secret",
+    "password",
+    "api_key",
+    "apikey",
+    "authorization",
+    "credential",
+    "private_key",
+];
+
+struct Context {
+    config: LoggingConfig,
+    redaction: LogRedaction,
+    request_id: Option<String>,
+    session_id: Option<String>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Context> = RefCell::new(Context {
+        config: LoggingConfig::default(),
+        redaction: LogRedaction::None,
+        request_id: None,
+        session_id: None,
+    });
+}
+
+/// Apply the configured level and redaction; the defaults when there is no `logging`
+/// section.
+pub fn configure(config: Option<&LoggingConfig>, redaction: LogRedaction) {
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.config = config.cloned().unwrap_or_default();
+        context.redaction = redaction;
+    });
+}
+
+/// Ids attached to the entries logged while handling the current message.
+pub fn set_scope(request_id: Option<&str>, session_id: Option<&str>) {
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.request_id = request_id.map(str::to_string);
+        context.session_id = session_id.map(str::to_string);
+    });
+}
+
+pub fn error(message: &str) {
+    emit(Level::Error, message, None);
+}
+
+pub fn warn(message: &str) {
+    emit(Level::Warn, message, None);
+}
+
+pub fn info(message: &str) {
+    emit(Level::Info, message, None);
+}
+
+pub fn debug(message: &str) {
+    emit(Level::Debug, message, None);
+}
+
+/// Log `value` as a structured field, with secrets redacted and long strings truncated.
+pub fn debug_value(message: &str, field: &str, value: &Value) {
+    let mut fields = Map::new();
+    fields.insert(field.to_string(), value.clone());
+    emit(Level::Debug, message, Some(Value::Object(fields)));
+}
+
+/// Like [`debug_value`], at info level.
+pub fn info_value(message: &str, field: &str, value: &Value) {
+    let mut fields = Map::new();
+    fields.insert(field.to_string(), value.clone());
+    emit(Level::Info, message, Some(Value::Object(fields)));
+}
+
+/// Like [`debug_value`], at trace level.
+pub fn trace_value(message: &str, field: &str, value: &Value) {
+    let mut fields = Map::new();
+    fields.insert(field.to_string(), value.clone());
+    emit(Level::Trace, message, Some(Value::Object(fields)));
+}
+
+fn emit(level: Level, message: &str, fields: Option<Value>) {
+    CONTEXT.with(|context| {
+        let context = context.borrow();
+        if level > context.config.level {
+            return;
+        }
+        let max_length = context.config.max_value_length;
+        let mut entry = Map::new();
+        entry.insert("level".to_string(), serde_json::json!(level));
+        entry.insert("at".to_string(), serde_json::json!(clock::now()));
+        entry.insert(
+            "message".to_string(),
+            Value::String(truncate(
+                &redact_message(message, context.redaction),
+                max_length,
+            )),
+        );
+        if let Some(request_id) = &context.request_id {
+            entry.insert("request_id".to_string(), Value::String(request_id.clone()));
+        }
+        if let Some(session_id) = &context.session_id {
+            entry.insert("session_id".to_string(), Value::String(session_id.clone()));
+        }
+        if let Some(fields) = fields {
+            entry.insert(
+                "fields".to_string(),
+                redact(&fields, max_length, context.redaction),
+            );
+        }
+        runtime::log(&Value::Object(entry).to_string());
+    });
+}
+
+/// `value` as it would be logged: secrets redacted, content redacted as configured,
+/// and long strings truncated.
+pub fn sanitize(value: &Value) -> Value {
+    CONTEXT.with(|context| {
+        let context = context.borrow();
+        redact(value, context.config.max_value_length, context.redaction)
+    })
+}
+
+/// `message` as it would be logged, for event records that carry free-form text such
+/// as errors.
+pub fn sanitize_text(message: &str) -> String {
+    CONTEXT.with(|context| redact_message(message, context.borrow().redaction))
+}
+
+/// `value` with secret fields replaced, content fields redacted, and long strings
+/// truncated.
+fn redact(value: &Value, max_length: usize, redaction: LogRedaction) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| {
+                    let value = if is_secret_key(key) {
+                        Value::String("[redacted]".to_string())
+                    } else if redaction != LogRedaction::None && is_content_key(key) {
+                        redact_content(value, redaction)
+                    } else {
+                        redact(value, max_length, redaction)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| redact(value, max_length, redaction))
+                .collect(),
+        ),
+        Value::String(text) => Value::String(truncate(text, max_length)),
+        other => other.clone(),
+    }
+}
+
+/// A content field's value: objects and arrays are redacted as their JSON text.
+fn redact_content(value: &Value, redaction: LogRedaction) -> Value {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        Value::Null => return Value::Null,
+        other => other.to_string(),
+    };
+    Value::String(redact_text(&text, redaction))
+}
+
+/// Keep the part of a message before its first ": ", which the assistant writes
+/// itself, and redact the details after it, which may quote prompts, paths, or output.
+fn redact_message(message: &str, redaction: LogRedaction) -> String {
+    if redaction == LogRedaction::None {
+        return message.to_string();
+    }
+    match message.split_once(": ") {
+        Some((head, details)) => format!("{}: {}", head, redact_text(details, redaction)),
+        None => message.to_string(),
+    }
+}
+
+fn redact_text(text: &str, redaction: LogRedaction) -> String {
+    let redacted = format!(
+        "[{} chars, fingerprint {}]",
+        text.chars().count(),
+        fingerprint(text)
+    );
+    match redaction {
+        LogRedaction::None => text.to_string(),
+        LogRedaction::Partial => match text.char_indices().nth(PARTIAL_LENGTH) {
+            Some((end, _)) => format!("{}... {}", &text[..end], redacted),
+            None => text.to_string(),
+        },
+        LogRedaction::Full => redacted,
+    }
+}
+
+/// FNV-1a hash of `text`: not a secure digest, but stable across runs, so entries
+/// holding the same content can be matched up.
+pub fn fingerprint(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn is_content_key(key: &str) -> bool {
+    // Whole words only, so that e.g. `context` isn't taken for `text`
+    let key = key.to_lowercase();
+    key.split('_').any(|word| {
+        CONTENT_KEYS
+            .iter()
+            .any(|content| word == *content || word.strip_suffix('s') == Some(content))
+    })
+}
+
+/// Whether a field named `key` holds a credential, e.g. `api_key` or `forge_token`.
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    key.ends_with("token") || SECRET_KEYS.iter().any(|secret| key.contains(secret))
+}
+
+fn truncate(text: &str, max_length: usize) -> String {
+    match text.char_indices().nth(max_length) {
+        Some((end, _)) => format!("{}... ({} chars)", &text[..end], text.chars().count()),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,108 @@
+//! Composition of the model's MCP servers: the named default servers, less the ones
+//! `disable_default_mcp_servers` names, followed by `extra_mcp_servers`. A configured
+//! `mcp_servers` list still replaces the defaults as a whole.
+
+use serde_json::Value;
+
+/// The git tools, limited by the command policy and the scope
+pub const GIT: &str = "git";
+/// The task monitor and its `task_complete` tool
+pub const TASK_MONITOR: &str = "task-monitor";
+/// Exec tools, only added when the model runs a review benchmark
+pub const EXEC: &str = "exec";
+
+/// Names of the default servers, in the order they are listed.
+pub const DEFAULT_NAMES: &[&str] = &[GIT, TASK_MONITOR, EXEC];
+
+/// A default server and its name.
+#[derive(Debug, Clone)]
+pub struct DefaultServer {
+    pub name: &'static str,
+    pub server: Value,
+}
+
+/// The servers before `extra_mcp_servers`: `replacement` when `mcp_servers` is
+/// configured, otherwise the defaults that aren't `disabled`.
+pub fn base(
+    defaults: Vec<DefaultServer>,
+    replacement: Option<&Value>,
+    disabled: &[String],
+) -> Value {
+    match replacement {
+        Some(servers) => servers.clone(),
+        None => Value::Array(
+            defaults
+                .into_iter()
+                .filter(|default| !is_disabled(disabled, default.name))
+                .map(|default| default.server)
+                .collect(),
+        ),
+    }
+}
+
+/// Append `extra` to `servers`.
+pub fn append(servers: &mut Value, extra: &[Value]) {
+    if let Some(servers) = servers.as_array_mut() {
+        servers.extend(extra.iter().cloned());
+    }
+}
+
+/// Whether `name` is one of the `disabled` default servers.
+pub fn is_disabled(disabled: &[String], name: &str) -> bool {
+    disabled.iter().any(|disabled| disabled == name)
+}
+
+/// Names in `disable_default_mcp_servers` that aren't default servers.
+pub fn unknown_names(disabled: &[String]) -> Vec<&str> {
+    disabled
+        .iter()
+        .map(String::as_str)
+        .filter(|name| !DEFAULT_NAMES.contains(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn defaults() -> Vec<DefaultServer> {
+        vec![
+            DefaultServer {
+                name: GIT,
+                server: json!({"actor": {"manifest_path": "git.toml"}}),
+            },
+            DefaultServer {
+                name: TASK_MONITOR,
+                server: json!({"actor": {"manifest_path": "task-monitor.toml"}}),
+            },
+        ]
+    }
+
+    #[test]
+    fn extra_servers_follow_the_defaults_that_are_left() {
+        let mut servers = base(defaults(), None, &[TASK_MONITOR.to_string()]);
+        append(
+            &mut servers,
+            &[json!({"actor": {"manifest_path": "search.toml"}})],
+        );
+        assert_eq!(
+            servers,
+            json!([
+                {"actor": {"manifest_path": "git.toml"}},
+                {"actor": {"manifest_path": "search.toml"}}
+            ])
+        );
+    }
+
+    #[test]
+    fn mcp_servers_replace_every_default() {
+        let replacement = json!([{"actor": {"manifest_path": "custom.toml"}}]);
+        let servers = base(defaults(), Some(&replacement), &[]);
+        assert_eq!(servers, replacement);
+        assert_eq!(
+            unknown_names(&["git".to_string(), "gti".to_string()]),
+            ["gti"]
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Counters and timings for operators watching many assistants: requests by type, error
+//! responses by code, the model's tool calls, child actors spawned again after a
+//! failure, and how long workflows ran. `GetMetrics` returns them as JSON or in the
+//! Prometheus text format.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetricsFormat {
+    #[default]
+    Json,
+    /// The Prometheus text exposition format
+    Prometheus,
+}
+
+impl MetricsFormat {
+    pub fn media_type(self) -> &'static str {
+        match self {
+            MetricsFormat::Json => "application/json",
+            MetricsFormat::Prometheus => "text/plain; version=0.0.4",
+        }
+    }
+}
+
+/// Runs of one workflow, with how long they took in seconds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Timing {
+    pub count: u64,
+    /// Runs that finished with an error
+    #[serde(default)]
+    pub failed: u64,
+    pub total_secs: u64,
+    pub max_secs: u64,
+}
+
+/// What is counted as it happens, kept in state.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Metrics {
+    /// Requests handled, by type
+    #[serde(default)]
+    pub requests: BTreeMap<String, u64>,
+    /// Error responses, by code
+    #[serde(default)]
+    pub errors: BTreeMap<String, u64>,
+    /// The model's tool calls, by tool
+    #[serde(default)]
+    pub tool_calls: BTreeMap<String, u64>,
+    /// Children spawned again after they failed or exited, by kind
+    #[serde(default)]
+    pub child_restarts: BTreeMap<String, u64>,
+    /// Finished workflows, by name
+    #[serde(default)]
+    pub workflows: BTreeMap<String, Timing>,
+}
+
+/// Everything `GetMetrics` reports: the counted metrics and the counters the assistant
+/// keeps anyway.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MetricsSnapshot {
+    pub actor_id: String,
+    #[serde(default)]
+    pub uptime_secs: Option<u64>,
+    pub messages_forwarded: u64,
+    pub generations_requested: u64,
+    pub generations_finished: u64,
+    #[serde(flatten)]
+    pub metrics: Metrics,
+}
+
+impl Metrics {
+    pub fn count_request(&mut self, kind: &str) {
+        *self.requests.entry(kind.to_string()).or_default() += 1;
+    }
+
+    pub fn count_error(&mut self, code: &str) {
+        *self.errors.entry(code.to_string()).or_default() += 1;
+    }
+
+    pub fn count_tool_call(&mut self, tool: &str) {
+        *self.tool_calls.entry(tool.to_string()).or_default() += 1;
+    }
+
+    pub fn count_restart(&mut self, child: &str) {
+        *self.child_restarts.entry(child.to_string()).or_default() += 1;
+    }
+
+    pub fn record_workflow(&mut self, workflow: &str, secs: u64, failed: bool) {
+        let timing = self.workflows.entry(workflow.to_string()).or_default();
+        timing.count += 1;
+        timing.failed += u64::from(failed);
+        timing.total_secs += secs;
+        timing.max_secs = timing.max_secs.max(secs);
+    }
+}
+
+/// Label values are quoted; backslashes, quotes, and newlines are escaped.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+struct Exposition {
+    text: String,
+    actor: String,
+}
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        self.text.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} {}\n",
+            name, help, name, kind
+        ));
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: u64) {
+        let mut pairs = vec![format!("actor=\"{}\"", self.actor)];
+        pairs.extend(
+            labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, label(value))),
+        );
+        self.text
+            .push_str(&format!("{}{{{}}} {}\n", name, pairs.join(","), value));
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: u64) {
+        self.family(name, "counter", help);
+        self.sample(name, &[], value);
+    }
+
+    fn counters(&mut self, name: &str, help: &str, key: &str, values: &BTreeMap<String, u64>) {
+        self.family(name, "counter", help);
+        for (value_key, value) in values {
+            self.sample(name, &[(key, value_key)], *value);
+        }
+    }
+}
+
+/// `snapshot` in the Prometheus text format, every sample labelled with the actor.
+pub fn prometheus(snapshot: &MetricsSnapshot) -> String {
+    let mut exposition = Exposition {
+        text: String::new(),
+        actor: label(&snapshot.actor_id),
+    };
+    if let Some(uptime_secs) = snapshot.uptime_secs {
+        exposition.family(
+            "git_chat_uptime_seconds",
+            "gauge",
+            "Seconds since the assistant started",
+        );
+        exposition.sample("git_chat_uptime_seconds", &[], uptime_secs);
+    }
+    let metrics = &snapshot.metrics;
+    exposition.counters(
+        "git_chat_requests_total",
+        "Requests handled, by type",
+        "type",
+        &metrics.requests,
+    );
+    exposition.counters(
+        "git_chat_request_errors_total",
+        "Error responses, by code",
+        "code",
+        &metrics.errors,
+    );
+    exposition.counter(
+        "git_chat_messages_forwarded_total",
+        "Messages forwarded to chat-state actors",
+        snapshot.messages_forwarded,
+    );
+    exposition.counter(
+        "git_chat_generations_requested_total",
+        "Generations requested from chat-state actors",
+        snapshot.generations_requested,
+    );
+    exposition.counter(
+        "git_chat_generations_finished_total",
+        "Generations that finished",
+        snapshot.generations_finished,
+    );
+    exposition.counters(
+        "git_chat_tool_calls_total",
+        "Tool calls of the model, by tool",
+        "tool",
+        &metrics.tool_calls,
+    );
+    exposition.counters(
+        "git_chat_child_restarts_total",
+        "Child actors spawned again after they failed or exited, by kind",
+        "child",
+        &metrics.child_restarts,
+    );
+    exposition.family(
+        "git_chat_workflow_duration_seconds",
+        "summary",
+        "How long finished workflows ran, by workflow",
+    );
+    for (workflow, timing) in &metrics.workflows {
+        let labels = [("workflow", workflow.as_str())];
+        exposition.sample(
+            "git_chat_workflow_duration_seconds_sum",
+            &labels,
+            timing.total_secs,
+        );
+        exposition.sample(
+            "git_chat_workflow_duration_seconds_count",
+            &labels,
+            timing.count,
+        );
+    }
+    exposition.family(
+        "git_chat_workflow_duration_seconds_max",
+        "gauge",
+        "Longest run of each workflow, in seconds",
+    );
+    for (workflow, timing) in &metrics.workflows {
+        let labels = [("workflow", workflow.as_str())];
+        exposition.sample(
+            "git_chat_workflow_duration_seconds_max",
+            &labels,
+            timing.max_secs,
+        );
+    }
+    let failures: BTreeMap<String, u64> = metrics
+        .workflows
+        .iter()
+        .map(|(workflow, timing)| (workflow.clone(), timing.failed))
+        .collect();
+    exposition.counters(
+        "git_chat_workflow_failures_total",
+        "Workflows that finished with an error, by workflow",
+        "workflow",
+        &failures,
+    );
+    exposition.text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_render_as_prometheus_samples() {
+        let mut metrics = Metrics::default();
+        metrics.count_request("StartChat");
+        metrics.count_request("GetStatus");
+        metrics.count_request("GetStatus");
+        metrics.count_error("busy");
+        metrics.count_tool_call("git_commit");
+        metrics.count_restart("task-monitor");
+        metrics.record_workflow("commit", 40, false);
+        metrics.record_workflow("commit", 100, true);
+        assert_eq!(
+            metrics.workflows["commit"],
+            Timing {
+                count: 2,
+                failed: 1,
+                total_secs: 140,
+                max_secs: 100,
+            }
+        );
+
+        let snapshot = MetricsSnapshot {
+            actor_id: "git-\"chat\"".to_string(),
+            uptime_secs: Some(300),
+            messages_forwarded: 5,
+            generations_requested: 4,
+            generations_finished: 3,
+            metrics,
+        };
+        let text = prometheus(&snapshot);
+        for line in [
+            "# TYPE git_chat_requests_total counter",
+            "git_chat_requests_total{actor=\"git-\\\"chat\\\"\",type=\"GetStatus\"} 2",
+            "git_chat_request_errors_total{actor=\"git-\\\"chat\\\"\",code=\"busy\"} 1",
+            "git_chat_uptime_seconds{actor=\"git-\\\"chat\\\"\"} 300",
+            "git_chat_generations_finished_total{actor=\"git-\\\"chat\\\"\"} 3",
+            "git_chat_workflow_duration_seconds_sum{actor=\"git-\\\"chat\\\"\",workflow=\"commit\"} 140",
+            "git_chat_workflow_duration_seconds_count{actor=\"git-\\\"chat\\\"\",workflow=\"commit\"} 2",
+            "git_chat_child_restarts_total{actor=\"git-\\\"chat\\\"\",child=\"task-monitor\"} 1",
+        ] {
+            assert!(text.lines().any(|candidate| candidate == line), "{}", line);
+        }
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["requests"]["GetStatus"], 2);
+        assert_eq!(json["generations_requested"], 4);
+    }
+}
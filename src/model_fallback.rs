@@ -0,0 +1,41 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A model to fall back to, in the same shape as `model_config`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModelConfig {
+    pub model: String,
+    pub provider: String,
+    /// Other provider settings, passed through to the chat-state actor
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, Value>,
+}
+
+/// Phrases providers use for failures another model may not have.
+const PROVIDER_ERROR_PHRASES: &[&str] = &[
+    "rate limit",
+    "rate_limit",
+    "too many requests",
+    "overloaded",
+    "unavailable",
+    "timed out",
+    "timeout",
+    "internal server error",
+    "bad gateway",
+];
+
+/// Whether a generation error is a rate limit or provider outage, as opposed to a
+/// problem with the request that every model would reject.
+pub fn is_provider_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    if PROVIDER_ERROR_PHRASES
+        .iter()
+        .any(|phrase| error.contains(phrase))
+    {
+        return true;
+    }
+    Regex::new(r"\b(429|500|502|503|504|529)\b")
+        .map(|status| status.is_match(&error))
+        .unwrap_or(false)
+}
@@ -0,0 +1,119 @@
+//! Named model presets, e.g. `fast`, `quality`, and `cheap`: a model config with its
+//! generation parameters, named in place of `model_config`, switched to at runtime with
+//! `SetModelPreset`, or switched to at stages of a workflow with `workflow_presets`.
+
+use crate::config_patch::GenerationConfig;
+use crate::model_fallback::ModelConfig;
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// One entry of the `model_presets` config.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModelPreset {
+    pub model_config: ModelConfig,
+    /// Kept from the current config when unset
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+}
+
+/// Stages of a workflow `workflow_presets` can switch presets at.
+pub const STAGES: &[&str] = &[
+    // The workflow starts, before its opening message
+    "start",
+    // The commit workflow's commit plan is approved, with `commit_split`
+    "plan-approved",
+];
+
+impl ModelPreset {
+    /// `current` on this preset's model and parameters.
+    pub fn generation_config(&self, current: &GenerationConfig) -> GenerationConfig {
+        GenerationConfig {
+            model_config: self.model_config.clone(),
+            temperature: self.temperature.unwrap_or(current.temperature),
+            max_tokens: self.max_tokens.unwrap_or(current.max_tokens),
+        }
+    }
+}
+
+/// The `model_presets` of a raw config, if they parse.
+pub fn presets(raw: &Value) -> BTreeMap<String, ModelPreset> {
+    serde_json::from_value(raw["model_presets"].clone()).unwrap_or_default()
+}
+
+/// Replace a preset name in `model_config` with the preset, and fill in `temperature`
+/// and `max_tokens` from it unless they are set. Returns the preset's name, if any.
+pub fn resolve(mut raw: Value) -> Result<(Value, Option<String>), String> {
+    let Some(name) = raw["model_config"].as_str().map(str::to_string) else {
+        return Ok((raw, None));
+    };
+    let preset = presets(&raw)
+        .remove(&name)
+        .ok_or_else(|| format!("model_config names an unknown model preset `{}`", name))?;
+    raw["model_config"] = serde_json::to_value(&preset.model_config)
+        .map_err(|e| format!("Failed to serialize model preset {}: {}", name, e))?;
+    if let (Some(temperature), true) = (preset.temperature, raw["temperature"].is_null()) {
+        raw["temperature"] = serde_json::json!(temperature);
+    }
+    if let (Some(max_tokens), true) = (preset.max_tokens, raw["max_tokens"].is_null()) {
+        raw["max_tokens"] = serde_json::json!(max_tokens);
+    }
+    Ok((raw, Some(name)))
+}
+
+/// Preset `workflow_presets` names for `stage` of `workflow`.
+pub fn for_stage<'a>(
+    workflow_presets: &'a BTreeMap<String, BTreeMap<String, String>>,
+    workflow: &Workflow,
+    stage: &str,
+) -> Option<&'a str> {
+    workflow_presets
+        .get(workflow.name())?
+        .get(stage)
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn a_preset_name_in_model_config_is_resolved() {
+        let raw = json!({
+            "model_config": "fast",
+            "max_tokens": 2048,
+            "model_presets": {
+                "fast": {
+                    "model_config": {"model": "claude-haiku-4", "provider": "anthropic"},
+                    "temperature": 0.2,
+                    "max_tokens": 1024
+                }
+            }
+        });
+        let (resolved, name) = resolve(raw).unwrap();
+        assert_eq!(name.as_deref(), Some("fast"));
+        assert_eq!(resolved["model_config"]["model"], "claude-haiku-4");
+        assert_eq!(resolved["temperature"], 0.2);
+        // Set in the config, so the preset doesn't override it
+        assert_eq!(resolved["max_tokens"], 2048);
+
+        assert!(resolve(json!({"model_config": "quality"})).is_err());
+        let (unchanged, name) = resolve(json!({"model_config": {"model": "m"}})).unwrap();
+        assert_eq!(unchanged["model_config"]["model"], "m");
+        assert_eq!(name, None);
+
+        let preset = presets(&resolved);
+        let current = GenerationConfig {
+            model_config: serde_json::from_value(json!({"model": "m", "provider": "p"})).unwrap(),
+            temperature: 0.7,
+            max_tokens: 8192,
+        };
+        let switched = preset["fast"].generation_config(&current);
+        assert_eq!(switched.model_config.model, "claude-haiku-4");
+        assert_eq!((switched.temperature, switched.max_tokens), (0.2, 1024));
+    }
+}
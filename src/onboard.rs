@@ -0,0 +1,289 @@
+//! The `onboard` workflow: the model walks a new contributor through an unfamiliar
+//! repository. The assistant reads its layout, the modules that change most, recent
+//! areas of activity, and the key contributors from the git history, and the model
+//! turns them into an onboarding document, exported with `ExportTranscript`.
+
+use crate::git_tools::run_git;
+use crate::sanitize::{self, TextKind};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// History read when the args don't give `since`.
+const DEFAULT_SINCE: &str = "1 year ago";
+
+/// History that counts as recent activity.
+const RECENT_SINCE: &str = "30 days ago";
+
+/// Entries of each list in the opening message.
+const TOP: usize = 10;
+
+/// A directory (two levels deep at most) or a file at the root, with a count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleCount {
+    pub module: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Contributor {
+    pub name: String,
+    pub commits: usize,
+}
+
+/// What the history says about the repository, for the opening message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoOverview {
+    /// Git date the history is read from, e.g. `1 year ago`
+    pub since: String,
+    pub tracked_files: usize,
+    /// Tracked files per module, most first
+    pub layout: Vec<ModuleCount>,
+    pub commits: usize,
+    /// Commits touching each module since `since`, most first
+    pub most_changed: Vec<ModuleCount>,
+    /// Commits touching each module in the last 30 days, most first
+    pub recent: Vec<ModuleCount>,
+    pub contributors: Vec<Contributor>,
+}
+
+/// `since` from `StartChat { args: {"since": "6 months ago"} }`.
+pub fn since_from_args(args: Option<&Value>) -> String {
+    args.and_then(|args| args.get("since"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|since| !since.is_empty())
+        .unwrap_or(DEFAULT_SINCE)
+        .to_string()
+}
+
+/// The module a path belongs to: its directory, two levels deep at most, or the path
+/// itself for a file at the root.
+pub fn module_of(path: &str) -> String {
+    let parts: Vec<&str> = path.split('/').collect();
+    match parts.len() {
+        1 => path.to_string(),
+        2 => parts[0].to_string(),
+        _ => format!("{}/{}", parts[0], parts[1]),
+    }
+}
+
+fn ranked(counts: BTreeMap<String, usize>) -> Vec<ModuleCount> {
+    let mut ranked: Vec<ModuleCount> = counts
+        .into_iter()
+        .map(|(module, count)| ModuleCount { module, count })
+        .collect();
+    ranked.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.module.cmp(&b.module)));
+    ranked
+}
+
+/// Tracked files per module, from `git ls-files`.
+pub fn count_files(ls_files: &str) -> Vec<ModuleCount> {
+    let mut counts = BTreeMap::new();
+    for path in ls_files.lines().filter(|line| !line.trim().is_empty()) {
+        *counts.entry(module_of(path.trim())).or_insert(0) += 1;
+    }
+    ranked(counts)
+}
+
+/// Commits touching each module, from `git log --format=@%h --name-only`, and the
+/// number of commits. A commit counts once per module.
+pub fn count_commits(log: &str) -> (Vec<ModuleCount>, usize) {
+    let mut counts = BTreeMap::new();
+    let mut commits = 0;
+    let mut modules = BTreeSet::new();
+    for line in log.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if line.starts_with('@') {
+            for module in std::mem::take(&mut modules) {
+                *counts.entry(module).or_insert(0) += 1;
+            }
+            commits += 1;
+        } else {
+            modules.insert(module_of(line));
+        }
+    }
+    for module in modules {
+        *counts.entry(module).or_insert(0) += 1;
+    }
+    (ranked(counts), commits)
+}
+
+/// Authors and their commit counts, from `git shortlog -sn`.
+pub fn parse_shortlog(shortlog: &str) -> Vec<Contributor> {
+    shortlog
+        .lines()
+        .filter_map(|line| {
+            let (commits, name) = line.trim().split_once(char::is_whitespace)?;
+            Some(Contributor {
+                name: name.trim().to_string(),
+                commits: commits.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn log_names(git_actor_id: &str, directory: Option<&str>, since: &str) -> Result<String, String> {
+    run_git(
+        git_actor_id,
+        directory,
+        &[
+            "log",
+            "--no-merges",
+            &format!("--since={}", since),
+            "--format=@%h",
+            "--name-only",
+        ],
+    )
+}
+
+pub fn fetch_overview(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    since: &str,
+) -> Result<RepoOverview, String> {
+    let layout = count_files(&run_git(git_actor_id, directory, &["ls-files"])?);
+    let (most_changed, commits) = count_commits(&log_names(git_actor_id, directory, since)?);
+    let (recent, _) = count_commits(&log_names(git_actor_id, directory, RECENT_SINCE)?);
+    let shortlog = run_git(
+        git_actor_id,
+        directory,
+        &[
+            "shortlog",
+            "-sn",
+            "--no-merges",
+            &format!("--since={}", since),
+            "HEAD",
+        ],
+    )?;
+    Ok(RepoOverview {
+        since: since.to_string(),
+        tracked_files: layout.iter().map(|module| module.count).sum(),
+        layout,
+        commits,
+        most_changed,
+        recent,
+        contributors: parse_shortlog(&shortlog),
+    })
+}
+
+fn list(title: &str, entries: Vec<String>) -> String {
+    if entries.is_empty() {
+        return format!("\n{}: none", title);
+    }
+    let mut block = format!("\n{}:", title);
+    for entry in entries.into_iter().take(TOP) {
+        block.push_str(&format!("\n- {}", entry));
+    }
+    block
+}
+
+fn module_list(title: &str, modules: &[ModuleCount], unit: &str) -> String {
+    let entries = modules
+        .iter()
+        .map(|module| {
+            format!(
+                "{} ({} {})",
+                sanitize::escape(&module.module, TextKind::Name),
+                module.count,
+                unit
+            )
+        })
+        .collect();
+    list(title, entries)
+}
+
+impl RepoOverview {
+    /// Block for the opening message.
+    pub fn describe(&self) -> String {
+        let mut block = format!(
+            "\n\nREPOSITORY OVERVIEW: {} tracked files, {} commits since {}.",
+            self.tracked_files,
+            self.commits,
+            sanitize::escape(&self.since, TextKind::Message)
+        );
+        block.push_str(&module_list(
+            "LAYOUT, files per module",
+            &self.layout,
+            "files",
+        ));
+        block.push_str(&module_list(
+            "MOST CHANGED MODULES",
+            &self.most_changed,
+            "commits",
+        ));
+        block.push_str(&module_list(
+            "RECENT ACTIVITY, last 30 days",
+            &self.recent,
+            "commits",
+        ));
+        let contributors = self
+            .contributors
+            .iter()
+            .map(|contributor| {
+                format!(
+                    "{} ({} commits)",
+                    sanitize::escape(&contributor.name, TextKind::Name),
+                    contributor.commits
+                )
+            })
+            .collect();
+        block.push_str(&list("KEY CONTRIBUTORS", contributors));
+        block
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_is_counted_by_module() {
+        assert_eq!(module_of("Cargo.toml"), "Cargo.toml");
+        assert_eq!(module_of("src/lib.rs"), "src");
+        assert_eq!(module_of("src/protocol/external.rs"), "src/protocol");
+
+        let log = "@4f2c9a1\n\nsrc/lib.rs\nsrc/channel.rs\nREADME.md\n\
+            @9b8e7d6\n\nsrc/protocol/external.rs\nsrc/lib.rs\n\
+            @1a2b3c4\n\nsrc/protocol/conformance.rs\n";
+        let (modules, commits) = count_commits(log);
+        assert_eq!(commits, 3);
+        assert_eq!(
+            modules,
+            vec![
+                ModuleCount {
+                    module: "src".to_string(),
+                    count: 2
+                },
+                ModuleCount {
+                    module: "src/protocol".to_string(),
+                    count: 2
+                },
+                ModuleCount {
+                    module: "README.md".to_string(),
+                    count: 1
+                },
+            ]
+        );
+
+        let contributors = parse_shortlog("    42\tAda Lovelace\n     7\tGrace Hopper\n");
+        assert_eq!(contributors[0].name, "Ada Lovelace");
+        assert_eq!(contributors[1].commits, 7);
+
+        let overview = RepoOverview {
+            since: since_from_args(None),
+            tracked_files: 3,
+            layout: count_files("src/lib.rs\nsrc/channel.rs\nREADME.md\n"),
+            commits,
+            most_changed: modules,
+            recent: Vec::new(),
+            contributors,
+        };
+        let block = overview.describe();
+        assert!(block.starts_with(
+            "\n\nREPOSITORY OVERVIEW: 3 tracked files, 3 commits since 1 year ago.\n\
+            LAYOUT, files per module:\n- src (2 files)"
+        ));
+        assert!(block.contains("RECENT ACTIVITY, last 30 days: none"));
+        assert!(block.ends_with("- Grace Hopper (7 commits)"));
+    }
+}
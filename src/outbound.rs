@@ -0,0 +1,62 @@
+use crate::protocol::{ChatStateRequest, Provenance};
+use genai_types::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Requests kept for a retry; the oldest are dropped beyond this.
+const MAX_QUEUED_SENDS: usize = 100;
+
+/// Attempts, the first send included, before a queued request is given up on.
+pub const MAX_SEND_ATTEMPTS: u32 = 5;
+
+/// A request to a session's chat-state actor that couldn't be sent yet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueuedSend {
+    /// Resolved to the session's chat-state actor on each attempt, so a respawned
+    /// actor gets the request
+    pub session_id: String,
+    pub request: ChatStateRequest,
+    pub attempts: u32,
+    pub last_error: String,
+    /// When the next attempt is due, in seconds since the epoch; right away when unset
+    #[serde(default)]
+    pub retry_at: Option<u64>,
+}
+
+/// Messages held per session while its generation runs; more are refused.
+pub const MAX_HELD_MESSAGES: usize = 20;
+
+/// What happens to a user's message that arrives while its session is generating, the
+/// `generation_overlap` config. Messages the assistant sends itself are always held.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum GenerationOverlap {
+    /// Hold it until the generation ends
+    #[default]
+    Queue,
+    /// Cancel the generation and generate for the message right away
+    Interrupt,
+    /// Refuse it with `busy`
+    Reject,
+}
+
+/// A message that arrived while its session was generating. The messages held for a
+/// session are forwarded together once the generation ends, with one generation for
+/// them all.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HeldMessage {
+    pub message: Message,
+    pub provenance: Provenance,
+}
+
+/// Queue `send`, dropping the oldest request when the queue is full. Returns the
+/// dropped request.
+pub fn enqueue(queue: &mut VecDeque<QueuedSend>, send: QueuedSend) -> Option<QueuedSend> {
+    let dropped = if queue.len() >= MAX_QUEUED_SENDS {
+        queue.pop_front()
+    } else {
+        None
+    };
+    queue.push_back(send);
+    dropped
+}
@@ -0,0 +1,107 @@
+//! Workflow pipelines: `"workflow": ["review", "commit", "changelog"]` runs each
+//! workflow in turn in the default session, starting the next one once the one before
+//! it completes.
+
+use crate::protocol::external::{PipelineResult, WorkflowResult};
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// `workflow` as it may be configured: one workflow, or a sequence of them.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum WorkflowSpec {
+    One(Workflow),
+    Sequence(Vec<Workflow>),
+}
+
+impl WorkflowSpec {
+    /// The configured workflows, in order.
+    pub fn steps(self) -> Vec<Workflow> {
+        match self {
+            WorkflowSpec::One(workflow) => vec![workflow],
+            WorkflowSpec::Sequence(steps) => steps,
+        }
+    }
+}
+
+/// Move a `workflow` (or `task`) sequence into `pipeline`, leaving its first workflow
+/// as `workflow`. A sequence of one is a plain workflow.
+pub fn split_sequence(mut raw: Value) -> Value {
+    let config = match raw.as_object_mut() {
+        Some(config) => config,
+        None => return raw,
+    };
+    for key in ["workflow", "task"] {
+        let steps = match config.get(key) {
+            Some(Value::Array(steps)) => steps.clone(),
+            _ => continue,
+        };
+        config.insert(
+            key.to_string(),
+            steps.first().cloned().unwrap_or(Value::Null),
+        );
+        if steps.len() > 1 {
+            config.insert("pipeline".to_string(), Value::Array(steps));
+        }
+    }
+    raw
+}
+
+/// Progress of a running pipeline.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PipelineRun {
+    pub steps: Vec<Workflow>,
+    /// Results of the steps that have completed, in order
+    pub results: Vec<WorkflowResult>,
+}
+
+impl PipelineRun {
+    pub fn new(steps: Vec<Workflow>) -> Self {
+        Self {
+            steps,
+            results: Vec::new(),
+        }
+    }
+
+    /// The step after the completed ones, if any is left.
+    pub fn next_step(&self) -> Option<&Workflow> {
+        self.steps.get(self.results.len())
+    }
+
+    /// What the previous step did, for the opening message of the current one.
+    pub fn previous_step_context(&self) -> Option<String> {
+        let previous = self.results.last()?;
+        let mut context = format!(
+            "\n\nPIPELINE: This is step {} of {} ({}). The {} step before it is done.",
+            self.results.len() + 1,
+            self.steps.len(),
+            self.steps
+                .iter()
+                .map(Workflow::name)
+                .collect::<Vec<_>>()
+                .join(" -> "),
+            previous.workflow.name()
+        );
+        if !previous.commits_created.is_empty() {
+            context.push_str(" It created these commits:");
+            for commit in &previous.commits_created {
+                context.push_str(&format!("\n- {} {}", commit.sha, commit.subject));
+            }
+        }
+        if let Some(summary) = &previous.summary {
+            context.push_str(&format!("\nIts summary:\n{}", summary));
+        }
+        Some(context)
+    }
+
+    /// The aggregate result, once the last step has completed or a step has failed.
+    pub fn finish(self, error: Option<String>) -> PipelineResult {
+        let skipped = self.steps[self.results.len().min(self.steps.len())..].to_vec();
+        PipelineResult {
+            steps: self.results,
+            skipped,
+            error,
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Repository states a workflow must leave behind, checked with git when it completes.
+//! A workflow that misses one fails instead of reporting success.
+
+use crate::errors::ErrorCode;
+use crate::git_tools::run_git;
+use crate::signatures::SignatureStatus;
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Files or commits named in a failure before the rest are counted.
+const MAX_LISTED: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Postcondition {
+    /// No changes, staged or not, and no untracked files
+    CleanWorktree,
+    /// Nothing left in the index
+    NoStagedChanges,
+    /// HEAD moved since the workflow started
+    NewCommits,
+    /// No unmerged files
+    NoConflicts,
+    /// Tag `tag` exists, or, without a name, a tag points at HEAD
+    TagExists {
+        #[serde(default)]
+        tag: Option<String>,
+    },
+    /// Every commit since the workflow started has a `Signed-off-by` trailer
+    SignedOff,
+    /// Every commit since the workflow started has a good GPG signature
+    GpgSigned,
+}
+
+/// A postcondition that wasn't met, and what was found instead.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PostconditionFailure {
+    pub postcondition: Postcondition,
+    pub detail: String,
+}
+
+/// Postconditions of `workflow` in the `postconditions` config.
+pub fn for_workflow<'a>(
+    postconditions: &'a BTreeMap<String, Vec<Postcondition>>,
+    workflow: &Workflow,
+) -> &'a [Postcondition] {
+    postconditions
+        .get(workflow.name())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// The postconditions the `require_signoff` and `gpg_sign` configs add to the commit
+/// workflow.
+pub fn signing(require_signoff: bool, gpg_sign: bool) -> Vec<Postcondition> {
+    let mut postconditions = Vec::new();
+    if require_signoff {
+        postconditions.push(Postcondition::SignedOff);
+    }
+    if gpg_sign {
+        postconditions.push(Postcondition::GpgSigned);
+    }
+    postconditions
+}
+
+/// The postconditions that aren't met. One that can't be checked counts as not met.
+/// `commit_base` is HEAD when the workflow started.
+pub fn check(
+    postconditions: &[Postcondition],
+    git_actor_id: &str,
+    directory: Option<&str>,
+    commit_base: Option<&str>,
+) -> Vec<PostconditionFailure> {
+    postconditions
+        .iter()
+        .filter_map(|postcondition| {
+            let detail = match check_one(postcondition, git_actor_id, directory, commit_base) {
+                Ok(None) => return None,
+                Ok(Some(detail)) => detail,
+                Err(e) => format!("could not be checked: {}", e),
+            };
+            Some(PostconditionFailure {
+                postcondition: postcondition.clone(),
+                detail,
+            })
+        })
+        .collect()
+}
+
+/// What breaks `postcondition`, or `None` when it is met.
+fn check_one(
+    postcondition: &Postcondition,
+    git_actor_id: &str,
+    directory: Option<&str>,
+    commit_base: Option<&str>,
+) -> Result<Option<String>, String> {
+    match postcondition {
+        Postcondition::CleanWorktree => {
+            let status = run_git(git_actor_id, directory, &["status", "--porcelain"])?;
+            let files = porcelain_files(&status, |_| true);
+            Ok((!files.is_empty()).then(|| describe_files("changed or untracked", &files)))
+        }
+        Postcondition::NoStagedChanges => {
+            let status = run_git(git_actor_id, directory, &["status", "--porcelain"])?;
+            let files = porcelain_files(&status, |index| index != ' ' && index != '?');
+            Ok((!files.is_empty()).then(|| describe_files("still staged", &files)))
+        }
+        Postcondition::NewCommits => {
+            let base = match commit_base {
+                Some(base) => base,
+                None => return Ok(Some("no commit base was recorded".to_string())),
+            };
+            let head = run_git(git_actor_id, directory, &["rev-parse", "HEAD"])?;
+            Ok((head.trim() == base).then(|| format!("HEAD is still at {}", base)))
+        }
+        Postcondition::NoConflicts => {
+            let unmerged = run_git(
+                git_actor_id,
+                directory,
+                &["diff", "--name-only", "--diff-filter=U"],
+            )?;
+            let files: Vec<String> = unmerged
+                .lines()
+                .map(str::trim)
+                .filter(|file| !file.is_empty())
+                .map(str::to_string)
+                .collect();
+            Ok((!files.is_empty()).then(|| describe_files("still conflicted", &files)))
+        }
+        Postcondition::TagExists { tag: Some(tag) } => {
+            let tags = run_git(git_actor_id, directory, &["tag", "--list", tag])?;
+            Ok((!tags.lines().any(|line| line.trim() == tag))
+                .then(|| format!("tag {} doesn't exist", tag)))
+        }
+        Postcondition::TagExists { tag: None } => {
+            let tags = run_git(git_actor_id, directory, &["tag", "--points-at", "HEAD"])?;
+            Ok(tags
+                .trim()
+                .is_empty()
+                .then(|| "no tag points at HEAD".to_string()))
+        }
+        Postcondition::SignedOff | Postcondition::GpgSigned => {
+            let base = match commit_base {
+                Some(base) => base,
+                None => return Ok(Some("no commit base was recorded".to_string())),
+            };
+            let log = run_git(
+                git_actor_id,
+                directory,
+                &["log", SIGNING_FORMAT, &format!("{}..HEAD", base)],
+            )?;
+            let commits = parse_signing_log(&log);
+            let (missing, state) = if *postcondition == Postcondition::SignedOff {
+                let missing = commits.iter().filter(|commit| !commit.signed_off);
+                (
+                    missing.collect::<Vec<_>>(),
+                    "without a Signed-off-by trailer",
+                )
+            } else {
+                let missing = commits
+                    .iter()
+                    .filter(|commit| commit.signature != SignatureStatus::Signed);
+                (missing.collect(), "without a good GPG signature")
+            };
+            let shas: Vec<String> = missing.iter().map(|commit| commit.sha.clone()).collect();
+            Ok((!shas.is_empty()).then(|| describe_commits(state, &shas)))
+        }
+    }
+}
+
+/// One record per commit: short sha, `%G?`, and the `Signed-off-by` values.
+const SIGNING_FORMAT: &str =
+    "--format=%x1e%h%x1f%G?%x1f%(trailers:key=Signed-off-by,valueonly,separator=%x2c)";
+
+/// What the signing postconditions look at in a commit.
+#[derive(Debug, Clone, PartialEq)]
+struct CommitSigning {
+    sha: String,
+    signature: SignatureStatus,
+    signed_off: bool,
+}
+
+fn parse_signing_log(log: &str) -> Vec<CommitSigning> {
+    log.split('\u{1e}')
+        .filter_map(|record| {
+            let mut fields = record.split('\u{1f}');
+            let sha = fields.next()?.trim();
+            if sha.is_empty() {
+                return None;
+            }
+            Some(CommitSigning {
+                sha: sha.to_string(),
+                signature: SignatureStatus::from_code(fields.next().unwrap_or("N").trim()),
+                signed_off: fields
+                    .next()
+                    .is_some_and(|trailers| !trailers.trim().is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// Paths in `git status --porcelain` output whose index status passes `index`.
+fn porcelain_files(status: &str, index: impl Fn(char) -> bool) -> Vec<String> {
+    status
+        .lines()
+        .filter(|line| line.len() > 3 && line.chars().next().is_some_and(&index))
+        .map(|line| line[3..].to_string())
+        .collect()
+}
+
+fn describe_commits(state: &str, shas: &[String]) -> String {
+    describe_list("commits", state, shas)
+}
+
+fn describe_files(state: &str, files: &[String]) -> String {
+    describe_list("files", state, files)
+}
+
+fn describe_list(noun: &str, state: &str, entries: &[String]) -> String {
+    let mut detail = format!(
+        "{} {} {}: {}",
+        entries.len(),
+        noun,
+        state,
+        entries
+            .iter()
+            .take(MAX_LISTED)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if entries.len() > MAX_LISTED {
+        detail.push_str(&format!(" and {} more", entries.len() - MAX_LISTED));
+    }
+    detail
+}
+
+/// The workflow error for `failures`.
+pub fn describe(workflow: &Workflow, failures: &[PostconditionFailure]) -> String {
+    format!(
+        "{}: the {} workflow completed, but {}",
+        ErrorCode::PostconditionsFailed,
+        workflow.name(),
+        failures
+            .iter()
+            .map(|failure| failure.detail.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signing_log_is_parsed_per_commit() {
+        let log = "\u{1e}4f2c9a1\u{1f}G\u{1f}Ada Lovelace <ada@example.com>\n\
+            \u{1e}9b8e7d6\u{1f}N\u{1f}\n";
+        let commits = parse_signing_log(log);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].signature, SignatureStatus::Signed);
+        assert!(commits[0].signed_off);
+        assert_eq!(commits[1].signature, SignatureStatus::Unsigned);
+        assert!(!commits[1].signed_off);
+
+        assert_eq!(signing(true, false), vec![Postcondition::SignedOff]);
+        assert_eq!(
+            describe_commits("without a good GPG signature", &["9b8e7d6".to_string()]),
+            "1 commits without a good GPG signature: 9b8e7d6"
+        );
+    }
+}
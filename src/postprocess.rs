@@ -0,0 +1,126 @@
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A step applied to the model's output before the assistant uses it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PostProcessStep {
+    /// Unwrap text the model put entirely inside a ``` fence
+    StripFences,
+    /// Keep only the last fenced JSON block, when there is one
+    ExtractJson,
+    /// Drop trailing spaces and surrounding blank lines, and collapse runs of blank
+    /// lines into one
+    NormalizeWhitespace,
+}
+
+impl PostProcessStep {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            PostProcessStep::StripFences => strip_fences(text),
+            PostProcessStep::ExtractJson => extract_json(text),
+            PostProcessStep::NormalizeWhitespace => normalize_whitespace(text),
+        }
+    }
+
+    /// Whether the step keeps text usable as a commit message.
+    fn suits_commit_messages(self) -> bool {
+        !matches!(self, PostProcessStep::ExtractJson)
+    }
+}
+
+/// The `postprocess` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PostProcessConfig {
+    /// Steps for single workflows, keyed by workflow name, replacing their built-in
+    /// steps; an empty list turns post-processing off
+    #[serde(default)]
+    pub workflows: BTreeMap<String, Vec<PostProcessStep>>,
+}
+
+impl PostProcessConfig {
+    /// Steps applied to the replies and the task summary of `workflow`, in order.
+    pub fn steps(&self, workflow: Option<&Workflow>) -> Vec<PostProcessStep> {
+        let configured = workflow.and_then(|workflow| self.workflows.get(workflow.name()));
+        match configured {
+            Some(steps) => steps.clone(),
+            None => default_steps(workflow),
+        }
+    }
+
+    /// Apply the steps of `workflow` to `text`.
+    pub fn apply(&self, workflow: Option<&Workflow>, text: &str) -> String {
+        self.steps(workflow)
+            .into_iter()
+            .fold(text.to_string(), |text, step| step.apply(&text))
+    }
+
+    /// Apply the steps of `workflow` that suit a commit message the model wrote.
+    pub fn apply_to_commit_message(&self, workflow: Option<&Workflow>, message: &str) -> String {
+        self.steps(workflow)
+            .into_iter()
+            .filter(|step| step.suits_commit_messages())
+            .fold(message.to_string(), |message, step| step.apply(&message))
+    }
+}
+
+fn default_steps(workflow: Option<&Workflow>) -> Vec<PostProcessStep> {
+    match workflow {
+        Some(Workflow::Rebase) => vec![
+            PostProcessStep::ExtractJson,
+            PostProcessStep::StripFences,
+            PostProcessStep::NormalizeWhitespace,
+        ],
+        Some(Workflow::PrDescription | Workflow::Changelog) => vec![
+            PostProcessStep::StripFences,
+            PostProcessStep::NormalizeWhitespace,
+        ],
+        _ => vec![PostProcessStep::NormalizeWhitespace],
+    }
+}
+
+fn strip_fences(text: &str) -> String {
+    let trimmed = text.trim();
+    let inner = match trimmed
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    {
+        Some(inner) => inner,
+        None => return text.to_string(),
+    };
+    // The opening fence may name a language; the content starts on the next line
+    match inner.split_once('\n') {
+        Some((info, content)) if !info.trim().contains(char::is_whitespace) => {
+            // A fence inside means the text holds several blocks, not one wrapped block
+            if content.contains("```") {
+                return text.to_string();
+            }
+            content.trim_end().to_string()
+        }
+        _ => text.to_string(),
+    }
+}
+
+fn extract_json(text: &str) -> String {
+    text.split("```json")
+        .skip(1)
+        .filter_map(|block| block.split("```").next())
+        .last()
+        .map(|json| json.trim().to_string())
+        .unwrap_or_else(|| text.to_string())
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
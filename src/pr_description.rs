@@ -0,0 +1,133 @@
+use crate::git_tools::{ensure_revision, run_git};
+use crate::renames;
+use crate::sanitize::{self, TextKind};
+use serde::{Deserialize, Serialize};
+
+/// The `pr_description` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PrDescriptionConfig {
+    /// Branch the pull request will be merged into
+    #[serde(default = "default_base_branch")]
+    pub base_branch: String,
+    /// Markdown skeleton the description must follow, e.g. a repository's PR template
+    pub template: Option<String>,
+    /// Labels `SuggestPrDescription` picks from; any label when empty
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+fn default_base_branch() -> String {
+    "main".to_string()
+}
+
+impl Default for PrDescriptionConfig {
+    fn default() -> Self {
+        Self {
+            base_branch: default_base_branch(),
+            template: None,
+            labels: Vec::new(),
+        }
+    }
+}
+
+impl PrDescriptionConfig {
+    pub fn prompt_fragment(&self) -> String {
+        let mut fragment = format!(
+            "\n\nPULL REQUEST SETTINGS:\n- Base branch: {}",
+            self.base_branch
+        );
+        match &self.template {
+            Some(template) => fragment.push_str(&format!(
+                "\n- Fill in this template, keeping its headings in order:\n{}",
+                template
+            )),
+            None => fragment.push_str(
+                "\n- Format: a first line with the title, then ## Summary, ## Testing, \
+                and ## Risk sections",
+            ),
+        }
+        fragment
+    }
+}
+
+/// Markers around the changelog section the assistant keeps up to date in a pull
+/// request description; text outside them is left alone.
+const CHANGELOG_START: &str = "<!-- git-chat-assistant:changelog:start -->";
+const CHANGELOG_END: &str = "<!-- git-chat-assistant:changelog:end -->";
+
+/// Changelog section listing the branch's commits against `base`, newest first.
+pub fn fetch_changelog_section(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: &str,
+) -> Result<String, String> {
+    ensure_revision("base branch", base)?;
+    let commits = run_git(
+        git_actor_id,
+        directory,
+        &["log", "--format=%h %s", &format!("{}..HEAD", base)],
+    )?;
+    let entries: Vec<String> = commits
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(sha, subject)| format!("- {} ({})", subject, sha))
+        .collect();
+    Ok(format!(
+        "{}\n## Changelog\n\n{}\n{}",
+        CHANGELOG_START,
+        entries.join("\n"),
+        CHANGELOG_END
+    ))
+}
+
+/// `body` with its changelog section replaced by `section`, or with `section` appended
+/// when it has none yet.
+pub fn replace_changelog_section(body: &str, section: &str) -> String {
+    if let Some(start) = body.find(CHANGELOG_START) {
+        if let Some(end) = body[start..].find(CHANGELOG_END) {
+            let end = start + end + CHANGELOG_END.len();
+            return format!("{}{}{}", &body[..start], section, &body[end..]);
+        }
+    }
+    if body.trim().is_empty() {
+        return section.to_string();
+    }
+    format!("{}\n\n{}", body.trim_end(), section)
+}
+
+/// Commits, diff stat, and renames and copies of the branch against `base`, for the
+/// auto-initiation message.
+pub fn fetch_branch_overview(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: &str,
+) -> Result<String, String> {
+    ensure_revision("base branch", base)?;
+    let commits = run_git(
+        git_actor_id,
+        directory,
+        &["log", "--format=%h %s", &format!("{}..HEAD", base)],
+    )?;
+    let stat = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--stat", "-M", "-C", &format!("{}...HEAD", base)],
+    )?;
+    let moves = renames::fetch(git_actor_id, directory, &[&format!("{}...HEAD", base)])?;
+    if commits.trim().is_empty() {
+        return Ok(format!(
+            "\n\nNOTE: The branch has no commits that aren't on {}; say so instead of \
+            writing a description.",
+            base
+        ));
+    }
+    Ok(format!(
+        "\n\nBRANCH COMPARED WITH {}:\nCommits:\n{}\n\nDiff stat:\n{}\n{}",
+        base,
+        sanitize::escape(commits.trim(), TextKind::Message),
+        sanitize::escape(stat.trim(), TextKind::Message),
+        renames::render(&moves)
+    )
+    .trim_end()
+    .to_string())
+}
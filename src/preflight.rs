@@ -0,0 +1,205 @@
+//! Preflight checks of the environment at init: the manifests the assistant spawns
+//! actors from exist, `current_directory` is a git repository, and the MCP actors the
+//! assistant needs can be spawned. The report is kept for `GetConfigDiagnostics`.
+
+use crate::config_check::{ConfigDiagnostic, Severity};
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    /// The check could not tell; the assistant starts anyway
+    Warning,
+    Failed,
+    /// Nothing to check, e.g. a remote manifest
+    Skipped,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PreflightCheck {
+    /// What was checked, e.g. `git_repository` or `manifest:mcp_servers[0]`
+    pub name: String,
+    pub status: CheckStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl PreflightCheck {
+    fn new(name: &str, status: CheckStatus, detail: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PreflightReport {
+    pub checked_at: u64,
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    pub fn failures(&self) -> Vec<&PreflightCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Failed)
+            .collect()
+    }
+
+    /// Failed checks on one line, for the init error with `strict_preflight`.
+    pub fn describe_failures(&self) -> String {
+        self.failures()
+            .iter()
+            .map(|check| match &check.detail {
+                Some(detail) => format!("{}: {}", check.name, detail),
+                None => check.name.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// One check per configured manifest path, from what `check_manifest_paths` found.
+pub fn manifest_checks(
+    paths: &[(String, String)],
+    diagnostics: &[ConfigDiagnostic],
+) -> Vec<PreflightCheck> {
+    paths
+        .iter()
+        .map(|(field, path)| {
+            let name = format!("manifest:{}", field);
+            match diagnostics
+                .iter()
+                .find(|diagnostic| &diagnostic.field == field)
+            {
+                Some(diagnostic) if diagnostic.severity == Severity::Error => PreflightCheck::new(
+                    &name,
+                    CheckStatus::Failed,
+                    Some(diagnostic.message.clone()),
+                ),
+                Some(diagnostic) => PreflightCheck::new(
+                    &name,
+                    CheckStatus::Warning,
+                    Some(diagnostic.message.clone()),
+                ),
+                None => PreflightCheck::new(&name, CheckStatus::Passed, Some(path.clone())),
+            }
+        })
+        .collect()
+}
+
+/// A manifest the assistant spawns from itself; remote ones are fetched by the runtime.
+pub fn builtin_manifest_check(
+    name: &str,
+    path: &str,
+    present: impl FnOnce(&str) -> Result<bool, String>,
+) -> PreflightCheck {
+    let name = format!("manifest:{}", name);
+    if path.contains("://") {
+        return PreflightCheck::new(
+            &name,
+            CheckStatus::Skipped,
+            Some(format!("remote {}", path)),
+        );
+    }
+    match present(path) {
+        Ok(true) => PreflightCheck::new(&name, CheckStatus::Passed, Some(path.to_string())),
+        Ok(false) => PreflightCheck::new(
+            &name,
+            CheckStatus::Failed,
+            Some(format!("manifest {} does not exist", path)),
+        ),
+        Err(e) => PreflightCheck::new(&name, CheckStatus::Warning, Some(e)),
+    }
+}
+
+/// Whether spawning one of the assistant's MCP actors worked.
+pub fn actor_check(name: &str, spawned: &Result<String, String>) -> PreflightCheck {
+    let name = format!("actor:{}", name);
+    match spawned {
+        Ok(actor_id) => PreflightCheck::new(&name, CheckStatus::Passed, Some(actor_id.clone())),
+        Err(e) => PreflightCheck::new(&name, CheckStatus::Failed, Some(e.clone())),
+    }
+}
+
+/// Whether `directory` is inside a git work tree.
+pub fn repository_check(git_actor_id: Option<&str>, directory: Option<&str>) -> PreflightCheck {
+    let name = "git_repository";
+    let Some(directory) = directory else {
+        return PreflightCheck::new(
+            name,
+            CheckStatus::Failed,
+            Some("no current_directory, and none was found".to_string()),
+        );
+    };
+    let Some(git_actor_id) = git_actor_id else {
+        return PreflightCheck::new(
+            name,
+            CheckStatus::Skipped,
+            Some("the git tools actor could not be spawned".to_string()),
+        );
+    };
+    match run_git(
+        git_actor_id,
+        Some(directory),
+        &["rev-parse", "--is-inside-work-tree"],
+    ) {
+        Ok(output) if output.trim() == "true" => {
+            PreflightCheck::new(name, CheckStatus::Passed, Some(directory.to_string()))
+        }
+        Ok(_) | Err(_) => PreflightCheck::new(
+            name,
+            CheckStatus::Failed,
+            Some(format!("{} is not a git repository", directory)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_follow_what_was_found() {
+        let paths = vec![
+            (
+                "mcp_servers[0].actor.manifest_path".to_string(),
+                "/a.toml".to_string(),
+            ),
+            ("forge_mcp.manifest_path".to_string(), "/b.toml".to_string()),
+        ];
+        let diagnostics = vec![ConfigDiagnostic::error(
+            "forge_mcp.manifest_path",
+            "manifest /b.toml does not exist".to_string(),
+        )];
+        let mut checks = manifest_checks(&paths, &diagnostics);
+        assert_eq!(checks[0].status, CheckStatus::Passed);
+        assert_eq!(checks[1].status, CheckStatus::Failed);
+
+        checks.push(builtin_manifest_check(
+            "chat_state",
+            "https://x/manifest.toml",
+            |_| panic!("remote manifests are not checked"),
+        ));
+        checks.push(builtin_manifest_check("chat_state", "/c.toml", |_| {
+            Ok(true)
+        }));
+        checks.push(actor_check("git_tools", &Err("no network".to_string())));
+        assert_eq!(checks[2].status, CheckStatus::Skipped);
+        assert_eq!(checks[3].status, CheckStatus::Passed);
+
+        let report = PreflightReport {
+            checked_at: 0,
+            checks,
+        };
+        assert_eq!(
+            report.describe_failures(),
+            "manifest:forge_mcp.manifest_path: manifest /b.toml does not exist; \
+             actor:git_tools: no network"
+        );
+    }
+}
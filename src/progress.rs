@@ -0,0 +1,136 @@
+//! Progress of the auto-initiated workflow, for `GetWorkflowProgress` and the
+//! `WorkflowProgress` event: the phase it is in, read from the model's git tool calls,
+//! the files it has worked through, its turns and time, and the approvals it waits for.
+
+use crate::command_policy;
+use crate::git_tools::run_git;
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProgressPhase {
+    /// Reading the repository, before anything is staged
+    #[default]
+    Analyzing,
+    /// Staging, unstaging, or stashing changes
+    Staging,
+    /// Creating or rewriting commits
+    Committing,
+    /// The model signalled completion and its work is being checked
+    Verifying,
+}
+
+impl ProgressPhase {
+    /// The phase a git subcommand run by the model moves the workflow to; `None` for
+    /// subcommands that only read.
+    pub fn of_subcommand(subcommand: &str) -> Option<ProgressPhase> {
+        match subcommand {
+            "add" | "restore" | "reset" | "rm" | "mv" | "stash" => Some(ProgressPhase::Staging),
+            "commit" | "cherry-pick" | "revert" | "merge" | "rebase" | "tag" => {
+                Some(ProgressPhase::Committing)
+            }
+            _ => None,
+        }
+    }
+
+    /// The phase a model's tool call moves the workflow to, if any.
+    pub fn of_tool_call(tool: &str, arguments: Option<&Value>) -> Option<ProgressPhase> {
+        command_policy::tool_subcommand(tool, arguments)
+            .as_deref()
+            .and_then(ProgressPhase::of_subcommand)
+    }
+}
+
+/// Files the workflow has worked through so far.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FileCounts {
+    pub committed: usize,
+    pub staged: usize,
+    pub pending: usize,
+}
+
+/// Staged and pending (staged, changed, or untracked) files in `git status --porcelain`
+/// output.
+fn count_status(status: &str) -> (usize, usize) {
+    let entries: Vec<&str> = status.lines().filter(|line| line.len() > 3).collect();
+    let staged = entries
+        .iter()
+        .filter(|line| !matches!(line.as_bytes()[0], b' ' | b'?' | b'!'))
+        .count();
+    (staged, entries.len())
+}
+
+pub fn fetch_file_counts(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: Option<&str>,
+) -> Result<FileCounts, String> {
+    let status = run_git(git_actor_id, directory, &["status", "--porcelain"])?;
+    let (staged, pending) = count_status(&status);
+    let committed = match base {
+        Some(base) => run_git(
+            git_actor_id,
+            directory,
+            &["diff", "--name-only", base, "HEAD"],
+        )?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count(),
+        None => 0,
+    };
+    Ok(FileCounts {
+        committed,
+        staged,
+        pending,
+    })
+}
+
+/// Where the auto-initiated workflow is.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorkflowProgress {
+    pub workflow: Workflow,
+    pub turns: u32,
+    /// Turns the workflow has before it is asked to wrap up, from `max_turns`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+    pub phase: ProgressPhase,
+    /// Seconds since the workflow started
+    pub elapsed_secs: u64,
+    /// Files changed by the commits made since the workflow started
+    pub files_committed: usize,
+    pub files_staged: usize,
+    /// Files with uncommitted changes, staged or not, and untracked files
+    pub files_pending: usize,
+    /// Approvals the workflow waits for, e.g. `confirmation confirm-3: delete-branches`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_on: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn phases_follow_the_git_tool_calls() {
+        assert_eq!(
+            ProgressPhase::of_tool_call("git_add", None),
+            Some(ProgressPhase::Staging)
+        );
+        assert_eq!(
+            ProgressPhase::of_tool_call("git_cherry_pick", None),
+            Some(ProgressPhase::Committing)
+        );
+        assert_eq!(
+            ProgressPhase::of_tool_call("git", Some(&json!({"args": ["commit", "-m", "fix"]}))),
+            Some(ProgressPhase::Committing)
+        );
+        assert_eq!(ProgressPhase::of_tool_call("git_diff", None), None);
+        assert_eq!(ProgressPhase::of_tool_call("read_file", None), None);
+
+        let status = "M  src/lib.rs\n M README.md\nA  src/progress.rs\n?? notes.txt\n";
+        assert_eq!(count_status(status), (2, 4));
+    }
+}
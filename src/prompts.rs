@@ -0,0 +1,464 @@
+//! Named templates the system prompt is composed from, and the `prompts` config that
+//! overrides single templates without replacing the whole prompt.
+
+use crate::commit_template;
+use crate::locale::{LanguageConfig, Locale};
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Opening of the system prompt, before any context.
+pub const BASE: &str = "You are a Git Task Assistant with access to git tools. You specialize \
+    in completing specific git-related tasks efficiently and thoroughly.\n\
+    \n\
+    AVAILABLE CAPABILITIES:\n\
+    - Git repository operations (status, diff, log, branch management)\n\
+    - File staging and commit creation\n\
+    - Branch operations and history analysis\n\
+    - Code review and quality assessment\n\
+    - Repository cleanup and organization\n\
+    - Task completion signaling\n\
+    \n\
+    APPROACH:\n\
+    - Always start by understanding the current repository state\n\
+    - Break down complex tasks into clear steps\n\
+    - Provide explanations for all git operations\n\
+    - Follow git best practices and conventions\n\
+    - Signal completion when tasks are finished";
+
+/// Where the model works, with `{{directory}}` filled in.
+pub const DIRECTORY: &str = "WORKING DIRECTORY: {{directory}}\n\
+    All git operations should be performed in this directory.";
+
+/// Completion instruction for workflows, when the task monitor is used.
+pub const TASK_COMPLETE_REQUIRED: &str = "\n\nIMPORTANT: When you have completed your assigned \
+    task, you MUST call the 'task_complete' tool to signal that the work is finished. This allows \
+    the system to properly conclude the task session.";
+
+/// Completion instruction for interactive sessions, when the task monitor is used.
+pub const TASK_COMPLETE_AVAILABLE: &str = "\n\nNOTE: You have access to a 'task_complete' tool. \
+    Use it if the user explicitly asks you to complete a specific task or when you finish a \
+    well-defined piece of work.";
+
+/// Language of commit messages, with `{{language}}` and `{{instruction}}` filled in.
+pub const COMMIT_LANGUAGE: &str = "COMMIT MESSAGE LANGUAGE: {{instruction}} Write commit \
+    message subjects and bodies in {{language}}, but keep code identifiers, file paths, and \
+    conventional commit types such as `feat:` as they are. Commit messages in another \
+    language are sent back to you.";
+
+/// Language of replies, with `{{language}}` and `{{instruction}}` filled in.
+pub const REPLY_LANGUAGE: &str = "REPLY LANGUAGE: {{instruction}} Answer and explain your \
+    work in {{language}}, whatever language the repository and its history are in. Git \
+    commands and tool calls stay as they are.";
+
+/// Instructions for commit messages and for replies, in the locale's own language.
+fn locale_instructions(locale: Locale) -> (&'static str, &'static str) {
+    match locale {
+        Locale::En => (
+            "Write commit messages in English.",
+            "Write your replies and explanations in English.",
+        ),
+        Locale::De => (
+            "Schreibe Commit-Nachrichten auf Deutsch.",
+            "Schreibe deine Antworten und Erklärungen auf Deutsch.",
+        ),
+        Locale::Fr => (
+            "Rédige les messages de commit en français.",
+            "Rédige tes réponses et explications en français.",
+        ),
+        Locale::Es => (
+            "Escribe los mensajes de commit en español.",
+            "Escribe tus respuestas y explicaciones en español.",
+        ),
+        Locale::It => (
+            "Scrivi i messaggi di commit in italiano.",
+            "Scrivi le tue risposte e spiegazioni in italiano.",
+        ),
+        Locale::Pt => (
+            "Escreva as mensagens de commit em português.",
+            "Escreva suas respostas e explicações em português.",
+        ),
+        Locale::Nl => (
+            "Schrijf commitberichten in het Nederlands.",
+            "Schrijf je antwoorden en uitleg in het Nederlands.",
+        ),
+        Locale::Ru => (
+            "Пиши сообщения коммитов на русском языке.",
+            "Пиши ответы и объяснения на русском языке.",
+        ),
+        Locale::Ja => (
+            "コミットメッセージは日本語で書いてください。",
+            "回答と説明は日本語で書いてください。",
+        ),
+        Locale::Zh => ("请用中文撰写提交信息。", "请用中文撰写回答和说明。"),
+        Locale::Ko => (
+            "커밋 메시지는 한국어로 작성하세요.",
+            "답변과 설명은 한국어로 작성하세요.",
+        ),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PrimingRole {
+    User,
+    Assistant,
+}
+
+/// A message of a workflow's priming sequence. Example assistant turns show the model
+/// what a good answer looks like before the real task comes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PrimingMessage {
+    pub role: PrimingRole,
+    pub text: String,
+}
+
+/// The `prompts` section of the assistant config. Every template may use `{{name}}`
+/// placeholders for `vars`, `{{directory}}`, and `{{workflow}}`; opening messages may
+/// use the `StartChat` args too.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PromptConfig {
+    /// Replaces [`BASE`]; `system_prompt` still takes precedence
+    pub base: Option<String>,
+    /// Replaces [`DIRECTORY`]
+    pub directory: Option<String>,
+    /// Replaces the instruction on how to signal completion
+    pub completion: Option<String>,
+    /// Replaces [`COMMIT_LANGUAGE`]
+    pub commit_language: Option<String>,
+    /// Replaces [`REPLY_LANGUAGE`]
+    pub reply_language: Option<String>,
+    /// Replace the steps of single workflows, keyed by workflow name
+    #[serde(default)]
+    pub workflows: BTreeMap<String, String>,
+    /// Replace the opening messages of single workflows, keyed by workflow name
+    #[serde(default)]
+    pub auto_messages: BTreeMap<String, String>,
+    /// Messages sent before the opening messages of single workflows, in order, keyed
+    /// by workflow name
+    #[serde(default)]
+    pub priming: BTreeMap<String, Vec<PrimingMessage>>,
+    /// Values for placeholders in the templates
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+}
+
+impl PromptConfig {
+    /// `vars` with the built-in values added; configured values win.
+    pub fn values(
+        &self,
+        directory: Option<&str>,
+        workflow: Option<&Workflow>,
+    ) -> BTreeMap<String, String> {
+        let mut values = BTreeMap::new();
+        if let Some(directory) = directory {
+            values.insert("directory".to_string(), directory.to_string());
+        }
+        if let Some(workflow) = workflow {
+            values.insert("workflow".to_string(), workflow.name().to_string());
+        }
+        values.extend(self.vars.clone());
+        values
+    }
+
+    /// The opening of the prompt: `system_prompt` as it is, or the base template.
+    pub fn base(&self, system_prompt: Option<&str>, values: &BTreeMap<String, String>) -> String {
+        match system_prompt {
+            Some(system_prompt) => system_prompt.to_string(),
+            None => interpolate(self.base.as_deref().unwrap_or(BASE), values),
+        }
+    }
+
+    /// The working directory section.
+    pub fn directory(&self, values: &BTreeMap<String, String>) -> String {
+        interpolate(self.directory.as_deref().unwrap_or(DIRECTORY), values)
+    }
+
+    /// The steps of `workflow`; empty for unknown workflows without an override.
+    pub fn workflow(&self, workflow: &Workflow, values: &BTreeMap<String, String>) -> String {
+        match self.workflows.get(workflow.name()) {
+            Some(template) => interpolate(template, values),
+            None => workflow.prompt_fragment().to_string(),
+        }
+    }
+
+    /// The opening message of `workflow` with `args` filled in. Args its template doesn't
+    /// use are listed after it, so the built-in messages get them too.
+    pub fn auto_message(
+        &self,
+        workflow: &Workflow,
+        values: &BTreeMap<String, String>,
+        args: Option<&Value>,
+    ) -> Result<String, String> {
+        let template = self
+            .auto_messages
+            .get(workflow.name())
+            .map_or(workflow.auto_message(), String::as_str);
+        let mut values = values.clone();
+        values.extend(arg_values(args));
+        let missing = commit_template::missing_values(template, &values);
+        if !missing.is_empty() {
+            return Err(format!(
+                "The opening message of the {} workflow needs args {}",
+                workflow.name(),
+                missing.join(", ")
+            ));
+        }
+        let mut message = interpolate(template, &values);
+        let uses = |name: &str| {
+            template.contains(&format!("{{{{{}}}}}", name))
+                || template.contains(&format!("{{{{{}?}}}}", name))
+        };
+        match args {
+            None | Some(Value::Null) => {}
+            _ if uses("args") => {}
+            Some(Value::Object(args)) => {
+                let unused: Vec<String> = args
+                    .iter()
+                    .filter(|(name, _)| !uses(name))
+                    .map(|(name, value)| format!("\n- {}: {}", name, arg_text(value)))
+                    .collect();
+                if !unused.is_empty() {
+                    message.push_str("\n\nARGUMENTS:");
+                    message.push_str(&unused.concat());
+                }
+            }
+            Some(value) => message.push_str(&format!("\n\nARGUMENTS: {}", arg_text(value))),
+        }
+        Ok(message)
+    }
+
+    /// The priming sequence of `workflow` with `args` filled in; empty when none is
+    /// configured.
+    pub fn priming(
+        &self,
+        workflow: &Workflow,
+        values: &BTreeMap<String, String>,
+        args: Option<&Value>,
+    ) -> Vec<PrimingMessage> {
+        let mut values = values.clone();
+        values.extend(arg_values(args));
+        self.priming
+            .get(workflow.name())
+            .into_iter()
+            .flatten()
+            .map(|message| PrimingMessage {
+                role: message.role,
+                text: interpolate(&message.text, &values),
+            })
+            .collect()
+    }
+
+    /// The completion instruction, or `default` when it isn't overridden.
+    pub fn completion(&self, default: &str, values: &BTreeMap<String, String>) -> String {
+        match &self.completion {
+            Some(template) => interpolate(template, values),
+            None => default.to_string(),
+        }
+    }
+
+    /// The language sections for `language`, each with the locale's own instruction.
+    pub fn language(&self, language: &LanguageConfig, values: &BTreeMap<String, String>) -> String {
+        let sections = [
+            (
+                language
+                    .commits()
+                    .map(|locale| (locale, locale_instructions(locale).0)),
+                self.commit_language.as_deref().unwrap_or(COMMIT_LANGUAGE),
+            ),
+            (
+                language
+                    .replies()
+                    .map(|locale| (locale, locale_instructions(locale).1)),
+                self.reply_language.as_deref().unwrap_or(REPLY_LANGUAGE),
+            ),
+        ];
+        let mut fragment = String::new();
+        for (locale, template) in sections {
+            let Some((locale, instruction)) = locale else {
+                continue;
+            };
+            let mut values = values.clone();
+            values.insert("language".to_string(), locale.name().to_string());
+            values.insert("instruction".to_string(), instruction.to_string());
+            fragment.push_str("\n\n");
+            fragment.push_str(&interpolate(template, &values));
+        }
+        fragment
+    }
+}
+
+/// `template` with its placeholders filled from `values`. Placeholders without a value
+/// are left as they are, and `{{name?}}` ones are dropped.
+pub fn interpolate(template: &str, values: &BTreeMap<String, String>) -> String {
+    commit_template::render(template, values)
+}
+
+/// A `StartChat` arg as text: strings as they are, anything else as JSON.
+fn arg_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Placeholder values from the `StartChat` args: each field of an object, and all of
+/// them as `{{args}}`.
+pub fn arg_values(args: Option<&Value>) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    let Some(args) = args.filter(|args| !args.is_null()) else {
+        return values;
+    };
+    if let Value::Object(fields) = args {
+        for (name, value) in fields {
+            values.insert(name.clone(), arg_text(value));
+        }
+    }
+    values.insert("args".to_string(), arg_text(args));
+    values
+}
+
+/// The system prompt: `base`, then each non-empty section in order, separated by a
+/// blank line.
+pub fn compose(base: &str, sections: &[&str]) -> String {
+    let mut prompt = base.trim_end().to_string();
+    for section in sections {
+        let section = section.trim_start();
+        if !section.trim().is_empty() {
+            prompt.push_str("\n\n");
+            prompt.push_str(section);
+        }
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_follow_the_base_in_order() {
+        let prompt = compose("BASE\n", &["\n\nDIR", "", "\n\nTASK\n\nRULES", "\n\nDONE"]);
+        assert_eq!(prompt, "BASE\n\nDIR\n\nTASK\n\nRULES\n\nDONE");
+    }
+
+    #[test]
+    fn overrides_replace_only_their_section() {
+        let config: PromptConfig = serde_json::from_value(serde_json::json!({
+            "directory": "Repository: {{directory}} ({{team}})",
+            "workflows": {"commit": "Commit {{workflow}} changes for {{team}}."},
+            "vars": {"team": "payments"}
+        }))
+        .unwrap();
+        let values = config.values(Some("/repo"), Some(&Workflow::Commit));
+        let prompt = compose(
+            &config.base(None, &values),
+            &[
+                &config.directory(&values),
+                &config.workflow(&Workflow::Commit, &values),
+                &config.completion("\n\nSignal completion.", &values),
+            ],
+        );
+        assert!(prompt.starts_with(BASE));
+        assert!(prompt.ends_with(
+            "\n\nRepository: /repo (payments)\n\nCommit commit changes for payments.\n\n\
+            Signal completion."
+        ));
+        assert_eq!(
+            config.workflow(&Workflow::Review, &values),
+            Workflow::Review.prompt_fragment()
+        );
+    }
+
+    #[test]
+    fn auto_messages_are_filled_from_the_args() {
+        let config: PromptConfig = serde_json::from_value(serde_json::json!({
+            "auto_messages": {"review": "Review {{ticket}} in {{directory}}."}
+        }))
+        .unwrap();
+        let values = config.values(Some("/repo"), Some(&Workflow::Review));
+        let args = serde_json::json!({"ticket": "PAY-12", "focus": ["locking"]});
+        assert_eq!(
+            config
+                .auto_message(&Workflow::Review, &values, Some(&args))
+                .unwrap(),
+            "Review PAY-12 in /repo.\n\nARGUMENTS:\n- focus: [\"locking\"]"
+        );
+        assert!(config
+            .auto_message(&Workflow::Review, &values, None)
+            .unwrap_err()
+            .ends_with("needs args ticket"));
+        assert_eq!(
+            config
+                .auto_message(&Workflow::Commit, &values, Some(&"only docs".into()))
+                .unwrap(),
+            format!(
+                "{}\n\nARGUMENTS: only docs",
+                Workflow::Commit.auto_message()
+            )
+        );
+    }
+
+    #[test]
+    fn priming_sequences_are_filled_in_order() {
+        let config: PromptConfig = serde_json::from_value(serde_json::json!({
+            "priming": {"commit": [
+                {"role": "user", "text": "Commit the fix in {{directory}}."},
+                {"role": "assistant", "text": "fix({{scope}}): handle tabs"}
+            ]}
+        }))
+        .unwrap();
+        let values = config.values(Some("/repo"), Some(&Workflow::Commit));
+        let args = serde_json::json!({"scope": "parser"});
+        let priming = config.priming(&Workflow::Commit, &values, Some(&args));
+        assert_eq!(priming.len(), 2);
+        assert_eq!(priming[0].text, "Commit the fix in /repo.");
+        assert_eq!(priming[1].role, PrimingRole::Assistant);
+        assert_eq!(priming[1].text, "fix(parser): handle tabs");
+        assert!(config.priming(&Workflow::Review, &values, None).is_empty());
+    }
+
+    #[test]
+    fn system_prompt_wins_over_the_base_template() {
+        let config = PromptConfig {
+            base: Some("Base for {{workflow}}".to_string()),
+            ..PromptConfig::default()
+        };
+        let values = config.values(None, Some(&Workflow::Review));
+        assert_eq!(config.base(None, &values), "Base for review");
+        assert_eq!(
+            config.base(Some("Custom {{workflow}}"), &values),
+            "Custom {{workflow}}"
+        );
+    }
+
+    #[test]
+    fn configured_vars_win_and_unknown_placeholders_stay() {
+        let config = PromptConfig {
+            vars: BTreeMap::from([("workflow".to_string(), "release".to_string())]),
+            ..PromptConfig::default()
+        };
+        let values = config.values(None, Some(&Workflow::Commit));
+        assert_eq!(
+            interpolate("{{workflow}} {{missing}}{{optional?}}", &values),
+            "release {{missing}}"
+        );
+    }
+
+    #[test]
+    fn language_sections_use_the_locale_instructions() {
+        let config = PromptConfig {
+            reply_language: Some("Reply in {{language}}.".to_string()),
+            ..PromptConfig::default()
+        };
+        let language: LanguageConfig =
+            serde_json::from_value(serde_json::json!({"commits": "en", "replies": "de"})).unwrap();
+        let fragment = config.language(&language, &BTreeMap::new());
+        assert!(fragment.starts_with(
+            "\n\nCOMMIT MESSAGE LANGUAGE: Write commit messages in English. Write commit \
+            message subjects and bodies in English,"
+        ));
+        assert!(fragment.ends_with("\n\nReply in German."));
+    }
+}
@@ -1,8 +1,15 @@
-use genai_types::Message;
+use genai_types::messages::Role;
+use genai_types::{Message, MessageContent};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
+use crate::config_patch::GenerationConfig;
+
+#[cfg(test)]
+mod conformance;
+pub mod external;
+
 // Actor API request structures
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type")]
@@ -34,9 +41,43 @@ pub struct McpError {
 #[serde(tag = "type")]
 pub enum ChatStateRequest {
     #[serde(rename = "add_message")]
-    AddMessage { message: Message },
+    AddMessage {
+        message: Message,
+        /// Who wrote the message, kept with it in the transcript
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
+    },
     #[serde(rename = "generate_completion")]
     GenerateCompletion,
+    #[serde(rename = "cancel_generation")]
+    CancelGeneration,
+    /// The latest `limit` messages, optionally only those before message `before`
+    #[serde(rename = "get_history")]
+    GetHistory {
+        limit: Option<usize>,
+        before: Option<String>,
+    },
+    /// Replace the system prompt; the transcript is kept
+    #[serde(rename = "update_system_prompt")]
+    UpdateSystemPrompt { system_prompt: String },
+    /// Replace the MCP servers, e.g. after an actor behind one was replaced; the
+    /// transcript is kept
+    #[serde(rename = "update_mcp_servers")]
+    UpdateMcpServers { mcp_servers: Value },
+    /// Replace the generation parameters; the transcript is kept
+    #[serde(rename = "update_config")]
+    UpdateConfig { config: GenerationConfig },
+    /// Id of the latest message, the model's reply once a generation finished
+    #[serde(rename = "get_head")]
+    GetHead,
+    #[serde(rename = "get_message")]
+    GetMessage { message_id: String },
+    /// Drop message `from` and every later one; the messages before it are kept
+    #[serde(rename = "truncate_history")]
+    TruncateHistory { from: String },
+    /// Liveness probe; any answer means the actor is up
+    #[serde(rename = "ping")]
+    Ping,
 }
 
 /// Data associated with the response
@@ -46,10 +87,79 @@ pub enum ChatStateResponse {
     #[serde(rename = "success")]
     Success,
 
+    #[serde(rename = "history")]
+    History { messages: Vec<HistoryMessage> },
+
+    /// Answer to `get_head`; unset while the transcript is empty
+    #[serde(rename = "head")]
+    Head { head: Option<String> },
+
+    #[serde(rename = "chat_message")]
+    ChatMessage { message: HistoryMessage },
+
+    /// Answer to `ping`
+    #[serde(rename = "pong")]
+    Pong,
+
     #[serde(rename = "error")]
     Error { error: ErrorInfo },
 }
 
+/// A message in the chat-state actor's transcript
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryMessage {
+    /// Id to pass as `before` when paging back through the transcript
+    pub id: String,
+    pub message: Message,
+    /// Set on user-role messages the assistant forwarded with a provenance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl HistoryMessage {
+    /// Who wrote a user-role message: its provenance, or, without one, the tool when it
+    /// only holds tool results and the user otherwise. `None` for the model's replies.
+    pub fn source(&self) -> Option<Provenance> {
+        if !matches!(self.message.role, Role::User) {
+            return None;
+        }
+        let only_tool_results = !self.message.content.is_empty()
+            && self
+                .message
+                .content
+                .iter()
+                .all(|content| matches!(content, MessageContent::ToolResult { .. }));
+        Some(match self.provenance {
+            Some(provenance) => provenance,
+            None if only_tool_results => Provenance::Tool,
+            None => Provenance::User,
+        })
+    }
+}
+
+/// Who wrote a user-role message. The model only ever sees user messages, so this
+/// tells what the person actually said from what the assistant put in their place.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Provenance {
+    /// Sent by a client with `AddMessage` or `SendAndAwait`
+    User,
+    /// Written by the assistant mid-task: reprompts, wrap-ups, and reports of what it
+    /// did on request
+    Supervisor,
+    /// Results of the model's tool calls
+    Tool,
+    /// Opening message of a workflow or task the assistant started
+    Workflow,
+}
+
+impl Provenance {
+    /// Whether the assistant wrote the message rather than the person or a tool.
+    pub fn is_injected(self) -> bool {
+        matches!(self, Provenance::Supervisor | Provenance::Workflow)
+    }
+}
+
 /// Error information
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ErrorInfo {
@@ -62,3 +172,303 @@ pub struct ErrorInfo {
     /// Additional error details
     pub details: Option<HashMap<String, String>>,
 }
+
+/// Notifications children (the task monitor, the chat-state actor) send to the
+/// assistant with `send`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum ChildNotification {
+    /// The model called `task_complete`
+    TaskComplete {
+        #[serde(default)]
+        summary: Option<String>,
+    },
+    /// The model gave up on the task
+    TaskFailed { reason: String },
+    /// The model submitted review findings with the `submit_review` tool
+    ReviewSubmitted {
+        findings: Vec<crate::review::ReviewFinding>,
+        /// Chat-state actor whose model submitted them; the default session when absent
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    /// The model invoked one of its tools
+    ToolInvoked {
+        tool: String,
+        #[serde(default)]
+        arguments: Option<Value>,
+        /// Chat-state actor whose model invoked it; the default session when absent
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    /// A tool the model invoked returned
+    ToolFinished {
+        tool: String,
+        #[serde(default)]
+        is_error: bool,
+        #[serde(default)]
+        output: Option<String>,
+        /// Chat-state actor whose model invoked it; the default session when absent
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    /// A completion requested with `generate_completion` finished
+    GenerationFinished {
+        #[serde(default)]
+        stop_reason: Option<String>,
+        /// Chat-state actor that finished; the default session when absent
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    /// A completion requested with `generate_completion` failed, e.g. on a rate limit
+    GenerationFailed {
+        error: String,
+        /// Chat-state actor that failed; the default session when absent
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    /// Tokens a completion used, reported by the chat-state actor
+    UsageReported {
+        input_tokens: u64,
+        output_tokens: u64,
+        /// Model that generated; the session's current model when absent
+        #[serde(default)]
+        model: Option<String>,
+        /// Chat-state actor that generated; the default session when absent
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    /// Sent periodically by a scheduler (a timer actor or the parent) to drive the
+    /// digest and the divergence watch; `timestamp` is the current time in seconds
+    /// since the epoch
+    Tick { timestamp: u64 },
+    /// Sent by a sibling assistant when the task delegated to it with `delegation_id`
+    /// completed, with its workflow result, or couldn't be run
+    DelegationFinished {
+        delegation_id: String,
+        #[serde(default)]
+        result: Option<external::WorkflowResult>,
+        #[serde(default)]
+        error: Option<String>,
+    },
+}
+
+/// Tokens a generation used, as the chat-state actor reports them
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GenerationUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Model that generated; the session's current model when absent
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Notifications the chat-state actor sends its parent with `send` as the conversation
+/// moves along. Each carries the id of the actor that sent it; the default session's
+/// when absent.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum ChatStateNotification {
+    /// A message went into the transcript, from the parent or from the model
+    #[serde(rename = "message_added")]
+    MessageAdded {
+        message_id: String,
+        #[serde(default)]
+        role: Option<Role>,
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    #[serde(rename = "generation_started")]
+    GenerationStarted {
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    /// Text the model generated so far, sent while it streams
+    #[serde(rename = "generation_chunk")]
+    GenerationChunk {
+        text: String,
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    #[serde(rename = "generation_completed")]
+    GenerationCompleted {
+        #[serde(default)]
+        stop_reason: Option<String>,
+        #[serde(default)]
+        usage: Option<GenerationUsage>,
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    #[serde(rename = "tool_call_started")]
+    ToolCallStarted {
+        tool: String,
+        #[serde(default)]
+        arguments: Option<Value>,
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    #[serde(rename = "tool_call_finished")]
+    ToolCallFinished {
+        tool: String,
+        #[serde(default)]
+        is_error: bool,
+        #[serde(default)]
+        output: Option<String>,
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+    /// Something went wrong in the actor; a generation running then has failed
+    #[serde(rename = "error")]
+    Error {
+        error: ErrorInfo,
+        #[serde(default)]
+        chat_state_actor_id: Option<String>,
+    },
+}
+
+impl ChatStateNotification {
+    pub fn chat_state_actor_id(&self) -> Option<&str> {
+        match self {
+            ChatStateNotification::MessageAdded {
+                chat_state_actor_id,
+                ..
+            }
+            | ChatStateNotification::GenerationStarted {
+                chat_state_actor_id,
+            }
+            | ChatStateNotification::GenerationChunk {
+                chat_state_actor_id,
+                ..
+            }
+            | ChatStateNotification::GenerationCompleted {
+                chat_state_actor_id,
+                ..
+            }
+            | ChatStateNotification::ToolCallStarted {
+                chat_state_actor_id,
+                ..
+            }
+            | ChatStateNotification::ToolCallFinished {
+                chat_state_actor_id,
+                ..
+            }
+            | ChatStateNotification::Error {
+                chat_state_actor_id,
+                ..
+            } => chat_state_actor_id.as_deref(),
+        }
+    }
+
+    /// The notifications the assistant already handles that this one stands for: a
+    /// completed generation reports its usage and finishes, and tool calls are
+    /// invocations. Empty for those only the chat-state actor sends.
+    pub fn child_notifications(&self) -> Vec<ChildNotification> {
+        let chat_state_actor_id = self.chat_state_actor_id().map(str::to_string);
+        match self {
+            ChatStateNotification::GenerationCompleted {
+                stop_reason, usage, ..
+            } => {
+                let mut notifications: Vec<ChildNotification> = usage
+                    .iter()
+                    .map(|usage| ChildNotification::UsageReported {
+                        input_tokens: usage.input_tokens,
+                        output_tokens: usage.output_tokens,
+                        model: usage.model.clone(),
+                        chat_state_actor_id: chat_state_actor_id.clone(),
+                    })
+                    .collect();
+                notifications.push(ChildNotification::GenerationFinished {
+                    stop_reason: stop_reason.clone(),
+                    chat_state_actor_id,
+                });
+                notifications
+            }
+            ChatStateNotification::ToolCallStarted {
+                tool, arguments, ..
+            } => vec![ChildNotification::ToolInvoked {
+                tool: tool.clone(),
+                arguments: arguments.clone(),
+                chat_state_actor_id,
+            }],
+            ChatStateNotification::ToolCallFinished {
+                tool,
+                is_error,
+                output,
+                ..
+            } => vec![ChildNotification::ToolFinished {
+                tool: tool.clone(),
+                is_error: *is_error,
+                output: output.clone(),
+                chat_state_actor_id,
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// What the assistant reads from the exit state a chat-state actor passes to
+/// `shutdown`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChatStateExit {
+    /// Summary of the final transcript
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Why the actor stopped, when it stopped on a failure
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ChatStateExit {
+    /// Parse an exit state; one that isn't readable counts as a failure.
+    pub fn parse(exit_state: Option<&[u8]>) -> Self {
+        match exit_state.filter(|bytes| !bytes.is_empty()) {
+            None => Self::default(),
+            Some(bytes) => serde_json::from_slice(bytes).unwrap_or_else(|e| Self {
+                summary: None,
+                error: Some(format!("Unreadable exit state: {}", e)),
+            }),
+        }
+    }
+
+    pub fn is_abnormal(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn completed_generations_report_usage_then_finish() {
+        let notification: ChatStateNotification = serde_json::from_value(json!({
+            "type": "generation_completed",
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 1200, "output_tokens": 300},
+            "chat_state_actor_id": "chat-2"
+        }))
+        .unwrap();
+        let notifications = notification.child_notifications();
+        assert!(matches!(
+            &notifications[..],
+            [
+                ChildNotification::UsageReported {
+                    input_tokens: 1200,
+                    output_tokens: 300,
+                    model: None,
+                    chat_state_actor_id: Some(usage_actor),
+                },
+                ChildNotification::GenerationFinished {
+                    stop_reason: Some(stop_reason),
+                    chat_state_actor_id: Some(finished_actor),
+                },
+            ] if usage_actor == "chat-2" && finished_actor == "chat-2" && stop_reason == "end_turn"
+        ));
+
+        let chunk: ChatStateNotification =
+            serde_json::from_value(json!({"type": "generation_chunk", "text": "Staging"})).unwrap();
+        assert_eq!(chunk.chat_state_actor_id(), None);
+        assert!(chunk.child_notifications().is_empty());
+    }
+}
@@ -0,0 +1,339 @@
+//! Golden fixtures of the wire protocol, kept in `conformance/` at the crate root so
+//! clients in other languages can check their encoding against the same bytes.
+//!
+//! `exchanges/<name>.jsonl` holds a request on its first line and a response to it on
+//! the second. `notifications.jsonl`, `events.jsonl`, `client_frames.jsonl`, and
+//! `server_frames.jsonl` hold one message per line, and `rejected_requests.jsonl` holds
+//! requests that must fail to decode. Every other line is exactly what the assistant
+//! encodes for that message.
+
+use super::external::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const REQUESTS: &[&str] = &[
+    "GetChatStateActorId",
+    "AddMessage",
+    "AddMessages",
+    "SendAndAwait",
+    "AcquireTurn",
+    "ReleaseTurn",
+    "SuggestCommitMessage",
+    "SuggestBranchName",
+    "SuggestPrDescription",
+    "StartChat",
+    "SetWorkflow",
+    "CreateSession",
+    "ListSessions",
+    "CloseSession",
+    "SubmitSecurityFindings",
+    "GetStatus",
+    "GetWorkflowProgress",
+    "VerifyHistory",
+    "CheckSpelling",
+    "CreateBranch",
+    "CancelGeneration",
+    "RunDigest",
+    "CompareRepos",
+    "GetHistory",
+    "ExportTranscript",
+    "SaveSession",
+    "RestoreSession",
+    "GetScratchpad",
+    "GetConfigDiagnostics",
+    "GetDivergence",
+    "ListWorktrees",
+    "SetActiveWorktree",
+    "GetSparseCheckout",
+    "SetSparsePaths",
+    "GetConflicts",
+    "ResolveConflict",
+    "AnalyzeRewrite",
+    "ReviewRefs",
+    "GetReviewReport",
+    "PublishReview",
+    "PlanRebase",
+    "ExecuteRebasePlan",
+    "GetRebase",
+    "ExecuteBranchCleanup",
+    "GetBranchCleanup",
+    "ExecuteRelease",
+    "GetRelease",
+    "ApproveCommitPlan",
+    "GetCommitPlan",
+    "ScanForSecrets",
+    "GetPendingConfirmations",
+    "Confirm",
+    "RejectConfirmation",
+    "KeepAlive",
+    "StartBisect",
+    "SubmitBisectVerdict",
+    "AbortBisect",
+    "GetBisect",
+    "SkipCherryPick",
+    "AbortCherryPick",
+    "GetCherryPick",
+    "ExecuteSync",
+    "AbortSync",
+    "GetSync",
+    "UndoLastTurn",
+    "RegisterSibling",
+    "ListSiblings",
+    "DelegateTask",
+    "AcceptDelegation",
+    "GetDelegations",
+    "StartFanOut",
+    "GetFanOut",
+    "Subscribe",
+    "Unsubscribe",
+    "DebugDump",
+    "Describe",
+    "Ping",
+    "GetUsage",
+    "GetMetrics",
+    "RetryToolAttach",
+    "GetAuditLog",
+    "CompactState",
+    "FlushQueue",
+    "SetTemplateVars",
+    "UpdateConfig",
+    "SetModelPreset",
+    "CleanArtifacts",
+];
+
+const RESPONSES: &[&str] = &[
+    "ChatStateActorId",
+    "HistoryVerification",
+    "Status",
+    "WorkflowProgress",
+    "SessionCreated",
+    "Sessions",
+    "SecurityFindings",
+    "Spelling",
+    "BranchCreated",
+    "InvalidBranchName",
+    "Digest",
+    "History",
+    "Transcript",
+    "SessionSnapshot",
+    "Scratchpad",
+    "ConfigDiagnostics",
+    "Divergence",
+    "Worktrees",
+    "ActiveWorktreeSet",
+    "TurnAcquired",
+    "SparseCheckout",
+    "Conflicts",
+    "RewriteAnalysis",
+    "ReviewStarted",
+    "ReviewReport",
+    "ReviewFindings",
+    "ReviewPublished",
+    "RebasePlanning",
+    "RebaseExecuting",
+    "Rebase",
+    "BranchCleanup",
+    "Release",
+    "CommitPlan",
+    "Siblings",
+    "DelegationStarted",
+    "Delegations",
+    "FanOut",
+    "SecretScan",
+    "ConfirmationRequired",
+    "PendingConfirmations",
+    "Resumed",
+    "Bisect",
+    "CherryPick",
+    "Sync",
+    "TurnUndone",
+    "DebugDump",
+    "Description",
+    "Completion",
+    "CommitMessageSuggested",
+    "BranchNamesSuggested",
+    "PrDescriptionSuggested",
+    "Pong",
+    "Usage",
+    "Metrics",
+    "ToolsAttached",
+    "AuditLog",
+    "StateCompacted",
+    "QueueFlushed",
+    "TemplateVarsSet",
+    "ConfigUpdated",
+    "ArtifactsCleaned",
+    "QuickCommand",
+    "Success",
+    "Error",
+];
+
+const NOTIFICATIONS: &[&str] = &[
+    "WorkflowCompleted",
+    "Ready",
+    "FatalError",
+    "Digest",
+    "DivergenceExceeded",
+    "Halted",
+    "RebasePlanProposed",
+    "BranchCleanupProposed",
+    "ReleaseProposed",
+    "CommitPlanProposed",
+    "DelegationFinished",
+    "FanOutFinished",
+    "RebaseFinished",
+    "BisectFinished",
+    "CherryPickFinished",
+    "WorkflowStalled",
+    "PipelineCompleted",
+    "WorkflowResumed",
+    "FilePolicyViolated",
+];
+
+const EVENTS: &[&str] = &[
+    "MessageAdded",
+    "GenerationStarted",
+    "GenerationFinished",
+    "WorkflowStateChanged",
+    "ChildCrashed",
+    "ModelDowngraded",
+    "TemplateVarsRequested",
+    "SessionClosed",
+    "SessionFailed",
+    "CommitPlanProposed",
+    "GenerationChunk",
+    "AssistantOutput",
+    "ToolActivity",
+    "ApprovalRequested",
+    "WorkflowProgress",
+    "CommandsSuggested",
+    "ToolsAvailabilityChanged",
+];
+
+const CLIENT_FRAMES: &[&str] = &[
+    "Open",
+    "Resume",
+    "Ack",
+    "Request",
+    "Input",
+    "Approval",
+    "Subscribe",
+];
+
+const SERVER_FRAMES: &[&str] = &["Welcome", "Event", "Response", "Error"];
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("conformance")
+}
+
+fn read_lines(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+fn type_of(line: &str) -> String {
+    let value: Value = serde_json::from_str(line).unwrap();
+    value["type"].as_str().unwrap_or_default().to_string()
+}
+
+fn assert_covers(seen: BTreeSet<String>, variants: &[&str]) {
+    let variants: BTreeSet<String> = variants.iter().map(|name| name.to_string()).collect();
+    assert_eq!(seen, variants);
+}
+
+/// Parse each line of `file` and check that encoding it again gives the same bytes.
+fn check_messages<T: Serialize + DeserializeOwned>(file: &str, variants: &[&str]) {
+    let mut seen = BTreeSet::new();
+    for line in read_lines(&fixtures().join(file)) {
+        let envelope: Envelope<T> =
+            serde_json::from_str(&line).unwrap_or_else(|e| panic!("{}: {}", line, e));
+        assert_eq!(serde_json::to_string(&envelope).unwrap(), line);
+        seen.insert(type_of(&line));
+    }
+    assert_covers(seen, variants);
+}
+
+#[test]
+fn exchanges_decode_and_encode_byte_for_byte() {
+    let mut paths: Vec<PathBuf> = fs::read_dir(fixtures().join("exchanges"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+
+    let mut requests = BTreeSet::new();
+    let mut responses = BTreeSet::new();
+    for path in paths {
+        let lines = read_lines(&path);
+        assert_eq!(lines.len(), 2, "{}", path.display());
+
+        let request = decode_request(lines[0].as_bytes())
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert_eq!(
+            serde_json::to_string(&Envelope::new(&request)).unwrap(),
+            lines[0]
+        );
+
+        let expected: Envelope<GitChatResponse> =
+            serde_json::from_str(&lines[1]).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert!(expected.meta.is_some(), "{}", path.display());
+        let mut encoded: Envelope<GitChatResponse> =
+            serde_json::from_slice(&encode_response(&expected.body).unwrap()).unwrap();
+        // The fixtures carry a fixed build rather than this one
+        encoded.meta = expected.meta.clone();
+        assert_eq!(serde_json::to_string(&encoded).unwrap(), lines[1]);
+
+        requests.insert(type_of(&lines[0]));
+        responses.insert(type_of(&lines[1]));
+    }
+    assert_covers(requests, REQUESTS);
+    assert_covers(responses, RESPONSES);
+}
+
+#[test]
+fn notifications_encode_byte_for_byte() {
+    let mut seen = BTreeSet::new();
+    for line in read_lines(&fixtures().join("notifications.jsonl")) {
+        let envelope: Envelope<GitChatNotification> =
+            serde_json::from_str(&line).unwrap_or_else(|e| panic!("{}: {}", line, e));
+        let at = envelope.at.expect("notifications carry `at`");
+        let encoded = encode_notification(&envelope.body, at).unwrap();
+        assert_eq!(String::from_utf8(encoded).unwrap(), line);
+        seen.insert(type_of(&line));
+    }
+    assert_covers(seen, NOTIFICATIONS);
+}
+
+#[test]
+fn events_encode_byte_for_byte() {
+    let mut seen = BTreeSet::new();
+    for line in read_lines(&fixtures().join("events.jsonl")) {
+        let envelope: Envelope<GitChatEvent> =
+            serde_json::from_str(&line).unwrap_or_else(|e| panic!("{}: {}", line, e));
+        let at = envelope.at.expect("events carry `at`");
+        let encoded = encode_event(&envelope.body, at).unwrap();
+        assert_eq!(String::from_utf8(encoded).unwrap(), line);
+        seen.insert(type_of(&line));
+    }
+    assert_covers(seen, EVENTS);
+}
+
+#[test]
+fn channel_frames_encode_byte_for_byte() {
+    check_messages::<ClientFrame>("client_frames.jsonl", CLIENT_FRAMES);
+    check_messages::<ServerFrame>("server_frames.jsonl", SERVER_FRAMES);
+}
+
+#[test]
+fn rejected_requests_fail_to_decode() {
+    for line in read_lines(&fixtures().join("rejected_requests.jsonl")) {
+        assert!(decode_request(line.as_bytes()).is_err(), "{}", line);
+    }
+}
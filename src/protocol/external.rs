@@ -0,0 +1,2351 @@
+//! Wire protocol spoken by clients of the git chat assistant.
+//!
+//! Every message is a JSON object tagged with its variant in `"type"` and the
+//! protocol version in `"v"`. Requests without `"v"` are treated as version 1.
+
+use genai_types::Message;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub use crate::advice_only::ToolOutage;
+pub use crate::artifacts::ArtifactKind;
+pub use crate::audit::{Approval, AuditEntry, AuditOutcome, AuditSource};
+pub use crate::bisect::{BisectPhase, BisectState, BisectStep, BisectVerdict};
+pub use crate::branch::BranchNameError;
+pub use crate::branch_cleanup::{BranchCandidate, BranchCleanup, CleanupReason, DeletionFailure};
+pub use crate::build_info::BuildInfo;
+pub use crate::channel::{ClientFrame, ServerFrame};
+pub use crate::cherry_pick::{CherryPickPhase, CherryPickState, PickStatus, PickedCommit};
+pub use crate::commit_plan::{CommitPlan, PlannedCommit};
+pub use crate::compare::CompareAspect;
+pub use crate::config_check::{ConfigDiagnostic, Severity};
+pub use crate::config_patch::GenerationConfig;
+pub use crate::confirmation::{PendingConfirmation, Severity as ActionSeverity};
+pub use crate::conflicts::{ConflictHunk, ConflictedFile, Resolution};
+pub use crate::crash_loop::{Crash, SessionFailure};
+pub use crate::dead_man_switch::HaltSnapshot;
+pub use crate::debug_dump::DebugSection;
+pub use crate::delegation::{DelegatedTask, Delegation, DelegationStatus, Sibling};
+pub use crate::digest::{Activity, DigestCommit, RepoDigest};
+pub use crate::divergence::{Divergence, DivergingCommit};
+pub use crate::errors::ErrorCode;
+pub use crate::fan_out::{FanOutResult, RepoRun};
+pub use crate::generation_overrides::GenerationOverrides;
+pub use crate::large_files::{FileAction, FileViolation};
+pub use crate::metrics::{Metrics, MetricsFormat, MetricsSnapshot, Timing};
+pub use crate::postcondition::{Postcondition, PostconditionFailure};
+pub use crate::preflight::{CheckStatus, PreflightCheck, PreflightReport};
+pub use crate::progress::{ProgressPhase, WorkflowProgress};
+pub use crate::protocol::{HistoryMessage, Provenance};
+pub use crate::quick_command::QuickCommandResult;
+pub use crate::rebase_plan::{
+    RangeCommit, RebaseAction, RebasePlan, RebaseState, RebaseStatus, RebaseStep,
+};
+pub use crate::release::{Bump, Release, ReleaseCommit};
+pub use crate::resume::{GitOperation, ResumeAction, ResumeReport, WorkflowPhase};
+pub use crate::review::{
+    BenchmarkFinding, BenchmarkResult, FindingSeverity, RejectedFinding, ReviewFinding,
+    SecurityFinding,
+};
+pub use crate::review_report::{
+    PublishMode, PublishResult, ReviewComment, ReviewReport, ReviewRound,
+};
+pub use crate::rewrite_safety::{PublishedCommit, RewriteAnalysis};
+pub use crate::schedule::ScheduledRun;
+pub use crate::scratchpad::Scratchpad;
+pub use crate::secret_scan::SecretFinding;
+pub use crate::session_store::SnapshotInfo;
+pub use crate::signatures::{CommitSignature, SignatureReport, SignatureStatus};
+pub use crate::sparse::SparseCheckout;
+pub use crate::spelling::SpellingIssue;
+pub use crate::state_limits::CompactionReport;
+pub use crate::suggest_only::{RejectedSuggestion, SuggestedCommand};
+pub use crate::sync::{SyncPhase, SyncState, SyncStrategy};
+pub use crate::transcript::TranscriptFormat;
+pub use crate::turn::TurnClaim;
+pub use crate::turn_limit::WorkflowStall;
+pub use crate::undo::{Compensation, TurnUndo};
+pub use crate::usage::{Usage, UsageReport};
+pub use crate::verification::{CheckOutcome, CompletionStatus, Verification, VerificationCheck};
+pub use crate::workflow::Workflow;
+pub use crate::worktree::Worktree;
+
+/// Current version of the external protocol.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+fn default_version() -> u32 {
+    PROTOCOL_VERSION
+}
+
+/// Versioned wrapper carried by every request and response on the wire.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Envelope<T> {
+    #[serde(default = "default_version")]
+    pub v: u32,
+    /// Set on responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ResponseMeta>,
+    /// Set on events and notifications: when they were sent, in seconds since the epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<u64>,
+    #[serde(flatten)]
+    pub body: T,
+}
+
+impl<T> Envelope<T> {
+    pub fn new(body: T) -> Self {
+        Self {
+            v: PROTOCOL_VERSION,
+            meta: None,
+            at: None,
+            body,
+        }
+    }
+}
+
+/// What every response says about the assistant that sent it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResponseMeta {
+    pub build: BuildInfo,
+}
+
+/// Requests accepted by the git chat assistant.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum GitChatRequest {
+    GetChatStateActorId,
+    AddMessage {
+        message: Message,
+        session_id: Option<String>,
+        /// Client sending the message; needed while the session is claimed with
+        /// `AcquireTurn`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client: Option<String>,
+        /// Generation parameters for the generation this message starts only
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        overrides: Option<GenerationOverrides>,
+    },
+    /// Forward several messages in order with one generation for them all, e.g. to
+    /// replay a prior conversation or inject context in parts
+    AddMessages {
+        messages: Vec<Message>,
+        #[serde(default)]
+        session_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client: Option<String>,
+    },
+    /// Like `AddMessage`, but answered with the model's reply, as `Completion`, once the
+    /// generation finishes. Only over the message server's `request`
+    SendAndAwait {
+        message: Message,
+        #[serde(default)]
+        session_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client: Option<String>,
+    },
+    /// Claim a session (the default session when omitted) for `client`, so that messages
+    /// from other clients fail until `ReleaseTurn` or the lease of `lease_secs` (300 when
+    /// omitted) runs out. Acquiring again renews the lease
+    AcquireTurn {
+        #[serde(default)]
+        session_id: Option<String>,
+        client: String,
+        #[serde(default)]
+        lease_secs: Option<u64>,
+    },
+    /// Give up `client`'s claim on a session (the default session when omitted)
+    ReleaseTurn {
+        #[serde(default)]
+        session_id: Option<String>,
+        client: String,
+    },
+    /// Write a commit message for the staged changes, or for every change against HEAD
+    /// unless `staged_only` is set, in one model turn outside any session. Answered with
+    /// `CommitMessageSuggested`. Only over the message server's `request`
+    SuggestCommitMessage {
+        #[serde(default)]
+        staged_only: bool,
+    },
+    /// Name a branch for the work in `description` in one model turn outside any
+    /// session. Answered with `BranchNamesSuggested`. Only over the message server's
+    /// `request`
+    SuggestBranchName {
+        description: String,
+    },
+    /// Describe the current branch's pull request into `base`, or
+    /// `pr_description.base_branch`, in one model turn outside any session. Answered
+    /// with `PrDescriptionSuggested`. Only over the message server's `request`
+    SuggestPrDescription {
+        #[serde(default)]
+        base: Option<String>,
+    },
+    /// Start `workflow`, switching to it as `SetWorkflow` does, or the configured workflow.
+    /// The commit workflow doesn't start while the pending changes look like they contain
+    /// credentials, unless `allow_secrets` is set, and fails when its commits touch files
+    /// outside `scope_paths`, unless `allow_out_of_scope` is set. `args` fill the
+    /// placeholders of the opening message and are kept for the later steps of a
+    /// pipeline, e.g. the file and lines for `blame-explain`
+    StartChat {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        workflow: Option<Workflow>,
+        #[serde(default, alias = "params", skip_serializing_if = "Option::is_none")]
+        args: Option<Value>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_secrets: bool,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_out_of_scope: bool,
+        /// Take the git tools away and have the model suggest commands instead, as
+        /// `suggest_only` does
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        suggest_only: bool,
+    },
+    /// Switch the default session to `workflow`, updating its system prompt, and start
+    /// the workflow right away when `start` is set
+    SetWorkflow {
+        workflow: Workflow,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        start: bool,
+    },
+    CreateSession {
+        session_id: Option<String>,
+        current_directory: Option<String>,
+        workflow: Option<Workflow>,
+    },
+    ListSessions,
+    CloseSession {
+        session_id: String,
+    },
+    SubmitSecurityFindings {
+        findings: Vec<SecurityFinding>,
+    },
+    GetStatus,
+    /// Phase, files, turns, time, and blocking approvals of the auto-initiated workflow
+    GetWorkflowProgress,
+    VerifyHistory {
+        range: String,
+    },
+    /// Spellcheck a commit message or PR description; corrections are only suggested
+    CheckSpelling {
+        text: String,
+    },
+    /// Create a branch, normalizing the name when it breaks git's or the configured rules
+    CreateBranch {
+        name: String,
+        start_point: Option<String>,
+    },
+    /// Abort the generation running in a session (the default session when omitted)
+    CancelGeneration {
+        session_id: Option<String>,
+    },
+    /// Summarize repository activity since `since` (seconds since the epoch), or the
+    /// latest commits when omitted; scheduled digests are unaffected
+    RunDigest {
+        since: Option<u64>,
+    },
+    /// Ask the model to compare the current checkout with another one; the comparison
+    /// arrives in the conversation
+    CompareRepos {
+        other_directory: String,
+        aspect: CompareAspect,
+    },
+    /// Read a session's transcript (the default session when omitted), newest `limit`
+    /// messages before message `before`
+    GetHistory {
+        #[serde(default)]
+        limit: Option<usize>,
+        #[serde(default)]
+        before: Option<String>,
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    /// A session's whole transcript (the default session when omitted) as a document
+    /// in `format`
+    ExportTranscript {
+        format: TranscriptFormat,
+        #[serde(default)]
+        session_id: Option<String>,
+        /// Leave out the messages the assistant wrote in the user's place
+        #[serde(default)]
+        exclude_injected: bool,
+    },
+    /// Save the default session (config, transcript, workflow, and audit log) to the
+    /// runtime's store, labelled `label`
+    SaveSession {
+        label: String,
+    },
+    /// Replace the default session with the snapshot `id`, a label or a hash
+    /// `SaveSession` returned
+    RestoreSession {
+        id: String,
+    },
+    /// The model's notes to itself in a session (the default session when omitted),
+    /// kept when `scratchpad` is set
+    GetScratchpad {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    /// Warnings recorded while validating the initial config
+    GetConfigDiagnostics,
+    /// Ahead/behind counts against `upstream` (the tracking branch when omitted)
+    GetDivergence {
+        #[serde(default)]
+        upstream: Option<String>,
+    },
+    /// Worktrees of the repository and the paths in `worktrees`
+    ListWorktrees,
+    /// Run the default session's git operations in the worktree at `path`, one listed by
+    /// `ListWorktrees`
+    SetActiveWorktree {
+        path: String,
+    },
+    /// Whether the checkout is sparse, and which paths it holds
+    GetSparseCheckout,
+    /// Check out only the directories `paths`, or `scope_paths` when empty, with
+    /// `git sparse-checkout set --cone`
+    SetSparsePaths {
+        #[serde(default)]
+        paths: Vec<String>,
+    },
+    /// The conflicted files of a merge, rebase, or cherry-pick, and their hunks
+    GetConflicts,
+    /// Resolve hunk `hunk`, as numbered by `GetConflicts`, of the conflicted `file`. The
+    /// file is staged once no conflicts are left in it
+    ResolveConflict {
+        file: String,
+        hunk: usize,
+        resolution: Resolution,
+    },
+    /// Which commits in `range` are already on a remote-tracking branch
+    AnalyzeRewrite {
+        range: String,
+    },
+    /// Review `branch` (the current branch when omitted) against `base` (`main` on the
+    /// first review). Later reviews of the same branch only cover new commits and the
+    /// findings are merged into the same report
+    ReviewRefs {
+        #[serde(default)]
+        branch: Option<String>,
+        #[serde(default)]
+        base: Option<String>,
+    },
+    /// The report of `branch`, or the findings of the current or last review workflow
+    /// when omitted
+    GetReviewReport {
+        #[serde(default)]
+        branch: Option<String>,
+    },
+    /// Post a review report's findings to the branch's pull request through the
+    /// `forge_mcp` tools; findings posted before are skipped
+    PublishReview {
+        report_id: String,
+        mode: PublishMode,
+    },
+    /// Ask the model for a rebase plan of `range` (`<onto>..HEAD` when omitted) onto
+    /// `onto`. Nothing is rewritten; the plan arrives as `RebasePlanProposed`
+    PlanRebase {
+        onto: String,
+        #[serde(default)]
+        range: Option<String>,
+    },
+    /// Have the model carry out `plan`, the proposed one or an edited copy of it
+    ExecuteRebasePlan {
+        plan: RebasePlan,
+    },
+    GetRebase,
+    /// Delete `branches` from the `branch-cleanup` proposal, or every proposed branch
+    /// when empty. Always waits for `Confirm`
+    ExecuteBranchCleanup {
+        #[serde(default)]
+        branches: Vec<String>,
+    },
+    GetBranchCleanup,
+    /// Create the annotated tag the `tag-and-release` workflow proposed, with `message`
+    /// or else the drafted notes. Always waits for `Confirm`
+    ExecuteRelease {
+        #[serde(default)]
+        message: Option<String>,
+    },
+    GetRelease,
+    /// Approve the plan the commit workflow proposed with `commit_split`, or `plan`, an
+    /// edited copy of it, and have the model make its commits
+    ApproveCommitPlan {
+        #[serde(default)]
+        plan: Option<CommitPlan>,
+    },
+    GetCommitPlan,
+    /// Scan `diff` (the uncommitted changes when omitted) for credentials
+    ScanForSecrets {
+        #[serde(default)]
+        diff: Option<String>,
+    },
+    /// Requests held back by the `confirmation_policy`
+    GetPendingConfirmations,
+    /// Run a held-back request; the response is that request's response
+    Confirm {
+        id: String,
+    },
+    RejectConfirmation {
+        id: String,
+    },
+    /// Ping from the orchestrator of an autonomous run; see `dead_man_switch`
+    KeepAlive,
+    /// Start `git bisect` between `good` and `bad` (`HEAD` when omitted) and have the
+    /// model look at the first commit to test
+    StartBisect {
+        good: String,
+        #[serde(default)]
+        bad: Option<String>,
+    },
+    /// Mark the checked-out commit and move on to the next one
+    SubmitBisectVerdict {
+        verdict: BisectVerdict,
+    },
+    /// Stop bisecting and go back to the commit the bisect started from
+    AbortBisect,
+    GetBisect,
+    /// Skip the cherry-pick's commit that stopped on conflicts and pick the rest
+    SkipCherryPick,
+    /// Stop the cherry-pick, undoing a pick stopped on conflicts; picked commits stay
+    AbortCherryPick,
+    GetCherryPick,
+    /// Catch up with the upstream of the `sync` workflow by `strategy`, or the
+    /// recommended one. Always waits for `Confirm`
+    ExecuteSync {
+        #[serde(default)]
+        strategy: Option<SyncStrategy>,
+    },
+    /// Stop the sync, undoing a merge or rebase stopped on conflicts
+    AbortSync,
+    GetSync,
+    /// Drop the user's last turn from the default session's transcript: their message
+    /// and every later one. Files the turn staged are unstaged and, in the commit
+    /// workflow, commits it made are soft reset, once `Confirm`ed
+    UndoLastTurn,
+    /// Add `sibling` to the assistants tasks can be delegated to, replacing the one with
+    /// the same actor id
+    RegisterSibling {
+        sibling: Sibling,
+    },
+    ListSiblings,
+    /// Have the registered sibling `actor_id` run `task`. Its result is told to the
+    /// default session's model and sent as `DelegationFinished`
+    DelegateTask {
+        actor_id: String,
+        task: DelegatedTask,
+    },
+    /// Sent by the assistant `from` to a sibling it delegates `task` to; the sibling
+    /// starts the task's workflow and reports back when it completes
+    AcceptDelegation {
+        delegation_id: String,
+        from: String,
+        task: DelegatedTask,
+    },
+    GetDelegations,
+    /// Spawn a child assistant in each of `repositories` and have each run `task`, in
+    /// parallel. Needs `fan_out` in the config; the combined result is sent as
+    /// `FanOutFinished` once every repository's run finished
+    StartFanOut {
+        repositories: Vec<String>,
+        task: DelegatedTask,
+    },
+    GetFanOut,
+    /// Send `actor_id` the `events` kinds from now on, or every kind when empty.
+    /// Subscribing again replaces the kinds
+    Subscribe {
+        actor_id: String,
+        #[serde(default)]
+        events: Vec<EventKind>,
+    },
+    Unsubscribe {
+        actor_id: String,
+    },
+    /// Sanitized dump of the `include` sections of the assistant's state, or all of
+    /// them when empty; needs `enable_debug`
+    DebugDump {
+        #[serde(default)]
+        include: Vec<DebugSection>,
+    },
+    /// The assistant's build, protocol version, and known workflows
+    Describe,
+    /// Health check; probes the default session's chat-state actor
+    Ping,
+    /// Tokens used and their estimated cost, per session and overall
+    GetUsage,
+    /// Request, error, tool call, child restart, and workflow duration counters since
+    /// the assistant was spawned, in `format`
+    GetMetrics {
+        #[serde(default)]
+        format: MetricsFormat,
+    },
+    /// Try again to spawn the git tools that couldn't be attached at init, and give them
+    /// to every session once they spawn
+    RetryToolAttach,
+    /// The newest `limit` audit log entries, or all of them
+    GetAuditLog {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Shrink the saved state now, as `state_limits` would past its `max_bytes`
+    CompactState,
+    /// Retry the requests to chat-state actors that couldn't be sent
+    FlushQueue,
+    /// Values for the commit template's placeholders, merged over those sent before
+    /// and over `template_vars`
+    SetTemplateVars {
+        vars: BTreeMap<String, String>,
+    },
+    /// JSON merge patch of `temperature`, `max_tokens`, and `model_config`, applied to
+    /// every session's chat-state actor and to those spawned later
+    UpdateConfig {
+        patch: Value,
+    },
+    /// Switch every session to a preset of `model_presets`, like `UpdateConfig`
+    SetModelPreset {
+        preset: String,
+    },
+    /// Remove session artifacts of `kinds` (every kind when empty) from the git
+    /// directory, keeping the newest `keep` of them
+    CleanArtifacts {
+        #[serde(default)]
+        kinds: Vec<ArtifactKind>,
+        #[serde(default)]
+        keep: usize,
+    },
+}
+
+impl GitChatRequest {
+    /// Session the request is about, when it names one.
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            GitChatRequest::AddMessage { session_id, .. }
+            | GitChatRequest::AddMessages { session_id, .. }
+            | GitChatRequest::SendAndAwait { session_id, .. }
+            | GitChatRequest::CreateSession { session_id, .. }
+            | GitChatRequest::CancelGeneration { session_id }
+            | GitChatRequest::GetHistory { session_id, .. }
+            | GitChatRequest::GetScratchpad { session_id }
+            | GitChatRequest::AcquireTurn { session_id, .. }
+            | GitChatRequest::ReleaseTurn { session_id, .. } => session_id.as_deref(),
+            GitChatRequest::CloseSession { session_id } => Some(session_id),
+            _ => None,
+        }
+    }
+
+    /// Whether the request is answered with `respond_to_request` once its generation
+    /// finishes, which only a request through the message server can be.
+    pub fn defers_response(&self) -> bool {
+        matches!(
+            self,
+            GitChatRequest::SendAndAwait { .. }
+                | GitChatRequest::SuggestCommitMessage { .. }
+                | GitChatRequest::SuggestBranchName { .. }
+                | GitChatRequest::SuggestPrDescription { .. }
+        )
+    }
+}
+
+/// Responses returned by the git chat assistant.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum GitChatResponse {
+    ChatStateActorId {
+        actor_id: String,
+    },
+    HistoryVerification {
+        report: SignatureReport,
+    },
+    Status {
+        status: SessionStatus,
+    },
+    /// Answer to `GetWorkflowProgress`; unset when no workflow is running
+    WorkflowProgress {
+        progress: Option<WorkflowProgress>,
+    },
+    SessionCreated {
+        session: SessionInfo,
+    },
+    Sessions {
+        sessions: Vec<SessionInfo>,
+    },
+    SecurityFindings {
+        accepted: Vec<SecurityFinding>,
+        rejected: Vec<RejectedFinding>,
+    },
+    Spelling {
+        issues: Vec<SpellingIssue>,
+    },
+    BranchCreated {
+        name: String,
+        /// The requested name, when it had to be normalized
+        normalized_from: Option<String>,
+    },
+    InvalidBranchName {
+        error: BranchNameError,
+    },
+    Digest {
+        digest: RepoDigest,
+    },
+    History {
+        messages: Vec<HistoryMessage>,
+    },
+    /// Answer to `ExportTranscript`
+    Transcript {
+        format: TranscriptFormat,
+        /// Media type of `document`, e.g. `text/markdown`
+        media_type: String,
+        document: String,
+    },
+    /// Answer to `SaveSession` and `RestoreSession`
+    SessionSnapshot {
+        snapshot: SnapshotInfo,
+    },
+    /// Answer to `GetScratchpad`; unset until the model writes one
+    Scratchpad {
+        session_id: String,
+        scratchpad: Option<Scratchpad>,
+    },
+    /// Answer to `GetConfigDiagnostics`, with the preflight report of init
+    ConfigDiagnostics {
+        diagnostics: Vec<ConfigDiagnostic>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        preflight: Option<PreflightReport>,
+    },
+    Divergence {
+        divergence: Divergence,
+    },
+    Worktrees {
+        worktrees: Vec<Worktree>,
+    },
+    /// Answer to `SetActiveWorktree`
+    ActiveWorktreeSet {
+        worktree: Worktree,
+    },
+    /// Answer to `AcquireTurn`
+    TurnAcquired {
+        turn: TurnClaim,
+    },
+    /// Answer to `GetConflicts` and `ResolveConflict`: the conflicts left
+    Conflicts {
+        files: Vec<ConflictedFile>,
+    },
+    /// Answer to `GetSparseCheckout` and `SetSparsePaths`
+    SparseCheckout {
+        sparse: SparseCheckout,
+    },
+    RewriteAnalysis {
+        analysis: RewriteAnalysis,
+    },
+    /// The review message was sent; findings arrive through `SubmitSecurityFindings`
+    ReviewStarted {
+        report: ReviewReport,
+    },
+    ReviewReport {
+        report: ReviewReport,
+    },
+    /// Findings submitted during the current or last review workflow
+    ReviewFindings {
+        findings: Vec<ReviewFinding>,
+        /// Measured findings of a performance review with `review.benchmark`
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        benchmark_findings: Vec<BenchmarkFinding>,
+    },
+    ReviewPublished {
+        result: PublishResult,
+    },
+    /// The planning message was sent; the plan arrives as `RebasePlanProposed`
+    RebasePlanning {
+        rebase: RebaseState,
+    },
+    RebaseExecuting {
+        rebase: RebaseState,
+    },
+    Rebase {
+        rebase: Option<RebaseState>,
+    },
+    BranchCleanup {
+        cleanup: Option<BranchCleanup>,
+    },
+    Release {
+        release: Option<Release>,
+    },
+    CommitPlan {
+        plan: Option<CommitPlan>,
+    },
+    Siblings {
+        siblings: Vec<Sibling>,
+    },
+    /// The sibling started the task; the result arrives as `DelegationFinished`
+    DelegationStarted {
+        delegation: Delegation,
+    },
+    Delegations {
+        delegations: Vec<Delegation>,
+    },
+    FanOut {
+        fan_out: Option<FanOutResult>,
+    },
+    SecretScan {
+        findings: Vec<SecretFinding>,
+    },
+    /// The request was queued; send `Confirm` or `RejectConfirmation` with its id
+    ConfirmationRequired {
+        confirmation: PendingConfirmation,
+    },
+    PendingConfirmations {
+        confirmations: Vec<PendingConfirmation>,
+    },
+    /// A `KeepAlive` lifted the halt described by `snapshot`
+    Resumed {
+        snapshot: Box<HaltSnapshot>,
+    },
+    Bisect {
+        bisect: Option<BisectState>,
+    },
+    CherryPick {
+        cherry_pick: Option<CherryPickState>,
+    },
+    Sync {
+        sync: Option<SyncState>,
+    },
+    TurnUndone {
+        undo: TurnUndo,
+    },
+    /// One object per included section, with secrets redacted
+    DebugDump {
+        dump: Value,
+    },
+    Description {
+        build: BuildInfo,
+        protocol_version: u32,
+        workflows: Vec<String>,
+    },
+    /// Answer to `SendAndAwait`: the model's reply
+    Completion {
+        message: Message,
+    },
+    /// Answer to `SuggestCommitMessage`
+    CommitMessageSuggested {
+        message: String,
+        /// Where the message breaks the commit convention or template; the message is
+        /// returned either way
+        problems: Vec<String>,
+        /// Probable misspellings in the message, with `spellcheck` set
+        #[serde(default)]
+        misspellings: Vec<SpellingIssue>,
+    },
+    /// Answer to `SuggestBranchName`: up to 3 names, each valid under git's rules and
+    /// `branch_naming`
+    BranchNamesSuggested {
+        candidates: Vec<String>,
+    },
+    /// Answer to `SuggestPrDescription`; labels are among `pr_description.labels` when
+    /// it lists any
+    PrDescriptionSuggested {
+        title: String,
+        body: String,
+        labels: Vec<String>,
+        /// Probable misspellings, with `spellcheck` set; line 1 is the title and the
+        /// body starts on line 2
+        #[serde(default)]
+        misspellings: Vec<SpellingIssue>,
+    },
+    /// Answer to `Ping`
+    Pong {
+        /// Seconds between the first and the latest `Tick`; unset before any tick
+        uptime: Option<u64>,
+        /// Whether the default session's chat-state actor answered the probe
+        child_alive: bool,
+    },
+    Usage {
+        usage: UsageReport,
+    },
+    /// Answer to `GetMetrics`: `metrics` in the `json` format, `text` in the
+    /// `prometheus` one
+    Metrics {
+        format: MetricsFormat,
+        media_type: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metrics: Option<MetricsSnapshot>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    /// Answer to `RetryToolAttach`: the git tools are back and advice-only mode is over
+    ToolsAttached {
+        git_tools_actor_id: String,
+    },
+    /// Audit log entries, oldest first
+    AuditLog {
+        entries: Vec<AuditEntry>,
+    },
+    /// What `CompactState` dropped
+    StateCompacted {
+        report: CompactionReport,
+    },
+    /// Outcome of `FlushQueue`: requests sent now, and those still queued
+    QueueFlushed {
+        delivered: usize,
+        queued: usize,
+    },
+    /// Answer to `SetTemplateVars`: placeholders of the commit template still without
+    /// a value
+    TemplateVarsSet {
+        missing: Vec<String>,
+    },
+    /// Answer to `UpdateConfig` and `SetModelPreset`: the generation parameters now in use
+    ConfigUpdated {
+        config: GenerationConfig,
+    },
+    /// Answer to `CleanArtifacts`: the files removed, and the number of artifacts left
+    ArtifactsCleaned {
+        removed: Vec<String>,
+        kept: usize,
+    },
+    /// Answer to a slash command in `AddMessage` or `SendAndAwait` text, given instead
+    /// of a model turn
+    QuickCommand {
+        result: QuickCommandResult,
+    },
+    Success,
+    Error {
+        message: String,
+        /// Machine-readable reason, e.g. `secret-detected`; unset only in responses of
+        /// older assistants
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<ErrorCode>,
+    },
+}
+
+/// Messages the assistant sends on its own, to the configured `notify_actor`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum GitChatNotification {
+    WorkflowCompleted {
+        result: WorkflowResult,
+    },
+    /// Sent to `parent_actor_id` once init or a resume is done
+    Ready {
+        resumed: bool,
+        workflow: Option<Workflow>,
+        chat_state_actor_id: Option<String>,
+        build: BuildInfo,
+        /// Set when the init config failed and the assistant started in safe mode, with
+        /// the error
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        safe_mode: Option<String>,
+    },
+    /// Sent to `parent_actor_id` when init fails or the default session's chat-state
+    /// actor crashes in a loop
+    FatalError {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+        error: String,
+    },
+    /// A scheduled digest of recent repository activity
+    Digest {
+        digest: RepoDigest,
+    },
+    /// The divergence watch threshold was crossed
+    DivergenceExceeded {
+        divergence: Divergence,
+    },
+    /// Keep-alives stopped during an autonomous run and the assistant halted
+    Halted {
+        snapshot: HaltSnapshot,
+    },
+    /// The model's rebase plan, or the reason it couldn't be parsed in `rebase.error`
+    RebasePlanProposed {
+        rebase: RebaseState,
+    },
+    /// Branches the `branch-cleanup` workflow proposes to delete
+    BranchCleanupProposed {
+        cleanup: BranchCleanup,
+    },
+    /// Version and commits of the `tag-and-release` workflow, and the notes once drafted
+    ReleaseProposed {
+        release: Release,
+    },
+    /// The commit workflow proposed a plan of commits, with `commit_split`; it waits for
+    /// `ApproveCommitPlan`
+    CommitPlanProposed {
+        plan: CommitPlan,
+    },
+    /// A sibling finished a task delegated with `DelegateTask`, or failed it
+    DelegationFinished {
+        delegation: Delegation,
+    },
+    /// Every repository of a `StartFanOut` finished its run
+    FanOutFinished {
+        fan_out: FanOutResult,
+    },
+    /// The rebase from `ExecuteRebasePlan` completed or failed
+    RebaseFinished {
+        rebase: RebaseState,
+    },
+    /// The bisect found the first bad commit, in `bisect.first_bad`
+    BisectFinished {
+        bisect: BisectState,
+    },
+    /// The cherry-pick workflow picked or skipped every commit, or git refused a pick
+    CherryPickFinished {
+        cherry_pick: CherryPickState,
+    },
+    /// The workflow went past `max_turns` and didn't wrap up, or past
+    /// `workflow_timeout_secs`, and was stopped
+    WorkflowStalled {
+        stall: WorkflowStall,
+    },
+    /// The actor was restarted mid-workflow; what the repository showed and how the
+    /// workflow went on
+    WorkflowResumed {
+        resume: ResumeReport,
+    },
+    /// Files the `large_files` policy refuses: in a commit the model tried to make, which
+    /// was refused, or in the commits checked when the commit workflow completes
+    FilePolicyViolated {
+        violations: Vec<FileViolation>,
+        committed: bool,
+    },
+    /// Every step of a workflow pipeline completed, or one of them failed
+    PipelineCompleted {
+        result: PipelineResult,
+    },
+}
+
+/// Kinds of [`GitChatEvent`] an observer can subscribe to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    MessageAdded,
+    GenerationStarted,
+    GenerationFinished,
+    WorkflowStateChanged,
+    ChildCrashed,
+    ModelDowngraded,
+    TemplateVarsRequested,
+    SessionClosed,
+    SessionFailed,
+    CommitPlanProposed,
+    GenerationChunk,
+    AssistantOutput,
+    ToolActivity,
+    ApprovalRequested,
+    WorkflowProgress,
+    CommandsSuggested,
+    ToolsAvailabilityChanged,
+}
+
+impl EventKind {
+    /// Whether an observer listing `kinds` gets events of this kind. Chunks and tool
+    /// activity come too often to send to observers that didn't ask for them by name.
+    pub fn wanted_by(self, kinds: &BTreeSet<EventKind>) -> bool {
+        if kinds.is_empty() {
+            !matches!(self, EventKind::GenerationChunk | EventKind::ToolActivity)
+        } else {
+            kinds.contains(&self)
+        }
+    }
+}
+
+/// Where an auto-initiated workflow is, for `WorkflowStateChanged`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkflowState {
+    Started,
+    Finished,
+    /// Stopped by the dead-man switch
+    Halted,
+    Resumed,
+    /// Stopped after going past `max_turns` or `workflow_timeout_secs`
+    Stalled,
+    /// Completed without meeting its postconditions
+    Failed,
+}
+
+/// Whether a tool the model invoked started or returned, for `ToolActivity`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToolPhase {
+    Started,
+    Finished,
+}
+
+/// Events sent to the actors registered with `Subscribe`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum GitChatEvent {
+    /// A message was forwarded to a session's chat-state actor
+    MessageAdded {
+        session_id: String,
+    },
+    /// `generation_id` names the generation in its chunks and its end; a retry with a
+    /// fallback model keeps the id
+    GenerationStarted {
+        session_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        generation_id: Option<String>,
+    },
+    /// Text a session's model generated so far, as its chat-state actor streams it; only
+    /// sent to observers that list `generation-chunk`
+    GenerationChunk {
+        session_id: String,
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        generation_id: Option<String>,
+    },
+    /// A generation ended, with the provider's `error` when it failed
+    GenerationFinished {
+        session_id: Option<String>,
+        #[serde(default)]
+        stop_reason: Option<String>,
+        #[serde(default)]
+        error: Option<String>,
+        /// Id of the reply in the session's history, for `GetHistory { before }`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        generation_id: Option<String>,
+    },
+    WorkflowStateChanged {
+        workflow: Option<Workflow>,
+        state: WorkflowState,
+    },
+    ChildCrashed {
+        child_id: String,
+        error: String,
+    },
+    /// A session switched to a cheaper model because usage crossed a threshold in
+    /// `budget.downgrades`
+    ModelDowngraded {
+        session_id: String,
+        model: String,
+    },
+    /// The commit workflow needs values for these placeholders of `commit_template`
+    /// before it can start; send them with `SetTemplateVars`
+    TemplateVarsRequested {
+        missing: Vec<String>,
+        /// The template with the values known so far filled in
+        template: String,
+    },
+    /// A session's chat-state actor exited. `abnormal` is set when it stopped on a
+    /// failure, with the reason in `error`
+    SessionClosed {
+        session_id: String,
+        abnormal: bool,
+        #[serde(default)]
+        summary: Option<String>,
+        #[serde(default)]
+        error: Option<String>,
+        /// Set when the session was given a new chat-state actor to retry its workflow
+        #[serde(default)]
+        respawned: bool,
+    },
+    /// A session's chat-state actor crashed more often than `crash_loop` allows, so it
+    /// isn't replaced again
+    SessionFailed {
+        failure: SessionFailure,
+    },
+    /// The commit workflow proposed a plan of commits, as sent to `notify_actor`
+    CommitPlanProposed {
+        plan: CommitPlan,
+    },
+    /// Text of a session's reply once its generation finished, scratchpad taken out
+    AssistantOutput {
+        session_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message_id: Option<String>,
+        text: String,
+    },
+    /// The model invoked one of its tools, or the tool returned; `ok` is set once it
+    /// returned. Only sent to observers that list `tool-activity`
+    ToolActivity {
+        session_id: Option<String>,
+        tool: String,
+        phase: ToolPhase,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ok: Option<bool>,
+    },
+    /// Commands the model suggested in suggest-only mode, for the client to run, and the
+    /// suggestions that weren't a single command
+    CommandsSuggested {
+        session_id: String,
+        commands: Vec<SuggestedCommand>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        rejected: Vec<RejectedSuggestion>,
+    },
+    /// A request was held back until it is confirmed or rejected
+    ApprovalRequested {
+        confirmation: PendingConfirmation,
+    },
+    /// An auto-initiated workflow finished a turn or moved to another phase
+    WorkflowProgress {
+        #[serde(flatten)]
+        progress: WorkflowProgress,
+    },
+    /// The git tools couldn't be attached at init, with the error, so the assistant
+    /// runs in advice-only mode; or `RetryToolAttach` attached them
+    ToolsAvailabilityChanged {
+        available: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+impl GitChatEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            GitChatEvent::MessageAdded { .. } => EventKind::MessageAdded,
+            GitChatEvent::GenerationStarted { .. } => EventKind::GenerationStarted,
+            GitChatEvent::GenerationFinished { .. } => EventKind::GenerationFinished,
+            GitChatEvent::WorkflowStateChanged { .. } => EventKind::WorkflowStateChanged,
+            GitChatEvent::ChildCrashed { .. } => EventKind::ChildCrashed,
+            GitChatEvent::ModelDowngraded { .. } => EventKind::ModelDowngraded,
+            GitChatEvent::TemplateVarsRequested { .. } => EventKind::TemplateVarsRequested,
+            GitChatEvent::SessionClosed { .. } => EventKind::SessionClosed,
+            GitChatEvent::SessionFailed { .. } => EventKind::SessionFailed,
+            GitChatEvent::CommitPlanProposed { .. } => EventKind::CommitPlanProposed,
+            GitChatEvent::GenerationChunk { .. } => EventKind::GenerationChunk,
+            GitChatEvent::AssistantOutput { .. } => EventKind::AssistantOutput,
+            GitChatEvent::ToolActivity { .. } => EventKind::ToolActivity,
+            GitChatEvent::ApprovalRequested { .. } => EventKind::ApprovalRequested,
+            GitChatEvent::WorkflowProgress { .. } => EventKind::WorkflowProgress,
+            GitChatEvent::CommandsSuggested { .. } => EventKind::CommandsSuggested,
+            GitChatEvent::ToolsAvailabilityChanged { .. } => EventKind::ToolsAvailabilityChanged,
+        }
+    }
+}
+
+/// Outcome of an auto-initiated workflow, also passed as the assistant's exit state.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorkflowResult {
+    pub workflow: Workflow,
+    pub commits_created: Vec<CreatedCommit>,
+    pub files_touched: Vec<String>,
+    /// Summary supplied by the model when it completed the task
+    pub summary: Option<String>,
+    /// Set when the workflow finished without passing its checks
+    pub error: Option<String>,
+    /// `completed-with-warnings` when verification found discrepancies
+    #[serde(default)]
+    pub status: CompletionStatus,
+    /// Postconditions the repository didn't meet when the workflow completed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub postcondition_failures: Vec<PostconditionFailure>,
+    /// Audit log entries recorded while the workflow ran
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audit_log: Vec<AuditEntry>,
+    /// Findings the model submitted with `submit_review` while the workflow ran
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub review_findings: Vec<ReviewFinding>,
+    /// Checks of the repository once a workflow that changes it completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+}
+
+/// Outcome of a workflow pipeline, also passed as the assistant's exit state.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PipelineResult {
+    /// Results of the steps that ran, in order
+    pub steps: Vec<WorkflowResult>,
+    /// Steps that didn't run because an earlier one failed
+    pub skipped: Vec<Workflow>,
+    /// Set when a step failed or the next one couldn't be started
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatedCommit {
+    pub sha: String,
+    pub subject: String,
+    /// Committer date in seconds since the epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub committed_at: Option<u64>,
+}
+
+/// Serialize a notification inside a versioned envelope.
+pub fn encode_notification(notification: &GitChatNotification, at: u64) -> Result<Vec<u8>, String> {
+    let mut envelope = Envelope::new(notification);
+    envelope.at = Some(at);
+    serde_json::to_vec(&envelope).map_err(|e| format!("Failed to serialize notification: {}", e))
+}
+
+/// Serialize an event inside a versioned envelope.
+pub fn encode_event(event: &GitChatEvent, at: u64) -> Result<Vec<u8>, String> {
+    let mut envelope = Envelope::new(event);
+    envelope.at = Some(at);
+    serde_json::to_vec(&envelope).map_err(|e| format!("Failed to serialize event: {}", e))
+}
+
+/// A chat-state child the assistant routes messages to.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub chat_state_actor_id: String,
+    pub current_directory: Option<String>,
+    pub workflow: Option<Workflow>,
+}
+
+/// Snapshot of what the assistant is doing, returned by `GetStatus`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionStatus {
+    pub chat_state_actor_id: Option<String>,
+    pub workflow: Option<Workflow>,
+    pub current_directory: Option<String>,
+    pub auto_initiated: bool,
+    pub messages_forwarded: u64,
+    pub generations_requested: u64,
+    pub last_error: Option<String>,
+    pub tool_invocations: u64,
+    pub generations_finished: u64,
+    pub workflow_finished: bool,
+    /// Requests to chat-state actors waiting for a retry
+    #[serde(default)]
+    pub queued_sends: usize,
+    /// Outcome of the latest probe of the default session's chat-state actor, by
+    /// `Ping`; unset until the first probe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub child_alive: Option<bool>,
+    /// When the assistant started, in seconds since the epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    /// Whether the workflow was stopped for going past one of its limits; `last_error`
+    /// says which
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub workflow_aborted: bool,
+    /// Seconds since the assistant started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed: Option<u64>,
+    /// Last and next run of each `schedule` entry, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedule: Vec<ScheduledRun>,
+    /// Sessions given up on because their chat-state actor crashed in a loop
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_sessions: Vec<SessionFailure>,
+    /// Preset of `model_presets` in use, unset once `UpdateConfig` changed the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_preset: Option<String>,
+    /// Set while the assistant runs in safe mode, with the error the init config failed
+    /// with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safe_mode: Option<String>,
+    /// Ticket the work belongs to, from the `ticket` config or the branch name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket_id: Option<String>,
+    /// Fingerprint of the chat config the default session's chat-state actor runs with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_fingerprint: Option<String>,
+    /// Set while the assistant runs in advice-only mode, with why the git tools couldn't
+    /// be attached; boxed to keep halt snapshots small
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_outage: Option<Box<ToolOutage>>,
+}
+
+/// Parse a request envelope, rejecting versions this build doesn't speak.
+pub fn decode_request(bytes: &[u8]) -> Result<GitChatRequest, String> {
+    let envelope: Envelope<GitChatRequest> =
+        serde_json::from_slice(bytes).map_err(|e| format!("Failed to parse request: {}", e))?;
+    if envelope.v != PROTOCOL_VERSION {
+        return Err(format!(
+            "Unsupported protocol version {} (expected {})",
+            envelope.v, PROTOCOL_VERSION
+        ));
+    }
+    Ok(envelope.body)
+}
+
+/// Serialize a response inside a versioned envelope, with the build in its `meta`.
+pub fn encode_response(response: &GitChatResponse) -> Result<Vec<u8>, String> {
+    let mut envelope = Envelope::new(response);
+    envelope.meta = Some(ResponseMeta {
+        build: BuildInfo::current(),
+    });
+    serde_json::to_vec(&envelope).map_err(|e| format!("Failed to serialize response: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: Value) {
+        let parsed: Envelope<T> = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), value);
+    }
+
+    #[test]
+    fn requests_round_trip() {
+        let requests = [
+            json!({"v": 1, "type": "GetChatStateActorId"}),
+            json!({"v": 1, "type": "StartChat"}),
+            json!({"v": 1, "type": "StartChat", "allow_secrets": true}),
+            json!({"v": 1, "type": "StartChat", "allow_out_of_scope": true}),
+            json!({"v": 1, "type": "StartChat", "args": {"file": "src/lib.rs", "start_line": 10}}),
+            json!({"v": 1, "type": "StartChat", "workflow": "review", "args": "only the parser"}),
+            json!({"v": 1, "type": "SetWorkflow", "workflow": "review"}),
+            json!({"v": 1, "type": "SetWorkflow", "workflow": "commit", "start": true}),
+            json!({"v": 1, "type": "ScanForSecrets", "diff": null}),
+            json!({"v": 1, "type": "GetPendingConfirmations"}),
+            json!({"v": 1, "type": "AbortBisect"}),
+            json!({"v": 1, "type": "GetBisect"}),
+            json!({"v": 1, "type": "SkipCherryPick"}),
+            json!({"v": 1, "type": "AbortCherryPick"}),
+            json!({"v": 1, "type": "GetCherryPick"}),
+            json!({"v": 1, "type": "ExecuteSync", "strategy": "rebase"}),
+            json!({"v": 1, "type": "AbortSync"}),
+            json!({"v": 1, "type": "GetSync"}),
+            json!({"v": 1, "type": "UndoLastTurn"}),
+            json!({
+                "v": 1,
+                "type": "Subscribe",
+                "actor_id": "observer-1",
+                "events": ["generation-finished", "child-crashed"]
+            }),
+            json!({"v": 1, "type": "Unsubscribe", "actor_id": "observer-1"}),
+            json!({"v": 1, "type": "DebugDump", "include": ["sessions", "events"]}),
+            json!({"v": 1, "type": "CompactState"}),
+            json!({"v": 1, "type": "KeepAlive"}),
+            json!({"v": 1, "type": "Confirm", "id": "confirmation-1"}),
+            json!({"v": 1, "type": "RejectConfirmation", "id": "confirmation-1"}),
+            json!({"v": 1, "type": "GetStatus"}),
+            json!({"v": 1, "type": "ListSessions"}),
+            json!({
+                "v": 1,
+                "type": "AddMessage",
+                "message": {"role": "user", "content": [{"type": "text", "text": "hi"}]},
+                "session_id": "session-1"
+            }),
+            json!({
+                "v": 1,
+                "type": "SendAndAwait",
+                "message": {"role": "user", "content": [{"type": "text", "text": "hi"}]},
+                "session_id": null
+            }),
+            json!({
+                "v": 1,
+                "type": "CreateSession",
+                "session_id": null,
+                "current_directory": "/repo",
+                "workflow": "commit"
+            }),
+            json!({"v": 1, "type": "CloseSession", "session_id": "session-1"}),
+            json!({"v": 1, "type": "VerifyHistory", "range": "v1.0.0..HEAD"}),
+            json!({"v": 1, "type": "CheckSpelling", "text": "Fix teh parser"}),
+            json!({"v": 1, "type": "CreateBranch", "name": "Fix Parser", "start_point": null}),
+            json!({"v": 1, "type": "CancelGeneration", "session_id": null}),
+            json!({"v": 1, "type": "RunDigest", "since": 1760000000}),
+            json!({"v": 1, "type": "GetConfigDiagnostics"}),
+            json!({"v": 1, "type": "GetDivergence", "upstream": "upstream/main"}),
+            json!({"v": 1, "type": "AnalyzeRewrite", "range": "origin/main..HEAD"}),
+            json!({"v": 1, "type": "ReviewRefs", "branch": "feature/parser", "base": null}),
+            json!({"v": 1, "type": "GetReviewReport", "branch": "feature/parser"}),
+            json!({"v": 1, "type": "GetReviewReport", "branch": null}),
+            json!({"v": 1, "type": "PublishReview", "report_id": "review-1", "mode": "dry-run"}),
+            json!({"v": 1, "type": "PlanRebase", "onto": "origin/main", "range": null}),
+            json!({"v": 1, "type": "GetRebase"}),
+            json!({
+                "v": 1,
+                "type": "GetHistory",
+                "limit": 20,
+                "before": "msg-41",
+                "session_id": null
+            }),
+            json!({
+                "v": 1,
+                "type": "CompareRepos",
+                "other_directory": "/forks/repo",
+                "aspect": "divergence"
+            }),
+            json!({
+                "v": 1,
+                "type": "SubmitSecurityFindings",
+                "findings": [{
+                    "file": "src/db.rs",
+                    "line": 12,
+                    "title": "Query built from user input",
+                    "cwe": "CWE-89",
+                    "severity": "high",
+                    "exploitability": "Any caller controls the filter string",
+                    "suggestion": null
+                }]
+            }),
+        ];
+        for request in requests {
+            round_trip::<GitChatRequest>(request);
+        }
+    }
+
+    #[test]
+    fn responses_round_trip() {
+        let responses = [
+            json!({"v": 1, "type": "Success"}),
+            json!({"v": 1, "type": "Error", "message": "boom"}),
+            json!({
+                "v": 1,
+                "type": "Error",
+                "message": "Secrets found in the pending changes",
+                "code": "secret-detected"
+            }),
+            json!({
+                "v": 1,
+                "type": "DebugDump",
+                "dump": {
+                    "events": [
+                        {
+                            "at": 1700000000,
+                            "source": "request",
+                            "kind": "AddMessage",
+                            "session_id": null
+                        }
+                    ]
+                }
+            }),
+            json!({
+                "v": 1,
+                "type": "ConfirmationRequired",
+                "confirmation": {
+                    "id": "confirmation-1",
+                    "action": "publish-review",
+                    "severity": "remote-mutation",
+                    "session_id": "default",
+                    "description": "Post review review-1 to the forge (Inline)",
+                    "request": {"type": "PublishReview", "report_id": "review-1", "mode": "inline"}
+                }
+            }),
+            json!({"v": 1, "type": "PendingConfirmations", "confirmations": []}),
+            json!({
+                "v": 1,
+                "type": "SecretScan",
+                "findings": [
+                    {
+                        "file": "config/deploy.yml",
+                        "line": 14,
+                        "rule": "aws-access-key-id",
+                        "excerpt": "AKIA****************"
+                    },
+                    {"file": ".env", "line": null, "rule": "sensitive-file", "excerpt": ".env"}
+                ]
+            }),
+            json!({"v": 1, "type": "ChatStateActorId", "actor_id": "abc"}),
+            json!({
+                "v": 1,
+                "type": "RewriteAnalysis",
+                "analysis": {
+                    "range": "origin/main..HEAD",
+                    "commits_checked": 3,
+                    "published": [{
+                        "sha": "0123abcd",
+                        "subject": "Add parser",
+                        "remote_branches": ["origin/feature/parser"]
+                    }]
+                }
+            }),
+            json!({
+                "v": 1,
+                "type": "ReviewStarted",
+                "report": {
+                    "id": "review-1",
+                    "branch": "feature/parser",
+                    "base": "main",
+                    "last_reviewed_sha": "89abcdef",
+                    "rounds": [
+                        {"from": null, "to": "0123abcd", "commits": 2, "after_force_push": false},
+                        {"from": "0123abcd", "to": "89abcdef", "commits": 1, "after_force_push": false}
+                    ],
+                    "findings": [{
+                        "file": "src/db.rs",
+                        "line": 12,
+                        "title": "Query built from user input",
+                        "cwe": "CWE-89",
+                        "severity": "high",
+                        "exploitability": "Any caller controls the filter string",
+                        "suggestion": null
+                    }]
+                }
+            }),
+            json!({
+                "v": 1,
+                "type": "ReviewPublished",
+                "result": {
+                    "report_id": "review-1",
+                    "mode": "inline",
+                    "change_request": 42,
+                    "comments": [{
+                        "fingerprint": "src/db.rs:12:CWE-89",
+                        "path": "src/db.rs",
+                        "line": 12,
+                        "body": "**high: Query built from user input** (CWE-89)"
+                    }],
+                    "already_posted": 1,
+                    "remaining": 0
+                }
+            }),
+            json!({
+                "v": 1,
+                "type": "ConfigDiagnostics",
+                "diagnostics": [{
+                    "field": "temprature",
+                    "severity": "warning",
+                    "message": "unknown field, did you mean `temperature`?"
+                }]
+            }),
+            json!({
+                "v": 1,
+                "type": "History",
+                "messages": [{
+                    "id": "msg-40",
+                    "message": {"role": "user", "content": [{"type": "text", "text": "hi"}]}
+                }]
+            }),
+            json!({
+                "v": 1,
+                "type": "BranchCreated",
+                "name": "fix-parser",
+                "normalized_from": "Fix Parser"
+            }),
+            json!({
+                "v": 1,
+                "type": "InvalidBranchName",
+                "error": {
+                    "proposed": "wip",
+                    "normalized": "wip",
+                    "problems": ["name must start with one of feature/"]
+                }
+            }),
+            json!({
+                "v": 1,
+                "type": "Digest",
+                "digest": {
+                    "since": null,
+                    "until": null,
+                    "total_commits": 1,
+                    "commits": [{"sha": "0123abcd", "author": "Ada", "subject": "Fix parser"}],
+                    "authors": [{"name": "Ada", "commits": 1}],
+                    "hot_files": [{"name": "src/parser.rs", "commits": 1}]
+                }
+            }),
+            json!({
+                "v": 1,
+                "type": "Spelling",
+                "issues": [{"word": "teh", "line": 1, "suggestion": "the"}]
+            }),
+            json!({
+                "v": 1,
+                "type": "Status",
+                "status": {
+                    "chat_state_actor_id": "abc",
+                    "workflow": "merge-conflict",
+                    "current_directory": null,
+                    "auto_initiated": true,
+                    "messages_forwarded": 2,
+                    "generations_requested": 2,
+                    "last_error": null,
+                    "tool_invocations": 5,
+                    "generations_finished": 1,
+                    "workflow_finished": false,
+                    "queued_sends": 0,
+                    "started_at": 1760000000,
+                    "elapsed": 42
+                }
+            }),
+            json!({
+                "v": 1,
+                "type": "Sessions",
+                "sessions": [{
+                    "session_id": "default",
+                    "chat_state_actor_id": "abc",
+                    "current_directory": "/repo",
+                    "workflow": null
+                }]
+            }),
+            json!({
+                "v": 1,
+                "type": "HistoryVerification",
+                "report": {
+                    "range": "HEAD~1..HEAD",
+                    "commits": [{
+                        "sha": "0123abcd",
+                        "status": "signed",
+                        "code": "G",
+                        "signer": "Jane <jane@example.com>",
+                        "key": "ABCDEF",
+                        "subject": "Fix bug"
+                    }],
+                    "signed": 1,
+                    "unsigned": 0,
+                    "bad": 0,
+                    "unverifiable": 0
+                }
+            }),
+        ];
+        for response in responses {
+            round_trip::<GitChatResponse>(response);
+        }
+    }
+
+    #[test]
+    fn notifications_round_trip() {
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "WorkflowCompleted",
+            "result": {
+                "workflow": "commit",
+                "commits_created": [{"sha": "0123abcd", "subject": "feat: add parser", "committed_at": 1760000000}],
+                "files_touched": ["src/parser.rs"],
+                "summary": "Committed the parser",
+                "error": null,
+                "status": "completed"
+            }
+        }));
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "WorkflowCompleted",
+            "result": {
+                "workflow": "commit",
+                "commits_created": [],
+                "files_touched": ["src/parser.rs"],
+                "summary": "Committed the parser",
+                "error": "postconditions-failed: the commit workflow completed, but 1 files still staged: src/parser.rs",
+                "status": "failed",
+                "postcondition_failures": [{
+                    "postcondition": {"kind": "no-staged-changes"},
+                    "detail": "1 files still staged: src/parser.rs"
+                }, {
+                    "postcondition": {"kind": "tag-exists", "tag": "v1.2.0"},
+                    "detail": "tag v1.2.0 doesn't exist"
+                }]
+            }
+        }));
+        round_trip::<GitChatEvent>(json!({
+            "v": 1,
+            "at": 1760000000,
+            "type": "WorkflowStateChanged",
+            "workflow": "commit",
+            "state": "failed"
+        }));
+    }
+
+    #[test]
+    fn events_round_trip() {
+        let events = [
+            json!({"v": 1, "type": "MessageAdded", "session_id": "default"}),
+            json!({"v": 1, "type": "GenerationStarted", "session_id": "session-2"}),
+            json!({
+                "v": 1,
+                "type": "GenerationFinished",
+                "session_id": "default",
+                "stop_reason": null,
+                "error": "rate limited"
+            }),
+            json!({
+                "v": 1,
+                "type": "GenerationFinished",
+                "session_id": "default",
+                "stop_reason": "end_turn",
+                "error": null,
+                "message_id": "message-12"
+            }),
+            json!({
+                "v": 1,
+                "type": "WorkflowStateChanged",
+                "workflow": "commit",
+                "state": "halted"
+            }),
+            json!({
+                "v": 1,
+                "type": "ChildCrashed",
+                "child_id": "actor-7",
+                "error": "out of fuel"
+            }),
+            json!({
+                "v": 1,
+                "type": "ModelDowngraded",
+                "session_id": "session-2",
+                "model": "claude-3-5-haiku-20241022"
+            }),
+            json!({
+                "v": 1,
+                "type": "TemplateVarsRequested",
+                "missing": ["ticket"],
+                "template": "feat(billing): <summary>\n\nRefs: {{ticket}}"
+            }),
+            json!({
+                "v": 1,
+                "type": "SessionClosed",
+                "session_id": "default",
+                "abnormal": true,
+                "summary": null,
+                "error": "out of fuel",
+                "respawned": true
+            }),
+        ];
+        for event in events {
+            round_trip::<GitChatEvent>(event);
+        }
+    }
+
+    #[test]
+    fn divergence_round_trips() {
+        let divergence = json!({
+            "upstream": "upstream/main",
+            "ahead": 2,
+            "behind": 14,
+            "merge_base": "89abcdef",
+            "first_local": {"sha": "0123abcd", "subject": "Add fork patches"},
+            "first_upstream": null
+        });
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "Divergence",
+            "divergence": divergence.clone()
+        }));
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "DivergenceExceeded",
+            "divergence": divergence
+        }));
+    }
+
+    #[test]
+    fn halt_snapshot_round_trips() {
+        let snapshot = json!({
+            "halted_at": 1760000300,
+            "last_keepalive_at": 1760000000,
+            "head": "0123abcd",
+            "cancelled_sessions": ["default"],
+            "status": {
+                "chat_state_actor_id": "abc",
+                "workflow": "commit",
+                "current_directory": "/repo",
+                "auto_initiated": true,
+                "messages_forwarded": 1,
+                "generations_requested": 3,
+                "last_error": null,
+                "tool_invocations": 12,
+                "generations_finished": 2,
+                "workflow_finished": false,
+                "queued_sends": 2
+            }
+        });
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "Halted",
+            "snapshot": snapshot.clone()
+        }));
+        round_trip::<GitChatResponse>(json!({"v": 1, "type": "Resumed", "snapshot": snapshot}));
+    }
+
+    #[test]
+    fn pipeline_result_round_trips() {
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "PipelineCompleted",
+            "result": {
+                "steps": [{
+                    "workflow": "review",
+                    "commits_created": [],
+                    "files_touched": [],
+                    "summary": "No blocking findings",
+                    "error": null,
+                    "status": "completed"
+                }, {
+                    "workflow": "commit",
+                    "commits_created": [],
+                    "files_touched": [],
+                    "summary": null,
+                    "error": "secret-detected: the commits contain credentials",
+                    "status": "failed"
+                }],
+                "skipped": ["changelog"],
+                "error": "The commit step failed"
+            }
+        }));
+    }
+
+    #[test]
+    fn workflow_stall_round_trips() {
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "WorkflowStalled",
+            "stall": {"workflow": "commit", "turns": 22, "max_turns": 20}
+        }));
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "WorkflowStalled",
+            "stall": {"workflow": "commit", "turns": 6, "elapsed_secs": 612, "timeout_secs": 600}
+        }));
+        round_trip::<GitChatEvent>(json!({
+            "v": 1,
+            "type": "WorkflowStateChanged",
+            "workflow": "commit",
+            "state": "stalled"
+        }));
+    }
+
+    #[test]
+    fn rebase_plan_round_trips() {
+        let plan = json!({
+            "onto": "origin/main",
+            "steps": [
+                {
+                    "action": "reword",
+                    "sha": "0123abcd",
+                    "subject": "wip",
+                    "message": "Add parser",
+                    "reason": "The subject doesn't say what changed"
+                },
+                {
+                    "action": "fixup",
+                    "sha": "89abcdef",
+                    "subject": "fix typo",
+                    "message": null,
+                    "reason": null
+                }
+            ]
+        });
+        let rebase = json!({
+            "onto": "origin/main",
+            "range": "origin/main..HEAD",
+            "status": "proposed",
+            "commits": [
+                {"sha": "0123abcd", "subject": "wip"},
+                {"sha": "89abcdef", "subject": "fix typo"}
+            ],
+            "original_head": "89abcdef",
+            "plan": plan.clone(),
+            "error": null
+        });
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "ExecuteRebasePlan", "plan": plan}));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "RebasePlanning",
+            "rebase": rebase.clone()
+        }));
+        round_trip::<GitChatResponse>(json!({"v": 1, "type": "Rebase", "rebase": null}));
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "RebasePlanProposed",
+            "rebase": rebase
+        }));
+    }
+
+    #[test]
+    fn branch_cleanup_round_trips() {
+        let cleanup = json!({
+            "candidates": [
+                {
+                    "branch": "feature/login",
+                    "sha": "0123abcd",
+                    "reason": {"kind": "merged"}
+                },
+                {
+                    "branch": "origin/spike/cache",
+                    "remote": "origin",
+                    "sha": "89abcdef",
+                    "reason": {"kind": "stale", "days": 140}
+                }
+            ],
+            "deleted": ["feature/login"],
+            "failures": [{"branch": "origin/spike/cache", "error": "remote rejected"}]
+        });
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "ExecuteBranchCleanup",
+            "branches": ["feature/login"]
+        }));
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "GetBranchCleanup"}));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "BranchCleanup",
+            "cleanup": cleanup.clone()
+        }));
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "BranchCleanupProposed",
+            "cleanup": cleanup
+        }));
+    }
+
+    #[test]
+    fn release_round_trips() {
+        let release = json!({
+            "previous_tag": "v1.4.2",
+            "version": "1.5.0",
+            "tag": "v1.5.0",
+            "bump": "minor",
+            "commits": [
+                {
+                    "sha": "0123abcd",
+                    "subject": "feat(parser): nested lists",
+                    "type": "feat",
+                    "breaking": false,
+                    "bump": "minor"
+                },
+                {
+                    "sha": "89abcdef",
+                    "subject": "Update README",
+                    "type": null,
+                    "breaking": false,
+                    "bump": null
+                }
+            ],
+            "notes": "v1.5.0\n\n- Nested lists",
+            "tagged": true,
+            "error": null
+        });
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "ExecuteRelease",
+            "message": "v1.5.0"
+        }));
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "GetRelease"}));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "Release",
+            "release": release.clone()
+        }));
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "ReleaseProposed",
+            "release": release
+        }));
+    }
+
+    #[test]
+    fn sparse_checkout_round_trips() {
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "GetSparseCheckout"}));
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "SetSparsePaths",
+            "paths": ["services/api", "libs/common"]
+        }));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "SparseCheckout",
+            "sparse": {"enabled": true, "cone": true, "paths": ["services/api", "libs/common"]}
+        }));
+    }
+
+    #[test]
+    fn turn_round_trips() {
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "AcquireTurn",
+            "session_id": "review",
+            "client": "editor",
+            "lease_secs": 60
+        }));
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "ReleaseTurn",
+            "session_id": null,
+            "client": "editor"
+        }));
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "AddMessage",
+            "message": {"role": "user", "content": [{"type": "text", "text": "Go on"}]},
+            "session_id": "review",
+            "client": "editor"
+        }));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "TurnAcquired",
+            "turn": {
+                "session_id": "review",
+                "client": "editor",
+                "acquired_at": 1760000000,
+                "expires_at": 1760000060
+            }
+        }));
+    }
+
+    #[test]
+    fn conflicts_round_trip() {
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "GetConflicts"}));
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "ResolveConflict",
+            "file": "src/lib.rs",
+            "hunk": 0,
+            "resolution": {"kind": "custom", "text": "run(3);\n"}
+        }));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "Conflicts",
+            "files": [{
+                "path": "src/lib.rs",
+                "hunks": [{
+                    "index": 0,
+                    "line": 12,
+                    "ours": "run(1);\n",
+                    "base": null,
+                    "theirs": "run(2);\n"
+                }]
+            }]
+        }));
+    }
+
+    #[test]
+    fn delegation_round_trips() {
+        let sibling = json!({
+            "actor_id": "test-fixer",
+            "name": "test fixer",
+            "description": "Fixes failing tests"
+        });
+        let task = json!({"workflow": "commit", "instructions": "Fix tests/parser.rs"});
+        let delegation = json!({
+            "id": "delegation-1",
+            "actor_id": "test-fixer",
+            "task": task.clone(),
+            "status": "completed",
+            "started_at": 1760000000,
+            "finished_at": 1760000300,
+            "result": {
+                "workflow": "commit",
+                "commits_created": [{"sha": "0123abcd", "subject": "fix: flaky parser test"}],
+                "files_touched": ["tests/parser.rs"],
+                "summary": "Fixed the test",
+                "error": null,
+                "status": "completed"
+            },
+            "error": null
+        });
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "RegisterSibling",
+            "sibling": sibling.clone()
+        }));
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "ListSiblings"}));
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "DelegateTask",
+            "actor_id": "test-fixer",
+            "task": task.clone()
+        }));
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "AcceptDelegation",
+            "delegation_id": "delegation-1",
+            "from": "git-assistant",
+            "task": task
+        }));
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "GetDelegations"}));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "Siblings",
+            "siblings": [sibling]
+        }));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "Delegations",
+            "delegations": [delegation.clone()]
+        }));
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "DelegationFinished",
+            "delegation": delegation
+        }));
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "StartFanOut",
+            "repositories": ["/work/api", "/work/web"],
+            "task": {"workflow": "commit", "instructions": null}
+        }));
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "GetFanOut"}));
+        round_trip::<GitChatResponse>(json!({"v": 1, "type": "FanOut", "fan_out": null}));
+    }
+
+    #[test]
+    fn bisect_round_trips() {
+        let step = json!({
+            "sha": "0123abcd",
+            "subject": "Cache parsed configs",
+            "verdict": "bad",
+            "test_output": "test config::reload ... FAILED"
+        });
+        let bisect = json!({
+            "phase": "found",
+            "good": "v1.2.0",
+            "bad": "HEAD",
+            "steps": [
+                {
+                    "sha": "89abcdef",
+                    "subject": "Split config module",
+                    "verdict": "good",
+                    "test_output": null
+                },
+                step.clone()
+            ],
+            "steps_left": 0,
+            "first_bad": step
+        });
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "StartBisect",
+            "good": "v1.2.0",
+            "bad": null
+        }));
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "SubmitBisectVerdict",
+            "verdict": "skip"
+        }));
+        round_trip::<GitChatResponse>(json!({"v": 1, "type": "Bisect", "bisect": null}));
+        round_trip::<GitChatNotification>(json!({
+            "v": 1,
+            "type": "BisectFinished",
+            "bisect": bisect
+        }));
+    }
+
+    #[test]
+    fn requests_without_version_default_to_v1() {
+        let request = decode_request(br#"{"type": "GetStatus"}"#).unwrap();
+        assert!(matches!(request, GitChatRequest::GetStatus));
+    }
+
+    #[test]
+    fn only_generation_backed_requests_defer_their_response() {
+        for body in [
+            r#"{"type": "SendAndAwait", "message": {"role": "user", "content": []}}"#,
+            r#"{"type": "SuggestCommitMessage"}"#,
+            r#"{"type": "SuggestBranchName", "description": "parser"}"#,
+            r#"{"type": "SuggestPrDescription"}"#,
+        ] {
+            assert!(decode_request(body.as_bytes()).unwrap().defers_response());
+        }
+        let request = decode_request(
+            br#"{"type": "AddMessage", "message": {"role": "user", "content": []}}"#,
+        )
+        .unwrap();
+        assert!(!request.defers_response());
+    }
+
+    #[test]
+    fn unsupported_version_is_rejected() {
+        let error = decode_request(br#"{"v": 2, "type": "GetStatus"}"#).unwrap_err();
+        assert!(error.contains("Unsupported protocol version 2"));
+    }
+
+    #[test]
+    fn responses_carry_version_and_build() {
+        let bytes = encode_response(&GitChatResponse::Success).unwrap();
+        let value: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "v": 1,
+                "meta": {"build": BuildInfo::current()},
+                "type": "Success"
+            })
+        );
+    }
+
+    #[test]
+    fn describe_round_trips() {
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "Describe"}));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "meta": {"build": {"version": "0.1.0", "commit": "4f2c9a1b7e3d"}},
+            "type": "Description",
+            "build": {"version": "0.1.0", "commit": "4f2c9a1b7e3d"},
+            "protocol_version": 1,
+            "workflows": ["commit", "review"]
+        }));
+    }
+
+    #[test]
+    fn clean_artifacts_round_trips() {
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "CleanArtifacts",
+            "kinds": ["session-log", "backup"],
+            "keep": 5
+        }));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "ArtifactsCleaned",
+            "removed": ["000001-session-log-commit.json", "000002-backup-rebase.json"],
+            "kept": 7
+        }));
+    }
+
+    #[test]
+    fn completion_round_trips() {
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "Completion",
+            "message": {"role": "assistant", "content": [{"type": "text", "text": "Done."}]}
+        }));
+    }
+
+    #[test]
+    fn ping_round_trips() {
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "Ping"}));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "meta": {"build": {"version": "0.1.0", "commit": "4f2c9a1b7e3d"}},
+            "type": "Pong",
+            "uptime": 3600,
+            "child_alive": false
+        }));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "type": "Status",
+            "status": {
+                "chat_state_actor_id": "abc",
+                "workflow": null,
+                "current_directory": null,
+                "auto_initiated": false,
+                "messages_forwarded": 0,
+                "generations_requested": 0,
+                "last_error": "The chat-state actor didn't answer a ping",
+                "tool_invocations": 0,
+                "generations_finished": 0,
+                "workflow_finished": false,
+                "queued_sends": 0,
+                "child_alive": false,
+                "tool_outage": {
+                    "error": "Spawn failed: manifest not found",
+                    "since": 1760000000,
+                    "retries": 1,
+                    "last_retry_at": 1760000300
+                }
+            }
+        }));
+    }
+
+    #[test]
+    fn usage_round_trips() {
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "GetUsage"}));
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "GetMetrics", "format": "prometheus"}));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "meta": {"build": {"version": "0.1.0", "commit": "4f2c9a1b7e3d"}},
+            "type": "Usage",
+            "usage": {
+                "sessions": {
+                    "default": {
+                        "input_tokens": 12000,
+                        "output_tokens": 800,
+                        "cost": 0.048,
+                        "unpriced_tokens": 0
+                    }
+                },
+                "total": {
+                    "input_tokens": 12000,
+                    "output_tokens": 800,
+                    "cost": 0.048,
+                    "unpriced_tokens": 0
+                },
+                "max_tokens": null,
+                "max_cost": 5.0,
+                "budget_exceeded": false
+            }
+        }));
+    }
+
+    #[test]
+    fn audit_log_round_trips() {
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "GetAuditLog", "limit": 20}));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "meta": {"build": {"version": "0.1.0", "commit": "4f2c9a1b7e3d"}},
+            "type": "AuditLog",
+            "entries": [{
+                "id": 4,
+                "at": 1760000000,
+                "source": "tool",
+                "session_id": "default",
+                "command": "git_commit",
+                "arguments": {"message": "feat: add parser"},
+                "approval": "automatic",
+                "outcome": {"ok": true, "detail": "[main 0123abc] feat: add parser"}
+            }, {
+                "id": 5,
+                "at": 1760000060,
+                "source": "request",
+                "session_id": "default",
+                "command": "delete-branches",
+                "arguments": {"type": "ExecuteBranchCleanup", "branches": []},
+                "approval": "pending",
+                "confirmation_id": "confirmation-2"
+            }]
+        }));
+    }
+
+    #[test]
+    fn queue_flush_round_trips() {
+        round_trip::<GitChatRequest>(json!({"v": 1, "type": "FlushQueue"}));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "meta": {"build": {"version": "0.1.0", "commit": "4f2c9a1b7e3d"}},
+            "type": "QueueFlushed",
+            "delivered": 2,
+            "queued": 1
+        }));
+    }
+
+    #[test]
+    fn template_vars_round_trip() {
+        round_trip::<GitChatRequest>(json!({
+            "v": 1,
+            "type": "SetTemplateVars",
+            "vars": {"ticket": "PAY-142", "co_authors": "Co-authored-by: Ana <ana@example.com>"}
+        }));
+        round_trip::<GitChatResponse>(json!({
+            "v": 1,
+            "meta": {"build": {"version": "0.1.0", "commit": "4f2c9a1b7e3d"}},
+            "type": "TemplateVarsSet",
+            "missing": []
+        }));
+    }
+
+    #[test]
+    fn channel_frames_round_trip() {
+        round_trip::<ClientFrame>(json!({"v": 1, "type": "Open", "events": ["message-added"]}));
+        round_trip::<ClientFrame>(json!({
+            "v": 1,
+            "type": "Resume",
+            "stream_id": "stream-3",
+            "last_seq": 41
+        }));
+        round_trip::<ClientFrame>(json!({"v": 1, "type": "Ack", "seq": 42}));
+        round_trip::<ClientFrame>(json!({
+            "v": 1,
+            "type": "Request",
+            "seq": 7,
+            "request": {"type": "GetStatus"}
+        }));
+        round_trip::<ServerFrame>(json!({
+            "v": 1,
+            "type": "Welcome",
+            "stream_id": "stream-3",
+            "next_seq": 45,
+            "missed": 0
+        }));
+        round_trip::<ServerFrame>(json!({
+            "v": 1,
+            "type": "Event",
+            "seq": 43,
+            "event": {"type": "GenerationStarted", "session_id": "default"}
+        }));
+        round_trip::<ServerFrame>(json!({
+            "v": 1,
+            "type": "Event",
+            "seq": 46,
+            "event": {"type": "GenerationStarted", "session_id": "default"},
+            "at": 1760000000
+        }));
+        round_trip::<ServerFrame>(json!({
+            "v": 1,
+            "type": "Response",
+            "seq": 44,
+            "request_seq": 7,
+            "response": {"type": "Success"}
+        }));
+        round_trip::<ServerFrame>(
+            json!({"v": 1, "type": "Error", "message": "Unknown stream stream-9"}),
+        );
+    }
+}
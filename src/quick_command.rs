@@ -0,0 +1,214 @@
+//! Slash commands in `AddMessage` text, answered by the assistant without a model turn
+//! when `quick_commands` is set. Text starting with `//` goes to the model with one
+//! slash removed, so a message can still start with a command name.
+
+use crate::amend::{fetch_remote_branches, split_status};
+use crate::git_tools::run_git;
+use crate::rebase_plan::RebasePlan;
+use crate::tool_output::ToolOutputPolicy;
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+
+/// Names of the commands, as typed after the slash.
+pub const KNOWN_NAMES: &[&str] = &["status", "diff", "undo", "plan", "help"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuickCommand {
+    Status,
+    /// Changes against `target`, or against HEAD
+    Diff {
+        target: Option<String>,
+    },
+    /// Soft-reset the last commit of the workflow
+    Undo,
+    Plan,
+    Help,
+}
+
+/// What a message's text turned out to be.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parsed {
+    Command(QuickCommand),
+    /// A known command with arguments it doesn't take
+    Invalid(String),
+    /// An escaped command, for the model without its extra slash
+    Escaped(String),
+    /// Anything else, for the model as it is
+    Message,
+}
+
+/// Answer to a quick command, tagged with the command's name.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum QuickCommandResult {
+    Status {
+        branch: String,
+        /// Staged changes as `<status> <path>`
+        staged: Vec<String>,
+        /// Unstaged and untracked files
+        unstaged: Vec<String>,
+    },
+    Diff {
+        target: String,
+        stat: String,
+        /// Elided like the model's tool output
+        diff: String,
+    },
+    /// The commit that was undone; its changes stay staged
+    Undo {
+        sha: String,
+        subject: String,
+    },
+    Plan {
+        workflow: Option<Workflow>,
+        /// Steps of the pipeline, empty outside one
+        steps: Vec<Workflow>,
+        completed: usize,
+        rebase: Option<RebasePlan>,
+    },
+    Help {
+        commands: Vec<String>,
+    },
+}
+
+/// Tell a command in `text` from a message for the model.
+pub fn parse(text: &str) -> Parsed {
+    let text = text.trim();
+    if let Some(escaped) = text.strip_prefix("//") {
+        return match parse(&format!("/{}", escaped)) {
+            Parsed::Message => Parsed::Message,
+            _ => Parsed::Escaped(text[1..].to_string()),
+        };
+    }
+    let Some(line) = text.strip_prefix('/') else {
+        return Parsed::Message;
+    };
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let args: Vec<&str> = words.collect();
+    let command = match (name, args.as_slice()) {
+        ("status", []) => QuickCommand::Status,
+        ("diff", []) => QuickCommand::Diff { target: None },
+        ("diff", [target]) if !target.starts_with('-') => QuickCommand::Diff {
+            target: Some(target.to_string()),
+        },
+        ("undo", []) => QuickCommand::Undo,
+        ("plan", []) => QuickCommand::Plan,
+        ("help", []) => QuickCommand::Help,
+        _ if KNOWN_NAMES.contains(&name) => return Parsed::Invalid(usage(name)),
+        // Paths like `/etc/hosts` and unknown commands are for the model
+        _ => return Parsed::Message,
+    };
+    Parsed::Command(command)
+}
+
+fn usage(name: &str) -> String {
+    match name {
+        "diff" => "Usage: /diff [ref]".to_string(),
+        _ => format!("Usage: /{} (takes no arguments)", name),
+    }
+}
+
+pub fn help() -> QuickCommandResult {
+    QuickCommandResult::Help {
+        commands: vec![
+            "/status - branch and pending changes".to_string(),
+            "/diff [ref] - changes against HEAD or ref".to_string(),
+            "/undo - undo the last commit of this workflow, keeping its changes staged".to_string(),
+            "/plan - the workflow, pipeline, and rebase plan".to_string(),
+            "/help - this list; start a message with // to send it to the model".to_string(),
+        ],
+    }
+}
+
+pub fn status(git_actor_id: &str, directory: Option<&str>) -> Result<QuickCommandResult, String> {
+    let branch = run_git(
+        git_actor_id,
+        directory,
+        &["rev-parse", "--abbrev-ref", "HEAD"],
+    )?;
+    let status = run_git(git_actor_id, directory, &["status", "--porcelain=v1"])?;
+    let (staged, unstaged) = split_status(&status);
+    Ok(QuickCommandResult::Status {
+        branch: branch.trim().to_string(),
+        staged,
+        unstaged,
+    })
+}
+
+pub fn diff(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    target: Option<&str>,
+    policy: &ToolOutputPolicy,
+) -> Result<QuickCommandResult, String> {
+    let target = target.unwrap_or("HEAD");
+    let stat = run_git(git_actor_id, directory, &["diff", "--stat", target, "--"])?;
+    let diff = run_git(git_actor_id, directory, &["diff", target, "--"])?;
+    Ok(QuickCommandResult::Diff {
+        target: target.to_string(),
+        stat,
+        diff: policy.elide(&diff),
+    })
+}
+
+/// Soft-reset HEAD if it was committed after `base` and isn't on a remote yet.
+pub fn undo(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: Option<&str>,
+) -> Result<QuickCommandResult, String> {
+    let base = base.ok_or_else(|| "No workflow commits to undo".to_string())?;
+    let range = format!("{}..HEAD", base);
+    let count = run_git(git_actor_id, directory, &["rev-list", "--count", &range])?;
+    if count.trim() == "0" {
+        return Err("No workflow commits to undo".to_string());
+    }
+    let remote_branches = fetch_remote_branches(git_actor_id, directory)?;
+    if !remote_branches.is_empty() {
+        return Err(format!(
+            "The last commit is already on {}; it won't be undone",
+            remote_branches.join(", ")
+        ));
+    }
+    let log = run_git(git_actor_id, directory, &["log", "-1", "--format=%H%x1f%s"])?;
+    let (sha, subject) = log
+        .split_once('\x1f')
+        .ok_or_else(|| format!("Unexpected git log output: {}", log))?;
+    run_git(git_actor_id, directory, &["reset", "--soft", "HEAD~1"])?;
+    Ok(QuickCommandResult::Undo {
+        sha: sha.trim().to_string(),
+        subject: subject.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_parsed_with_their_arguments() {
+        assert_eq!(parse("/status"), Parsed::Command(QuickCommand::Status));
+        assert_eq!(
+            parse("  /diff main \n"),
+            Parsed::Command(QuickCommand::Diff {
+                target: Some("main".to_string())
+            })
+        );
+        assert_eq!(
+            parse("/diff"),
+            Parsed::Command(QuickCommand::Diff { target: None })
+        );
+        assert_eq!(parse("/undo now"), Parsed::Invalid(usage("undo")));
+        assert_eq!(parse("/diff --output=x"), Parsed::Invalid(usage("diff")));
+    }
+
+    #[test]
+    fn other_text_goes_to_the_model() {
+        assert_eq!(parse("Commit the parser changes"), Parsed::Message);
+        assert_eq!(parse("/etc/hosts is missing"), Parsed::Message);
+        assert_eq!(parse("/"), Parsed::Message);
+        assert_eq!(parse("//status"), Parsed::Escaped("/status".to_string()));
+        assert_eq!(parse("//etc/hosts"), Parsed::Message);
+    }
+}
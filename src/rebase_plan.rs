@@ -0,0 +1,269 @@
+use crate::git_tools::{ensure_revision, run_git};
+use crate::rewrite_safety::RewriteAnalysis;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Action under which executing a plan that rewrites commits already on a remote waits
+/// for `Confirm`, unless `allow_published_rewrite` is set.
+pub const REBASE_PUBLISHED: &str = "rebase-published-commits";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RebaseAction {
+    Pick,
+    /// Keep the changes with the new `message`
+    Reword,
+    /// Fold into the previous kept commit, with the combined `message`
+    Squash,
+    /// Fold into the previous kept commit, keeping its message
+    Fixup,
+    Drop,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RebaseStep {
+    pub action: RebaseAction,
+    pub sha: String,
+    #[serde(default)]
+    pub subject: String,
+    /// New message for `reword` and `squash`
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Why the model proposes this step
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Steps in the order the commits will be replayed onto `onto`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RebasePlan {
+    pub onto: String,
+    pub steps: Vec<RebaseStep>,
+}
+
+impl RebasePlan {
+    /// Commits `onto..HEAD` should hold once the plan has run.
+    pub fn expected_commits(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| matches!(step.action, RebaseAction::Pick | RebaseAction::Reword))
+            .count()
+    }
+
+    /// Check the plan against the commits it rebases, filling in full shas and subjects.
+    pub fn validate(&mut self, commits: &[RangeCommit]) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("the plan has no steps".to_string());
+        }
+        let mut seen = BTreeSet::new();
+        let mut kept_any = false;
+        for step in &mut self.steps {
+            let commit = match commits
+                .iter()
+                .find(|commit| step.sha.len() >= 7 && commit.sha.starts_with(&step.sha))
+            {
+                Some(commit) => commit,
+                None => return Err(format!("{} is not a commit in the range", step.sha)),
+            };
+            if !seen.insert(commit.sha.clone()) {
+                return Err(format!("{} appears more than once", step.sha));
+            }
+            step.sha = commit.sha.clone();
+            step.subject = commit.subject.clone();
+            match step.action {
+                RebaseAction::Squash | RebaseAction::Fixup if !kept_any => {
+                    return Err(format!(
+                        "{} can't be folded into a previous commit; nothing is kept before it",
+                        step.sha
+                    ))
+                }
+                RebaseAction::Reword | RebaseAction::Squash
+                    if step
+                        .message
+                        .as_deref()
+                        .unwrap_or_default()
+                        .trim()
+                        .is_empty() =>
+                {
+                    return Err(format!(
+                        "{} needs a message for {:?}",
+                        step.sha, step.action
+                    ))
+                }
+                RebaseAction::Pick | RebaseAction::Reword => kept_any = true,
+                _ => {}
+            }
+        }
+        // Commits left out of the plan would be dropped silently
+        if let Some(missing) = commits.iter().find(|commit| !seen.contains(&commit.sha)) {
+            return Err(format!(
+                "{} {} is missing from the plan; use drop to remove it",
+                missing.sha, missing.subject
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RangeCommit {
+    pub sha: String,
+    pub subject: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RebaseStatus {
+    /// The model was asked for a plan
+    Planning,
+    /// The model's plan was parsed and is waiting for `ExecuteRebasePlan`
+    Proposed,
+    /// The model was asked to carry out the plan
+    Executing,
+    Completed,
+    Failed,
+}
+
+/// The rebase tracked from `PlanRebase` until it completes or fails.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RebaseState {
+    pub onto: String,
+    pub range: String,
+    pub status: RebaseStatus,
+    /// Oldest first
+    pub commits: Vec<RangeCommit>,
+    /// HEAD before the rebase, to recover with `git reset --hard`
+    pub original_head: String,
+    pub plan: Option<RebasePlan>,
+    pub error: Option<String>,
+    /// Which of the commits are already on a remote-tracking branch, checked when the
+    /// rebase is planned and again before it is executed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewrite: Option<RewriteAnalysis>,
+}
+
+/// Commits in `range`, oldest first.
+pub fn fetch_range_commits(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    range: &str,
+) -> Result<Vec<RangeCommit>, String> {
+    ensure_revision("range", range)?;
+    let log = run_git(
+        git_actor_id,
+        directory,
+        &["log", "--reverse", "--format=%H%x1f%s", range],
+    )?;
+    Ok(log
+        .lines()
+        .filter_map(|line| {
+            let (sha, subject) = line.split_once('\u{1f}')?;
+            Some(RangeCommit {
+                sha: sha.trim().to_string(),
+                subject: subject.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Whether a rebase has stopped part way, e.g. on a conflict.
+pub fn rebase_in_progress(git_actor_id: &str, directory: Option<&str>) -> bool {
+    run_git(
+        git_actor_id,
+        directory,
+        &["rev-parse", "-q", "--verify", "REBASE_HEAD"],
+    )
+    .is_ok()
+}
+
+/// Message asking the model for a plan, without touching the repository.
+pub fn planning_message(rebase: &RebaseState) -> String {
+    let commits: Vec<String> = rebase
+        .commits
+        .iter()
+        .map(|commit| format!("- {} {}", commit.sha, commit.subject))
+        .collect();
+    let published = match rebase.rewrite.as_ref().filter(|rewrite| !rewrite.is_safe()) {
+        Some(rewrite) => format!(
+            "\n\nPUBLISHED HISTORY: {}. Rewriting them needs a force push over work others \
+            may have pulled; prefer a plan that keeps them as they are, and say so when it \
+            can't.",
+            rewrite.describe()
+        ),
+        None => String::new(),
+    };
+    format!(
+        "Propose an interactive rebase plan for {} onto {}. Do not run any git commands \
+        that change the repository yet.\n\nCommits, oldest first:\n{}\n\n\
+        Reply with the plan as a JSON block listing every commit once, in the order they \
+        should be replayed:\n\
+        ```json\n{{\"steps\": [{{\"action\": \"pick|reword|squash|fixup|drop\", \
+        \"sha\": \"<sha>\", \"message\": \"<new message for reword and squash, else null>\", \
+        \"reason\": \"<why>\"}}]}}\n```{}",
+        rebase.range,
+        rebase.onto,
+        commits.join("\n"),
+        published
+    )
+}
+
+/// Find the plan in the model's reply: the last fenced JSON block with `steps`, or the
+/// whole reply when post-processing already extracted the block.
+pub fn parse_plan(reply: &str, onto: &str) -> Result<RebasePlan, String> {
+    #[derive(Deserialize)]
+    struct Steps {
+        steps: Vec<RebaseStep>,
+    }
+    let parse = |json: &str| {
+        serde_json::from_str::<Steps>(json.trim())
+            .map(|parsed| RebasePlan {
+                onto: onto.to_string(),
+                steps: parsed.steps,
+            })
+            .map_err(|e| format!("invalid plan: {}", e))
+    };
+    let mut found = Err("no JSON plan in the reply".to_string());
+    for block in reply.split("```json").skip(1) {
+        found = parse(block.split("```").next().unwrap_or_default());
+    }
+    if found.is_err() && reply.trim_start().starts_with('{') {
+        found = parse(reply);
+    }
+    found
+}
+
+/// Message asking the model to carry out `plan` exactly.
+pub fn execution_message(plan: &RebasePlan, original_head: &str) -> String {
+    let steps: Vec<String> = plan
+        .steps
+        .iter()
+        .map(|step| {
+            let mut line = format!(
+                "{} {} {}",
+                serde_json::to_value(step.action)
+                    .ok()
+                    .and_then(|action| action.as_str().map(str::to_string))
+                    .unwrap_or_default(),
+                step.sha,
+                step.subject
+            );
+            if let Some(message) = &step.message {
+                line.push_str(&format!(
+                    "\n    new message: {}",
+                    message.replace('\n', "\n    ")
+                ));
+            }
+            line
+        })
+        .collect();
+    format!(
+        "Carry out this rebase onto {} exactly as listed, using a non-interactive \
+        sequence editor (e.g. GIT_SEQUENCE_EDITOR) rather than waiting for input:\n{}\n\n\
+        If a step conflicts, stop, leave the rebase in progress, and explain the conflict. \
+        The branch was at {} before the rebase. Call task_complete when the rebase has \
+        finished.",
+        plan.onto,
+        steps.join("\n"),
+        original_head
+    )
+}
@@ -0,0 +1,460 @@
+//! The `tag-and-release` workflow: the next version from the conventional-commit types
+//! of the commits since the last tag, release notes drafted by the model, and an
+//! annotated tag the assistant creates once `ExecuteRelease` is confirmed.
+
+use crate::git_tools::run_git;
+use crate::signatures::{self, CommitSignature, SignatureStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Part of the version a release increments, smallest first.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// The `release` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReleaseConfig {
+    /// Put before the version in tag names
+    #[serde(default = "default_tag_prefix")]
+    pub tag_prefix: String,
+    /// Bump of each commit type, e.g. `{"feat": "minor", "fix": "patch"}`
+    #[serde(default = "default_bumps")]
+    pub bumps: BTreeMap<String, Bump>,
+    /// Bump of commits whose type isn't in `bumps`; they don't bump when unset
+    #[serde(default)]
+    pub default_bump: Option<Bump>,
+    /// Bump of breaking changes, marked with `!` or a `BREAKING CHANGE:` footer
+    #[serde(default = "default_breaking")]
+    pub breaking: Bump,
+    /// Version of the first release, when no tag has the prefix
+    #[serde(default = "default_initial_version")]
+    pub initial_version: String,
+}
+
+fn default_tag_prefix() -> String {
+    "v".to_string()
+}
+
+fn default_bumps() -> BTreeMap<String, Bump> {
+    [
+        ("feat", Bump::Minor),
+        ("fix", Bump::Patch),
+        ("perf", Bump::Patch),
+    ]
+    .into_iter()
+    .map(|(kind, bump)| (kind.to_string(), bump))
+    .collect()
+}
+
+fn default_breaking() -> Bump {
+    Bump::Major
+}
+
+fn default_initial_version() -> String {
+    "0.1.0".to_string()
+}
+
+impl Default for ReleaseConfig {
+    fn default() -> Self {
+        Self {
+            tag_prefix: default_tag_prefix(),
+            bumps: default_bumps(),
+            default_bump: None,
+            breaking: default_breaking(),
+            initial_version: default_initial_version(),
+        }
+    }
+}
+
+impl ReleaseConfig {
+    /// How `commit` bumps the version, if at all.
+    fn bump_of(&self, commit: &ReleaseCommit) -> Option<Bump> {
+        if commit.breaking {
+            return Some(self.breaking);
+        }
+        match &commit.kind {
+            Some(kind) => self.bumps.get(kind).copied().or(self.default_bump),
+            None => self.default_bump,
+        }
+    }
+}
+
+/// A `major.minor.patch` version.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    /// Parse `major.minor.patch`, ignoring pre-release and build suffixes.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let core = text.split(['-', '+']).next().unwrap_or_default();
+        let parts: Vec<&str> = core.split('.').collect();
+        let number = |part: &str| {
+            part.parse::<u64>()
+                .map_err(|_| format!("{} is not a major.minor.patch version", text))
+        };
+        match parts.as_slice() {
+            [major, minor, patch] => Ok(Self {
+                major: number(major)?,
+                minor: number(minor)?,
+                patch: number(patch)?,
+            }),
+            _ => Err(format!("{} is not a major.minor.patch version", text)),
+        }
+    }
+
+    pub fn bump(self, bump: Bump) -> Self {
+        match bump {
+            Bump::Major => Self {
+                major: self.major + 1,
+                minor: 0,
+                patch: 0,
+            },
+            Bump::Minor => Self {
+                minor: self.minor + 1,
+                patch: 0,
+                ..self
+            },
+            Bump::Patch => Self {
+                patch: self.patch + 1,
+                ..self
+            },
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// A commit since the last tag, with what its message says about the version.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReleaseCommit {
+    pub sha: String,
+    pub subject: String,
+    /// Conventional-commit type, e.g. `feat`; unset for other subjects
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub breaking: bool,
+    pub bump: Option<Bump>,
+}
+
+/// The release a `tag-and-release` workflow proposes and, once confirmed, tags.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Release {
+    /// Latest tag with the prefix; unset for the first release
+    pub previous_tag: Option<String>,
+    pub version: String,
+    pub tag: String,
+    pub bump: Bump,
+    /// Commits since `previous_tag`, oldest first
+    pub commits: Vec<ReleaseCommit>,
+    /// Tag message the model drafted
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Commits in the release that are unsigned or carry a bad signature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signature_issues: Vec<CommitSignature>,
+    #[serde(default)]
+    pub tagged: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Type and breaking marker of a conventional commit message.
+pub fn classify(message: &str) -> (Option<String>, bool) {
+    let subject = message.lines().next().unwrap_or_default();
+    let footer_breaking = message
+        .lines()
+        .skip(1)
+        .any(|line| line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:"));
+    let Some((prefix, _)) = subject.split_once(':') else {
+        return (None, footer_breaking);
+    };
+    let (prefix, marked) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+    let kind = prefix.split('(').next().unwrap_or_default();
+    let valid = !kind.is_empty()
+        && kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && (kind.len() == prefix.len() || prefix.ends_with(')'));
+    if !valid {
+        return (None, footer_breaking);
+    }
+    (Some(kind.to_lowercase()), marked || footer_breaking)
+}
+
+/// The release the commits since the last tag call for.
+pub fn plan(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    config: &ReleaseConfig,
+) -> Result<Release, String> {
+    let pattern = format!("{}[0-9]*", config.tag_prefix);
+    // Fails when no tag matches
+    let previous_tag = run_git(
+        git_actor_id,
+        directory,
+        &["describe", "--tags", "--abbrev=0", "--match", &pattern],
+    )
+    .ok()
+    .map(|tag| tag.trim().to_string())
+    .filter(|tag| !tag.is_empty());
+    let range = match &previous_tag {
+        Some(tag) => format!("{}..HEAD", tag),
+        None => "HEAD".to_string(),
+    };
+    let log = run_git(
+        git_actor_id,
+        directory,
+        &[
+            "log",
+            "--reverse",
+            "--no-merges",
+            "--format=%H%x1f%B%x1e",
+            &range,
+        ],
+    )?;
+    let since = previous_tag.as_deref().unwrap_or("the first commit");
+    let commits = parse_log(&log, config);
+    if commits.is_empty() {
+        return Err(format!("There are no commits since {}", since));
+    }
+    let bump = commits
+        .iter()
+        .filter_map(|commit| commit.bump)
+        .max()
+        .ok_or_else(|| {
+            format!(
+                "None of the {} commits since {} bumps the version",
+                commits.len(),
+                since
+            )
+        })?;
+    let version = match &previous_tag {
+        Some(tag) => Version::parse(tag.strip_prefix(&config.tag_prefix).unwrap_or(tag))?
+            .bump(bump)
+            .to_string(),
+        None => Version::parse(&config.initial_version)?.to_string(),
+    };
+    let signature_issues =
+        signature_issues(signatures::verify_history(git_actor_id, directory, &range)?.commits);
+    Ok(Release {
+        previous_tag,
+        tag: format!("{}{}", config.tag_prefix, version),
+        version,
+        bump,
+        commits,
+        notes: None,
+        signature_issues,
+        tagged: false,
+        error: None,
+    })
+}
+
+/// The commits that are unsigned or badly signed, oldest first.
+fn signature_issues(commits: Vec<CommitSignature>) -> Vec<CommitSignature> {
+    commits
+        .into_iter()
+        .rev()
+        .filter(|commit| {
+            matches!(
+                commit.status,
+                SignatureStatus::Unsigned | SignatureStatus::Bad
+            )
+        })
+        .collect()
+}
+
+/// `<sha> US <message> RS` records of `git log`.
+fn parse_log(log: &str, config: &ReleaseConfig) -> Vec<ReleaseCommit> {
+    log.split('\u{1e}')
+        .filter_map(|record| {
+            let (sha, message) = record.trim_start().split_once('\u{1f}')?;
+            let message = message.trim();
+            let (kind, breaking) = classify(message);
+            let mut commit = ReleaseCommit {
+                sha: sha.to_string(),
+                subject: message.lines().next().unwrap_or_default().to_string(),
+                kind,
+                breaking,
+                bump: None,
+            };
+            commit.bump = config.bump_of(&commit);
+            Some(commit)
+        })
+        .collect()
+}
+
+/// Block for the opening message with the proposed version and the commits.
+pub fn describe(release: &Release) -> String {
+    let mut block = format!(
+        "\n\nRELEASE: {} commits since {}; they call for a {} release, version {} \
+        (tag {}).\nCommits, oldest first:",
+        release.commits.len(),
+        release
+            .previous_tag
+            .as_deref()
+            .unwrap_or("the first commit"),
+        serde_json::to_value(release.bump)
+            .ok()
+            .and_then(|bump| bump.as_str().map(str::to_string))
+            .unwrap_or_default(),
+        release.version,
+        release.tag
+    );
+    for commit in &release.commits {
+        let mut line = format!(
+            "\n- {} {}",
+            &commit.sha[..commit.sha.len().min(7)],
+            commit.subject
+        );
+        if commit.breaking {
+            line.push_str(" (breaking)");
+        }
+        block.push_str(&line);
+    }
+    if !release.signature_issues.is_empty() {
+        block.push_str(&format!(
+            "\n\n{} of these commits are unsigned or have a bad signature. Point them out \
+            to the user before the tag is created:",
+            release.signature_issues.len()
+        ));
+        for commit in &release.signature_issues {
+            block.push_str(&format!(
+                "\n- {} {} ({})",
+                &commit.sha[..commit.sha.len().min(7)],
+                commit.subject,
+                serde_json::to_value(commit.status)
+                    .ok()
+                    .and_then(|status| status.as_str().map(str::to_string))
+                    .unwrap_or_default()
+            ));
+        }
+    }
+    block.push_str(&format!(
+        "\n\nReply with the message for the annotated tag {} in a ```release-notes block.",
+        release.tag
+    ));
+    block
+}
+
+/// The tag message in the model's reply: its last `release-notes` block.
+pub fn parse_notes(reply: &str) -> Option<String> {
+    reply
+        .split("```release-notes")
+        .skip(1)
+        .filter_map(|block| block.split("```").next())
+        .map(str::trim)
+        .filter(|notes| !notes.is_empty())
+        .last()
+        .map(str::to_string)
+}
+
+/// Create the annotated tag of `release` at HEAD with `message`.
+pub fn create_tag(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    release: &Release,
+    message: &str,
+) -> Result<(), String> {
+    run_git(
+        git_actor_id,
+        directory,
+        &["tag", "--annotate", &release.tag, "--message", message],
+    )
+    .map(|_| ())
+}
+
+/// Message telling the model whether the tag was created, so it can wrap up.
+pub fn outcome_message(release: &Release) -> String {
+    match &release.error {
+        Some(error) => format!("Creating the tag {} failed: {}", release.tag, error),
+        None => format!(
+            "The annotated tag {} was created. It hasn't been pushed. Summarize the \
+            release and use the task_complete tool.",
+            release.tag
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_types_and_breaking_changes_are_recognized() {
+        assert_eq!(
+            classify("feat(parser): nested lists"),
+            (Some("feat".to_string()), false)
+        );
+        assert_eq!(
+            classify("fix!: drop the old flag"),
+            (Some("fix".to_string()), true)
+        );
+        assert_eq!(
+            classify("refactor: split the lexer\n\nBREAKING CHANGE: Token is private"),
+            (Some("refactor".to_string()), true)
+        );
+        assert_eq!(classify("Update README: typos"), (None, false));
+    }
+
+    #[test]
+    fn the_largest_bump_wins() {
+        let config = ReleaseConfig::default();
+        let log =
+            "a1\u{1f}docs: typo\u{1e}\nb2\u{1f}fix: off by one\u{1e}\nc3\u{1f}feat: lists\u{1e}\n";
+        let commits = parse_log(log, &config);
+        let bumps: Vec<Option<Bump>> = commits.iter().map(|commit| commit.bump).collect();
+        assert_eq!(bumps, vec![None, Some(Bump::Patch), Some(Bump::Minor)]);
+        let version = Version::parse("1.4.2").unwrap();
+        assert_eq!(version.bump(Bump::Minor).to_string(), "1.5.0");
+        assert_eq!(version.bump(Bump::Major).to_string(), "2.0.0");
+        assert!(Version::parse("1.4").is_err());
+    }
+
+    #[test]
+    fn the_last_notes_block_is_used() {
+        let reply = "Draft:\n```release-notes\nold\n```\nRevised:\n```release-notes\nv1.5.0\n\n- Nested lists\n```";
+        assert_eq!(
+            parse_notes(reply).as_deref(),
+            Some("v1.5.0\n\n- Nested lists")
+        );
+        assert_eq!(parse_notes("No notes yet"), None);
+    }
+
+    #[test]
+    fn unsigned_and_badly_signed_commits_are_flagged_oldest_first() {
+        let commit = |sha: &str, code: &str| CommitSignature {
+            sha: sha.to_string(),
+            status: SignatureStatus::from_code(code),
+            code: code.to_string(),
+            signer: None,
+            key: None,
+            subject: format!("fix: {}", sha),
+        };
+        let newest_first = vec![
+            commit("d4", "G"),
+            commit("c3", "B"),
+            commit("b2", "E"),
+            commit("a1", "N"),
+        ];
+        let shas: Vec<String> = signature_issues(newest_first)
+            .into_iter()
+            .map(|commit| commit.sha)
+            .collect();
+        assert_eq!(shas, vec!["a1", "c3"]);
+    }
+}
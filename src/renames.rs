@@ -0,0 +1,113 @@
+//! Renames and copies found by git's `-M -C` detection, so change summaries and review
+//! context say "renamed X to Y" instead of a deletion and an addition.
+
+use crate::git_tools::run_git;
+use crate::sanitize::{self, TextKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveKind {
+    Rename,
+    Copy,
+}
+
+/// A file git matched to the one it was renamed or copied from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMove {
+    pub kind: MoveKind,
+    pub from: String,
+    pub to: String,
+    /// How much of the content is unchanged, in percent
+    pub similarity: u8,
+}
+
+impl FileMove {
+    pub fn describe(&self) -> String {
+        let verb = match self.kind {
+            MoveKind::Rename => "renamed",
+            MoveKind::Copy => "copied",
+        };
+        let mut description = format!(
+            "{} {} to {}",
+            verb,
+            sanitize::escape(&self.from, TextKind::Name),
+            sanitize::escape(&self.to, TextKind::Name)
+        );
+        if self.similarity < 100 {
+            description.push_str(&format!(" ({}% similar)", self.similarity));
+        }
+        description
+    }
+}
+
+/// Renames and copies in `git diff --name-status -M -C` output, e.g. `R096\told\tnew`.
+pub fn parse_name_status(output: &str) -> Vec<FileMove> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let status = fields.next()?;
+            let (from, to) = (fields.next()?, fields.next()?);
+            let kind = match status.chars().next()? {
+                'R' => MoveKind::Rename,
+                'C' => MoveKind::Copy,
+                _ => return None,
+            };
+            Some(FileMove {
+                kind,
+                from: from.to_string(),
+                to: to.to_string(),
+                similarity: status[1..].parse().unwrap_or(100),
+            })
+        })
+        .collect()
+}
+
+/// Renames and copies between the revisions in `target`, as `git diff` takes them,
+/// e.g. `["HEAD"]`, `["--cached"]`, or `["main...HEAD"]`.
+pub fn fetch(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    target: &[&str],
+) -> Result<Vec<FileMove>, String> {
+    let mut args = vec!["diff", "--name-status", "-M", "-C"];
+    args.extend_from_slice(target);
+    let output = run_git(git_actor_id, directory, &args)?;
+    Ok(parse_name_status(&output))
+}
+
+/// Block listing `moves` for the model; empty when there are none.
+pub fn render(moves: &[FileMove]) -> String {
+    if moves.is_empty() {
+        return String::new();
+    }
+    let mut block = String::from("RENAMES AND COPIES:");
+    for file_move in moves {
+        block.push_str(&format!("\n- {}", file_move.describe()));
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_and_copies_are_parsed_and_described() {
+        let output = "M\tsrc/lib.rs\n\
+            R096\tsrc/util.rs\tsrc/util/mod.rs\n\
+            C075\tsrc/parser.rs\tsrc/lexer.rs\n\
+            R100\tdocs/old.md\tdocs/new.md\n";
+        let moves = parse_name_status(output);
+        assert_eq!(moves.len(), 3);
+        assert_eq!(moves[0].kind, MoveKind::Rename);
+        assert_eq!(moves[1].kind, MoveKind::Copy);
+        assert_eq!(
+            render(&moves),
+            "RENAMES AND COPIES:\n\
+            - renamed src/util.rs to src/util/mod.rs (96% similar)\n\
+            - copied src/parser.rs to src/lexer.rs (75% similar)\n\
+            - renamed docs/old.md to docs/new.md"
+        );
+        assert_eq!(render(&[]), "");
+    }
+}
@@ -0,0 +1,190 @@
+//! Per-repository overrides read from a config file in `current_directory`.
+//!
+//! Precedence, highest first: the init config, the repository file, built-in defaults.
+//! Objects are merged key by key, so a repository can set `commit_convention.types`
+//! while the init config sets `commit_convention.max_subject_length`; any other value
+//! from the init config replaces the file's outright.
+
+use crate::config_check::ConfigDiagnostic;
+use crate::exec_tools::run_command;
+use serde_json::{Map, Value};
+
+/// Files looked for in `current_directory`, in order; only the first one found is read.
+pub const REPO_CONFIG_FILES: &[&str] = &[".gitassistant.toml", ".gitassistant.json"];
+
+/// Fields a repository file may set. Anything that spawns actors, points at other
+/// directories, or sends messages stays with whoever starts the assistant.
+const REPO_FIELDS: &[&str] = &[
+    "workflow",
+    "task",
+    "system_prompt",
+    "prompts",
+    "temperature",
+    "max_tokens",
+    "dirty_worktree_policy",
+    "commit_convention",
+    "commit_template",
+    "template_vars",
+    "language",
+    "commit_split",
+    "require_signoff",
+    "gpg_sign",
+    "commit_size",
+    "submodules",
+    "large_files",
+    "diff_budget",
+    "diff_summary",
+    "repo_stats",
+    "tool_output",
+    "branch_naming",
+    "branch_cleanup",
+    "release",
+    "branch_context",
+    "ticket",
+    "spellcheck",
+    "changelog",
+    "pr_description",
+    "postprocess",
+    "review",
+    "bisect",
+    "sync",
+    "blocked_commands",
+    "scope_paths",
+    "max_turns",
+    "workflow_timeout_secs",
+    "postconditions",
+    "verification",
+    "command_policy",
+    "suggest_only",
+];
+
+/// Find and read the repository config file in `directory`, using the exec actor.
+/// Returns the file name and its contents, or `None` when there is no file.
+pub fn read_repo_config(
+    exec_actor_id: &str,
+    directory: &str,
+) -> Result<Option<(&'static str, String)>, String> {
+    for name in REPO_CONFIG_FILES {
+        let command = format!("test -f {} && echo present || echo missing", name);
+        if run_command(exec_actor_id, Some(directory), &command)?.trim() != "present" {
+            continue;
+        }
+        let contents = run_command(exec_actor_id, Some(directory), &format!("cat {}", name))?;
+        return Ok(Some((name, contents)));
+    }
+    Ok(None)
+}
+
+/// Parse a repository config file as TOML or JSON, going by its extension.
+pub fn parse_repo_config(name: &str, contents: &str) -> Result<Value, String> {
+    let value: Value = if name.ends_with(".toml") {
+        toml::from_str(contents).map_err(|e| e.to_string())?
+    } else {
+        serde_json::from_str(contents).map_err(|e| e.to_string())?
+    };
+    match value {
+        Value::Object(_) => Ok(value),
+        _ => Err("expected a table of settings".to_string()),
+    }
+}
+
+/// Merge the repository file `repo` under the init config `explicit`. Fields a
+/// repository may not set are dropped with a warning.
+pub fn merge_under(explicit: &Value, repo: &Value, source: &str) -> (Value, Vec<ConfigDiagnostic>) {
+    let mut diagnostics = Vec::new();
+    let mut allowed = Map::new();
+    if let Value::Object(fields) = repo {
+        for (key, value) in fields {
+            if REPO_FIELDS.contains(&key.as_str()) {
+                allowed.insert(key.clone(), value.clone());
+            } else {
+                diagnostics.push(ConfigDiagnostic::warning(
+                    key,
+                    format!("ignored in {}; set it in the init config instead", source),
+                ));
+            }
+        }
+    }
+    (merge_values(explicit, &Value::Object(allowed)), diagnostics)
+}
+
+/// `high` wins over `low`, except that two objects are merged recursively.
+fn merge_values(high: &Value, low: &Value) -> Value {
+    match (high, low) {
+        (Value::Object(high), Value::Object(low)) => {
+            let mut merged = low.clone();
+            for (key, value) in high {
+                let value = match low.get(key) {
+                    Some(low_value) => merge_values(value, low_value),
+                    None => value.clone(),
+                };
+                merged.insert(key.clone(), value);
+            }
+            Value::Object(merged)
+        }
+        // An explicit null keeps the default rather than the repository's value
+        _ => high.clone(),
+    }
+}
+
+/// System prompt rule for the configured `blocked_commands`.
+pub fn blocked_commands_fragment(commands: &[String]) -> String {
+    format!(
+        "\n\nBLOCKED COMMANDS: This repository does not allow the following commands. \
+        Never run them, and ask the user to run them instead if the task needs one: {}",
+        commands.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn init_config_wins_and_objects_merge() {
+        let explicit = json!({
+            "temperature": 0.2,
+            "commit_convention": {"max_subject_length": 50}
+        });
+        let repo = json!({
+            "temperature": 0.9,
+            "system_prompt": "Be terse.",
+            "commit_convention": {"types": ["feat", "fix"], "max_subject_length": 72}
+        });
+        let (merged, diagnostics) = merge_under(&explicit, &repo, ".gitassistant.toml");
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            merged,
+            json!({
+                "temperature": 0.2,
+                "system_prompt": "Be terse.",
+                "commit_convention": {"types": ["feat", "fix"], "max_subject_length": 50}
+            })
+        );
+    }
+
+    #[test]
+    fn restricted_fields_are_dropped() {
+        let repo = json!({
+            "mcp_servers": [{"actor": {"manifest_path": "/tmp/evil.toml"}}],
+            "current_directory": "/",
+            "blocked_commands": ["git push --force"]
+        });
+        let (merged, diagnostics) = merge_under(&json!({}), &repo, ".gitassistant.json");
+        assert_eq!(merged, json!({"blocked_commands": ["git push --force"]}));
+        let fields: Vec<&str> = diagnostics.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, ["current_directory", "mcp_servers"]);
+    }
+
+    #[test]
+    fn toml_and_json_parse_alike() {
+        let toml = "workflow = \"commit\"\n\n[commit_convention]\ntypes = [\"feat\"]\n";
+        let json = r#"{"workflow": "commit", "commit_convention": {"types": ["feat"]}}"#;
+        assert_eq!(
+            parse_repo_config(".gitassistant.toml", toml).unwrap(),
+            parse_repo_config(".gitassistant.json", json).unwrap()
+        );
+        assert!(parse_repo_config(".gitassistant.json", "[1, 2]").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use crate::git_tools::run_git;
+use crate::logging;
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+
+/// What to do when a workflow that assumes a clean tree starts with uncommitted changes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DirtyWorktreePolicy {
+    /// Refuse to start the workflow.
+    Block,
+    /// Stash the changes (including untracked files) before starting.
+    Stash,
+    /// Start anyway and tell the model the changes must be carried through.
+    Include,
+    /// Start by asking the user how to handle the changes.
+    #[default]
+    Ask,
+}
+
+/// Summary of `git status --porcelain --branch`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RepoState {
+    pub branch: Option<String>,
+    pub staged: usize,
+    pub unstaged: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+}
+
+impl RepoState {
+    pub fn parse_porcelain(output: &str) -> Self {
+        let mut state = RepoState::default();
+        for line in output.lines() {
+            if let Some(header) = line.strip_prefix("## ") {
+                let branch = header.split("...").next().unwrap_or(header);
+                let branch = branch.split(' ').next().unwrap_or(branch);
+                state.branch = Some(branch.to_string());
+                continue;
+            }
+            let mut codes = line.chars();
+            let (x, y) = match (codes.next(), codes.next()) {
+                (Some(x), Some(y)) => (x, y),
+                _ => continue,
+            };
+            match (x, y) {
+                ('?', '?') => state.untracked += 1,
+                ('!', '!') => {}
+                ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => state.conflicted += 1,
+                _ => {
+                    if x != ' ' {
+                        state.staged += 1;
+                    }
+                    if y != ' ' {
+                        state.unstaged += 1;
+                    }
+                }
+            }
+        }
+        state
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.staged == 0 && self.unstaged == 0 && self.untracked == 0 && self.conflicted == 0
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} staged, {} unstaged, {} untracked, {} conflicted",
+            self.staged, self.unstaged, self.untracked, self.conflicted
+        )
+    }
+}
+
+pub fn fetch_repo_state(git_actor_id: &str, directory: Option<&str>) -> Result<RepoState, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &["status", "--porcelain", "--branch"],
+    )?;
+    Ok(RepoState::parse_porcelain(&output))
+}
+
+/// Paths with unresolved merge conflicts.
+pub fn conflicted_files(
+    git_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--name-only", "--diff-filter=U"],
+    )?;
+    Ok(output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Check the working tree before `workflow` starts and apply the dirty-worktree policy.
+///
+/// Returns `Ok(None)` when the workflow can start as-is, `Ok(Some(note))` when it can start
+/// but the model must be told about the tree (the note is appended to the auto-initiation
+/// message), and `Err` when the policy refuses to start.
+pub fn preflight(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    workflow: &Workflow,
+    policy: DirtyWorktreePolicy,
+) -> Result<Option<String>, String> {
+    if !workflow.requires_clean_tree() {
+        return Ok(None);
+    }
+
+    let repo_state = fetch_repo_state(git_actor_id, directory)
+        .map_err(|e| format!("Pre-flight repository check failed: {}", e))?;
+    logging::info(&format!(
+        "Pre-flight repo state for {} workflow: {:?}",
+        workflow.name(),
+        repo_state
+    ));
+
+    if repo_state.is_clean() {
+        return Ok(None);
+    }
+
+    if repo_state.conflicted > 0 {
+        return Err(format!(
+            "Cannot start {} workflow: the repository has {} unresolved conflicts",
+            workflow.name(),
+            repo_state.conflicted
+        ));
+    }
+
+    match policy {
+        DirtyWorktreePolicy::Block => Err(format!(
+            "Cannot start {} workflow with a dirty working tree ({}); dirty_worktree_policy is \"block\"",
+            workflow.name(),
+            repo_state.describe()
+        )),
+        DirtyWorktreePolicy::Stash => {
+            let stash_message = format!("git-chat-assistant: before {}", workflow.name());
+            run_git(
+                git_actor_id,
+                directory,
+                &["stash", "push", "--include-untracked", "-m", &stash_message],
+            )
+            .map_err(|e| format!("Failed to stash uncommitted changes: {}", e))?;
+            logging::info(&format!("Stashed uncommitted changes: {}", stash_message));
+            Ok(Some(format!(
+                "\n\nNOTE: Uncommitted changes ({}) were stashed before starting, with the message \
+                \"{}\". Restore them with git stash pop once the workflow is finished.",
+                repo_state.describe(),
+                stash_message
+            )))
+        }
+        DirtyWorktreePolicy::Include => Ok(Some(format!(
+            "\n\nNOTE: The working tree has uncommitted changes ({}). They must be preserved \
+            throughout the workflow (for example with --autostash); never discard them.",
+            repo_state.describe()
+        ))),
+        DirtyWorktreePolicy::Ask => Ok(Some(format!(
+            "\n\nBEFORE STARTING: The working tree has uncommitted changes ({}). Ask me whether \
+            to stash them, commit them, or abort, and do not run any other git operation until \
+            I answer.",
+            repo_state.describe()
+        ))),
+    }
+}
@@ -0,0 +1,224 @@
+//! The `repo_stats` config: statistics of the repository (commit frequency, top
+//! contributors, the files that change most, and the branch count), read through the git
+//! tools and sent to the default session before the opening message of the workflows
+//! that benefit, such as review and onboarding. They are kept in state and read again
+//! once they are older than `ttl_secs`.
+
+use crate::git_tools::run_git;
+use crate::onboard::{self, Contributor};
+use crate::sanitize::{self, TextKind};
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The `repo_stats` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RepoStatsConfig {
+    #[serde(default = "default_workflows")]
+    pub workflows: Vec<Workflow>,
+    /// How long gathered statistics are reused
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Days of history the statistics cover
+    #[serde(default = "default_since_days")]
+    pub since_days: u32,
+    /// Entries of the contributor and file lists
+    #[serde(default = "default_top")]
+    pub top: usize,
+}
+
+fn default_workflows() -> Vec<Workflow> {
+    vec![Workflow::Review, Workflow::Onboard]
+}
+
+fn default_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_since_days() -> u32 {
+    90
+}
+
+fn default_top() -> usize {
+    5
+}
+
+impl Default for RepoStatsConfig {
+    fn default() -> Self {
+        Self {
+            workflows: default_workflows(),
+            ttl_secs: default_ttl_secs(),
+            since_days: default_since_days(),
+            top: default_top(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HotFile {
+    pub path: String,
+    pub commits: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RepoStats {
+    /// When they were read, in seconds since the epoch
+    pub gathered_at: u64,
+    pub since_days: u32,
+    pub commits: usize,
+    /// Days with at least one commit
+    pub active_days: usize,
+    pub contributors: Vec<Contributor>,
+    /// Files changed by the most commits, most first
+    pub hot_files: Vec<HotFile>,
+    pub branches: usize,
+}
+
+/// Commits and the days they were made on, from `git log --format=%cs`.
+fn count_days(log: &str) -> (usize, usize) {
+    let dates: Vec<&str> = log
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let days: BTreeSet<&str> = dates.iter().copied().collect();
+    (dates.len(), days.len())
+}
+
+/// Commits touching each file, most first, from `git log --format= --name-only`.
+fn count_files(log: &str) -> Vec<HotFile> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for path in log.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        *counts.entry(path).or_insert(0) += 1;
+    }
+    let mut files: Vec<HotFile> = counts
+        .into_iter()
+        .map(|(path, commits)| HotFile {
+            path: path.to_string(),
+            commits,
+        })
+        .collect();
+    files.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.path.cmp(&b.path)));
+    files
+}
+
+pub fn fetch(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    config: &RepoStatsConfig,
+    now: u64,
+) -> Result<RepoStats, String> {
+    let since = format!("--since={}.days", config.since_days);
+    let dates = run_git(
+        git_actor_id,
+        directory,
+        &["log", "--no-merges", &since, "--format=%cs"],
+    )?;
+    let (commits, active_days) = count_days(&dates);
+    let names = run_git(
+        git_actor_id,
+        directory,
+        &["log", "--no-merges", &since, "--format=", "--name-only"],
+    )?;
+    let shortlog = run_git(
+        git_actor_id,
+        directory,
+        &["shortlog", "-sn", "--no-merges", &since, "HEAD"],
+    )?;
+    let branches = run_git(
+        git_actor_id,
+        directory,
+        &["for-each-ref", "--format=%(refname)", "refs/heads"],
+    )?;
+    let mut contributors = onboard::parse_shortlog(&shortlog);
+    contributors.truncate(config.top);
+    let mut hot_files = count_files(&names);
+    hot_files.truncate(config.top);
+    Ok(RepoStats {
+        gathered_at: now,
+        since_days: config.since_days,
+        commits,
+        active_days,
+        contributors,
+        hot_files,
+        branches: branches.lines().filter(|line| !line.is_empty()).count(),
+    })
+}
+
+impl RepoStats {
+    pub fn is_fresh(&self, ttl_secs: u64, now: u64) -> bool {
+        now.saturating_sub(self.gathered_at) < ttl_secs
+    }
+
+    /// The context message sent before the opening message.
+    pub fn describe(&self) -> String {
+        let weeks = (f64::from(self.since_days) / 7.0).max(1.0);
+        let mut text = format!(
+            "REPOSITORY STATISTICS for the last {} days, for context; don't repeat them \
+            back unless they matter to the task.\n\
+            - {} commits on {} days, about {:.1} a week\n\
+            - {} local branches",
+            self.since_days,
+            self.commits,
+            self.active_days,
+            self.commits as f64 / weeks,
+            self.branches
+        );
+        if !self.contributors.is_empty() {
+            text.push_str("\nTop contributors:");
+            for contributor in &self.contributors {
+                text.push_str(&format!(
+                    "\n- {} ({} commits)",
+                    sanitize::escape(&contributor.name, TextKind::Name),
+                    contributor.commits
+                ));
+            }
+        }
+        if !self.hot_files.is_empty() {
+            text.push_str("\nMost changed files:");
+            for file in &self.hot_files {
+                text.push_str(&format!(
+                    "\n- {} ({} commits)",
+                    sanitize::escape(&file.path, TextKind::Name),
+                    file.commits
+                ));
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics_are_counted_from_the_log() {
+        assert_eq!(count_days("2025-10-01\n2025-10-01\n2025-10-03\n\n"), (3, 2));
+        let files = count_files("src/lib.rs\nREADME.md\n\nsrc/lib.rs\nsrc/parser.rs\n");
+        assert_eq!(
+            files[0],
+            HotFile {
+                path: "src/lib.rs".to_string(),
+                commits: 2
+            }
+        );
+        assert_eq!(files.len(), 3);
+
+        let stats = RepoStats {
+            gathered_at: 1_760_000_000,
+            since_days: 14,
+            commits: 9,
+            active_days: 6,
+            contributors: onboard::parse_shortlog("     7\tAda Lovelace\n     2\tGrace Hopper\n"),
+            hot_files: files,
+            branches: 4,
+        };
+        let text = stats.describe();
+        assert!(text.contains("9 commits on 6 days, about 4.5 a week"));
+        assert!(text.contains("- Ada Lovelace (7 commits)"));
+        assert!(text.contains("- src/lib.rs (2 commits)"));
+        assert!(stats.is_fresh(3600, 1_760_003_599));
+        assert!(!stats.is_fresh(3600, 1_760_003_600));
+    }
+}
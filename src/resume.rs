@@ -0,0 +1,209 @@
+//! Resuming a workflow the host interrupted by restarting the actor. The state saved
+//! after every call carries a checkpoint of the running workflow: its phase and the
+//! actions still waiting. On resume, the repository is checked against it, a git
+//! operation left half done is aborted when the conversation was lost with it, and the
+//! workflow goes on from its phase.
+
+use crate::git_tools::run_git;
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkflowPhase {
+    /// The model works on the task
+    Working,
+    /// A plan or a held request waits for a client's approval
+    AwaitingApproval,
+    /// An approved plan is being carried out
+    Executing,
+}
+
+impl WorkflowPhase {
+    fn describe(self) -> &'static str {
+        match self {
+            WorkflowPhase::Working => "running",
+            WorkflowPhase::AwaitingApproval => "waiting for an approval",
+            WorkflowPhase::Executing => "carrying out an approved plan",
+        }
+    }
+}
+
+/// The running workflow, as saved with the state after every call.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorkflowCheckpoint {
+    pub workflow: Workflow,
+    pub phase: WorkflowPhase,
+    /// Whether the default session was generating
+    pub generating: bool,
+    /// Actions waiting on a client or on the generation, e.g. `confirmation confirm-3:
+    /// delete-branches`
+    #[serde(default)]
+    pub pending: Vec<String>,
+}
+
+/// A git operation that can stop part way and leave the repository mid-change.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitOperation {
+    Rebase,
+    CherryPick,
+    Merge,
+    Revert,
+}
+
+impl GitOperation {
+    const ALL: [GitOperation; 4] = [
+        GitOperation::Rebase,
+        GitOperation::CherryPick,
+        GitOperation::Merge,
+        GitOperation::Revert,
+    ];
+
+    /// The git subcommand, which is also how the operation is named.
+    pub fn command(self) -> &'static str {
+        match self {
+            GitOperation::Rebase => "rebase",
+            GitOperation::CherryPick => "cherry-pick",
+            GitOperation::Merge => "merge",
+            GitOperation::Revert => "revert",
+        }
+    }
+
+    /// Ref git keeps while the operation is stopped.
+    fn head_ref(self) -> &'static str {
+        match self {
+            GitOperation::Rebase => "REBASE_HEAD",
+            GitOperation::CherryPick => "CHERRY_PICK_HEAD",
+            GitOperation::Merge => "MERGE_HEAD",
+            GitOperation::Revert => "REVERT_HEAD",
+        }
+    }
+}
+
+/// The git operation that has stopped part way, if any.
+pub fn operation_in_progress(git_actor_id: &str, directory: Option<&str>) -> Option<GitOperation> {
+    GitOperation::ALL.into_iter().find(|operation| {
+        run_git(
+            git_actor_id,
+            directory,
+            &["rev-parse", "-q", "--verify", operation.head_ref()],
+        )
+        .is_ok()
+    })
+}
+
+/// Abort `operation`, putting the repository back where it was before it started.
+pub fn abort(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    operation: GitOperation,
+) -> Result<(), String> {
+    run_git(git_actor_id, directory, &[operation.command(), "--abort"]).map(|_| ())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResumeAction {
+    /// The conversation survived the restart and goes on as it was
+    Continued,
+    /// A new conversation picks the workflow up from the repository as it was found
+    Restarted,
+    /// A new conversation waits for the approval the workflow was waiting for
+    Waiting,
+}
+
+/// What to do with a workflow in `phase`.
+pub fn action(phase: WorkflowPhase, conversation_kept: bool) -> ResumeAction {
+    match (conversation_kept, phase) {
+        (true, _) => ResumeAction::Continued,
+        (false, WorkflowPhase::AwaitingApproval) => ResumeAction::Waiting,
+        (false, _) => ResumeAction::Restarted,
+    }
+}
+
+/// What resuming an interrupted workflow found and did.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResumeReport {
+    pub workflow: Workflow,
+    pub phase: WorkflowPhase,
+    pub action: ResumeAction,
+    /// Commits made since the workflow started, as `<short sha> <subject>`
+    pub commits: Vec<String>,
+    /// Git operation that was left half done and aborted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rolled_back: Option<GitOperation>,
+}
+
+impl ResumeReport {
+    /// What the model of a new conversation is told.
+    pub fn message(&self) -> String {
+        let mut message = format!(
+            "\n\nRESUMED: The assistant was restarted while the {} workflow was {}, and the \
+            conversation started over. The repository was checked since:",
+            self.workflow.name(),
+            self.phase.describe()
+        );
+        if self.commits.is_empty() {
+            message.push_str("\n- No commits were made since the workflow started.");
+        } else {
+            message.push_str(
+                "\n- These commits were made since the workflow started; they are done, so \
+                don't make them again:",
+            );
+            for commit in &self.commits {
+                message.push_str(&format!("\n  - {}", commit));
+            }
+        }
+        if let Some(operation) = self.rolled_back {
+            message.push_str(&format!(
+                "\n- A {} was left half done and was aborted; the repository is back where \
+                it was before the {} started.",
+                operation.command(),
+                operation.command()
+            ));
+        }
+        message.push_str(match self.action {
+            ResumeAction::Waiting => {
+                "\nThe workflow waits for a client's approval, as it did before the restart. \
+                You will be told when it comes."
+            }
+            _ => "\nContinue the workflow from there.",
+        });
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lost_conversations_restart_from_the_repository() {
+        assert_eq!(
+            action(WorkflowPhase::Executing, true),
+            ResumeAction::Continued
+        );
+        assert_eq!(
+            action(WorkflowPhase::AwaitingApproval, false),
+            ResumeAction::Waiting
+        );
+        assert_eq!(
+            action(WorkflowPhase::Working, false),
+            ResumeAction::Restarted
+        );
+
+        let report = ResumeReport {
+            workflow: Workflow::Commit,
+            phase: WorkflowPhase::Executing,
+            action: ResumeAction::Restarted,
+            commits: vec!["4f2c9a1 fix(parser): handle tabs".to_string()],
+            rolled_back: Some(GitOperation::CherryPick),
+        };
+        let message = report.message();
+        assert!(message.contains("while the commit workflow was carrying out an approved plan"));
+        assert!(message.contains("\n  - 4f2c9a1 fix(parser): handle tabs"));
+        assert!(message.contains("A cherry-pick was left half done and was aborted"));
+        assert!(message.ends_with("Continue the workflow from there."));
+    }
+}
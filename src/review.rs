@@ -0,0 +1,643 @@
+use crate::api_diff::ApiDiffConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Focus of the review workflow.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewProfile {
+    #[default]
+    Standard,
+    Security,
+    Performance,
+}
+
+/// The `review` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReviewConfig {
+    #[serde(default)]
+    pub profile: ReviewProfile,
+    /// Benchmark the performance profile runs around suggested changes
+    pub benchmark: Option<BenchmarkConfig>,
+    /// Public-API diff run when a review touches Rust code
+    pub api_diff: Option<ApiDiffConfig>,
+}
+
+/// Benchmark run through the exec tool before and after a suggested change.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchmarkConfig {
+    pub command: String,
+    /// Suggested changes are applied on branches named `<prefix>/<name>`
+    #[serde(default = "default_sandbox_branch_prefix")]
+    pub sandbox_branch_prefix: String,
+}
+
+fn default_sandbox_branch_prefix() -> String {
+    "perf-sandbox".to_string()
+}
+
+impl ReviewConfig {
+    /// Whether the chat needs the exec tools to run the configured benchmark.
+    pub fn needs_exec_tools(&self) -> bool {
+        self.profile == ReviewProfile::Performance && self.benchmark.is_some()
+    }
+
+    pub fn prompt_fragment(&self) -> String {
+        match self.profile {
+            ReviewProfile::Standard => String::new(),
+            ReviewProfile::Performance => {
+                let mut fragment = String::from(
+                    "\n\nREVIEW PROFILE: PERFORMANCE\n\
+                    Focus the review on performance: algorithmic complexity, unnecessary \
+                    allocations and copies, repeated work in loops, blocking I/O on hot paths, \
+                    and contention. For every finding, estimate the impact and the inputs it \
+                    matters for.",
+                );
+                match &self.benchmark {
+                    Some(benchmark) => fragment.push_str(&format!(
+                        "\n\nBENCHMARK: Measure findings with the exec tool instead of estimating:\n\
+                        1. Run `{command}` on the current HEAD to get a baseline\n\
+                        2. Create a sandbox branch {prefix}/<short-name> from HEAD and apply \
+                        the suggested change there, never on the branch under review\n\
+                        3. Run `{command}` again on the sandbox branch\n\
+                        4. Switch back to the original branch and delete the sandbox branch\n\
+                        End your review with a fenced ```json block of the form:\n\
+                        {{\"findings\": [{{\"file\": \"path\", \"line\": 42, \"title\": \"...\", \
+                        \"suggestion\": \"...\", \"benchmark\": {{\"metric\": \"...\", \
+                        \"unit\": \"ns/iter\", \"before\": 1200.0, \"after\": 950.0, \
+                        \"delta_percent\": -20.8}}}}]}}\n\
+                        Leave out \"benchmark\" for findings you could not measure.",
+                        command = benchmark.command,
+                        prefix = benchmark.sandbox_branch_prefix
+                    )),
+                    None => fragment.push_str(
+                        "\nNo benchmark is configured, so label impact estimates as estimates.",
+                    ),
+                }
+                fragment
+            }
+            ReviewProfile::Security => format!(
+                "\n\nREVIEW PROFILE: SECURITY\n\
+                Focus the review on security weaknesses. For every finding, identify the \
+                matching CWE weakness and explain how an attacker could exploit it, including \
+                the preconditions and impact.\n\
+                End your review with a fenced ```json block of the form:\n\
+                {{\"findings\": [{{\"file\": \"path\", \"line\": 42, \"title\": \"...\", \
+                \"cwe\": \"CWE-89\", \"severity\": \"critical|high|medium|low\", \
+                \"exploitability\": \"...\", \"suggestion\": \"...\"}}]}}\n\
+                Only use CWE identifiers you are certain of; findings with unknown CWE ids are \
+                rejected. Common identifiers: {}",
+                COMMON_CWE_IDS
+                    .iter()
+                    .map(|id| format!("CWE-{}", id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SecurityFinding {
+    pub file: String,
+    pub line: Option<u32>,
+    pub title: String,
+    /// CWE identifier such as `"CWE-79"`
+    pub cwe: String,
+    pub severity: String,
+    /// How an attacker could exploit the weakness
+    pub exploitability: String,
+    pub suggestion: Option<String>,
+}
+
+impl SecurityFinding {
+    /// Identifies a finding across review rounds: the same weakness at the same place.
+    pub fn fingerprint(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}:{}", self.file, line, self.cwe),
+            None => format!("{}:{}", self.file, self.cwe),
+        }
+    }
+}
+
+/// How much a review finding matters.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingSeverity {
+    Critical,
+    High,
+    Medium,
+    Low,
+    Info,
+}
+
+/// One finding of the review workflow, as the model submits it with `submit_review`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReviewFinding {
+    pub file: String,
+    /// First line the finding is about; `None` for the whole file
+    #[serde(default)]
+    pub start_line: Option<u32>,
+    /// Last line, when the finding covers a range
+    #[serde(default)]
+    pub end_line: Option<u32>,
+    pub severity: FindingSeverity,
+    /// e.g. `correctness`, `security`, `performance`, `style`, `tests`, or `docs`
+    pub category: String,
+    pub title: String,
+    #[serde(default)]
+    pub suggestion: Option<String>,
+}
+
+impl ReviewFinding {
+    /// Identifies a finding across submissions: the same title at the same place.
+    pub fn fingerprint(&self) -> String {
+        match self.start_line {
+            Some(line) => format!("{}:{}:{}", self.file, line, self.title),
+            None => format!("{}:{}", self.file, self.title),
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.file.trim().is_empty() {
+            return Err(format!("Finding without a file: {}", self.title));
+        }
+        if self.title.trim().is_empty() {
+            return Err(format!("Finding in {} without a title", self.file));
+        }
+        match (self.start_line, self.end_line) {
+            (None, Some(_)) => Err(format!(
+                "Finding in {} has an end line but no start line",
+                self.file
+            )),
+            (Some(start), Some(end)) if end < start => Err(format!(
+                "Finding in {} ends at line {} before it starts at line {}",
+                self.file, end, start
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Split submitted findings into usable ones and the reasons the rest were dropped.
+pub fn check_review_findings(findings: Vec<ReviewFinding>) -> (Vec<ReviewFinding>, Vec<String>) {
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    for finding in findings {
+        match finding.check() {
+            Ok(()) => accepted.push(finding),
+            Err(reason) => rejected.push(reason),
+        }
+    }
+    (accepted, rejected)
+}
+
+/// Review workflow instructions for submitting findings in structured form.
+pub const SUBMIT_REVIEW_PROMPT: &str = "\n\nSTRUCTURED FINDINGS: Before you complete the \
+    task, call the submit_review tool, if it is available, with every finding of the review: \
+    {\"findings\": [{\"file\": \"path\", \"start_line\": 40, \"end_line\": 42, \
+    \"severity\": \"critical|high|medium|low|info\", \"category\": \
+    \"correctness|security|performance|style|tests|docs\", \"title\": \"...\", \
+    \"suggestion\": \"...\"}]}. Leave out the lines for findings about a whole file. \
+    Submitting again adds to the findings submitted before.";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RejectedFinding {
+    pub finding: SecurityFinding,
+    pub reason: String,
+}
+
+/// Split findings into those with a known CWE id and exploitability note, and the rest.
+pub fn validate_findings(
+    findings: Vec<SecurityFinding>,
+) -> (Vec<SecurityFinding>, Vec<RejectedFinding>) {
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    for finding in findings {
+        match validate_finding(&finding) {
+            Ok(()) => accepted.push(finding),
+            Err(reason) => rejected.push(RejectedFinding { finding, reason }),
+        }
+    }
+    (accepted, rejected)
+}
+
+/// The findings in the last ```json block of a security review's reply, if there is
+/// one. They still need `validate_findings`.
+pub fn parse_security_findings(reply: &str) -> Option<Result<Vec<SecurityFinding>, String>> {
+    parse_findings_block(reply)
+}
+
+/// The `findings` of the last ```json block of `reply`, if there is one.
+fn parse_findings_block<T: serde::de::DeserializeOwned>(
+    reply: &str,
+) -> Option<Result<Vec<T>, String>> {
+    #[derive(Deserialize)]
+    struct Findings<T> {
+        findings: Vec<T>,
+    }
+    let block = reply.split("```json").skip(1).last()?;
+    let json = block.split("```").next().unwrap_or_default();
+    Some(
+        serde_json::from_str::<Findings<T>>(json.trim())
+            .map(|parsed| parsed.findings)
+            .map_err(|e| format!("invalid findings: {}", e)),
+    )
+}
+
+fn validate_finding(finding: &SecurityFinding) -> Result<(), String> {
+    let id = finding
+        .cwe
+        .trim()
+        .to_ascii_uppercase()
+        .strip_prefix("CWE-")
+        .and_then(|id| id.parse::<u32>().ok())
+        .ok_or_else(|| format!("Malformed CWE identifier: {}", finding.cwe))?;
+    let name = cwe_name(id).ok_or_else(|| format!("Unknown CWE identifier: CWE-{}", id))?;
+    if finding.exploitability.trim().is_empty() {
+        return Err(format!(
+            "Missing exploitability note for CWE-{} ({})",
+            id, name
+        ));
+    }
+    Ok(())
+}
+
+/// Measurement of a suggested change with the configured benchmark.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    pub metric: String,
+    pub unit: String,
+    /// Result on the reviewed HEAD
+    pub before: f64,
+    /// Result on the sandbox branch with the change applied
+    pub after: f64,
+    pub delta_percent: f64,
+}
+
+/// One finding of a performance review with a benchmark configured.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BenchmarkFinding {
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u32>,
+    pub title: String,
+    #[serde(default)]
+    pub suggestion: Option<String>,
+    /// Unset when the model couldn't measure the change
+    #[serde(default)]
+    pub benchmark: Option<BenchmarkResult>,
+}
+
+impl BenchmarkFinding {
+    /// Identifies a finding across review rounds: the same title at the same place.
+    pub fn fingerprint(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}:{}", self.file, line, self.title),
+            None => format!("{}:{}", self.file, self.title),
+        }
+    }
+}
+
+/// Percentage points `delta_percent` may be off from the one `before` and `after` give,
+/// since the model rounds it.
+const DELTA_TOLERANCE: f64 = 0.5;
+
+/// The findings in the last ```json block of a benchmarked performance review's reply,
+/// if there is one, each as sent. They still need `check_benchmark_findings`.
+pub fn parse_benchmark_findings(reply: &str) -> Option<Result<Vec<Value>, String>> {
+    parse_findings_block(reply)
+}
+
+/// Split findings into well-formed ones and the reasons the rest were dropped: a
+/// finding is dropped when it doesn't parse, or when its measurement isn't two positive
+/// numbers and the change between them.
+pub fn check_benchmark_findings(findings: Vec<Value>) -> (Vec<BenchmarkFinding>, Vec<String>) {
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    for value in findings {
+        let finding = match serde_json::from_value::<BenchmarkFinding>(value) {
+            Ok(finding) => finding,
+            Err(e) => {
+                rejected.push(format!("Malformed benchmark finding: {}", e));
+                continue;
+            }
+        };
+        match check_benchmark(&finding) {
+            Ok(()) => accepted.push(finding),
+            Err(reason) => rejected.push(reason),
+        }
+    }
+    (accepted, rejected)
+}
+
+fn check_benchmark(finding: &BenchmarkFinding) -> Result<(), String> {
+    let Some(result) = &finding.benchmark else {
+        return Ok(());
+    };
+    if result.metric.trim().is_empty() || result.unit.trim().is_empty() {
+        return Err(format!(
+            "Benchmark of {} has no metric or unit",
+            finding.fingerprint()
+        ));
+    }
+    let measured = result.before.is_finite()
+        && result.before > 0.0
+        && result.after.is_finite()
+        && result.after >= 0.0;
+    if !measured {
+        return Err(format!(
+            "Benchmark of {} measured {} before and {} after",
+            finding.fingerprint(),
+            result.before,
+            result.after
+        ));
+    }
+    let delta = (result.after - result.before) / result.before * 100.0;
+    if (delta - result.delta_percent).abs() > DELTA_TOLERANCE {
+        return Err(format!(
+            "Benchmark of {} reports a {}% change, but {} to {} is {:.1}%",
+            finding.fingerprint(),
+            result.delta_percent,
+            result.before,
+            result.after,
+            delta
+        ));
+    }
+    Ok(())
+}
+
+pub fn cwe_name(id: u32) -> Option<&'static str> {
+    CWE_TABLE
+        .iter()
+        .find(|(cwe_id, _)| *cwe_id == id)
+        .map(|(_, name)| *name)
+}
+
+/// Identifiers listed in the security prompt (the CWE Top 25).
+const COMMON_CWE_IDS: &[u32] = &[
+    79, 787, 89, 352, 22, 125, 78, 416, 862, 434, 94, 20, 77, 287, 269, 502, 200, 863, 918, 119,
+    476, 798, 190, 400, 306,
+];
+
+/// Embedded table of CWE weaknesses findings may reference.
+const CWE_TABLE: &[(u32, &str)] = &[
+    (20, "Improper Input Validation"),
+    (22, "Path Traversal"),
+    (23, "Relative Path Traversal"),
+    (59, "Improper Link Resolution Before File Access"),
+    (74, "Injection"),
+    (77, "Command Injection"),
+    (78, "OS Command Injection"),
+    (79, "Cross-site Scripting"),
+    (88, "Argument Injection"),
+    (89, "SQL Injection"),
+    (90, "LDAP Injection"),
+    (91, "XML Injection"),
+    (93, "CRLF Injection"),
+    (94, "Code Injection"),
+    (95, "Eval Injection"),
+    (98, "PHP Remote File Inclusion"),
+    (113, "HTTP Response Splitting"),
+    (116, "Improper Encoding or Escaping of Output"),
+    (117, "Improper Output Neutralization for Logs"),
+    (
+        119,
+        "Improper Restriction of Operations within the Bounds of a Memory Buffer",
+    ),
+    (120, "Classic Buffer Overflow"),
+    (121, "Stack-based Buffer Overflow"),
+    (122, "Heap-based Buffer Overflow"),
+    (125, "Out-of-bounds Read"),
+    (129, "Improper Validation of Array Index"),
+    (134, "Use of Externally-Controlled Format String"),
+    (190, "Integer Overflow or Wraparound"),
+    (191, "Integer Underflow"),
+    (
+        200,
+        "Exposure of Sensitive Information to an Unauthorized Actor",
+    ),
+    (209, "Information Exposure Through an Error Message"),
+    (250, "Execution with Unnecessary Privileges"),
+    (252, "Unchecked Return Value"),
+    (256, "Plaintext Storage of a Password"),
+    (259, "Use of Hard-coded Password"),
+    (269, "Improper Privilege Management"),
+    (276, "Incorrect Default Permissions"),
+    (285, "Improper Authorization"),
+    (287, "Improper Authentication"),
+    (295, "Improper Certificate Validation"),
+    (297, "Improper Validation of Certificate with Host Mismatch"),
+    (306, "Missing Authentication for Critical Function"),
+    (
+        307,
+        "Improper Restriction of Excessive Authentication Attempts",
+    ),
+    (311, "Missing Encryption of Sensitive Data"),
+    (312, "Cleartext Storage of Sensitive Information"),
+    (319, "Cleartext Transmission of Sensitive Information"),
+    (321, "Use of Hard-coded Cryptographic Key"),
+    (322, "Key Exchange without Entity Authentication"),
+    (326, "Inadequate Encryption Strength"),
+    (327, "Use of a Broken or Risky Cryptographic Algorithm"),
+    (328, "Use of Weak Hash"),
+    (330, "Use of Insufficiently Random Values"),
+    (338, "Use of Cryptographically Weak PRNG"),
+    (345, "Insufficient Verification of Data Authenticity"),
+    (347, "Improper Verification of Cryptographic Signature"),
+    (352, "Cross-Site Request Forgery"),
+    (362, "Race Condition"),
+    (367, "Time-of-check Time-of-use Race Condition"),
+    (369, "Divide By Zero"),
+    (377, "Insecure Temporary File"),
+    (384, "Session Fixation"),
+    (400, "Uncontrolled Resource Consumption"),
+    (401, "Missing Release of Memory after Effective Lifetime"),
+    (402, "Transmission of Private Resources into a New Sphere"),
+    (404, "Improper Resource Shutdown or Release"),
+    (407, "Inefficient Algorithmic Complexity"),
+    (415, "Double Free"),
+    (416, "Use After Free"),
+    (425, "Direct Request (Forced Browsing)"),
+    (426, "Untrusted Search Path"),
+    (427, "Uncontrolled Search Path Element"),
+    (434, "Unrestricted Upload of File with Dangerous Type"),
+    (444, "HTTP Request Smuggling"),
+    (457, "Use of Uninitialized Variable"),
+    (459, "Incomplete Cleanup"),
+    (476, "NULL Pointer Dereference"),
+    (489, "Active Debug Code"),
+    (494, "Download of Code Without Integrity Check"),
+    (502, "Deserialization of Untrusted Data"),
+    (521, "Weak Password Requirements"),
+    (522, "Insufficiently Protected Credentials"),
+    (532, "Insertion of Sensitive Information into Log File"),
+    (
+        538,
+        "Insertion of Sensitive Information into Externally-Accessible File",
+    ),
+    (552, "Files or Directories Accessible to External Parties"),
+    (
+        565,
+        "Reliance on Cookies without Validation and Integrity Checking",
+    ),
+    (601, "Open Redirect"),
+    (611, "XML External Entity Reference"),
+    (613, "Insufficient Session Expiration"),
+    (614, "Sensitive Cookie Without 'Secure' Attribute"),
+    (639, "Authorization Bypass Through User-Controlled Key"),
+    (640, "Weak Password Recovery Mechanism"),
+    (643, "XPath Injection"),
+    (665, "Improper Initialization"),
+    (667, "Improper Locking"),
+    (668, "Exposure of Resource to Wrong Sphere"),
+    (670, "Always-Incorrect Control Flow Implementation"),
+    (674, "Uncontrolled Recursion"),
+    (680, "Integer Overflow to Buffer Overflow"),
+    (681, "Incorrect Conversion between Numeric Types"),
+    (682, "Incorrect Calculation"),
+    (697, "Incorrect Comparison"),
+    (703, "Improper Check or Handling of Exceptional Conditions"),
+    (704, "Incorrect Type Conversion or Cast"),
+    (732, "Incorrect Permission Assignment for Critical Resource"),
+    (754, "Improper Check for Unusual or Exceptional Conditions"),
+    (755, "Improper Handling of Exceptional Conditions"),
+    (770, "Allocation of Resources Without Limits or Throttling"),
+    (772, "Missing Release of Resource after Effective Lifetime"),
+    (776, "XML Entity Expansion"),
+    (787, "Out-of-bounds Write"),
+    (798, "Use of Hard-coded Credentials"),
+    (
+        829,
+        "Inclusion of Functionality from Untrusted Control Sphere",
+    ),
+    (833, "Deadlock"),
+    (834, "Excessive Iteration"),
+    (835, "Infinite Loop"),
+    (841, "Improper Enforcement of Behavioral Workflow"),
+    (862, "Missing Authorization"),
+    (863, "Incorrect Authorization"),
+    (908, "Use of Uninitialized Resource"),
+    (909, "Missing Initialization of Resource"),
+    (
+        913,
+        "Improper Control of Dynamically-Managed Code Resources",
+    ),
+    (
+        915,
+        "Improperly Controlled Modification of Dynamically-Determined Object Attributes",
+    ),
+    (
+        916,
+        "Use of Password Hash With Insufficient Computational Effort",
+    ),
+    (917, "Expression Language Injection"),
+    (918, "Server-Side Request Forgery"),
+    (922, "Insecure Storage of Sensitive Information"),
+    (
+        940,
+        "Improper Verification of Source of a Communication Channel",
+    ),
+    (
+        943,
+        "Improper Neutralization of Special Elements in Data Query Logic",
+    ),
+    (
+        1021,
+        "Improper Restriction of Rendered UI Layers (Clickjacking)",
+    ),
+    (1104, "Use of Unmaintained Third Party Components"),
+    (1321, "Prototype Pollution"),
+    (1333, "Inefficient Regular Expression Complexity"),
+    (1336, "Template Injection"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(file: &str, start_line: Option<u32>, end_line: Option<u32>) -> ReviewFinding {
+        ReviewFinding {
+            file: file.to_string(),
+            start_line,
+            end_line,
+            severity: FindingSeverity::Medium,
+            category: "correctness".to_string(),
+            title: "Off-by-one in the loop bound".to_string(),
+            suggestion: None,
+        }
+    }
+
+    #[test]
+    fn security_replies_are_parsed_and_validated() {
+        let reply = "Two issues.\n```json\n{\"findings\": [\
+            {\"file\": \"src/db.rs\", \"line\": 12, \"title\": \"SQL built by hand\", \
+            \"cwe\": \"CWE-89\", \"severity\": \"high\", \
+            \"exploitability\": \"Any user-supplied name reaches the query\", \
+            \"suggestion\": null}, \
+            {\"file\": \"src/api.rs\", \"line\": null, \"title\": \"Made up\", \
+            \"cwe\": \"CWE-99999\", \"severity\": \"low\", \"exploitability\": \"None\", \
+            \"suggestion\": null}]}\n```";
+        let findings = parse_security_findings(reply).unwrap().unwrap();
+        let (accepted, rejected) = validate_findings(findings);
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(accepted[0].fingerprint(), "src/db.rs:12:CWE-89");
+        assert_eq!(rejected[0].reason, "Unknown CWE identifier: CWE-99999");
+
+        assert!(parse_security_findings("No findings worth reporting.").is_none());
+        assert!(parse_security_findings("```json\n{\"issues\": []}\n```")
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn benchmark_findings_need_consistent_measurements() {
+        let reply = "Measured.\n```json\n{\"findings\": [\
+            {\"file\": \"src/lexer.rs\", \"line\": 88, \"title\": \"Clone per token\", \
+            \"benchmark\": {\"metric\": \"lex\", \"unit\": \"ns/iter\", \
+            \"before\": 1200.0, \"after\": 950.0, \"delta_percent\": -20.8}}, \
+            {\"file\": \"src/parser.rs\", \"title\": \"Quadratic lookup\"}, \
+            {\"file\": \"src/parser.rs\", \"line\": 3, \"title\": \"Units\", \
+            \"benchmark\": {\"metric\": \"parse\", \"unit\": \"ms\", \
+            \"before\": \"1.2ms\", \"after\": 0.9, \"delta_percent\": -25.0}}, \
+            {\"file\": \"src/parser.rs\", \"line\": 7, \"title\": \"Wrong delta\", \
+            \"benchmark\": {\"metric\": \"parse\", \"unit\": \"ms\", \
+            \"before\": 2.0, \"after\": 1.0, \"delta_percent\": 50.0}}, \
+            {\"file\": \"src/parser.rs\", \"line\": 9, \"title\": \"No baseline\", \
+            \"benchmark\": {\"metric\": \"parse\", \"unit\": \"ms\", \
+            \"before\": 0.0, \"after\": 1.0, \"delta_percent\": 0.0}}]}\n```";
+        let findings = parse_benchmark_findings(reply).unwrap().unwrap();
+        let (accepted, rejected) = check_benchmark_findings(findings);
+        let fingerprints: Vec<String> =
+            accepted.iter().map(BenchmarkFinding::fingerprint).collect();
+        assert_eq!(
+            fingerprints,
+            vec![
+                "src/lexer.rs:88:Clone per token",
+                "src/parser.rs:Quadratic lookup"
+            ]
+        );
+        assert_eq!(rejected.len(), 3);
+        assert!(rejected[0].starts_with("Malformed benchmark finding"));
+        assert!(rejected[1].contains("but 2 to 1 is -50.0%"));
+        assert!(rejected[2].contains("measured 0 before"));
+    }
+
+    #[test]
+    fn review_findings_need_a_place_and_a_sane_range() {
+        let (accepted, rejected) = check_review_findings(vec![
+            finding("src/parser.rs", Some(40), Some(42)),
+            finding("src/parser.rs", None, None),
+            finding("", Some(1), None),
+            finding("src/lexer.rs", Some(9), Some(3)),
+            finding("src/lexer.rs", None, Some(3)),
+        ]);
+        assert_eq!(accepted.len(), 2);
+        assert_eq!(
+            accepted[0].fingerprint(),
+            "src/parser.rs:40:Off-by-one in the loop bound"
+        );
+        assert_eq!(rejected.len(), 3);
+        assert!(rejected[1].contains("ends at line 3 before it starts at line 9"));
+    }
+}
@@ -0,0 +1,298 @@
+use crate::git_tools::{ensure_revision, run_git};
+use crate::renames;
+use crate::review::{BenchmarkFinding, ReviewFinding, SecurityFinding};
+use crate::sanitize::{self, TextKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Base a branch is reviewed against on its first review, when none is given.
+pub const DEFAULT_REVIEW_BASE: &str = "main";
+
+/// One pass over a branch: the full branch on the first review, then each follow-up push.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewRound {
+    /// Reviewed up to this commit before the round; `None` for a full review
+    pub from: Option<String>,
+    pub to: String,
+    pub commits: usize,
+    /// The branch was rewritten since the last round, so it was reviewed in full again
+    #[serde(default)]
+    pub after_force_push: bool,
+}
+
+/// Accumulated review of a branch across pushes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewReport {
+    pub id: String,
+    pub branch: String,
+    pub base: String,
+    pub last_reviewed_sha: String,
+    pub rounds: Vec<ReviewRound>,
+    /// Findings from every round, without duplicates
+    pub findings: Vec<SecurityFinding>,
+    /// Findings the model submitted with `submit_review`, from every round, without
+    /// duplicates
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub review_findings: Vec<ReviewFinding>,
+    /// Findings of a benchmarked performance review, from every round, without duplicates
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub benchmark_findings: Vec<BenchmarkFinding>,
+}
+
+impl ReviewReport {
+    /// Add findings from the latest round, skipping ones already reported at the same
+    /// place. Returns how many were new.
+    pub fn merge_findings(&mut self, findings: &[SecurityFinding]) -> usize {
+        let mut added = 0;
+        for finding in findings {
+            let duplicate = self
+                .findings
+                .iter()
+                .any(|existing| existing.fingerprint() == finding.fingerprint());
+            if !duplicate {
+                self.findings.push(finding.clone());
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// Add submitted review findings, skipping ones already reported at the same place.
+    /// Returns how many were new.
+    pub fn merge_review_findings(&mut self, findings: &[ReviewFinding]) -> usize {
+        let before = self.review_findings.len();
+        for finding in findings {
+            if !self
+                .review_findings
+                .iter()
+                .any(|existing| existing.fingerprint() == finding.fingerprint())
+            {
+                self.review_findings.push(finding.clone());
+            }
+        }
+        self.review_findings.len() - before
+    }
+
+    /// Add benchmark findings, skipping ones already reported at the same place. Returns
+    /// how many were new.
+    pub fn merge_benchmark_findings(&mut self, findings: &[BenchmarkFinding]) -> usize {
+        let before = self.benchmark_findings.len();
+        for finding in findings {
+            if !self
+                .benchmark_findings
+                .iter()
+                .any(|existing| existing.fingerprint() == finding.fingerprint())
+            {
+                self.benchmark_findings.push(finding.clone());
+            }
+        }
+        self.benchmark_findings.len() - before
+    }
+}
+
+/// What the next review of a branch covers.
+pub struct ReviewScope {
+    pub tip: String,
+    /// Reviewed commit the new changes start from; `None` means review from the base
+    pub from: Option<String>,
+    pub after_force_push: bool,
+}
+
+/// Work out what is new on `branch` since `previous` was reviewed.
+pub fn review_scope(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    branch: &str,
+    previous: Option<&ReviewReport>,
+) -> Result<ReviewScope, String> {
+    ensure_revision("branch", branch)?;
+    let tip = run_git(git_actor_id, directory, &["rev-parse", branch])?
+        .trim()
+        .to_string();
+    let previous = match previous {
+        Some(previous) => previous,
+        None => {
+            return Ok(ReviewScope {
+                tip,
+                from: None,
+                after_force_push: false,
+            })
+        }
+    };
+    // merge-base --is-ancestor exits non-zero when the old tip is gone from the branch
+    let fast_forward = run_git(
+        git_actor_id,
+        directory,
+        &[
+            "merge-base",
+            "--is-ancestor",
+            &previous.last_reviewed_sha,
+            &tip,
+        ],
+    )
+    .is_ok();
+    Ok(ReviewScope {
+        tip,
+        from: fast_forward.then(|| previous.last_reviewed_sha.clone()),
+        after_force_push: !fast_forward,
+    })
+}
+
+/// Commits, diff stat, and renames and copies in `range`, for the review message.
+pub fn fetch_range_overview(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    range: &str,
+) -> Result<(usize, String), String> {
+    let commits = run_git(git_actor_id, directory, &["log", "--format=%h %s", range])?;
+    let stat = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--stat", "-M", "-C", range],
+    )?;
+    let moves = renames::fetch(git_actor_id, directory, &[range])?;
+    let count = commits
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count();
+    Ok((
+        count,
+        format!(
+            "Commits:\n{}\n\nDiff stat:\n{}\n{}",
+            sanitize::escape(commits.trim(), TextKind::Message),
+            sanitize::escape(stat.trim(), TextKind::Message),
+            renames::render(&moves)
+        )
+        .trim_end()
+        .to_string(),
+    ))
+}
+
+/// Message asking the model to review `range`, telling it what earlier rounds found.
+pub fn review_message(
+    branch: &str,
+    range: &str,
+    overview: &str,
+    scope: &ReviewScope,
+    previous: Option<&ReviewReport>,
+) -> String {
+    let mut message = match (&scope.from, scope.after_force_push) {
+        (Some(from), _) => format!(
+            "Branch {} has new commits since the last review (reviewed up to {}). Review \
+            only the changes in {}, and say which earlier findings they address.",
+            branch, from, range
+        ),
+        (None, true) => format!(
+            "Branch {} was rewritten since the last review. Review all of {} again, and say \
+            which earlier findings still apply.",
+            branch, range
+        ),
+        (None, false) => format!("Please review branch {} ({}).", branch, range),
+    };
+    message.push_str(&format!("\n\n{}", overview));
+
+    if let Some(previous) = previous
+        .filter(|previous| !previous.findings.is_empty() || !previous.review_findings.is_empty())
+    {
+        message.push_str("\n\nEARLIER FINDINGS (don't report these again):");
+        for finding in &previous.findings {
+            let location = match finding.line {
+                Some(line) => format!("{}:{}", finding.file, line),
+                None => finding.file.clone(),
+            };
+            message.push_str(&format!(
+                "\n- {} {} ({})",
+                location, finding.title, finding.cwe
+            ));
+        }
+        for finding in &previous.review_findings {
+            let location = match finding.start_line {
+                Some(line) => format!("{}:{}", finding.file, line),
+                None => finding.file.clone(),
+            };
+            message.push_str(&format!(
+                "\n- {} {} ({})",
+                location, finding.title, finding.category
+            ));
+        }
+    }
+    message
+}
+
+/// How `PublishReview` posts a report's findings.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PublishMode {
+    /// A comment on each finding's line; findings without a line go in a general comment
+    Inline,
+    /// All findings in one general comment
+    Summary,
+    /// Build the comments without posting or recording anything
+    DryRun,
+}
+
+/// A comment for one finding, as posted to the forge.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewComment {
+    /// [`SecurityFinding::fingerprint`] of the finding, recorded once posted
+    pub fingerprint: String,
+    pub path: String,
+    /// Line to comment on; `None` for a general comment
+    pub line: Option<u32>,
+    pub body: String,
+}
+
+impl ReviewComment {
+    pub fn new(finding: &SecurityFinding, mode: PublishMode) -> Self {
+        let mut body = format!(
+            "**{}: {}** ({})\n\n{}",
+            finding.severity, finding.title, finding.cwe, finding.exploitability
+        );
+        if let Some(suggestion) = &finding.suggestion {
+            body.push_str(&format!("\n\nSuggestion: {}", suggestion));
+        }
+        Self {
+            fingerprint: finding.fingerprint(),
+            path: finding.file.clone(),
+            line: finding.line.filter(|_| mode != PublishMode::Summary),
+            body,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishResult {
+    pub report_id: String,
+    pub mode: PublishMode,
+    /// Pull request number (merge request iid) posted to; `None` for a dry run
+    pub change_request: Option<u64>,
+    /// Comments posted, or that would be posted in a dry run
+    pub comments: Vec<ReviewComment>,
+    /// Findings skipped because an earlier `PublishReview` posted them
+    pub already_posted: usize,
+    /// Findings left over the comment limit, posted by the next `PublishReview`
+    pub remaining: usize,
+}
+
+/// Comments for the findings of `report` not in `posted`, at most `limit`. Returns the
+/// comments, how many findings were already posted, and how many are left over.
+pub fn pending_comments(
+    report: &ReviewReport,
+    posted: &BTreeSet<String>,
+    mode: PublishMode,
+    limit: usize,
+) -> (Vec<ReviewComment>, usize, usize) {
+    let pending: Vec<&SecurityFinding> = report
+        .findings
+        .iter()
+        .filter(|finding| !posted.contains(&finding.fingerprint()))
+        .collect();
+    let already_posted = report.findings.len() - pending.len();
+    let remaining = pending.len().saturating_sub(limit);
+    let comments = pending
+        .into_iter()
+        .take(limit)
+        .map(|finding| ReviewComment::new(finding, mode))
+        .collect();
+    (comments, already_posted, remaining)
+}
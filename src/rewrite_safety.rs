@@ -0,0 +1,134 @@
+use crate::git_tools::{ensure_revision, run_git};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Published commits whose remote branches are looked up; the rest are only listed.
+const MAX_BRANCH_LOOKUPS: usize = 20;
+
+/// A commit that would be rewritten but already exists on a remote-tracking branch.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishedCommit {
+    pub sha: String,
+    pub subject: String,
+    /// Remote-tracking branches containing the commit; empty past the lookup limit
+    pub remote_branches: Vec<String>,
+}
+
+/// Which of the commits in a range are already published.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RewriteAnalysis {
+    pub range: String,
+    pub commits_checked: usize,
+    /// Newest first
+    pub published: Vec<PublishedCommit>,
+}
+
+impl RewriteAnalysis {
+    /// Whether the range can be rewritten without force-pushing over published work.
+    pub fn is_safe(&self) -> bool {
+        self.published.is_empty()
+    }
+
+    pub fn describe(&self) -> String {
+        let listed: Vec<String> = self
+            .published
+            .iter()
+            .map(|commit| {
+                let on = if commit.remote_branches.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (on {})", commit.remote_branches.join(", "))
+                };
+                format!("{} {}{}", short_sha(&commit.sha), commit.subject, on)
+            })
+            .collect();
+        format!(
+            "{} of {} commits in {} are already on a remote: {}",
+            self.published.len(),
+            self.commits_checked,
+            self.range,
+            listed.join("; ")
+        )
+    }
+}
+
+fn short_sha(sha: &str) -> &str {
+    sha.get(..8).unwrap_or(sha)
+}
+
+/// Find the commits in `range` (a `git log` revision range) that remote-tracking
+/// branches already contain. The whole range is walked, even in performance mode, so
+/// a published commit past the history bounds can't make the rewrite look safe.
+pub fn analyze_rewrite(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    range: &str,
+) -> Result<RewriteAnalysis, String> {
+    ensure_revision("range", range)?;
+    let commits = run_git(
+        git_actor_id,
+        directory,
+        &["log", "--format=%H%x1f%s", range],
+    )?;
+
+    let unpublished: BTreeSet<String> = run_git(
+        git_actor_id,
+        directory,
+        &["rev-list", range, "--not", "--remotes"],
+    )?
+    .lines()
+    .map(|sha| sha.trim().to_string())
+    .collect();
+
+    let mut commits_checked = 0;
+    let mut published = Vec::new();
+    for line in commits.lines() {
+        let (sha, subject) = match line.split_once('\u{1f}') {
+            Some((sha, subject)) => (sha.trim(), subject),
+            None => continue,
+        };
+        commits_checked += 1;
+        if unpublished.contains(sha) {
+            continue;
+        }
+        let remote_branches = if published.len() < MAX_BRANCH_LOOKUPS {
+            run_git(
+                git_actor_id,
+                directory,
+                &[
+                    "branch",
+                    "--remotes",
+                    "--contains",
+                    sha,
+                    "--format=%(refname:short)",
+                ],
+            )?
+            .lines()
+            .map(str::trim)
+            .filter(|branch| !branch.is_empty())
+            .map(str::to_string)
+            .collect()
+        } else {
+            Vec::new()
+        };
+        published.push(PublishedCommit {
+            sha: sha.to_string(),
+            subject: subject.to_string(),
+            remote_branches,
+        });
+    }
+
+    Ok(RewriteAnalysis {
+        range: range.to_string(),
+        commits_checked,
+        published,
+    })
+}
+
+/// System prompt rule keeping the model from amending or rewriting published commits.
+pub fn prompt_fragment() -> &'static str {
+    "\n\nPUBLISHED HISTORY: Never amend, rebase, squash, or filter commits that are \
+    already on a remote-tracking branch. Before rewriting, check with \
+    git branch --remotes --contains <commit>; if any commit is published, stop and \
+    explain instead. The user can allow this with allow_published_rewrite."
+}
@@ -0,0 +1,96 @@
+//! Safe mode: when the init config can't be parsed or validated, the assistant starts
+//! anyway, from a minimal config with read-only git tools, and refuses every request
+//! that could change something. `UpdateConfig` then takes a merge patch of the init
+//! config, and once the patched config is valid the assistant starts over from it.
+
+use crate::command_policy::{CommandPolicy, CommandRules};
+use crate::confirmation::{self, Severity};
+use crate::protocol::external::GitChatRequest;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Git subcommands the model may run in safe mode.
+const READ_ONLY_SUBCOMMANDS: &[&str] = &["status", "log", "diff", "show", "blame"];
+
+/// Fields of the init config kept in safe mode, when they are strings: where the
+/// assistant runs and who hears about it.
+const KEPT_FIELDS: &[&str] = &["current_directory", "parent_actor_id", "reply_to"];
+
+/// Why the assistant is in safe mode, kept in state until `UpdateConfig` leaves it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SafeMode {
+    /// The error the init config failed with
+    pub error: String,
+    /// The init config that failed, which `UpdateConfig` patches
+    pub raw_config: Value,
+    pub entered_at: u64,
+}
+
+/// The config the assistant starts from in safe mode.
+pub fn config(raw_config: &Value) -> Value {
+    let mut config = Map::new();
+    for field in KEPT_FIELDS {
+        if let Some(value) = raw_config.get(*field).filter(|value| value.is_string()) {
+            config.insert(field.to_string(), value.clone());
+        }
+    }
+    let policy = CommandPolicy {
+        rules: CommandRules {
+            allowed: Some(
+                READ_ONLY_SUBCOMMANDS
+                    .iter()
+                    .map(|subcommand| subcommand.to_string())
+                    .collect(),
+            ),
+            denied: Vec::new(),
+        },
+        ..CommandPolicy::default()
+    };
+    config.insert(
+        "command_policy".to_string(),
+        serde_json::to_value(policy).unwrap_or_default(),
+    );
+    Value::Object(config)
+}
+
+/// Whether `request` is served in safe mode: it only reads, or manages confirmations
+/// and turns. Workflows count as changes, so none is started.
+pub fn allows(request: &GitChatRequest) -> bool {
+    !confirmation::always_confirmed(request)
+        && confirmation::classify(request)
+            .is_none_or(|(_, severity)| severity == Severity::ReadOnly)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::Workflow;
+    use serde_json::json;
+
+    #[test]
+    fn safe_config_keeps_only_where_and_who() {
+        let raw = json!({
+            "current_directory": "/repo",
+            "parent_actor_id": "actor-1",
+            "reply_to": 7,
+            "workflow": "commit",
+            "max_tokens": "lots"
+        });
+        let config = config(&raw);
+        assert_eq!(config["current_directory"], "/repo");
+        assert_eq!(config["parent_actor_id"], "actor-1");
+        assert!(config.get("reply_to").is_none());
+        assert!(config.get("workflow").is_none());
+        let policy: CommandPolicy =
+            serde_json::from_value(config["command_policy"].clone()).unwrap();
+        assert!(policy.permits(None, "diff"));
+        assert!(!policy.permits(None, "commit"));
+
+        assert!(allows(&GitChatRequest::GetStatus));
+        assert!(!allows(&GitChatRequest::SetWorkflow {
+            workflow: Workflow::Commit,
+            start: true,
+        }));
+        assert!(!allows(&GitChatRequest::ExecuteRelease { message: None }));
+    }
+}
@@ -0,0 +1,181 @@
+//! Detection of characters that hide or reorder text (bidi controls, zero-width and
+//! tag characters) in content that ends up in git or in the model's context.
+
+use crate::command_policy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Where the checked text is used; messages may span lines, names and paths may not.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextKind {
+    Message,
+    Name,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HiddenCharacter {
+    /// Character offset in the checked text
+    pub position: usize,
+    /// Code point as `U+XXXX`
+    pub code_point: String,
+    pub category: String,
+}
+
+fn classify(c: char, kind: TextKind) -> Option<&'static str> {
+    match c {
+        '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => Some("bidi override"),
+        '\u{200E}' | '\u{200F}' | '\u{061C}' => Some("bidi mark"),
+        '\u{200B}'..='\u{200D}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}' | '\u{180E}' => {
+            Some("zero-width")
+        }
+        '\u{00AD}' | '\u{034F}' | '\u{115F}' | '\u{1160}' | '\u{3164}' | '\u{FFA0}' => {
+            Some("invisible filler")
+        }
+        '\u{E0000}'..='\u{E007F}' => Some("tag character"),
+        '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}' => match kind {
+            // Variation selectors are legitimate in emoji (gitmoji) in messages
+            TextKind::Message => None,
+            TextKind::Name => Some("variation selector"),
+        },
+        '\n' | '\t' | '\r' if kind == TextKind::Message => None,
+        c if c.is_control() => Some("control character"),
+        _ => None,
+    }
+}
+
+/// Every hidden or reordering character in `text`.
+pub fn find_hidden(text: &str, kind: TextKind) -> Vec<HiddenCharacter> {
+    text.chars()
+        .enumerate()
+        .filter_map(|(position, c)| {
+            classify(c, kind).map(|category| HiddenCharacter {
+                position,
+                code_point: format!("U+{:04X}", c as u32),
+                category: category.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `text` with hidden characters removed.
+pub fn strip(text: &str, kind: TextKind) -> String {
+    text.chars()
+        .filter(|c| classify(*c, kind).is_none())
+        .collect()
+}
+
+/// `text` with hidden characters replaced by a visible `<U+XXXX>` marker, so a path
+/// stays identifiable without being able to spoof what surrounds it.
+pub fn escape(text: &str, kind: TextKind) -> String {
+    text.chars()
+        .map(|c| match classify(c, kind) {
+            Some(_) => format!("<U+{:04X}>", c as u32),
+            None => c.to_string(),
+        })
+        .collect()
+}
+
+/// One-line description of the hidden characters found.
+pub fn describe(hidden: &[HiddenCharacter]) -> String {
+    let listed: Vec<String> = hidden
+        .iter()
+        .map(|found| {
+            format!(
+                "{} ({}) at {}",
+                found.code_point, found.category, found.position
+            )
+        })
+        .collect();
+    format!("{} hidden characters: {}", hidden.len(), listed.join(", "))
+}
+
+/// Reject `text` when it contains hidden characters.
+pub fn ensure_visible(text: &str, kind: TextKind) -> Result<(), String> {
+    let hidden = find_hidden(text, kind);
+    if hidden.is_empty() {
+        Ok(())
+    } else {
+        Err(describe(&hidden))
+    }
+}
+
+/// Reject a model's git tool call that writes hidden characters into history or a ref
+/// name: every string in the `args` of a commit, or of a call that can create a branch.
+pub fn check_git_tool_call(name: &str, args: &Value) -> Result<(), String> {
+    let kind = match command_policy::tool_subcommand(name, Some(args)).as_deref() {
+        Some("commit") => TextKind::Message,
+        Some("branch" | "checkout" | "switch") => TextKind::Name,
+        _ => return Ok(()),
+    };
+    let mut pending = vec![args];
+    while let Some(value) = pending.pop() {
+        match value {
+            Value::String(text) => ensure_visible(text, kind)?,
+            Value::Array(values) => pending.extend(values),
+            Value::Object(fields) => pending.extend(fields.values()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn hidden_characters_are_found_by_kind() {
+        let hidden = find_hidden("fix\u{202E}txt.exe\u{200B}", TextKind::Message);
+        let found: Vec<(usize, &str, &str)> = hidden
+            .iter()
+            .map(|found| {
+                (
+                    found.position,
+                    found.code_point.as_str(),
+                    found.category.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![(3, "U+202E", "bidi override"), (11, "U+200B", "zero-width")]
+        );
+        // Line breaks and emoji variation selectors only belong in messages
+        assert!(
+            find_hidden("feat: \u{2728}\u{FE0F} sparkle\n\nbody", TextKind::Message).is_empty()
+        );
+        assert_eq!(classify('\n', TextKind::Name), Some("control character"));
+        assert_eq!(
+            classify('\u{FE0F}', TextKind::Name),
+            Some("variation selector")
+        );
+        assert_eq!(
+            classify('\u{E0041}', TextKind::Message),
+            Some("tag character")
+        );
+        assert_eq!(escape("a\u{200F}b", TextKind::Name), "a<U+200F>b");
+        assert_eq!(strip("a\u{200F}b", TextKind::Name), "ab");
+    }
+
+    #[test]
+    fn commit_and_branch_tool_calls_are_checked() {
+        assert!(
+            check_git_tool_call("git_commit", &json!({"message": "fix: tabs\n\nbody"})).is_ok()
+        );
+        assert!(
+            check_git_tool_call("git_commit", &json!({"message": "fix\u{202E}: tabs"})).is_err()
+        );
+        assert!(
+            check_git_tool_call("git", &json!({"args": ["commit", "-m", "fix\u{200B}"]})).is_err()
+        );
+        assert!(check_git_tool_call(
+            "git",
+            &json!({"args": ["switch", "-c", "feature/x\u{2066}y"]})
+        )
+        .is_err());
+        assert!(check_git_tool_call("git_branch", &json!({"name": "feature\nx"})).is_err());
+        // Other calls only read what is already there
+        assert!(check_git_tool_call("git_log", &json!({"args": ["--grep", "\u{200B}"]})).is_ok());
+    }
+}
@@ -0,0 +1,268 @@
+//! Workflows started on a schedule, e.g. a nightly commit of a scratch repository or a
+//! weekly branch cleanup. `Tick` drives them, like the digest: each entry of the
+//! `schedule` config runs when a tick arrives at or after its next run.
+
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+
+/// One entry of the `schedule` config; it sets either `every_minutes` or `cron`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScheduleEntry {
+    pub workflow: Workflow,
+    /// Run this many minutes after the last run, and at the first tick
+    #[serde(default)]
+    pub every_minutes: Option<u64>,
+    /// `minute hour day-of-month month day-of-week` in UTC, with `*`, lists, ranges,
+    /// and steps, e.g. `0 2 * * *` for 02:00 every night
+    #[serde(default)]
+    pub cron: Option<String>,
+}
+
+/// What happened to an entry of the schedule, exposed in `GetStatus`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScheduledRun {
+    pub workflow: Workflow,
+    /// Seconds since the epoch
+    pub last_run_at: Option<u64>,
+    /// Unset when the entry is invalid or its cron expression never matches
+    pub next_run_at: Option<u64>,
+    /// `started`, the pending confirmation, or the error of the last run
+    pub last_outcome: Option<String>,
+}
+
+/// Allowed values of one cron field, as a bit per value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CronField(u64);
+
+impl CronField {
+    fn parse(field: &str, min: u32, max: u32) -> Result<Self, String> {
+        let mut bits = 0u64;
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (
+                    range,
+                    step.parse::<u32>()
+                        .ok()
+                        .filter(|step| *step > 0)
+                        .ok_or_else(|| format!("invalid step in {}", part))?,
+                ),
+                None => (part, 1),
+            };
+            let value = |text: &str| {
+                text.parse::<u32>()
+                    .ok()
+                    .filter(|value| (min..=max).contains(value))
+                    .ok_or_else(|| format!("{} is not between {} and {}", text, min, max))
+            };
+            let (start, end) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((start, end)) => (value(start)?, value(end)?),
+                    // `5/15` runs from 5 to the end
+                    None if step > 1 => (value(range)?, max),
+                    None => (value(range)?, value(range)?),
+                },
+            };
+            if start > end {
+                return Err(format!("empty range {}", range));
+            }
+            for value in (start..=end).step_by(step as usize) {
+                bits |= 1 << value;
+            }
+        }
+        Ok(CronField(bits))
+    }
+
+    fn matches(self, value: u32) -> bool {
+        self.0 & (1 << value) != 0
+    }
+}
+
+/// A parsed cron expression.
+#[derive(Debug, Clone, PartialEq)]
+struct Cron {
+    minute: CronField,
+    hour: CronField,
+    day: CronField,
+    month: CronField,
+    weekday: CronField,
+    /// Whether day-of-month and day-of-week were both restricted, in which case either
+    /// may match, as in cron
+    either_day: bool,
+}
+
+impl Cron {
+    fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "cron expression {:?} needs 5 fields, not {}",
+                expression,
+                fields.len()
+            ));
+        };
+        let mut weekday = CronField::parse(weekday, 0, 7)?;
+        // 7 is Sunday too
+        if weekday.matches(7) {
+            weekday.0 |= 1;
+        }
+        Ok(Cron {
+            minute: CronField::parse(minute, 0, 59)?,
+            hour: CronField::parse(hour, 0, 23)?,
+            day: CronField::parse(day, 1, 31)?,
+            month: CronField::parse(month, 1, 12)?,
+            weekday,
+            either_day: day != "*" && fields[4] != "*",
+        })
+    }
+
+    fn matches_day(&self, date: &Date) -> bool {
+        if !self.month.matches(date.month) {
+            return false;
+        }
+        let (day, weekday) = (
+            self.day.matches(date.day),
+            self.weekday.matches(date.weekday),
+        );
+        if self.either_day {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    /// The first matching minute after `after`, looking up to five years ahead.
+    fn next_after(&self, after: u64) -> Option<u64> {
+        let mut minute = after / 60 + 1;
+        let limit = minute + 5 * 366 * 24 * 60;
+        while minute < limit {
+            let days = minute / (24 * 60);
+            if !self.matches_day(&Date::from_days(days)) {
+                minute = (days + 1) * 24 * 60;
+                continue;
+            }
+            let (hour, minute_of_hour) = ((minute / 60 % 24) as u32, (minute % 60) as u32);
+            if !self.hour.matches(hour) {
+                minute = (minute / 60 + 1) * 60;
+                continue;
+            }
+            if self.minute.matches(minute_of_hour) {
+                return Some(minute * 60);
+            }
+            minute += 1;
+        }
+        None
+    }
+}
+
+struct Date {
+    month: u32,
+    day: u32,
+    /// 0 for Sunday
+    weekday: u32,
+}
+
+impl Date {
+    /// The UTC date `days` days after 1970-01-01.
+    fn from_days(days: u64) -> Self {
+        // Howard Hinnant's civil_from_days, for dates after the epoch
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let day_of_era = z - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        Date {
+            month: if mp < 10 { mp + 3 } else { mp - 9 } as u32,
+            day: (day_of_year - (153 * mp + 2) / 5 + 1) as u32,
+            weekday: ((days + 4) % 7) as u32,
+        }
+    }
+}
+
+impl ScheduleEntry {
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.every_minutes, &self.cron) {
+            (Some(0), None) => Err("every_minutes must be at least 1".to_string()),
+            (Some(_), None) => Ok(()),
+            (None, Some(cron)) => Cron::parse(cron).map(|_| ()),
+            (Some(_), Some(_)) => Err("set either every_minutes or cron, not both".to_string()),
+            (None, None) => Err("set every_minutes or cron".to_string()),
+        }
+    }
+
+    /// When the entry runs next, given its last run and the time of the current tick.
+    pub fn next_run(&self, last_run_at: Option<u64>, now: u64) -> Option<u64> {
+        self.validate().ok()?;
+        match (self.every_minutes, &self.cron) {
+            (Some(minutes), _) => Some(last_run_at.map_or(now, |last| last + minutes * 60)),
+            (None, Some(cron)) => Cron::parse(cron)
+                .ok()?
+                .next_after(last_run_at.unwrap_or(now.saturating_sub(60))),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Keep `runs` in line with `entries`, one run per entry, starting over for entries
+/// that changed.
+pub fn sync(entries: &[ScheduleEntry], runs: &mut Vec<ScheduledRun>) {
+    runs.truncate(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        match runs.get_mut(index) {
+            Some(run) if run.workflow == entry.workflow => {}
+            Some(run) => *run = ScheduledRun::new(entry),
+            None => runs.push(ScheduledRun::new(entry)),
+        }
+    }
+}
+
+impl ScheduledRun {
+    fn new(entry: &ScheduleEntry) -> Self {
+        ScheduledRun {
+            workflow: entry.workflow.clone(),
+            last_run_at: None,
+            next_run_at: None,
+            last_outcome: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(every_minutes: Option<u64>, cron: Option<&str>) -> ScheduleEntry {
+        ScheduleEntry {
+            workflow: Workflow::Commit,
+            every_minutes,
+            cron: cron.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn intervals_and_cron_expressions_give_the_next_run() {
+        let nightly = entry(Some(24 * 60), None);
+        assert_eq!(nightly.next_run(None, 1_000), Some(1_000));
+        assert_eq!(nightly.next_run(Some(1_000), 2_000), Some(1_000 + 86_400));
+
+        // 2025-10-09 is a Thursday; 1760000000 is 08:53:20 UTC that day
+        let now = 1_760_000_000;
+        let at_two = entry(None, Some("0 2 * * *"));
+        assert_eq!(at_two.next_run(None, now), Some(1_760_061_600));
+        let mondays = entry(None, Some("30 9 * * 1"));
+        assert_eq!(mondays.next_run(None, now), Some(1_760_347_800));
+        let quarter_hours = entry(None, Some("*/15 8-9 * * 1-5"));
+        assert_eq!(quarter_hours.next_run(None, now), Some(1_760_000_400));
+        assert_eq!(
+            quarter_hours.next_run(Some(1_760_000_400), now),
+            Some(1_760_001_300)
+        );
+
+        assert!(entry(None, Some("0 2 * *")).validate().is_err());
+        assert!(entry(None, Some("61 * * * *")).validate().is_err());
+        assert!(entry(Some(5), Some("* * * * *")).validate().is_err());
+        assert_eq!(entry(None, Some("0 0 31 2 *")).next_run(None, now), None);
+    }
+}
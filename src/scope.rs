@@ -0,0 +1,69 @@
+//! Limits the assistant to some subdirectories of a monorepo, set with `scope_paths`.
+
+use crate::git_tools::run_git;
+
+/// Scope paths as git prints them: relative to the repository root, without `./` or
+/// a trailing slash. Empty entries and `.` mean the whole repository.
+pub fn normalize(scope_paths: &[String]) -> Vec<String> {
+    scope_paths
+        .iter()
+        .map(|path| {
+            path.trim()
+                .trim_start_matches("./")
+                .trim_end_matches('/')
+                .to_string()
+        })
+        .collect()
+}
+
+/// Whether `file`, relative to the repository root, is under one of `scope`.
+pub fn contains(scope: &[String], file: &str) -> bool {
+    scope.iter().any(|path| {
+        path.is_empty()
+            || path == "."
+            || file == path
+            || file
+                .strip_prefix(path.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+/// Configured paths that can't name a subdirectory of the repository.
+pub fn invalid_paths(scope_paths: &[String]) -> Vec<&str> {
+    scope_paths
+        .iter()
+        .map(String::as_str)
+        .filter(|path| path.starts_with('/') || path.split('/').any(|part| part == ".."))
+        .collect()
+}
+
+/// System prompt rule confining the model to `scope`.
+pub fn prompt_fragment(scope: &[String]) -> String {
+    format!(
+        "\n\nSCOPE: This is a monorepo, and you only work on these paths, relative to the \
+        repository root: {}. Leave changes elsewhere alone: don't stage, commit, or discard \
+        them, and pass these paths to git status, diff, and log so their output only covers \
+        the scope. The commit workflow rejects commits that touch files outside it.",
+        scope.join(", ")
+    )
+}
+
+/// Files changed between `base` and HEAD that are outside `scope`.
+pub fn files_outside(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: &str,
+    scope: &[String],
+) -> Result<Vec<String>, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--name-only", "--no-renames", base, "HEAD"],
+    )?;
+    Ok(output
+        .lines()
+        .map(str::trim)
+        .filter(|file| !file.is_empty() && !contains(scope, file))
+        .map(str::to_string)
+        .collect())
+}
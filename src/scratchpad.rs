@@ -0,0 +1,118 @@
+//! Per-session scratchpads: notes the model keeps for itself, like a todo list, in a
+//! `<scratchpad>` block of its reply. The latest block replaces the session's
+//! scratchpad, and exported transcripts leave the blocks out.
+
+use crate::protocol::HistoryMessage;
+use genai_types::MessageContent;
+use serde::{Deserialize, Serialize};
+
+const OPEN: &str = "<scratchpad>";
+const CLOSE: &str = "</scratchpad>";
+
+/// Longest scratchpad kept, in bytes; the rest is cut off.
+pub const MAX_BYTES: usize = 8 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Scratchpad {
+    pub content: String,
+    /// Seconds since the epoch of the last write
+    pub updated_at: u64,
+    /// Writes so far, an empty block included
+    pub revisions: u32,
+    /// Whether the last write was longer than `MAX_BYTES`
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+impl Scratchpad {
+    /// The scratchpad after the model wrote `content`, which replaces what it held.
+    pub fn write(previous: Option<&Scratchpad>, content: &str, now: u64) -> Self {
+        let mut content = content.trim().to_string();
+        let truncated = content.len() > MAX_BYTES;
+        if truncated {
+            let mut end = MAX_BYTES;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content.truncate(end);
+        }
+        Self {
+            content,
+            updated_at: now,
+            revisions: previous.map_or(0, |previous| previous.revisions) + 1,
+            truncated,
+        }
+    }
+}
+
+/// Content of the last complete scratchpad block in `reply`, if there is one.
+pub fn parse(reply: &str) -> Option<&str> {
+    let start = reply.rfind(OPEN)? + OPEN.len();
+    let end = reply[start..].find(CLOSE)?;
+    Some(reply[start..start + end].trim())
+}
+
+/// `text` without its scratchpad blocks. An unclosed block is kept.
+pub fn strip(text: &str) -> String {
+    let mut stripped = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+        let Some(end) = rest[start..].find(CLOSE) else {
+            break;
+        };
+        stripped.push_str(rest[..start].trim_end_matches(' '));
+        rest = &rest[start + end + CLOSE.len()..];
+    }
+    stripped.push_str(rest);
+    stripped.trim().to_string()
+}
+
+/// Drop scratchpad blocks from the text of `messages`, and the text parts left empty.
+pub fn strip_messages(messages: &mut [HistoryMessage]) {
+    for entry in messages {
+        for content in &mut entry.message.content {
+            if let MessageContent::Text { text } = content {
+                if text.contains(OPEN) {
+                    *text = strip(text);
+                }
+            }
+        }
+        entry
+            .message
+            .content
+            .retain(|content| !matches!(content, MessageContent::Text { text } if text.is_empty()));
+    }
+}
+
+/// System prompt rule telling the model how to keep its scratchpad.
+pub fn prompt_fragment() -> &'static str {
+    "\n\nSCRATCHPAD: You have a scratchpad for notes to yourself, such as a todo list for \
+    a long task. To write it, put the whole new content between <scratchpad> and \
+    </scratchpad> in your reply; it replaces what the scratchpad held, and the user \
+    doesn't see it. Write it only when it changes, and keep it short. An empty block \
+    clears it."
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_last_block_is_the_scratchpad_and_blocks_are_stripped() {
+        let reply = "Staged the parser.\n<scratchpad>\n- [x] parser\n- [ ] docs\n</scratchpad>\nNext: docs.";
+        assert_eq!(parse(reply), Some("- [x] parser\n- [ ] docs"));
+        assert_eq!(strip(reply), "Staged the parser.\n\nNext: docs.");
+        assert_eq!(parse("<scratchpad>unclosed"), None);
+        assert_eq!(
+            strip("Kept <scratchpad>unclosed"),
+            "Kept <scratchpad>unclosed"
+        );
+
+        let first = Scratchpad::write(None, " todo ", 10);
+        let second = Scratchpad::write(Some(&first), &"x".repeat(MAX_BYTES + 1), 20);
+        assert_eq!(first.content, "todo");
+        assert_eq!(second.revisions, 2);
+        assert!(second.truncated);
+        assert_eq!(second.content.len(), MAX_BYTES);
+    }
+}
@@ -0,0 +1,243 @@
+//! Regex-based detection of credentials in diffs, so they are caught before they are
+//! committed rather than after they are pushed.
+
+use crate::git_tools::run_git;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The `secret_scan` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SecretScanConfig {
+    /// Scanning is on for the commit workflow unless disabled here
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Regexes for matches known not to be secrets, e.g. keys in test fixtures
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for SecretScanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            allow: Vec::new(),
+        }
+    }
+}
+
+/// Something in a diff that looks like a credential.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SecretFinding {
+    pub file: String,
+    /// Line in the new version of the file; `None` for files flagged by name
+    pub line: Option<u32>,
+    pub rule: String,
+    /// The start of the match, with the rest masked
+    pub excerpt: String,
+}
+
+/// Rule name and pattern, matched against added lines.
+const RULES: &[(&str, &str)] = &[
+    ("aws-access-key-id", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    (
+        "aws-secret-access-key",
+        r#"(?i)aws_?secret_?access_?key["']?\s*[:=]\s*["']?[A-Za-z0-9/+=]{40}"#,
+    ),
+    ("private-key", r"-----BEGIN (?:[A-Z]+ )*PRIVATE KEY-----"),
+    (
+        "github-token",
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})\b",
+    ),
+    ("gitlab-token", r"\bglpat-[A-Za-z0-9_-]{20,}"),
+    ("slack-token", r"\bxox[abposr]-[A-Za-z0-9-]{10,}"),
+    (
+        "generic-secret",
+        r#"(?i)(?:api[_-]?key|secret|token|password|passwd)["']?\s*[:=]\s*["'][^"'\s]{12,}["']"#,
+    ),
+];
+
+/// Files that usually hold credentials, flagged by name alone.
+const SENSITIVE_FILE: &str =
+    r"(?:^|/)(?:\.env(?:\.[\w.-]+)?|id_(?:rsa|dsa|ecdsa|ed25519)|[^/]+\.(?:pem|p12|pfx|key))$";
+
+/// Suffixes of sample files that are meant to be committed, e.g. `.env.example`.
+const SAMPLE_SUFFIXES: &[&str] = &[".example", ".sample", ".template"];
+
+pub struct SecretScanner {
+    rules: Vec<(&'static str, Regex)>,
+    sensitive_file: Regex,
+    allow: Vec<Regex>,
+}
+
+impl SecretScanner {
+    pub fn new(config: &SecretScanConfig) -> Result<Self, String> {
+        let rules = RULES
+            .iter()
+            .map(|(name, pattern)| {
+                Regex::new(pattern)
+                    .map(|regex| (*name, regex))
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Result<_, _>>()?;
+        let allow = config
+            .allow
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| format!("invalid secret_scan.allow pattern {}: {}", pattern, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            rules,
+            sensitive_file: Regex::new(SENSITIVE_FILE).map_err(|e| e.to_string())?,
+            allow,
+        })
+    }
+
+    /// Findings on the lines a unified diff adds.
+    pub fn scan_diff(&self, diff: &str) -> Vec<SecretFinding> {
+        let mut findings = Vec::new();
+        let mut file: Option<String> = None;
+        let mut line = 0u32;
+        for text in diff.lines() {
+            if let Some(path) = text.strip_prefix("+++ ") {
+                file = match path {
+                    "/dev/null" => None,
+                    path => Some(path.strip_prefix("b/").unwrap_or(path).to_string()),
+                };
+                if let Some(file) = &file {
+                    findings.extend(self.scan_path(file));
+                }
+                continue;
+            }
+            if text.starts_with("diff --git") {
+                file = None;
+                continue;
+            }
+            if let Some(hunk) = text.strip_prefix("@@ ") {
+                // "@@ -a,b +c,d @@": added lines are numbered from c
+                line = hunk
+                    .split_whitespace()
+                    .find_map(|range| range.strip_prefix('+'))
+                    .and_then(|range| range.split(',').next())
+                    .and_then(|start| start.parse().ok())
+                    .unwrap_or(1);
+                continue;
+            }
+            let file = match &file {
+                Some(file) => file,
+                None => continue,
+            };
+            if let Some(added) = text.strip_prefix('+') {
+                for (rule, regex) in &self.rules {
+                    for found in regex.find_iter(added) {
+                        if self.is_allowed(found.as_str()) {
+                            continue;
+                        }
+                        findings.push(SecretFinding {
+                            file: file.clone(),
+                            line: Some(line),
+                            rule: rule.to_string(),
+                            excerpt: mask(found.as_str()),
+                        });
+                    }
+                }
+                line += 1;
+            } else if !text.starts_with('-') && !text.starts_with('\\') {
+                line += 1;
+            }
+        }
+        findings
+    }
+
+    /// A finding when `path` names a file that usually holds credentials.
+    pub fn scan_path(&self, path: &str) -> Option<SecretFinding> {
+        if !self.sensitive_file.is_match(path)
+            || SAMPLE_SUFFIXES.iter().any(|suffix| path.ends_with(suffix))
+            || self.is_allowed(path)
+        {
+            return None;
+        }
+        Some(SecretFinding {
+            file: path.to_string(),
+            line: None,
+            rule: "sensitive-file".to_string(),
+            excerpt: path.rsplit('/').next().unwrap_or(path).to_string(),
+        })
+    }
+
+    fn is_allowed(&self, text: &str) -> bool {
+        self.allow.iter().any(|allow| allow.is_match(text))
+    }
+}
+
+/// The first four characters of `secret`, then asterisks.
+fn mask(secret: &str) -> String {
+    let start: String = secret.chars().take(4).collect();
+    format!(
+        "{}{}",
+        start,
+        "*".repeat(secret.chars().count().saturating_sub(4).min(16))
+    )
+}
+
+/// Findings in the changes not yet committed: the diff against `HEAD` and the names of
+/// untracked files.
+pub fn scan_pending_changes(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    scanner: &SecretScanner,
+) -> Result<Vec<SecretFinding>, String> {
+    let diff = run_git(git_actor_id, directory, &["diff", "HEAD"])?;
+    let mut findings = scanner.scan_diff(&diff);
+    let untracked = run_git(
+        git_actor_id,
+        directory,
+        &["ls-files", "--others", "--exclude-standard"],
+    )?;
+    findings.extend(
+        untracked
+            .lines()
+            .filter_map(|path| scanner.scan_path(path.trim())),
+    );
+    Ok(findings)
+}
+
+/// Findings in every commit after `base`, including secrets a later commit removed.
+pub fn scan_commits(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: &str,
+    scanner: &SecretScanner,
+) -> Result<Vec<SecretFinding>, String> {
+    let range = format!("{}..HEAD", base);
+    let log = run_git(git_actor_id, directory, &["log", "-p", "--format=", &range])?;
+    Ok(scanner.scan_diff(&log))
+}
+
+/// One line per finding, for error messages and prompts.
+pub fn describe(findings: &[SecretFinding]) -> String {
+    findings
+        .iter()
+        .map(|finding| match finding.line {
+            Some(line) => format!(
+                "- {}:{}: {} ({})",
+                finding.file, line, finding.rule, finding.excerpt
+            ),
+            None => format!("- {}: {}", finding.file, finding.rule),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn prompt_fragment() -> &'static str {
+    "\n\nSECRETS: Before each commit, check the staged diff (git diff --cached) for \
+    credentials such as AWS keys, private keys, API tokens, and passwords, and for files \
+    like .env or *.pem. Never commit them: unstage the file and tell the user what you \
+    found instead. The assistant scans the commits too and will ask you to remove any \
+    credentials it finds."
+}
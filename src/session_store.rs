@@ -0,0 +1,228 @@
+//! Snapshots of the default session in the runtime's content-addressed store, for
+//! `SaveSession` and `RestoreSession`: the config, the transcript, the workflow, and the
+//! audit log, so a review can be picked up days later or on another machine. Snapshots
+//! are addressed by the hash of their content and labelled with a name of the client's
+//! choosing. Credentials never go into the store; a restored session keeps the ones it
+//! has.
+
+use crate::audit::AuditLog;
+use crate::bindings::theater::simple::store::{self, ContentRef};
+use crate::errors::ErrorCode;
+use crate::logging;
+use crate::progress::ProgressPhase;
+use crate::protocol::HistoryMessage;
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Layout of the snapshots this build writes; older or newer ones are refused.
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// The workflow of a snapshot, as it stood when it was saved.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedWorkflow {
+    pub workflow: Option<Workflow>,
+    #[serde(default)]
+    pub args: Option<Value>,
+    pub auto_initiated: bool,
+    pub finished: bool,
+    pub turns: u32,
+    pub phase: ProgressPhase,
+    /// HEAD when the workflow started
+    #[serde(default)]
+    pub commit_base: Option<String>,
+    /// Branch checked out when the workflow started
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Id of the first audit log entry of the workflow
+    pub audit_base: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionSnapshot {
+    pub version: u32,
+    pub label: String,
+    /// When it was saved, in seconds since the epoch
+    pub saved_at: u64,
+    pub current_directory: Option<String>,
+    /// The assistant config, with credentials left out
+    pub config: Value,
+    /// The default session's transcript
+    pub transcript: Vec<HistoryMessage>,
+    pub workflow: SavedWorkflow,
+    pub audit_log: AuditLog,
+}
+
+/// A saved snapshot, as `SaveSession` and `RestoreSession` report it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotInfo {
+    /// Hash of the snapshot in the store; `RestoreSession` takes it, or the label
+    pub id: String,
+    pub label: String,
+    pub store_id: String,
+    pub saved_at: u64,
+    /// Messages of the transcript
+    pub messages: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow: Option<Workflow>,
+}
+
+impl SessionSnapshot {
+    pub fn new(
+        label: String,
+        saved_at: u64,
+        current_directory: Option<String>,
+        mut config: Value,
+        transcript: Vec<HistoryMessage>,
+        workflow: SavedWorkflow,
+        audit_log: AuditLog,
+    ) -> Self {
+        strip_credentials(&mut config);
+        Self {
+            version: SNAPSHOT_VERSION,
+            label,
+            saved_at,
+            current_directory,
+            config,
+            transcript,
+            workflow,
+            audit_log,
+        }
+    }
+
+    pub fn info(&self, id: String, store_id: String) -> SnapshotInfo {
+        SnapshotInfo {
+            id,
+            label: self.label.clone(),
+            store_id,
+            saved_at: self.saved_at,
+            messages: self.transcript.len(),
+            workflow: self.workflow.workflow.clone(),
+        }
+    }
+}
+
+/// Drop every credential field, e.g. `api_key` or `forge_token`, from `config`.
+fn strip_credentials(config: &mut Value) {
+    match config {
+        Value::Object(fields) => {
+            fields.retain(|key, value| !(value.is_string() && logging::is_secret_key(key)));
+            fields.values_mut().for_each(strip_credentials);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_credentials),
+        _ => {}
+    }
+}
+
+/// Put the credential fields of `current` back into `saved`, where `saved` has the
+/// object they belong to.
+pub fn restore_credentials(saved: &mut Value, current: &Value) {
+    match (saved, current) {
+        (Value::Object(saved), Value::Object(current)) => {
+            for (key, value) in current {
+                if value.is_string() && logging::is_secret_key(key) {
+                    saved.entry(key.clone()).or_insert_with(|| value.clone());
+                } else if let Some(saved) = saved.get_mut(key) {
+                    restore_credentials(saved, value);
+                }
+            }
+        }
+        (Value::Array(saved), Value::Array(current)) => {
+            for (saved, current) in saved.iter_mut().zip(current) {
+                restore_credentials(saved, current);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Store `snapshot` in `store_id` under its label. Returns its hash.
+pub fn save(store_id: &str, snapshot: &SessionSnapshot) -> Result<String, String> {
+    let content = serde_json::to_vec(snapshot)
+        .map_err(|e| format!("Failed to serialize the snapshot: {}", e))?;
+    let content_ref = store::store(store_id, &content)?;
+    store::label(store_id, &snapshot.label, &content_ref)?;
+    Ok(content_ref.hash)
+}
+
+/// The snapshot `id` names in `store_id`: a label, or else a hash. Returns it with its
+/// hash.
+pub fn load(store_id: &str, id: &str) -> Result<(String, SessionSnapshot), String> {
+    let content_ref = store::get_by_label(store_id, id)?.unwrap_or_else(|| ContentRef {
+        hash: id.to_string(),
+    });
+    let content = store::get(store_id, &content_ref).map_err(|e| {
+        format!(
+            "{}: no snapshot {} in store {}: {}",
+            ErrorCode::NotFound,
+            id,
+            store_id,
+            e
+        )
+    })?;
+    let snapshot: SessionSnapshot = serde_json::from_slice(&content)
+        .map_err(|e| format!("snapshot {} doesn't parse: {}", id, e))?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(format!(
+            "snapshot {} has version {}, this assistant reads version {}",
+            id, snapshot.version, SNAPSHOT_VERSION
+        ));
+    }
+    Ok((content_ref.hash, snapshot))
+}
+
+/// A new store for snapshots. Its id goes into `session_store_id` to share it.
+pub fn create_store() -> Result<String, String> {
+    store::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn credentials_stay_out_of_snapshots() {
+        let current = json!({
+            "workflow": "review",
+            "model_config": {"model": "claude-sonnet-4", "api_key": "sk-live"},
+            "forge_mcp": {"forge_token": "ghp_123", "update_pr_changelog": true}
+        });
+        let snapshot = SessionSnapshot::new(
+            "review-42".to_string(),
+            1_760_000_000,
+            Some("/repo".to_string()),
+            current.clone(),
+            Vec::new(),
+            SavedWorkflow {
+                workflow: Some(Workflow::Review),
+                args: None,
+                auto_initiated: true,
+                finished: false,
+                turns: 3,
+                phase: ProgressPhase::Analyzing,
+                commit_base: None,
+                branch: None,
+                audit_base: 0,
+            },
+            AuditLog::default(),
+        );
+        assert_eq!(
+            snapshot.config,
+            json!({
+                "workflow": "review",
+                "model_config": {"model": "claude-sonnet-4"},
+                "forge_mcp": {"update_pr_changelog": true}
+            })
+        );
+        let info = snapshot.info("9f86d08".to_string(), "store-1".to_string());
+        assert_eq!(info.workflow, Some(Workflow::Review));
+
+        let mut restored = snapshot.config.clone();
+        restored["model_config"]["model"] = json!("claude-opus-4");
+        restore_credentials(&mut restored, &current);
+        assert_eq!(restored["model_config"]["api_key"], "sk-live");
+        assert_eq!(restored["model_config"]["model"], "claude-opus-4");
+        assert_eq!(restored["forge_mcp"]["forge_token"], "ghp_123");
+    }
+}
@@ -0,0 +1,104 @@
+use crate::git_tools::{ensure_revision, run_git};
+use serde::{Deserialize, Serialize};
+
+/// Signature state of a single commit, derived from git's `%G?` placeholder.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureStatus {
+    /// A good signature (`G`, or `U` for a good signature of unknown validity).
+    Signed,
+    /// No signature (`N`).
+    Unsigned,
+    /// A bad signature, or one made with an expired or revoked key (`B`, `X`, `Y`, `R`).
+    Bad,
+    /// The signature could not be checked, usually because the key is missing (`E`).
+    Unverifiable,
+}
+
+impl SignatureStatus {
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "G" | "U" => SignatureStatus::Signed,
+            "N" => SignatureStatus::Unsigned,
+            "E" => SignatureStatus::Unverifiable,
+            _ => SignatureStatus::Bad,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommitSignature {
+    pub sha: String,
+    pub status: SignatureStatus,
+    /// Raw `%G?` code reported by git.
+    pub code: String,
+    pub signer: Option<String>,
+    pub key: Option<String>,
+    pub subject: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignatureReport {
+    pub range: String,
+    pub commits: Vec<CommitSignature>,
+    pub signed: usize,
+    pub unsigned: usize,
+    pub bad: usize,
+    pub unverifiable: usize,
+}
+
+/// Fields are separated by a unit separator so subjects can contain anything else.
+const LOG_FORMAT: &str = "--format=%H%x1f%G?%x1f%GS%x1f%GK%x1f%s";
+
+/// Signature state of every commit in `range`. Never bounded by performance mode: an
+/// unsigned commit past the bounds would otherwise go unreported.
+pub fn verify_history(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    range: &str,
+) -> Result<SignatureReport, String> {
+    ensure_revision("range", range)?;
+    let output = run_git(git_actor_id, directory, &["log", LOG_FORMAT, range])?;
+    Ok(parse_signature_log(range, &output))
+}
+
+fn parse_signature_log(range: &str, output: &str) -> SignatureReport {
+    let commits: Vec<CommitSignature> = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\u{1f}');
+            let sha = fields.next()?.trim();
+            if sha.is_empty() {
+                return None;
+            }
+            let code = fields.next().unwrap_or("N").trim().to_string();
+            let non_empty = |field: Option<&str>| {
+                field
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+            };
+            let signer = non_empty(fields.next());
+            let key = non_empty(fields.next());
+            let subject = fields.next().unwrap_or_default().to_string();
+            Some(CommitSignature {
+                sha: sha.to_string(),
+                status: SignatureStatus::from_code(&code),
+                code,
+                signer,
+                key,
+                subject,
+            })
+        })
+        .collect();
+
+    let count = |status: SignatureStatus| commits.iter().filter(|c| c.status == status).count();
+    SignatureReport {
+        range: range.to_string(),
+        signed: count(SignatureStatus::Signed),
+        unsigned: count(SignatureStatus::Unsigned),
+        bad: count(SignatureStatus::Bad),
+        unverifiable: count(SignatureStatus::Unverifiable),
+        commits,
+    }
+}
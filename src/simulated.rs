@@ -0,0 +1,210 @@
+//! Test-only stand-in for the chat-state actor, enabled with `simulated_chat_state`.
+//!
+//! No chat-state actor is spawned: sessions get an id starting with
+//! [`SIMULATED_PREFIX`], and the requests sent to it are answered here. Each generation
+//! echoes the latest user message and reports `GenerationFinished` back to the
+//! assistant with `send`, like the real actor, so supervision, queuing, and event
+//! fan-out can be tested end to end without a model.
+//!
+//! Transcripts live in the actor instance rather than in the persisted state, so they
+//! don't survive a restart.
+
+use crate::bindings::theater::simple::message_server_host::send;
+use crate::protocol::{
+    ChatStateRequest, ChatStateResponse, ChildNotification, HistoryMessage, Provenance,
+};
+use genai_types::messages::Role;
+use genai_types::{Message, MessageContent};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// Prefix of the ids given to simulated chat-state actors.
+pub const SIMULATED_PREFIX: &str = "simulated-chat-state-";
+
+struct Simulation {
+    enabled: bool,
+    /// The assistant, which gets the `GenerationFinished` notifications
+    supervisor_id: String,
+    next_number: u64,
+    transcripts: BTreeMap<String, Vec<HistoryMessage>>,
+}
+
+thread_local! {
+    static SIMULATION: RefCell<Simulation> = const {
+        RefCell::new(Simulation {
+            enabled: false,
+            supervisor_id: String::new(),
+            next_number: 1,
+            transcripts: BTreeMap::new(),
+        })
+    };
+}
+
+/// Apply the `simulated_chat_state` setting for the message being handled.
+pub fn configure(enabled: bool, supervisor_id: &str) {
+    SIMULATION.with(|simulation| {
+        let mut simulation = simulation.borrow_mut();
+        simulation.enabled = enabled;
+        simulation.supervisor_id = supervisor_id.to_string();
+    });
+}
+
+pub fn is_enabled() -> bool {
+    SIMULATION.with(|simulation| simulation.borrow().enabled)
+}
+
+pub fn is_simulated(actor_id: &str) -> bool {
+    actor_id.starts_with(SIMULATED_PREFIX)
+}
+
+/// Whether `actor_id` is a simulated actor this instance still has a transcript for.
+pub fn is_running(actor_id: &str) -> bool {
+    SIMULATION.with(|simulation| simulation.borrow().transcripts.contains_key(actor_id))
+}
+
+/// Start a simulated chat-state actor and return its id.
+pub fn spawn() -> String {
+    SIMULATION.with(|simulation| {
+        let mut simulation = simulation.borrow_mut();
+        let actor_id = format!("{}{}", SIMULATED_PREFIX, simulation.next_number);
+        simulation.next_number += 1;
+        simulation.transcripts.insert(actor_id.clone(), Vec::new());
+        actor_id
+    })
+}
+
+pub fn stop(actor_id: &str) -> Result<(), String> {
+    SIMULATION.with(
+        |simulation| match simulation.borrow_mut().transcripts.remove(actor_id) {
+            Some(_) => Ok(()),
+            None => Err(format!("{} is not running", actor_id)),
+        },
+    )
+}
+
+/// Answer `request` the way the chat-state actor would.
+pub fn handle(actor_id: &str, request: &ChatStateRequest) -> Result<ChatStateResponse, String> {
+    let supervisor_id = SIMULATION.with(|simulation| -> Result<Option<String>, String> {
+        let mut simulation = simulation.borrow_mut();
+        let supervisor_id = simulation.supervisor_id.clone();
+        let transcript = simulation
+            .transcripts
+            .get_mut(actor_id)
+            .ok_or_else(|| format!("{} is not running", actor_id))?;
+        match request {
+            ChatStateRequest::AddMessage {
+                message,
+                provenance,
+            } => {
+                push(transcript, message.clone(), *provenance);
+                Ok(None)
+            }
+            ChatStateRequest::GenerateCompletion => {
+                let latest = transcript
+                    .iter()
+                    .rev()
+                    .find(|entry| matches!(entry.message.role, Role::User))
+                    .map(|entry| text_of(&entry.message))
+                    .unwrap_or_default();
+                push(
+                    transcript,
+                    Message {
+                        role: Role::Assistant,
+                        content: vec![MessageContent::Text {
+                            text: format!("Echo: {}", latest),
+                        }],
+                    },
+                    None,
+                );
+                Ok(Some(supervisor_id))
+            }
+            ChatStateRequest::TruncateHistory { from } => {
+                let index = transcript
+                    .iter()
+                    .position(|entry| entry.id == *from)
+                    .ok_or_else(|| format!("No message {} in {}", from, actor_id))?;
+                transcript.truncate(index);
+                Ok(None)
+            }
+            ChatStateRequest::CancelGeneration
+            | ChatStateRequest::GetHistory { .. }
+            | ChatStateRequest::UpdateSystemPrompt { .. }
+            | ChatStateRequest::UpdateMcpServers { .. }
+            | ChatStateRequest::UpdateConfig { .. }
+            | ChatStateRequest::GetHead
+            | ChatStateRequest::GetMessage { .. }
+            | ChatStateRequest::Ping => Ok(None),
+        }
+    })?;
+
+    match request {
+        ChatStateRequest::GetHistory { limit, before } => {
+            return Ok(ChatStateResponse::History {
+                messages: history(actor_id, *limit, before.as_deref()),
+            })
+        }
+        ChatStateRequest::Ping => return Ok(ChatStateResponse::Pong),
+        ChatStateRequest::GetHead => {
+            return Ok(ChatStateResponse::Head {
+                head: history(actor_id, Some(1), None).pop().map(|entry| entry.id),
+            })
+        }
+        ChatStateRequest::GetMessage { message_id } => {
+            return history(actor_id, None, None)
+                .into_iter()
+                .find(|entry| entry.id == *message_id)
+                .map(|message| ChatStateResponse::ChatMessage { message })
+                .ok_or_else(|| format!("No message {} in {}", message_id, actor_id))
+        }
+        _ => {}
+    }
+    // Reported through the message server, so it is queued like the real actor's
+    if let Some(supervisor_id) = supervisor_id {
+        let notification = ChildNotification::GenerationFinished {
+            stop_reason: Some("end_turn".to_string()),
+            chat_state_actor_id: Some(actor_id.to_string()),
+        };
+        let bytes = serde_json::to_vec(&notification)
+            .map_err(|e| format!("Failed to serialize notification: {}", e))?;
+        send(&supervisor_id, &bytes)
+            .map_err(|e| format!("Failed to notify {}: {:?}", supervisor_id, e))?;
+    }
+    Ok(ChatStateResponse::Success)
+}
+
+fn push(transcript: &mut Vec<HistoryMessage>, message: Message, provenance: Option<Provenance>) {
+    let id = format!("message-{}", transcript.len() + 1);
+    transcript.push(HistoryMessage {
+        id,
+        message,
+        provenance,
+    });
+}
+
+fn text_of(message: &Message) -> String {
+    message
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            MessageContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The latest `limit` messages before message `before`, oldest first.
+fn history(actor_id: &str, limit: Option<usize>, before: Option<&str>) -> Vec<HistoryMessage> {
+    SIMULATION.with(|simulation| {
+        let simulation = simulation.borrow();
+        let transcript = match simulation.transcripts.get(actor_id) {
+            Some(transcript) => transcript,
+            None => return Vec::new(),
+        };
+        let end = before
+            .and_then(|before| transcript.iter().position(|entry| entry.id == before))
+            .unwrap_or(transcript.len());
+        let start = limit.map_or(0, |limit| end.saturating_sub(limit));
+        transcript[start..end].to_vec()
+    })
+}
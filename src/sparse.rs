@@ -0,0 +1,113 @@
+//! Sparse checkouts of a monorepo, for `GetSparseCheckout` and `SetSparsePaths`, and
+//! the rule that keeps workflows inside the checked-out paths.
+
+use crate::git_tools::{ensure_revision, run_git};
+use crate::scope;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SparseCheckout {
+    /// Whether `core.sparseCheckout` is on
+    pub enabled: bool,
+    /// Whether the paths are directories (cone mode) rather than patterns
+    pub cone: bool,
+    /// Directories or patterns checked out, as `git sparse-checkout list` prints them;
+    /// empty when the checkout isn't sparse
+    pub paths: Vec<String>,
+}
+
+/// Whether the boolean config `key` is set to true; unset counts as false.
+fn config_enabled(git_actor_id: &str, directory: Option<&str>, key: &str) -> bool {
+    // `git config --get` fails when the key is unset
+    run_git(git_actor_id, directory, &["config", "--bool", "--get", key])
+        .is_ok_and(|value| value.trim() == "true")
+}
+
+/// The sparse-checkout state of the repository in `directory`.
+pub fn read(git_actor_id: &str, directory: Option<&str>) -> Result<SparseCheckout, String> {
+    if !config_enabled(git_actor_id, directory, "core.sparseCheckout") {
+        return Ok(SparseCheckout {
+            enabled: false,
+            cone: false,
+            paths: Vec::new(),
+        });
+    }
+    let cone = config_enabled(git_actor_id, directory, "core.sparseCheckoutCone");
+    let output = run_git(git_actor_id, directory, &["sparse-checkout", "list"])?;
+    Ok(SparseCheckout {
+        enabled: true,
+        cone,
+        paths: output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect(),
+    })
+}
+
+/// `paths` made ready for `git sparse-checkout set`, or why they can't be: each must
+/// name a directory of the repository, inside `scope` when one is configured.
+pub fn prepare_paths(paths: &[String], scope: &[String]) -> Result<Vec<String>, String> {
+    let paths: Vec<String> = scope::normalize(paths)
+        .into_iter()
+        .filter(|path| !path.is_empty() && path != ".")
+        .collect();
+    if paths.is_empty() {
+        return Err("No paths to check out".to_string());
+    }
+    let invalid = scope::invalid_paths(&paths);
+    if let Some(path) = invalid.first() {
+        return Err(format!("{} isn't a path inside the repository", path));
+    }
+    for path in &paths {
+        ensure_revision("path", path)?;
+    }
+    if !scope.is_empty() {
+        if let Some(path) = paths.iter().find(|path| !scope::contains(scope, path)) {
+            return Err(format!("{} is outside scope_paths", path));
+        }
+    }
+    Ok(paths)
+}
+
+/// Check out only the directories `paths`, in cone mode, and return the new state.
+pub fn set_paths(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    paths: &[String],
+) -> Result<SparseCheckout, String> {
+    let mut args = vec!["sparse-checkout", "set", "--cone"];
+    args.extend(paths.iter().map(String::as_str));
+    run_git(git_actor_id, directory, &args)?;
+    read(git_actor_id, directory)
+}
+
+/// Rule added to workflow messages while the checkout is sparse.
+pub fn prompt_fragment(sparse: &SparseCheckout) -> String {
+    format!(
+        "\n\nSPARSE CHECKOUT: Only these paths of the repository are checked out: {}. List, \
+        stage, and commit changes only within them. Don't pass --sparse to git add, don't \
+        create files outside them, and don't change the sparse-checkout set yourself.",
+        sparse.paths.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_normalized_and_checked_against_the_scope() {
+        let scope = vec!["services".to_string(), "libs/common".to_string()];
+        let paths = vec!["./services/api/".to_string(), "libs/common".to_string()];
+        assert_eq!(
+            prepare_paths(&paths, &scope).unwrap(),
+            vec!["services/api", "libs/common"]
+        );
+        assert!(prepare_paths(&["docs".to_string()], &scope).is_err());
+        assert!(prepare_paths(&["../other".to_string()], &[]).is_err());
+        assert!(prepare_paths(&["--no-cone".to_string()], &[]).is_err());
+        assert!(prepare_paths(&[".".to_string()], &[]).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The `spellcheck` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpellcheckConfig {
+    /// Extra misspellings and their corrections, on top of the built-in list
+    #[serde(default)]
+    pub dictionary: BTreeMap<String, String>,
+    /// Words that are never flagged
+    #[serde(default)]
+    pub words: Vec<String>,
+    /// Project word list in the repository, one word per line (`#` starts a comment)
+    #[serde(default = "default_word_list_path")]
+    pub word_list_path: String,
+}
+
+fn default_word_list_path() -> String {
+    ".git-chat-words".to_string()
+}
+
+impl Default for SpellcheckConfig {
+    fn default() -> Self {
+        Self {
+            dictionary: BTreeMap::new(),
+            words: Vec::new(),
+            word_list_path: default_word_list_path(),
+        }
+    }
+}
+
+/// A probable misspelling and the suggested correction.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SpellingIssue {
+    pub word: String,
+    /// 1-based line of `word` in the checked text
+    pub line: usize,
+    pub suggestion: String,
+}
+
+pub struct Spellchecker {
+    corrections: BTreeMap<String, String>,
+    allowed: BTreeSet<String>,
+}
+
+impl Spellchecker {
+    pub fn new(config: &SpellcheckConfig, project_words: &[String]) -> Self {
+        let mut corrections: BTreeMap<String, String> = COMMON_MISSPELLINGS
+            .iter()
+            .map(|(word, correction)| (word.to_string(), correction.to_string()))
+            .collect();
+        for (word, correction) in &config.dictionary {
+            corrections.insert(word.to_lowercase(), correction.clone());
+        }
+        let allowed = config
+            .words
+            .iter()
+            .chain(project_words)
+            .map(|word| word.to_lowercase())
+            .collect();
+        Self {
+            corrections,
+            allowed,
+        }
+    }
+
+    /// Build a checker with the project word list read from `HEAD`, if there is one.
+    pub fn load(git_actor_id: &str, directory: Option<&str>, config: &SpellcheckConfig) -> Self {
+        let blob = format!("HEAD:{}", config.word_list_path);
+        let project_words = run_git(git_actor_id, directory, &["show", &blob])
+            .map(|content| parse_word_list(&content))
+            .unwrap_or_default();
+        Self::new(config, &project_words)
+    }
+
+    /// Probable misspellings in `text`. Nothing is corrected; callers offer the suggestions.
+    pub fn check(&self, text: &str) -> Vec<SpellingIssue> {
+        let mut issues = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            // Code spans are identifiers, not prose
+            let prose = line.split('`').step_by(2).collect::<Vec<_>>().join(" ");
+            for word in prose.split(|c: char| !(c.is_alphanumeric() || c == '\'')) {
+                let word = word.trim_matches('\'');
+                if !is_prose_word(word) {
+                    continue;
+                }
+                let lower = word.to_lowercase();
+                if self.allowed.contains(&lower) {
+                    continue;
+                }
+                if let Some(correction) = self.corrections.get(&lower) {
+                    issues.push(SpellingIssue {
+                        word: word.to_string(),
+                        line: index + 1,
+                        suggestion: match_case(word, correction),
+                    });
+                }
+            }
+        }
+        issues
+    }
+}
+
+/// Skip identifiers: words with digits, underscores, or capitals after the first letter.
+fn is_prose_word(word: &str) -> bool {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) if first.is_alphabetic() => chars.all(|c| c.is_lowercase() || c == '\''),
+        _ => false,
+    }
+}
+
+fn match_case(word: &str, correction: &str) -> String {
+    if word.chars().next().is_some_and(char::is_uppercase) {
+        let mut chars = correction.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    } else {
+        correction.to_string()
+    }
+}
+
+fn parse_word_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Frequent misspellings in commit messages and their corrections.
+const COMMON_MISSPELLINGS: &[(&str, &str)] = &[
+    ("accomodate", "accommodate"),
+    ("acheive", "achieve"),
+    ("accross", "across"),
+    ("adress", "address"),
+    ("agressive", "aggressive"),
+    ("alot", "a lot"),
+    ("allready", "already"),
+    ("alredy", "already"),
+    ("aparent", "apparent"),
+    ("apropriate", "appropriate"),
+    ("arguement", "argument"),
+    ("asynchonous", "asynchronous"),
+    ("attribtue", "attribute"),
+    ("availible", "available"),
+    ("avaliable", "available"),
+    ("backwords", "backwards"),
+    ("becuase", "because"),
+    ("begining", "beginning"),
+    ("beleive", "believe"),
+    ("benifit", "benefit"),
+    ("buidl", "build"),
+    ("calender", "calendar"),
+    ("cancelation", "cancellation"),
+    ("catagory", "category"),
+    ("changable", "changeable"),
+    ("charater", "character"),
+    ("choosen", "chosen"),
+    ("commited", "committed"),
+    ("comming", "coming"),
+    ("commmit", "commit"),
+    ("compatability", "compatibility"),
+    ("compatable", "compatible"),
+    ("compiller", "compiler"),
+    ("completly", "completely"),
+    ("concious", "conscious"),
+    ("configuraiton", "configuration"),
+    ("conection", "connection"),
+    ("consistant", "consistent"),
+    ("contructor", "constructor"),
+    ("convinient", "convenient"),
+    ("correclty", "correctly"),
+    ("currenly", "currently"),
+    ("definately", "definitely"),
+    ("defualt", "default"),
+    ("dependancy", "dependency"),
+    ("dependancies", "dependencies"),
+    ("depricated", "deprecated"),
+    ("descripton", "description"),
+    ("diffrent", "different"),
+    ("dissapear", "disappear"),
+    ("documentaion", "documentation"),
+    ("doesnt", "doesn't"),
+    ("efficent", "efficient"),
+    ("embarass", "embarrass"),
+    ("enviroment", "environment"),
+    ("exampel", "example"),
+    ("excecute", "execute"),
+    ("existance", "existence"),
+    ("explicitely", "explicitly"),
+    ("explaination", "explanation"),
+    ("familar", "familiar"),
+    ("fucntion", "function"),
+    ("funtion", "function"),
+    ("futher", "further"),
+    ("gaurd", "guard"),
+    ("goverment", "government"),
+    ("grammer", "grammar"),
+    ("guarentee", "guarantee"),
+    ("handeling", "handling"),
+    ("heirarchy", "hierarchy"),
+    ("identifer", "identifier"),
+    ("immediatly", "immediately"),
+    ("implemenation", "implementation"),
+    ("implmentation", "implementation"),
+    ("incase", "in case"),
+    ("independant", "independent"),
+    ("initalize", "initialize"),
+    ("intial", "initial"),
+    ("interupt", "interrupt"),
+    ("intergration", "integration"),
+    ("isnt", "isn't"),
+    ("langauge", "language"),
+    ("lenght", "length"),
+    ("libary", "library"),
+    ("maintainance", "maintenance"),
+    ("managment", "management"),
+    ("mesage", "message"),
+    ("messsage", "message"),
+    ("millenium", "millennium"),
+    ("minimun", "minimum"),
+    ("mispell", "misspell"),
+    ("neccessary", "necessary"),
+    ("necesary", "necessary"),
+    ("noticable", "noticeable"),
+    ("occassion", "occasion"),
+    ("occured", "occurred"),
+    ("occurence", "occurrence"),
+    ("occurrance", "occurrence"),
+    ("optmize", "optimize"),
+    ("paramter", "parameter"),
+    ("paramaters", "parameters"),
+    ("parralel", "parallel"),
+    ("perfomance", "performance"),
+    ("performace", "performance"),
+    ("persistant", "persistent"),
+    ("posible", "possible"),
+    ("precedance", "precedence"),
+    ("prefered", "preferred"),
+    ("preceeding", "preceding"),
+    ("privilage", "privilege"),
+    ("proccess", "process"),
+    ("procotol", "protocol"),
+    ("programatically", "programmatically"),
+    ("propogate", "propagate"),
+    ("recieve", "receive"),
+    ("recieved", "received"),
+    ("reccomend", "recommend"),
+    ("recomend", "recommend"),
+    ("refering", "referring"),
+    ("refrence", "reference"),
+    ("relevent", "relevant"),
+    ("remaning", "remaining"),
+    ("repositry", "repository"),
+    ("reponse", "response"),
+    ("requried", "required"),
+    ("resouce", "resource"),
+    ("retreive", "retrieve"),
+    ("retrun", "return"),
+    ("seperate", "separate"),
+    ("seperator", "separator"),
+    ("sucess", "success"),
+    ("succesful", "successful"),
+    ("successfull", "successful"),
+    ("suport", "support"),
+    ("supress", "suppress"),
+    ("surpress", "suppress"),
+    ("syncronous", "synchronous"),
+    ("teh", "the"),
+    ("threshhold", "threshold"),
+    ("tommorow", "tomorrow"),
+    ("transfered", "transferred"),
+    ("truely", "truly"),
+    ("unecessary", "unnecessary"),
+    ("unneccessary", "unnecessary"),
+    ("untill", "until"),
+    ("upadte", "update"),
+    ("usefull", "useful"),
+    ("usualy", "usually"),
+    ("validaiton", "validation"),
+    ("verison", "version"),
+    ("visable", "visible"),
+    ("wich", "which"),
+    ("wierd", "weird"),
+    ("wihtout", "without"),
+    ("writting", "writing"),
+];
@@ -0,0 +1,105 @@
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+
+/// Stashes whose diff stat is put in the opening message; the rest are only listed.
+const MAX_DESCRIBED_STASHES: usize = 20;
+
+/// An entry of `git stash list`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StashEntry {
+    pub sha: String,
+    /// `stash@{n}` when it was listed; dropping a stash renumbers the ones after it
+    pub reference: String,
+    pub message: String,
+}
+
+impl StashEntry {
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(7)]
+    }
+}
+
+/// The repository's stashes, newest first.
+pub fn fetch_stashes(
+    git_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<Vec<StashEntry>, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &["stash", "list", "--format=%H%x1f%gd%x1f%gs"],
+    )?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().splitn(3, '\u{1f}');
+            let sha = fields.next()?.to_string();
+            let reference = fields.next()?.to_string();
+            let message = fields.next().unwrap_or_default().to_string();
+            (!sha.is_empty()).then_some(StashEntry {
+                sha,
+                reference,
+                message,
+            })
+        })
+        .collect())
+}
+
+/// Block for the opening message listing every stash, with the files and line counts
+/// of the newest ones.
+pub fn describe_stashes(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    stashes: &[StashEntry],
+) -> String {
+    if stashes.is_empty() {
+        return "\n\nSTASHES: none. Tell me there is nothing to triage and use the \
+            task_complete tool."
+            .to_string();
+    }
+    let mut block = format!("\n\nSTASHES ({}):", stashes.len());
+    for (index, stash) in stashes.iter().enumerate() {
+        block.push_str(&format!(
+            "\n- {} {} \"{}\"",
+            stash.short_sha(),
+            stash.reference,
+            stash.message
+        ));
+        if index >= MAX_DESCRIBED_STASHES {
+            continue;
+        }
+        match run_git(
+            git_actor_id,
+            directory,
+            &["stash", "show", "--stat", &stash.sha],
+        ) {
+            Ok(stat) => {
+                for line in stat.lines().filter(|line| !line.trim().is_empty()) {
+                    block.push_str(&format!("\n    {}", line.trim()));
+                }
+            }
+            Err(e) => block.push_str(&format!("\n    (diff stat unavailable: {})", e)),
+        }
+    }
+    if stashes.len() > MAX_DESCRIBED_STASHES {
+        block.push_str(&format!(
+            "\n(Only the newest {} stashes have a diff stat; use git stash show for the rest.)",
+            MAX_DESCRIBED_STASHES
+        ));
+    }
+    block
+}
+
+/// Stashes from `listed` that are still in `current` but not named as kept, by short
+/// sha, in the model's `summary`.
+pub fn undecided<'a>(
+    listed: &'a [StashEntry],
+    current: &[StashEntry],
+    summary: Option<&str>,
+) -> Vec<&'a StashEntry> {
+    listed
+        .iter()
+        .filter(|stash| current.iter().any(|entry| entry.sha == stash.sha))
+        .filter(|stash| !summary.is_some_and(|summary| summary.contains(stash.short_sha())))
+        .collect()
+}
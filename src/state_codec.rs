@@ -0,0 +1,269 @@
+//! Encoding of the actor state between handler calls.
+//!
+//! Encoded state starts with a header naming its codec, so a state saved with one codec
+//! still decodes after the configured codec changes. State saved before the header
+//! existed is plain JSON and decodes as such.
+
+use crate::logging;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// Starts every header. JSON can't start with a NUL byte, so headerless state is told
+/// apart by its first byte.
+const MAGIC: &[u8] = b"\0GCS";
+
+/// Layout of the header: `MAGIC`, this version, then the codec's id.
+const HEADER_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + 2;
+
+/// Turns the state into bytes and back.
+pub trait StateCodec {
+    /// Identifies the codec in the header; never reuse the id of a removed codec.
+    const ID: u8;
+
+    fn encode<T: Serialize>(state: &T) -> Result<Vec<u8>, String>;
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String>;
+}
+
+pub struct JsonCodec;
+
+impl StateCodec for JsonCodec {
+    const ID: u8 = 1;
+
+    fn encode<T: Serialize>(state: &T) -> Result<Vec<u8>, String> {
+        serde_json::to_vec(state).map_err(|e| e.to_string())
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        serde_json::from_slice(bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// Fields whose values the redacting codec keeps out of saved state, besides credentials
+/// such as `api_key` or `forge_token`: custom prompts may quote proprietary code.
+const PROMPT_KEYS: &[&str] = &["system_prompt", "prompts"];
+
+/// Marks a redacted value in saved state; the fingerprint names it in the vault.
+const REDACTED_PREFIX: &str = "[redacted ";
+
+thread_local! {
+    /// Values redacted from the last state encoded, by fingerprint. They live only as
+    /// long as the actor instance, like the logging context.
+    static VAULT: RefCell<BTreeMap<String, Value>> = const { RefCell::new(BTreeMap::new()) };
+    /// Fields dropped by the last decode because their values were no longer in the vault.
+    static DROPPED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// JSON with credentials and custom prompts replaced by fingerprints. The values are
+/// kept in memory and put back on decode; state resumed by a fresh actor instance
+/// loses them, and those fields fall back to their defaults.
+pub struct RedactedJsonCodec;
+
+impl StateCodec for RedactedJsonCodec {
+    const ID: u8 = 2;
+
+    fn encode<T: Serialize>(state: &T) -> Result<Vec<u8>, String> {
+        let mut value = serde_json::to_value(state).map_err(|e| e.to_string())?;
+        let mut vault = BTreeMap::new();
+        redact(&mut value, &mut vault);
+        VAULT.with(|stored| *stored.borrow_mut() = vault);
+        serde_json::to_vec(&value).map_err(|e| e.to_string())
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        let mut value: Value = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+        let mut dropped = Vec::new();
+        VAULT.with(|vault| restore(&mut value, &vault.borrow(), "", &mut dropped));
+        DROPPED.with(|stored| *stored.borrow_mut() = dropped);
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+}
+
+fn is_sensitive(key: &str, value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::String(_) => logging::is_secret_key(key) || PROMPT_KEYS.contains(&key),
+        _ => PROMPT_KEYS.contains(&key),
+    }
+}
+
+fn redact(value: &mut Value, vault: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if is_sensitive(key, field) {
+                    let id = logging::fingerprint(&field.to_string());
+                    let original = std::mem::replace(
+                        field,
+                        Value::String(format!("{}{}]", REDACTED_PREFIX, id)),
+                    );
+                    vault.insert(id, original);
+                } else {
+                    redact(field, vault);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|value| redact(value, vault)),
+        _ => {}
+    }
+}
+
+fn redacted_id(value: &Value) -> Option<&str> {
+    value
+        .as_str()?
+        .strip_prefix(REDACTED_PREFIX)?
+        .strip_suffix(']')
+}
+
+/// Put vaulted values back, and drop the fields whose values are gone, leaving them to
+/// their defaults.
+fn restore(
+    value: &mut Value,
+    vault: &BTreeMap<String, Value>,
+    path: &str,
+    dropped: &mut Vec<String>,
+) {
+    match value {
+        Value::Object(fields) => {
+            let mut kept = Map::new();
+            for (key, mut field) in std::mem::take(fields) {
+                let field_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match redacted_id(&field).map(|id| vault.get(id)) {
+                    Some(Some(original)) => field = original.clone(),
+                    Some(None) => {
+                        dropped.push(field_path);
+                        continue;
+                    }
+                    None => restore(&mut field, vault, &field_path, dropped),
+                }
+                kept.insert(key, field);
+            }
+            *fields = kept;
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                restore(value, vault, &format!("{}[{}]", path, index), dropped);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Fields the last decode dropped because their redacted values were lost.
+pub fn take_dropped() -> Vec<String> {
+    DROPPED.with(|dropped| std::mem::take(&mut *dropped.borrow_mut()))
+}
+
+/// The `state_codec` config field: the codec new state is written with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum StateFormat {
+    #[default]
+    Json,
+    /// JSON without credentials and custom prompts; see [`RedactedJsonCodec`]
+    RedactedJson,
+}
+
+/// `state` behind a header naming `format`'s codec.
+pub fn encode<T: Serialize>(format: StateFormat, state: &T) -> Result<Vec<u8>, String> {
+    let (id, body) = match format {
+        StateFormat::Json => (JsonCodec::ID, JsonCodec::encode(state)?),
+        StateFormat::RedactedJson => (RedactedJsonCodec::ID, RedactedJsonCodec::encode(state)?),
+    };
+    let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&[HEADER_VERSION, id]);
+    bytes.extend_from_slice(&body);
+    Ok(bytes)
+}
+
+/// Decode state with the codec its header names, or as JSON without a header.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    let Some(header) = bytes.strip_prefix(MAGIC) else {
+        return JsonCodec::decode(bytes);
+    };
+    let (version, id, body) = match header {
+        [version, id, body @ ..] => (*version, *id, body),
+        _ => return Err("Truncated state header".to_string()),
+    };
+    if version != HEADER_VERSION {
+        return Err(format!(
+            "State header version {} is not supported (expected {})",
+            version, HEADER_VERSION
+        ));
+    }
+    match id {
+        JsonCodec::ID => JsonCodec::decode(body),
+        RedactedJsonCodec::ID => RedactedJsonCodec::decode(body),
+        _ => Err(format!("Unknown state codec {}", id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn state_round_trips_behind_a_header() {
+        let state = json!({"workflow": "commit", "messages_forwarded": 3});
+        let bytes = encode(StateFormat::Json, &state).unwrap();
+        assert!(bytes.starts_with(MAGIC));
+        assert_eq!(decode::<Value>(&bytes).unwrap(), state);
+    }
+
+    #[test]
+    fn headerless_state_decodes_as_json() {
+        let state: Value = decode(br#"{"workflow": "commit"}"#).unwrap();
+        assert_eq!(state, json!({"workflow": "commit"}));
+    }
+
+    #[test]
+    fn redacted_state_keeps_secrets_in_memory_only() {
+        let state = json!({
+            "assistant_config": {
+                "system_prompt": "Review our payment code",
+                "forge": {"init_state": {"api_token": "ghp_abc"}},
+                "max_tokens": 100,
+            },
+            "secrets_allowed": false,
+        });
+        let bytes = encode(StateFormat::RedactedJson, &state).unwrap();
+        let saved = String::from_utf8_lossy(&bytes);
+        assert!(!saved.contains("payment") && !saved.contains("ghp_abc"));
+        assert!(saved.contains("\"max_tokens\":100"));
+        assert_eq!(decode::<Value>(&bytes).unwrap(), state);
+        assert!(take_dropped().is_empty());
+
+        // A fresh instance has no vault: the redacted fields are left to their defaults
+        VAULT.with(|vault| vault.borrow_mut().clear());
+        let resumed: Value = decode(&bytes).unwrap();
+        assert_eq!(resumed["assistant_config"]["system_prompt"], Value::Null);
+        assert_eq!(resumed["assistant_config"]["max_tokens"], json!(100));
+        assert_eq!(
+            take_dropped(),
+            vec![
+                "assistant_config.forge.init_state.api_token",
+                "assistant_config.system_prompt"
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_headers_are_rejected() {
+        let error = decode::<Value>(b"\0GCS\x02\x01{}").unwrap_err();
+        assert!(error.contains("version 2"));
+        let error = decode::<Value>(b"\0GCS\x01\x09{}").unwrap_err();
+        assert!(error.contains("codec 9"));
+        assert!(decode::<Value>(b"\0GCS\x01").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Limits on the size of the encoded actor state. Every handler decodes and encodes the
+//! whole state, so logs kept in it make every call slower as they grow. Past
+//! `max_bytes`, the state is compacted: the oldest audit entries and debug events are
+//! dropped and the history cache is emptied. The chat config can also be kept as a hash
+//! of the config the assistant builds, plus what changed at runtime.
+
+use crate::config_patch::merge_patch;
+use crate::logging;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The `state_limits` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StateLimits {
+    /// Encoded size past which the state is compacted after a call
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+    /// Newest audit entries a compaction keeps
+    #[serde(default = "default_audit_entries")]
+    pub audit_entries: usize,
+    /// Newest debug events a compaction keeps
+    #[serde(default = "default_recent_events")]
+    pub recent_events: usize,
+    /// Keep the chat config as overrides of the one the assistant builds, always
+    /// rather than only once compacted
+    #[serde(default)]
+    pub compact_config: bool,
+}
+
+fn default_max_bytes() -> usize {
+    1_048_576
+}
+
+fn default_audit_entries() -> usize {
+    100
+}
+
+fn default_recent_events() -> usize {
+    20
+}
+
+impl Default for StateLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_max_bytes(),
+            audit_entries: default_audit_entries(),
+            recent_events: default_recent_events(),
+            compact_config: false,
+        }
+    }
+}
+
+/// What a compaction dropped, for `CompactState` and the logs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CompactionReport {
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+    pub audit_entries_dropped: usize,
+    pub events_dropped: usize,
+    pub cache_entries_dropped: usize,
+    /// Whether the chat config is now kept as overrides
+    pub config_compacted: bool,
+}
+
+impl CompactionReport {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} bytes to {}: dropped {} audit entries, {} debug events, and {} cached \
+            queries{}",
+            self.bytes_before,
+            self.bytes_after,
+            self.audit_entries_dropped,
+            self.events_dropped,
+            self.cache_entries_dropped,
+            if self.config_compacted {
+                "; the chat config is kept as overrides"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+/// The chat config as saved in compacted state.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConfigOverrides {
+    /// Fingerprint of the config the overrides apply to
+    pub base_hash: String,
+    /// Merge patch from that config to the chat config
+    pub overrides: Value,
+}
+
+/// Merge patch that turns `base` into `target`.
+fn diff(base: &Value, target: &Value) -> Value {
+    let (Value::Object(base), Value::Object(target)) = (base, target) else {
+        return target.clone();
+    };
+    let mut patch = Map::new();
+    for (key, value) in target {
+        match base.get(key) {
+            Some(old) if old == value => {}
+            Some(old) => {
+                patch.insert(key.clone(), diff(old, value));
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    for key in base.keys().filter(|key| !target.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    Value::Object(patch)
+}
+
+/// `config` as overrides of `base`.
+pub fn compact_config(base: &Value, config: &Value) -> ConfigOverrides {
+    ConfigOverrides {
+        base_hash: logging::fingerprint(&base.to_string()),
+        overrides: diff(base, config),
+    }
+}
+
+/// The chat config from its overrides and the config the assistant builds now. Returns
+/// whether `base` is the config the overrides were taken from; after an upgrade that
+/// changed the built config, the new one is used with the overrides.
+pub fn expand_config(base: Value, saved: &ConfigOverrides) -> (Value, bool) {
+    let same_base = logging::fingerprint(&base.to_string()) == saved.base_hash;
+    let mut config = base;
+    merge_patch(&mut config, &saved.overrides);
+    (config, same_base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn config_overrides_round_trip() {
+        let base = json!({
+            "system_prompt": "You are a git assistant.",
+            "model_config": {"model": "claude-sonnet-4", "provider": "anthropic"},
+            "temperature": 0.3,
+            "mcp_servers": [{"actor": {"manifest_path": "/git.toml"}}]
+        });
+        let mut config = base.clone();
+        config["model_config"]["model"] = json!("claude-haiku-4");
+        config["max_tokens"] = json!(1024);
+        config.as_object_mut().unwrap().remove("temperature");
+
+        let saved = compact_config(&base, &config);
+        assert_eq!(
+            saved.overrides,
+            json!({
+                "model_config": {"model": "claude-haiku-4"},
+                "max_tokens": 1024,
+                "temperature": null
+            })
+        );
+        assert_eq!(expand_config(base.clone(), &saved), (config.clone(), true));
+
+        let mut upgraded = base;
+        upgraded["system_prompt"] = json!("You are a careful git assistant.");
+        let (expanded, same_base) = expand_config(upgraded, &saved);
+        assert!(!same_base);
+        assert_eq!(expanded["model_config"]["model"], "claude-haiku-4");
+        assert_eq!(
+            expanded["system_prompt"],
+            "You are a careful git assistant."
+        );
+    }
+}
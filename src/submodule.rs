@@ -0,0 +1,168 @@
+//! Submodules in the commit workflow: which ones changed, what the model is told about
+//! them, and the `submodules` policy for pointer bumps.
+
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+
+/// Action name of the confirmation that lets the commit workflow bump submodule pointers.
+pub const POINTER_BUMPS: &str = "commit-submodule-pointers";
+
+/// What the commit workflow does with submodule pointer bumps.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PointerPolicy {
+    /// Commit them, explained in the message
+    #[default]
+    Include,
+    /// Leave them out of the commits
+    Exclude,
+    /// Commit them once `Confirm` allows it
+    Confirm,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SubmoduleConfig {
+    #[serde(default)]
+    pub pointer_bumps: PointerPolicy,
+    /// Have the model commit changes inside a submodule before bumping its pointer
+    #[serde(default)]
+    pub commit_inside: bool,
+}
+
+/// A submodule whose checkout differs from HEAD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmoduleChange {
+    pub path: String,
+    /// Commit HEAD points the submodule at
+    pub recorded: String,
+    /// Whether the submodule is at another commit, checked out or staged
+    pub pointer_moved: bool,
+    /// Whether it has uncommitted or untracked changes inside
+    pub dirty: bool,
+}
+
+/// Submodules in `git status --porcelain=v2` output whose checkout differs from HEAD.
+pub fn parse_status(output: &str) -> Vec<SubmoduleChange> {
+    output
+        .lines()
+        .filter_map(|line| {
+            // 1 <XY> <sub> <mH> <mI> <mW> <hH> <hI> <path>
+            let fields: Vec<&str> = line.splitn(9, ' ').collect();
+            if fields.len() < 9 || fields[0] != "1" {
+                return None;
+            }
+            let sub: Vec<char> = fields[2].chars().collect();
+            if sub.len() != 4 || sub[0] != 'S' {
+                return None;
+            }
+            let (recorded, staged) = (fields[6], fields[7]);
+            Some(SubmoduleChange {
+                path: fields[8].to_string(),
+                recorded: recorded.to_string(),
+                pointer_moved: sub[1] == 'C' || recorded != staged,
+                dirty: sub[2] == 'M' || sub[3] == 'U',
+            })
+        })
+        .collect()
+}
+
+pub fn fetch_changes(
+    git_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<Vec<SubmoduleChange>, String> {
+    let output = run_git(git_actor_id, directory, &["status", "--porcelain=v2"])?;
+    Ok(parse_status(&output))
+}
+
+/// Submodules whose pointer the commits between `base` and HEAD move.
+pub fn pointers_moved(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: &str,
+) -> Result<Vec<String>, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--raw", "--no-renames", base, "HEAD"],
+    )?;
+    Ok(parse_gitlinks(&output))
+}
+
+/// Paths of submodules in `git diff --raw` output.
+fn parse_gitlinks(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (meta, path) = line.strip_prefix(':')?.split_once('\t')?;
+            let mut modes = meta.split(' ');
+            let (old_mode, new_mode) = (modes.next()?, modes.next()?);
+            (old_mode == "160000" || new_mode == "160000").then(|| path.to_string())
+        })
+        .collect()
+}
+
+/// Commit workflow rule for the submodules in `changes`; `bumps_allowed` says whether
+/// their pointers may be committed.
+pub fn prompt_fragment(
+    changes: &[SubmoduleChange],
+    config: &SubmoduleConfig,
+    bumps_allowed: bool,
+) -> String {
+    let mut fragment = String::from("\n\nSUBMODULES: These submodules changed:");
+    for change in changes {
+        let mut state = Vec::new();
+        if change.pointer_moved {
+            state.push(format!(
+                "pointer moved from {}",
+                &change.recorded[..change.recorded.len().min(12)]
+            ));
+        }
+        if change.dirty {
+            state.push("uncommitted changes inside".to_string());
+        }
+        fragment.push_str(&format!("\n- {}: {}", change.path, state.join("; ")));
+    }
+    if config.commit_inside {
+        fragment.push_str(
+            "\nCommit changes inside a submodule first, with git -C <path> add and commit and \
+            the same commit rules, then stage its new pointer.",
+        );
+    } else {
+        fragment.push_str("\nLeave uncommitted changes inside submodules alone.");
+    }
+    if bumps_allowed {
+        fragment.push_str(
+            "\nCommit a pointer bump on its own or with the change that needs it, and explain \
+            in the message what it brings in: read git -C <path> log --oneline \
+            <old pointer>..HEAD.",
+        );
+    } else {
+        fragment.push_str(
+            "\nDon't stage or commit submodule pointer changes; leave them in the working tree.",
+        );
+    }
+    fragment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn submodule_changes_and_gitlinks_are_parsed() {
+        let status = "1 .M SC.. 160000 160000 160000 1111111111 1111111111 libs/proto\n\
+            1 .M S.MU 160000 160000 160000 2222222222 2222222222 vendor/ui\n\
+            1 M. N... 100644 100644 100644 3333333333 4444444444 src/main.rs\n\
+            1 M. S... 160000 160000 160000 5555555555 6666666666 tools/lint\n\
+            ? notes.txt\n";
+        let changes = parse_status(status);
+        assert_eq!(changes.len(), 3);
+        assert!(changes[0].pointer_moved && !changes[0].dirty);
+        assert!(!changes[1].pointer_moved && changes[1].dirty);
+        assert!(changes[2].pointer_moved);
+
+        let diff = ":160000 160000 1111111 7777777 M\tlibs/proto\n\
+            :100644 100644 3333333 4444444 M\tsrc/main.rs\n";
+        assert_eq!(parse_gitlinks(diff), vec!["libs/proto"]);
+    }
+}
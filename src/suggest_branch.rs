@@ -0,0 +1,69 @@
+//! `SuggestBranchName`: branch names for a described change from one short model turn,
+//! checked against git's rules and `branch_naming`.
+
+use crate::branch::{self, BranchNameError, BranchNaming};
+
+/// Candidates the model is asked for, and the most returned.
+pub const CANDIDATES: usize = 3;
+
+/// Some variety between the candidates.
+pub const TEMPERATURE: f64 = 0.5;
+
+pub const MAX_TOKENS: u32 = 256;
+
+pub const SYSTEM_PROMPT: &str = "You name git branches. You are given a description of \
+    the work the branch is for. Reply with 3 different branch names, one per line, and \
+    nothing else.";
+
+pub fn prompt(description: &str) -> String {
+    format!(
+        "Suggest branch names for this work:\n\n{}",
+        description.trim()
+    )
+}
+
+/// Valid, distinct names among the lines of the model's reply, normalized where they
+/// break the rules, with the lines that couldn't be made valid.
+pub fn candidates(reply: &str, naming: &BranchNaming) -> (Vec<String>, Vec<BranchNameError>) {
+    let mut names: Vec<String> = Vec::new();
+    let mut rejected = Vec::new();
+    for line in reply.lines() {
+        // Lists come numbered or bulleted, and names in backticks
+        let proposed = line
+            .trim()
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .trim_start_matches(['.', ')', '-', '*'])
+            .trim()
+            .trim_matches('`');
+        if proposed.is_empty() {
+            continue;
+        }
+        match branch::prepare_branch_name(proposed, naming) {
+            Ok(name) if !names.contains(&name) => names.push(name),
+            Ok(_) => {}
+            Err(error) => rejected.push(error),
+        }
+    }
+    names.truncate(CANDIDATES);
+    (names, rejected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_markers_are_dropped_and_names_checked() {
+        let naming = BranchNaming {
+            prefixes: vec!["feature/".to_string(), "fix/".to_string()],
+            pattern: None,
+            max_length: Some(30),
+        };
+        let reply =
+            "1. `feature/nested-lists`\n2. feature/Nested Lists\n\n- fix/list-parser\n* lists\n";
+        let (names, rejected) = candidates(reply, &naming);
+        assert_eq!(names, vec!["feature/nested-lists", "fix/list-parser"]);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].proposed, "lists");
+    }
+}
@@ -0,0 +1,87 @@
+//! `SuggestCommitMessage`: one model turn in a throwaway chat-state actor without
+//! tools, from a summary and the diff of the changes to the commit message alone.
+
+use crate::git_tools::run_git;
+use crate::renames;
+use crate::tool_output::ToolOutputPolicy;
+
+/// Temperature of the turn; commit messages are best kept plain.
+pub const TEMPERATURE: f64 = 0.2;
+
+pub const MAX_TOKENS: u32 = 1024;
+
+pub const SYSTEM_PROMPT: &str = "You write git commit messages. You are given a summary \
+    and the diff of a change. Reply with the commit message only: a subject line in the \
+    imperative mood, and a body after a blank line when the change needs explaining. \
+    Describe renamed and copied files as such, not as deleted and added. No preamble, no \
+    quotes, no code fences.";
+
+/// Diff stats with the renames and copies, and the diff, elided like tool output, of
+/// the staged changes or of every change against HEAD.
+pub fn fetch_changes(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    staged_only: bool,
+    policy: &ToolOutputPolicy,
+) -> Result<(String, String), String> {
+    let target = if staged_only { "--cached" } else { "HEAD" };
+    let stat = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--stat=1000", "-M", "-C", target],
+    )?;
+    if stat.trim().is_empty() {
+        return Err(if staged_only {
+            "Nothing is staged".to_string()
+        } else {
+            "There are no changes".to_string()
+        });
+    }
+    let moves = renames::fetch(git_actor_id, directory, &[target])?;
+    let stat = format!("{}\n{}", stat.trim_end(), renames::render(&moves));
+    let diff = run_git(git_actor_id, directory, &["diff", "-M", "-C", target])?;
+    Ok((stat, policy.elide(&diff)))
+}
+
+/// The only message of the turn.
+pub fn prompt(stat: &str, diff: &str) -> String {
+    format!(
+        "Write the commit message for this change.\n\nSUMMARY:\n{}\n\nDIFF:\n{}",
+        stat.trim_end(),
+        diff.trim_end()
+    )
+}
+
+/// The commit message in the model's reply, without a code fence or quotes around it.
+pub fn extract_message(reply: &str) -> String {
+    let mut message = reply.trim();
+    if let Some(fenced) = message.strip_prefix("```") {
+        // Drop the fence's language, if any
+        let body = fenced.split_once('\n').map_or("", |(_, body)| body);
+        message = body.trim_end().strip_suffix("```").unwrap_or(body).trim();
+    }
+    for quote in ['"', '\''] {
+        if message.len() > 1 && message.starts_with(quote) && message.ends_with(quote) {
+            message = message[1..message.len() - 1].trim();
+        }
+    }
+    message.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fences_and_quotes_are_stripped() {
+        assert_eq!(
+            extract_message("```text\nfeat(parser): add nested lists\n\nBody.\n```\n"),
+            "feat(parser): add nested lists\n\nBody."
+        );
+        assert_eq!(
+            extract_message("\"fix: handle empty input\""),
+            "fix: handle empty input"
+        );
+        assert_eq!(extract_message("  docs: typo  "), "docs: typo");
+    }
+}
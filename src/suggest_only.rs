@@ -0,0 +1,166 @@
+//! Suggest-only mode, for environments where the assistant may not run anything: the
+//! model gets no git tools and suggests the commands instead, in a
+//! `<suggested_commands>` block of its reply, for the client to run itself. Each
+//! suggestion must be a single command; the ones that aren't are reported as rejected.
+
+use serde::{Deserialize, Serialize};
+
+const OPEN: &str = "<suggested_commands>";
+const CLOSE: &str = "</suggested_commands>";
+
+pub const PROMPT_FRAGMENT: &str = "\n\nSUGGEST ONLY: You have no tools to run commands in \
+this session; the user runs them. Wherever your instructions say to run a git command or \
+use a git tool, suggest the command instead and wait for the user to tell you its output. \
+End every reply that suggests commands with a block listing them in the order to run them, \
+as a JSON array:\n<suggested_commands>\n[{\"cmd\": \"git add src/parser.rs\", \"rationale\": \
+\"Stage the parser fix on its own\"}]\n</suggested_commands>\nEach cmd is one command on one \
+line, without &&, ;, pipes, redirections, or command substitution, and each has a short \
+rationale.";
+
+/// A command the model suggests the client run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SuggestedCommand {
+    pub cmd: String,
+    pub rationale: String,
+}
+
+/// A suggestion that isn't a single runnable command, and why.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RejectedSuggestion {
+    pub cmd: String,
+    pub reason: String,
+}
+
+/// The suggestions of one reply.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Suggestions {
+    pub commands: Vec<SuggestedCommand>,
+    pub rejected: Vec<RejectedSuggestion>,
+}
+
+/// Why `cmd` isn't a single command the client can run, if it isn't. Quoted text, such
+/// as a commit message, may hold anything.
+fn problem(cmd: &str) -> Option<&'static str> {
+    if cmd.is_empty() {
+        return Some("the command is empty");
+    }
+    if cmd.contains('\n') {
+        return Some("the command spans several lines");
+    }
+    let mut quote = None;
+    let mut chars = cmd.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"'), '`') => return Some("the command uses command substitution"),
+            (Some('"'), '$') if chars.peek() == Some(&'(') => {
+                return Some("the command uses command substitution")
+            }
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ';' | '&' | '|') => return Some("the command chains several commands"),
+            (None, '<' | '>') => return Some("the command redirects its input or output"),
+            (None, '`') => return Some("the command uses command substitution"),
+            (None, '$') if chars.peek() == Some(&'(') => {
+                return Some("the command uses command substitution")
+            }
+            (None, _) => {}
+        }
+    }
+    if quote.is_some() {
+        return Some("the command has an unclosed quote");
+    }
+    None
+}
+
+/// The suggestions in the last complete `<suggested_commands>` block of `reply`, if it
+/// has one.
+pub fn parse(reply: &str) -> Option<Suggestions> {
+    let start = reply.rfind(OPEN)? + OPEN.len();
+    let end = reply[start..].find(CLOSE)?;
+    let block = reply[start..start + end].trim();
+    let entries: Vec<SuggestedCommand> = match serde_json::from_str(block) {
+        Ok(entries) => entries,
+        Err(e) => {
+            return Some(Suggestions {
+                commands: Vec::new(),
+                rejected: vec![RejectedSuggestion {
+                    cmd: block.to_string(),
+                    reason: format!("the block isn't a JSON array of {{cmd, rationale}}: {}", e),
+                }],
+            })
+        }
+    };
+    let mut suggestions = Suggestions::default();
+    for entry in entries {
+        let cmd = entry.cmd.trim().to_string();
+        let reason = match problem(&cmd) {
+            Some(reason) => Some(reason),
+            None if entry.rationale.trim().is_empty() => Some("the rationale is empty"),
+            None => None,
+        };
+        match reason {
+            Some(reason) => suggestions.rejected.push(RejectedSuggestion {
+                cmd,
+                reason: reason.to_string(),
+            }),
+            None => suggestions.commands.push(SuggestedCommand {
+                cmd,
+                rationale: entry.rationale.trim().to_string(),
+            }),
+        }
+    }
+    Some(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_single_commands_are_suggested() {
+        let reply = "Stage the fix first.\n<suggested_commands>\n[\
+            {\"cmd\": \"git add src/parser.rs\", \"rationale\": \"Stage the fix\"},\
+            {\"cmd\": \"git commit -m 'fix: a; b | c'\", \"rationale\": \"Commit it\"},\
+            {\"cmd\": \"git add . && git push\", \"rationale\": \"Ship it\"},\
+            {\"cmd\": \"git log > log.txt\", \"rationale\": \"Keep the log\"},\
+            {\"cmd\": \"git checkout \\\"$(git rev-parse HEAD~1)\\\"\", \"rationale\": \"Go back\"},\
+            {\"cmd\": \"git status\", \"rationale\": \" \"}\
+            ]\n</suggested_commands>";
+        let suggestions = parse(reply).unwrap();
+        assert_eq!(
+            suggestions.commands,
+            vec![
+                SuggestedCommand {
+                    cmd: "git add src/parser.rs".to_string(),
+                    rationale: "Stage the fix".to_string(),
+                },
+                SuggestedCommand {
+                    cmd: "git commit -m 'fix: a; b | c'".to_string(),
+                    rationale: "Commit it".to_string(),
+                },
+            ]
+        );
+        let reasons: Vec<&str> = suggestions
+            .rejected
+            .iter()
+            .map(|rejected| rejected.reason.as_str())
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                "the command chains several commands",
+                "the command redirects its input or output",
+                "the command uses command substitution",
+                "the rationale is empty",
+            ]
+        );
+
+        assert_eq!(parse("No commands needed."), None);
+        let broken = parse("<suggested_commands>git status</suggested_commands>").unwrap();
+        assert!(broken.commands.is_empty());
+        assert!(broken.rejected[0]
+            .reason
+            .starts_with("the block isn't a JSON array"));
+    }
+}
@@ -0,0 +1,163 @@
+//! `SuggestPrDescription`: a pull request title, body, and labels for the current branch
+//! from one model turn, for scripts that open the pull request themselves.
+
+use crate::git_tools::{ensure_revision, run_git};
+use crate::pr_description::PrDescriptionConfig;
+use crate::renames;
+use crate::tool_output::ToolOutputPolicy;
+use serde::Deserialize;
+
+/// Faithful to the diff, like the pr-description workflow.
+pub const TEMPERATURE: f64 = 0.4;
+
+pub const MAX_TOKENS: u32 = 2048;
+
+pub const SYSTEM_PROMPT: &str = "You write pull request descriptions. You are given the \
+    commits, a summary, and the diff of a branch. Reply with a JSON object only, with a \
+    `title` string, a Markdown `body` string, and a `labels` array of strings. No preamble \
+    and no code fences.";
+
+/// Most labels kept from a reply.
+const MAX_LABELS: usize = 5;
+
+/// What the branch holds against `base`: its commits, the diff stats with the renames
+/// and copies, and the diff, elided like tool output.
+pub struct BranchChanges {
+    pub commits: String,
+    pub stat: String,
+    pub diff: String,
+}
+
+pub fn fetch_changes(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    base: &str,
+    policy: &ToolOutputPolicy,
+) -> Result<BranchChanges, String> {
+    if base.is_empty() {
+        return Err("Invalid base branch: it is empty".to_string());
+    }
+    ensure_revision("base branch", base)?;
+    let commits = run_git(
+        git_actor_id,
+        directory,
+        &[
+            "log",
+            "--reverse",
+            "--format=%h %s",
+            &format!("{}..HEAD", base),
+        ],
+    )?;
+    if commits.trim().is_empty() {
+        return Err(format!("The branch has no commits that aren't on {}", base));
+    }
+    let range = format!("{}...HEAD", base);
+    let stat = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--stat=1000", "-M", "-C", &range],
+    )?;
+    let moves = renames::fetch(git_actor_id, directory, &[&range])?;
+    let diff = run_git(git_actor_id, directory, &["diff", "-M", "-C", &range])?;
+    Ok(BranchChanges {
+        commits,
+        stat: format!("{}\n{}", stat.trim_end(), renames::render(&moves)),
+        diff: policy.elide(&diff),
+    })
+}
+
+/// The only message of the turn, with the template to fill in and the labels to pick
+/// from.
+pub fn prompt(base: &str, changes: &BranchChanges, config: &PrDescriptionConfig) -> String {
+    let mut prompt = format!("Describe the pull request of this branch into {}.", base);
+    match &config.template {
+        Some(template) => prompt.push_str(&format!(
+            "\n\nThe body fills in this template, keeping its headings in order:\n{}",
+            template.trim_end()
+        )),
+        None => prompt.push_str("\n\nThe body has ## Summary, ## Testing, and ## Risk sections."),
+    }
+    if config.labels.is_empty() {
+        prompt.push_str("\n\nSuggest at most 3 short labels, or none.");
+    } else {
+        prompt.push_str(&format!(
+            "\n\nPick labels from: {}. Leave `labels` empty when none applies.",
+            config.labels.join(", ")
+        ));
+    }
+    prompt.push_str(&format!(
+        "\n\nCOMMITS, OLDEST FIRST:\n{}\n\nSUMMARY:\n{}\n\nDIFF:\n{}",
+        changes.commits.trim_end(),
+        changes.stat.trim_end(),
+        changes.diff.trim_end()
+    ));
+    prompt
+}
+
+/// A pull request description, as the model replied it.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct PrSuggestion {
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+/// The description in the model's reply, with labels outside `config.labels` dropped
+/// when it lists any.
+pub fn parse(reply: &str, config: &PrDescriptionConfig) -> Result<PrSuggestion, String> {
+    // Models wrap JSON in fences or prose despite being asked not to
+    let start = reply.find('{');
+    let end = reply.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => return Err("The model replied without a JSON object".to_string()),
+    };
+    let mut suggestion: PrSuggestion = serde_json::from_str(json)
+        .map_err(|e| format!("The model's description is invalid: {}", e))?;
+    suggestion.title = suggestion.title.trim().to_string();
+    suggestion.body = suggestion.body.trim().to_string();
+    if suggestion.title.is_empty() {
+        return Err("The model's description has no title".to_string());
+    }
+    let mut labels: Vec<String> = Vec::new();
+    for label in &suggestion.labels {
+        let label = label.trim();
+        if label.is_empty() || labels.iter().any(|kept| kept.eq_ignore_ascii_case(label)) {
+            continue;
+        }
+        if config.labels.is_empty() {
+            labels.push(label.to_string());
+        } else if let Some(known) = config
+            .labels
+            .iter()
+            .find(|known| known.eq_ignore_ascii_case(label))
+        {
+            labels.push(known.clone());
+        }
+    }
+    labels.truncate(MAX_LABELS);
+    suggestion.labels = labels;
+    Ok(suggestion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_are_parsed_and_labels_checked() {
+        let config = PrDescriptionConfig {
+            labels: vec!["enhancement".to_string(), "bug".to_string()],
+            ..PrDescriptionConfig::default()
+        };
+        let reply = "```json\n{\"title\": \" Parse nested lists \", \"body\": \"## Summary\\nLists nest.\", \
+            \"labels\": [\"Enhancement\", \"parser\", \"enhancement\"]}\n```";
+        let suggestion = parse(reply, &config).unwrap();
+        assert_eq!(suggestion.title, "Parse nested lists");
+        assert_eq!(suggestion.body, "## Summary\nLists nest.");
+        assert_eq!(suggestion.labels, vec!["enhancement"]);
+        assert!(parse("{\"title\": \"\", \"body\": \"\"}", &config).is_err());
+        assert!(parse("No idea", &config).is_err());
+    }
+}
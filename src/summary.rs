@@ -0,0 +1,253 @@
+use crate::git_tools::run_git;
+use crate::logging;
+use crate::renames::{self, FileMove, MoveKind};
+use crate::sanitize::{self, TextKind};
+use std::collections::BTreeMap;
+
+/// Maximum number of file names listed per directory before the rest are only counted.
+const MAX_FILES_PER_DIRECTORY: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChange {
+    pub path: String,
+    /// Short status code: M (modified), A (added), D (deleted), R (renamed),
+    /// C (copied), U (conflicted), ? (untracked)
+    pub status: char,
+    /// Lines changed according to `git diff --numstat`, `None` for binary or unknown files
+    pub lines_changed: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSummary {
+    /// Changed files grouped by parent directory (`.` for the repository root)
+    pub directories: BTreeMap<String, Vec<FileChange>>,
+    /// Renames and copies git detected against HEAD
+    pub moves: Vec<FileMove>,
+}
+
+impl ChangeSummary {
+    /// Build a summary from `git status --porcelain` and
+    /// `git diff --numstat --no-renames` output.
+    pub fn from_git_output(porcelain: &str, numstat: &str) -> Self {
+        let lines_changed = parse_numstat(numstat);
+        let mut summary = ChangeSummary::default();
+
+        for line in porcelain.lines() {
+            if line.starts_with("## ") {
+                continue;
+            }
+            let (codes, path) = match (line.get(..2), line.get(3..)) {
+                (Some(codes), Some(path)) if !path.is_empty() => (codes, path),
+                _ => continue,
+            };
+            let mut codes = codes.chars();
+            let (x, y) = (codes.next().unwrap_or(' '), codes.next().unwrap_or(' '));
+            let status = match (x, y) {
+                ('?', '?') => '?',
+                ('!', '!') => continue,
+                ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => 'U',
+                (' ', y) => y,
+                (x, _) => x,
+            };
+            // Renames and copies are reported as "old -> new"
+            let path = path.rsplit(" -> ").next().unwrap_or(path);
+            let path = path.trim_matches('"').to_string();
+
+            let file = FileChange {
+                lines_changed: lines_changed.get(&path).copied(),
+                // Paths end up in the prompt; keep hidden characters visible
+                path: sanitize::escape(&path, TextKind::Name),
+                status,
+            };
+            summary
+                .directories
+                .entry(parent_directory(&path))
+                .or_default()
+                .push(file);
+        }
+        summary
+    }
+
+    /// Mark the files `moves` created as renamed or copied, and drop the deleted sides of
+    /// renames, which the moves already list.
+    pub fn with_moves(mut self, moves: Vec<FileMove>) -> Self {
+        for file_move in &moves {
+            let to = sanitize::escape(&file_move.to, TextKind::Name);
+            let from = sanitize::escape(&file_move.from, TextKind::Name);
+            for files in self.directories.values_mut() {
+                if file_move.kind == MoveKind::Rename {
+                    files.retain(|file| !(file.path == from && file.status == 'D'));
+                }
+                if let Some(file) = files.iter_mut().find(|file| file.path == to) {
+                    file.status = match file_move.kind {
+                        MoveKind::Rename => 'R',
+                        MoveKind::Copy => 'C',
+                    };
+                }
+            }
+        }
+        self.directories.retain(|_, files| !files.is_empty());
+        self.moves = moves;
+        self
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.directories.values().map(Vec::len).sum()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.files().map(|file| file.path.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.directories.is_empty()
+    }
+
+    /// Render the compact context block handed to the model.
+    pub fn render(&self) -> String {
+        if self.is_empty() {
+            return "CHANGE SUMMARY: working tree clean".to_string();
+        }
+
+        let total_lines: u32 = self.files().filter_map(|file| file.lines_changed).sum();
+        let mut block = format!(
+            "CHANGE SUMMARY: {} files in {} directories, {} lines changed ({})",
+            self.file_count(),
+            self.directories.len(),
+            total_lines,
+            status_counts(self.files())
+        );
+
+        for (directory, files) in &self.directories {
+            let lines: u32 = files.iter().filter_map(|file| file.lines_changed).sum();
+            block.push_str(&format!(
+                "\n{}/ - {} files, {} lines ({})",
+                directory,
+                files.len(),
+                lines,
+                status_counts(files.iter())
+            ));
+
+            let listed: Vec<String> = files
+                .iter()
+                .take(MAX_FILES_PER_DIRECTORY)
+                .map(|file| {
+                    let name = file.path.rsplit('/').next().unwrap_or(&file.path);
+                    match file.lines_changed {
+                        Some(lines) => format!("{} {} ({})", file.status, name, lines),
+                        None => format!("{} {}", file.status, name),
+                    }
+                })
+                .collect();
+            block.push_str(&format!("\n  {}", listed.join(", ")));
+            if files.len() > MAX_FILES_PER_DIRECTORY {
+                block.push_str(&format!(
+                    ", ... and {} more",
+                    files.len() - MAX_FILES_PER_DIRECTORY
+                ));
+            }
+        }
+        if !self.moves.is_empty() {
+            block.push('\n');
+            block.push_str(&renames::render(&self.moves));
+            block.push_str(
+                "\nStage both paths of a rename in the same commit so git records it as one, \
+                and describe it as a rename or copy in the message, not as a deletion and an \
+                addition.",
+            );
+        }
+        block
+    }
+
+    fn files(&self) -> impl Iterator<Item = &FileChange> {
+        self.directories.values().flatten()
+    }
+}
+
+/// Collect status and diff stats through the git tools and summarize them.
+pub fn fetch_change_summary(
+    git_actor_id: &str,
+    directory: Option<&str>,
+) -> Result<ChangeSummary, String> {
+    let porcelain = run_git(git_actor_id, directory, &["status", "--porcelain"])?;
+    // Without renames a renamed file is counted under its new path, as status lists it
+    let numstat = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--numstat", "--no-renames", "HEAD"],
+    )
+    .unwrap_or_else(|e| {
+        // Repositories without commits have no HEAD to diff against
+        logging::warn(&format!("Could not collect diff stats: {}", e));
+        String::new()
+    });
+    let moves = renames::fetch(git_actor_id, directory, &["HEAD"]).unwrap_or_else(|e| {
+        logging::warn(&format!("Could not detect renames: {}", e));
+        Vec::new()
+    });
+    Ok(ChangeSummary::from_git_output(&porcelain, &numstat).with_moves(moves))
+}
+
+/// Map each path in `git diff --numstat` output to its added and deleted lines; binary
+/// files, counted as `-`, are left out.
+fn parse_numstat(numstat: &str) -> BTreeMap<String, u32> {
+    numstat
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (added, deleted, path) = (fields.next()?, fields.next()?, fields.next()?);
+            let count = added.parse::<u32>().ok()? + deleted.parse::<u32>().ok()?;
+            Some((path.trim_matches('"').to_string(), count))
+        })
+        .collect()
+}
+
+fn parent_directory(path: &str) -> String {
+    match path.trim_end_matches('/').rsplit_once('/') {
+        Some((directory, _)) => directory.to_string(),
+        None => ".".to_string(),
+    }
+}
+
+fn status_counts<'a>(files: impl Iterator<Item = &'a FileChange>) -> String {
+    let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for file in files {
+        let label = match file.status {
+            'A' => "added",
+            'D' => "deleted",
+            'R' => "renamed",
+            'C' => "copied",
+            'U' => "conflicted",
+            '?' => "untracked",
+            _ => "modified",
+        };
+        *counts.entry(label).or_default() += 1;
+    }
+    counts
+        .iter()
+        .map(|(label, count)| format!("{} {}", count, label))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renamed_files_keep_their_line_counts() {
+        let porcelain = "R  src/old_name.rs -> src/new_name.rs\n M README.md\nA  assets/logo.png\n";
+        let numstat = "0\t12\tsrc/old_name.rs\n14\t0\tsrc/new_name.rs\n3\t1\tREADME.md\n\
+            -\t-\tassets/logo.png\n";
+        let summary = ChangeSummary::from_git_output(porcelain, numstat);
+        let lines_changed = |path: &str| {
+            summary
+                .files()
+                .find(|file| file.path == path)
+                .map(|file| file.lines_changed)
+        };
+        assert_eq!(lines_changed("src/new_name.rs"), Some(Some(14)));
+        assert_eq!(lines_changed("README.md"), Some(Some(4)));
+        assert_eq!(lines_changed("assets/logo.png"), Some(None));
+    }
+}
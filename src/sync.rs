@@ -0,0 +1,393 @@
+//! The `sync` workflow: the assistant fetches, compares `HEAD` with its upstream, and
+//! recommends how to catch up under the `sync` policy. Once `ExecuteSync` is confirmed,
+//! it merges or rebases and hands each stop on conflicts to the model to resolve.
+
+use crate::divergence::{Divergence, DEFAULT_UPSTREAM};
+use crate::git_tools::{ensure_revision, run_git};
+use crate::repo_state;
+use crate::sanitize::{self, TextKind};
+use serde::{Deserialize, Serialize};
+
+/// How the `sync` policy catches up with the upstream.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncPolicy {
+    /// Rebase unpublished local commits, up to `max_rebase_commits`, and merge otherwise
+    #[default]
+    Auto,
+    Merge,
+    /// Rebase unless the local commits are already on a remote
+    Rebase,
+}
+
+/// The `sync` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyncConfig {
+    #[serde(default)]
+    pub policy: SyncPolicy,
+    /// Branch to catch up with; the tracking branch when omitted
+    #[serde(default)]
+    pub upstream: Option<String>,
+    /// Remote fetched first; the current branch's remote when omitted
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// Local commits `auto` still rebases
+    #[serde(default = "default_max_rebase_commits")]
+    pub max_rebase_commits: usize,
+}
+
+fn default_max_rebase_commits() -> usize {
+    10
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            policy: SyncPolicy::default(),
+            upstream: None,
+            remote: None,
+            max_rebase_commits: default_max_rebase_commits(),
+        }
+    }
+}
+
+impl SyncConfig {
+    pub fn upstream(&self) -> &str {
+        self.upstream.as_deref().unwrap_or(DEFAULT_UPSTREAM)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncStrategy {
+    FastForward,
+    Merge,
+    Rebase,
+}
+
+impl SyncStrategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SyncStrategy::FastForward => "fast-forward",
+            SyncStrategy::Merge => "merge",
+            SyncStrategy::Rebase => "rebase",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncPhase {
+    /// Waiting for `ExecuteSync`
+    Proposed,
+    /// The merge or rebase stopped on conflicts and waits for them to be resolved
+    Conflicted,
+    Finished,
+    /// git refused the merge or rebase for a reason other than conflicts, in `error`
+    Failed,
+    Aborted,
+}
+
+/// The sync of the `sync` workflow, kept after it finishes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyncState {
+    pub phase: SyncPhase,
+    /// The upstream branch, e.g. `origin/main`
+    pub upstream: String,
+    pub divergence: Divergence,
+    /// Whether some local commits are already on a remote
+    pub published: bool,
+    /// `None` when `HEAD` already has every upstream commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recommended: Option<SyncStrategy>,
+    pub reason: String,
+    /// The strategy being run, once `ExecuteSync` is confirmed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<SyncStrategy>,
+    /// `HEAD` before the sync
+    pub original_head: String,
+    /// Files left conflicted at the last stop
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicted_files: Vec<String>,
+    /// `HEAD` once finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Where running or continuing the strategy left the repository.
+pub enum SyncOutcome {
+    Done { head: String },
+    Conflicted { files: Vec<String> },
+}
+
+/// The strategy the policy picks for `divergence`, and why, or `None` with the reason
+/// when there is nothing to catch up with.
+pub fn recommend(
+    config: &SyncConfig,
+    divergence: &Divergence,
+    published: bool,
+) -> (Option<SyncStrategy>, String) {
+    if divergence.behind == 0 {
+        return (
+            None,
+            format!("HEAD already has every commit of {}", divergence.upstream),
+        );
+    }
+    if divergence.ahead == 0 {
+        return (
+            Some(SyncStrategy::FastForward),
+            "there are no local commits, so HEAD can move forward".to_string(),
+        );
+    }
+    let (strategy, reason) = match config.policy {
+        SyncPolicy::Merge => (SyncStrategy::Merge, "the policy is to merge".to_string()),
+        _ if published => (
+            SyncStrategy::Merge,
+            "some local commits are already on a remote, and rebasing them would need a \
+            force push"
+                .to_string(),
+        ),
+        SyncPolicy::Rebase => (
+            SyncStrategy::Rebase,
+            "the policy is to rebase unpublished commits".to_string(),
+        ),
+        SyncPolicy::Auto if divergence.ahead > config.max_rebase_commits => (
+            SyncStrategy::Merge,
+            format!(
+                "{} local commits are more than the {} worth replaying",
+                divergence.ahead, config.max_rebase_commits
+            ),
+        ),
+        SyncPolicy::Auto => (
+            SyncStrategy::Rebase,
+            "the local commits are unpublished and few enough to replay for a linear history"
+                .to_string(),
+        ),
+    };
+    (Some(strategy), reason)
+}
+
+/// Fetch `remote`, or the current branch's remote.
+pub fn fetch(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    remote: Option<&str>,
+) -> Result<(), String> {
+    let mut args = vec!["fetch", "--prune"];
+    if let Some(remote) = remote {
+        ensure_revision("remote", remote)?;
+        args.push(remote);
+    }
+    run_git(git_actor_id, directory, &args).map(|_| ())
+}
+
+/// The branch `upstream` names, e.g. `origin/main` for `@{upstream}`.
+pub fn resolve_upstream(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    upstream: &str,
+) -> Result<String, String> {
+    ensure_revision("upstream", upstream)?;
+    let name = run_git(
+        git_actor_id,
+        directory,
+        &["rev-parse", "--abbrev-ref", upstream],
+    )
+    .map_err(|e| format!("{} doesn't name a branch to sync with: {}", upstream, e))?;
+    Ok(name.trim().to_string())
+}
+
+pub fn head(git_actor_id: &str, directory: Option<&str>) -> Result<String, String> {
+    let sha = run_git(git_actor_id, directory, &["rev-parse", "HEAD"])?;
+    Ok(sha.trim().to_string())
+}
+
+/// The git command that carries out `strategy` and the one that continues it.
+fn command(strategy: SyncStrategy) -> &'static str {
+    match strategy {
+        SyncStrategy::FastForward | SyncStrategy::Merge => "merge",
+        SyncStrategy::Rebase => "rebase",
+    }
+}
+
+/// Why a merge or rebase that git refused stopped: conflicts, or a failure, after which
+/// it is aborted.
+fn stopped(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    strategy: SyncStrategy,
+    error: String,
+) -> Result<SyncOutcome, String> {
+    let files = repo_state::conflicted_files(git_actor_id, directory)?;
+    if !files.is_empty() {
+        return Ok(SyncOutcome::Conflicted { files });
+    }
+    if strategy != SyncStrategy::FastForward {
+        let _ = run_git(git_actor_id, directory, &[command(strategy), "--abort"]);
+    }
+    Err(error)
+}
+
+/// Bring `HEAD` up to `upstream` with `strategy`.
+pub fn run(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    strategy: SyncStrategy,
+    upstream: &str,
+) -> Result<SyncOutcome, String> {
+    let args: &[&str] = match strategy {
+        SyncStrategy::FastForward => &["merge", "--ff-only", upstream],
+        SyncStrategy::Merge => &["merge", "--no-edit", upstream],
+        SyncStrategy::Rebase => &["rebase", upstream],
+    };
+    match run_git(git_actor_id, directory, args) {
+        Ok(_) => head(git_actor_id, directory).map(|head| SyncOutcome::Done { head }),
+        Err(e) => stopped(git_actor_id, directory, strategy, e),
+    }
+}
+
+/// Go on with a merge or rebase once its conflicts are resolved and staged. A rebase
+/// may stop again on a later commit.
+pub fn continue_sync(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    strategy: SyncStrategy,
+) -> Result<SyncOutcome, String> {
+    let files = repo_state::conflicted_files(git_actor_id, directory)?;
+    if !files.is_empty() {
+        return Ok(SyncOutcome::Conflicted { files });
+    }
+    match run_git(
+        git_actor_id,
+        directory,
+        &["-c", "core.editor=true", command(strategy), "--continue"],
+    ) {
+        Ok(_) => head(git_actor_id, directory).map(|head| SyncOutcome::Done { head }),
+        Err(e) => stopped(git_actor_id, directory, strategy, e),
+    }
+}
+
+/// Undo a merge or rebase stopped on conflicts, back to the original `HEAD`.
+pub fn abort(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    strategy: SyncStrategy,
+) -> Result<(), String> {
+    run_git(git_actor_id, directory, &[command(strategy), "--abort"]).map(|_| ())
+}
+
+/// Block for the workflow's opening message: the divergence and the recommendation.
+pub fn describe(state: &SyncState) -> String {
+    let mut text = format!(
+        "\n\nSYNC WITH {} (fetched just now):\n{}",
+        sanitize::escape(&state.upstream, TextKind::Name),
+        sanitize::escape(&state.divergence.describe(), TextKind::Message)
+    );
+    match state.recommended {
+        Some(strategy) => text.push_str(&format!(
+            "\n\nRECOMMENDED: {}, because {}.\n\nExplain the divergence and the \
+            recommendation, and what the alternative would mean here. The assistant runs \
+            the sync once the user approves it with ExecuteSync.",
+            strategy.name(),
+            state.reason
+        )),
+        None => text.push_str(&format!(
+            "\n\nNothing to sync: {}. Tell the user, then use the task_complete tool.",
+            state.reason
+        )),
+    }
+    text
+}
+
+/// Message handing a sync stopped on conflicts to the model.
+pub fn conflict_message(state: &SyncState) -> String {
+    let strategy = state
+        .strategy
+        .map(|strategy| strategy.name())
+        .unwrap_or("sync");
+    format!(
+        "The {} with {} stopped on conflicts in: {}. Explain what each side changed and \
+        why they conflict, then resolve each file and stage it with git add. Don't commit, \
+        and don't run git merge or git rebase yourself; once nothing is left unresolved, \
+        the assistant continues the {}.",
+        strategy,
+        sanitize::escape(&state.upstream, TextKind::Name),
+        state.conflicted_files.join(", "),
+        strategy
+    )
+}
+
+/// Message asking the model to report on the finished sync.
+pub fn finished_message(state: &SyncState) -> String {
+    format!(
+        "The sync is finished: {} with {} moved HEAD from {} to {}.\n\nSummarize what came \
+        in from the upstream and how the conflicts, if any, were resolved, then use the \
+        task_complete tool with that summary.",
+        state
+            .strategy
+            .map(|strategy| strategy.name())
+            .unwrap_or("sync"),
+        sanitize::escape(&state.upstream, TextKind::Name),
+        state.original_head,
+        state.head.as_deref().unwrap_or("HEAD")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn divergence(ahead: usize, behind: usize) -> Divergence {
+        Divergence {
+            upstream: "origin/main".to_string(),
+            ahead,
+            behind,
+            merge_base: Some("abc1234".to_string()),
+            first_local: None,
+            first_upstream: None,
+        }
+    }
+
+    #[test]
+    fn the_policy_picks_the_strategy() {
+        let auto = SyncConfig::default();
+        assert_eq!(recommend(&auto, &divergence(3, 0), false).0, None);
+        assert_eq!(
+            recommend(&auto, &divergence(0, 5), false).0,
+            Some(SyncStrategy::FastForward)
+        );
+        assert_eq!(
+            recommend(&auto, &divergence(3, 5), false).0,
+            Some(SyncStrategy::Rebase)
+        );
+        assert_eq!(
+            recommend(&auto, &divergence(3, 5), true).0,
+            Some(SyncStrategy::Merge)
+        );
+        assert_eq!(
+            recommend(&auto, &divergence(11, 5), false).0,
+            Some(SyncStrategy::Merge)
+        );
+
+        let rebase: SyncConfig = serde_json::from_value(serde_json::json!({
+            "policy": "rebase",
+            "upstream": "origin/develop"
+        }))
+        .unwrap();
+        assert_eq!(rebase.upstream(), "origin/develop");
+        assert_eq!(
+            recommend(&rebase, &divergence(40, 5), false).0,
+            Some(SyncStrategy::Rebase)
+        );
+        let merge = SyncConfig {
+            policy: SyncPolicy::Merge,
+            ..SyncConfig::default()
+        };
+        assert_eq!(
+            recommend(&merge, &divergence(1, 1), false).0,
+            Some(SyncStrategy::Merge)
+        );
+    }
+}
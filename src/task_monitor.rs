@@ -0,0 +1,58 @@
+//! The task monitor as a child of the assistant, when `supervise_task_monitor` is set.
+//! Sessions get its actor id instead of spawning monitors of their own, and a monitor
+//! that fails is replaced.
+
+use crate::bindings::theater::simple::supervisor::spawn;
+use crate::logging;
+use serde_json::{to_vec, Value};
+
+/// Replacements of a failed task monitor before the assistant stops supervising it.
+pub const MAX_RESTARTS: u32 = 3;
+
+/// Spawn a task monitor that reports to `management_actor`.
+pub fn spawn_task_monitor(manifest_path: &str, management_actor: &str) -> Result<String, String> {
+    logging::debug("Spawning task monitor...");
+    let init_state = to_vec(&serde_json::json!({ "management_actor": management_actor }))
+        .map_err(|e| format!("Failed to serialize task monitor state: {}", e))?;
+    match spawn(manifest_path, Some(&init_state)) {
+        Ok(actor_id) => {
+            logging::info(&format!("Task monitor spawned: {}", actor_id));
+            Ok(actor_id)
+        }
+        Err(e) => {
+            logging::error(&format!("Failed to spawn task monitor: {:?}", e));
+            Err(format!("Spawn failed: {:?}", e))
+        }
+    }
+}
+
+/// Point the servers in `mcp_servers` that use actor `old` at actor `new`. Returns
+/// whether any did.
+pub fn replace_actor_id(mcp_servers: &mut Value, old: &str, new: &str) -> bool {
+    let mut replaced = false;
+    for server in mcp_servers.as_array_mut().into_iter().flatten() {
+        if server["actor_id"].as_str() == Some(old) {
+            server["actor_id"] = Value::String(new.to_string());
+            replaced = true;
+        }
+    }
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn only_servers_of_the_old_actor_move() {
+        let mut servers = json!([
+            {"actor_id": null, "actor": {"manifest_path": "git.toml"}},
+            {"actor_id": "monitor-1", "actor": {"manifest_path": "task-monitor.toml"}}
+        ]);
+        assert!(replace_actor_id(&mut servers, "monitor-1", "monitor-2"));
+        assert_eq!(servers[0]["actor_id"], Value::Null);
+        assert_eq!(servers[1]["actor_id"], "monitor-2");
+        assert!(!replace_actor_id(&mut servers, "monitor-1", "monitor-3"));
+    }
+}
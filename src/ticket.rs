@@ -0,0 +1,118 @@
+//! The `ticket` config: the issue tracker ticket the work belongs to, e.g. `PROJ-123`,
+//! given outright with `ticket_id` or taken from the branch name at init. The model is
+//! told to reference it in commit messages and pull request descriptions, and
+//! `GetStatus` reports it.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The `ticket` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TicketConfig {
+    /// Regex matched against the branch name; the ticket id is its first capture group,
+    /// or the whole match when it has none
+    #[serde(default = "default_pattern")]
+    pub pattern: String,
+    /// Ticket to use whatever the branch is called
+    #[serde(default)]
+    pub ticket_id: Option<String>,
+}
+
+fn default_pattern() -> String {
+    r"[A-Z][A-Z0-9]+-\d+".to_string()
+}
+
+impl Default for TicketConfig {
+    fn default() -> Self {
+        Self {
+            pattern: default_pattern(),
+            ticket_id: None,
+        }
+    }
+}
+
+/// The ticket the assistant works on, kept in state.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ticket {
+    pub id: String,
+    /// Branch the id was taken from; unset when `ticket_id` gave it
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
+impl TicketConfig {
+    /// The `ticket_id` override, when it is set.
+    pub fn configured(&self) -> Option<Ticket> {
+        let id = self.ticket_id.as_deref().map(str::trim)?;
+        (!id.is_empty()).then(|| Ticket {
+            id: id.to_string(),
+            branch: None,
+        })
+    }
+
+    /// Match the pattern against `branch`. Returns `None` when it doesn't match, e.g. on
+    /// `main`.
+    pub fn extract(&self, branch: &str) -> Result<Option<Ticket>, String> {
+        let regex = Regex::new(&self.pattern)
+            .map_err(|e| format!("invalid ticket pattern {}: {}", self.pattern, e))?;
+        let Some(captures) = regex.captures(branch) else {
+            return Ok(None);
+        };
+        let id = captures
+            .iter()
+            .skip(1)
+            .flatten()
+            .next()
+            .or_else(|| captures.get(0))
+            .map(|found| found.as_str())
+            .unwrap_or_default();
+        Ok((!id.is_empty()).then(|| Ticket {
+            id: id.to_string(),
+            branch: Some(branch.to_string()),
+        }))
+    }
+}
+
+impl Ticket {
+    pub fn prompt_fragment(&self) -> String {
+        let origin = match &self.branch {
+            Some(branch) => format!(" (from the branch name, {})", branch),
+            None => String::new(),
+        };
+        format!(
+            "\n\nTICKET: This work belongs to ticket {}{}. Reference it in every commit \
+            message, in the form the commit conventions use, or as a `Refs: {}` trailer \
+            when they don't say, and in pull request titles and descriptions. Don't ask \
+            the user for it.",
+            self.id, origin, self.id
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticket_is_taken_from_the_branch_unless_configured() {
+        let config = TicketConfig::default();
+        let ticket = config.extract("feature/PROJ-123-tabs").unwrap().unwrap();
+        assert_eq!(ticket.id, "PROJ-123");
+        assert_eq!(ticket.branch.as_deref(), Some("feature/PROJ-123-tabs"));
+        assert_eq!(config.extract("main").unwrap(), None);
+        assert_eq!(config.configured(), None);
+
+        let config = TicketConfig {
+            pattern: r"issue-(\d+)".to_string(),
+            ticket_id: Some(" GH-42 ".to_string()),
+        };
+        assert_eq!(config.extract("issue-7-fix").unwrap().unwrap().id, "7");
+        assert_eq!(config.configured().unwrap().id, "GH-42");
+        assert!(TicketConfig {
+            pattern: "(".to_string(),
+            ticket_id: None,
+        }
+        .extract("main")
+        .is_err());
+    }
+}
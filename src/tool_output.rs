@@ -0,0 +1,126 @@
+//! Elision of oversized git tool output before it reaches the model, so one
+//! `git log -p` can't fill the context window. The model's git tools are routed
+//! through the assistant when `tool_output` is set.
+
+use crate::context::{DiffBudget, DiffTrimmer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The `tool_output` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ToolOutputPolicy {
+    /// Bytes of one tool result passed to the model
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+    /// Lines kept from the start of output over `max_bytes`
+    #[serde(default = "default_head_lines")]
+    pub head_lines: usize,
+    /// Lines kept from the end of output over `max_bytes`
+    #[serde(default = "default_tail_lines")]
+    pub tail_lines: usize,
+    /// Lines of each file's diff kept in output with a unified diff
+    #[serde(default = "default_max_lines_per_file")]
+    pub max_lines_per_file: usize,
+}
+
+fn default_max_bytes() -> usize {
+    30_000
+}
+
+fn default_head_lines() -> usize {
+    200
+}
+
+fn default_tail_lines() -> usize {
+    50
+}
+
+fn default_max_lines_per_file() -> usize {
+    200
+}
+
+impl Default for ToolOutputPolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_max_bytes(),
+            head_lines: default_head_lines(),
+            tail_lines: default_tail_lines(),
+            max_lines_per_file: default_max_lines_per_file(),
+        }
+    }
+}
+
+impl ToolOutputPolicy {
+    /// `text` cut down to `max_bytes`. Diffs lose the end of long files and then whole
+    /// files, generated and binary ones first; what is still too long keeps its first
+    /// and last lines. Every cut is noted in the text.
+    pub fn elide(&self, text: &str) -> String {
+        if text.len() <= self.max_bytes {
+            return text.to_string();
+        }
+        let budget = DiffBudget {
+            max_lines_per_file: self.max_lines_per_file,
+            max_bytes: self.max_bytes,
+            ..DiffBudget::default()
+        };
+        let text = match DiffTrimmer::new(&budget) {
+            Ok(trimmer) => trimmer.trim(text),
+            Err(_) => text.to_string(),
+        };
+        if text.len() <= self.max_bytes {
+            return text;
+        }
+        self.keep_head_and_tail(&text)
+    }
+
+    fn keep_head_and_tail(&self, text: &str) -> String {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut elided = if lines.len() > self.head_lines + self.tail_lines {
+            let omitted = &lines[self.head_lines..lines.len() - self.tail_lines];
+            let omitted_bytes: usize = omitted.iter().map(|line| line.len() + 1).sum();
+            let mut kept = lines[..self.head_lines].to_vec();
+            let note = format!(
+                "[... {} lines ({} bytes) omitted; narrow the command, e.g. with a path, \
+                -n, or --stat, to see them ...]",
+                omitted.len(),
+                omitted_bytes
+            );
+            kept.push(&note);
+            kept.extend_from_slice(&lines[lines.len() - self.tail_lines..]);
+            kept.join("\n")
+        } else {
+            text.to_string()
+        };
+        // Few but very long lines
+        if elided.len() > self.max_bytes {
+            let mut end = self.max_bytes;
+            while !elided.is_char_boundary(end) {
+                end -= 1;
+            }
+            let omitted_bytes = elided.len() - end;
+            elided.truncate(end);
+            elided.push_str(&format!("\n[... {} more bytes omitted ...]", omitted_bytes));
+        }
+        elided
+    }
+
+    /// Elide the text blocks of an MCP tool result in place. Returns the bytes taken
+    /// out.
+    pub fn elide_result(&self, result: &mut Value) -> usize {
+        let blocks = match result.get_mut("content").and_then(Value::as_array_mut) {
+            Some(blocks) => blocks,
+            None => return 0,
+        };
+        let mut removed = 0;
+        for block in blocks {
+            let text = match block.get("text").and_then(Value::as_str) {
+                Some(text) if text.len() > self.max_bytes => text,
+                _ => continue,
+            };
+            let elided = self.elide(text);
+            removed += text.len().saturating_sub(elided.len());
+            block["text"] = Value::String(elided);
+        }
+        removed
+    }
+}
@@ -0,0 +1,248 @@
+//! Rendering of a session's transcript for `ExportTranscript`. User-role messages are
+//! labeled with who wrote them, so what the person said stands apart from what the
+//! assistant sent in their place.
+
+use crate::protocol::{HistoryMessage, Provenance};
+use genai_types::messages::Role;
+use genai_types::MessageContent;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TranscriptFormat {
+    Markdown,
+    /// The messages as the chat-state actor holds them, with their ids and the
+    /// provenance of every user-role message
+    Json,
+    Text,
+    /// The assistant's latest reply alone, in Markdown, for workflows such as `onboard`
+    /// whose last reply is a document
+    Document,
+}
+
+impl TranscriptFormat {
+    pub fn media_type(self) -> &'static str {
+        match self {
+            TranscriptFormat::Markdown => "text/markdown",
+            TranscriptFormat::Json => "application/json",
+            TranscriptFormat::Text => "text/plain",
+            TranscriptFormat::Document => "text/markdown",
+        }
+    }
+}
+
+/// `messages`, oldest first, as a document in `format`.
+pub fn render(format: TranscriptFormat, messages: &[HistoryMessage]) -> Result<String, String> {
+    match format {
+        TranscriptFormat::Json => {
+            let messages: Vec<HistoryMessage> = messages
+                .iter()
+                .map(|entry| HistoryMessage {
+                    provenance: entry.source(),
+                    ..entry.clone()
+                })
+                .collect();
+            serde_json::to_string_pretty(&messages)
+                .map_err(|e| format!("Failed to serialize transcript: {}", e))
+        }
+        TranscriptFormat::Markdown => Ok(render_markdown(messages)),
+        TranscriptFormat::Text => Ok(render_text(messages)),
+        TranscriptFormat::Document => render_document(messages),
+    }
+}
+
+/// Who wrote the message: its source for user-role messages, otherwise its role.
+fn author(entry: &HistoryMessage) -> &'static str {
+    match (entry.source(), &entry.message.role) {
+        (Some(Provenance::User), _) | (None, Role::User) => "User",
+        (Some(Provenance::Supervisor), _) => "Supervisor",
+        (Some(Provenance::Tool), _) => "Tool",
+        (Some(Provenance::Workflow), _) => "Workflow",
+        (None, Role::Assistant) => "Assistant",
+        (None, Role::System) => "System",
+    }
+}
+
+fn render_markdown(messages: &[HistoryMessage]) -> String {
+    let mut document = String::from("# Transcript\n");
+    for entry in messages {
+        document.push_str(&format!("\n## {}\n", author(entry)));
+        for content in &entry.message.content {
+            match content {
+                MessageContent::Text { text } => {
+                    document.push_str(&format!("\n{}\n", text.trim_end()));
+                }
+                MessageContent::ToolUse { id, name, input } => {
+                    document.push_str(&format!(
+                        "\n**Tool call** `{}` ({})\n\n{}\n",
+                        name,
+                        id,
+                        fenced("json", &pretty(input))
+                    ));
+                }
+                MessageContent::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => {
+                    let label = if is_error.unwrap_or(false) {
+                        "Tool error"
+                    } else {
+                        "Tool result"
+                    };
+                    document.push_str(&format!(
+                        "\n**{}** ({})\n\n{}\n",
+                        label,
+                        tool_use_id,
+                        fenced("", &tool_result_text(content))
+                    ));
+                }
+            }
+        }
+    }
+    document
+}
+
+fn render_text(messages: &[HistoryMessage]) -> String {
+    let mut blocks = Vec::new();
+    for entry in messages {
+        let mut block = format!("{}:", author(entry).to_uppercase());
+        for content in &entry.message.content {
+            match content {
+                MessageContent::Text { text } => {
+                    block.push_str(&format!("\n{}", text.trim_end()));
+                }
+                MessageContent::ToolUse { name, input, .. } => {
+                    block.push_str(&format!("\n[tool call {}] {}", name, input));
+                }
+                MessageContent::ToolResult {
+                    content, is_error, ..
+                } => {
+                    let label = if is_error.unwrap_or(false) {
+                        "tool error"
+                    } else {
+                        "tool result"
+                    };
+                    block.push_str(&format!("\n[{}]\n{}", label, tool_result_text(content)));
+                }
+            }
+        }
+        blocks.push(block);
+    }
+    let mut document = blocks.join("\n\n");
+    document.push('\n');
+    document
+}
+
+fn render_document(messages: &[HistoryMessage]) -> Result<String, String> {
+    let text = messages
+        .iter()
+        .rev()
+        .filter(|entry| matches!(entry.message.role, Role::Assistant))
+        .map(|entry| {
+            entry
+                .message
+                .content
+                .iter()
+                .filter_map(|content| match content {
+                    MessageContent::Text { text } => Some(text.trim()),
+                    _ => None,
+                })
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        })
+        .find(|text| !text.is_empty())
+        .ok_or("The assistant hasn't replied with text yet")?;
+    Ok(format!("{}\n", text))
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// `text` in a code fence longer than any backtick run inside it.
+fn fenced(language: &str, text: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, language, text.trim_end(), fence)
+}
+
+/// Text parts of a tool result; other parts are named by their type.
+fn tool_result_text<T: Serialize>(content: &[T]) -> String {
+    content
+        .iter()
+        .map(|part| {
+            let part = serde_json::to_value(part).unwrap_or_default();
+            match part["text"].as_str() {
+                Some(text) => text.trim_end().to_string(),
+                None => format!("[{}]", part["type"].as_str().unwrap_or("content")),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn transcript() -> Vec<HistoryMessage> {
+        serde_json::from_value(json!([
+            {"id": "m0", "provenance": "workflow", "message": {"role": "user", "content": [
+                {"type": "text", "text": "Commit the staged changes."}
+            ]}},
+            {"id": "m1", "provenance": "user", "message": {"role": "user", "content": [
+                {"type": "text", "text": "Commit the parser changes"}
+            ]}},
+            {"id": "m2", "message": {"role": "assistant", "content": [
+                {"type": "text", "text": "Checking the status."},
+                {"type": "tool_use", "id": "t1", "name": "git_status", "input": {"repo": "."}}
+            ]}},
+            {"id": "m3", "message": {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t1", "is_error": null, "content": [
+                    {"type": "text", "text": "M src/parser.rs\n```"}
+                ]}
+            ]}}
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn markdown_shows_tool_calls_and_results() {
+        let document = render(TranscriptFormat::Markdown, &transcript()).unwrap();
+        assert!(document.starts_with(
+            "# Transcript\n\n## Workflow\n\nCommit the staged changes.\n\n## User\n\nCommit"
+        ));
+        assert!(document
+            .contains("**Tool call** `git_status` (t1)\n\n```json\n{\n  \"repo\": \".\"\n}\n```"));
+        // The result holds a fence of its own
+        assert!(document
+            .contains("## Tool\n\n**Tool result** (t1)\n\n````\nM src/parser.rs\n```\n````"));
+    }
+
+    #[test]
+    fn text_and_json_keep_every_message() {
+        let document = render(TranscriptFormat::Text, &transcript()).unwrap();
+        assert!(document.starts_with("WORKFLOW:\nCommit the staged changes.\n\nUSER:\nCommit"));
+        assert!(document.contains("[tool call git_status] {\"repo\":\".\"}"));
+        assert!(document.contains("[tool result]\nM src/parser.rs"));
+
+        let document = render(TranscriptFormat::Json, &transcript()).unwrap();
+        let messages: Vec<HistoryMessage> = serde_json::from_str(&document).unwrap();
+        assert_eq!(messages.len(), 4);
+        // Tool results are labeled even when the chat-state actor didn't tag them
+        assert_eq!(messages[3].provenance, Some(Provenance::Tool));
+        assert_eq!(messages[2].provenance, None);
+
+        let document = render(TranscriptFormat::Document, &transcript()).unwrap();
+        assert_eq!(document, "Checking the status.\n");
+    }
+}
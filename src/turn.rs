@@ -0,0 +1,123 @@
+//! Turn-taking between clients of the same session: `AcquireTurn` claims a session for
+//! one client, and until the claim is released or its lease runs out, `AddMessage` and
+//! `SendAndAwait` from other clients fail with `turn-taken`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Lease of a claim when `AcquireTurn` doesn't set one, so a client that goes away
+/// doesn't keep the session forever.
+pub const DEFAULT_LEASE_SECS: u64 = 300;
+
+/// A client's claim on a session.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TurnClaim {
+    pub session_id: String,
+    pub client: String,
+    /// Seconds since the epoch
+    pub acquired_at: u64,
+    pub expires_at: u64,
+}
+
+/// The claim on `session_id`, unless it has expired.
+pub fn holder<'a>(
+    claims: &'a BTreeMap<String, TurnClaim>,
+    session_id: &str,
+    now: u64,
+) -> Option<&'a TurnClaim> {
+    claims
+        .get(session_id)
+        .filter(|claim| claim.expires_at > now)
+}
+
+/// Claim `session_id` for `client`, or renew its claim.
+pub fn acquire(
+    claims: &mut BTreeMap<String, TurnClaim>,
+    session_id: &str,
+    client: &str,
+    lease_secs: u64,
+    now: u64,
+) -> Result<TurnClaim, String> {
+    if client.trim().is_empty() {
+        return Err("A turn needs a client name".to_string());
+    }
+    if lease_secs == 0 {
+        return Err("lease_secs must be at least 1".to_string());
+    }
+    let acquired_at = match holder(claims, session_id, now) {
+        Some(claim) if claim.client != client => return Err(taken_message(claim)),
+        Some(claim) => claim.acquired_at,
+        None => now,
+    };
+    let claim = TurnClaim {
+        session_id: session_id.to_string(),
+        client: client.to_string(),
+        acquired_at,
+        expires_at: now.saturating_add(lease_secs),
+    };
+    claims.insert(session_id.to_string(), claim.clone());
+    Ok(claim)
+}
+
+/// Give up `client`'s claim on `session_id`; releasing an unclaimed session is fine.
+pub fn release(
+    claims: &mut BTreeMap<String, TurnClaim>,
+    session_id: &str,
+    client: &str,
+    now: u64,
+) -> Result<(), String> {
+    if let Some(claim) = holder(claims, session_id, now) {
+        if claim.client != client {
+            return Err(taken_message(claim));
+        }
+    }
+    claims.remove(session_id);
+    Ok(())
+}
+
+/// Whether `client` may send to `session_id`: anyone may while it isn't claimed.
+pub fn check(
+    claims: &BTreeMap<String, TurnClaim>,
+    session_id: &str,
+    client: Option<&str>,
+    now: u64,
+) -> Result<(), String> {
+    match holder(claims, session_id, now) {
+        Some(claim) if Some(claim.client.as_str()) != client => Err(taken_message(claim)),
+        _ => Ok(()),
+    }
+}
+
+fn taken_message(claim: &TurnClaim) -> String {
+    format!(
+        "Session {} is claimed by {} until {}",
+        claim.session_id, claim.client, claim.expires_at
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claims_block_other_clients_until_released_or_expired() {
+        let mut claims = BTreeMap::new();
+        let claim = acquire(&mut claims, "default", "cli", 60, 100).unwrap();
+        assert_eq!(claim.expires_at, 160);
+        assert!(acquire(&mut claims, "default", "editor", 60, 110).is_err());
+        assert!(check(&claims, "default", Some("editor"), 110).is_err());
+        assert!(check(&claims, "default", None, 110).is_err());
+        assert!(check(&claims, "default", Some("cli"), 110).is_ok());
+        assert!(check(&claims, "review", Some("editor"), 110).is_ok());
+
+        // Renewing keeps when the claim was first acquired
+        let renewed = acquire(&mut claims, "default", "cli", 60, 150).unwrap();
+        assert_eq!((renewed.acquired_at, renewed.expires_at), (100, 210));
+
+        assert!(release(&mut claims, "default", "editor", 160).is_err());
+        assert!(check(&claims, "default", Some("editor"), 210).is_ok());
+        assert!(acquire(&mut claims, "default", "editor", 60, 210).is_ok());
+        release(&mut claims, "default", "editor", 220).unwrap();
+        assert!(claims.is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use crate::workflow::Workflow;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The `max_turns` section of the assistant config. A turn is one generation of the
+/// default session during an auto-initiated workflow.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TurnLimits {
+    /// Turns any workflow may take; unlimited when unset
+    pub default: Option<u32>,
+    /// Limits for single workflows, keyed by workflow name, replacing `default`
+    #[serde(default)]
+    pub workflows: BTreeMap<String, u32>,
+    /// Turns the model gets after being told to wrap up, before the workflow is stopped
+    #[serde(default = "default_wrap_up_turns")]
+    pub wrap_up_turns: u32,
+}
+
+fn default_wrap_up_turns() -> u32 {
+    2
+}
+
+impl TurnLimits {
+    /// Turns `workflow` may take before the model is told to wrap up.
+    pub fn limit(&self, workflow: &Workflow) -> Option<u32> {
+        self.workflows
+            .get(workflow.name())
+            .copied()
+            .or(self.default)
+    }
+}
+
+/// The `workflow_timeout_secs` section of the assistant config: how long an
+/// auto-initiated workflow may run, from its start, before it is stopped.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorkflowTimeouts {
+    /// Seconds any workflow may run; unlimited when unset
+    pub default: Option<u64>,
+    /// Timeouts for single workflows, keyed by workflow name, replacing `default`
+    #[serde(default)]
+    pub workflows: BTreeMap<String, u64>,
+}
+
+impl WorkflowTimeouts {
+    /// Seconds `workflow` may run before it is stopped.
+    pub fn limit(&self, workflow: &Workflow) -> Option<u64> {
+        self.workflows
+            .get(workflow.name())
+            .copied()
+            .or(self.default)
+    }
+}
+
+/// A workflow stopped because it went past its turn limit or its timeout.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorkflowStall {
+    pub workflow: Workflow,
+    pub turns: u32,
+    /// Set when the workflow ran out of turns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+    /// Seconds the workflow ran, set when it ran out of time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Message telling the model its turns are used up.
+pub fn wrap_up_message(max_turns: u32) -> String {
+    format!(
+        "You have used the {} turns this task may take. Wrap up now: finish or undo the \
+        step you are on, leave the repository in a consistent state, and call the \
+        task_complete tool with a summary of what is done and what is left.",
+        max_turns
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workflow_limits_replace_the_default() {
+        let timeouts: WorkflowTimeouts = serde_json::from_value(serde_json::json!({
+            "default": 1800,
+            "workflows": {"commit": 600, "nightly": 60}
+        }))
+        .unwrap();
+        assert_eq!(timeouts.limit(&Workflow::Commit), Some(600));
+        assert_eq!(timeouts.limit(&Workflow::Review), Some(1800));
+        assert_eq!(
+            Workflow::unknown_names(timeouts.workflows.keys()),
+            vec!["nightly"]
+        );
+    }
+}
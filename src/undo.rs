@@ -0,0 +1,199 @@
+//! `UndoLastTurn`: the user's last exchange with the model is dropped from the default
+//! session's transcript, and what the turn did to the repository is backed out. Where
+//! the repository stood is marked before each of the user's turns; commits made since
+//! are undone with a soft reset, in the commit workflow only, and files staged since are
+//! unstaged.
+
+use crate::git_tools::run_git;
+use crate::protocol::{HistoryMessage, Provenance};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Action of the git step, held for `Confirm` before it runs.
+pub const UNDO_TURN: &str = "undo-turn";
+
+/// Where the repository stood before the user's latest turn.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TurnMark {
+    /// `None` before the first commit
+    pub head: Option<String>,
+    pub staged: Vec<String>,
+    /// When the turn started, in seconds since the epoch
+    pub at: u64,
+}
+
+/// The git step that backs out a turn.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Compensation {
+    /// Commit `HEAD` is soft reset to, with the number of commits that undoes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_to: Option<String>,
+    #[serde(default)]
+    pub commits: usize,
+    /// Files unstaged afterwards
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unstage: Vec<String>,
+}
+
+/// What `UndoLastTurn` undid.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TurnUndo {
+    /// The user's message the turn started with; it and every later message are gone
+    pub message_id: String,
+    pub messages_removed: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compensation: Option<Compensation>,
+}
+
+fn lines(output: &str) -> impl Iterator<Item = String> + '_ {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+pub fn mark(git_actor_id: &str, directory: Option<&str>, now: u64) -> Result<TurnMark, String> {
+    // Fails before the first commit
+    let head = run_git(
+        git_actor_id,
+        directory,
+        &["rev-parse", "--verify", "-q", "HEAD"],
+    )
+    .ok()
+    .map(|sha| sha.trim().to_string())
+    .filter(|sha| !sha.is_empty());
+    let staged = run_git(
+        git_actor_id,
+        directory,
+        &["diff", "--cached", "--name-only"],
+    )?;
+    Ok(TurnMark {
+        head,
+        staged: lines(&staged).collect(),
+        at: now,
+    })
+}
+
+/// The message the user's last turn started with, and its index in `history`.
+pub fn last_user_turn(history: &[HistoryMessage]) -> Option<(usize, &HistoryMessage)> {
+    history
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, entry)| entry.source() == Some(Provenance::User))
+}
+
+/// The git step that brings the repository back to `mark`. Commits are only undone with
+/// `undo_commits`, and only when `HEAD` still descends from the marked one.
+pub fn compensation(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    mark: &TurnMark,
+    undo_commits: bool,
+) -> Result<Compensation, String> {
+    let git = |args: &[&str]| run_git(git_actor_id, directory, args);
+    let staged_before: BTreeSet<&String> = mark.staged.iter().collect();
+    let mut changed: BTreeSet<String> =
+        lines(&git(&["diff", "--cached", "--name-only"])?).collect();
+    let mut compensation = Compensation::default();
+
+    if let (true, Some(marked)) = (undo_commits, &mark.head) {
+        let head = git(&["rev-parse", "HEAD"])?.trim().to_string();
+        let descends = git(&["merge-base", "--is-ancestor", marked, "HEAD"]).is_ok();
+        if head != *marked && descends {
+            let range = format!("{}..HEAD", marked);
+            let count = git(&["rev-list", "--count", &range])?;
+            compensation.commits = count
+                .trim()
+                .parse()
+                .map_err(|_| format!("Unexpected rev-list output: {}", count))?;
+            compensation.reset_to = Some(marked.clone());
+            // Their changes are staged again by the soft reset
+            changed.extend(lines(&git(&["diff", "--name-only", marked, "HEAD"])?));
+        }
+    }
+    compensation.unstage = changed
+        .into_iter()
+        .filter(|path| !staged_before.contains(path))
+        .collect();
+    Ok(compensation)
+}
+
+impl Compensation {
+    pub fn is_empty(&self) -> bool {
+        self.reset_to.is_none() && self.unstage.is_empty()
+    }
+
+    pub fn describe(&self) -> String {
+        let mut steps = Vec::new();
+        if let Some(reset_to) = &self.reset_to {
+            steps.push(format!(
+                "undo {} commits with git reset --soft {}",
+                self.commits, reset_to
+            ));
+        }
+        if !self.unstage.is_empty() {
+            steps.push(format!("unstage {}", self.unstage.join(", ")));
+        }
+        steps.join(", then ")
+    }
+
+    pub fn run(&self, git_actor_id: &str, directory: Option<&str>) -> Result<(), String> {
+        if let Some(reset_to) = &self.reset_to {
+            run_git(git_actor_id, directory, &["reset", "--soft", reset_to])?;
+        }
+        if !self.unstage.is_empty() {
+            let mut args = vec!["reset", "-q", "--"];
+            args.extend(self.unstage.iter().map(String::as_str));
+            run_git(git_actor_id, directory, &args)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genai_types::messages::Role;
+    use genai_types::{Message, MessageContent};
+
+    fn entry(id: &str, role: Role, provenance: Option<Provenance>) -> HistoryMessage {
+        HistoryMessage {
+            id: id.to_string(),
+            message: Message {
+                role,
+                content: vec![MessageContent::Text {
+                    text: id.to_string(),
+                }],
+            },
+            provenance,
+        }
+    }
+
+    #[test]
+    fn the_turn_starts_at_the_users_last_message() {
+        let history = vec![
+            entry("m1", Role::User, Some(Provenance::Workflow)),
+            entry("m2", Role::Assistant, None),
+            entry("m3", Role::User, Some(Provenance::User)),
+            entry("m4", Role::Assistant, None),
+            entry("m5", Role::User, Some(Provenance::Supervisor)),
+            entry("m6", Role::Assistant, None),
+        ];
+        let (index, message) = last_user_turn(&history).unwrap();
+        assert_eq!((index, message.id.as_str()), (2, "m3"));
+        assert!(last_user_turn(&history[..2]).is_none());
+
+        let compensation = Compensation {
+            reset_to: Some("0123abcd".to_string()),
+            commits: 2,
+            unstage: vec!["src/lib.rs".to_string()],
+        };
+        assert_eq!(
+            compensation.describe(),
+            "undo 2 commits with git reset --soft 0123abcd, then unstage src/lib.rs"
+        );
+        assert!(Compensation::default().is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::model_fallback::ModelConfig;
+use crate::protocol::external::GitChatRequest;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Price of a model, in dollars per million tokens.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// Prices of models missing from `budget.prices`, matched by model name prefix.
+const DEFAULT_PRICES: &[(&str, ModelPrice)] = &[
+    (
+        "claude-opus-4",
+        ModelPrice {
+            input_per_million: 15.0,
+            output_per_million: 75.0,
+        },
+    ),
+    (
+        "claude-sonnet-4",
+        ModelPrice {
+            input_per_million: 3.0,
+            output_per_million: 15.0,
+        },
+    ),
+    (
+        "claude-3-7-sonnet",
+        ModelPrice {
+            input_per_million: 3.0,
+            output_per_million: 15.0,
+        },
+    ),
+    (
+        "claude-3-5-haiku",
+        ModelPrice {
+            input_per_million: 0.8,
+            output_per_million: 4.0,
+        },
+    ),
+];
+
+/// The `budget` section of the assistant config. The limits cover every session over
+/// the assistant's lifetime; once one is reached, running generations are cancelled
+/// and no new ones start.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Budget {
+    /// Most input and output tokens, together
+    pub max_tokens: Option<u64>,
+    /// Most estimated cost, in dollars
+    pub max_cost: Option<f64>,
+    /// Prices by model name, used before the built-in ones
+    #[serde(default)]
+    pub prices: BTreeMap<String, ModelPrice>,
+    /// Cheaper models to switch to as usage grows, in the order their thresholds are
+    /// crossed
+    #[serde(default)]
+    pub downgrades: Vec<ModelDowngrade>,
+}
+
+/// A model sessions switch to once usage crosses a threshold.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModelDowngrade {
+    /// Input and output tokens, together, after which the model is used
+    pub after_tokens: Option<u64>,
+    /// Estimated cost, in dollars, after which the model is used
+    pub after_cost: Option<f64>,
+    pub model: ModelConfig,
+}
+
+impl ModelDowngrade {
+    pub fn is_reached(&self, total: &Usage) -> bool {
+        self.after_tokens
+            .is_some_and(|after_tokens| total.total_tokens() >= after_tokens)
+            || self
+                .after_cost
+                .is_some_and(|after_cost| total.cost >= after_cost)
+    }
+}
+
+impl Budget {
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        if let Some(price) = self.prices.get(model) {
+            return Some(*price);
+        }
+        DEFAULT_PRICES
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map(|(_, price)| *price)
+    }
+
+    /// How many of `downgrades` apply: one past the last one whose threshold is reached.
+    pub fn downgrade_level(&self, total: &Usage) -> usize {
+        self.downgrades
+            .iter()
+            .rposition(|downgrade| downgrade.is_reached(total))
+            .map_or(0, |index| index + 1)
+    }
+
+    /// The model of downgrade level `level`; `None` at level 0.
+    pub fn downgrade_model(&self, level: usize) -> Option<&ModelConfig> {
+        level
+            .checked_sub(1)
+            .and_then(|index| self.downgrades.get(index))
+            .map(|downgrade| &downgrade.model)
+    }
+
+    pub fn is_exceeded(&self, total: &Usage) -> bool {
+        self.max_tokens
+            .is_some_and(|max_tokens| total.total_tokens() >= max_tokens)
+            || self.max_cost.is_some_and(|max_cost| total.cost >= max_cost)
+    }
+}
+
+/// Tokens used and what they cost.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated cost in dollars
+    pub cost: f64,
+    /// Tokens of models without a known price, which `cost` leaves out
+    pub unpriced_tokens: u64,
+}
+
+impl Usage {
+    /// Usage of one generation by a model with price `price`.
+    pub fn measure(input_tokens: u64, output_tokens: u64, price: Option<ModelPrice>) -> Self {
+        match price {
+            Some(price) => Self {
+                input_tokens,
+                output_tokens,
+                cost: (input_tokens as f64 * price.input_per_million
+                    + output_tokens as f64 * price.output_per_million)
+                    / 1_000_000.0,
+                unpriced_tokens: 0,
+            },
+            None => Self {
+                input_tokens,
+                output_tokens,
+                cost: 0.0,
+                unpriced_tokens: input_tokens + output_tokens,
+            },
+        }
+    }
+
+    pub fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost += other.cost;
+        self.unpriced_tokens += other.unpriced_tokens;
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Usage per session and overall, for `GetUsage`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UsageReport {
+    /// Keyed by session id, including closed sessions
+    pub sessions: BTreeMap<String, Usage>,
+    pub total: Usage,
+    pub max_tokens: Option<u64>,
+    pub max_cost: Option<f64>,
+    pub budget_exceeded: bool,
+}
+
+/// Sum of the usage of every session.
+pub fn total(sessions: &BTreeMap<String, Usage>) -> Usage {
+    let mut total = Usage::default();
+    for usage in sessions.values() {
+        total.add(usage);
+    }
+    total
+}
+
+/// Whether `request` asks the model for a completion.
+pub fn starts_generation(request: &GitChatRequest) -> bool {
+    matches!(
+        request,
+        GitChatRequest::AddMessage { .. }
+            | GitChatRequest::AddMessages { .. }
+            | GitChatRequest::SendAndAwait { .. }
+            | GitChatRequest::SuggestCommitMessage { .. }
+            | GitChatRequest::SuggestBranchName { .. }
+            | GitChatRequest::SuggestPrDescription { .. }
+            | GitChatRequest::StartChat { .. }
+            | GitChatRequest::SetWorkflow { start: true, .. }
+            | GitChatRequest::ApproveCommitPlan { .. }
+            | GitChatRequest::SkipCherryPick
+            | GitChatRequest::ExecuteSync { .. }
+    )
+}
@@ -0,0 +1,273 @@
+//! Verification of a workflow that changes the repository, once it reports done: the
+//! assistant checks with git that the working tree is clean, that the commit workflow
+//! made its commits, with the planned messages when a commit plan was approved, and
+//! that the branch the workflow started on is still checked out. Discrepancies don't
+//! fail the workflow; its result is completed with warnings and lists them.
+
+use crate::commit_plan::CommitPlan;
+use crate::git_tools::run_git;
+use crate::postcondition::{self, Postcondition};
+use crate::protocol::external::CreatedCommit;
+use serde::{Deserialize, Serialize};
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum VerificationCheck {
+    /// No changes, staged or not, and no untracked files
+    CleanStatus,
+    /// The commit workflow made commits, with the approved plan's messages if any
+    CommitsPresent,
+    /// The branch checked out when the workflow started still is
+    BranchUnchanged,
+}
+
+/// Verification is on by default.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VerificationConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Checks left out, e.g. `clean-status` where workflows leave changes behind
+    #[serde(default)]
+    pub skip: Vec<VerificationCheck>,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            skip: Vec::new(),
+        }
+    }
+}
+
+/// One check and what it found.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CheckOutcome {
+    pub check: VerificationCheck,
+    pub passed: bool,
+    /// What was found instead, for a check that didn't pass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Every check run on a completed workflow.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Verification {
+    /// Seconds since the epoch
+    pub verified_at: u64,
+    pub checks: Vec<CheckOutcome>,
+}
+
+/// How a workflow ended, in its result.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompletionStatus {
+    #[default]
+    Completed,
+    /// Completed, but verification found discrepancies
+    CompletedWithWarnings,
+    Failed,
+}
+
+/// What the workflow is verified against.
+pub struct Expectations<'a> {
+    /// Branch checked out when the workflow started; unset when it wasn't recorded
+    pub branch: Option<&'a str>,
+    /// Commits to check, newest first; unset for workflows that needn't commit
+    pub commits: Option<&'a [CreatedCommit]>,
+    pub plan: Option<&'a CommitPlan>,
+}
+
+impl VerificationCheck {
+    pub fn name(self) -> &'static str {
+        match self {
+            VerificationCheck::CleanStatus => "clean-status",
+            VerificationCheck::CommitsPresent => "commits-present",
+            VerificationCheck::BranchUnchanged => "branch-unchanged",
+        }
+    }
+}
+
+impl CheckOutcome {
+    fn new(check: VerificationCheck, detail: Option<String>) -> Self {
+        Self {
+            check,
+            passed: detail.is_none(),
+            detail,
+        }
+    }
+}
+
+impl Verification {
+    /// The checks that didn't pass, one line each.
+    pub fn warnings(&self) -> Vec<String> {
+        self.checks
+            .iter()
+            .filter(|outcome| !outcome.passed)
+            .map(|outcome| match &outcome.detail {
+                Some(detail) => format!("{}: {}", outcome.check.name(), detail),
+                None => outcome.check.name().to_string(),
+            })
+            .collect()
+    }
+}
+
+pub fn status(error: Option<&str>, verification: Option<&Verification>) -> CompletionStatus {
+    match (error, verification) {
+        (Some(_), _) => CompletionStatus::Failed,
+        (None, Some(verification)) if verification.checks.iter().any(|check| !check.passed) => {
+            CompletionStatus::CompletedWithWarnings
+        }
+        (None, _) => CompletionStatus::Completed,
+    }
+}
+
+/// What's wrong with `commits`, newest first, given the approved `plan`.
+fn commits_detail(commits: &[CreatedCommit], plan: Option<&CommitPlan>) -> Option<String> {
+    if commits.is_empty() {
+        return Some("no commit was created".to_string());
+    }
+    let plan = plan.filter(|plan| plan.approved)?;
+    let expected: Vec<&str> = plan
+        .commits
+        .iter()
+        .map(|planned| planned.message.lines().next().unwrap_or_default().trim())
+        .collect();
+    let found: Vec<&str> = commits
+        .iter()
+        .rev()
+        .map(|commit| commit.subject.trim())
+        .collect();
+    (expected != found).then(|| {
+        format!(
+            "the approved plan has the commits \"{}\", but the commits are \"{}\"",
+            expected.join("\", \""),
+            found.join("\", \"")
+        )
+    })
+}
+
+/// Run the checks `config` doesn't skip. A check that can't be run doesn't pass.
+pub fn verify(
+    config: &VerificationConfig,
+    expectations: &Expectations,
+    git_actor_id: &str,
+    directory: Option<&str>,
+    now: u64,
+) -> Verification {
+    let mut checks = Vec::new();
+    let wanted = |check| !config.skip.contains(&check);
+    if wanted(VerificationCheck::CleanStatus) {
+        let failures = postcondition::check(
+            &[Postcondition::CleanWorktree],
+            git_actor_id,
+            directory,
+            None,
+        );
+        let detail = failures.into_iter().next().map(|failure| failure.detail);
+        checks.push(CheckOutcome::new(VerificationCheck::CleanStatus, detail));
+    }
+    if let (true, Some(commits)) = (
+        wanted(VerificationCheck::CommitsPresent),
+        expectations.commits,
+    ) {
+        let detail = commits_detail(commits, expectations.plan);
+        checks.push(CheckOutcome::new(VerificationCheck::CommitsPresent, detail));
+    }
+    if let (true, Some(branch)) = (
+        wanted(VerificationCheck::BranchUnchanged),
+        expectations.branch,
+    ) {
+        let detail = match run_git(
+            git_actor_id,
+            directory,
+            &["rev-parse", "--abbrev-ref", "HEAD"],
+        ) {
+            Ok(current) if current.trim() == branch => None,
+            Ok(current) => Some(format!(
+                "{} is checked out instead of {}",
+                current.trim(),
+                branch
+            )),
+            Err(e) => Some(format!("could not be checked: {}", e)),
+        };
+        checks.push(CheckOutcome::new(
+            VerificationCheck::BranchUnchanged,
+            detail,
+        ));
+    }
+    Verification {
+        verified_at: now,
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commit_plan::PlannedCommit;
+
+    fn commit(sha: &str, subject: &str) -> CreatedCommit {
+        CreatedCommit {
+            sha: sha.to_string(),
+            subject: subject.to_string(),
+            committed_at: None,
+        }
+    }
+
+    #[test]
+    fn commits_are_checked_against_the_approved_plan() {
+        let planned = |message: &str| PlannedCommit {
+            message: message.to_string(),
+            files: vec!["src/lib.rs".to_string()],
+            hunks: Vec::new(),
+        };
+        let mut plan = CommitPlan {
+            commits: vec![
+                planned("feat: add parser\n\nWith tests"),
+                planned("docs: describe parser"),
+            ],
+            approved: true,
+            misspellings: Vec::new(),
+        };
+        // Newest first, as git log lists them
+        let commits = vec![
+            commit("bbb", "docs: describe parser"),
+            commit("aaa", "feat: add parser"),
+        ];
+        assert_eq!(commits_detail(&commits, Some(&plan)), None);
+        assert_eq!(
+            commits_detail(&commits[..1], Some(&plan)).unwrap(),
+            "the approved plan has the commits \"feat: add parser\", \"docs: describe parser\", \
+             but the commits are \"docs: describe parser\""
+        );
+        assert_eq!(commits_detail(&[], None).unwrap(), "no commit was created");
+        plan.approved = false;
+        assert_eq!(commits_detail(&commits[..1], Some(&plan)), None);
+
+        let verification = Verification {
+            verified_at: 100,
+            checks: vec![
+                CheckOutcome::new(VerificationCheck::CommitsPresent, None),
+                CheckOutcome::new(
+                    VerificationCheck::BranchUnchanged,
+                    Some("main is checked out instead of feature".to_string()),
+                ),
+            ],
+        };
+        assert_eq!(
+            status(None, Some(&verification)),
+            CompletionStatus::CompletedWithWarnings
+        );
+        assert_eq!(
+            verification.warnings(),
+            vec!["branch-unchanged: main is checked out instead of feature"]
+        );
+        assert_eq!(status(Some("failed"), None), CompletionStatus::Failed);
+        assert_eq!(status(None, None), CompletionStatus::Completed);
+    }
+}
@@ -0,0 +1,157 @@
+//! The `notifications` config: events POSTed as JSON to a webhook through the runtime's
+//! HTTP client, so a team hears when an autonomous run finishes, waits for an approval,
+//! or fails, e.g. in a Slack channel. A webhook that can't be reached is logged; the
+//! event still goes to everyone else.
+
+use crate::bindings::theater::simple::http_client::send_http;
+use crate::bindings::theater::simple::http_types::HttpRequest;
+use crate::protocol::external::{EventKind, GitChatEvent, WorkflowState};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum WebhookFormat {
+    /// The event as subscribers get it
+    #[default]
+    Event,
+    /// `{"text": ...}` with a line about the event, as Slack's incoming webhooks take
+    Slack,
+}
+
+/// The `notifications` section of the assistant config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NotificationsConfig {
+    pub url: String,
+    /// Kinds of events posted
+    #[serde(default = "default_events")]
+    pub events: BTreeSet<EventKind>,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// Headers sent with every post, e.g. `Authorization`
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+fn default_events() -> BTreeSet<EventKind> {
+    BTreeSet::from([
+        EventKind::WorkflowStateChanged,
+        EventKind::ApprovalRequested,
+        EventKind::SessionFailed,
+    ])
+}
+
+/// A line about `event` for chat webhooks.
+pub fn summary(event: &GitChatEvent) -> String {
+    match event {
+        GitChatEvent::WorkflowStateChanged { workflow, state } => {
+            let workflow = workflow
+                .as_ref()
+                .map(|workflow| format!("The {} workflow", workflow.name()))
+                .unwrap_or_else(|| "The workflow".to_string());
+            let state = match state {
+                WorkflowState::Started => "started",
+                WorkflowState::Finished => "finished",
+                WorkflowState::Halted => "was halted by the dead-man switch",
+                WorkflowState::Resumed => "resumed",
+                WorkflowState::Stalled => "stalled",
+                WorkflowState::Failed => "failed its postconditions",
+            };
+            format!("{} {}", workflow, state)
+        }
+        GitChatEvent::ApprovalRequested { confirmation } => format!(
+            "Approval needed in session {}: {} (confirm {})",
+            confirmation.session_id, confirmation.description, confirmation.id
+        ),
+        GitChatEvent::SessionFailed { failure } => format!(
+            "Session {} failed after {} crashes",
+            failure.session_id,
+            failure.crashes.len()
+        ),
+        GitChatEvent::ChildCrashed { child_id, error } => {
+            format!("Child actor {} crashed: {}", child_id, error)
+        }
+        other => {
+            let kind = serde_json::to_value(other.kind())
+                .ok()
+                .and_then(|kind| kind.as_str().map(str::to_string))
+                .unwrap_or_default();
+            format!("Event {}", kind)
+        }
+    }
+}
+
+impl NotificationsConfig {
+    /// The post for `event`, whose envelope as subscribers get it is `envelope`.
+    fn request(&self, event: &GitChatEvent, envelope: &[u8]) -> HttpRequest {
+        let body = match self.format {
+            WebhookFormat::Event => envelope.to_vec(),
+            WebhookFormat::Slack => serde_json::json!({
+                "text": format!("git-chat-assistant: {}", summary(event))
+            })
+            .to_string()
+            .into_bytes(),
+        };
+        let mut headers = vec![("Content-Type".to_string(), "application/json".to_string())];
+        headers.extend(
+            self.headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        HttpRequest {
+            method: "POST".to_string(),
+            uri: self.url.clone(),
+            headers,
+            body: Some(body),
+        }
+    }
+
+    /// Post `event` to the webhook.
+    pub fn post(&self, event: &GitChatEvent, envelope: &[u8]) -> Result<(), String> {
+        let response = send_http(&self.request(event, envelope))?;
+        if !(200..300).contains(&response.status) {
+            return Err(format!("the webhook answered {}", response.status));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::Workflow;
+
+    #[test]
+    fn slack_posts_carry_a_line_about_the_event() {
+        let config: NotificationsConfig = serde_json::from_value(serde_json::json!({
+            "url": "https://hooks.slack.com/services/T000/B000/XXXX",
+            "format": "slack",
+            "headers": {"X-Team": "platform"}
+        }))
+        .unwrap();
+        assert!(config.events.contains(&EventKind::ApprovalRequested));
+
+        let event = GitChatEvent::WorkflowStateChanged {
+            workflow: Some(Workflow::Commit),
+            state: WorkflowState::Finished,
+        };
+        let request = config.request(&event, b"{}");
+        assert_eq!(request.method, "POST");
+        assert_eq!(
+            request.body.unwrap(),
+            br#"{"text":"git-chat-assistant: The commit workflow finished"}"#.to_vec()
+        );
+        assert!(request
+            .headers
+            .contains(&("X-Team".to_string(), "platform".to_string())));
+
+        let raw = NotificationsConfig {
+            format: WebhookFormat::Event,
+            ..config
+        };
+        assert_eq!(
+            raw.request(&event, b"{\"v\":1}").body.unwrap(),
+            b"{\"v\":1}"
+        );
+    }
+}
@@ -0,0 +1,529 @@
+use serde::{Deserialize, Serialize};
+
+/// A named git workflow the assistant can be started with.
+///
+/// Workflows are configured by name (`"workflow": "commit"`); names that are not
+/// recognised are kept as `Other` so the session still starts with the generic prompt.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "String", into = "String")]
+pub enum Workflow {
+    Commit,
+    Review,
+    Rebase,
+    Analyze,
+    Cleanup,
+    MergeConflict,
+    Changelog,
+    PrDescription,
+    Bisect,
+    StashTriage,
+    BranchCleanup,
+    Amend,
+    TagAndRelease,
+    HookReview,
+    BlameExplain,
+    CherryPick,
+    Onboard,
+    Sync,
+    Other(String),
+}
+
+impl From<String> for Workflow {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "commit" => Workflow::Commit,
+            "review" => Workflow::Review,
+            "rebase" => Workflow::Rebase,
+            "analyze" => Workflow::Analyze,
+            "cleanup" => Workflow::Cleanup,
+            "merge-conflict" => Workflow::MergeConflict,
+            "changelog" => Workflow::Changelog,
+            "pr-description" => Workflow::PrDescription,
+            "bisect" => Workflow::Bisect,
+            "stash-triage" => Workflow::StashTriage,
+            "branch-cleanup" => Workflow::BranchCleanup,
+            "amend" => Workflow::Amend,
+            "tag-and-release" => Workflow::TagAndRelease,
+            "hook-review" => Workflow::HookReview,
+            "blame-explain" => Workflow::BlameExplain,
+            "cherry-pick" => Workflow::CherryPick,
+            "onboard" => Workflow::Onboard,
+            "sync" => Workflow::Sync,
+            _ => Workflow::Other(name),
+        }
+    }
+}
+
+impl From<Workflow> for String {
+    fn from(workflow: Workflow) -> Self {
+        workflow.name().to_string()
+    }
+}
+
+impl Workflow {
+    /// Names of every workflow with its own prompt.
+    pub const KNOWN_NAMES: &'static [&'static str] = &[
+        "commit",
+        "review",
+        "rebase",
+        "analyze",
+        "cleanup",
+        "merge-conflict",
+        "changelog",
+        "pr-description",
+        "bisect",
+        "stash-triage",
+        "branch-cleanup",
+        "amend",
+        "tag-and-release",
+        "hook-review",
+        "blame-explain",
+        "cherry-pick",
+        "onboard",
+        "sync",
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Workflow::Commit => "commit",
+            Workflow::Review => "review",
+            Workflow::Rebase => "rebase",
+            Workflow::Analyze => "analyze",
+            Workflow::Cleanup => "cleanup",
+            Workflow::MergeConflict => "merge-conflict",
+            Workflow::Changelog => "changelog",
+            Workflow::PrDescription => "pr-description",
+            Workflow::Bisect => "bisect",
+            Workflow::StashTriage => "stash-triage",
+            Workflow::BranchCleanup => "branch-cleanup",
+            Workflow::Amend => "amend",
+            Workflow::TagAndRelease => "tag-and-release",
+            Workflow::HookReview => "hook-review",
+            Workflow::BlameExplain => "blame-explain",
+            Workflow::CherryPick => "cherry-pick",
+            Workflow::Onboard => "onboard",
+            Workflow::Sync => "sync",
+            Workflow::Other(name) => name,
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Workflow::Other(_))
+    }
+
+    /// The names that aren't known workflows, e.g. among the keys of a per-workflow
+    /// config section.
+    pub fn unknown_names<'a>(names: impl IntoIterator<Item = &'a String>) -> Vec<&'a str> {
+        names
+            .into_iter()
+            .map(String::as_str)
+            .filter(|name| !Workflow::from(name.to_string()).is_known())
+            .collect()
+    }
+
+    /// Whether the workflow rewrites, replays, or checks out history, or tags HEAD,
+    /// and therefore assumes a clean working tree before it starts.
+    pub fn requires_clean_tree(&self) -> bool {
+        matches!(
+            self,
+            Workflow::Rebase
+                | Workflow::Bisect
+                | Workflow::CherryPick
+                | Workflow::Sync
+                | Workflow::TagAndRelease
+        )
+    }
+
+    /// Whether the workflow rewrites the commits after its upstream, which needs
+    /// `allow_published_rewrite` when they are already pushed.
+    pub fn rewrites_history(&self) -> bool {
+        matches!(self, Workflow::Rebase)
+    }
+
+    /// Whether the list of conflicted files is injected into the auto-initiation message.
+    pub fn needs_conflict_context(&self) -> bool {
+        matches!(self, Workflow::MergeConflict)
+    }
+
+    /// Whether a summary of the working tree changes is injected into the
+    /// auto-initiation message.
+    pub fn wants_change_summary(&self) -> bool {
+        matches!(self, Workflow::Commit | Workflow::Review)
+    }
+
+    /// Whether the workflow changes the repository, so its outcome is verified with git
+    /// once it reports done.
+    pub fn changes_repository(&self) -> bool {
+        matches!(
+            self,
+            Workflow::Commit
+                | Workflow::Rebase
+                | Workflow::Cleanup
+                | Workflow::MergeConflict
+                | Workflow::StashTriage
+                | Workflow::BranchCleanup
+                | Workflow::Amend
+                | Workflow::TagAndRelease
+                | Workflow::CherryPick
+                | Workflow::Sync
+        )
+    }
+
+    /// Whether changed files are grouped by language with per-language review checklists.
+    pub fn wants_language_annotation(&self) -> bool {
+        matches!(self, Workflow::Review)
+    }
+
+    /// System prompt fragment describing the workflow's steps.
+    pub fn prompt_fragment(&self) -> &'static str {
+        match self {
+            Workflow::Commit => {
+                "\n\nTASK: AUTOMATED COMMIT\n\
+                Your task is to analyze the current repository and create appropriate commits:\n\
+                \n\
+                STEPS:\n\
+                1. Check git status to identify changed files\n\
+                2. Review changes using git diff to understand what was modified\n\
+                3. Stage appropriate files for logical commits\n\
+                4. Create meaningful, conventional commit messages\n\
+                5. Execute commits with clear explanations\n\
+                6. When all commits are complete, use the task_complete tool\n\
+                \n\
+                GOAL: Create clean, atomic commits with descriptive messages. \
+                If there are multiple logical changes, create separate commits. \
+                Always explain your reasoning and call task_complete when finished."
+            }
+            Workflow::Review => {
+                "\n\nTASK: CODE REVIEW\n\
+                Your task is to thoroughly review the current code changes:\n\
+                \n\
+                STEPS:\n\
+                1. Check git status and diff to understand all changes\n\
+                2. Analyze code quality, style, and architecture\n\
+                3. Identify potential bugs, security issues, or performance problems\n\
+                4. Suggest specific improvements with examples\n\
+                5. Provide constructive feedback on implementation choices\n\
+                6. When review is complete, use the task_complete tool\n\
+                \n\
+                GOAL: Provide thorough, constructive code review that helps improve \
+                code quality. Focus on being educational and actionable."
+            }
+            Workflow::Rebase => {
+                "\n\nTASK: INTERACTIVE REBASE\n\
+                Your task is to help clean up the git history through rebase:\n\
+                \n\
+                STEPS:\n\
+                1. Analyze current branch history and commit structure\n\
+                2. Plan an appropriate rebase strategy\n\
+                3. Guide through interactive rebase steps\n\
+                4. Help resolve any merge conflicts that arise\n\
+                5. Verify the final history is clean and logical\n\
+                6. When rebase is complete, use the task_complete tool\n\
+                \n\
+                GOAL: Achieve a clean, linear git history while preserving \
+                all important changes and maintaining code integrity."
+            }
+            Workflow::Analyze => {
+                "\n\nTASK: REPOSITORY ANALYSIS\n\
+                Your task is to provide a comprehensive analysis of the repository:\n\
+                \n\
+                STEPS:\n\
+                1. Examine repository structure and organization\n\
+                2. Analyze recent commit history and patterns\n\
+                3. Review current branch state and outstanding changes\n\
+                4. Identify potential issues or improvements\n\
+                5. Provide actionable recommendations\n\
+                6. When analysis is complete, use the task_complete tool\n\
+                \n\
+                GOAL: Provide valuable insights about the repository state, \
+                development patterns, and potential improvements."
+            }
+            Workflow::Cleanup => {
+                "\n\nTASK: REPOSITORY CLEANUP\n\
+                Your task is to clean up and organize the repository:\n\
+                \n\
+                STEPS:\n\
+                1. Identify untracked files, stale branches, and clutter\n\
+                2. Review .gitignore and suggest improvements\n\
+                3. Clean up unnecessary files or directories\n\
+                4. Organize commits if needed (squash, reorder)\n\
+                5. Update documentation if outdated\n\
+                6. When cleanup is complete, use the task_complete tool\n\
+                \n\
+                GOAL: Leave the repository in a clean, organized state \
+                that follows best practices and is easy to navigate."
+            }
+            Workflow::MergeConflict => {
+                "\n\nTASK: MERGE CONFLICT RESOLUTION\n\
+                Your task is to help resolve the merge conflicts in the repository:\n\
+                \n\
+                STEPS:\n\
+                1. Enumerate every conflicted file (git status / git diff --name-only --diff-filter=U)\n\
+                2. For each file, show the conflicting hunks and explain what each side changed and why they conflict\n\
+                3. Propose a resolution for each conflict, explaining how it preserves the intent of both sides\n\
+                4. Apply resolutions only after they have been agreed, then stage the resolved files\n\
+                5. Verify no conflict markers remain and complete the merge or rebase step\n\
+                6. When all conflicts are resolved, use the task_complete tool\n\
+                \n\
+                GOAL: Resolve every conflict correctly without losing changes from either side. \
+                Never pick a side wholesale without explaining what is discarded."
+            }
+            Workflow::Changelog => {
+                "\n\nTASK: CHANGELOG DRAFT\n\
+                Your task is to draft release notes from the git history:\n\
+                \n\
+                STEPS:\n\
+                1. Find the most recent release tag (git describe --tags --abbrev=0)\n\
+                2. Read every commit since that tag (git log <tag>..HEAD), or the whole history if there is none\n\
+                3. Group the commits as configured and skip merge commits and pure housekeeping\n\
+                4. Rewrite each change as a short, user-facing entry, calling out breaking changes first\n\
+                5. Present the CHANGELOG entry and, if asked, add it to the top of CHANGELOG.md\n\
+                6. When the entry is finished, use the task_complete tool\n\
+                \n\
+                GOAL: A changelog entry users can read without knowing the code, that \
+                covers every notable change since the last release."
+            }
+            Workflow::PrDescription => {
+                "\n\nTASK: PULL REQUEST DESCRIPTION\n\
+                Your task is to write the pull request description for the current branch:\n\
+                \n\
+                STEPS:\n\
+                1. Read every commit between the base branch and HEAD (git log <base>..HEAD)\n\
+                2. Review the full diff against the base (git diff <base>...HEAD)\n\
+                3. Write a concise title and a summary of what changed and why\n\
+                4. Describe how the change was or should be tested\n\
+                5. Assess the risk: what could break, migrations, and anything reviewers should check closely\n\
+                6. Call the task_complete tool with the complete description as the summary\n\
+                \n\
+                GOAL: A description a reviewer can read before the diff. Do not modify the \
+                repository, push, or open the pull request yourself."
+            }
+            Workflow::Bisect => {
+                "\n\nTASK: GIT BISECT\n\
+                Your task is to find the commit that introduced a problem using git bisect:\n\
+                \n\
+                STEPS:\n\
+                1. Ask the user to describe the problem, a known good ref where it does not occur, and a known bad ref (HEAD if not given)\n\
+                2. Check that both refs exist and say how many commits lie between them; the assistant starts the bisect once the refs are confirmed\n\
+                3. For each commit the bisect checks out, look at what it changed and whether it could cause the problem\n\
+                4. The verdict for each commit (good, bad, or skip) comes from the user or a configured test command\n\
+                5. When the first bad commit is found, explain what in it most likely causes the problem\n\
+                6. Then use the task_complete tool\n\
+                \n\
+                GOAL: Find the first bad commit in as few steps as possible. Never run git \
+                bisect, git checkout, or git reset yourself; the assistant drives the bisect."
+            }
+            Workflow::StashTriage => {
+                "\n\nTASK: STASH TRIAGE\n\
+                Your task is to help clean up the repository's stashes, one at a time:\n\
+                \n\
+                STEPS:\n\
+                1. Go through the stashes listed in the opening message, oldest first\n\
+                2. For each stash, read its changes (git stash show -p) and summarize what it contains\n\
+                3. Check whether the changes are already on a branch or no longer apply, and recommend applying, dropping, or keeping it\n\
+                4. Wait for the user's decision on each stash before applying or dropping it\n\
+                5. Dropping a stash renumbers the stash@{n} references after it; run git stash list before each apply or drop and match stashes by their short sha\n\
+                6. When every stash has been applied, dropped, or kept, use the task_complete tool with a summary that names the short sha of every stash that is kept\n\
+                \n\
+                GOAL: No stash left without a decision. Never drop a stash or run git \
+                stash clear without the user's agreement."
+            }
+            Workflow::BranchCleanup => {
+                "\n\nTASK: BRANCH CLEANUP\n\
+                Your task is to help remove branches that are no longer needed:\n\
+                \n\
+                STEPS:\n\
+                1. Go through the branches proposed for deletion in the opening message\n\
+                2. For each branch, check what it holds (git log <base>..<branch>) and explain why it is merged or stale\n\
+                3. Point out any branch that still looks useful, e.g. one with commits that are on no other branch\n\
+                4. Ask the user which of the proposed branches to delete; the assistant deletes them once the user confirms\n\
+                5. When you are told the outcome, summarize what was deleted and what was kept\n\
+                6. Then use the task_complete tool\n\
+                \n\
+                GOAL: Only branches nobody needs are deleted. Never delete a branch or push \
+                a deletion yourself, and never suggest deleting a protected branch."
+            }
+            Workflow::Amend => {
+                "\n\nTASK: AMEND THE LAST COMMIT\n\
+                Your task is to fix the last commit, its message or its contents:\n\
+                \n\
+                STEPS:\n\
+                1. Read the last commit and the pending changes listed in the opening message\n\
+                2. Decide whether the pending changes belong in the last commit; leave unrelated changes for a separate commit\n\
+                3. Stage only the changes that belong in it, or none to fix just the message\n\
+                4. Amend the commit with git commit --amend, rewriting the message when it is wrong or no longer covers the changes\n\
+                5. Show the amended commit and explain what changed\n\
+                6. Then use the task_complete tool\n\
+                \n\
+                GOAL: A last commit that is correct and says what it does. Amend only the \
+                last commit, never push, and never amend a commit the opening message doesn't \
+                name."
+            }
+            Workflow::TagAndRelease => {
+                "\n\nTASK: TAG AND RELEASE\n\
+                Your task is to draft the release the opening message proposes:\n\
+                \n\
+                STEPS:\n\
+                1. Read the commits since the last tag listed in the opening message (git show for details)\n\
+                2. Check that the proposed version follows from them and point out any commit whose type looks wrong\n\
+                3. Draft the annotated tag message: a title line with the version, then the notable changes grouped as breaking changes, features, and fixes\n\
+                4. Reply with the message in a ```release-notes block; revise it in a new block when the user asks\n\
+                5. The assistant creates the tag once the user confirms; when you are told the outcome, summarize the release\n\
+                6. Then use the task_complete tool\n\
+                \n\
+                GOAL: An accurate version and release notes users can read. Never run git \
+                tag or git push yourself."
+            }
+            Workflow::HookReview => {
+                "\n\nTASK: HOOK FAILURE REVIEW\n\
+                Your task is to fix what the failing git hooks complain about:\n\
+                \n\
+                STEPS:\n\
+                1. Read the hook output in the opening message, or ask for it, and list each failing check with the files it names\n\
+                2. Diagnose each failure: formatting, lint, a failing test, a large file, or a check on the commit itself\n\
+                3. Propose a fix for each one, then apply it, with the fixer the hook uses when there is one (e.g. the formatter), otherwise by editing the files\n\
+                4. Stage every file you fixed that was staged (git add -- <file>), and nothing else\n\
+                5. Summarize what failed and what you changed; say so when a failure needs a decision you can't make\n\
+                6. Then use the task_complete tool\n\
+                \n\
+                GOAL: Staged changes the hooks accept. Never commit, never skip hooks with \
+                --no-verify, and never change a hook's configuration to make it pass."
+            }
+            Workflow::BlameExplain => {
+                "\n\nTASK: EXPLAIN CODE HISTORY\n\
+                Your task is to explain why the code named in the opening message looks the way it does:\n\
+                \n\
+                STEPS:\n\
+                1. Read the code, its blame, and the commits that changed it, listed in the opening message\n\
+                2. Read the key commits in full (git show), and follow older history with git log -L or git blame <commit>^ when a commit only moved the code\n\
+                3. Tell the story of the code: when it was added, by whom, and why, and each change that shaped it\n\
+                4. Summarize the key commits and their authors, and point out constraints the history reveals that a change today must keep\n\
+                5. Then use the task_complete tool\n\
+                \n\
+                GOAL: An explanation grounded in the commits, quoting their messages where they \
+                give the reason. Never change files, and say so when the history doesn't tell why."
+            }
+            Workflow::CherryPick => {
+                "\n\nTASK: CHERRY-PICK\n\
+                Your task is to bring the commits listed in the opening message onto the current branch:\n\
+                \n\
+                STEPS:\n\
+                1. Read each listed commit (git show --stat, then the relevant parts of the diff)\n\
+                2. Plan the order to pick them in so that each applies on top of the ones before; skip a commit only when its changes are already on the branch or the user asks\n\
+                3. Reply with the plan in a ```json block; the assistant picks the commits in that order\n\
+                4. When a pick stops on conflicts, explain them, resolve each file, and stage it; the assistant continues the pick\n\
+                5. When told the cherry-pick is finished, summarize the picked and skipped commits\n\
+                6. Then use the task_complete tool\n\
+                \n\
+                GOAL: Every commit picked or skipped for a stated reason, with conflicts resolved \
+                faithfully to the original change. Never run git cherry-pick, git commit, or git \
+                reset yourself; the assistant drives the picks."
+            }
+            Workflow::Onboard => {
+                "\n\nTASK: ONBOARDING GUIDE\n\
+                Your task is to walk a new contributor through this repository:\n\
+                \n\
+                STEPS:\n\
+                1. Read the overview in the opening message: the layout, the most changed modules, recent activity, and key contributors\n\
+                2. Read the README and the entry points of the main modules, and skim the history of the busiest ones (git log -- <path>)\n\
+                3. Work out what each main module does, how they fit together, and where a change usually starts\n\
+                4. Reply with the onboarding document in Markdown: the project's purpose, a map of the modules, where development is active, who to ask about which area, and a first reading list\n\
+                5. Revise the document in a new reply when the user asks\n\
+                6. Then use the task_complete tool\n\
+                \n\
+                GOAL: A document a newcomer can read on their first day, grounded in the code \
+                and the history. Never change files; say so when the history doesn't tell."
+            }
+            Workflow::Sync => {
+                "\n\nTASK: SYNC WITH UPSTREAM\n\
+                Your task is to bring the current branch up to date with its upstream:\n\
+                \n\
+                STEPS:\n\
+                1. Read the divergence in the opening message: the local commits (git log <upstream>..HEAD) and the incoming ones (git log HEAD..<upstream>)\n\
+                2. Explain how the branches diverged and the recommended strategy, and what merging or rebasing instead would mean here\n\
+                3. Wait for the user to approve the sync; the assistant runs the merge or rebase\n\
+                4. When it stops on conflicts, explain what each side changed, resolve each file, and stage it; the assistant continues\n\
+                5. When told the sync is finished, summarize what came in and how conflicts were resolved\n\
+                6. Then use the task_complete tool\n\
+                \n\
+                GOAL: The branch caught up with its upstream, with conflicts resolved keeping \
+                both sides' intent. Never run git fetch, pull, merge, rebase, reset, commit, or \
+                push yourself; the assistant drives the sync."
+            }
+            Workflow::Other(_) => "",
+        }
+    }
+
+    /// Message sent on the user's behalf when the session starts with this workflow.
+    pub fn auto_message(&self) -> &'static str {
+        match self {
+            Workflow::Commit => "Please analyze the repository and commit any pending changes with appropriate commit messages. Start by checking git status to see what files have changed.",
+            Workflow::Review => "Please perform a comprehensive code review of the current changes. Start by examining what has been modified.",
+            Workflow::Rebase => "Please help me clean up the git history through an interactive rebase. Start by showing the current commit history.",
+            Workflow::Analyze => "Please provide a comprehensive analysis of this repository. Start by examining the overall structure and recent activity.",
+            Workflow::Cleanup => "Please help clean up and organize this repository. Start by identifying what needs attention.",
+            Workflow::MergeConflict => "Please help me resolve the current merge conflicts. Start by listing every conflicted file, then explain each conflict and propose a resolution.",
+            Workflow::Changelog => "Please draft a changelog entry for the changes since the last release. Start by finding the last release tag and reading the commits since then.",
+            Workflow::PrDescription => "Please write a pull request description for the current branch. Start by reading the commits and the diff against the base branch.",
+            Workflow::Bisect => "Please help me find the commit that introduced a problem using git bisect. Start by asking me to describe the problem and for a known good ref and a known bad ref.",
+            Workflow::StashTriage => "Please help me clean up my git stashes. Start with the oldest stash: summarize what it contains and recommend whether to apply, drop, or keep it.",
+            Workflow::BranchCleanup => "Please help me clean up merged and stale branches. Start by going through the branches proposed for deletion and explaining each one.",
+            Workflow::TagAndRelease => "Please help me release the changes since the last tag. Start by going through the commits and checking the proposed version.",
+            Workflow::HookReview => "Please help me fix what my failing git hooks report. Start by going through the hook output and diagnosing each failure.",
+            Workflow::BlameExplain => "Please explain why this code looks the way it does. Start by reading its blame and the commits that changed it.",
+            Workflow::CherryPick => "Please help me cherry-pick these commits onto the current branch. Start by reading them and planning the order to pick them in.",
+            Workflow::Onboard => "Please help me get to know this repository. Start with the overview of its history, then read the code and write me an onboarding document.",
+            Workflow::Sync => "Please help me bring this branch up to date with its upstream. Start by explaining how they diverged and the recommended way to catch up.",
+            Workflow::Amend => "Please help me fix my last commit. Start by comparing the last commit with the pending changes and tell me whether to fold them in, reword the message, or both.",
+            Workflow::Other(_) => "Please proceed with the assigned task. Let me know if you need clarification on what should be done.",
+        }
+    }
+
+    pub fn default_temperature(&self) -> f64 {
+        match self {
+            Workflow::Commit => 0.3,        // More deterministic for commit messages
+            Workflow::Review => 0.5,        // Balanced for analysis
+            Workflow::Rebase => 0.2,        // Very precise for history operations
+            Workflow::Analyze => 0.6,       // Slightly creative for insights
+            Workflow::Cleanup => 0.3,       // Methodical approach
+            Workflow::MergeConflict => 0.2, // Precise, code must stay correct
+            Workflow::Changelog => 0.4,     // Faithful to history, readable prose
+            Workflow::PrDescription => 0.4, // Same balance as the changelog
+            Workflow::Bisect => 0.2,        // Precise, verdicts drive the search
+            Workflow::StashTriage => 0.3,   // Methodical, like cleanup
+            Workflow::BranchCleanup => 0.2, // Precise, deletions are hard to undo
+            Workflow::Amend => 0.3,         // Like commit, which it redoes
+            Workflow::TagAndRelease => 0.3, // Notes faithful to the commits
+            Workflow::HookReview => 0.2,    // Precise, fixes must satisfy the hooks
+            Workflow::BlameExplain => 0.4,  // Faithful to history, readable prose
+            Workflow::CherryPick => 0.2,    // Precise, conflicts must keep the change intact
+            Workflow::Onboard => 0.5,       // Readable prose, grounded in the code
+            Workflow::Sync => 0.2,          // Precise, conflicts must keep both sides intact
+            Workflow::Other(_) => 0.7,
+        }
+    }
+
+    pub fn default_title(&self) -> &'static str {
+        match self {
+            Workflow::Commit => "Git Commit Assistant",
+            Workflow::Review => "Git Code Review Assistant",
+            Workflow::Rebase => "Git Rebase Assistant",
+            Workflow::Analyze => "Git Analysis Assistant",
+            Workflow::Cleanup => "Git Cleanup Assistant",
+            Workflow::MergeConflict => "Git Merge Conflict Assistant",
+            Workflow::Changelog => "Git Changelog Assistant",
+            Workflow::PrDescription => "Git Pull Request Assistant",
+            Workflow::Bisect => "Git Bisect Assistant",
+            Workflow::StashTriage => "Git Stash Triage Assistant",
+            Workflow::BranchCleanup => "Git Branch Cleanup Assistant",
+            Workflow::Amend => "Git Amend Assistant",
+            Workflow::TagAndRelease => "Git Release Assistant",
+            Workflow::HookReview => "Git Hook Review Assistant",
+            Workflow::BlameExplain => "Git History Explainer",
+            Workflow::CherryPick => "Git Cherry-Pick Assistant",
+            Workflow::Onboard => "Git Onboarding Guide",
+            Workflow::Sync => "Git Sync Assistant",
+            Workflow::Other(_) => "Git Task Assistant",
+        }
+    }
+}
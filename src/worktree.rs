@@ -0,0 +1,156 @@
+//! Worktrees of the repository, and the `worktrees` config paths, for
+//! `ListWorktrees` and `SetActiveWorktree`.
+
+use crate::git_tools::run_git;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Worktree {
+    pub path: String,
+    /// Commit checked out; unset for bare repositories and unreadable paths
+    pub head: Option<String>,
+    /// Branch checked out, without `refs/heads/`; unset when HEAD is detached
+    pub branch: Option<String>,
+    /// Listed by `git worktree list`
+    pub registered: bool,
+    /// Listed in `worktrees`
+    pub configured: bool,
+    /// The directory git operations run in
+    pub active: bool,
+    pub locked: bool,
+    /// Why the assistant can't work in it, e.g. a bare repository or a missing
+    /// directory
+    pub unusable: Option<String>,
+}
+
+/// `path` without trailing slashes, to compare paths as configured and as git lists them.
+pub fn normalize_path(path: &str) -> &str {
+    match path.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// Worktrees in `git worktree list --porcelain` output.
+pub fn parse_porcelain(output: &str) -> Vec<Worktree> {
+    let mut worktrees = Vec::new();
+    for record in output.split("\n\n") {
+        let mut worktree: Option<Worktree> = None;
+        for line in record.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            if key == "worktree" {
+                worktree = Some(Worktree {
+                    path: value.to_string(),
+                    head: None,
+                    branch: None,
+                    registered: true,
+                    configured: false,
+                    active: false,
+                    locked: false,
+                    unusable: None,
+                });
+                continue;
+            }
+            let Some(worktree) = worktree.as_mut() else {
+                continue;
+            };
+            match key {
+                "HEAD" => worktree.head = Some(value.to_string()),
+                "branch" => {
+                    let branch = value.strip_prefix("refs/heads/").unwrap_or(value);
+                    worktree.branch = Some(branch.to_string());
+                }
+                "bare" => worktree.unusable = Some("bare repository".to_string()),
+                "locked" => worktree.locked = true,
+                "prunable" => {
+                    worktree.unusable = Some(match value {
+                        "" => "prunable".to_string(),
+                        reason => format!("prunable: {}", reason),
+                    })
+                }
+                _ => {}
+            }
+        }
+        worktrees.extend(worktree);
+    }
+    worktrees
+}
+
+/// Worktrees of the repository in `directory`, then the configured paths git doesn't
+/// list, with `active` marking `directory`.
+pub fn list(
+    git_actor_id: &str,
+    directory: Option<&str>,
+    configured: &[String],
+) -> Result<Vec<Worktree>, String> {
+    let output = run_git(
+        git_actor_id,
+        directory,
+        &["worktree", "list", "--porcelain"],
+    )?;
+    let mut worktrees = parse_porcelain(&output);
+    for path in configured {
+        let path = normalize_path(path);
+        if let Some(worktree) = worktrees
+            .iter_mut()
+            .find(|worktree| normalize_path(&worktree.path) == path)
+        {
+            worktree.configured = true;
+            continue;
+        }
+        // A separate checkout; read what it has checked out directly
+        let (head, branch, unusable) =
+            match run_git(git_actor_id, Some(path), &["rev-parse", "HEAD"]) {
+                Ok(head) => {
+                    let branch = run_git(
+                        git_actor_id,
+                        Some(path),
+                        &["symbolic-ref", "--quiet", "--short", "HEAD"],
+                    )
+                    .ok()
+                    .map(|branch| branch.trim().to_string());
+                    (Some(head.trim().to_string()), branch, None)
+                }
+                Err(e) => (None, None, Some(e)),
+            };
+        worktrees.push(Worktree {
+            path: path.to_string(),
+            head,
+            branch,
+            registered: false,
+            configured: true,
+            active: false,
+            locked: false,
+            unusable,
+        });
+    }
+    if let Some(directory) = directory.map(normalize_path) {
+        for worktree in &mut worktrees {
+            worktree.active = normalize_path(&worktree.path) == directory;
+        }
+    }
+    Ok(worktrees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn porcelain_records_become_worktrees() {
+        let output = "worktree /repo\nHEAD 4f2c9a1b\nbranch refs/heads/main\n\n\
+            worktree /repo-review\nHEAD 9e8d7c6b\ndetached\nlocked reviewing\n\n\
+            worktree /tmp/gone\nHEAD 1a2b3c4d\nbranch refs/heads/spike\nprunable gitdir file points to non-existent location\n";
+        let worktrees = parse_porcelain(output);
+        assert_eq!(worktrees.len(), 3);
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        assert_eq!(worktrees[0].unusable, None);
+        assert_eq!(worktrees[1].branch, None);
+        assert!(worktrees[1].locked);
+        assert!(worktrees[2]
+            .unusable
+            .as_deref()
+            .is_some_and(|reason| reason.starts_with("prunable")));
+        assert_eq!(normalize_path("/repo-review/"), "/repo-review");
+    }
+}